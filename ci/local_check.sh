@@ -83,6 +83,20 @@ test_check() {
   cargo test --workspace --all-features --locked -- --nocapture
 }
 
+# Each greentic-pack feature must build on its own, so embedders can pick
+# only what they need. The slim build comes first: with no features the crate
+# is the manifest types alone, without warnings.
+feature_matrix_check() {
+  require_tool cargo "cargo check" || return $?
+  echo "greentic-pack features: --no-default-features"
+  RUSTFLAGS="${RUSTFLAGS:-} -D warnings" cargo check -p greentic-pack --no-default-features --all-targets --locked
+  local features
+  for features in reader builder plan schema render bins "reader,builder" "builder,schema"; do
+    echo "greentic-pack features: ${features}"
+    cargo check -p greentic-pack --no-default-features --features "$features" --all-targets --locked
+  done
+}
+
 builder_demo_check() (
   require_tool cargo "builder demo" || return $?
   require_tool jq "builder demo report validation" || return $?
@@ -95,8 +109,8 @@ builder_demo_check() (
   local out1="$tmpdir/demo1.gtpack"
   local out2="$tmpdir/demo2.gtpack"
 
-  cargo run -p greentic-pack --example build_demo -- --out "$out1"
-  cargo run -p greentic-pack --example build_demo -- --out "$out2"
+  cargo run -p greentic-pack --features builder --example build_demo -- --out "$out1"
+  cargo run -p greentic-pack --features builder --example build_demo -- --out "$out2"
 
   local unpack1="$tmpdir/unpack1"
   local unpack2="$tmpdir/unpack2"
@@ -111,7 +125,7 @@ builder_demo_check() (
   fi
 
   local report
-  report=$(cargo run -p greentic-pack --bin gtpack-inspect -- --policy devok --json "$out1")
+  report=$(cargo run -p greentic-pack --features bins --bin gtpack-inspect -- --policy devok --json "$out1")
   echo "$report" | jq -e 'has("sbom") and (all(.sbom[]; (.media_type | length > 0)))' >/dev/null
 )
 
@@ -154,7 +168,7 @@ packc_gtpack_check() {
   fi
 
   local report
-  report=$(cargo run -p greentic-pack --bin gtpack-inspect -- --policy devok --json "$gtpack")
+  report=$(cargo run -p greentic-pack --features bins --bin gtpack-inspect -- --policy devok --json "$gtpack")
   echo "$report" | jq -e 'has("sbom") and (all(.sbom[]; (.media_type | length > 0)))' >/dev/null
 }
 
@@ -178,6 +192,9 @@ main() {
   step "Tests"
   run_or_skip "cargo test" test_check
 
+  step "greentic-pack feature matrix"
+  run_or_skip "feature matrix" feature_matrix_check
+
   step "Builder demo determinism"
   run_or_skip "builder demo" builder_demo_check
 
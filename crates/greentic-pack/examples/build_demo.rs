@@ -1,7 +1,9 @@
 use std::fs;
 use std::path::PathBuf;
 
-use greentic_pack::builder::{ComponentArtifact, FlowBundle, PackBuilder, PackMeta, Provenance};
+use greentic_pack::builder::{
+    ComponentArtifact, FlowBundle, PackBuilder, PackMeta, Provenance, WasmSource,
+};
 use semver::Version;
 use serde_json::{Map, json};
 use tempfile::tempdir;
@@ -30,6 +32,8 @@ fn main() -> anyhow::Result<()> {
         homepage: None,
         support: None,
         vendor: None,
+        tags: Vec::new(),
+        categories: Vec::new(),
         imports: vec![],
         entry_flows: vec!["demo".into()],
         created_at_utc: "2025-01-01T00:00:00Z".into(),
@@ -37,6 +41,23 @@ fn main() -> anyhow::Result<()> {
         repo: None,
         messaging: None,
         interfaces: Vec::new(),
+        media: None,
+        config: None,
+        config_schema: None,
+        config_defaults: None,
+        lifecycle: None,
+        quotas: None,
+        limits: None,
+        dispatch: None,
+        qos: None,
+        secrets: None,
+        capabilities: None,
+        metrics: None,
+        schedule: None,
+        classification: None,
+        locales: None,
+        data_handling: None,
+        permissions: None,
         annotations: Map::new(),
     };
 
@@ -61,7 +82,7 @@ fn main() -> anyhow::Result<()> {
     let component = ComponentArtifact {
         name: "demo-component".into(),
         version: Version::parse("1.0.0").unwrap(),
-        wasm_path: wasm_path.clone(),
+        wasm: WasmSource::Path(wasm_path.clone()),
         schema_json: None,
         manifest_json: None,
         capabilities: None,
@@ -77,6 +98,7 @@ fn main() -> anyhow::Result<()> {
         built_at_utc: "2025-01-01T00:00:00Z".into(),
         host: Some("local".into()),
         notes: Some("example build".into()),
+        profile_blake3: None,
     };
 
     let result = PackBuilder::new(meta)
@@ -0,0 +1,182 @@
+//! Crash-safe artifact writes.
+//!
+//! Every artifact is first written to a hidden temporary file next to its
+//! destination and then renamed over it, so readers only ever observe the old
+//! or the new contents, never a truncated mix of the two.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+/// Whether writes are flushed to stable storage before they are published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Durability {
+    /// Rely on the OS page cache; the rename is still atomic but may be lost on
+    /// power failure.
+    #[default]
+    Buffered,
+    /// `fsync` the temporary file before the rename and the directory after it.
+    Fsync,
+}
+
+impl Durability {
+    pub fn from_flag(fsync: bool) -> Self {
+        if fsync { Self::Fsync } else { Self::Buffered }
+    }
+}
+
+/// Atomically replaces `path` with `contents`.
+pub fn write(path: &Path, contents: &[u8], durability: Durability) -> Result<()> {
+    write_with(path, durability, |file| {
+        file.write_all(contents)?;
+        Ok(())
+    })
+}
+
+/// Atomically replaces `path` with a copy of `source`.
+pub fn copy(source: &Path, path: &Path, durability: Durability) -> Result<()> {
+    let mut input =
+        File::open(source).with_context(|| format!("failed to open {}", source.display()))?;
+    write_with(path, durability, |file| {
+        std::io::copy(&mut input, file)?;
+        Ok(())
+    })
+}
+
+/// Streams the new contents of `path` through `fill`, publishing them only if
+/// `fill` succeeds. On error (or panic) the temporary file is discarded and the
+/// previous contents of `path` are left untouched.
+pub fn write_with<F>(path: &Path, durability: Durability, fill: F) -> Result<()>
+where
+    F: FnOnce(&mut File) -> Result<()>,
+{
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create directory {}", dir.display()))?;
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "artifact".to_string());
+    let mut temp = tempfile::Builder::new()
+        .prefix(&format!(".{file_name}."))
+        .suffix(".tmp")
+        .tempfile_in(dir)
+        .with_context(|| format!("failed to create temporary file in {}", dir.display()))?;
+
+    fill(temp.as_file_mut()).with_context(|| format!("failed to write {}", path.display()))?;
+    temp.as_file_mut()
+        .flush()
+        .with_context(|| format!("failed to flush {}", path.display()))?;
+    if durability == Durability::Fsync {
+        temp.as_file()
+            .sync_all()
+            .with_context(|| format!("failed to sync {}", path.display()))?;
+    }
+    apply_permissions(temp.as_file(), path)?;
+
+    temp.persist(path)
+        .map_err(|err| err.error)
+        .with_context(|| format!("failed to replace {}", path.display()))?;
+
+    if durability == Durability::Fsync {
+        sync_dir(dir)?;
+    }
+    Ok(())
+}
+
+/// Temporary files are created owner-only; give the published artifact the
+/// permissions of the file it replaces, or the usual `0644` for new files.
+fn apply_permissions(temp: &File, path: &Path) -> Result<()> {
+    let permissions = match fs::metadata(path) {
+        Ok(meta) => meta.permissions(),
+        Err(_) => default_permissions(temp)?,
+    };
+    temp.set_permissions(permissions)
+        .with_context(|| format!("failed to set permissions for {}", path.display()))
+}
+
+#[cfg(unix)]
+fn default_permissions(_temp: &File) -> Result<fs::Permissions> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok(fs::Permissions::from_mode(0o644))
+}
+
+#[cfg(not(unix))]
+fn default_permissions(temp: &File) -> Result<fs::Permissions> {
+    Ok(temp.metadata()?.permissions())
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<()> {
+    File::open(dir)
+        .and_then(|handle| handle.sync_all())
+        .with_context(|| format!("failed to sync directory {}", dir.display()))
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+
+    fn leftovers(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect()
+    }
+
+    #[test]
+    fn replaces_contents_and_syncs() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("out/manifest.cbor");
+        write(&path, b"first", Durability::Fsync).unwrap();
+        write(&path, b"second", Durability::Buffered).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert!(leftovers(path.parent().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn failed_write_keeps_previous_artifact() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("sbom.json");
+        write(&path, b"{\"complete\":true}", Durability::Buffered).unwrap();
+
+        let err = write_with(&path, Durability::Fsync, |file| {
+            file.write_all(b"{\"compl")?;
+            anyhow::bail!("disk full");
+        })
+        .unwrap_err();
+        assert!(format!("{err:#}").contains("disk full"));
+        assert_eq!(fs::read(&path).unwrap(), b"{\"complete\":true}");
+        assert!(leftovers(temp.path()).is_empty());
+    }
+
+    #[test]
+    fn crash_mid_write_never_exposes_partial_contents() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("pack.gtpack");
+        write(&path, b"old archive", Durability::Buffered).unwrap();
+
+        let outcome = catch_unwind(AssertUnwindSafe(|| {
+            write_with(&path, Durability::Buffered, |file| {
+                file.write_all(b"new arch")?;
+                panic!("simulated crash");
+            })
+        }));
+        assert!(outcome.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"old archive");
+        assert!(leftovers(temp.path()).is_empty());
+    }
+}
@@ -0,0 +1,33 @@
+use anyhow::Result;
+use greentic_pack::extract::{ExtractOptions, ExtractReport, extract_pack};
+
+use crate::ExtractArgs;
+
+pub fn run(args: &ExtractArgs) -> Result<()> {
+    let opts = ExtractOptions {
+        verify: args.verify.then(|| args.policy.into()),
+        pack_yaml: args.pack_yaml,
+    };
+    let report = extract_pack(&args.path, &args.out, opts)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_human(&report);
+    }
+    Ok(())
+}
+
+fn print_human(report: &ExtractReport) {
+    println!(
+        "Extracted {} files to {}{}",
+        report.files.len(),
+        report.out_dir.display(),
+        if report.verified { " (verified)" } else { "" }
+    );
+    for file in &report.files {
+        println!("  {file}");
+    }
+    for warning in &report.warnings {
+        println!("warning: {warning}");
+    }
+}
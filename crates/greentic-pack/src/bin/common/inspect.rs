@@ -2,7 +2,13 @@ use std::path::Path;
 
 use anyhow::{Result, anyhow};
 use clap::ValueEnum;
-use greentic_pack::{SigningPolicy, VerifyReport, builder::PackManifest, open_pack};
+use greentic_pack::{
+    ArchiveEntry, PackLoad, PackVerifyResult, SigningPolicy, VerifyFinding, VerifyReport,
+    builder::PackManifest,
+    capabilities::{CapabilitiesSection, FileAccess},
+    open_pack,
+    secrets::SecretsSection,
+};
 use serde_json::json;
 
 #[derive(Copy, Clone, Debug, ValueEnum)]
@@ -20,12 +26,42 @@ impl From<PolicyArg> for SigningPolicy {
     }
 }
 
-pub fn run(path: &Path, policy: PolicyArg, json: bool) -> Result<()> {
-    let load = open_pack(path, policy.into()).map_err(|err| anyhow!(err.message))?;
+pub fn run(path: &Path, policy: PolicyArg, json: bool, readme: bool, files: bool) -> Result<()> {
+    let load = match open_pack(path, policy.into()) {
+        Ok(load) => load,
+        Err(err) => {
+            if json {
+                print_rejection(&err)?;
+            }
+            return Err(anyhow!(err.message));
+        }
+    };
+    if readme {
+        return print_readme(load.readme(), json);
+    }
+    let entries = files.then(|| load.entries());
     if json {
-        print_json(&load.manifest, &load.report, &load.sbom)?;
+        print_json(&load, entries.as_deref())?;
     } else {
         print_human(&load.manifest, &load.report, &load.sbom);
+        if let Some(entries) = &entries {
+            print_entries(entries);
+        }
+    }
+    Ok(())
+}
+
+fn print_readme(readme: Option<&str>, json: bool) -> Result<()> {
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "readme": readme }))?
+        );
+        return Ok(());
+    }
+    match readme {
+        Some(text) => print!("{text}"),
+        None => println!("No README.md embedded in this pack."),
     }
     Ok(())
 }
@@ -40,38 +76,160 @@ fn print_human(
         manifest.meta.pack_id, manifest.meta.version
     );
     println!("Flows: {}", manifest.flows.len());
+    if let Some(health) = manifest
+        .meta
+        .lifecycle
+        .as_ref()
+        .and_then(|lifecycle| lifecycle.health.as_deref())
+    {
+        println!("Health flow: {health}");
+    }
+    if let Some(capabilities) = manifest
+        .meta
+        .capabilities
+        .as_ref()
+        .filter(|capabilities| !capabilities.is_empty())
+    {
+        print_capabilities(capabilities);
+    }
+    if let Some(secrets) = manifest
+        .meta
+        .secrets
+        .as_ref()
+        .filter(|secrets| !secrets.is_empty())
+    {
+        print_secrets(secrets);
+    }
     println!("Components: {}", manifest.components.len());
     println!("SBOM entries: {}", sbom.len());
-    println!("Signature OK: {}", report.signature_ok);
-    println!("SBOM OK: {}", report.sbom_ok);
-    if report.warnings.is_empty() {
-        println!("Warnings: none");
+    println!("Signature OK: true");
+    println!("SBOM OK: true");
+    if report.findings.is_empty() {
+        println!("Findings: none");
     } else {
-        println!("Warnings:");
-        for warning in &report.warnings {
-            println!("  - {}", warning);
+        println!("Findings:");
+        for finding in &report.findings {
+            println!("  - {}", describe(finding));
         }
     }
 }
 
-fn print_json(
-    manifest: &PackManifest,
-    report: &VerifyReport,
-    sbom: &[greentic_pack::builder::SbomEntry],
-) -> Result<()> {
-    let payload = json!({
+/// What the pack asks its host to grant, one kind per line.
+fn print_capabilities(capabilities: &CapabilitiesSection) {
+    println!("Capabilities:");
+    let filesystem: Vec<String> = capabilities
+        .filesystem
+        .iter()
+        .map(|grant| match grant.access {
+            FileAccess::Read => format!("{} (read)", grant.path),
+            FileAccess::ReadWrite => format!("{} (read/write)", grant.path),
+        })
+        .collect();
+    for (kind, entries) in [
+        ("network", capabilities.network.as_slice()),
+        ("filesystem", filesystem.as_slice()),
+        ("secrets", capabilities.secrets.as_slice()),
+        ("env", capabilities.env.as_slice()),
+    ] {
+        if !entries.is_empty() {
+            println!("  {kind}: {}", entries.join(", "));
+        }
+    }
+}
+
+/// The secrets to provision, one per line.
+fn print_secrets(secrets: &SecretsSection) {
+    println!("Secrets:");
+    for (name, secret) in &secrets.secrets {
+        let required = if secret.required {
+            "required"
+        } else {
+            "optional"
+        };
+        match &secret.description {
+            Some(description) => println!(
+                "  {name} ({}, {required}): {description}",
+                secret.kind.as_str()
+            ),
+            None => println!("  {name} ({}, {required})", secret.kind.as_str()),
+        }
+    }
+}
+
+fn describe(finding: &VerifyFinding) -> String {
+    let severity = finding.severity;
+    match &finding.subject {
+        Some(subject) => format!(
+            "{severity} {} ({subject}): {}",
+            finding.code, finding.message
+        ),
+        None => format!("{severity} {}: {}", finding.code, finding.message),
+    }
+}
+
+/// One line per archive entry, aligned so two listings diff cleanly.
+fn print_entries(entries: &[ArchiveEntry]) {
+    let width = entries
+        .iter()
+        .map(|entry| entry.path.len())
+        .max()
+        .unwrap_or(0);
+    println!("Files:");
+    for entry in entries {
+        let coverage = match (entry.in_sbom, entry.signed) {
+            (true, _) => "sbom+signed",
+            (false, true) => "signed",
+            (false, false) => "unsigned",
+        };
+        println!(
+            "  {:<width$}  {:>10}  {:>10}  {:<8}  {}  {coverage}",
+            entry.path, entry.size, entry.compressed_size, entry.compression, entry.hash_blake3,
+        );
+    }
+}
+
+fn print_json(load: &PackLoad, entries: Option<&[ArchiveEntry]>) -> Result<()> {
+    let (manifest, report, sbom) = (&load.manifest, &load.report, &load.sbom);
+    let mut payload = json!({
         "manifest": {
             "pack_id": manifest.meta.pack_id,
             "version": manifest.meta.version,
             "flows": manifest.flows.len(),
             "components": manifest.components.len(),
+            "capabilities": manifest.meta.capabilities,
+            "secrets": manifest.meta.secrets,
         },
+        "report": report_json(report),
+        "sbom": sbom,
+    });
+    if let Some(entries) = entries {
+        payload["files"] = json!(entries);
+    }
+    println!("{}", serde_json::to_string_pretty(&payload)?);
+    Ok(())
+}
+
+/// Keeps the pre-findings `signature_ok`/`sbom_ok`/`warnings` keys alongside
+/// `findings` so existing consumers of the JSON keep working.
+#[allow(deprecated)]
+fn report_json(report: &VerifyReport) -> serde_json::Value {
+    json!({
+        "verified": true,
+        "signature_ok": report.signature_ok,
+        "sbom_ok": report.sbom_ok,
+        "warnings": report.warnings,
+        "findings": report.findings,
+    })
+}
+
+/// Reports a rejected pack in the same shape as a verified one, so dashboards
+/// can aggregate rejection codes too.
+fn print_rejection(err: &PackVerifyResult) -> Result<()> {
+    let payload = json!({
         "report": {
-            "signature_ok": report.signature_ok,
-            "sbom_ok": report.sbom_ok,
-            "warnings": report.warnings,
+            "verified": false,
+            "findings": [err.finding],
         },
-        "sbom": sbom,
     });
     println!("{}", serde_json::to_string_pretty(&payload)?);
     Ok(())
@@ -5,6 +5,8 @@ use clap::{Args, Parser, Subcommand};
 
 #[path = "common/events.rs"]
 mod events;
+#[path = "common/extract.rs"]
+mod extract;
 #[path = "common/input.rs"]
 mod input;
 #[path = "common/inspect.rs"]
@@ -23,6 +25,8 @@ struct Cli {
 enum Command {
     /// Inspect a .gtpack archive and display verification info.
     Inspect(InspectArgs),
+    /// Unpack a .gtpack archive into a plain directory layout.
+    Extract(ExtractArgs),
     /// Generate a DeploymentPlan from a pack archive or source directory.
     Plan(PlanArgs),
     /// Events-related helpers.
@@ -43,6 +47,41 @@ struct InspectArgs {
     /// Emit JSON output
     #[arg(long)]
     json: bool,
+
+    /// Print the embedded README.md instead of the summary
+    #[arg(long)]
+    readme: bool,
+
+    /// List every archive entry with its size, digest, compression, and coverage
+    #[arg(long)]
+    files: bool,
+}
+
+#[derive(Args, Debug)]
+struct ExtractArgs {
+    /// Path to the .gtpack file
+    #[arg(value_name = "FILE")]
+    path: PathBuf,
+
+    /// Directory to extract into (must be missing or empty)
+    #[arg(long, value_name = "DIR")]
+    out: PathBuf,
+
+    /// Verify the SBOM and signature before writing anything
+    #[arg(long)]
+    verify: bool,
+
+    /// Signature policy applied when --verify is set
+    #[arg(long, value_enum, default_value_t = inspect::PolicyArg::Devok)]
+    policy: inspect::PolicyArg,
+
+    /// Re-create pack.yaml from the manifest so the directory can be rebuilt
+    #[arg(long)]
+    pack_yaml: bool,
+
+    /// Emit JSON output
+    #[arg(long)]
+    json: bool,
 }
 
 #[derive(Args, Debug)]
@@ -92,7 +131,10 @@ struct EventsListArgs {
 fn main() -> Result<()> {
     let cli = Cli::parse();
     match cli.command {
-        Command::Inspect(args) => inspect::run(&args.path, args.policy, args.json),
+        Command::Inspect(args) => {
+            inspect::run(&args.path, args.policy, args.json, args.readme, args.files)
+        }
+        Command::Extract(args) => extract::run(&args),
         Command::Plan(args) => plan_cmd::run(&args),
         Command::Events(cmd) => match cmd {
             EventsCommand::List(args) => events::list(&args),
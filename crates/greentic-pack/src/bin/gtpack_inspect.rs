@@ -24,9 +24,17 @@ struct Args {
     /// Emit JSON output
     #[arg(long)]
     json: bool,
+
+    /// Print the embedded README.md instead of the summary
+    #[arg(long)]
+    readme: bool,
+
+    /// List every archive entry with its size, digest, compression, and coverage
+    #[arg(long)]
+    files: bool,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
-    inspect::run(&args.path, args.policy, args.json)
+    inspect::run(&args.path, args.policy, args.json, args.readme, args.files)
 }
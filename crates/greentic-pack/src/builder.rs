@@ -1,49 +1,107 @@
-use std::collections::BTreeSet;
+#[cfg(feature = "builder")]
+use std::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "builder")]
 use std::fs;
-use std::io::Write;
+#[cfg(feature = "reader")]
+use std::io::Read;
+#[cfg(feature = "builder")]
+use std::io::{Cursor, Seek, Write};
+#[cfg(feature = "builder")]
 use std::path::{Path, PathBuf};
+#[cfg(feature = "builder")]
 use std::sync::Arc;
 
-use anyhow::{Context, Result, anyhow, bail};
+#[cfg(any(feature = "builder", feature = "reader"))]
+use anyhow::{Context, anyhow};
+use anyhow::{Result, bail};
+#[cfg(feature = "builder")]
 use base64::Engine;
+#[cfg(feature = "builder")]
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+#[cfg(any(feature = "builder", feature = "reader"))]
 use blake3::Hasher;
-use ed25519_dalek::Signer as _;
-use ed25519_dalek::SigningKey;
+#[cfg(feature = "builder")]
+use ed25519_dalek::{Signer as _, SigningKey};
+#[cfg(feature = "builder")]
 use pkcs8::EncodePrivateKey;
+#[cfg(feature = "builder")]
 use rand_core_06::OsRng;
+#[cfg(feature = "builder")]
 use rcgen::{CertificateParams, DistinguishedName, DnType, KeyPair, PKCS_ED25519};
+#[cfg(feature = "builder")]
 use rustls_pki_types::PrivatePkcs8KeyDer;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map as JsonMap, Value as JsonValue};
+#[cfg(feature = "builder")]
 use time::OffsetDateTime;
+#[cfg(feature = "builder")]
 use time::format_description::well_known::Rfc3339;
+#[cfg(feature = "builder")]
 use zip::write::SimpleFileOptions;
+#[cfg(feature = "builder")]
 use zip::{CompressionMethod, DateTime as ZipDateTime, ZipWriter};
 
+#[cfg(feature = "builder")]
+use crate::atomic::{self, Durability};
+use crate::capabilities::CapabilitiesSection;
+use crate::classification::{ClassificationSection, DataHandling};
+use crate::config::ConfigSection;
+use crate::config_schema::{ConfigDefaults, ConfigSchema};
+use crate::dispatch::DispatchSection;
 use crate::events::EventsSection;
+use crate::lifecycle::LifecycleSection;
+use crate::limits::LimitsSection;
+use crate::locales::LocalesSection;
+use crate::media::MediaSection;
+#[cfg(feature = "builder")]
+use crate::media::{MAX_ICON_BYTES, MAX_SCREENSHOT_BYTES, MediaFormat};
 use crate::messaging::MessagingSection;
+use crate::metrics::MetricsSection;
+use crate::permissions::PermissionSummary;
+#[cfg(feature = "builder")]
+use crate::permissions::{I18N_DIR, validate_locale};
+use crate::qos::{FlowQos, QosSection};
+use crate::quotas::QuotaSection;
 use crate::repo::{InterfaceBinding, RepoPackSection};
+use crate::schedule::ScheduleSection;
+use crate::secrets::SecretsSection;
 use greentic_types::PackKind;
 
+#[cfg(any(feature = "builder", feature = "reader"))]
 pub(crate) const SBOM_FORMAT: &str = "greentic-sbom-v1";
+#[cfg(any(feature = "builder", feature = "reader"))]
 pub(crate) const SIGNATURE_PATH: &str = "signatures/pack.sig";
+#[cfg(any(feature = "builder", feature = "reader"))]
 pub(crate) const SIGNATURE_CHAIN_PATH: &str = "signatures/chain.pem";
+#[cfg(feature = "builder")]
+/// BLAKE3 key derivation context for the dev keys of reproducible builds.
+const DEV_KEY_CONTEXT: &str = "greentic-pack 2025 reproducible dev signing key";
 pub const PACK_VERSION: u32 = 1;
+/// Archive path of the pack source spec embedded by `with_source_spec`.
+pub const SOURCE_SPEC_PATH: &str = "pack.yaml";
+pub const MANIFEST_PATH: &str = "manifest.cbor";
+/// Archive path of the zstd-compressed manifest written by
+/// [`ManifestEncoding::Both`] and [`ManifestEncoding::Zstd`].
+pub const MANIFEST_ZSTD_PATH: &str = "manifest.cbor.zst";
 
 fn default_pack_version() -> u32 {
     PACK_VERSION
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct PackMeta {
     #[serde(rename = "packVersion", default = "default_pack_version")]
     pub pack_version: u32,
     pub pack_id: String,
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
     pub version: Version,
     pub name: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
     pub kind: Option<PackKind>,
     #[serde(default)]
     pub description: Option<String>,
@@ -57,6 +115,10 @@ pub struct PackMeta {
     pub support: Option<String>,
     #[serde(default)]
     pub vendor: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
     #[serde(default)]
     pub imports: Vec<ImportRef>,
     pub entry_flows: Vec<String>,
@@ -69,11 +131,54 @@ pub struct PackMeta {
     pub messaging: Option<MessagingSection>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub interfaces: Vec<InterfaceBinding>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<MediaSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<ConfigSection>,
+    /// Install-time settings; the build derives it from `config.params`
+    /// when the pack declares no schema of its own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_schema: Option<ConfigSchema>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_defaults: Option<ConfigDefaults>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lifecycle: Option<LifecycleSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quotas: Option<QuotaSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<LimitsSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dispatch: Option<DispatchSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qos: Option<QosSection>,
+    /// Network hosts, paths, secrets, and environment variables the pack
+    /// asks the host to grant.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<CapabilitiesSection>,
+    /// Secrets flows read, for provisioning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<SecretsSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<MetricsSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<ScheduleSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classification: Option<ClassificationSection>,
+    /// Template locales, with every variant recorded by the build.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locales: Option<LocalesSection>,
+    /// Summary of `classification`, filled in by the build.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_handling: Option<DataHandling>,
+    /// Consent prompts for what the flows require, filled in by the build.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<PermissionSummary>,
     #[serde(default)]
     pub annotations: JsonMap<String, JsonValue>,
 }
 
 impl PackMeta {
+    #[cfg(feature = "builder")]
     fn validate(&self) -> Result<()> {
         if self.pack_version != PACK_VERSION {
             bail!(
@@ -106,23 +211,86 @@ impl PackMeta {
         for binding in &self.interfaces {
             binding.validate("interfaces")?;
         }
+        if let Some(config) = &self.config {
+            config.validate()?;
+        }
+        if let Some(config_schema) = &self.config_schema {
+            config_schema.validate()?;
+        }
+        if let Some(config_defaults) = &self.config_defaults {
+            config_defaults.validate(self.config_schema.as_ref())?;
+        }
+        if let Some(lifecycle) = &self.lifecycle {
+            lifecycle.validate()?;
+        }
+        if let Some(quotas) = &self.quotas {
+            quotas.validate()?;
+        }
+        if let Some(limits) = &self.limits {
+            limits.validate()?;
+        }
+        if let Some(dispatch) = &self.dispatch {
+            dispatch.validate()?;
+        }
+        if let Some(qos) = &self.qos {
+            qos.validate()?;
+        }
+        if let Some(capabilities) = &self.capabilities {
+            capabilities.validate()?;
+        }
+        if let Some(secrets) = &self.secrets {
+            secrets.validate()?;
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.validate()?;
+        }
+        if let Some(schedule) = &self.schedule {
+            schedule.validate()?;
+        }
+        if let Some(classification) = &self.classification {
+            classification.validate()?;
+        }
+        if let Some(locales) = &self.locales {
+            locales.validate()?;
+        }
+        if let Some(permissions) = &self.permissions {
+            permissions.validate()?;
+        }
+        if let Some(media) = &self.media {
+            media.validate()?;
+            for entry in media.entries() {
+                if !entry.path.starts_with("media/") {
+                    bail!(
+                        "media path `{}` must point into the archive's media/ directory",
+                        entry.path
+                    );
+                }
+            }
+        }
         Ok(())
     }
 }
 
+#[cfg(feature = "builder")]
 pub use greentic_flow::flow_bundle::{ComponentPin, FlowBundle, NodeRef};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct ImportRef {
     pub pack_id: String,
     pub version_req: String,
+    /// Hosts may load the pack without this import; flows fall back where
+    /// they use it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
 }
 
+#[cfg(feature = "builder")]
 #[derive(Clone, Debug)]
 pub struct ComponentArtifact {
     pub name: String,
     pub version: Version,
-    pub wasm_path: PathBuf,
+    pub wasm: WasmSource,
     pub schema_json: Option<String>,
     pub manifest_json: Option<String>,
     pub capabilities: Option<JsonValue>,
@@ -130,6 +298,16 @@ pub struct ComponentArtifact {
     pub hash_blake3: Option<String>,
 }
 
+/// Where a component's wasm is read from.
+#[cfg(feature = "builder")]
+#[derive(Clone, Debug)]
+pub enum WasmSource {
+    /// A file read when the pack is built.
+    Path(PathBuf),
+    /// Bytes already in memory, for archives assembled without touching disk.
+    Bytes(Vec<u8>),
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Provenance {
     pub builder: String,
@@ -144,21 +322,29 @@ pub struct Provenance {
     pub host: Option<String>,
     #[serde(default)]
     pub notes: Option<String>,
+    /// BLAKE3 digest of the host profile data a profile-guided build was
+    /// laid out by.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_blake3: Option<String>,
 }
 
+#[cfg(feature = "builder")]
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ExternalSignature {
     pub alg: String,
     pub sig: Vec<u8>,
 }
 
+#[cfg(feature = "builder")]
 pub trait Signer: Send + Sync {
     fn sign(&self, message: &[u8]) -> Result<ExternalSignature>;
     fn chain_pem(&self) -> Result<Vec<u8>>;
 }
 
+#[cfg(feature = "builder")]
 type DynSigner = dyn Signer + Send + Sync + 'static;
 
+#[cfg(feature = "builder")]
 #[derive(Clone, Default)]
 pub enum Signing {
     #[default]
@@ -167,27 +353,95 @@ pub enum Signing {
     External(Arc<DynSigner>),
 }
 
+#[cfg(feature = "builder")]
 pub struct PackBuilder {
     meta: PackMeta,
     flows: Vec<FlowBundle>,
+    eager_flows: BTreeSet<String>,
     components: Vec<ComponentArtifact>,
     assets: Vec<Asset>,
+    media: Vec<Asset>,
+    i18n: BTreeMap<String, Vec<u8>>,
+    readme: Option<String>,
+    source_spec: Option<String>,
     signing: Signing,
     provenance: Option<Provenance>,
+    durability: Durability,
+    manifest_encoding: ManifestEncoding,
+    timestamp: Option<String>,
+}
+
+/// Which manifest entries a pack carries. Readers prefer
+/// `manifest.cbor.zst` when present and fall back to `manifest.cbor`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestEncoding {
+    /// Only `manifest.cbor`, which every reader understands.
+    #[default]
+    Plain,
+    /// `manifest.cbor` and `manifest.cbor.zst`.
+    Both,
+    /// Only `manifest.cbor.zst`; readers without zstd support reject the pack.
+    Zstd,
+}
+
+impl std::str::FromStr for ManifestEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "plain" => Ok(Self::Plain),
+            "both" => Ok(Self::Both),
+            "zstd" => Ok(Self::Zstd),
+            other => bail!("unknown manifest encoding `{other}`; expected plain, both, or zstd"),
+        }
+    }
+}
+
+/// Sizes of the manifest entries, to judge whether compression pays off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ManifestSizes {
+    /// Size of the CBOR manifest, whether or not it is written as is.
+    pub cbor: u64,
+    /// Size of `manifest.cbor.zst`, when one is written.
+    pub zstd: Option<u64>,
 }
 
+#[cfg(feature = "builder")]
 struct Asset {
     path: String,
     bytes: Vec<u8>,
 }
 
+#[cfg(feature = "builder")]
 #[derive(Debug, Clone)]
 pub struct BuildResult {
     pub out_path: PathBuf,
     pub manifest_hash_blake3: String,
+    pub manifest_sizes: ManifestSizes,
     pub files: Vec<SbomEntry>,
 }
 
+/// A `.gtpack` built in memory by [`PackBuilder::build_to_vec`].
+#[cfg(feature = "builder")]
+#[derive(Debug, Clone)]
+pub struct BuiltArchive {
+    pub bytes: Vec<u8>,
+    pub manifest_hash_blake3: String,
+    pub manifest_sizes: ManifestSizes,
+    /// The entries `sbom.json` lists.
+    pub files: Vec<SbomEntry>,
+}
+
+/// The entries of an archive, sorted by path, before they are zipped.
+#[cfg(feature = "builder")]
+struct Assembled {
+    files: Vec<PendingFile>,
+    manifest_hash_blake3: String,
+    manifest_sizes: ManifestSizes,
+    sbom: Vec<SbomEntry>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SbomEntry {
     pub path: String,
@@ -196,7 +450,9 @@ pub struct SbomEntry {
     pub media_type: String,
 }
 
+/// The `manifest.cbor` of a `.gtpack`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct PackManifest {
     pub meta: PackMeta,
     pub flows: Vec<FlowEntry>,
@@ -204,6 +460,7 @@ pub struct PackManifest {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct FlowEntry {
     pub id: String,
     pub kind: String,
@@ -211,11 +468,20 @@ pub struct FlowEntry {
     pub file_yaml: String,
     pub file_json: String,
     pub hash_blake3: String,
+    /// Hosts should instantiate the flow when the pack loads rather than on
+    /// its first run.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub eager: bool,
+    /// Scheduling class from the pack's `qos` section; `standard` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qos: Option<FlowQos>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct ComponentEntry {
     pub name: String,
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
     pub version: Version,
     pub file_wasm: String,
     pub hash_blake3: String,
@@ -225,6 +491,7 @@ pub struct ComponentEntry {
     pub capabilities: Option<JsonValue>,
 }
 
+#[cfg(any(feature = "builder", feature = "reader"))]
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct SignatureEnvelope {
     pub alg: String,
@@ -235,16 +502,15 @@ pub(crate) struct SignatureEnvelope {
     pub key_fingerprint: Option<String>,
 }
 
+#[cfg(feature = "builder")]
 impl SignatureEnvelope {
     fn new(
         alg: impl Into<String>,
         sig_bytes: &[u8],
         digest: &blake3::Hash,
         key_fingerprint: Option<String>,
+        signed_at: String,
     ) -> Self {
-        let signed_at = OffsetDateTime::now_utc()
-            .format(&Rfc3339)
-            .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string());
         Self {
             alg: alg.into(),
             sig: URL_SAFE_NO_PAD.encode(sig_bytes),
@@ -255,14 +521,16 @@ impl SignatureEnvelope {
     }
 }
 
-struct PendingFile {
+#[cfg(feature = "builder")]
+pub(crate) struct PendingFile {
     path: String,
     media_type: String,
     bytes: Vec<u8>,
 }
 
+#[cfg(feature = "builder")]
 impl PendingFile {
-    fn new(path: String, media_type: impl Into<String>, bytes: Vec<u8>) -> Self {
+    pub(crate) fn new(path: String, media_type: impl Into<String>, bytes: Vec<u8>) -> Self {
         Self {
             path,
             media_type: media_type.into(),
@@ -279,15 +547,24 @@ impl PendingFile {
     }
 }
 
+#[cfg(feature = "builder")]
 impl PackBuilder {
     pub fn new(meta: PackMeta) -> Self {
         Self {
             meta,
             flows: Vec::new(),
+            eager_flows: BTreeSet::new(),
             components: Vec::new(),
             assets: Vec::new(),
+            media: Vec::new(),
+            i18n: BTreeMap::new(),
+            readme: None,
+            source_spec: None,
             signing: Signing::Dev,
             provenance: None,
+            durability: Durability::Buffered,
+            manifest_encoding: ManifestEncoding::Plain,
+            timestamp: None,
         }
     }
 
@@ -296,6 +573,13 @@ impl PackBuilder {
         self
     }
 
+    /// Marks a flow added with [`PackBuilder::with_flow`] for eager
+    /// instantiation.
+    pub fn with_eager_flow(mut self, flow_id: impl Into<String>) -> Self {
+        self.eager_flows.insert(flow_id.into());
+        self
+    }
+
     pub fn with_component(mut self, component: ComponentArtifact) -> Self {
         self.components.push(component);
         self
@@ -310,7 +594,7 @@ impl PackBuilder {
         self.with_component(ComponentArtifact {
             name: name.into(),
             version,
-            wasm_path: wasm_path.into(),
+            wasm: WasmSource::Path(wasm_path.into()),
             schema_json: None,
             manifest_json: None,
             capabilities: None,
@@ -327,6 +611,35 @@ impl PackBuilder {
         self
     }
 
+    /// Adds an image stored under `media/<path_in_pack>`. Reference it from
+    /// `PackMeta::media` using the archive path (e.g. `media/icon.png`).
+    pub fn with_media_bytes(mut self, path_in_pack: impl Into<String>, bytes: Vec<u8>) -> Self {
+        self.media.push(Asset {
+            path: path_in_pack.into(),
+            bytes,
+        });
+        self
+    }
+
+    /// Embeds the i18n bundle of `locale`, a flat JSON object of messages, at
+    /// `i18n/<locale>.json`. Hosts render templates with these strings.
+    pub fn with_i18n_bundle(mut self, locale: impl Into<String>, bytes: Vec<u8>) -> Self {
+        self.i18n.insert(locale.into(), bytes);
+        self
+    }
+
+    /// Embeds a Markdown README at the archive root (`README.md`).
+    pub fn with_readme(mut self, markdown: impl Into<String>) -> Self {
+        self.readme = Some(markdown.into());
+        self
+    }
+
+    /// Embeds the source `pack.yaml` so the archive can be extracted and rebuilt.
+    pub fn with_source_spec(mut self, yaml: impl Into<String>) -> Self {
+        self.source_spec = Some(yaml.into());
+        self
+    }
+
     pub fn with_signing(mut self, signing: Signing) -> Self {
         self.signing = signing;
         self
@@ -337,8 +650,65 @@ impl PackBuilder {
         self
     }
 
+    /// Controls whether the archive is fsynced before it replaces `out_path`.
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    /// Chooses the manifest entries written; see [`ManifestEncoding`].
+    pub fn with_manifest_encoding(mut self, encoding: ManifestEncoding) -> Self {
+        self.manifest_encoding = encoding;
+        self
+    }
+
+    /// Pins the RFC 3339 time recorded as the signing time and, unless the
+    /// provenance sets one, the build time. Dev signatures then use a key
+    /// derived from the signed digest, so identical inputs produce a
+    /// byte-identical archive.
+    pub fn with_timestamp(mut self, timestamp: impl Into<String>) -> Self {
+        self.timestamp = Some(timestamp.into());
+        self
+    }
+
     pub fn build(self, out_path: impl AsRef<Path>) -> Result<BuildResult> {
-        let meta = self.meta;
+        let durability = self.durability;
+        let assembled = self.assemble()?;
+
+        let out_path = out_path.as_ref().to_path_buf();
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create directory {}", parent.display()))?;
+        }
+
+        write_zip(&out_path, &assembled.files, durability)?;
+
+        Ok(BuildResult {
+            out_path,
+            manifest_hash_blake3: assembled.manifest_hash_blake3,
+            manifest_sizes: assembled.manifest_sizes,
+            files: assembled.sbom,
+        })
+    }
+
+    /// Builds the archive in memory rather than into a file, byte for byte
+    /// what [`PackBuilder::build`] writes.
+    pub fn build_to_vec(self) -> Result<BuiltArchive> {
+        let assembled = self.assemble()?;
+        let bytes = zip_entries(Cursor::new(Vec::new()), &assembled.files)?.into_inner();
+        Ok(BuiltArchive {
+            bytes,
+            manifest_hash_blake3: assembled.manifest_hash_blake3,
+            manifest_sizes: assembled.manifest_sizes,
+            files: assembled.sbom,
+        })
+    }
+
+    fn assemble(self) -> Result<Assembled> {
+        let mut meta = self.meta;
+        if meta.config_schema.is_none() {
+            meta.config_schema = meta.config.as_ref().and_then(ConfigSection::params_schema);
+        }
         meta.validate()?;
 
         if self.flows.is_empty() {
@@ -375,6 +745,11 @@ impl PackBuilder {
             ));
 
             flow_entries.push(FlowEntry {
+                eager: self.eager_flows.contains(&flow.id),
+                qos: meta
+                    .qos
+                    .as_ref()
+                    .and_then(|qos| qos.flows.get(&flow.id).copied()),
                 id: flow.id,
                 kind: flow.kind,
                 entry: flow.entry,
@@ -389,6 +764,33 @@ impl PackBuilder {
                 bail!("entry flow `{}` not present in provided flows", entry);
             }
         }
+        if let Some(eager) = self.eager_flows.difference(&seen_flow_ids).next() {
+            bail!("eager flow `{}` not present in provided flows", eager);
+        }
+        if let Some(lifecycle) = &meta.lifecycle {
+            lifecycle.check_flows(seen_flow_ids.iter().map(String::as_str))?;
+        }
+        if let Some(quotas) = &meta.quotas {
+            quotas.check_references(
+                seen_flow_ids.iter().map(String::as_str),
+                meta.imports.iter().map(|import| import.pack_id.as_str()),
+            )?;
+        }
+        if let Some(limits) = &meta.limits {
+            limits.check_flows(seen_flow_ids.iter().map(String::as_str))?;
+        }
+        if let Some(dispatch) = &meta.dispatch {
+            dispatch.check_flows(seen_flow_ids.iter().map(String::as_str))?;
+        }
+        if let Some(qos) = &meta.qos {
+            qos.check_flows(seen_flow_ids.iter().map(String::as_str))?;
+        }
+        if let Some(schedule) = &meta.schedule {
+            schedule.check_flows(seen_flow_ids.iter().map(String::as_str))?;
+        }
+        if let Some(classification) = &meta.classification {
+            classification.check_flows(seen_flow_ids.iter().map(String::as_str))?;
+        }
 
         flow_entries.sort_by(|a, b| a.id.cmp(&b.id));
 
@@ -402,12 +804,12 @@ impl PackBuilder {
                 bail!("duplicate component artifact detected: {}", key);
             }
 
-            let wasm_bytes = fs::read(&component.wasm_path).with_context(|| {
-                format!(
-                    "failed to read component wasm at {}",
-                    component.wasm_path.display()
-                )
-            })?;
+            let wasm_bytes = match component.wasm {
+                WasmSource::Bytes(bytes) => bytes,
+                WasmSource::Path(path) => fs::read(&path).with_context(|| {
+                    format!("failed to read component wasm at {}", path.display())
+                })?,
+            };
             let wasm_hash = hex_hash(&wasm_bytes);
             if let Some(expected) = component.hash_blake3.as_deref()
                 && !equals_ignore_case(expected, &wasm_hash)
@@ -474,6 +876,61 @@ impl PackBuilder {
             ));
         }
 
+        let mut media_paths = BTreeSet::new();
+        for blob in self.media {
+            let path = normalize_relative_path(&["media", &blob.path])?;
+            let format = MediaFormat::from_path(&path)
+                .ok_or_else(|| anyhow!("media `{}` has an unsupported image format", path))?;
+            if !media_paths.insert(path.clone()) {
+                bail!("duplicate media entry detected: {}", path);
+            }
+            if let Some(section) = &meta.media {
+                let limit = if section.icon.as_ref().is_some_and(|icon| icon.path == path) {
+                    MAX_ICON_BYTES
+                } else {
+                    MAX_SCREENSHOT_BYTES
+                };
+                if let Some(entry) = section.entries().find(|entry| entry.path == path) {
+                    crate::media::validate_media_bytes("media", entry, &blob.bytes, limit)?;
+                }
+            }
+            pending_files.push(PendingFile::new(path, format.media_type(), blob.bytes));
+        }
+        if let Some(section) = &meta.media {
+            for entry in section.entries() {
+                if !media_paths.contains(&entry.path) {
+                    bail!("media `{}` referenced by meta was not provided", entry.path);
+                }
+            }
+        }
+
+        for (locale, bytes) in self.i18n {
+            validate_locale(&locale).with_context(|| format!("i18n bundle `{locale}`"))?;
+            serde_json::from_slice::<JsonMap<String, JsonValue>>(&bytes)
+                .with_context(|| format!("i18n bundle `{locale}` is not a JSON object"))?;
+            pending_files.push(PendingFile::new(
+                format!("{I18N_DIR}/{locale}.json"),
+                "application/json",
+                bytes,
+            ));
+        }
+
+        if let Some(readme) = self.readme {
+            pending_files.push(PendingFile::new(
+                crate::readme::README_PATH.to_string(),
+                "text/markdown",
+                normalize_newlines(&readme).into_bytes(),
+            ));
+        }
+
+        if let Some(spec) = self.source_spec {
+            pending_files.push(PendingFile::new(
+                SOURCE_SPEC_PATH.to_string(),
+                "application/yaml",
+                normalize_newlines(&spec).into_bytes(),
+            ));
+        }
+
         let manifest_model = PackManifest {
             meta: meta.clone(),
             flows: flow_entries,
@@ -483,18 +940,43 @@ impl PackBuilder {
         let manifest_cbor = encode_manifest_cbor(&manifest_model)?;
         let manifest_json = serde_json::to_vec_pretty(&manifest_model)?;
 
-        pending_files.push(PendingFile::new(
-            "manifest.cbor".to_string(),
-            "application/cbor",
-            manifest_cbor.clone(),
-        ));
+        let manifest_zstd = match self.manifest_encoding {
+            ManifestEncoding::Plain => None,
+            ManifestEncoding::Both | ManifestEncoding::Zstd => {
+                Some(compress_manifest(&manifest_cbor))
+            }
+        };
+        let manifest_sizes = ManifestSizes {
+            cbor: manifest_cbor.len() as u64,
+            zstd: manifest_zstd.as_ref().map(|bytes| bytes.len() as u64),
+        };
+        if self.manifest_encoding != ManifestEncoding::Zstd {
+            pending_files.push(PendingFile::new(
+                MANIFEST_PATH.to_string(),
+                "application/cbor",
+                manifest_cbor.clone(),
+            ));
+        }
+        if let Some(compressed) = manifest_zstd {
+            pending_files.push(PendingFile::new(
+                MANIFEST_ZSTD_PATH.to_string(),
+                "application/zstd",
+                compressed,
+            ));
+        }
         pending_files.push(PendingFile::new(
             "manifest.json".to_string(),
             "application/json",
             manifest_json,
         ));
 
-        let provenance = finalize_provenance(self.provenance);
+        let reproducible = self.timestamp.is_some();
+        let timestamp = self.timestamp.unwrap_or_else(|| {
+            OffsetDateTime::now_utc()
+                .format(&Rfc3339)
+                .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string())
+        });
+        let provenance = finalize_provenance(self.provenance, &timestamp);
         let provenance_json = serde_json::to_vec_pretty(&provenance)?;
         pending_files.push(PendingFile::new(
             "provenance.json".to_string(),
@@ -530,9 +1012,9 @@ impl PackBuilder {
         if !matches!(self.signing, Signing::None) {
             let digest = signature_digest_from_entries(&build_files, &manifest_cbor, &sbom_bytes);
             let (signature_doc, chain_bytes) = match &self.signing {
-                Signing::Dev => dev_signature(&digest)?,
+                Signing::Dev => dev_signature(&digest, timestamp, reproducible)?,
                 Signing::None => unreachable!(),
-                Signing::External(signer) => external_signature(&**signer, &digest)?,
+                Signing::External(signer) => external_signature(&**signer, &digest, timestamp)?,
             };
 
             let sig_bytes = serde_json::to_vec_pretty(&signature_doc)?;
@@ -555,30 +1037,26 @@ impl PackBuilder {
         all_files.extend(signature_files);
         all_files.sort_by(|a, b| a.path.cmp(&b.path));
 
-        let out_path = out_path.as_ref().to_path_buf();
-        if let Some(parent) = out_path.parent() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("failed to create directory {}", parent.display()))?;
-        }
-
-        write_zip(&out_path, &all_files)?;
-
-        Ok(BuildResult {
-            out_path,
+        Ok(Assembled {
+            files: all_files,
             manifest_hash_blake3: manifest_hash,
-            files: build_files,
+            manifest_sizes,
+            sbom: build_files,
         })
     }
 }
 
+#[cfg(feature = "builder")]
 fn equals_ignore_case(expected: &str, actual: &str) -> bool {
     expected.trim().eq_ignore_ascii_case(actual.trim())
 }
 
+#[cfg(feature = "builder")]
 fn normalize_newlines(input: &str) -> String {
     input.replace("\r\n", "\n")
 }
 
+#[cfg(feature = "builder")]
 fn normalize_relative_path(parts: &[&str]) -> Result<String> {
     let mut segments = Vec::new();
     for part in parts {
@@ -596,6 +1074,7 @@ fn normalize_relative_path(parts: &[&str]) -> Result<String> {
     Ok(segments.join("/"))
 }
 
+#[cfg(feature = "builder")]
 fn validate_identifier(value: &str, label: &str) -> Result<()> {
     if value.trim().is_empty() {
         bail!("{} must not be empty", label);
@@ -606,6 +1085,29 @@ fn validate_identifier(value: &str, label: &str) -> Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "builder")]
+fn compress_manifest(manifest_cbor: &[u8]) -> Vec<u8> {
+    ruzstd::encoding::compress_to_vec(manifest_cbor, ruzstd::encoding::CompressionLevel::Fastest)
+}
+
+/// Decompresses a `manifest.cbor.zst` entry, refusing output beyond `limit`
+/// bytes.
+#[cfg(feature = "reader")]
+pub(crate) fn decompress_manifest(compressed: &[u8], limit: u64) -> Result<Vec<u8>> {
+    let decoder = ruzstd::decoding::StreamingDecoder::new(compressed)
+        .map_err(|err| anyhow!("{MANIFEST_ZSTD_PATH} is not a zstd frame: {err}"))?;
+    let mut manifest = Vec::new();
+    decoder
+        .take(limit + 1)
+        .read_to_end(&mut manifest)
+        .with_context(|| format!("failed to decompress {MANIFEST_ZSTD_PATH}"))?;
+    if manifest.len() as u64 > limit {
+        bail!("{MANIFEST_ZSTD_PATH} decompresses to more than {limit} bytes");
+    }
+    Ok(manifest)
+}
+
+#[cfg(feature = "builder")]
 fn encode_manifest_cbor(manifest: &PackManifest) -> Result<Vec<u8>> {
     let mut buffer = Vec::new();
     {
@@ -616,18 +1118,16 @@ fn encode_manifest_cbor(manifest: &PackManifest) -> Result<Vec<u8>> {
     Ok(buffer)
 }
 
-fn finalize_provenance(provenance: Option<Provenance>) -> Provenance {
+#[cfg(feature = "builder")]
+fn finalize_provenance(provenance: Option<Provenance>, timestamp: &str) -> Provenance {
     let builder_default = format!("greentic-pack@{}", env!("CARGO_PKG_VERSION"));
-    let now = OffsetDateTime::now_utc()
-        .format(&Rfc3339)
-        .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string());
     match provenance {
         Some(mut prov) => {
             if prov.builder.trim().is_empty() {
                 prov.builder = builder_default;
             }
             if prov.built_at_utc.trim().is_empty() {
-                prov.built_at_utc = now;
+                prov.built_at_utc = timestamp.to_string();
             }
             prov
         }
@@ -636,13 +1136,15 @@ fn finalize_provenance(provenance: Option<Provenance>) -> Provenance {
             git_commit: None,
             git_repo: None,
             toolchain: None,
-            built_at_utc: now,
+            built_at_utc: timestamp.to_string(),
             host: None,
             notes: None,
+            profile_blake3: None,
         },
     }
 }
 
+#[cfg(any(feature = "builder", feature = "reader"))]
 pub(crate) fn signature_digest_from_entries(
     entries: &[SbomEntry],
     manifest_cbor: &[u8],
@@ -667,9 +1169,20 @@ pub(crate) fn signature_digest_from_entries(
     hasher.finalize()
 }
 
-fn dev_signature(digest: &blake3::Hash) -> Result<(SignatureEnvelope, Option<Vec<u8>>)> {
-    let mut rng = OsRng;
-    let signing_key = SigningKey::generate(&mut rng);
+#[cfg(feature = "builder")]
+/// Signs with a throwaway key. A reproducible signature uses a key derived
+/// from `digest` instead; like any dev signature it proves nothing about
+/// who built the pack.
+fn dev_signature(
+    digest: &blake3::Hash,
+    signed_at: String,
+    reproducible: bool,
+) -> Result<(SignatureEnvelope, Option<Vec<u8>>)> {
+    let signing_key = if reproducible {
+        SigningKey::from_bytes(&blake3::derive_key(DEV_KEY_CONTEXT, digest.as_bytes()))
+    } else {
+        SigningKey::generate(&mut OsRng)
+    };
     let signature = signing_key.sign(digest.as_bytes());
     let signature_bytes = signature.to_bytes();
 
@@ -689,13 +1202,21 @@ fn dev_signature(digest: &blake3::Hash) -> Result<(SignatureEnvelope, Option<Vec
     let chain = normalize_newlines(&cert.pem()).into_bytes();
     let fingerprint = hex_hash(signing_key.verifying_key().as_bytes());
 
-    let envelope = SignatureEnvelope::new("ed25519", &signature_bytes, digest, Some(fingerprint));
+    let envelope = SignatureEnvelope::new(
+        "ed25519",
+        &signature_bytes,
+        digest,
+        Some(fingerprint),
+        signed_at,
+    );
     Ok((envelope, Some(chain)))
 }
 
+#[cfg(feature = "builder")]
 fn external_signature(
     signer: &DynSigner,
     digest: &blake3::Hash,
+    signed_at: String,
 ) -> Result<(SignatureEnvelope, Option<Vec<u8>>)> {
     let ExternalSignature { alg, sig } = signer.sign(digest.as_bytes())?;
     let chain = signer.chain_pem()?;
@@ -705,18 +1226,32 @@ fn external_signature(
         let chain_str = String::from_utf8(chain)?;
         Some(normalize_newlines(&chain_str).into_bytes())
     };
-    let envelope = SignatureEnvelope::new(alg, &sig, digest, None);
+    let envelope = SignatureEnvelope::new(alg, &sig, digest, None, signed_at);
     Ok((envelope, chain_bytes))
 }
 
+#[cfg(any(feature = "builder", feature = "reader"))]
 pub(crate) fn hex_hash(bytes: &[u8]) -> String {
     blake3::hash(bytes).to_hex().to_string()
 }
 
-fn write_zip(out_path: &Path, files: &[PendingFile]) -> Result<()> {
-    let file = fs::File::create(out_path)
-        .with_context(|| format!("failed to create {}", out_path.display()))?;
-    let mut writer = ZipWriter::new(file);
+#[cfg(feature = "builder")]
+pub(crate) fn write_zip(
+    out_path: &Path,
+    files: &[PendingFile],
+    durability: Durability,
+) -> Result<()> {
+    atomic::write_with(out_path, durability, |file| {
+        zip_entries(file, files)?;
+        Ok(())
+    })
+}
+
+/// Zips `files` in order, stored uncompressed with a fixed timestamp and
+/// permissions so equal entries make equal archives.
+#[cfg(feature = "builder")]
+fn zip_entries<W: Write + Seek>(sink: W, files: &[PendingFile]) -> Result<W> {
+    let mut writer = ZipWriter::new(sink);
     let timestamp = zip_timestamp();
 
     for entry in files {
@@ -733,15 +1268,15 @@ fn write_zip(out_path: &Path, files: &[PendingFile]) -> Result<()> {
             .with_context(|| format!("failed to write {}", entry.path))?;
     }
 
-    writer.finish().context("failed to finish gtpack archive")?;
-    Ok(())
+    writer.finish().context("failed to finish gtpack archive")
 }
 
+#[cfg(feature = "builder")]
 fn zip_timestamp() -> ZipDateTime {
     ZipDateTime::from_date_and_time(1980, 1, 1, 0, 0, 0).unwrap_or_else(|_| ZipDateTime::default())
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "builder", feature = "reader"))]
 mod tests {
     use super::*;
     use serde_json::json;
@@ -791,6 +1326,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn builds_in_memory_what_build_writes() {
+        let temp = tempdir().unwrap();
+        let wasm_path = temp.path().join("component.wasm");
+        fs::write(&wasm_path, test_wasm_bytes()).unwrap();
+
+        let builder = || {
+            PackBuilder::new(sample_meta())
+                .with_flow(sample_flow())
+                .with_provenance(sample_provenance())
+                .with_timestamp("2025-01-01T00:00:00Z")
+        };
+        let built = builder()
+            .with_component(ComponentArtifact {
+                wasm: WasmSource::Bytes(test_wasm_bytes()),
+                ..sample_component(Path::new("missing.wasm"))
+            })
+            .build_to_vec()
+            .unwrap();
+        let out_path = temp.path().join("pack.gtpack");
+        let written = builder()
+            .with_component(sample_component(&wasm_path))
+            .build(&out_path)
+            .unwrap();
+
+        assert_eq!(built.bytes, fs::read(&out_path).unwrap());
+        assert_eq!(built.files, written.files);
+        assert_eq!(built.manifest_hash_blake3, written.manifest_hash_blake3);
+        let load = crate::open_pack_bytes(&built.bytes, crate::SigningPolicy::DevOk).unwrap();
+        assert_eq!(load.manifest.components[0].name, "oauth");
+    }
+
     #[test]
     fn dev_signing_writes_signature_files() {
         let temp = tempdir().unwrap();
@@ -831,6 +1398,32 @@ mod tests {
         assert!(chain_found, "certificate chain should be present");
     }
 
+    #[test]
+    fn pinned_timestamp_makes_dev_signed_builds_reproducible() {
+        let temp = tempdir().unwrap();
+        let wasm_path = temp.path().join("component.wasm");
+        fs::write(&wasm_path, test_wasm_bytes()).unwrap();
+
+        let build = |name: &str| {
+            let out = temp.path().join(name);
+            PackBuilder::new(sample_meta())
+                .with_flow(sample_flow())
+                .with_component(sample_component(&wasm_path))
+                .with_signing(Signing::Dev)
+                .with_timestamp("2025-01-01T00:00:00Z")
+                .build(&out)
+                .unwrap();
+            fs::read(out).unwrap()
+        };
+
+        let bytes = build("a.gtpack");
+        assert_eq!(bytes, build("b.gtpack"));
+        let load = crate::open_pack_bytes(&bytes, crate::SigningPolicy::DevOk).unwrap();
+        let provenance: Provenance =
+            serde_json::from_slice(load.file("provenance.json").unwrap()).unwrap();
+        assert_eq!(provenance.built_at_utc, "2025-01-01T00:00:00Z");
+    }
+
     fn sample_meta() -> PackMeta {
         PackMeta {
             pack_version: PACK_VERSION,
@@ -844,6 +1437,8 @@ mod tests {
             homepage: None,
             support: None,
             vendor: None,
+            tags: Vec::new(),
+            categories: Vec::new(),
             imports: Vec::new(),
             entry_flows: vec!["main".to_string()],
             created_at_utc: "2025-01-01T00:00:00Z".to_string(),
@@ -851,6 +1446,23 @@ mod tests {
             repo: None,
             messaging: None,
             interfaces: Vec::new(),
+            media: None,
+            config: None,
+            config_schema: None,
+            config_defaults: None,
+            lifecycle: None,
+            quotas: None,
+            limits: None,
+            dispatch: None,
+            qos: None,
+            secrets: None,
+            capabilities: None,
+            metrics: None,
+            schedule: None,
+            classification: None,
+            locales: None,
+            data_handling: None,
+            permissions: None,
             annotations: JsonMap::new(),
         }
     }
@@ -880,7 +1492,7 @@ mod tests {
         ComponentArtifact {
             name: "oauth".to_string(),
             version: Version::parse("1.0.0").unwrap(),
-            wasm_path: wasm_path.to_path_buf(),
+            wasm: WasmSource::Path(wasm_path.to_path_buf()),
             schema_json: None,
             manifest_json: None,
             capabilities: None,
@@ -902,6 +1514,7 @@ mod tests {
             built_at_utc: "2025-01-01T00:00:00Z".to_string(),
             host: Some("ci".to_string()),
             notes: None,
+            profile_blake3: None,
         }
     }
 }
@@ -0,0 +1,417 @@
+//! Capabilities flows require, and host policy that denies some of them.
+//!
+//! A flow requires the component of each of its nodes (`mcp.exec`,
+//! `email.send`, ...) and the pack imports it uses. An import is used when a
+//! node component lives under the import's root (`mcp.exec` uses `mcp.*` and
+//! `greentic:host-import/mcp`) or when node configuration refers to that root
+//! as a namespace, e.g. `secrets.api_token`.
+//!
+//! Hosts that load a pack but must not grant some capabilities (say, outbound
+//! email in trial tenants) pass a [`CapabilityFilter`] to
+//! [`PackLoad::downgrade`](crate::PackLoad::downgrade); flows that need a
+//! denied capability are disabled and reported instead of failing the load,
+//! unless the capability is an optional import the flow has fallbacks for.
+//!
+//! Independently of its flows, a pack declares the sandbox it needs under
+//! `capabilities` in `pack.yaml`, so hosts can grant exactly that:
+//!
+//! ```yaml
+//! capabilities:
+//!   network: [api.example.com, "*.crm.example.com:8443"]
+//!   filesystem:
+//!     - path: /data/cache
+//!       access: read_write
+//!   secrets: [CRM_TOKEN]
+//!   env: [HTTPS_PROXY]
+//! ```
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::fallbacks::{FALLBACK_KEY, NodeFallbacks};
+use crate::import_spec::ImportSpec;
+use crate::node_docs::DOC_KEY;
+use crate::secrets::is_secret_name;
+
+/// What a single flow needs from the host.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct FlowRequirements {
+    /// Node count per component.
+    pub components: BTreeMap<String, usize>,
+    /// Pack imports the flow uses, in declaration order and string form.
+    pub imports: Vec<String>,
+    /// Capabilities by node id: the node's components, then its imports.
+    #[serde(skip)]
+    pub nodes: BTreeMap<String, Vec<String>>,
+}
+
+impl FlowRequirements {
+    /// Every capability the flow requires: components first, then imports.
+    pub fn capabilities(&self) -> impl Iterator<Item = &str> {
+        self.components
+            .keys()
+            .chain(self.imports.iter())
+            .map(String::as_str)
+    }
+}
+
+/// Reads the requirements of a flow from its YAML source; `imports` are the
+/// pack's declared imports.
+pub fn flow_requirements(flow_yaml: &str, imports: &[ImportSpec]) -> Result<FlowRequirements> {
+    let source: JsonValue =
+        serde_yaml_bw::from_str(flow_yaml).context("flow source is not valid YAML")?;
+
+    let mut components = BTreeMap::new();
+    let mut nodes = BTreeMap::new();
+    if let Some(source_nodes) = source.get("nodes").and_then(JsonValue::as_object) {
+        for (id, node) in source_nodes {
+            let Some(node) = node.as_object() else {
+                continue;
+            };
+            let mut capabilities = Vec::new();
+            let mut strings = Vec::new();
+            for (key, config) in node {
+                if key == "routing" || key == DOC_KEY || key == FALLBACK_KEY {
+                    continue;
+                }
+                *components.entry(key.clone()).or_insert(0) += 1;
+                capabilities.push(key.clone());
+                collect_strings(config, &mut strings);
+            }
+            let used: Vec<String> = imports
+                .iter()
+                .filter(|import| {
+                    let root = import.root();
+                    capabilities.iter().any(|component| within(component, root))
+                        || strings.iter().any(|value| mentions_namespace(value, root))
+                })
+                .map(ToString::to_string)
+                .collect();
+            capabilities.extend(used);
+            nodes.insert(id.clone(), capabilities);
+        }
+    }
+
+    let imports = imports
+        .iter()
+        .map(ToString::to_string)
+        .filter(|import| nodes.values().any(|caps| caps.contains(import)))
+        .collect();
+
+    Ok(FlowRequirements {
+        components,
+        imports,
+        nodes,
+    })
+}
+
+/// `secrets` for `secrets.get`, `mcp` for `greentic:host-import/mcp@0.6`.
+pub fn import_root(import: &str) -> &str {
+    if import.contains(':') {
+        let path = import.split('@').next().unwrap_or(import);
+        path.rsplit('/').next().unwrap_or(path)
+    } else {
+        import.split('.').next().unwrap_or(import)
+    }
+}
+
+/// Whether `value` contains `root.` at the start of an identifier.
+pub fn mentions_namespace(value: &str, root: &str) -> bool {
+    let needle = format!("{root}.");
+    value.match_indices(&needle).any(|(at, _)| {
+        !value[..at]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+    })
+}
+
+/// `name` equals `prefix` or continues it past a `.`, `/`, or `@`.
+fn within(name: &str, prefix: &str) -> bool {
+    name.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '/', '@']))
+}
+
+fn collect_strings(value: &JsonValue, out: &mut Vec<String>) {
+    match value {
+        JsonValue::String(text) => out.push(text.clone()),
+        JsonValue::Array(items) => items.iter().for_each(|item| collect_strings(item, out)),
+        JsonValue::Object(map) => map.values().for_each(|item| collect_strings(item, out)),
+        _ => {}
+    }
+}
+
+/// Capabilities a host refuses to grant.
+///
+/// An entry denies the capability it names and everything under it: `email`
+/// denies `email.send`, and `greentic:host-import/email` denies
+/// `greentic:host-import/email@0.6`. A bare name also denies WIT interfaces
+/// of that name, so `email` covers `greentic:host-import/email@0.6` as well.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CapabilityFilter {
+    deny: Vec<String>,
+}
+
+impl CapabilityFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn deny(mut self, capability: impl Into<String>) -> Self {
+        self.deny.push(capability.into());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deny.is_empty()
+    }
+
+    /// The entry that denies `capability`, if any.
+    pub fn denies(&self, capability: &str) -> Option<&str> {
+        let interface = capability.contains(':').then(|| import_root(capability));
+        self.deny.iter().map(String::as_str).find(|entry| {
+            within(capability, entry)
+                || interface.is_some_and(|name| !entry.contains(':') && within(name, entry))
+        })
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for CapabilityFilter {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        Self {
+            deny: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// A flow removed from a pack by a [`CapabilityFilter`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DisabledFlow {
+    pub flow: String,
+    /// Capabilities the flow requires that the filter denies.
+    pub denied: Vec<String>,
+    pub reason: String,
+}
+
+/// A flow that runs without some optional imports the filter denies; the
+/// nodes using them take their fallbacks.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DegradedFlow {
+    pub flow: String,
+    /// Optional imports the filter denies.
+    pub denied: Vec<String>,
+    /// Fallbacks of the nodes that use them, by node id.
+    pub fallbacks: NodeFallbacks,
+    pub reason: String,
+}
+
+/// What a pack asks its host to grant, declared in `pack.yaml`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct CapabilitiesSection {
+    /// Hosts the pack connects to: `api.example.com`, `*.example.com`, or
+    /// either with a `:port`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub network: Vec<String>,
+    /// Absolute paths the pack reads or writes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filesystem: Vec<FilesystemGrant>,
+    /// Names of the secrets the pack reads.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secrets: Vec<String>,
+    /// Environment variables the pack reads.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct FilesystemGrant {
+    pub path: String,
+    #[serde(default)]
+    pub access: FileAccess,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum FileAccess {
+    #[default]
+    Read,
+    ReadWrite,
+}
+
+impl CapabilitiesSection {
+    pub fn validate(&self) -> Result<()> {
+        for host in &self.network {
+            if host.contains("://") || host.contains('/') {
+                bail!("capabilities.network: `{host}` must be a host name, not a URL");
+            }
+            let (name, port) = match host.rsplit_once(':') {
+                Some((name, port)) => (name, Some(port)),
+                None => (host.as_str(), None),
+            };
+            if port.is_some_and(|port| !matches!(port.parse::<u16>(), Ok(1..))) {
+                bail!("capabilities.network: `{host}` has an invalid port");
+            }
+            let name = name.strip_prefix("*.").unwrap_or(name);
+            let valid_label = |label: &str| {
+                !label.is_empty()
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            };
+            if !name.split('.').all(valid_label) {
+                bail!("capabilities.network: `{host}` is not a valid host name");
+            }
+        }
+        for grant in &self.filesystem {
+            let path = &grant.path;
+            if !path.starts_with('/') || path.split('/').any(|segment| segment == "..") {
+                bail!("capabilities.filesystem: `{path}` must be an absolute path without `..`");
+            }
+        }
+        for secret in &self.secrets {
+            if !is_secret_name(secret) {
+                bail!("capabilities.secrets: `{secret}` is not a valid secret name");
+            }
+        }
+        for var in &self.env {
+            let mut chars = var.chars();
+            let valid = chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                bail!("capabilities.env: `{var}` is not a valid environment variable name");
+            }
+        }
+        let paths: Vec<&String> = self.filesystem.iter().map(|grant| &grant.path).collect();
+        for (field, entries) in [
+            ("network", self.network.iter().collect::<Vec<_>>()),
+            ("filesystem", paths),
+            ("secrets", self.secrets.iter().collect()),
+            ("env", self.env.iter().collect()),
+        ] {
+            if let Some(entry) = entries
+                .iter()
+                .enumerate()
+                .find_map(|(index, entry)| entries[..index].contains(entry).then_some(entry))
+            {
+                bail!("capabilities.{field} lists `{entry}` twice");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.network.is_empty()
+            && self.filesystem.is_empty()
+            && self.secrets.is_empty()
+            && self.env.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLOW: &str = r#"
+id: notify
+type: messaging
+nodes:
+  lookup:
+    mcp.exec:
+      component: crm
+      args: { token: "secrets.crm_token" }
+    routing:
+      - to: send
+  send:
+    email.send:
+      to: "{{payload.customer.email}}"
+    routing:
+      - out: true
+"#;
+
+    fn imports(entries: &[&str]) -> Vec<ImportSpec> {
+        entries.iter().map(|entry| entry.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn requirements_cover_components_and_used_imports() {
+        let requirements = flow_requirements(
+            FLOW,
+            &imports(&[
+                "secrets.get",
+                "telemetry.emit",
+                "greentic:host-import/mcp@0.6",
+                "greentic:host-import/email",
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            requirements.capabilities().collect::<Vec<_>>(),
+            vec![
+                "email.send",
+                "mcp.exec",
+                "secrets.get",
+                "greentic:host-import/mcp@0.6",
+                "greentic:host-import/email",
+            ]
+        );
+
+        assert_eq!(import_root("wasix:mcp/router"), "router");
+        assert!(!mentions_namespace("my_secrets.value", "secrets"));
+        assert!(!mentions_namespace("payload.secrets.value", "secrets"));
+    }
+
+    #[test]
+    fn pack_capabilities_validate_each_grant() {
+        let section: CapabilitiesSection = serde_yaml_bw::from_str(
+            "network: [api.example.com, \"*.crm.example.com:8443\"]\nfilesystem:\n  - path: /data/cache\n    access: read_write\n  - path: /etc/ssl\nsecrets: [CRM_TOKEN]\nenv: [HTTPS_PROXY]\n",
+        )
+        .unwrap();
+        section.validate().unwrap();
+        assert_eq!(section.filesystem[1].access, FileAccess::Read);
+
+        for (yaml, message) in [
+            ("network: [\"https://api.example.com\"]", "not a URL"),
+            ("network: [\"api.example.com:0\"]", "invalid port"),
+            ("network: [api..example.com]", "not a valid host name"),
+            ("filesystem: [{ path: data }]", "absolute path"),
+            ("filesystem: [{ path: /data/../etc }]", "absolute path"),
+            ("secrets: [\"crm token\"]", "not a valid secret name"),
+            ("env: [1PROXY]", "not a valid environment variable"),
+            ("env: [HOME, HOME]", "twice"),
+        ] {
+            let section: CapabilitiesSection = serde_yaml_bw::from_str(yaml).unwrap();
+            let err = section.validate().unwrap_err();
+            assert!(err.to_string().contains(message), "{yaml}: {err}");
+        }
+    }
+
+    #[test]
+    fn filter_denies_names_and_everything_under_them() {
+        let filter: CapabilityFilter = ["email", "greentic:host-import/mcp"].into_iter().collect();
+        assert_eq!(filter.denies("email.send"), Some("email"));
+        assert_eq!(filter.denies("email"), Some("email"));
+        assert_eq!(filter.denies("emails.send"), None);
+        assert_eq!(
+            filter.denies("greentic:host-import/email@0.6"),
+            Some("email")
+        );
+        assert_eq!(
+            filter.denies("greentic:host-import/mcp@0.6"),
+            Some("greentic:host-import/mcp")
+        );
+        assert_eq!(filter.denies("greentic:host-import/mcp-admin"), None);
+        assert!(CapabilityFilter::new().is_empty());
+    }
+}
@@ -0,0 +1,408 @@
+//! Recorded host import interactions of a flow run.
+//!
+//! A runner in recording mode feeds every import call and its response to a
+//! [`Recorder`]; the resulting [`Cassette`] is saved as JSON. Replaying hands
+//! the cassette to a [`Replayer`], which answers the same calls in the same
+//! order without touching the real imports, so an incident can be re-run
+//! deterministically on a laptop.
+//!
+//! Values are redacted before they are written: [`RedactionRules`] blank out
+//! object keys (such as `authorization`) wherever they occur and specific
+//! dotted paths. Replay applies the same rules to live requests before
+//! comparing them with the recording.
+
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+use crate::atomic::{self, Durability};
+
+pub const CASSETTE_FORMAT: &str = "greentic-cassette-v1";
+
+/// Replacement for redacted values.
+pub const REDACTED: &str = "[redacted]";
+
+/// Keys [`RedactionRules::standard`] redacts.
+const STANDARD_KEYS: &[&str] = &[
+    "authorization",
+    "api_key",
+    "apikey",
+    "password",
+    "secret",
+    "token",
+    "access_token",
+    "refresh_token",
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Cassette {
+    pub format: String,
+    pub pack_id: String,
+    pub flow: String,
+    pub recorded_at_utc: String,
+    /// Input the run started from.
+    pub input: JsonValue,
+    pub interactions: Vec<Interaction>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Interaction {
+    /// The `imports_required` entry that was called.
+    pub import: String,
+    pub request: JsonValue,
+    pub response: Outcome,
+}
+
+/// What an import call returned.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Ok(JsonValue),
+    Error(String),
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes =
+            std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let cassette: Cassette = serde_json::from_slice(&bytes)
+            .with_context(|| format!("{} is not a cassette", path.display()))?;
+        if cassette.format != CASSETTE_FORMAT {
+            bail!(
+                "{} has unsupported cassette format `{}`; expected `{CASSETTE_FORMAT}`",
+                path.display(),
+                cassette.format
+            );
+        }
+        Ok(cassette)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self).context("failed to serialise cassette")?;
+        atomic::write(path, &json, Durability::default())
+    }
+}
+
+/// Which values to blank out of recorded requests and responses.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct RedactionRules {
+    /// Object keys redacted wherever they appear, compared case-insensitively.
+    #[serde(default)]
+    pub keys: Vec<String>,
+    /// Dotted paths redacted from the root of each value, e.g.
+    /// `body.customer.email`; a segment crossing an array applies to every
+    /// element.
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+impl RedactionRules {
+    /// Credentials under their usual key names.
+    pub fn standard() -> Self {
+        Self {
+            keys: STANDARD_KEYS.iter().map(|key| key.to_string()).collect(),
+            paths: Vec::new(),
+        }
+    }
+
+    pub fn apply(&self, value: &mut JsonValue) {
+        if !self.keys.is_empty() {
+            self.redact_keys(value);
+        }
+        for path in &self.paths {
+            let segments: Vec<&str> = path.split('.').filter(|s| !s.is_empty()).collect();
+            redact_path(value, &segments);
+        }
+    }
+
+    fn redact_keys(&self, value: &mut JsonValue) {
+        match value {
+            JsonValue::Object(map) => {
+                for (key, child) in map.iter_mut() {
+                    if self.keys.iter().any(|k| k.eq_ignore_ascii_case(key)) {
+                        *child = JsonValue::String(REDACTED.into());
+                    } else {
+                        self.redact_keys(child);
+                    }
+                }
+            }
+            JsonValue::Array(items) => items.iter_mut().for_each(|item| self.redact_keys(item)),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn redacted(&self, value: &JsonValue) -> JsonValue {
+        let mut value = value.clone();
+        self.apply(&mut value);
+        value
+    }
+}
+
+fn redact_path(value: &mut JsonValue, segments: &[&str]) {
+    let Some((first, rest)) = segments.split_first() else {
+        return;
+    };
+    match value {
+        JsonValue::Object(map) => {
+            if let Some(child) = map.get_mut(*first) {
+                if rest.is_empty() {
+                    *child = JsonValue::String(REDACTED.into());
+                } else {
+                    redact_path(child, rest);
+                }
+            }
+        }
+        JsonValue::Array(items) => items
+            .iter_mut()
+            .for_each(|item| redact_path(item, segments)),
+        _ => {}
+    }
+}
+
+/// Collects the interactions of one run.
+pub struct Recorder {
+    cassette: Cassette,
+    rules: RedactionRules,
+}
+
+impl Recorder {
+    pub fn new(pack_id: &str, flow: &str, input: &JsonValue, rules: RedactionRules) -> Self {
+        let cassette = Cassette {
+            format: CASSETTE_FORMAT.to_string(),
+            pack_id: pack_id.to_string(),
+            flow: flow.to_string(),
+            recorded_at_utc: OffsetDateTime::now_utc()
+                .format(&Rfc3339)
+                .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string()),
+            input: rules.redacted(input),
+            interactions: Vec::new(),
+        };
+        Self { cassette, rules }
+    }
+
+    pub fn record(&mut self, import: &str, request: &JsonValue, response: &Outcome) {
+        let response = match response {
+            Outcome::Ok(value) => Outcome::Ok(self.rules.redacted(value)),
+            Outcome::Error(message) => Outcome::Error(message.clone()),
+        };
+        self.cassette.interactions.push(Interaction {
+            import: import.to_string(),
+            request: self.rules.redacted(request),
+            response,
+        });
+    }
+
+    pub fn finish(self) -> Cassette {
+        self.cassette
+    }
+}
+
+/// Answers import calls from a cassette, in recorded order.
+pub struct Replayer {
+    cassette: Cassette,
+    rules: RedactionRules,
+    next: usize,
+}
+
+impl Replayer {
+    /// `rules` must be the rules the cassette was recorded with, so live
+    /// requests are redacted the same way before they are compared.
+    pub fn new(cassette: Cassette, rules: RedactionRules) -> Self {
+        Self {
+            cassette,
+            rules,
+            next: 0,
+        }
+    }
+
+    pub fn cassette(&self) -> &Cassette {
+        &self.cassette
+    }
+
+    /// Returns the recorded response when `import` and `request` match the
+    /// next recorded call.
+    pub fn call(&mut self, import: &str, request: &JsonValue) -> Result<Outcome, ReplayError> {
+        let index = self.next;
+        let Some(recorded) = self.cassette.interactions.get(index) else {
+            return Err(ReplayError::Exhausted {
+                index,
+                import: import.to_string(),
+            });
+        };
+        if recorded.import != import {
+            return Err(ReplayError::Mismatch {
+                index,
+                expected: recorded.import.clone(),
+                detail: format!("called `{import}`"),
+            });
+        }
+        let request = self.rules.redacted(request);
+        if let Some(path) = first_difference(&recorded.request, &request, String::new()) {
+            return Err(ReplayError::Mismatch {
+                index,
+                expected: recorded.import.clone(),
+                detail: format!(
+                    "request differs at `{}`",
+                    if path.is_empty() { "." } else { &path }
+                ),
+            });
+        }
+        self.next += 1;
+        Ok(recorded.response.clone())
+    }
+
+    /// Fails when recorded calls were never replayed.
+    pub fn finish(self) -> Result<(), ReplayError> {
+        let remaining = self.cassette.interactions.len() - self.next;
+        if remaining > 0 {
+            return Err(ReplayError::Unused { remaining });
+        }
+        Ok(())
+    }
+}
+
+/// Dotted path of the first place `a` and `b` differ.
+fn first_difference(a: &JsonValue, b: &JsonValue, path: String) -> Option<String> {
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+    match (a, b) {
+        (JsonValue::Object(a), JsonValue::Object(b)) => a
+            .keys()
+            .chain(b.keys().filter(|key| !a.contains_key(*key)))
+            .find_map(|key| match (a.get(key), b.get(key)) {
+                (Some(x), Some(y)) => first_difference(x, y, join(key)),
+                _ => Some(join(key)),
+            }),
+        (JsonValue::Array(a), JsonValue::Array(b)) if a.len() == b.len() => a
+            .iter()
+            .zip(b)
+            .enumerate()
+            .find_map(|(i, (x, y))| first_difference(x, y, join(&i.to_string()))),
+        _ if a == b => None,
+        _ => Some(path),
+    }
+}
+
+/// A live run that strayed from its recording.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The run made more calls than were recorded.
+    Exhausted { index: usize, import: String },
+    /// The call at `index` does not match the recording.
+    Mismatch {
+        index: usize,
+        expected: String,
+        detail: String,
+    },
+    /// The run finished before replaying every recorded call.
+    Unused { remaining: usize },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Exhausted { index, import } => write!(
+                f,
+                "call #{index} to `{import}` was not recorded; the cassette has {index} call(s)"
+            ),
+            ReplayError::Mismatch {
+                index,
+                expected,
+                detail,
+            } => write!(f, "call #{index} should be to `{expected}` but {detail}"),
+            ReplayError::Unused { remaining } => {
+                write!(f, "{remaining} recorded call(s) were never made")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn records_redacted_calls_and_replays_them_in_order() {
+        let rules = RedactionRules {
+            paths: vec!["customers.email".into()],
+            ..RedactionRules::standard()
+        };
+        let request = json!({
+            "url": "https://crm.example/customers",
+            "headers": { "Authorization": "Bearer abc" },
+        });
+        let mut recorder =
+            Recorder::new("demo.crm", "sync", &json!({ "token": "t" }), rules.clone());
+        recorder.record(
+            "http.fetch",
+            &request,
+            &Outcome::Ok(json!({ "customers": [{ "id": 1, "email": "a@example.com" }] })),
+        );
+        recorder.record(
+            "email.send",
+            &json!({ "to": "ops" }),
+            &Outcome::Error("quota".into()),
+        );
+        let cassette = recorder.finish();
+        assert_eq!(cassette.input["token"], REDACTED);
+        assert_eq!(
+            cassette.interactions[0].request["headers"]["Authorization"],
+            REDACTED
+        );
+        assert_eq!(
+            cassette.interactions[0].response,
+            Outcome::Ok(json!({ "customers": [{ "id": 1, "email": REDACTED }] }))
+        );
+
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("sync.cassette.json");
+        cassette.save(&path).unwrap();
+        let cassette = Cassette::load(&path).unwrap();
+
+        let mut replay = Replayer::new(cassette.clone(), rules.clone());
+        let live = json!({
+            "url": "https://crm.example/customers",
+            "headers": { "Authorization": "Bearer other" },
+        });
+        assert!(matches!(
+            replay.call("http.fetch", &live),
+            Ok(Outcome::Ok(_))
+        ));
+        assert_eq!(
+            replay.call("email.send", &json!({ "to": "ops" })).unwrap(),
+            Outcome::Error("quota".into())
+        );
+        replay.finish().unwrap();
+
+        let mut replay = Replayer::new(cassette, rules);
+        let err = replay
+            .call(
+                "http.fetch",
+                &json!({
+                    "url": "https://crm.example/leads",
+                    "headers": { "Authorization": "Bearer abc" },
+                }),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "call #0 should be to `http.fetch` but request differs at `url`"
+        );
+        assert_eq!(replay.finish(), Err(ReplayError::Unused { remaining: 2 }));
+    }
+}
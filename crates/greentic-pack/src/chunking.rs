@@ -0,0 +1,325 @@
+//! Content-defined chunking of pack archives for incremental transfer.
+//!
+//! Archives are cut into variable-size chunks with FastCDC: a rolling gear
+//! hash picks cut points from the content itself, so inserting or changing
+//! bytes only moves the boundaries near the edit and every other chunk keeps
+//! its digest. A [`ChunkIndex`] lists the chunks of one archive; publishing or
+//! pulling a new version only has to move the chunks the other side lacks.
+//! Chunks are addressed by their BLAKE3 digest in a [`ChunkStore`].
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::atomic::{self, Durability};
+
+pub const CHUNK_INDEX_FORMAT: &str = "greentic-chunks-v1";
+
+/// Chunk size bounds, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkerConfig {
+    pub min: usize,
+    pub avg: usize,
+    pub max: usize,
+}
+
+impl Default for ChunkerConfig {
+    fn default() -> Self {
+        Self {
+            min: 16 * 1024,
+            avg: 64 * 1024,
+            max: 256 * 1024,
+        }
+    }
+}
+
+/// Gear hash table: one pseudo-random word per byte value.
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0x6772_6565_6e74_6963;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+impl ChunkerConfig {
+    fn validate(&self) -> Result<()> {
+        if self.min == 0 || self.min > self.avg || self.avg > self.max {
+            bail!(
+                "chunk sizes must satisfy 0 < min <= avg <= max (got {}/{}/{})",
+                self.min,
+                self.avg,
+                self.max
+            );
+        }
+        Ok(())
+    }
+
+    /// Offsets and lengths of the chunks of `data`.
+    pub fn split(&self, data: &[u8]) -> Vec<(usize, usize)> {
+        let mut chunks = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            let len = self.cut(&data[offset..]);
+            chunks.push((offset, len));
+            offset += len;
+        }
+        chunks
+    }
+
+    /// Length of the first chunk of `data`. Cut points before the average size
+    /// need more hash bits to be zero than those after it, which keeps chunk
+    /// sizes close to the average (FastCDC's normalised chunking).
+    fn cut(&self, data: &[u8]) -> usize {
+        if data.len() <= self.min {
+            return data.len();
+        }
+        let max = data.len().min(self.max);
+        let normal = max.min(self.avg);
+        let bits = self.avg.max(4).ilog2();
+        let strict = !0u64 << (64 - (bits + 1));
+        let loose = !0u64 << (64 - (bits - 1));
+        let mut hash = 0u64;
+        for (i, byte) in data.iter().enumerate().take(max).skip(self.min) {
+            hash = (hash << 1).wrapping_add(GEAR[*byte as usize]);
+            let mask = if i < normal { strict } else { loose };
+            if hash & mask == 0 {
+                return i + 1;
+            }
+        }
+        max
+    }
+}
+
+/// The chunks of one archive, stored next to it as `<archive>.chunks.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkIndex {
+    pub format: String,
+    pub size: u64,
+    /// BLAKE3 of the whole archive.
+    pub blake3: String,
+    pub chunks: Vec<ChunkRef>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkRef {
+    pub offset: u64,
+    pub size: u64,
+    pub blake3: String,
+}
+
+impl ChunkIndex {
+    pub fn build(data: &[u8], config: &ChunkerConfig) -> Result<Self> {
+        config.validate()?;
+        let chunks = config
+            .split(data)
+            .into_iter()
+            .map(|(offset, len)| ChunkRef {
+                offset: offset as u64,
+                size: len as u64,
+                blake3: blake3::hash(&data[offset..offset + len])
+                    .to_hex()
+                    .to_string(),
+            })
+            .collect();
+        Ok(Self {
+            format: CHUNK_INDEX_FORMAT.to_string(),
+            size: data.len() as u64,
+            blake3: blake3::hash(data).to_hex().to_string(),
+            chunks,
+        })
+    }
+
+    /// Path of the index belonging to `archive`.
+    pub fn path_for(archive: &Path) -> PathBuf {
+        let mut name = archive.file_name().unwrap_or_default().to_os_string();
+        name.push(".chunks.json");
+        archive.with_file_name(name)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let index: Self = serde_json::from_slice(&bytes)
+            .with_context(|| format!("{} is not a chunk index", path.display()))?;
+        if index.format != CHUNK_INDEX_FORMAT {
+            bail!(
+                "{} has unsupported format `{}`; expected `{CHUNK_INDEX_FORMAT}`",
+                path.display(),
+                index.format
+            );
+        }
+        let mut expected = 0;
+        for chunk in &index.chunks {
+            if chunk.offset != expected {
+                bail!("{} has a gap at offset {expected}", path.display());
+            }
+            expected += chunk.size;
+        }
+        if expected != index.size {
+            bail!(
+                "{} covers {expected} of {} bytes",
+                path.display(),
+                index.size
+            );
+        }
+        Ok(index)
+    }
+
+    pub fn save(&self, path: &Path, durability: Durability) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self).context("failed to serialise chunk index")?;
+        atomic::write(path, &json, durability)
+    }
+
+    /// Reassembles the archive from `store`, checking every chunk and the
+    /// result.
+    pub fn assemble(&self, store: &ChunkStore) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(self.size as usize);
+        for chunk in &self.chunks {
+            let bytes = store
+                .read(&chunk.blake3)?
+                .with_context(|| format!("chunk {} is missing", chunk.blake3))?;
+            data.extend_from_slice(&bytes);
+        }
+        let digest = blake3::hash(&data).to_hex().to_string();
+        if digest != self.blake3 {
+            bail!(
+                "reassembled archive has blake3 {digest}, expected {}",
+                self.blake3
+            );
+        }
+        Ok(data)
+    }
+}
+
+/// Chunks addressed by BLAKE3 digest, as `<root>/<first two hex digits>/<digest>`.
+#[derive(Debug, Clone)]
+pub struct ChunkStore {
+    root: PathBuf,
+}
+
+impl ChunkStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, digest: &str) -> PathBuf {
+        self.root.join(&digest[..2]).join(digest)
+    }
+
+    /// The chunk with `digest`. A stored chunk whose content does not match
+    /// its digest is deleted and reported as missing, so it is fetched again.
+    pub fn read(&self, digest: &str) -> Result<Option<Vec<u8>>> {
+        if !is_digest(digest) {
+            bail!("`{digest}` is not a BLAKE3 digest");
+        }
+        let path = self.path(digest);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()));
+            }
+        };
+        if blake3::hash(&bytes).to_hex().as_str() != digest {
+            fs::remove_file(&path)
+                .with_context(|| format!("failed to remove corrupt chunk {}", path.display()))?;
+            return Ok(None);
+        }
+        // Reads refresh the modification time, so caches can evict by least
+        // recent use.
+        let _ = fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(std::time::SystemTime::now()));
+        Ok(Some(bytes))
+    }
+
+    pub fn contains(&self, digest: &str) -> bool {
+        is_digest(digest) && self.path(digest).is_file()
+    }
+
+    /// Stores `bytes` under their digest, which is returned.
+    pub fn write(&self, bytes: &[u8]) -> Result<String> {
+        let digest = blake3::hash(bytes).to_hex().to_string();
+        let path = self.path(&digest);
+        if !path.is_file() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("failed to create {}", parent.display()))?;
+            }
+            atomic::write(&path, bytes, Durability::default())?;
+        }
+        Ok(digest)
+    }
+}
+
+fn is_digest(digest: &str) -> bool {
+    digest.len() == 64
+        && digest
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noise(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn edits_only_change_nearby_chunks() {
+        let config = ChunkerConfig::default();
+        let original = noise(2 * 1024 * 1024, 7);
+        let mut edited = original.clone();
+        edited.splice(1_000_000..1_000_000, noise(100, 9));
+
+        let before = ChunkIndex::build(&original, &config).unwrap();
+        let after = ChunkIndex::build(&edited, &config).unwrap();
+        assert!(before.chunks.iter().all(|c| c.size as usize <= config.max));
+        assert!(before.chunks.len() > 8, "{}", before.chunks.len());
+        let changed = after
+            .chunks
+            .iter()
+            .filter(|chunk| !before.chunks.iter().any(|c| c.blake3 == chunk.blake3))
+            .count();
+        assert!(changed <= 2, "{changed} chunks changed");
+
+        let temp = tempfile::tempdir().unwrap();
+        let store = ChunkStore::new(temp.path());
+        for chunk in &after.chunks {
+            let range = chunk.offset as usize..(chunk.offset + chunk.size) as usize;
+            store.write(&edited[range]).unwrap();
+        }
+        assert_eq!(after.assemble(&store).unwrap(), edited);
+
+        let first = &after.chunks[0].blake3;
+        fs::write(store.path(first), b"corrupt").unwrap();
+        assert_eq!(store.read(first).unwrap(), None);
+        assert!(!store.contains(first));
+    }
+}
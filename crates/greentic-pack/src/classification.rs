@@ -0,0 +1,236 @@
+//! Data classification of flow inputs and outputs.
+//!
+//! `pack.yaml` classifies the fields each flow reads and produces:
+//!
+//! ```yaml
+//! classification:
+//!   signup:
+//!     inputs: { email: pii, plan: internal }
+//!     outputs: { account.api_key: secret }
+//! ```
+//!
+//! The build turns the section into a [`DataHandling`] summary embedded in the
+//! manifest, so compliance tooling can tell which flows touch personal data
+//! without reading them.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::{Result, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::capabilities::{FlowRequirements, import_root};
+
+/// Capability roots whose flows must classify their data: a flow that sends
+/// email or talks to a CRM almost certainly handles personal data.
+pub const CLASSIFICATION_REQUIRED_FOR: &[&str] = &["email", "crm"];
+
+/// Sensitivity of a field, from least to most sensitive.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Classification {
+    Public,
+    Internal,
+    Pii,
+    Secret,
+}
+
+impl Classification {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Classification::Public => "public",
+            Classification::Internal => "internal",
+            Classification::Pii => "pii",
+            Classification::Secret => "secret",
+        }
+    }
+}
+
+impl fmt::Display for Classification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Field classifications by flow id.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(transparent)]
+pub struct ClassificationSection {
+    pub flows: BTreeMap<String, FlowClassification>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct FlowClassification {
+    /// Input fields by dotted path, e.g. `customer.email`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub inputs: BTreeMap<String, Classification>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub outputs: BTreeMap<String, Classification>,
+}
+
+impl FlowClassification {
+    /// The most sensitive class among the flow's fields.
+    pub fn level(&self) -> Option<Classification> {
+        self.inputs
+            .values()
+            .chain(self.outputs.values())
+            .max()
+            .copied()
+    }
+}
+
+impl ClassificationSection {
+    pub fn validate(&self) -> Result<()> {
+        for (flow, fields) in &self.flows {
+            if flow.trim().is_empty() {
+                bail!("classification must be keyed by flow id");
+            }
+            if fields.inputs.is_empty() && fields.outputs.is_empty() {
+                bail!("classification.{flow} must classify at least one input or output");
+            }
+            for (direction, map) in [("inputs", &fields.inputs), ("outputs", &fields.outputs)] {
+                for field in map.keys() {
+                    if field.trim().is_empty() || field.split('.').any(str::is_empty) {
+                        bail!("classification.{flow}.{direction} has an invalid field `{field}`");
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every classified flow is one of `flows`.
+    pub fn check_flows<'a>(&self, flows: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        let flows: Vec<&str> = flows.into_iter().collect();
+        if let Some(unknown) = self
+            .flows
+            .keys()
+            .find(|flow| !flows.contains(&flow.as_str()))
+        {
+            bail!("classification refers to unknown flow `{unknown}`");
+        }
+        Ok(())
+    }
+
+    pub fn summarize(&self) -> DataHandling {
+        let flows: BTreeMap<String, FlowDataHandling> = self
+            .flows
+            .iter()
+            .filter_map(|(flow, fields)| {
+                let level = fields.level()?;
+                let mut handling = FlowDataHandling {
+                    level,
+                    pii: Vec::new(),
+                    secret: Vec::new(),
+                };
+                for (direction, map) in [("inputs", &fields.inputs), ("outputs", &fields.outputs)] {
+                    for (field, class) in map {
+                        let label = format!("{direction}.{field}");
+                        match class {
+                            Classification::Pii => handling.pii.push(label),
+                            Classification::Secret => handling.secret.push(label),
+                            _ => {}
+                        }
+                    }
+                }
+                Some((flow.clone(), handling))
+            })
+            .collect();
+        DataHandling {
+            level: flows.values().map(|flow| flow.level).max(),
+            flows,
+        }
+    }
+}
+
+/// Data-handling summary embedded in the manifest.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct DataHandling {
+    /// Most sensitive class any flow handles; `None` when nothing is classified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<Classification>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub flows: BTreeMap<String, FlowDataHandling>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct FlowDataHandling {
+    pub level: Classification,
+    /// Personal-data fields, as `inputs.<path>` or `outputs.<path>`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pii: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secret: Vec<String>,
+}
+
+impl DataHandling {
+    pub fn handles_pii(&self) -> bool {
+        self.level >= Some(Classification::Pii)
+    }
+}
+
+/// Whether a flow requiring `capability` must classify its data.
+pub fn requires_classification(capability: &str) -> bool {
+    let root = import_root(capability);
+    CLASSIFICATION_REQUIRED_FOR.contains(&root)
+}
+
+/// Flows that require a capability from [`CLASSIFICATION_REQUIRED_FOR`] but
+/// have no entry in `section`, each with the capability that triggered the rule.
+pub fn unclassified_flows<'a>(
+    section: Option<&ClassificationSection>,
+    flows: impl IntoIterator<Item = (&'a str, &'a FlowRequirements)>,
+) -> Vec<(String, String)> {
+    flows
+        .into_iter()
+        .filter(|(flow, _)| section.is_none_or(|section| !section.flows.contains_key(*flow)))
+        .filter_map(|(flow, requirements)| {
+            let capability = requirements
+                .capabilities()
+                .find(|capability| requires_classification(capability))?;
+            Some((flow.to_string(), capability.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_reports_levels_and_sensitive_fields() {
+        let section: ClassificationSection = serde_yaml_bw::from_str(
+            r#"
+signup:
+  inputs: { email: pii, plan: internal }
+  outputs: { account.api_key: secret }
+status:
+  outputs: { uptime: public }
+"#,
+        )
+        .unwrap();
+        section.validate().unwrap();
+        section.check_flows(["signup", "status"]).unwrap();
+        assert!(section.check_flows(["signup"]).is_err());
+
+        let summary = section.summarize();
+        assert_eq!(summary.level, Some(Classification::Secret));
+        assert!(summary.handles_pii());
+        let signup = &summary.flows["signup"];
+        assert_eq!(signup.pii, vec!["inputs.email"]);
+        assert_eq!(signup.secret, vec!["outputs.account.api_key"]);
+        assert_eq!(summary.flows["status"].level, Classification::Public);
+
+        assert!(requires_classification("email.send"));
+        assert!(requires_classification("greentic:host-import/crm@1.0"));
+        assert!(!requires_classification("mcp.exec"));
+    }
+}
@@ -0,0 +1,450 @@
+//! Self-contained bundles of a pack and the packs it depends on.
+//!
+//! A closure bundle is a zip holding `closure.json` and one `.gtpack` per
+//! member under `packs/`. The closure manifest pins every member by size,
+//! BLAKE3 digest, and content digest, and lists the imports no member
+//! provides, which the installing host must supply itself:
+//!
+//! ```json
+//! {
+//!   "format": "greentic-closure-v1",
+//!   "root": "greentic.weather.demo@0.3.0",
+//!   "packs": [
+//!     { "pack_id": "greentic.weather.demo", "version": "0.3.0",
+//!       "path": "packs/greentic.weather.demo@0.3.0.gtpack", "size": 18211,
+//!       "hash_blake3": "…", "content_digest": "…" }
+//!   ],
+//!   "host_imports": ["secrets.get"]
+//! }
+//! ```
+//!
+//! [`open_closure`] verifies the whole bundle before anything is installed:
+//! each member must match its pin and verify under the same signing policy,
+//! and each import of each member must be met by another member or be listed
+//! as a host import. [`Closure::install`] then writes every member or none.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use semver::VersionReq;
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::atomic::{self, Durability};
+use crate::builder::hex_hash;
+use crate::reader::{PackLoad, SigningPolicy, open_pack_bytes};
+
+pub const CLOSURE_PATH: &str = "closure.json";
+pub const CLOSURE_FORMAT: &str = "greentic-closure-v1";
+
+/// Largest bundle [`open_closure`] reads, members included.
+pub const MAX_CLOSURE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClosureManifest {
+    pub format: String,
+    /// `<pack_id>@<version>` of the pack the bundle was made for.
+    pub root: String,
+    /// Members, the root first.
+    pub packs: Vec<ClosureMember>,
+    /// Imports no member provides, sorted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub host_imports: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClosureMember {
+    pub pack_id: String,
+    pub version: String,
+    /// Entry of the member's archive in the bundle.
+    pub path: String,
+    pub size: u64,
+    pub hash_blake3: String,
+    /// [`PackLoad::content_digest`] of the member.
+    pub content_digest: String,
+}
+
+impl ClosureMember {
+    pub fn key(&self) -> String {
+        format!("{}@{}", self.pack_id, self.version)
+    }
+
+    /// File name the member is installed under.
+    pub fn file_name(&self) -> String {
+        format!("{}-{}.gtpack", self.pack_id, self.version)
+    }
+}
+
+/// A verified bundle.
+#[derive(Debug)]
+pub struct Closure {
+    pub manifest: ClosureManifest,
+    /// Archive bytes of each member, in manifest order.
+    pub archives: Vec<Vec<u8>>,
+    pub loads: Vec<PackLoad>,
+}
+
+/// Writes a bundle of `archives`, the root pack first, to `out`. Every
+/// archive must verify under `policy`, and the imports of the members that no
+/// member provides are recorded as host imports.
+pub fn write_closure(
+    out: &Path,
+    archives: &[Vec<u8>],
+    policy: SigningPolicy,
+    durability: Durability,
+) -> Result<ClosureManifest> {
+    let mut packs = Vec::new();
+    let mut loads = Vec::new();
+    for bytes in archives {
+        let load = open_pack_bytes(bytes, policy)
+            .map_err(|err| anyhow!("closure member failed verification: {}", err.message))?;
+        let meta = &load.manifest.meta;
+        let version = meta.version.to_string();
+        packs.push(ClosureMember {
+            path: format!("packs/{}@{version}.gtpack", meta.pack_id),
+            pack_id: meta.pack_id.clone(),
+            version,
+            size: bytes.len() as u64,
+            hash_blake3: hex_hash(bytes),
+            content_digest: load.content_digest(),
+        });
+        loads.push(load);
+    }
+    let root = packs
+        .first()
+        .map(ClosureMember::key)
+        .context("a closure needs at least its root pack")?;
+    let manifest = ClosureManifest {
+        format: CLOSURE_FORMAT.to_string(),
+        root,
+        host_imports: unmet_imports(&loads)?,
+        packs,
+    };
+
+    let manifest_json =
+        serde_json::to_vec_pretty(&manifest).context("failed to serialise closure.json")?;
+    atomic::write_with(out, durability, |file| {
+        let mut writer = ZipWriter::new(file);
+        // Members are already compressed archives.
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Stored)
+            .unix_permissions(0o644)
+            .large_file(true);
+        writer.start_file(CLOSURE_PATH, options)?;
+        writer.write_all(&manifest_json)?;
+        for (member, bytes) in manifest.packs.iter().zip(archives) {
+            writer
+                .start_file(&member.path, options)
+                .with_context(|| format!("failed to add {} to the bundle", member.path))?;
+            writer.write_all(bytes)?;
+        }
+        writer.finish().context("failed to finish the bundle")?;
+        Ok(())
+    })?;
+    Ok(manifest)
+}
+
+/// Reads and verifies the bundle at `path`; see the module documentation.
+pub fn open_closure(path: &Path, policy: SigningPolicy) -> Result<Closure> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut archive = ZipArchive::new(file)
+        .with_context(|| format!("{} is not a valid closure bundle", path.display()))?;
+
+    let mut entries = HashMap::new();
+    let mut total = 0u64;
+    for idx in 0..archive.len() {
+        let mut entry = archive
+            .by_index(idx)
+            .with_context(|| format!("failed to read entry #{idx}"))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        total = total.saturating_add(entry.size());
+        if total > MAX_CLOSURE_BYTES {
+            bail!("closure bundle exceeds maximum allowed size ({MAX_CLOSURE_BYTES} bytes)");
+        }
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry
+            .read_to_end(&mut bytes)
+            .with_context(|| format!("failed to read {name}"))?;
+        if entries.insert(name.clone(), bytes).is_some() {
+            bail!("duplicate entry detected: {name}");
+        }
+    }
+
+    let manifest_json = entries
+        .remove(CLOSURE_PATH)
+        .context("closure.json missing from bundle")?;
+    let manifest: ClosureManifest =
+        serde_json::from_slice(&manifest_json).context("closure.json is invalid")?;
+    if manifest.format != CLOSURE_FORMAT {
+        bail!("unexpected closure format: {}", manifest.format);
+    }
+    if manifest.packs.first().map(ClosureMember::key).as_deref() != Some(manifest.root.as_str()) {
+        bail!("closure root {} is not its first member", manifest.root);
+    }
+
+    let mut archives = Vec::new();
+    let mut loads = Vec::new();
+    let mut seen = BTreeSet::new();
+    for member in &manifest.packs {
+        if !seen.insert(member.pack_id.as_str()) {
+            bail!("closure lists {} more than once", member.pack_id);
+        }
+        let bytes = entries
+            .remove(&member.path)
+            .with_context(|| format!("{} missing from bundle", member.path))?;
+        let actual = hex_hash(&bytes);
+        if bytes.len() as u64 != member.size || actual != member.hash_blake3 {
+            bail!(
+                "hash mismatch for {}: expected {}, found {actual}",
+                member.path,
+                member.hash_blake3
+            );
+        }
+        let load = open_pack_bytes(&bytes, policy)
+            .map_err(|err| anyhow!("{} failed verification: {}", member.key(), err.message))?;
+        let meta = &load.manifest.meta;
+        if meta.pack_id != member.pack_id || meta.version.to_string() != member.version {
+            bail!("{} holds {}@{}", member.path, meta.pack_id, meta.version);
+        }
+        if load.content_digest() != member.content_digest {
+            bail!("content digest mismatch for {}", member.key());
+        }
+        archives.push(bytes);
+        loads.push(load);
+    }
+    if let Some(extra) = entries.keys().min() {
+        bail!("bundle entry {extra} is not listed in closure.json");
+    }
+
+    let unmet = unmet_imports(&loads)?;
+    if let Some(missing) = unmet
+        .iter()
+        .find(|import| !manifest.host_imports.contains(import))
+    {
+        bail!("closure does not provide {missing}, and it is not listed as a host import");
+    }
+
+    Ok(Closure {
+        manifest,
+        archives,
+        loads,
+    })
+}
+
+/// Imports of `loads` that no member satisfies, sorted. An import naming a
+/// member whose version does not match the requirement is an error.
+fn unmet_imports(loads: &[PackLoad]) -> Result<Vec<String>> {
+    let mut unmet = Vec::new();
+    for load in loads {
+        let meta = &load.manifest.meta;
+        for import in &meta.imports {
+            let Some(provider) = loads
+                .iter()
+                .find(|other| other.manifest.meta.pack_id == import.pack_id)
+            else {
+                unmet.push(import.pack_id.clone());
+                continue;
+            };
+            let req = VersionReq::parse(&import.version_req).with_context(|| {
+                format!(
+                    "{} imports {} with invalid requirement `{}`",
+                    meta.pack_id, import.pack_id, import.version_req
+                )
+            })?;
+            if !req.matches(&provider.manifest.meta.version) {
+                bail!(
+                    "{} requires {} {}, but the closure holds {}",
+                    meta.pack_id,
+                    import.pack_id,
+                    import.version_req,
+                    provider.manifest.meta.version
+                );
+            }
+        }
+    }
+    unmet.sort();
+    unmet.dedup();
+    Ok(unmet)
+}
+
+impl Closure {
+    /// Installs every member into `dest` as `<pack_id>-<version>.gtpack`.
+    ///
+    /// Members are staged next to their targets first and only then moved
+    /// into place; if any step fails, the members already moved are removed
+    /// again, so `dest` ends up with all of the closure or none of it. A
+    /// member already installed with identical bytes is left as it is; one
+    /// installed with different bytes fails the install before anything is
+    /// written.
+    pub fn install(&self, dest: &Path, durability: Durability) -> Result<Vec<PathBuf>> {
+        fs::create_dir_all(dest).with_context(|| format!("failed to create {}", dest.display()))?;
+
+        let mut pending = Vec::new();
+        let mut installed = Vec::new();
+        for (member, bytes) in self.manifest.packs.iter().zip(&self.archives) {
+            let target = dest.join(member.file_name());
+            installed.push(target.clone());
+            match fs::read(&target) {
+                Ok(existing) if existing == *bytes => continue,
+                Ok(_) => bail!(
+                    "{} is already installed with different contents",
+                    target.display()
+                ),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("failed to read {}", target.display()));
+                }
+            }
+            pending.push((target, bytes));
+        }
+
+        let mut staged = Vec::new();
+        for (target, bytes) in pending {
+            let mut temp = tempfile::Builder::new()
+                .prefix(".closure.")
+                .suffix(".tmp")
+                .tempfile_in(dest)
+                .with_context(|| format!("failed to stage in {}", dest.display()))?;
+            temp.write_all(bytes)
+                .and_then(|_| temp.flush())
+                .with_context(|| format!("failed to stage {}", target.display()))?;
+            if durability == Durability::Fsync {
+                temp.as_file()
+                    .sync_all()
+                    .with_context(|| format!("failed to sync {}", target.display()))?;
+            }
+            staged.push((target, temp));
+        }
+
+        let mut moved: Vec<PathBuf> = Vec::new();
+        for (target, temp) in staged {
+            if let Err(err) = temp.persist_noclobber(&target) {
+                for path in &moved {
+                    let _ = fs::remove_file(path);
+                }
+                return Err(err.error)
+                    .with_context(|| format!("failed to install {}", target.display()));
+            }
+            moved.push(target);
+        }
+        Ok(installed)
+    }
+}
+
+#[cfg(all(test, feature = "builder"))]
+mod tests {
+    use super::*;
+    use crate::builder::{FlowBundle, ImportRef, PackBuilder, PackMeta, Signing};
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn pack(dir: &Path, pack_id: &str, imports: &[(&str, &str)]) -> Vec<u8> {
+        let mut meta: PackMeta = serde_json::from_value(json!({
+            "pack_id": pack_id,
+            "version": "0.1.0",
+            "name": pack_id,
+            "entry_flows": ["main"],
+            "created_at_utc": "2025-01-01T00:00:00Z",
+        }))
+        .unwrap();
+        meta.imports = imports
+            .iter()
+            .map(|(pack_id, version_req)| ImportRef {
+                pack_id: pack_id.to_string(),
+                version_req: version_req.to_string(),
+                optional: false,
+            })
+            .collect();
+        let flow = FlowBundle {
+            id: "main".into(),
+            kind: "flow/v1".into(),
+            entry: "start".into(),
+            yaml: "id: main\nentry: start\nnodes: {}\n".into(),
+            json: json!({"id": "main", "entry": "start", "nodes": {}}),
+            hash_blake3: hex_hash(b"main"),
+            nodes: Vec::new(),
+        };
+        let path = dir.join(format!("{pack_id}.gtpack"));
+        PackBuilder::new(meta)
+            .with_flow(flow)
+            .with_signing(Signing::Dev)
+            .build(&path)
+            .unwrap();
+        fs::read(path).unwrap()
+    }
+
+    #[test]
+    fn closures_verify_every_member_and_install_all_of_them() {
+        let dir = tempdir().unwrap();
+        let root = pack(
+            dir.path(),
+            "demo.root",
+            &[("demo.lib", "^0.1"), ("secrets.get", "*")],
+        );
+        let lib = pack(dir.path(), "demo.lib", &[]);
+        let bundle = dir.path().join("root.gtbundle");
+        let manifest = write_closure(
+            &bundle,
+            &[root.clone(), lib.clone()],
+            SigningPolicy::DevOk,
+            Durability::Buffered,
+        )
+        .unwrap();
+        assert_eq!(manifest.root, "demo.root@0.1.0");
+        assert_eq!(manifest.host_imports, ["secrets.get"]);
+
+        let closure = open_closure(&bundle, SigningPolicy::DevOk).unwrap();
+        let dest = dir.path().join("installed");
+        let installed = closure.install(&dest, Durability::Buffered).unwrap();
+        assert_eq!(installed.len(), 2);
+        assert_eq!(fs::read(dest.join("demo.lib-0.1.0.gtpack")).unwrap(), lib);
+        // Installing again is a no-op.
+        closure.install(&dest, Durability::Buffered).unwrap();
+
+        fs::write(dest.join("demo.lib-0.1.0.gtpack"), b"other").unwrap();
+        let err = closure.install(&dest, Durability::Buffered).unwrap_err();
+        assert!(err.to_string().contains("different contents"), "{err}");
+
+        let err = write_closure(
+            &dir.path().join("bad.gtbundle"),
+            &[pack(dir.path(), "demo.root", &[("demo.lib", "^0.2")]), lib],
+            SigningPolicy::DevOk,
+            Durability::Buffered,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("requires demo.lib ^0.2"), "{err}");
+    }
+
+    #[test]
+    fn open_rejects_a_swapped_member() {
+        let dir = tempdir().unwrap();
+        let root = pack(dir.path(), "demo.root", &[]);
+        let bundle = dir.path().join("root.gtbundle");
+        let manifest =
+            write_closure(&bundle, &[root], SigningPolicy::DevOk, Durability::Buffered).unwrap();
+
+        // A different, validly signed pack under the member's name.
+        let other = pack(dir.path(), "demo.other", &[]);
+        let file = File::create(&bundle).unwrap();
+        let mut writer = ZipWriter::new(file);
+        let options = SimpleFileOptions::default();
+        writer.start_file(CLOSURE_PATH, options).unwrap();
+        writer
+            .write_all(&serde_json::to_vec(&manifest).unwrap())
+            .unwrap();
+        writer.start_file(&manifest.packs[0].path, options).unwrap();
+        writer.write_all(&other).unwrap();
+        writer.finish().unwrap();
+
+        let err = open_closure(&bundle, SigningPolicy::DevOk).unwrap_err();
+        assert!(err.to_string().contains("hash mismatch"), "{err}");
+    }
+}
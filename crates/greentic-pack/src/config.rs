@@ -0,0 +1,630 @@
+//! Per-tenant pack configuration.
+//!
+//! A pack declares the configuration its flows need in the `config` section
+//! of `pack.yaml`: connection profiles, logical secret names, feature flags,
+//! and typed install-time parameters. Hosts keep the actual values in
+//! [`HostValues`] and call [`resolve`] for each tenant to get the
+//! [`ResolvedConfig`] handed to flows.
+//!
+//! Parameters are a shorthand for a flat
+//! [`config_schema`](crate::config_schema); the build turns them into one
+//! with [`ConfigSection::params_schema`]:
+//!
+//! ```yaml
+//! config:
+//!   params:
+//!     region:
+//!       type: enum
+//!       values: [eu, us]
+//!       default: eu
+//!     max_results: { type: int, default: 10 }
+//!     api_base_url: { type: string, required: true, description: CRM endpoint }
+//! ```
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+use greentic_types::TenantCtx;
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue, json};
+
+use crate::builder::PackMeta;
+use crate::config_schema::ConfigSchema;
+
+/// Placeholders secret keys may use; they expand from the tenant context.
+const KEY_PLACEHOLDERS: &[&str] = &["tenant", "team", "env"];
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct ConfigSection {
+    /// Connection profiles, e.g. the endpoint of a CRM, by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ConfigProfile>,
+    /// Secrets read by flows, by logical name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub secrets: BTreeMap<String, SecretMapping>,
+    /// Feature flags and their default values.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub flags: BTreeMap<String, bool>,
+    /// Typed settings the host collects at install time.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, ConfigParam>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct ConfigProfile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Settings the host must supply.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required: Vec<String>,
+    /// Settings used unless the host overrides them.
+    #[serde(default, skip_serializing_if = "JsonMap::is_empty")]
+    pub defaults: JsonMap<String, JsonValue>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct SecretMapping {
+    /// Key in the host's secret store; may use `{tenant}`, `{team}`, and `{env}`.
+    pub key: String,
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct ConfigParam {
+    #[serde(rename = "type")]
+    pub kind: ParamKind,
+    /// Allowed values; only for `enum`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<JsonValue>,
+    /// The host must supply a value; required parameters have no default.
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ParamKind {
+    String,
+    Int,
+    Bool,
+    Enum,
+}
+
+impl ParamKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ParamKind::String => "string",
+            ParamKind::Int => "int",
+            ParamKind::Bool => "bool",
+            ParamKind::Enum => "enum",
+        }
+    }
+}
+
+impl ConfigParam {
+    fn validate(&self, name: &str) -> Result<()> {
+        match self.kind {
+            ParamKind::Enum if self.values.is_empty() => {
+                bail!("config.params.{name} is an enum and needs `values`");
+            }
+            ParamKind::Enum => {
+                for (index, value) in self.values.iter().enumerate() {
+                    if self.values[..index].contains(value) {
+                        bail!("config.params.{name}.values lists `{value}` twice");
+                    }
+                }
+            }
+            kind if !self.values.is_empty() => {
+                bail!(
+                    "config.params.{name} is a {} and cannot have `values`",
+                    kind.as_str()
+                );
+            }
+            _ => {}
+        }
+        if let Some(default) = &self.default {
+            if self.required {
+                bail!("config.params.{name} is required and cannot have a default");
+            }
+            if !self.accepts(default) {
+                bail!(
+                    "config.params.{name}.default `{default}` is not a valid {}",
+                    self.kind.as_str()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn accepts(&self, value: &JsonValue) -> bool {
+        match self.kind {
+            ParamKind::String => value.is_string(),
+            ParamKind::Int => value.is_i64() || value.is_u64(),
+            ParamKind::Bool => value.is_boolean(),
+            ParamKind::Enum => value
+                .as_str()
+                .is_some_and(|value| self.values.iter().any(|allowed| allowed == value)),
+        }
+    }
+
+    /// The JSON Schema of the parameter's value.
+    fn schema(&self) -> JsonValue {
+        let mut schema = JsonMap::new();
+        let json_type = match self.kind {
+            ParamKind::String | ParamKind::Enum => "string",
+            ParamKind::Int => "integer",
+            ParamKind::Bool => "boolean",
+        };
+        schema.insert("type".into(), json_type.into());
+        if self.kind == ParamKind::Enum {
+            schema.insert("enum".into(), self.values.clone().into());
+        }
+        if let Some(description) = &self.description {
+            schema.insert("description".into(), description.clone().into());
+        }
+        if let Some(default) = &self.default {
+            schema.insert("default".into(), default.clone());
+        }
+        JsonValue::Object(schema)
+    }
+}
+
+impl ConfigSection {
+    pub fn validate(&self) -> Result<()> {
+        for (name, profile) in &self.profiles {
+            check_name("config.profiles", name)?;
+            for (index, setting) in profile.required.iter().enumerate() {
+                if setting.trim().is_empty() {
+                    bail!("config.profiles.{name}.required[{index}] must not be empty");
+                }
+                if profile.required[..index].contains(setting) {
+                    bail!("config.profiles.{name}.required lists `{setting}` twice");
+                }
+            }
+        }
+        for (name, secret) in &self.secrets {
+            check_name("config.secrets", name)?;
+            if secret.key.trim().is_empty() {
+                bail!("config.secrets.{name}.key must not be empty");
+            }
+            for placeholder in placeholders(&secret.key) {
+                if !KEY_PLACEHOLDERS.contains(&placeholder) {
+                    bail!(
+                        "config.secrets.{name}.key uses unknown placeholder `{{{placeholder}}}`; expected one of {}",
+                        KEY_PLACEHOLDERS
+                            .iter()
+                            .map(|p| format!("{{{p}}}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                }
+            }
+        }
+        for name in self.flags.keys() {
+            check_name("config.flags", name)?;
+        }
+        for (name, param) in &self.params {
+            check_name("config.params", name)?;
+            if name.contains('.') {
+                bail!("config.params name `{name}` must not contain `.`");
+            }
+            param.validate(name)?;
+        }
+        Ok(())
+    }
+
+    /// The `config_schema` the parameters describe; `None` without any.
+    pub fn params_schema(&self) -> Option<ConfigSchema> {
+        if self.params.is_empty() {
+            return None;
+        }
+        let properties: JsonMap<String, JsonValue> = self
+            .params
+            .iter()
+            .map(|(name, param)| (name.clone(), param.schema()))
+            .collect();
+        let required: Vec<&String> = self
+            .params
+            .iter()
+            .filter(|(_, param)| param.required)
+            .map(|(name, _)| name)
+            .collect();
+        let mut schema = JsonMap::new();
+        schema.insert("type".into(), "object".into());
+        schema.insert("properties".into(), properties.into());
+        if !required.is_empty() {
+            schema.insert("required".into(), json!(required));
+        }
+        schema.insert("additionalProperties".into(), false.into());
+        Some(ConfigSchema(schema))
+    }
+}
+
+fn check_name(section: &str, name: &str) -> Result<()> {
+    if name.trim().is_empty() || name.chars().any(char::is_whitespace) {
+        bail!("{section} name `{name}` must be non-empty and contain no whitespace");
+    }
+    Ok(())
+}
+
+/// Configuration values a host keeps for a pack.
+///
+/// Layers apply in order over the pack's defaults: `base`, the layer for the
+/// environment, the layer for the tenant, then the layer for `tenant/team`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct HostValues {
+    #[serde(flatten)]
+    pub base: ConfigLayer,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environments: BTreeMap<String, ConfigLayer>,
+    /// Keyed by tenant id, or `tenant/team` for a single team.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tenants: BTreeMap<String, ConfigLayer>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct ConfigLayer {
+    /// Settings per profile, merged key by key; `null` removes a setting.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, JsonMap<String, JsonValue>>,
+    /// Secret store keys overriding the pack's mapping, by logical name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub secrets: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub flags: BTreeMap<String, bool>,
+}
+
+/// Configuration for one tenant in one environment, ready to pass to flows.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResolvedConfig {
+    pub pack_id: String,
+    pub environment: String,
+    pub tenant: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team: Option<String>,
+    pub profiles: BTreeMap<String, JsonMap<String, JsonValue>>,
+    pub secrets: BTreeMap<String, ResolvedSecret>,
+    pub flags: BTreeMap<String, bool>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResolvedSecret {
+    /// Secret store key with placeholders expanded.
+    pub key: String,
+    pub required: bool,
+}
+
+impl ResolvedConfig {
+    pub fn profile(&self, name: &str) -> Option<&JsonMap<String, JsonValue>> {
+        self.profiles.get(name)
+    }
+
+    pub fn secret_key(&self, name: &str) -> Option<&str> {
+        self.secrets.get(name).map(|secret| secret.key.as_str())
+    }
+
+    /// Value of a declared flag; undeclared flags are off.
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.get(name).copied().unwrap_or(false)
+    }
+}
+
+/// Resolves the pack's configuration for `tenant` in `environment`.
+///
+/// Fails, listing every problem, when host values name profiles, secrets, or
+/// flags the pack does not declare, when a profile lacks a required setting,
+/// or when a required secret's key needs a team the tenant context does not
+/// carry.
+pub fn resolve(
+    meta: &PackMeta,
+    tenant: &TenantCtx,
+    environment: &str,
+    host: &HostValues,
+) -> Result<ResolvedConfig> {
+    let section = meta.config.clone().unwrap_or_default();
+    let tenant_id = tenant.tenant.as_str();
+    let team = tenant.team.as_ref().map(|team| team.as_str().to_string());
+
+    let mut layers = vec![("host values", Some(&host.base))];
+    layers.push(("environment layer", host.environments.get(environment)));
+    layers.push(("tenant layer", host.tenants.get(tenant_id)));
+    if let Some(team) = &team {
+        layers.push((
+            "team layer",
+            host.tenants.get(&format!("{tenant_id}/{team}")),
+        ));
+    }
+
+    let mut problems = Vec::new();
+    let mut profiles: BTreeMap<String, JsonMap<String, JsonValue>> = section
+        .profiles
+        .iter()
+        .map(|(name, profile)| (name.clone(), profile.defaults.clone()))
+        .collect();
+    let mut secret_keys: BTreeMap<&str, &str> = section
+        .secrets
+        .iter()
+        .map(|(name, secret)| (name.as_str(), secret.key.as_str()))
+        .collect();
+    let mut flags = section.flags.clone();
+
+    for (label, layer) in layers {
+        let Some(layer) = layer else { continue };
+        for (name, settings) in &layer.profiles {
+            let Some(profile) = profiles.get_mut(name) else {
+                problems.push(format!("{label} set undeclared profile `{name}`"));
+                continue;
+            };
+            for (key, value) in settings {
+                if value.is_null() {
+                    profile.remove(key);
+                } else {
+                    profile.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        for (name, key) in &layer.secrets {
+            match secret_keys.get_mut(name.as_str()) {
+                Some(current) => *current = key,
+                None => problems.push(format!("{label} set undeclared secret `{name}`")),
+            }
+        }
+        for (name, value) in &layer.flags {
+            match flags.get_mut(name) {
+                Some(current) => *current = *value,
+                None => problems.push(format!("{label} set undeclared flag `{name}`")),
+            }
+        }
+    }
+
+    for (name, profile) in &section.profiles {
+        for setting in &profile.required {
+            if !profiles[name].contains_key(setting) {
+                problems.push(format!("profile `{name}` is missing setting `{setting}`"));
+            }
+        }
+    }
+
+    let mut secrets = BTreeMap::new();
+    for (name, template) in secret_keys {
+        let vars = [
+            ("tenant", Some(tenant_id)),
+            ("team", team.as_deref()),
+            ("env", Some(environment)),
+        ];
+        let required = section.secrets[name].required;
+        match expand(template, &vars) {
+            Ok(key) => {
+                secrets.insert(name.to_string(), ResolvedSecret { key, required });
+            }
+            // Optional secrets whose key cannot be formed are simply absent.
+            Err(_) if !required => {}
+            Err(reason) => problems.push(format!("secret `{name}`: {reason}")),
+        }
+    }
+
+    if !problems.is_empty() {
+        bail!(
+            "configuration of pack `{}` for tenant `{tenant_id}` in `{environment}` is invalid:\n  - {}",
+            meta.pack_id,
+            problems.join("\n  - ")
+        );
+    }
+
+    Ok(ResolvedConfig {
+        pack_id: meta.pack_id.clone(),
+        environment: environment.to_string(),
+        tenant: tenant_id.to_string(),
+        team,
+        profiles,
+        secrets,
+        flags,
+    })
+}
+
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+}
+
+fn expand(template: &str, vars: &[(&str, Option<&str>)]) -> Result<String, String> {
+    let mut out = template.to_string();
+    for placeholder in placeholders(template) {
+        let value = vars
+            .iter()
+            .find(|(name, _)| *name == placeholder)
+            .ok_or_else(|| {
+                format!("key `{template}` uses unknown placeholder `{{{placeholder}}}`")
+            })?
+            .1
+            .ok_or_else(|| {
+                format!(
+                    "key `{template}` needs `{{{placeholder}}}` but the tenant context has none"
+                )
+            })?;
+        out = out.replace(&format!("{{{placeholder}}}"), value);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use greentic_types::{EnvId, TeamId, TenantId};
+    use serde_json::json;
+
+    use super::*;
+
+    fn meta(config: ConfigSection) -> PackMeta {
+        let mut meta: PackMeta = serde_json::from_value(json!({
+            "pack_id": "demo.crm",
+            "version": "1.0.0",
+            "name": "CRM",
+            "entry_flows": ["main"],
+            "created_at_utc": "2025-01-01T00:00:00Z",
+        }))
+        .unwrap();
+        meta.config = Some(config);
+        meta
+    }
+
+    fn section() -> ConfigSection {
+        serde_yaml_bw::from_str(
+            r#"
+profiles:
+  crm:
+    required: [base_url]
+    defaults: { timeout_ms: 5000, region: eu }
+secrets:
+  crm_token: { key: "{tenant}/{env}/crm-token" }
+  team_webhook: { key: "{tenant}/{team}/webhook" }
+  audit_sink: { key: "{team}/audit", required: false }
+flags:
+  beta_ui: false
+"#,
+        )
+        .unwrap()
+    }
+
+    fn tenant(team: Option<&str>) -> TenantCtx {
+        let mut ctx = TenantCtx::new(
+            EnvId::from_str("prod").unwrap(),
+            TenantId::from_str("acme").unwrap(),
+        );
+        ctx.team = team.map(|team| TeamId::from_str(team).unwrap());
+        ctx
+    }
+
+    #[test]
+    fn layers_apply_in_order() {
+        let host: HostValues = serde_json::from_value(json!({
+            "profiles": { "crm": { "base_url": "https://crm.example" } },
+            "environments": {
+                "prod": { "profiles": { "crm": { "region": null } }, "flags": { "beta_ui": false } }
+            },
+            "tenants": {
+                "acme": { "flags": { "beta_ui": true } },
+                "acme/sales": { "profiles": { "crm": { "base_url": "https://sales.crm.example" } } }
+            }
+        }))
+        .unwrap();
+        let meta = meta(section());
+
+        let config = resolve(&meta, &tenant(Some("sales")), "prod", &host).unwrap();
+        assert_eq!(
+            config.profile("crm").unwrap(),
+            json!({ "base_url": "https://sales.crm.example", "timeout_ms": 5000 })
+                .as_object()
+                .unwrap()
+        );
+        assert_eq!(config.secret_key("crm_token"), Some("acme/prod/crm-token"));
+        assert_eq!(
+            config.secret_key("team_webhook"),
+            Some("acme/sales/webhook")
+        );
+        assert_eq!(config.secret_key("audit_sink"), Some("sales/audit"));
+        assert!(config.flag("beta_ui"));
+        assert_eq!(config.team.as_deref(), Some("sales"));
+
+        let staging = resolve(&meta, &tenant(Some("ops")), "staging", &host).unwrap();
+        assert_eq!(staging.profile("crm").unwrap()["region"], "eu");
+        assert_eq!(
+            staging.profile("crm").unwrap()["base_url"],
+            "https://crm.example"
+        );
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let host: HostValues = serde_json::from_value(json!({
+            "profiles": { "erp": { "url": "x" } },
+            "flags": { "dark_mode": true },
+            "tenants": { "acme": { "secrets": { "crm_tokn": "k" } } }
+        }))
+        .unwrap();
+        let err = resolve(&meta(section()), &tenant(None), "prod", &host)
+            .unwrap_err()
+            .to_string();
+        for expected in [
+            "host values set undeclared profile `erp`",
+            "host values set undeclared flag `dark_mode`",
+            "tenant layer set undeclared secret `crm_tokn`",
+            "profile `crm` is missing setting `base_url`",
+            "secret `team_webhook`: key `{tenant}/{team}/webhook` needs `{team}`",
+        ] {
+            assert!(err.contains(expected), "missing `{expected}` in: {err}");
+        }
+
+        let mut bad = section();
+        bad.secrets.get_mut("crm_token").unwrap().key = "{region}/token".into();
+        let err = bad.validate().unwrap_err().to_string();
+        assert!(err.contains("unknown placeholder `{region}`"), "{err}");
+    }
+
+    #[test]
+    fn params_compile_to_a_config_schema() {
+        let section: ConfigSection = serde_yaml_bw::from_str(
+            r#"
+params:
+  region: { type: enum, values: [eu, us], default: eu }
+  max_results: { type: int, default: 10 }
+  api_base_url: { type: string, required: true, description: CRM endpoint }
+"#,
+        )
+        .unwrap();
+        section.validate().unwrap();
+        let schema = section.params_schema().unwrap();
+        #[cfg(feature = "builder")]
+        schema.validate().unwrap();
+        assert_eq!(
+            JsonValue::Object(schema.0.clone()),
+            json!({
+                "type": "object",
+                "properties": {
+                    "api_base_url": { "type": "string", "description": "CRM endpoint" },
+                    "max_results": { "type": "integer", "default": 10 },
+                    "region": { "type": "string", "enum": ["eu", "us"], "default": "eu" }
+                },
+                "required": ["api_base_url"],
+                "additionalProperties": false
+            })
+        );
+        assert_eq!(schema.required_keys(), ["api_base_url"]);
+        assert!(ConfigSection::default().params_schema().is_none());
+
+        for bad in [
+            "params: { region: { type: enum } }",
+            "params: { region: { type: enum, values: [eu, us], default: ap } }",
+            "params: { limit: { type: int, default: \"10\" } }",
+            "params: { limit: { type: int, values: [\"1\"] } }",
+            "params: { url: { type: string, required: true, default: x } }",
+            "params: { api.url: { type: string } }",
+        ] {
+            let section: ConfigSection = serde_yaml_bw::from_str(bad).unwrap();
+            assert!(section.validate().is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,553 @@
+//! Install-time pack configuration.
+//!
+//! `config_schema:` in `pack.yaml` is a JSON Schema for the settings a host
+//! collects when it installs the pack, such as API base URLs and toggles.
+//! Secrets stay in the `config` section. The schema is embedded in the
+//! manifest so hosts can render a configuration form from it, and the
+//! deployment plan lists its keys under `extra.config_keys`.
+//!
+//! `config_defaults:` supplies default settings and per-environment
+//! overrides on top of the schema's own `default`s. Hosts call
+//! `resolve_settings`, built with the `builder` or `plan` feature, to merge
+//! them with the values they collected.
+//!
+//! ```yaml
+//! config_schema:
+//!   type: object
+//!   required: [api]
+//!   properties:
+//!     api:
+//!       type: object
+//!       required: [base_url]
+//!       properties:
+//!         base_url: { type: string, format: uri }
+//!         timeout_ms: { type: integer, default: 5000 }
+//!     beta_ui: { type: boolean, default: false }
+//!
+//! config_defaults:
+//!   environments: [dev, prod]
+//!   values:
+//!     api: { base_url: https://api.example.com }
+//!   overrides:
+//!     dev:
+//!       api: { base_url: http://localhost:8080 }
+//!       beta_ui: true
+//! ```
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "builder")]
+use anyhow::anyhow;
+#[cfg(any(feature = "builder", feature = "plan"))]
+use anyhow::{Result, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::builder::PackMeta;
+
+/// JSON Schema of the settings a host supplies at install time.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(transparent)]
+pub struct ConfigSchema(pub JsonMap<String, JsonValue>);
+
+/// One setting described by a [`ConfigSchema`], for hosts rendering forms.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ConfigKey {
+    /// Dotted path of the setting, e.g. `api.base_url`.
+    pub key: String,
+    /// The host must supply a value: the setting and every object above it
+    /// are listed under `required`, and the schema gives no `default`.
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "type")]
+    pub value_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<JsonValue>,
+}
+
+impl ConfigSchema {
+    #[cfg(feature = "builder")]
+    pub fn validate(&self) -> Result<()> {
+        let schema = JsonValue::Object(self.0.clone());
+        jsonschema::meta::validate(&schema)
+            .map_err(|err| anyhow!("config_schema is not a valid JSON Schema: {err}"))?;
+        check_object("config_schema", &self.0)
+    }
+
+    /// The settings the schema describes, sorted by key. Nested objects with
+    /// `properties` contribute their leaves rather than a key of their own.
+    pub fn keys(&self) -> Vec<ConfigKey> {
+        let mut keys = Vec::new();
+        collect_keys(&self.0, "", true, &mut keys);
+        keys.sort_by(|a, b| a.key.cmp(&b.key));
+        keys
+    }
+
+    /// [`ConfigSchema::keys`] with the defaults in `defaults` filled in; a
+    /// setting with a default is not required.
+    pub fn keys_with_defaults(&self, defaults: &JsonMap<String, JsonValue>) -> Vec<ConfigKey> {
+        let mut keys = self.keys();
+        for key in &mut keys {
+            if let Some(value) = lookup(defaults, &key.key) {
+                key.default = Some(value.clone());
+                key.required = false;
+            }
+        }
+        keys
+    }
+
+    /// The schema's leaf `default`s as nested settings.
+    pub fn defaults(&self) -> JsonMap<String, JsonValue> {
+        let mut defaults = JsonMap::new();
+        for key in self.keys() {
+            if let Some(value) = key.default {
+                insert(&mut defaults, &key.key, value);
+            }
+        }
+        defaults
+    }
+
+    /// Where `values` does not conform to the schema, as `key: problem`.
+    /// With `partial`, settings may be missing: `required` is not enforced.
+    #[cfg(any(feature = "builder", feature = "plan"))]
+    pub fn violations(&self, values: &JsonMap<String, JsonValue>, partial: bool) -> Vec<String> {
+        let mut schema = JsonValue::Object(self.0.clone());
+        if partial {
+            strip_required(&mut schema);
+        }
+        let validator = match jsonschema::validator_for(&schema) {
+            Ok(validator) => validator,
+            Err(err) => return vec![format!("config_schema: {err}")],
+        };
+        let instance = JsonValue::Object(values.clone());
+        validator
+            .iter_errors(&instance)
+            .map(|error| {
+                let pointer = error.instance_path().to_string();
+                let key = pointer.trim_start_matches('/').replace('/', ".");
+                if key.is_empty() {
+                    error.to_string()
+                } else {
+                    format!("{key}: {error}")
+                }
+            })
+            .collect()
+    }
+
+    /// Dotted paths of the settings the host must supply.
+    pub fn required_keys(&self) -> Vec<String> {
+        self.keys()
+            .into_iter()
+            .filter(|key| key.required)
+            .map(|key| key.key)
+            .collect()
+    }
+}
+
+/// Default settings and per-environment overrides for [`ConfigSchema`].
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct ConfigDefaults {
+    /// Environments the pack is deployed to, e.g. `dev` and `prod`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub environments: Vec<String>,
+    /// Defaults for every environment.
+    #[serde(default, skip_serializing_if = "JsonMap::is_empty")]
+    pub values: JsonMap<String, JsonValue>,
+    /// Settings merged over `values` in one environment; `null` removes a default.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, JsonMap<String, JsonValue>>,
+}
+
+impl ConfigDefaults {
+    /// Checks that overrides name declared environments and that the
+    /// defaults of every environment conform to `schema`, apart from
+    /// settings left for the host to supply.
+    #[cfg(feature = "builder")]
+    pub fn validate(&self, schema: Option<&ConfigSchema>) -> Result<()> {
+        let Some(schema) = schema else {
+            bail!("config_defaults needs a config_schema to check the defaults against");
+        };
+        for (index, environment) in self.environments.iter().enumerate() {
+            if environment.trim().is_empty() || environment.chars().any(char::is_whitespace) {
+                bail!(
+                    "config_defaults.environments[{index}] must be non-empty and contain no whitespace"
+                );
+            }
+            if self.environments[..index].contains(environment) {
+                bail!("config_defaults.environments lists `{environment}` twice");
+            }
+        }
+
+        let mut problems = Vec::new();
+        for environment in self.overrides.keys() {
+            if !self.environments.contains(environment) {
+                problems.push(format!(
+                    "config_defaults.overrides.{environment} refers to undeclared environment `{environment}`"
+                ));
+            }
+        }
+        let base = self.merged(&schema.defaults(), None);
+        for violation in schema.violations(&base, true) {
+            problems.push(format!("config_defaults.values: {violation}"));
+        }
+        for environment in self.overrides.keys() {
+            let merged = self.merged(&schema.defaults(), Some(environment));
+            for violation in schema.violations(&merged, true) {
+                problems.push(format!("config_defaults for `{environment}`: {violation}"));
+            }
+        }
+        if !problems.is_empty() {
+            bail!(
+                "config_defaults is invalid:\n  - {}",
+                problems.join("\n  - ")
+            );
+        }
+        Ok(())
+    }
+
+    fn merged(
+        &self,
+        schema_defaults: &JsonMap<String, JsonValue>,
+        environment: Option<&str>,
+    ) -> JsonMap<String, JsonValue> {
+        let mut merged = schema_defaults.clone();
+        merge(&mut merged, &self.values);
+        if let Some(overrides) = environment.and_then(|env| self.overrides.get(env)) {
+            merge(&mut merged, overrides);
+        }
+        merged
+    }
+}
+
+/// Default settings of the pack in `environment`: the schema's `default`s,
+/// then `config_defaults.values`, then the environment's overrides.
+pub fn config_defaults(meta: &PackMeta, environment: &str) -> JsonMap<String, JsonValue> {
+    let schema_defaults = meta
+        .config_schema
+        .as_ref()
+        .map(ConfigSchema::defaults)
+        .unwrap_or_default();
+    match &meta.config_defaults {
+        Some(defaults) => defaults.merged(&schema_defaults, Some(environment)),
+        None => schema_defaults,
+    }
+}
+
+/// Settings of the pack in `environment`: [`config_defaults`] with `host`,
+/// the values the host collected, merged over them. Objects merge key by
+/// key and `null` removes a default.
+///
+/// Fails, listing every problem, when the result does not conform to the
+/// pack's `config_schema`, including required settings nobody supplied.
+#[cfg(any(feature = "builder", feature = "plan"))]
+pub fn resolve_settings(
+    meta: &PackMeta,
+    environment: &str,
+    host: &JsonMap<String, JsonValue>,
+) -> Result<JsonMap<String, JsonValue>> {
+    let mut settings = config_defaults(meta, environment);
+    merge(&mut settings, host);
+    if let Some(schema) = &meta.config_schema {
+        let problems = schema.violations(&settings, false);
+        if !problems.is_empty() {
+            bail!(
+                "settings of pack `{}` in `{environment}` are invalid:\n  - {}",
+                meta.pack_id,
+                problems.join("\n  - ")
+            );
+        }
+    }
+    Ok(settings)
+}
+
+fn merge(base: &mut JsonMap<String, JsonValue>, overlay: &JsonMap<String, JsonValue>) {
+    for (key, value) in overlay {
+        if value.is_null() {
+            base.remove(key);
+            continue;
+        }
+        if let (Some(JsonValue::Object(current)), JsonValue::Object(nested)) =
+            (base.get_mut(key), value)
+        {
+            merge(current, nested);
+            continue;
+        }
+        base.insert(key.clone(), value.clone());
+    }
+}
+
+fn lookup<'a>(values: &'a JsonMap<String, JsonValue>, key: &str) -> Option<&'a JsonValue> {
+    let (head, rest) = match key.split_once('.') {
+        Some((head, rest)) => (head, Some(rest)),
+        None => (key, None),
+    };
+    let value = values.get(head)?;
+    match rest {
+        Some(rest) => lookup(value.as_object()?, rest),
+        None => Some(value),
+    }
+}
+
+fn insert(values: &mut JsonMap<String, JsonValue>, key: &str, value: JsonValue) {
+    match key.split_once('.') {
+        Some((head, rest)) => {
+            let nested = values
+                .entry(head.to_string())
+                .or_insert_with(|| JsonValue::Object(JsonMap::new()));
+            if let JsonValue::Object(nested) = nested {
+                insert(nested, rest, value);
+            }
+        }
+        None => {
+            values.insert(key.to_string(), value);
+        }
+    }
+}
+
+#[cfg(any(feature = "builder", feature = "plan"))]
+fn strip_required(schema: &mut JsonValue) {
+    match schema {
+        JsonValue::Object(map) => {
+            if map.get("required").is_some_and(JsonValue::is_array) {
+                map.remove("required");
+            }
+            map.values_mut().for_each(strip_required);
+        }
+        JsonValue::Array(items) => items.iter_mut().for_each(strip_required),
+        _ => {}
+    }
+}
+
+/// Checks the shape hosts rely on beyond what the meta-schema enforces: the
+/// schema describes an object, and every `required` name is a property.
+#[cfg(feature = "builder")]
+fn check_object(path: &str, schema: &JsonMap<String, JsonValue>) -> Result<()> {
+    if !is_object(schema) {
+        bail!("{path} must have `type: object`");
+    }
+    let properties = properties(schema);
+    for name in required(schema) {
+        if !properties.is_some_and(|properties| properties.contains_key(name)) {
+            bail!("{path}.required lists `{name}`, which is not under {path}.properties");
+        }
+    }
+    for (name, property) in properties.into_iter().flatten() {
+        if name.trim().is_empty() || name.contains('.') {
+            bail!("{path}.properties key `{name}` must be non-empty and contain no `.`");
+        }
+        if let Some(property) = property.as_object()
+            && is_object(property)
+        {
+            check_object(&format!("{path}.properties.{name}"), property)?;
+        }
+    }
+    Ok(())
+}
+
+fn collect_keys(
+    schema: &JsonMap<String, JsonValue>,
+    prefix: &str,
+    required_here: bool,
+    keys: &mut Vec<ConfigKey>,
+) {
+    let required_names = required(schema);
+    for (name, property) in properties(schema).into_iter().flatten() {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{prefix}.{name}")
+        };
+        let property = property.as_object();
+        let default = property
+            .and_then(|property| property.get("default"))
+            .cloned();
+        let required =
+            required_here && required_names.contains(&name.as_str()) && default.is_none();
+        match property {
+            Some(nested) if is_object(nested) && properties(nested).is_some() => {
+                collect_keys(nested, &key, required, keys);
+            }
+            _ => keys.push(ConfigKey {
+                key,
+                required,
+                value_type: property
+                    .and_then(|property| property.get("type"))
+                    .and_then(JsonValue::as_str)
+                    .map(str::to_string),
+                description: property
+                    .and_then(|property| property.get("description"))
+                    .and_then(JsonValue::as_str)
+                    .map(str::to_string),
+                default,
+            }),
+        }
+    }
+}
+
+fn properties(schema: &JsonMap<String, JsonValue>) -> Option<&JsonMap<String, JsonValue>> {
+    schema.get("properties").and_then(JsonValue::as_object)
+}
+
+fn required(schema: &JsonMap<String, JsonValue>) -> Vec<&str> {
+    schema
+        .get("required")
+        .and_then(JsonValue::as_array)
+        .map(|names| names.iter().filter_map(JsonValue::as_str).collect())
+        .unwrap_or_default()
+}
+
+fn is_object(schema: &JsonMap<String, JsonValue>) -> bool {
+    schema.get("type").and_then(JsonValue::as_str) == Some("object")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(value: JsonValue) -> ConfigSchema {
+        serde_json::from_value(value).expect("config schema")
+    }
+
+    fn sample() -> ConfigSchema {
+        schema(json!({
+            "type": "object",
+            "required": ["api", "region"],
+            "properties": {
+                "api": {
+                    "type": "object",
+                    "required": ["base_url"],
+                    "properties": {
+                        "base_url": { "type": "string", "format": "uri" },
+                        "timeout_ms": { "type": "integer", "default": 5000 }
+                    }
+                },
+                "region": { "type": "string", "default": "eu" },
+                "beta_ui": { "type": "boolean", "description": "Enable the new UI" }
+            }
+        }))
+    }
+
+    #[test]
+    fn lists_leaf_keys_with_required_flags() {
+        let config = sample();
+        #[cfg(feature = "builder")]
+        config.validate().expect("valid schema");
+        let keys = config.keys();
+        let names: Vec<&str> = keys.iter().map(|key| key.key.as_str()).collect();
+        assert_eq!(
+            names,
+            ["api.base_url", "api.timeout_ms", "beta_ui", "region"]
+        );
+        assert_eq!(config.required_keys(), ["api.base_url"]);
+        assert_eq!(keys[1].default, Some(json!(5000)));
+        assert_eq!(keys[2].description.as_deref(), Some("Enable the new UI"));
+        assert_eq!(keys[2].value_type.as_deref(), Some("boolean"));
+    }
+
+    #[cfg(feature = "builder")]
+    fn defaults(value: JsonValue) -> ConfigDefaults {
+        serde_json::from_value(value).expect("config defaults")
+    }
+
+    #[cfg(feature = "builder")]
+    fn meta(defaults: ConfigDefaults) -> PackMeta {
+        let mut meta: PackMeta = serde_json::from_value(json!({
+            "pack_id": "demo.crm",
+            "version": "1.0.0",
+            "name": "CRM",
+            "entry_flows": ["main"],
+            "created_at_utc": "2025-01-01T00:00:00Z",
+        }))
+        .unwrap();
+        meta.config_schema = Some(sample());
+        meta.config_defaults = Some(defaults);
+        meta
+    }
+
+    #[cfg(feature = "builder")]
+    #[test]
+    fn merges_defaults_per_environment() {
+        let defaults = defaults(json!({
+            "environments": ["dev", "prod"],
+            "values": { "api": { "base_url": "https://api.example.com" } },
+            "overrides": {
+                "dev": { "api": { "base_url": "http://localhost:8080" }, "beta_ui": true }
+            }
+        }));
+        defaults.validate(Some(&sample())).expect("valid defaults");
+        let meta = meta(defaults);
+
+        let prod = config_defaults(&meta, "prod");
+        assert_eq!(
+            JsonValue::Object(prod),
+            json!({
+                "api": { "base_url": "https://api.example.com", "timeout_ms": 5000 },
+                "region": "eu"
+            })
+        );
+        let dev = resolve_settings(&meta, "dev", &JsonMap::new()).expect("dev settings");
+        assert_eq!(dev["api"]["base_url"], "http://localhost:8080");
+        assert_eq!(dev["beta_ui"], true);
+
+        let host = json!({ "api": { "timeout_ms": "slow" } });
+        let err = resolve_settings(&meta, "prod", host.as_object().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("api.timeout_ms"), "{err}");
+
+        let keys = sample().keys_with_defaults(&config_defaults(&meta, "prod"));
+        assert!(keys.iter().all(|key| !key.required), "{keys:?}");
+    }
+
+    #[cfg(feature = "builder")]
+    #[test]
+    fn rejects_bad_defaults_and_undeclared_environments() {
+        let defaults = defaults(json!({
+            "environments": ["prod"],
+            "values": { "api": { "timeout_ms": "soon" } },
+            "overrides": { "staging": { "beta_ui": true } }
+        }));
+        let err = defaults.validate(Some(&sample())).unwrap_err().to_string();
+        assert!(err.contains("undeclared environment `staging`"), "{err}");
+        assert!(
+            err.contains("config_defaults.values: api.timeout_ms"),
+            "{err}"
+        );
+        assert!(
+            ConfigDefaults::default()
+                .validate(None)
+                .unwrap_err()
+                .to_string()
+                .contains("needs a config_schema")
+        );
+    }
+
+    #[cfg(feature = "builder")]
+    #[test]
+    fn rejects_malformed_schemas() {
+        for (value, message) in [
+            (json!({ "type": "string" }), "must have `type: object`"),
+            (
+                json!({ "type": "object", "required": ["url"], "properties": {} }),
+                "lists `url`",
+            ),
+            (
+                json!({ "type": "object", "properties": { "a.b": { "type": "string" } } }),
+                "contain no `.`",
+            ),
+            (
+                json!({ "type": "object", "properties": { "url": { "type": "text" } } }),
+                "not a valid JSON Schema",
+            ),
+        ] {
+            let err = schema(value).validate().expect_err(message);
+            assert!(err.to_string().contains(message), "{err}");
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! How hosts may schedule runs of event-driven flows.
+//!
+//! A flow triggered by events says whether its runs may overlap and how to
+//! recognise a redelivered event, keyed by flow id:
+//!
+//! ```yaml
+//! dispatch:
+//!   order_events:
+//!     concurrency:
+//!       mode: serial_per_key
+//!       key: in.order.id
+//!     idempotency_key: in.event_id
+//! ```
+//!
+//! `parallel` runs may overlap freely; `serial_per_key` runs one at a time
+//! for each value of `key`. Hosts skip a run whose `idempotency_key` matches
+//! one they have already completed. Keys are `in.<field>` paths into the
+//! flow input, optionally in `{{ }}`, and must name fields the flow's input
+//! schema has.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::flow_schema::is_name;
+
+/// Dispatch declarations by flow id.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(transparent)]
+pub struct DispatchSection {
+    pub flows: BTreeMap<String, FlowDispatch>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct FlowDispatch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<Concurrency>,
+    /// Input field that identifies a delivery, e.g. `in.event_id`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct Concurrency {
+    pub mode: ConcurrencyMode,
+    /// Input field runs are serialised on; only for `serial_per_key`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ConcurrencyMode {
+    /// Runs may overlap.
+    Parallel,
+    /// One run at a time per value of the key.
+    SerialPerKey,
+}
+
+impl DispatchSection {
+    pub fn validate(&self) -> Result<()> {
+        for (flow, dispatch) in &self.flows {
+            if flow.trim().is_empty() {
+                bail!("dispatch must be keyed by flow id");
+            }
+            if *dispatch == FlowDispatch::default() {
+                bail!("dispatch.{flow} must set `concurrency` or `idempotency_key`");
+            }
+            for (field, key) in dispatch.keys() {
+                if key_path(key).is_none() {
+                    bail!("dispatch.{flow}.{field} `{key}` must be an `in.<field>` path");
+                }
+            }
+            if let Some(concurrency) = &dispatch.concurrency {
+                match (concurrency.mode, &concurrency.key) {
+                    (ConcurrencyMode::SerialPerKey, None) => {
+                        bail!("dispatch.{flow}.concurrency needs a `key` for serial_per_key")
+                    }
+                    (ConcurrencyMode::Parallel, Some(_)) => {
+                        bail!("dispatch.{flow}.concurrency.key only applies to serial_per_key")
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every entry names one of `flows`, the pack's flow ids.
+    pub fn check_flows<'a>(&self, flows: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        let flows: Vec<&str> = flows.into_iter().collect();
+        if let Some(flow) = self
+            .flows
+            .keys()
+            .find(|flow| !flows.contains(&flow.as_str()))
+        {
+            bail!("dispatch refers to unknown flow `{flow}`");
+        }
+        Ok(())
+    }
+
+    /// Checks that the keys of `flow` name fields of its `input_schema`.
+    /// Objects without `properties` accept any field.
+    pub fn check_keys(&self, flow: &str, input_schema: &JsonValue) -> Result<()> {
+        let Some(dispatch) = self.flows.get(flow) else {
+            return Ok(());
+        };
+        for (field, key) in dispatch.keys() {
+            let mut schema = input_schema;
+            for segment in key_path(key).unwrap_or_default() {
+                let Some(properties) = schema.get("properties") else {
+                    break;
+                };
+                let Some(property) = properties.get(segment) else {
+                    bail!("dispatch.{flow}.{field} `{key}` is not a field of the flow's input");
+                };
+                if property.get("type").and_then(JsonValue::as_str) == Some("array") {
+                    bail!("dispatch.{flow}.{field} `{key}` goes through an array");
+                }
+                schema = property;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn flow(&self, flow: &str) -> Option<&FlowDispatch> {
+        self.flows.get(flow)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flows.is_empty()
+    }
+}
+
+impl FlowDispatch {
+    /// The key expressions, by field name.
+    fn keys(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        let concurrency = self
+            .concurrency
+            .as_ref()
+            .and_then(|concurrency| concurrency.key.as_deref())
+            .map(|key| ("concurrency.key", key));
+        let idempotency = self
+            .idempotency_key
+            .as_deref()
+            .map(|key| ("idempotency_key", key));
+        concurrency.into_iter().chain(idempotency)
+    }
+}
+
+/// The field path of a key expression: `in.order.id` or `{{in.order.id}}`.
+pub fn key_path(key: &str) -> Option<Vec<&str>> {
+    let key = key.trim();
+    let key = key
+        .strip_prefix("{{")
+        .and_then(|key| key.strip_suffix("}}"))
+        .map_or(key, str::trim);
+    let mut segments = key.split('.');
+    if segments.next() != Some("in") {
+        return None;
+    }
+    let path: Vec<&str> = segments.collect();
+    (!path.is_empty() && path.iter().all(|segment| is_name(segment))).then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow_schema::flow_schemas;
+
+    const FLOW: &str = r#"
+id: order_events
+type: events
+nodes:
+  record:
+    orders.update:
+      id: in.order.id
+      lines: "{{#each in.lines}}{{sku}}{{/each}}"
+      event: "{{in.event_id}}"
+"#;
+
+    #[test]
+    fn validates_modes_and_keys_against_the_input_schema() {
+        let section: DispatchSection = serde_yaml_bw::from_str(
+            "order_events:\n  concurrency:\n    mode: serial_per_key\n    key: in.order.id\n  idempotency_key: \"{{in.event_id}}\"\n",
+        )
+        .unwrap();
+        section.validate().unwrap();
+        section.check_flows(["order_events"]).unwrap();
+        assert!(section.check_flows(["other"]).is_err());
+        let input = flow_schemas(FLOW).unwrap().input;
+        section.check_keys("order_events", &input).unwrap();
+        assert_eq!(key_path("{{ in.order.id }}"), Some(vec!["order", "id"]));
+
+        let mut unknown = section.clone();
+        unknown
+            .flows
+            .get_mut("order_events")
+            .unwrap()
+            .idempotency_key = Some("in.delivery".into());
+        let err = unknown.check_keys("order_events", &input).unwrap_err();
+        assert!(err.to_string().contains("not a field"), "{err}");
+        let mut array = section.clone();
+        array.flows.get_mut("order_events").unwrap().idempotency_key = Some("in.lines.sku".into());
+        assert!(array.check_keys("order_events", &input).is_err());
+
+        for yaml in [
+            "f:\n  concurrency:\n    mode: serial_per_key\n",
+            "f:\n  concurrency:\n    mode: parallel\n    key: in.id\n",
+            "f:\n  idempotency_key: event_id\n",
+            "f: {}\n",
+        ] {
+            let section: DispatchSection = serde_yaml_bw::from_str(yaml).unwrap();
+            assert!(section.validate().is_err(), "{yaml}");
+        }
+    }
+}
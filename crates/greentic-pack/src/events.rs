@@ -1,11 +1,14 @@
 use std::collections::BTreeSet;
 use std::fmt;
+use std::str::FromStr;
 
 use anyhow::{Result, bail};
+#[cfg(feature = "schema")]
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct EventsSection {
     #[serde(default)]
     pub providers: Vec<EventProviderSpec>,
@@ -24,7 +27,9 @@ impl EventsSection {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[non_exhaustive]
 pub struct EventProviderSpec {
     pub name: String,
     pub kind: EventProviderKind,
@@ -38,6 +43,60 @@ pub struct EventProviderSpec {
 }
 
 impl EventProviderSpec {
+    pub fn new(
+        name: impl Into<String>,
+        kind: EventProviderKind,
+        component: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            component: component.into(),
+            default_flow: None,
+            custom_flow: None,
+            capabilities: EventProviderCapabilities::default(),
+        }
+    }
+
+    pub fn with_default_flow(mut self, flow: impl Into<String>) -> Self {
+        self.default_flow = Some(flow.into());
+        self
+    }
+
+    pub fn with_custom_flow(mut self, flow: impl Into<String>) -> Self {
+        self.custom_flow = Some(flow.into());
+        self
+    }
+
+    pub fn with_capabilities(mut self, capabilities: EventProviderCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> &EventProviderKind {
+        &self.kind
+    }
+
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    pub fn default_flow(&self) -> Option<&str> {
+        self.default_flow.as_deref()
+    }
+
+    pub fn custom_flow(&self) -> Option<&str> {
+        self.custom_flow.as_deref()
+    }
+
+    pub fn capabilities(&self) -> &EventProviderCapabilities {
+        &self.capabilities
+    }
+
     fn validate(&self) -> Result<()> {
         if self.name.trim().is_empty() {
             bail!("events.providers[].name is required");
@@ -60,7 +119,9 @@ impl EventProviderSpec {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[non_exhaustive]
 pub struct EventProviderCapabilities {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub transport: Option<TransportKind>,
@@ -72,8 +133,52 @@ pub struct EventProviderCapabilities {
     pub topics: Vec<String>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+impl EventProviderCapabilities {
+    pub fn with_transport(mut self, transport: TransportKind) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    pub fn with_reliability(mut self, reliability: ReliabilityKind) -> Self {
+        self.reliability = Some(reliability);
+        self
+    }
+
+    pub fn with_ordering(mut self, ordering: OrderingKind) -> Self {
+        self.ordering = Some(ordering);
+        self
+    }
+
+    pub fn with_topics<I, T>(mut self, topics: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.topics.extend(topics.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn transport(&self) -> Option<&TransportKind> {
+        self.transport.as_ref()
+    }
+
+    pub fn reliability(&self) -> Option<&ReliabilityKind> {
+        self.reliability.as_ref()
+    }
+
+    pub fn ordering(&self) -> Option<&OrderingKind> {
+        self.ordering.as_ref()
+    }
+
+    pub fn topics(&self) -> &[String] {
+        &self.topics
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 #[serde(rename_all = "snake_case")]
+#[non_exhaustive]
 pub enum EventProviderKind {
     Broker,
     Source,
@@ -93,9 +198,25 @@ impl fmt::Display for EventProviderKind {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+impl FromStr for EventProviderKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Ok(match value {
+            "broker" => Self::Broker,
+            "source" => Self::Source,
+            "sink" => Self::Sink,
+            "bridge" => Self::Bridge,
+            other => bail!("unknown events provider kind `{other}`"),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 #[serde(rename_all = "snake_case")]
 #[serde(untagged)]
+#[non_exhaustive]
 pub enum TransportKind {
     Nats,
     Kafka,
@@ -118,8 +239,26 @@ impl fmt::Display for TransportKind {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+/// Names other than the known transports parse as [`TransportKind::Other`].
+impl FromStr for TransportKind {
+    type Err = std::convert::Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "nats" => Self::Nats,
+            "kafka" => Self::Kafka,
+            "sqs" => Self::Sqs,
+            "webhook" => Self::Webhook,
+            "email" => Self::Email,
+            other => Self::Other(other.to_string()),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 #[serde(rename_all = "snake_case")]
+#[non_exhaustive]
 pub enum ReliabilityKind {
     AtMostOnce,
     AtLeastOnce,
@@ -137,8 +276,23 @@ impl fmt::Display for ReliabilityKind {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+impl FromStr for ReliabilityKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Ok(match value {
+            "at_most_once" => Self::AtMostOnce,
+            "at_least_once" => Self::AtLeastOnce,
+            "effectively_once" => Self::EffectivelyOnce,
+            other => bail!("unknown reliability `{other}`"),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 #[serde(rename_all = "snake_case")]
+#[non_exhaustive]
 pub enum OrderingKind {
     None,
     PerKey,
@@ -155,3 +309,55 @@ impl fmt::Display for OrderingKind {
         f.write_str(value)
     }
 }
+
+impl FromStr for OrderingKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Ok(match value {
+            "none" => Self::None,
+            "per_key" => Self::PerKey,
+            "global" => Self::Global,
+            other => bail!("unknown ordering `{other}`"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_round_trip_through_strings() {
+        for kind in ["broker", "source", "sink", "bridge"] {
+            assert_eq!(kind.parse::<EventProviderKind>().unwrap().to_string(), kind);
+        }
+        for kind in ["nats", "kafka", "sqs", "webhook", "email", "mqtt"] {
+            assert_eq!(kind.parse::<TransportKind>().unwrap().to_string(), kind);
+        }
+        for kind in ["at_most_once", "at_least_once", "effectively_once"] {
+            assert_eq!(kind.parse::<ReliabilityKind>().unwrap().to_string(), kind);
+        }
+        for kind in ["none", "per_key", "global"] {
+            assert_eq!(kind.parse::<OrderingKind>().unwrap().to_string(), kind);
+        }
+        assert!("queue".parse::<EventProviderKind>().is_err());
+    }
+
+    #[test]
+    fn providers_build_from_constructors() {
+        let provider = EventProviderSpec::new("nats-core", EventProviderKind::Broker, "nats@1.0.0")
+            .with_default_flow("flows/events/nats/default.ygtc")
+            .with_capabilities(
+                EventProviderCapabilities::default()
+                    .with_transport(TransportKind::Nats)
+                    .with_topics(["greentic.*"]),
+            );
+        let events = EventsSection {
+            providers: vec![provider.clone(), provider],
+        };
+        let err = events.validate().unwrap_err();
+        assert!(err.to_string().contains("duplicate"), "{err}");
+        assert_eq!(events.providers[0].capabilities().topics(), ["greentic.*"]);
+    }
+}
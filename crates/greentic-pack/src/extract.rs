@@ -0,0 +1,533 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use greentic_types::PackKind;
+use serde::Serialize;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::builder::{PACK_VERSION, PackManifest, PackMeta, SOURCE_SPEC_PATH};
+use crate::capabilities::CapabilitiesSection;
+use crate::classification::ClassificationSection;
+use crate::config::ConfigSection;
+use crate::config_schema::{ConfigDefaults, ConfigSchema};
+use crate::dispatch::DispatchSection;
+use crate::events::EventsSection;
+use crate::import_spec::ImportSpec;
+use crate::lifecycle::LifecycleSection;
+use crate::limits::LimitsSection;
+use crate::locales::LocalesSection;
+use crate::media::MediaSection;
+use crate::messaging::MessagingSection;
+use crate::metrics::MetricsSection;
+use crate::qos::QosSection;
+use crate::quotas::QuotaSection;
+use crate::reader::{SigningPolicy, open_pack, read_pack_files};
+use crate::repo::{InterfaceBinding, RepoPackSection};
+use crate::schedule::ScheduleSection;
+use crate::secrets::SecretsSection;
+
+/// Archive prefix under which template assets are stored.
+const ASSETS_PREFIX: &str = "assets/";
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExtractOptions {
+    /// Verify the SBOM and signature under this policy before writing anything.
+    pub verify: Option<SigningPolicy>,
+    /// Ensure the output has a `pack.yaml`: the spec embedded at build time when
+    /// present, otherwise one reconstructed from the manifest.
+    pub pack_yaml: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtractReport {
+    pub out_dir: PathBuf,
+    /// Files written, relative to `out_dir`, in sorted order.
+    pub files: Vec<String>,
+    pub verified: bool,
+    pub warnings: Vec<String>,
+}
+
+/// Unpacks `archive` into `out_dir`.
+///
+/// Every entry keeps its archive path, except template assets which move from
+/// `assets/<path>` back to `<path>` so the result mirrors a pack source tree.
+/// `out_dir` must be missing or empty.
+pub fn extract_pack(archive: &Path, out_dir: &Path, opts: ExtractOptions) -> Result<ExtractReport> {
+    let files = match opts.verify {
+        Some(policy) => {
+            open_pack(archive, policy)
+                .map_err(|err| {
+                    anyhow!("refusing to extract {}: {}", archive.display(), err.message)
+                })?
+                .files
+        }
+        None => read_pack_files(archive)?,
+    };
+
+    ensure_empty_dir(out_dir)?;
+
+    let mut layout = BTreeMap::new();
+    for path in files.keys() {
+        let target = path.strip_prefix(ASSETS_PREFIX).unwrap_or(path);
+        if let Some(previous) = layout.insert(target.to_string(), path.as_str()) {
+            bail!("archive entries `{previous}` and `{path}` would both extract to `{target}`");
+        }
+    }
+
+    let mut warnings = Vec::new();
+    let spec_yaml = if opts.pack_yaml && !layout.contains_key(SOURCE_SPEC_PATH) {
+        let manifest = decode_manifest(&files)?;
+        let (yaml, spec_warnings) = pack_yaml(&manifest, files.keys().map(String::as_str))?;
+        warnings.extend(spec_warnings);
+        Some(yaml)
+    } else {
+        None
+    };
+
+    let mut written = Vec::new();
+    for (target, source) in &layout {
+        write_file(out_dir, target, &files[*source])?;
+        written.push(target.clone());
+    }
+    if let Some(yaml) = spec_yaml {
+        write_file(out_dir, SOURCE_SPEC_PATH, yaml.as_bytes())?;
+        written.push(SOURCE_SPEC_PATH.to_string());
+        written.sort();
+    }
+
+    Ok(ExtractReport {
+        out_dir: out_dir.to_path_buf(),
+        files: written,
+        verified: opts.verify.is_some(),
+        warnings,
+    })
+}
+
+/// Reconstructs a `pack.yaml` for an extracted archive from its manifest.
+///
+/// `archive_paths` lists the archive entries; template directories are derived
+/// from the `assets/` entries. Returns the YAML text and anything that could
+/// not be carried over.
+pub fn pack_yaml<'a>(
+    manifest: &PackManifest,
+    archive_paths: impl IntoIterator<Item = &'a str>,
+) -> Result<(String, Vec<String>)> {
+    let meta = &manifest.meta;
+    let mut warnings = Vec::new();
+
+    let mut template_dirs = BTreeSet::new();
+    for path in archive_paths {
+        if let Some(rest) = path.strip_prefix(ASSETS_PREFIX) {
+            match rest.split_once('/') {
+                Some((dir, _)) => {
+                    template_dirs.insert(dir.to_string());
+                }
+                None => warnings.push(format!(
+                    "asset `{rest}` is not inside a directory and cannot be listed in template_dirs"
+                )),
+            }
+        }
+    }
+
+    for component in &manifest.components {
+        if component.name != "pack_component" {
+            warnings.push(format!(
+                "component {}@{} is extracted but has no pack.yaml equivalent; re-declare it before rebuilding",
+                component.name, component.version
+            ));
+        }
+    }
+
+    // A schema the build derived from `config.params` is not written back.
+    let params_schema = meta.config.as_ref().and_then(ConfigSection::params_schema);
+    let spec = SourceSpec {
+        pack_version: PACK_VERSION,
+        id: &meta.pack_id,
+        version: meta.version.to_string(),
+        kind: meta.kind.as_ref(),
+        name: (meta.name != meta.pack_id).then_some(meta.name.as_str()),
+        description: meta.description.as_deref(),
+        authors: &meta.authors,
+        license: meta.license.as_deref(),
+        homepage: meta.homepage.as_deref(),
+        support: meta.support.as_deref(),
+        vendor: meta.vendor.as_deref(),
+        tags: &meta.tags,
+        categories: &meta.categories,
+        flow_files: manifest
+            .flows
+            .iter()
+            .map(|flow| flow.file_yaml.clone())
+            .collect(),
+        template_dirs: template_dirs.into_iter().collect(),
+        entry_flows: &meta.entry_flows,
+        imports_required: imports_required(meta),
+        events: meta.events.as_ref(),
+        repo: meta.repo.as_ref(),
+        messaging: meta.messaging.as_ref(),
+        interfaces: &meta.interfaces,
+        media: meta.media.as_ref(),
+        config: meta.config.as_ref(),
+        config_schema: meta
+            .config_schema
+            .as_ref()
+            .filter(|schema| params_schema.as_ref() != Some(*schema)),
+        config_defaults: meta.config_defaults.as_ref(),
+        lifecycle: meta.lifecycle.as_ref(),
+        quotas: meta.quotas.as_ref(),
+        limits: meta.limits.as_ref(),
+        dispatch: meta.dispatch.as_ref(),
+        qos: meta.qos.as_ref(),
+        capabilities: meta.capabilities.as_ref(),
+        secrets: meta.secrets.as_ref(),
+        metrics: meta.metrics.as_ref(),
+        schedule: meta.schedule.as_ref(),
+        classification: meta.classification.as_ref(),
+        locales: meta.locales.as_ref(),
+        annotations: &meta.annotations,
+    };
+    let yaml = serde_yaml_bw::to_string(&spec).context("failed to serialise pack.yaml")?;
+    Ok((yaml, warnings))
+}
+
+/// The `imports_required` of the source spec: as `packc build` recorded them
+/// in the annotations, which keep optional entries, or else the ids of the
+/// pack's imports.
+fn imports_required(meta: &PackMeta) -> Vec<ImportSpec> {
+    if let Some(recorded) = meta
+        .annotations
+        .get("imports_required")
+        .and_then(|value| serde_json::from_value(value.clone()).ok())
+    {
+        return recorded;
+    }
+    meta.imports
+        .iter()
+        .map(|import| {
+            import
+                .pack_id
+                .parse()
+                .unwrap_or_else(|_| ImportSpec::capability(&import.pack_id))
+                .with_optional(import.optional)
+        })
+        .collect()
+}
+
+/// Borrowed mirror of packc's `pack.yaml` schema, in the same field order.
+#[derive(Serialize)]
+struct SourceSpec<'a> {
+    #[serde(rename = "packVersion")]
+    pack_version: u32,
+    id: &'a str,
+    version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<&'a PackKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    authors: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    license: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    homepage: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    support: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vendor: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    tags: &'a [String],
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    categories: &'a [String],
+    flow_files: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    template_dirs: Vec<String>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    entry_flows: &'a [String],
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    imports_required: Vec<ImportSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    events: Option<&'a EventsSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repo: Option<&'a RepoPackSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    messaging: Option<&'a MessagingSection>,
+    #[serde(skip_serializing_if = "<[InterfaceBinding]>::is_empty")]
+    interfaces: &'a [InterfaceBinding],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    media: Option<&'a MediaSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config: Option<&'a ConfigSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config_schema: Option<&'a ConfigSchema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config_defaults: Option<&'a ConfigDefaults>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lifecycle: Option<&'a LifecycleSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quotas: Option<&'a QuotaSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limits: Option<&'a LimitsSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dispatch: Option<&'a DispatchSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    qos: Option<&'a QosSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    capabilities: Option<&'a CapabilitiesSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secrets: Option<&'a SecretsSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metrics: Option<&'a MetricsSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schedule: Option<&'a ScheduleSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    classification: Option<&'a ClassificationSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    locales: Option<&'a LocalesSection>,
+    #[serde(skip_serializing_if = "JsonMap::is_empty")]
+    annotations: &'a JsonMap<String, JsonValue>,
+}
+
+fn decode_manifest(files: &HashMap<String, Vec<u8>>) -> Result<PackManifest> {
+    let bytes = crate::reader::manifest_bytes(files)?;
+    serde_cbor::from_slice(&bytes).context("manifest.cbor is invalid")
+}
+
+fn ensure_empty_dir(dir: &Path) -> Result<()> {
+    if dir.exists() {
+        let mut entries =
+            fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+        if entries.next().is_some() {
+            bail!("output directory {} is not empty", dir.display());
+        }
+    } else {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create directory {}", dir.display()))?;
+    }
+    Ok(())
+}
+
+/// Writes `bytes` to `relative` under `root`, refusing paths that would land
+/// outside it however the archive spelled them.
+fn write_file(root: &Path, relative: &str, bytes: &[u8]) -> Result<()> {
+    if relative.is_empty()
+        || relative
+            .split('/')
+            .any(|segment| segment.is_empty() || segment == ".." || segment.contains('\\'))
+        || Path::new(relative).is_absolute()
+    {
+        bail!("refusing to extract `{relative}`: it is not a path inside the pack");
+    }
+    let path = root.join(relative);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory {}", parent.display()))?;
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("failed to resolve {}", root.display()))?;
+        let parent = parent
+            .canonicalize()
+            .with_context(|| format!("failed to resolve {}", parent.display()))?;
+        if !parent.starts_with(&root) {
+            bail!("refusing to extract `{relative}`: it resolves outside the output directory");
+        }
+    }
+    fs::write(&path, bytes).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(all(test, feature = "builder"))]
+mod tests {
+    use super::*;
+    use crate::builder::{PackBuilder, PackMeta, Provenance};
+    use greentic_flow::flow_bundle::FlowBundle;
+    use semver::Version;
+    use serde_json::json;
+    use std::io::{Read, Write};
+    use tempfile::tempdir;
+    use zip::write::SimpleFileOptions;
+    use zip::{ZipArchive, ZipWriter};
+
+    fn sample_meta() -> PackMeta {
+        PackMeta {
+            pack_version: PACK_VERSION,
+            pack_id: "ai.greentic.demo.extract".into(),
+            version: Version::parse("0.2.0").unwrap(),
+            name: "Extract Demo".into(),
+            kind: None,
+            description: Some("Round-trips through a directory".into()),
+            authors: vec!["Greentic".into()],
+            license: None,
+            homepage: None,
+            support: None,
+            vendor: None,
+            tags: vec!["weather".into()],
+            categories: Vec::new(),
+            imports: Vec::new(),
+            entry_flows: vec!["demo".into()],
+            created_at_utc: "2025-01-01T00:00:00Z".into(),
+            events: None,
+            repo: None,
+            messaging: None,
+            interfaces: Vec::new(),
+            media: None,
+            config: None,
+            config_schema: None,
+            config_defaults: None,
+            lifecycle: None,
+            quotas: None,
+            limits: None,
+            dispatch: None,
+            qos: None,
+            secrets: None,
+            capabilities: None,
+            metrics: None,
+            schedule: None,
+            classification: None,
+            locales: None,
+            data_handling: None,
+            permissions: None,
+            annotations: JsonMap::new(),
+        }
+    }
+
+    fn build_sample(dir: &Path) -> PathBuf {
+        let flow_json = json!({"id": "demo", "kind": "flow/v1", "entry": "start", "nodes": []});
+        let out = dir.join("demo.gtpack");
+        PackBuilder::new(sample_meta())
+            .with_flow(FlowBundle {
+                id: "demo".into(),
+                kind: "flow/v1".into(),
+                entry: "start".into(),
+                yaml: "id: demo\nentry: start\n".into(),
+                json: flow_json.clone(),
+                hash_blake3: blake3::hash(&serde_json::to_vec(&flow_json).unwrap())
+                    .to_hex()
+                    .to_string(),
+                nodes: Vec::new(),
+            })
+            .with_asset_bytes("templates/hello.hbs", b"Hello {{name}}".to_vec())
+            .with_readme("# Extract Demo\n")
+            .with_provenance(Provenance {
+                builder: "greentic-pack@test".into(),
+                git_commit: None,
+                git_repo: None,
+                toolchain: None,
+                built_at_utc: "2025-01-01T00:00:00Z".into(),
+                host: None,
+                notes: None,
+                profile_blake3: None,
+            })
+            .build(&out)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn extracts_source_layout_and_pack_yaml() {
+        let temp = tempdir().unwrap();
+        let archive = build_sample(temp.path());
+        let out = temp.path().join("unpacked");
+
+        let report = extract_pack(
+            &archive,
+            &out,
+            ExtractOptions {
+                verify: Some(SigningPolicy::DevOk),
+                pack_yaml: true,
+            },
+        )
+        .unwrap();
+
+        assert!(report.verified);
+        for expected in [
+            "flows/demo/flow.ygtc",
+            "templates/hello.hbs",
+            "manifest.cbor",
+            "sbom.json",
+            "README.md",
+            "signatures/pack.sig",
+            SOURCE_SPEC_PATH,
+        ] {
+            assert!(report.files.iter().any(|f| f == expected), "{expected}");
+            assert!(out.join(expected).is_file(), "{expected} written");
+        }
+        assert!(!out.join("assets").exists());
+
+        let spec: serde_yaml_bw::Value =
+            serde_yaml_bw::from_str(&fs::read_to_string(out.join(SOURCE_SPEC_PATH)).unwrap())
+                .unwrap();
+        assert_eq!(spec["id"].as_str(), Some("ai.greentic.demo.extract"));
+        assert_eq!(spec["version"].as_str(), Some("0.2.0"));
+        assert_eq!(spec["name"].as_str(), Some("Extract Demo"));
+        assert_eq!(spec["flow_files"][0].as_str(), Some("flows/demo/flow.ygtc"));
+        assert_eq!(spec["template_dirs"][0].as_str(), Some("templates"));
+        assert_eq!(spec["tags"][0].as_str(), Some("weather"));
+    }
+
+    #[test]
+    fn verification_failure_writes_nothing() {
+        let temp = tempdir().unwrap();
+        let archive = build_sample(temp.path());
+
+        // Rewrite the archive with a modified template so the SBOM no longer matches.
+        let tampered = temp.path().join("tampered.gtpack");
+        let mut source = ZipArchive::new(fs::File::open(&archive).unwrap()).unwrap();
+        let mut writer = ZipWriter::new(fs::File::create(&tampered).unwrap());
+        for idx in 0..source.len() {
+            let mut entry = source.by_index(idx).unwrap();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).unwrap();
+            if entry.name() == "assets/templates/hello.hbs" {
+                data = b"Goodbye".to_vec();
+            }
+            writer
+                .start_file(entry.name(), SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(&data).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let out = temp.path().join("unpacked");
+        let verified = ExtractOptions {
+            verify: Some(SigningPolicy::DevOk),
+            pack_yaml: false,
+        };
+        let err = extract_pack(&tampered, &out, verified).unwrap_err();
+        assert!(err.to_string().contains("hash mismatch"), "{err}");
+        assert!(!out.exists());
+
+        let report = extract_pack(&tampered, &out, ExtractOptions::default()).unwrap();
+        assert!(!report.verified);
+        assert_eq!(
+            fs::read(out.join("templates/hello.hbs")).unwrap(),
+            b"Goodbye"
+        );
+    }
+
+    #[test]
+    fn entries_escaping_the_output_are_refused() {
+        let temp = tempdir().unwrap();
+        let archive = temp.path().join("slip.gtpack");
+        let mut writer = ZipWriter::new(fs::File::create(&archive).unwrap());
+        writer
+            .start_file("..\\..\\evil", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"pwned").unwrap();
+        writer.finish().unwrap();
+
+        let out = temp.path().join("nested/unpacked");
+        assert!(extract_pack(&archive, &out, ExtractOptions::default()).is_err());
+        assert!(!temp.path().join("evil").exists());
+
+        let root = temp.path().join("root");
+        fs::create_dir(&root).unwrap();
+        for relative in ["../evil", "a/../../evil", "/evil", "a//b", "..\\evil"] {
+            assert!(write_file(&root, relative, b"x").is_err(), "{relative}");
+        }
+        assert!(!temp.path().join("evil").exists());
+        write_file(&root, "flows/demo/flow.ygtc", b"id: demo").unwrap();
+    }
+}
@@ -0,0 +1,150 @@
+//! What flow nodes do when an optional import is missing.
+//!
+//! An `imports_required` entry marked `optional` may be absent from the host.
+//! Every node that uses an optional import declares a `fallback:` next to
+//! its component, which the host applies when the import is unavailable:
+//!
+//! ```yaml
+//! nodes:
+//!   track_order:
+//!     analytics.track:
+//!       event: order_placed
+//!     fallback: skip
+//!     routing:
+//!       - to: confirm
+//!   enrich:
+//!     crm.lookup:
+//!       id: "{{in.customer}}"
+//!     fallback:
+//!       route: confirm
+//! ```
+//!
+//! `skip` passes the node's input on to its routing as if the node had run;
+//! `route` continues at another node instead. Hosts that lack such an import
+//! still load the flow and record the degradation, see
+//! [`PackLoad::downgrade`](crate::PackLoad::downgrade).
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::capabilities::FlowRequirements;
+use crate::import_spec::ImportSpec;
+
+/// Node key holding the fallback.
+pub const FALLBACK_KEY: &str = "fallback";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeFallback {
+    /// Continue with the node's routing, passing its input through.
+    Skip,
+    /// Continue at the named node.
+    Route(String),
+}
+
+/// Fallbacks by node id.
+pub type NodeFallbacks = BTreeMap<String, NodeFallback>;
+
+/// The `fallback:` declarations of a flow's nodes.
+pub fn node_fallbacks(flow_yaml: &str) -> Result<NodeFallbacks> {
+    let source: JsonValue =
+        serde_yaml_bw::from_str(flow_yaml).context("flow source is not valid YAML")?;
+    let mut fallbacks = NodeFallbacks::new();
+    let Some(nodes) = source.get("nodes").and_then(JsonValue::as_object) else {
+        return Ok(fallbacks);
+    };
+    for (node, body) in nodes {
+        let Some(fallback) = body.get(FALLBACK_KEY) else {
+            continue;
+        };
+        let fallback: NodeFallback =
+            serde_json::from_value(fallback.clone()).with_context(|| {
+                format!("node `{node}`: `{FALLBACK_KEY}` must be `skip` or `route: <node>`")
+            })?;
+        if let NodeFallback::Route(target) = &fallback
+            && (target == node || !nodes.contains_key(target))
+        {
+            bail!("node `{node}`: fallback route `{target}` is not another node of the flow");
+        }
+        fallbacks.insert(node.clone(), fallback);
+    }
+    Ok(fallbacks)
+}
+
+/// Checks that every node of the flow using an optional import among
+/// `imports` declares a fallback.
+pub fn check_fallbacks(
+    requirements: &FlowRequirements,
+    fallbacks: &NodeFallbacks,
+    imports: &[ImportSpec],
+) -> Result<()> {
+    let optional: Vec<String> = imports
+        .iter()
+        .filter(|import| import.optional)
+        .map(ToString::to_string)
+        .collect();
+    for (node, capabilities) in &requirements.nodes {
+        if fallbacks.contains_key(node) {
+            continue;
+        }
+        if let Some(import) = capabilities.iter().find(|cap| optional.contains(cap)) {
+            bail!(
+                "node `{node}` uses optional import `{import}` and needs a `{FALLBACK_KEY}` for hosts without it"
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capabilities::flow_requirements;
+
+    const FLOW: &str = r#"
+id: checkout
+type: messaging
+nodes:
+  track:
+    analytics.track:
+      event: order_placed
+    fallback: skip
+    routing:
+      - to: confirm
+  enrich:
+    crm.lookup:
+      id: "{{in.customer}}"
+    fallback:
+      route: confirm
+  confirm:
+    flow.return:
+      payload: done
+"#;
+
+    #[test]
+    fn reads_fallbacks_and_requires_them_on_optional_imports() {
+        let fallbacks = node_fallbacks(FLOW).unwrap();
+        assert_eq!(fallbacks["track"], NodeFallback::Skip);
+        assert_eq!(fallbacks["enrich"], NodeFallback::Route("confirm".into()));
+
+        let imports = vec![
+            ImportSpec::capability("analytics.track").with_optional(true),
+            ImportSpec::capability("crm.lookup"),
+        ];
+        let requirements = flow_requirements(FLOW, &imports).unwrap();
+        check_fallbacks(&requirements, &fallbacks, &imports).unwrap();
+
+        let mut partial = fallbacks.clone();
+        partial.remove("track");
+        let err = check_fallbacks(&requirements, &partial, &imports).unwrap_err();
+        assert!(err.to_string().contains("node `track`"), "{err}");
+
+        let dangling = FLOW.replace("route: confirm", "route: nowhere");
+        assert!(node_fallbacks(&dangling).is_err());
+        let unknown = FLOW.replace("fallback: skip", "fallback: retry");
+        assert!(node_fallbacks(&unknown).is_err());
+    }
+}
@@ -0,0 +1,326 @@
+//! Input and output JSON Schemas of a flow, derived from its source.
+//!
+//! Flows read their input through `in.<field>` references in node payloads,
+//! either as a bare path (`model: in.model`) or inside Handlebars expressions
+//! (`"{{in.question}}"`). Every referenced field becomes a property of the
+//! input schema. A field is required unless each reference to it sits inside
+//! an `{{#if}}` or `{{#unless}}` block; `{{#each in.items}}` marks it an array.
+//! A flow that references no input accepts any value.
+//!
+//! The output schema describes the `payload` of the flow's `flow.return`
+//! nodes: literal values become `const`s, templated strings plain strings,
+//! and objects require every key they list. Several return nodes give an
+//! `anyOf`; a flow without one may return anything.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{Map as JsonMap, Value as JsonValue, json};
+use serde_yaml_bw::Value as YamlValue;
+
+use crate::fallbacks::FALLBACK_KEY;
+use crate::node_docs::DOC_KEY;
+
+/// `$schema` of the derived schemas.
+pub const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+/// Component whose `payload` is the flow's output.
+pub const RETURN_COMPONENT: &str = "flow.return";
+/// Root of the references to the flow input.
+const INPUT_ROOT: &str = "in";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FlowSchemas {
+    pub input: JsonValue,
+    pub output: JsonValue,
+}
+
+/// The input and output schemas of the flow in `flow_yaml`.
+pub fn flow_schemas(flow_yaml: &str) -> Result<FlowSchemas> {
+    let source: YamlValue =
+        serde_yaml_bw::from_str(flow_yaml).context("flow source is not valid YAML")?;
+    let mut input = Field::default();
+    let mut outputs: Vec<JsonValue> = Vec::new();
+    if let Some(nodes) = source.get("nodes").and_then(YamlValue::as_mapping) {
+        for (_, body) in nodes {
+            let Some(body) = body.as_mapping() else {
+                continue;
+            };
+            for (key, payload) in body {
+                let key = key.as_str().unwrap_or_default();
+                if key == "routing" || key == DOC_KEY || key == FALLBACK_KEY {
+                    continue;
+                }
+                let payload = serde_json::to_value(payload)
+                    .with_context(|| format!("`{key}` payload is not valid JSON"))?;
+                collect_references(&payload, &mut input);
+                if key == RETURN_COMPONENT {
+                    let schema = payload
+                        .get("payload")
+                        .map(literal_schema)
+                        .unwrap_or_else(|| json!({}));
+                    if !outputs.contains(&schema) {
+                        outputs.push(schema);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut input = match input.children {
+        Some(children) if !children.is_empty() => object_schema(&children),
+        _ => JsonValue::Object(JsonMap::new()),
+    };
+    let mut output = match outputs.len() {
+        0 => JsonValue::Object(JsonMap::new()),
+        1 => outputs.remove(0),
+        _ => json!({ "anyOf": outputs }),
+    };
+    for schema in [&mut input, &mut output] {
+        if let JsonValue::Object(map) = schema {
+            map.insert("$schema".into(), SCHEMA_DIALECT.into());
+        }
+    }
+    Ok(FlowSchemas { input, output })
+}
+
+/// What the references say about one input field.
+#[derive(Debug, Default)]
+struct Field {
+    required: bool,
+    array: bool,
+    children: Option<BTreeMap<String, Field>>,
+}
+
+impl Field {
+    fn add(&mut self, path: &[&str], required: bool, array: bool) {
+        let Some((first, rest)) = path.split_first() else {
+            return;
+        };
+        let field = self
+            .children
+            .get_or_insert_with(BTreeMap::new)
+            .entry((*first).to_string())
+            .or_default();
+        field.required |= required;
+        if rest.is_empty() {
+            field.array |= array;
+        } else {
+            field.add(rest, required, array);
+        }
+    }
+
+    fn schema(&self) -> JsonValue {
+        match (&self.children, self.array) {
+            (Some(children), false) => object_schema(children),
+            (None, true) => json!({ "type": "array" }),
+            _ => json!({}),
+        }
+    }
+}
+
+fn object_schema(children: &BTreeMap<String, Field>) -> JsonValue {
+    let properties: JsonMap<String, JsonValue> = children
+        .iter()
+        .map(|(name, field)| (name.clone(), field.schema()))
+        .collect();
+    let required: Vec<&String> = children
+        .iter()
+        .filter(|(_, field)| field.required)
+        .map(|(name, _)| name)
+        .collect();
+    let mut schema = json!({ "type": "object", "properties": properties });
+    if !required.is_empty() {
+        schema["required"] = json!(required);
+    }
+    schema
+}
+
+fn collect_references(value: &JsonValue, input: &mut Field) {
+    match value {
+        JsonValue::String(text) => collect_from_text(text, input),
+        JsonValue::Array(items) => {
+            for item in items {
+                collect_references(item, input);
+            }
+        }
+        JsonValue::Object(map) => {
+            for value in map.values() {
+                collect_references(value, input);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_from_text(text: &str, input: &mut Field) {
+    if let Some(path) = input_path(text.trim()) {
+        input.add(&path, true, false);
+        return;
+    }
+    // Open `#if`/`#unless` blocks, and other blocks, innermost last.
+    let mut blocks: Vec<bool> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let expression = rest[start + 2..start + len]
+            .trim_matches(|c: char| c == '{' || c == '}' || c == '~' || c == '&')
+            .trim();
+        rest = &rest[start + len + 2..];
+
+        if expression.starts_with('/') {
+            blocks.pop();
+            continue;
+        }
+        let conditional = blocks.iter().any(|conditional| *conditional);
+        let (helper, args) = match expression.strip_prefix('#') {
+            Some(block) => {
+                let helper = block.split_whitespace().next().unwrap_or_default();
+                let opens_condition = matches!(helper, "if" | "unless");
+                blocks.push(opens_condition);
+                (Some(helper), &block[helper.len()..])
+            }
+            None => (None, expression),
+        };
+        let required = !conditional && !matches!(helper, Some("if" | "unless"));
+        let array = helper == Some("each");
+        for token in args.split(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+            let token = token.split_once('=').map_or(token, |(_, value)| value);
+            if let Some(path) = input_path(token) {
+                input.add(&path, required, array);
+            }
+        }
+    }
+}
+
+/// The field path of an `in.<field>...` reference, up to the first segment
+/// that is not a plain name (an index, for example).
+fn input_path(token: &str) -> Option<Vec<&str>> {
+    let mut segments = token.split('.');
+    if segments.next() != Some(INPUT_ROOT) {
+        return None;
+    }
+    let path: Vec<&str> = segments.take_while(|segment| is_name(segment)).collect();
+    (!path.is_empty()).then_some(path)
+}
+
+pub(crate) fn is_name(segment: &str) -> bool {
+    let mut chars = segment.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn literal_schema(value: &JsonValue) -> JsonValue {
+    match value {
+        JsonValue::String(text) if text.contains("{{") => json!({ "type": "string" }),
+        JsonValue::Array(items) => {
+            let mut schemas: Vec<JsonValue> = Vec::new();
+            for item in items {
+                let schema = literal_schema(item);
+                if !schemas.contains(&schema) {
+                    schemas.push(schema);
+                }
+            }
+            match schemas.len() {
+                0 => json!({ "type": "array" }),
+                1 => json!({ "type": "array", "items": schemas.remove(0) }),
+                _ => json!({ "type": "array", "items": { "anyOf": schemas } }),
+            }
+        }
+        JsonValue::Object(map) => {
+            let properties: JsonMap<String, JsonValue> = map
+                .iter()
+                .map(|(key, value)| (key.clone(), literal_schema(value)))
+                .collect();
+            let required: Vec<&String> = map.keys().collect();
+            json!({ "type": "object", "properties": properties, "required": required })
+        }
+        constant => json!({ "const": constant }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLOW: &str = r#"
+id: answer
+type: messaging
+nodes:
+  call_llm:
+    doc: Uses in.ignored only in its doc.
+    llm.openai.chat:
+      model: in.model
+      messages:
+        - role: system
+          content: |
+            {{#if in.profile}}
+            Profile: {{in.profile.name}}
+            {{else}}
+            (none supplied)
+            {{/if}}
+        - role: user
+          content: "{{in.question}} {{#each in.sources}}{{this}}{{/each}}"
+    routing:
+      - to: reply
+  reply:
+    flow.return:
+      payload:
+        - type: message
+          text: "{{call_llm.payload.answer}}"
+          final: true
+    routing:
+      - out: true
+"#;
+
+    #[test]
+    fn derives_input_and_output_schemas() {
+        let schemas = flow_schemas(FLOW).unwrap();
+        assert_eq!(
+            schemas.input,
+            json!({
+                "$schema": SCHEMA_DIALECT,
+                "type": "object",
+                "properties": {
+                    "model": {},
+                    "profile": {
+                        "type": "object",
+                        "properties": { "name": {} }
+                    },
+                    "question": {},
+                    "sources": { "type": "array" }
+                },
+                "required": ["model", "question", "sources"]
+            })
+        );
+        assert_eq!(
+            schemas.output,
+            json!({
+                "$schema": SCHEMA_DIALECT,
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "final": { "const": true },
+                        "text": { "type": "string" },
+                        "type": { "const": "message" }
+                    },
+                    "required": ["final", "text", "type"]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn flows_without_references_accept_anything() {
+        let schemas =
+            flow_schemas("id: ping\ntype: messaging\nnodes:\n  start:\n    flow.noop: {}\n")
+                .unwrap();
+        assert_eq!(schemas.input, json!({ "$schema": SCHEMA_DIALECT }));
+        assert_eq!(schemas.output, json!({ "$schema": SCHEMA_DIALECT }));
+    }
+}
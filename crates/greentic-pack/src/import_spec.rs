@@ -0,0 +1,293 @@
+//! Typed `imports_required` entries.
+//!
+//! An entry names a WIT interface the pack needs from its host, as
+//! `namespace:package/interface[@version]` (`greentic:host-import/secrets@0.6`),
+//! or is an older dotted capability name such as `secrets.get`. `pack.yaml`
+//! may write it in that string form or as a map:
+//!
+//! ```yaml
+//! imports_required:
+//!   - secrets.get
+//!   - greentic:host-import/secrets@0.6
+//!   - package: greentic:host-import
+//!     interface: http
+//!     version_req: "0.6"
+//!     optional: true
+//! ```
+//!
+//! Entries serialise back to the string form unless they are optional, so
+//! manifests written before the map form existed read the same.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Context, Result, anyhow, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use semver::VersionReq;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "ImportSpecRepr", into = "ImportSpecRepr")]
+pub struct ImportSpec {
+    /// WIT package, e.g. `greentic:host-import`; `None` for dotted capability
+    /// names.
+    pub package: Option<String>,
+    /// WIT interface, e.g. `secrets`, or the whole dotted capability name.
+    pub interface: String,
+    /// Semver requirement on the package, as written (`0.6` means `^0.6`).
+    pub version_req: Option<String>,
+    /// The pack still runs when the host lacks the import.
+    pub optional: bool,
+}
+
+impl ImportSpec {
+    /// An import of `interface` from the WIT `package`.
+    pub fn wit(package: impl Into<String>, interface: impl Into<String>) -> Self {
+        Self {
+            package: Some(package.into()),
+            interface: interface.into(),
+            version_req: None,
+            optional: false,
+        }
+    }
+
+    /// A dotted capability name such as `secrets.get`.
+    pub fn capability(name: impl Into<String>) -> Self {
+        Self {
+            package: None,
+            interface: name.into(),
+            version_req: None,
+            optional: false,
+        }
+    }
+
+    pub fn with_version_req(mut self, version_req: impl Into<String>) -> Self {
+        self.version_req = Some(version_req.into());
+        self
+    }
+
+    pub fn with_optional(mut self, optional: bool) -> Self {
+        self.optional = optional;
+        self
+    }
+
+    /// Whether the entry names a WIT interface rather than a dotted
+    /// capability name.
+    pub fn is_wit(&self) -> bool {
+        self.package.is_some()
+    }
+
+    /// The parsed version requirement; `None` when the entry has none.
+    pub fn requirement(&self) -> Result<Option<VersionReq>> {
+        self.version_req
+            .as_deref()
+            .map(|version| {
+                VersionReq::parse(version).with_context(|| {
+                    format!("import `{self}` has an invalid version requirement `{version}`")
+                })
+            })
+            .transpose()
+    }
+
+    /// What flows refer to the import by: `secrets` for `secrets.get`, `mcp`
+    /// for `greentic:host-import/mcp@0.6`.
+    pub fn root(&self) -> &str {
+        if self.is_wit() {
+            &self.interface
+        } else {
+            self.interface.split('.').next().unwrap_or(&self.interface)
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let Some(package) = &self.package else {
+            if self.interface.trim().is_empty() {
+                bail!("imports_required entries must not be empty");
+            }
+            if self.interface.contains([':', '/', '@']) {
+                bail!("import `{self}` must name a package to refer to a WIT interface");
+            }
+            if self.version_req.is_some() {
+                bail!("import `{self}` has a version requirement but no package");
+            }
+            return Ok(());
+        };
+        match package.split_once(':') {
+            Some((namespace, name)) if valid_name(namespace) && valid_name(name) => {}
+            _ => bail!("import `{self}` has an invalid package name `{package}`"),
+        }
+        if !valid_name(&self.interface) {
+            bail!(
+                "import `{self}` has an invalid interface name `{}`",
+                self.interface
+            );
+        }
+        self.requirement()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for ImportSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(package) = &self.package {
+            write!(f, "{package}/")?;
+        }
+        f.write_str(&self.interface)?;
+        if let Some(version) = &self.version_req {
+            write!(f, "@{version}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ImportSpec {
+    type Err = anyhow::Error;
+
+    /// Parses the string form; it cannot mark an import optional.
+    fn from_str(import: &str) -> Result<Self> {
+        if !import.contains(':') {
+            let spec = Self::capability(import);
+            spec.validate()?;
+            return Ok(spec);
+        }
+        let (path, version_req) = match import.split_once('@') {
+            Some((path, version)) => (path, Some(version.to_string())),
+            None => (import, None),
+        };
+        let (package, interface) = path.split_once('/').ok_or_else(|| {
+            anyhow!("import `{import}` must name an interface (`namespace:package/interface`)")
+        })?;
+        let spec = Self {
+            package: Some(package.to_string()),
+            interface: interface.to_string(),
+            version_req,
+            optional: false,
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+}
+
+/// WIT identifiers: lowercase letters, digits, and `-`.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// How an [`ImportSpec`] is written.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(untagged)]
+enum ImportSpecRepr {
+    /// `namespace:package/interface[@version]`, or a dotted capability name.
+    Text(String),
+    Fields(ImportSpecFields),
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+struct ImportSpecFields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    package: Option<String>,
+    interface: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version_req: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    optional: bool,
+}
+
+impl TryFrom<ImportSpecRepr> for ImportSpec {
+    type Error = anyhow::Error;
+
+    fn try_from(repr: ImportSpecRepr) -> Result<Self> {
+        match repr {
+            ImportSpecRepr::Text(text) => text.parse(),
+            ImportSpecRepr::Fields(fields) => {
+                let spec = Self {
+                    package: fields.package,
+                    interface: fields.interface,
+                    version_req: fields.version_req,
+                    optional: fields.optional,
+                };
+                spec.validate()?;
+                Ok(spec)
+            }
+        }
+    }
+}
+
+impl From<ImportSpec> for ImportSpecRepr {
+    fn from(spec: ImportSpec) -> Self {
+        if !spec.optional {
+            return Self::Text(spec.to_string());
+        }
+        Self::Fields(ImportSpecFields {
+            package: spec.package,
+            interface: spec.interface,
+            version_req: spec.version_req,
+            optional: spec.optional,
+        })
+    }
+}
+
+#[cfg(feature = "schema")]
+impl JsonSchema for ImportSpec {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "ImportSpec".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        ImportSpecRepr::json_schema(generator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_both_forms_and_writes_strings_back() {
+        let imports: Vec<ImportSpec> = serde_yaml_bw::from_str(
+            "- secrets.get\n- greentic:host-import/mcp@0.6\n- package: greentic:host-import\n  interface: http\n  optional: true\n",
+        )
+        .unwrap();
+        assert_eq!(imports[0], ImportSpec::capability("secrets.get"));
+        assert_eq!(
+            imports[1],
+            ImportSpec::wit("greentic:host-import", "mcp").with_version_req("0.6")
+        );
+        assert!(imports[2].optional);
+        let roots: Vec<&str> = imports.iter().map(ImportSpec::root).collect();
+        assert_eq!(roots, ["secrets", "mcp", "http"]);
+
+        assert_eq!(
+            serde_json::to_value(&imports).unwrap(),
+            serde_json::json!([
+                "secrets.get",
+                "greentic:host-import/mcp@0.6",
+                { "package": "greentic:host-import", "interface": "http", "optional": true }
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_entries() {
+        for (import, message) in [
+            ("greentic:host-import", "must name an interface"),
+            ("greentic/host-import/mcp", "must name a package"),
+            ("greentic:Host/mcp", "invalid package name"),
+            (
+                "greentic:host-import/mcp@banana",
+                "invalid version requirement",
+            ),
+            ("", "must not be empty"),
+        ] {
+            let err = import.parse::<ImportSpec>().unwrap_err();
+            assert!(err.to_string().contains(message), "{import}: {err}");
+        }
+    }
+}
@@ -1,10 +1,49 @@
 #![forbid(unsafe_code)]
 
+pub mod atomic;
 pub mod builder;
+pub mod capabilities;
+pub mod cassette;
+pub mod chunking;
+pub mod classification;
+#[cfg(feature = "reader")]
+pub mod closure;
+pub mod config;
+pub mod config_schema;
+pub mod dispatch;
 pub mod events;
+#[cfg(feature = "reader")]
+pub mod extract;
+pub mod fallbacks;
+pub mod flow_schema;
+pub mod import_spec;
+pub mod lifecycle;
+pub mod limits;
+pub mod locales;
+pub mod media;
 pub mod messaging;
+pub mod metrics;
+pub mod node_docs;
+pub mod permissions;
+#[cfg(feature = "plan")]
 pub mod plan;
+pub mod qos;
+pub mod quotas;
+#[cfg(feature = "reader")]
 pub mod reader;
+pub mod readme;
+#[cfg(feature = "reader")]
+pub mod registry;
+#[cfg(feature = "render")]
+pub mod render;
 pub mod repo;
+pub mod schedule;
+pub mod secrets;
+#[cfg(feature = "reader")]
+pub mod store;
+#[cfg(feature = "reader")]
+pub mod stream;
+pub mod trace;
 
+#[cfg(feature = "reader")]
 pub use reader::*;
@@ -0,0 +1,45 @@
+//! Lifecycle hooks hosts run for an installed pack.
+//!
+//! `lifecycle.health` names a flow hosts execute periodically to check that
+//! the pack and the services it depends on still work. The flow should be
+//! cheap: no side effects, and quick enough to run every minute or so.
+
+use anyhow::{Result, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct LifecycleSection {
+    /// Flow hosts run to check the pack's health.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<String>,
+}
+
+impl LifecycleSection {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(flow) = &self.health
+            && (flow.trim().is_empty() || flow.chars().any(char::is_whitespace))
+        {
+            bail!("lifecycle.health must be a flow id without whitespace");
+        }
+        Ok(())
+    }
+
+    /// Checks that the hook flows are among `flows`, the ids of the pack's flows.
+    pub fn check_flows<'a>(&self, flows: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        let Some(health) = &self.health else {
+            return Ok(());
+        };
+        let flows: Vec<&str> = flows.into_iter().collect();
+        if !flows.contains(&health.as_str()) {
+            bail!(
+                "lifecycle.health refers to unknown flow `{health}`; known flows: {}",
+                flows.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+//! Execution limits of flows.
+//!
+//! Flows declare the budget a single run may use, so local runs can be held
+//! to the same constraints hosts apply in production:
+//!
+//! ```yaml
+//! limits:
+//!   weather_bot:
+//!     timeout_ms: 5000
+//!     max_output_bytes: 65536
+//!     fuel: 50000000
+//!     memory_mb: 64
+//!     drain_ms: 3000
+//! ```
+//!
+//! A run that goes over budget fails with a [`LimitExceeded`], whose
+//! [`code`](LimitExceeded::code) is the error code hosts report.
+//!
+//! `drain_ms` bounds how long a host rolling its instances waits for a run to
+//! reach a safe point and checkpoint once the pack is asked to drain; it may
+//! not exceed the run's timeout or [`MAX_DRAIN_MS`].
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Longest drain a flow may declare; hosts replacing an instance cannot wait
+/// much longer before it is killed.
+pub const MAX_DRAIN_MS: u64 = 300_000;
+
+/// Limits by flow id.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(transparent)]
+pub struct LimitsSection {
+    pub flows: BTreeMap<String, FlowLimits>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct FlowLimits {
+    /// Wall-clock time a run may take.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Size of the serialised output a run may return.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_bytes: Option<u64>,
+    /// Wasmtime fuel the component may consume during a run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fuel: Option<u64>,
+    /// Linear memory the component may grow to, in MiB.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_mb: Option<u32>,
+    /// Time a run has to checkpoint after the pack is asked to drain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drain_ms: Option<u64>,
+}
+
+impl LimitsSection {
+    pub fn validate(&self) -> Result<()> {
+        for (flow, limits) in &self.flows {
+            if flow.trim().is_empty() {
+                bail!("limits must be keyed by flow id");
+            }
+            if *limits == FlowLimits::default() {
+                bail!("limits.{flow} must set at least one limit");
+            }
+            let fields = [
+                ("timeout_ms", limits.timeout_ms),
+                ("max_output_bytes", limits.max_output_bytes),
+                ("fuel", limits.fuel),
+                ("memory_mb", limits.memory_mb.map(u64::from)),
+                ("drain_ms", limits.drain_ms),
+            ];
+            for (field, value) in fields {
+                if value == Some(0) {
+                    bail!("limits.{flow}.{field} must be greater than 0");
+                }
+            }
+            if let Some(drain_ms) = limits.drain_ms {
+                if drain_ms > MAX_DRAIN_MS {
+                    bail!("limits.{flow}.drain_ms must be at most {MAX_DRAIN_MS}");
+                }
+                if limits
+                    .timeout_ms
+                    .is_some_and(|timeout_ms| drain_ms > timeout_ms)
+                {
+                    bail!("limits.{flow}.drain_ms must not exceed its timeout_ms");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every entry names one of `flows`, the pack's flow ids.
+    pub fn check_flows<'a>(&self, flows: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        let flows: Vec<&str> = flows.into_iter().collect();
+        if let Some(flow) = self
+            .flows
+            .keys()
+            .find(|flow| !flows.contains(&flow.as_str()))
+        {
+            bail!("limits refer to unknown flow `{flow}`");
+        }
+        Ok(())
+    }
+
+    /// The limits of `flow`; unlimited when it declares none.
+    pub fn flow(&self, flow: &str) -> FlowLimits {
+        self.flows.get(flow).copied().unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flows.is_empty()
+    }
+}
+
+impl FlowLimits {
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_ms.map(Duration::from_millis)
+    }
+
+    pub fn drain(&self) -> Option<Duration> {
+        self.drain_ms.map(Duration::from_millis)
+    }
+
+    pub fn memory_bytes(&self) -> Option<u64> {
+        self.memory_mb.map(|mb| u64::from(mb) * 1024 * 1024)
+    }
+
+    /// Fails once a run has taken longer than `timeout_ms`.
+    pub fn check_elapsed(&self, elapsed: Duration) -> Result<(), LimitExceeded> {
+        match self.timeout_ms {
+            Some(limit_ms) if elapsed > Duration::from_millis(limit_ms) => {
+                Err(LimitExceeded::Timeout {
+                    limit_ms,
+                    elapsed_ms: elapsed.as_millis().try_into().unwrap_or(u64::MAX),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails once a draining run has gone longer than `drain_ms` without
+    /// checkpointing; `elapsed` counts from the drain request.
+    pub fn check_drain(&self, elapsed: Duration) -> Result<(), LimitExceeded> {
+        match self.drain_ms {
+            Some(limit_ms) if elapsed > Duration::from_millis(limit_ms) => {
+                Err(LimitExceeded::DrainTimeout {
+                    limit_ms,
+                    elapsed_ms: elapsed.as_millis().try_into().unwrap_or(u64::MAX),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails when a serialised output of `bytes` is over `max_output_bytes`.
+    pub fn check_output(&self, bytes: u64) -> Result<(), LimitExceeded> {
+        match self.max_output_bytes {
+            Some(limit) if bytes > limit => Err(LimitExceeded::OutputTooLarge { limit, bytes }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A run that went over one of its [`FlowLimits`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum LimitExceeded {
+    Timeout { limit_ms: u64, elapsed_ms: u64 },
+    OutputTooLarge { limit: u64, bytes: u64 },
+    OutOfFuel { limit: u64 },
+    MemoryExceeded { limit_bytes: u64 },
+    DrainTimeout { limit_ms: u64, elapsed_ms: u64 },
+}
+
+impl LimitExceeded {
+    /// Stable error code, as hosts report it.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LimitExceeded::Timeout { .. } => "timeout",
+            LimitExceeded::OutputTooLarge { .. } => "output_too_large",
+            LimitExceeded::OutOfFuel { .. } => "out_of_fuel",
+            LimitExceeded::MemoryExceeded { .. } => "memory_exceeded",
+            LimitExceeded::DrainTimeout { .. } => "drain_timeout",
+        }
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitExceeded::Timeout {
+                limit_ms,
+                elapsed_ms,
+            } => write!(
+                f,
+                "flow ran for {elapsed_ms} ms, over its {limit_ms} ms timeout"
+            ),
+            LimitExceeded::OutputTooLarge { limit, bytes } => {
+                write!(
+                    f,
+                    "flow output is {bytes} bytes, over its {limit} byte limit"
+                )
+            }
+            LimitExceeded::OutOfFuel { limit } => {
+                write!(f, "flow used up its fuel budget of {limit}")
+            }
+            LimitExceeded::MemoryExceeded { limit_bytes } => {
+                write!(f, "flow tried to grow memory past {limit_bytes} bytes")
+            }
+            LimitExceeded::DrainTimeout {
+                limit_ms,
+                elapsed_ms,
+            } => write!(
+                f,
+                "flow did not checkpoint within {elapsed_ms} ms of the drain, over its {limit_ms} ms limit"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_limits_and_reports_typed_violations() {
+        let section: LimitsSection = serde_yaml_bw::from_str(
+            "weather_bot:\n  timeout_ms: 5000\n  max_output_bytes: 1024\n  memory_mb: 64\n",
+        )
+        .unwrap();
+        section.validate().unwrap();
+        section.check_flows(["weather_bot"]).unwrap();
+        assert!(section.check_flows(["other"]).is_err());
+
+        let limits = section.flow("weather_bot");
+        assert_eq!(limits.memory_bytes(), Some(64 * 1024 * 1024));
+        limits.check_output(1024).unwrap();
+        let err = limits.check_output(1025).unwrap_err();
+        assert_eq!(err.code(), "output_too_large");
+        let err = limits
+            .check_elapsed(Duration::from_millis(5001))
+            .unwrap_err();
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({ "code": "timeout", "limit_ms": 5000, "elapsed_ms": 5001 })
+        );
+        assert_eq!(section.flow("other"), FlowLimits::default());
+
+        let empty: LimitsSection = serde_yaml_bw::from_str("weather_bot: {}\n").unwrap();
+        assert!(empty.validate().is_err());
+        let zero: LimitsSection = serde_yaml_bw::from_str("weather_bot: { fuel: 0 }\n").unwrap();
+        assert!(zero.validate().is_err());
+
+        let drain: LimitsSection =
+            serde_yaml_bw::from_str("weather_bot: { timeout_ms: 5000, drain_ms: 3000 }\n").unwrap();
+        drain.validate().unwrap();
+        let limits = drain.flow("weather_bot");
+        limits.check_drain(Duration::from_millis(3000)).unwrap();
+        let err = limits.check_drain(Duration::from_millis(3001)).unwrap_err();
+        assert_eq!(err.code(), "drain_timeout");
+        for yaml in [
+            "weather_bot: { timeout_ms: 1000, drain_ms: 3000 }\n",
+            "weather_bot: { drain_ms: 300001 }\n",
+        ] {
+            let section: LimitsSection = serde_yaml_bw::from_str(yaml).unwrap();
+            assert!(section.validate().is_err(), "{yaml}");
+        }
+    }
+}
@@ -0,0 +1,323 @@
+//! Locale variants of templates.
+//!
+//! A pack declares the locales its templates come in; the base template
+//! (`templates/welcome.hbs`) is in the default locale:
+//!
+//! ```yaml
+//! locales:
+//!   default: en
+//!   supported: [de, fr, pt-BR]
+//!   fallbacks:
+//!     pt-BR: en
+//!   templates:
+//!     templates/welcome.hbs:
+//!       fr: templates/fr/bienvenue.hbs
+//! ```
+//!
+//! A variant is named after its base with the locale before the extension
+//! (`templates/welcome.de.hbs`), or listed under `templates`. Every template
+//! needs a variant in each supported locale, or that locale needs a fallback;
+//! [`LocalesSection::with_variants`] checks this at build time and records
+//! every variant, so hosts pick one with [`LocalesSection::variant`].
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::permissions::{DEFAULT_LOCALE, validate_locale};
+
+/// Extensions of the template files that need a variant per locale; other
+/// assets, such as images, are shared by every locale.
+pub const LOCALIZED_EXTENSIONS: &[&str] = &[
+    "hbs",
+    "handlebars",
+    "liquid",
+    "mustache",
+    "html",
+    "htm",
+    "txt",
+];
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct LocalesSection {
+    /// Locale of the base templates.
+    #[serde(default = "default_locale")]
+    pub default: String,
+    /// Other locales the templates are available in.
+    pub supported: Vec<String>,
+    /// Locale to use instead, by locale, when a template has no variant.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fallbacks: BTreeMap<String, String>,
+    /// Variants by base template and locale. The build adds the variants
+    /// named with a locale suffix.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub templates: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+fn default_locale() -> String {
+    DEFAULT_LOCALE.to_string()
+}
+
+impl LocalesSection {
+    pub fn validate(&self) -> Result<()> {
+        validate_locale(&self.default).map_err(|err| anyhow::anyhow!("locales.default: {err}"))?;
+        let mut seen = vec![normalize(&self.default)];
+        for locale in &self.supported {
+            validate_locale(locale).map_err(|err| anyhow::anyhow!("locales.supported: {err}"))?;
+            if seen.contains(&normalize(locale)) {
+                bail!("locales.supported lists `{locale}` twice or as the default");
+            }
+            seen.push(normalize(locale));
+        }
+        for (locale, fallback) in &self.fallbacks {
+            if self.supported_locale(locale).is_none() {
+                bail!("locales.fallbacks: `{locale}` is not a supported locale");
+            }
+            if self.known_locale(fallback).is_none() {
+                bail!("locales.fallbacks.{locale}: `{fallback}` is not a declared locale");
+            }
+            let mut current = locale.as_str();
+            for _ in 0..=self.fallbacks.len() {
+                match self.fallback(current) {
+                    Some(next) => current = next,
+                    None => break,
+                }
+            }
+            if self.fallback(current).is_some() {
+                bail!("locales.fallbacks: `{locale}` falls back in a cycle");
+            }
+        }
+        for (template, variants) in &self.templates {
+            if template.trim().is_empty() {
+                bail!("locales.templates must be keyed by template path");
+            }
+            for (locale, variant) in variants {
+                if self.supported_locale(locale).is_none() {
+                    bail!("locales.templates.{template}: `{locale}` is not a supported locale");
+                }
+                if variant.trim().is_empty() || variant == template {
+                    bail!("locales.templates.{template}.{locale} must name another template");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// This section with every variant among `templates` (the pack's
+    /// template paths) recorded under `templates`.
+    ///
+    /// Fails when a variant has no base template, a listed template is
+    /// missing, or a template has neither a variant nor a fallback for a
+    /// supported locale.
+    pub fn with_variants<'a>(&self, templates: impl IntoIterator<Item = &'a str>) -> Result<Self> {
+        let templates: Vec<&str> = templates.into_iter().collect();
+        let mut resolved = self.clone();
+        for (template, variants) in &self.templates {
+            for path in std::iter::once(template).chain(variants.values()) {
+                if !templates.contains(&path.as_str()) {
+                    bail!("locales.templates refers to unknown template `{path}`");
+                }
+            }
+        }
+
+        let mut variant_paths: Vec<&str> = self
+            .templates
+            .values()
+            .flat_map(|variants| variants.values().map(String::as_str))
+            .collect();
+        for path in &templates {
+            let Some((base, locale)) = self.split_variant(path) else {
+                continue;
+            };
+            if !templates.contains(&base.as_str()) {
+                bail!("template variant `{path}` has no base template `{base}`");
+            }
+            let variants = resolved.templates.entry(base.clone()).or_default();
+            match variants.get(locale) {
+                Some(listed) if listed != path => {
+                    bail!("`{base}` has two {locale} variants: `{listed}` and `{path}`")
+                }
+                _ => {
+                    variants.insert(locale.to_string(), path.to_string());
+                }
+            }
+            variant_paths.push(*path);
+        }
+
+        for path in &templates {
+            if variant_paths.contains(path) || !is_localized(path) {
+                continue;
+            }
+            let variants = resolved.templates.get(*path);
+            for locale in &self.supported {
+                let covered = variants.is_some_and(|variants| variants.contains_key(locale));
+                if !covered && !self.fallbacks.contains_key(locale) {
+                    bail!(
+                        "template `{path}` has no {locale} variant; add `{}` or a fallback for {locale} under locales.fallbacks",
+                        suffixed(path, locale)
+                    );
+                }
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// The template to render for `template` given `locales`, the caller's
+    /// preferred BCP 47 tags in order.
+    ///
+    /// Each tag is tried with its shorter prefixes (`de-CH`, then `de`) and
+    /// the fallbacks of the locales they match; the base template is used
+    /// once the default locale is reached or nothing matches.
+    pub fn variant<'a>(&'a self, template: &'a str, locales: &[&str]) -> &'a str {
+        let variants = self.templates.get(template);
+        for locale in self.candidates(locales) {
+            if locale == self.default.as_str() {
+                return template;
+            }
+            if let Some(variant) = variants.and_then(|variants| variants.get(locale)) {
+                return variant;
+            }
+        }
+        template
+    }
+
+    /// Declared locales to try for `locales`, most preferred first.
+    fn candidates<'a>(&'a self, locales: &[&str]) -> Vec<&'a str> {
+        let mut candidates: Vec<&str> = Vec::new();
+        for tag in locales {
+            let mut tag = tag.replace('_', "-");
+            loop {
+                let mut current = self.known_locale(&tag);
+                while let Some(locale) = current {
+                    if candidates.contains(&locale) {
+                        break;
+                    }
+                    candidates.push(locale);
+                    current = self.fallback(locale);
+                }
+                match tag.rfind('-') {
+                    Some(end) => tag.truncate(end),
+                    None => break,
+                }
+            }
+        }
+        candidates
+    }
+
+    fn fallback(&self, locale: &str) -> Option<&str> {
+        let (_, fallback) = self
+            .fallbacks
+            .iter()
+            .find(|(key, _)| normalize(key) == normalize(locale))?;
+        self.known_locale(fallback)
+    }
+
+    fn supported_locale(&self, locale: &str) -> Option<&str> {
+        self.supported
+            .iter()
+            .find(|supported| normalize(supported) == normalize(locale))
+            .map(String::as_str)
+    }
+
+    /// The declared spelling of `locale`, default included.
+    fn known_locale(&self, locale: &str) -> Option<&str> {
+        if normalize(&self.default) == normalize(locale) {
+            return Some(&self.default);
+        }
+        self.supported_locale(locale)
+    }
+
+    /// `(base, locale)` of a variant named like `welcome.de.hbs`.
+    fn split_variant<'a>(&'a self, path: &str) -> Option<(String, &'a str)> {
+        let (stem, extension) = path.rsplit_once('.')?;
+        let (name, tag) = stem.rsplit_once('.')?;
+        if name.is_empty() || name.ends_with('/') {
+            return None;
+        }
+        let locale = self.supported_locale(tag)?;
+        Some((format!("{name}.{extension}"), locale))
+    }
+}
+
+fn normalize(locale: &str) -> String {
+    locale.replace('_', "-").to_ascii_lowercase()
+}
+
+fn is_localized(path: &str) -> bool {
+    path.rsplit_once('.')
+        .is_some_and(|(_, extension)| LOCALIZED_EXTENSIONS.contains(&extension))
+}
+
+/// `path` with `locale` before its extension.
+fn suffixed(path: &str, locale: &str) -> String {
+    match path.rsplit_once('.') {
+        Some((stem, extension)) => format!("{stem}.{locale}.{extension}"),
+        None => format!("{path}.{locale}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(yaml: &str) -> LocalesSection {
+        let section: LocalesSection = serde_yaml_bw::from_str(yaml).unwrap();
+        section.validate().unwrap();
+        section
+    }
+
+    const TEMPLATES: &[&str] = &[
+        "templates/welcome.hbs",
+        "templates/welcome.de.hbs",
+        "templates/fr/bienvenue.hbs",
+        "templates/logo.png",
+    ];
+
+    #[test]
+    fn resolves_suffix_and_listed_variants() {
+        let locales = section(
+            "supported: [de, fr, pt-BR]\nfallbacks:\n  pt-BR: en\ntemplates:\n  templates/welcome.hbs:\n    fr: templates/fr/bienvenue.hbs\n",
+        )
+        .with_variants(TEMPLATES.iter().copied())
+        .unwrap();
+
+        let welcome = "templates/welcome.hbs";
+        assert_eq!(
+            locales.variant(welcome, &["de-CH"]),
+            "templates/welcome.de.hbs"
+        );
+        assert_eq!(
+            locales.variant(welcome, &["es", "fr_FR"]),
+            "templates/fr/bienvenue.hbs"
+        );
+        assert_eq!(locales.variant(welcome, &["pt-BR", "de"]), welcome);
+        assert_eq!(locales.variant(welcome, &["en", "de"]), welcome);
+        assert_eq!(locales.variant(welcome, &["ja"]), welcome);
+    }
+
+    #[test]
+    fn every_locale_needs_a_variant_or_a_fallback() {
+        let err = section("supported: [de, fr]\n")
+            .with_variants(TEMPLATES.iter().copied())
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("templates/welcome.fr.hbs"),
+            "{err}"
+        );
+
+        let err = section("supported: [de]\n")
+            .with_variants(["templates/welcome.de.hbs"])
+            .unwrap_err();
+        assert!(err.to_string().contains("no base template"), "{err}");
+
+        let cyclic: LocalesSection =
+            serde_yaml_bw::from_str("supported: [de, fr]\nfallbacks:\n  de: fr\n  fr: de\n")
+                .unwrap();
+        assert!(cyclic.validate().is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Result, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Largest icon accepted in a pack (256 KiB).
+pub const MAX_ICON_BYTES: u64 = 256 * 1024;
+/// Largest screenshot accepted in a pack (2 MiB).
+pub const MAX_SCREENSHOT_BYTES: u64 = 2 * 1024 * 1024;
+/// Maximum number of screenshots a pack may declare.
+pub const MAX_SCREENSHOTS: usize = 8;
+
+/// Marketplace-facing media declared by a pack: an icon plus captioned screenshots.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct MediaSection {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<MediaRef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub screenshots: Vec<MediaRef>,
+}
+
+impl MediaSection {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(icon) = &self.icon {
+            icon.validate("media.icon")?;
+            if icon.caption.is_some() {
+                bail!("media.icon does not support a caption");
+            }
+        }
+        if self.screenshots.len() > MAX_SCREENSHOTS {
+            bail!(
+                "media.screenshots declares {} entries; at most {} are allowed",
+                self.screenshots.len(),
+                MAX_SCREENSHOTS
+            );
+        }
+        let mut seen = BTreeSet::new();
+        for (idx, shot) in self.screenshots.iter().enumerate() {
+            shot.validate(&format!("media.screenshots[{idx}]"))?;
+            if !seen.insert(shot.path.as_str()) {
+                bail!("duplicate media.screenshots path: {}", shot.path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Iterates over every declared media reference, icon first.
+    pub fn entries(&self) -> impl Iterator<Item = &MediaRef> {
+        self.icon.iter().chain(self.screenshots.iter())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.icon.is_none() && self.screenshots.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct MediaRef {
+    /// Relative path of the image. In `pack.yaml` this is relative to the pack
+    /// root; inside a `.gtpack` manifest it is the archive path under `media/`.
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+}
+
+impl MediaRef {
+    fn validate(&self, label: &str) -> Result<()> {
+        let path = self.path.trim();
+        if path.is_empty() {
+            bail!("{label}.path must not be empty");
+        }
+        if path.starts_with('/') || path.split(['/', '\\']).any(|seg| seg == "..") {
+            bail!("{label}.path must be a relative path inside the pack: {path}");
+        }
+        if MediaFormat::from_path(path).is_none() {
+            bail!(
+                "{label}.path `{path}` has an unsupported image format (allowed: png, jpg, jpeg, webp, svg)"
+            );
+        }
+        if let Some(caption) = &self.caption
+            && caption.trim().is_empty()
+        {
+            bail!("{label}.caption must not be empty when provided");
+        }
+        Ok(())
+    }
+
+    pub fn format(&self) -> Option<MediaFormat> {
+        MediaFormat::from_path(&self.path)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MediaFormat {
+    Png,
+    Jpeg,
+    Webp,
+    Svg,
+}
+
+impl MediaFormat {
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = path.rsplit_once('.')?.1.to_ascii_lowercase();
+        match ext.as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "webp" => Some(Self::Webp),
+            "svg" => Some(Self::Svg),
+            _ => None,
+        }
+    }
+
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Webp => "image/webp",
+            Self::Svg => "image/svg+xml",
+        }
+    }
+
+    /// Checks that `bytes` carry the signature expected for this format.
+    pub fn matches(self, bytes: &[u8]) -> bool {
+        match self {
+            Self::Png => bytes.starts_with(b"\x89PNG\r\n\x1a\n"),
+            Self::Jpeg => bytes.starts_with(&[0xff, 0xd8, 0xff]),
+            Self::Webp => bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP",
+            Self::Svg => std::str::from_utf8(bytes)
+                .map(|text| text.contains("<svg"))
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Validates the payload for a media reference against its declared format and size limit.
+pub fn validate_media_bytes(label: &str, media: &MediaRef, bytes: &[u8], limit: u64) -> Result<()> {
+    let Some(format) = media.format() else {
+        bail!("{label} `{}` has an unsupported image format", media.path);
+    };
+    if !format.matches(bytes) {
+        bail!(
+            "{label} `{}` does not look like a {} image",
+            media.path,
+            format.media_type()
+        );
+    }
+    if bytes.len() as u64 > limit {
+        bail!(
+            "{label} `{}` is {} bytes; the limit is {} bytes",
+            media.path,
+            bytes.len(),
+            limit
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn media(path: &str) -> MediaRef {
+        MediaRef {
+            path: path.into(),
+            caption: None,
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_extension_and_traversal() {
+        let section = MediaSection {
+            icon: Some(media("icon.gif")),
+            screenshots: Vec::new(),
+        };
+        assert!(section.validate().is_err());
+
+        let section = MediaSection {
+            icon: None,
+            screenshots: vec![media("../shot.png")],
+        };
+        assert!(section.validate().is_err());
+    }
+
+    #[test]
+    fn checks_magic_bytes_and_limits() {
+        let png = b"\x89PNG\r\n\x1a\nrest".to_vec();
+        assert!(validate_media_bytes("media.icon", &media("icon.png"), &png, 1024).is_ok());
+        assert!(validate_media_bytes("media.icon", &media("icon.jpg"), &png, 1024).is_err());
+        assert!(validate_media_bytes("media.icon", &media("icon.png"), &png, 4).is_err());
+    }
+}
@@ -1,10 +1,13 @@
 use std::collections::BTreeSet;
+use std::fmt;
 
 use anyhow::{Result, bail};
+#[cfg(feature = "schema")]
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct MessagingSection {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub adapters: Option<Vec<MessagingAdapter>>,
@@ -25,7 +28,8 @@ impl MessagingSection {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct MessagingAdapter {
     pub name: String,
     pub kind: MessagingAdapterKind,
@@ -56,7 +60,8 @@ impl MessagingAdapter {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum MessagingAdapterKind {
     Ingress,
@@ -64,7 +69,19 @@ pub enum MessagingAdapterKind {
     IngressEgress,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema, Default)]
+impl fmt::Display for MessagingAdapterKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match self {
+            Self::Ingress => "ingress",
+            Self::Egress => "egress",
+            Self::IngressEgress => "ingress-egress",
+        };
+        f.write_str(value)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 #[serde(deny_unknown_fields)]
 pub struct MessagingAdapterCapabilities {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -0,0 +1,296 @@
+//! Usage metrics a pack reports through the host metrics import.
+//!
+//! Packs declare every instrument up front, so hosts can register them before
+//! the first run and refuse anything else:
+//!
+//! ```yaml
+//! metrics:
+//!   forecasts_served:
+//!     kind: counter
+//!     description: Forecasts returned to users
+//!     labels: [country]
+//!   forecast_latency_ms:
+//!     kind: histogram
+//!     unit: ms
+//!     buckets: [50, 100, 250, 500, 1000]
+//! ```
+//!
+//! Flows emit through `metrics.*` nodes that name the instrument and set its
+//! labels:
+//!
+//! ```yaml
+//! count_forecast:
+//!   metrics.increment:
+//!     name: forecasts_served
+//!     labels: { country: in.country }
+//! ```
+//!
+//! [`MetricsSection::check_emissions`] reports emissions of undeclared
+//! instruments or labels.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// Component root of metric nodes.
+pub const METRICS_ROOT: &str = "metrics";
+/// Labels an instrument may have; each multiplies the series hosts keep.
+pub const MAX_LABELS: usize = 8;
+const MAX_NAME_LEN: usize = 64;
+
+/// Declared instruments by name.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(transparent)]
+pub struct MetricsSection {
+    pub metrics: BTreeMap<String, MetricDeclaration>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum MetricKind {
+    Counter,
+    Histogram,
+}
+
+impl MetricKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Histogram => "histogram",
+        }
+    }
+
+    /// The kind a `metrics.*` component records, when it is specific to one.
+    fn of_component(component: &str) -> Option<Self> {
+        match component.strip_prefix("metrics.")? {
+            "increment" => Some(MetricKind::Counter),
+            "record" => Some(MetricKind::Histogram),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct MetricDeclaration {
+    pub kind: MetricKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Unit of recorded values, e.g. `ms` or `bytes`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// Histogram bucket boundaries, strictly increasing.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub buckets: Vec<f64>,
+}
+
+/// One `metrics.*` node of a flow.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct MetricEmission {
+    pub node: String,
+    pub component: String,
+    /// The instrument; `None` when the node does not name one.
+    pub name: Option<String>,
+    pub labels: Vec<String>,
+}
+
+impl MetricsSection {
+    pub fn validate(&self) -> Result<()> {
+        for (name, metric) in &self.metrics {
+            if !is_identifier(name, true) {
+                bail!(
+                    "metric name `{name}` must start with a lowercase letter and use only \
+                     lowercase letters, digits, `_`, and `.` (at most {MAX_NAME_LEN} characters)"
+                );
+            }
+            if metric.labels.len() > MAX_LABELS {
+                bail!("metrics.{name} has more than {MAX_LABELS} labels");
+            }
+            for (index, label) in metric.labels.iter().enumerate() {
+                if !is_identifier(label, false) || label.starts_with("__") {
+                    bail!(
+                        "metrics.{name} label `{label}` must start with a lowercase letter and \
+                         use only lowercase letters, digits, and `_`"
+                    );
+                }
+                if metric.labels[..index].contains(label) {
+                    bail!("metrics.{name} lists label `{label}` twice");
+                }
+            }
+            match metric.kind {
+                MetricKind::Counter if !metric.buckets.is_empty() => {
+                    bail!("metrics.{name} is a counter and cannot have buckets");
+                }
+                MetricKind::Histogram => {
+                    if metric.buckets.iter().any(|bound| !bound.is_finite()) {
+                        bail!("metrics.{name} buckets must be finite numbers");
+                    }
+                    if metric.buckets.windows(2).any(|pair| pair[0] >= pair[1]) {
+                        bail!("metrics.{name} buckets must be strictly increasing");
+                    }
+                }
+                MetricKind::Counter => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Problems with the metric nodes of `flow`, one message each.
+    pub fn check_emissions(&self, flow: &str, emissions: &[MetricEmission]) -> Vec<String> {
+        let mut problems = Vec::new();
+        for emission in emissions {
+            let at = format!("flow `{flow}` node `{}`", emission.node);
+            let Some(name) = &emission.name else {
+                problems.push(format!(
+                    "{at} uses `{}` without naming a metric",
+                    emission.component
+                ));
+                continue;
+            };
+            let Some(metric) = self.metrics.get(name) else {
+                problems.push(format!("{at} emits undeclared metric `{name}`"));
+                continue;
+            };
+            if let Some(kind) = MetricKind::of_component(&emission.component)
+                && kind != metric.kind
+            {
+                problems.push(format!(
+                    "{at} records `{name}` as a {} but it is declared as a {}",
+                    kind.as_str(),
+                    metric.kind.as_str()
+                ));
+            }
+            for label in &emission.labels {
+                if !metric.labels.contains(label) {
+                    problems.push(format!(
+                        "{at} sets label `{label}`, which metric `{name}` does not declare"
+                    ));
+                }
+            }
+        }
+        problems
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+}
+
+/// The `metrics.*` nodes of a flow, read from its YAML source.
+pub fn metric_emissions(flow_yaml: &str) -> Result<Vec<MetricEmission>> {
+    let source: JsonValue =
+        serde_yaml_bw::from_str(flow_yaml).context("flow source is not valid YAML")?;
+    let mut emissions = Vec::new();
+    let Some(nodes) = source.get("nodes").and_then(JsonValue::as_object) else {
+        return Ok(emissions);
+    };
+    for (node, body) in nodes {
+        let Some(body) = body.as_object() else {
+            continue;
+        };
+        for (component, config) in body {
+            if component.split('.').next() != Some(METRICS_ROOT) || component == METRICS_ROOT {
+                continue;
+            }
+            let labels = config
+                .get("labels")
+                .and_then(JsonValue::as_object)
+                .map(|labels| labels.keys().cloned().collect())
+                .unwrap_or_default();
+            emissions.push(MetricEmission {
+                node: node.clone(),
+                component: component.clone(),
+                name: config
+                    .get("name")
+                    .and_then(JsonValue::as_str)
+                    .map(str::to_string),
+                labels,
+            });
+        }
+    }
+    Ok(emissions)
+}
+
+fn is_identifier(value: &str, allow_dots: bool) -> bool {
+    value.len() <= MAX_NAME_LEN
+        && value.starts_with(|c: char| c.is_ascii_lowercase())
+        && value.chars().all(|c| {
+            c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || (allow_dots && c == '.')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(yaml: &str) -> MetricsSection {
+        serde_yaml_bw::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn validates_declarations_and_flags_undeclared_emissions() {
+        let metrics = section(
+            r#"
+forecasts_served:
+  kind: counter
+  labels: [country]
+forecast_latency_ms:
+  kind: histogram
+  unit: ms
+  buckets: [50, 100, 250]
+"#,
+        );
+        metrics.validate().unwrap();
+        for bad in [
+            "Forecasts: { kind: counter }",
+            "served: { kind: counter, labels: [country, country] }",
+            "served: { kind: counter, labels: [__internal] }",
+            "served: { kind: counter, buckets: [1] }",
+            "latency: { kind: histogram, buckets: [100, 50] }",
+        ] {
+            assert!(section(bad).validate().is_err(), "{bad}");
+        }
+
+        let emissions = metric_emissions(
+            r#"
+id: weather_bot
+nodes:
+  count:
+    metrics.increment:
+      name: forecasts_served
+      labels: { country: in.country, city: in.city }
+  timing:
+    metrics.increment:
+      name: forecast_latency_ms
+  stray:
+    metrics.record:
+      name: cache_hits
+  reply:
+    templating.handlebars:
+      text: done
+"#,
+        )
+        .unwrap();
+        assert_eq!(emissions.len(), 3);
+        let problems = metrics.check_emissions("weather_bot", &emissions);
+        assert_eq!(
+            problems,
+            [
+                "flow `weather_bot` node `count` sets label `city`, which metric \
+                 `forecasts_served` does not declare",
+                "flow `weather_bot` node `stray` emits undeclared metric `cache_hits`",
+                "flow `weather_bot` node `timing` records `forecast_latency_ms` as a counter \
+                 but it is declared as a histogram",
+            ]
+        );
+    }
+}
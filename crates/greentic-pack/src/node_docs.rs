@@ -0,0 +1,116 @@
+//! Documentation strings on flow nodes.
+//!
+//! A node may carry a `doc:` string next to its component and routing, for
+//! editors to show on hover:
+//!
+//! ```yaml
+//! nodes:
+//!   forecast_weather:
+//!     doc: Calls the weather API for the city the user named.
+//!     mcp.exec:
+//!       component: weather_api
+//! ```
+//!
+//! The flow parser only accepts a component and `routing` on a node, so
+//! builds read the docs with [`node_docs`] and hand the parser the source
+//! returned by [`strip_node_docs`], which drops `fallback:` keys
+//! ([`crate::fallbacks`]) as well.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, bail};
+use serde_yaml_bw::Value as YamlValue;
+
+use crate::fallbacks::FALLBACK_KEY;
+
+/// Node key holding the documentation string.
+pub const DOC_KEY: &str = "doc";
+
+/// Documentation strings by node id.
+pub type NodeDocs = BTreeMap<String, String>;
+
+/// The `doc:` strings of a flow's nodes.
+pub fn node_docs(flow_yaml: &str) -> Result<NodeDocs> {
+    let source: YamlValue =
+        serde_yaml_bw::from_str(flow_yaml).context("flow source is not valid YAML")?;
+    let mut docs = NodeDocs::new();
+    let Some(nodes) = source.get("nodes").and_then(YamlValue::as_mapping) else {
+        return Ok(docs);
+    };
+    for (node, body) in nodes {
+        let Some(doc) = body.get(DOC_KEY) else {
+            continue;
+        };
+        let node = node.as_str().unwrap_or_default();
+        let Some(doc) = doc.as_str().map(str::trim).filter(|doc| !doc.is_empty()) else {
+            bail!("node `{node}`: `{DOC_KEY}` must be a non-empty string");
+        };
+        docs.insert(node.to_string(), doc.to_string());
+    }
+    Ok(docs)
+}
+
+/// The flow source without `doc:` and `fallback:` keys, or `None` when no
+/// node has either.
+pub fn strip_node_docs(flow_yaml: &str) -> Result<Option<String>> {
+    let mut source: YamlValue =
+        serde_yaml_bw::from_str(flow_yaml).context("flow source is not valid YAML")?;
+    let Some(nodes) = source
+        .as_mapping_mut()
+        .and_then(|flow| flow.get_mut("nodes"))
+        .and_then(YamlValue::as_mapping_mut)
+    else {
+        return Ok(None);
+    };
+    let mut stripped = false;
+    for (_, body) in nodes.iter_mut() {
+        if let Some(body) = body.as_mapping_mut() {
+            for key in [DOC_KEY, FALLBACK_KEY] {
+                stripped |= body.remove(key).is_some();
+            }
+        }
+    }
+    if !stripped {
+        return Ok(None);
+    }
+    let yaml = serde_yaml_bw::to_string(&source).context("failed to serialise flow source")?;
+    Ok(Some(yaml))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLOW: &str = r#"
+id: weather_bot
+type: messaging
+nodes:
+  ask:
+    doc: "  Asks which city to forecast.  "
+    qa.process:
+      welcome: Hi
+    routing:
+      - to: reply
+  reply:
+    templating.handlebars:
+      text: done
+    fallback: skip
+"#;
+
+    #[test]
+    fn reads_and_strips_node_docs() {
+        let docs = node_docs(FLOW).unwrap();
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs["ask"], "Asks which city to forecast.");
+
+        let stripped = strip_node_docs(FLOW).unwrap().expect("flow has docs");
+        assert!(!stripped.contains("Asks which city"));
+        assert!(stripped.contains("qa.process"));
+        assert!(!stripped.contains("fallback"));
+        assert!(node_docs(&stripped).unwrap().is_empty());
+        assert_eq!(strip_node_docs(&stripped).unwrap(), None);
+
+        let bad = FLOW.replace("\"  Asks which city to forecast.  \"", "[not, text]");
+        assert!(node_docs(&bad).is_err());
+    }
+}
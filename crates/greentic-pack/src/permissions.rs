@@ -0,0 +1,270 @@
+//! Human-readable permission summaries for consent prompts.
+//!
+//! Interactive hosts ask the user before granting a pack what its flows
+//! require ("This pack wants to send email on your behalf"). The build groups
+//! the capabilities of every flow by root (see
+//! [`capabilities::import_root`](crate::capabilities::import_root)) and embeds
+//! one [`Permission`] per root in the manifest, with its text in every locale
+//! the pack's i18n bundles cover. Hosts pick a locale with
+//! [`PermissionSummary::localized`].
+//!
+//! Text comes from the bundle key `permission.<root>`, e.g.
+//! `permission.email`. The built-in English bundle covers the common host
+//! capabilities; packs add locales, or reword the English text, with their
+//! own bundles. Components under roots nobody wrote a message for (`flow`,
+//! `templating`, ...) run inside the pack and need no consent, but every
+//! declared import does, so imports without a message get a generic one.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::capabilities::{FlowRequirements, import_root};
+
+/// Locale of the built-in messages, and the fallback for every lookup.
+pub const DEFAULT_LOCALE: &str = "en";
+/// Prefix of permission messages in i18n bundles.
+pub const MESSAGE_PREFIX: &str = "permission.";
+/// Directory of the i18n bundles, `i18n/<locale>.json`, in a pack source tree
+/// and in the archive.
+pub const I18N_DIR: &str = "i18n";
+
+/// Built-in English messages by capability root.
+const BUILTIN_MESSAGES: &[(&str, &str)] = &[
+    ("crm", "Read and update records in your CRM"),
+    ("email", "Send email on your behalf"),
+    ("events", "Publish and receive events"),
+    ("http", "Connect to external web services"),
+    (
+        "llm",
+        "Send conversation content to a language model provider",
+    ),
+    ("mcp", "Run external tools through MCP servers"),
+    ("messaging", "Send messages to your channels"),
+    ("secrets", "Read stored secrets such as API tokens"),
+    ("state", "Store data between conversations"),
+];
+
+/// Messages of one locale, keyed like `permission.email`.
+pub type I18nBundle = BTreeMap<String, String>;
+
+/// Permission summary embedded in the manifest.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct PermissionSummary {
+    pub default_locale: String,
+    pub permissions: Vec<Permission>,
+}
+
+/// One capability root the pack needs the host to grant.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct Permission {
+    /// Capability root, e.g. `email`.
+    pub capability: String,
+    /// Flows that require it.
+    pub flows: Vec<String>,
+    /// Prompt text by locale; always has the default locale.
+    pub text: BTreeMap<String, String>,
+}
+
+/// A permission with its text resolved for one locale.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct LocalizedPermission<'a> {
+    pub capability: &'a str,
+    pub flows: &'a [String],
+    /// The locale `text` is in, after fallback.
+    pub locale: &'a str,
+    pub text: &'a str,
+}
+
+impl PermissionSummary {
+    /// Summarises what `flows` require. `bundles` are the pack's i18n bundles
+    /// by locale; an `en` bundle overrides the built-in messages.
+    pub fn build<'a>(
+        flows: impl IntoIterator<Item = (&'a str, &'a FlowRequirements)>,
+        bundles: &BTreeMap<String, I18nBundle>,
+    ) -> Self {
+        let mut roots: BTreeMap<&str, (bool, Vec<String>)> = BTreeMap::new();
+        for (flow, requirements) in flows {
+            for component in requirements.components.keys() {
+                let root = import_root(component);
+                let entry = roots.entry(root).or_default();
+                push_unique(&mut entry.1, flow);
+            }
+            for import in &requirements.imports {
+                let entry = roots.entry(import_root(import)).or_default();
+                entry.0 = true;
+                push_unique(&mut entry.1, flow);
+            }
+        }
+
+        let permissions = roots
+            .into_iter()
+            .filter_map(|(root, (imported, flows))| {
+                let key = format!("{MESSAGE_PREFIX}{root}");
+                let mut text: BTreeMap<String, String> = bundles
+                    .iter()
+                    .filter_map(|(locale, bundle)| {
+                        Some((locale.clone(), bundle.get(&key)?.clone()))
+                    })
+                    .collect();
+                if !text.contains_key(DEFAULT_LOCALE) {
+                    let builtin = builtin_message(root).map(str::to_string).or_else(|| {
+                        imported.then(|| format!("Use the `{root}` host capability"))
+                    })?;
+                    text.insert(DEFAULT_LOCALE.to_string(), builtin);
+                }
+                Some(Permission {
+                    capability: root.to_string(),
+                    flows,
+                    text,
+                })
+            })
+            .collect();
+
+        Self {
+            default_locale: DEFAULT_LOCALE.to_string(),
+            permissions,
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        validate_locale(&self.default_locale)?;
+        for permission in &self.permissions {
+            if permission.capability.trim().is_empty() {
+                bail!("permissions must name a capability");
+            }
+            for locale in permission.text.keys() {
+                validate_locale(locale)
+                    .map_err(|err| anyhow!("permission `{}`: {err}", permission.capability))?;
+            }
+            if !permission.text.contains_key(&self.default_locale) {
+                bail!(
+                    "permission `{}` has no text in the default locale `{}`",
+                    permission.capability,
+                    self.default_locale
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The permissions with text in `locale`, falling back from `de-CH` to
+    /// `de` and then to the default locale.
+    pub fn localized(&self, locale: &str) -> Vec<LocalizedPermission<'_>> {
+        self.permissions
+            .iter()
+            .filter_map(|permission| {
+                let (locale, text) = lookup(&permission.text, locale)
+                    .or_else(|| lookup(&permission.text, &self.default_locale))?;
+                Some(LocalizedPermission {
+                    capability: &permission.capability,
+                    flows: &permission.flows,
+                    locale,
+                    text,
+                })
+            })
+            .collect()
+    }
+}
+
+fn builtin_message(root: &str) -> Option<&'static str> {
+    BUILTIN_MESSAGES
+        .iter()
+        .find(|(name, _)| *name == root)
+        .map(|(_, message)| *message)
+}
+
+fn push_unique(flows: &mut Vec<String>, flow: &str) {
+    if !flows.iter().any(|existing| existing == flow) {
+        flows.push(flow.to_string());
+    }
+}
+
+/// Exact match first (ignoring case and `_` versus `-`), then each shorter
+/// prefix of the tag.
+pub(crate) fn lookup<'a, T>(
+    text: &'a BTreeMap<String, T>,
+    locale: &str,
+) -> Option<(&'a str, &'a T)> {
+    let wanted = locale.replace('_', "-").to_ascii_lowercase();
+    let mut candidate = wanted.as_str();
+    loop {
+        if let Some((locale, text)) = text
+            .iter()
+            .find(|(locale, _)| locale.replace('_', "-").to_ascii_lowercase() == candidate)
+        {
+            return Some((locale, text));
+        }
+        candidate = &candidate[..candidate.rfind('-')?];
+    }
+}
+
+/// Checks that `locale` looks like a BCP 47 tag such as `de` or `pt-BR`.
+pub fn validate_locale(locale: &str) -> Result<()> {
+    let mut parts = locale.split('-');
+    let language = parts.next().unwrap_or_default();
+    let valid = (2..=3).contains(&language.len())
+        && language.bytes().all(|b| b.is_ascii_alphabetic())
+        && parts.all(|part| {
+            (1..=8).contains(&part.len()) && part.bytes().all(|b| b.is_ascii_alphanumeric())
+        });
+    if !valid {
+        bail!("`{locale}` is not a locale tag like `de` or `pt-BR`");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn requirements(components: &[&str], imports: &[&str]) -> FlowRequirements {
+        FlowRequirements {
+            components: components.iter().map(|c| (c.to_string(), 1)).collect(),
+            imports: imports.iter().map(|i| i.to_string()).collect(),
+            nodes: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn summary_covers_host_capabilities_in_each_locale() {
+        let signup = requirements(&["email.send", "flow.return"], &["secrets.get"]);
+        let report = requirements(&["mcp.exec"], &["greentic:host-import/billing@1.0"]);
+        let bundles = BTreeMap::from([(
+            "de".to_string(),
+            I18nBundle::from([(
+                "permission.email".to_string(),
+                "E-Mails in Ihrem Namen senden".to_string(),
+            )]),
+        )]);
+        let summary =
+            PermissionSummary::build([("signup", &signup), ("report", &report)], &bundles);
+
+        let capabilities: Vec<&str> = summary
+            .permissions
+            .iter()
+            .map(|permission| permission.capability.as_str())
+            .collect();
+        assert_eq!(capabilities, ["billing", "email", "mcp", "secrets"]);
+        assert_eq!(
+            summary.permissions[0].text[DEFAULT_LOCALE],
+            "Use the `billing` host capability"
+        );
+
+        let german = summary.localized("de_CH");
+        assert_eq!(german[1].locale, "de");
+        assert_eq!(german[1].text, "E-Mails in Ihrem Namen senden");
+        assert_eq!(german[1].flows, ["signup"]);
+        assert_eq!(german[2].locale, DEFAULT_LOCALE);
+        assert_eq!(german[2].text, "Run external tools through MCP servers");
+
+        validate_locale("pt-BR").unwrap();
+        assert!(validate_locale("english").is_err());
+        assert!(validate_locale("de/CH").is_err());
+    }
+}
@@ -9,11 +9,20 @@ use greentic_types::deployment::{
 use serde_json::{Map as JsonMap, Value as JsonValue, json};
 
 use crate::builder::{FlowEntry, PackMeta};
+use crate::config_schema::config_defaults;
 
 /// Builds a provider-agnostic [`DeploymentPlan`] from the supplied pack metadata and component
 /// manifests. The resulting plan is intentionally conservative and focuses on expressing the
 /// minimal runtime contracts (flows, secrets, telemetry) so that deployers can extend it with
-/// provider-specific data.
+/// provider-specific data. Declared flow quotas are passed through under `extra.quotas` so
+/// hosts can configure rate limiters from the plan alone, flow execution limits and
+/// concurrency and idempotency declarations are handed to the runner in its
+/// `capabilities.limits` and `capabilities.dispatch`, the QoS class of every flow in
+/// `capabilities.qos` once any flow declares one, scheduled flows are listed under
+/// `extra.schedules` for hosts to register with their timers, and the settings described by
+/// `config_schema` are listed under `extra.config_keys` so hosts can ask for the required ones,
+/// with the pack's defaults for `environment` under `extra.config_defaults`. Imports the pack
+/// can run without are listed under `extra.optional_imports`.
 pub fn infer_base_deployment_plan(
     meta: &PackMeta,
     flows: &[FlowEntry],
@@ -22,18 +31,67 @@ pub fn infer_base_deployment_plan(
     tenant: &TenantCtx,
     environment: &str,
 ) -> DeploymentPlan {
+    let mut runner_capabilities = json!({
+        "flows": flows.iter().map(|flow| flow.id.clone()).collect::<Vec<_>>(),
+    });
+    if let Some(limits) = meta.limits.as_ref().filter(|limits| !limits.is_empty()) {
+        runner_capabilities["limits"] = json!(limits);
+    }
+    if let Some(dispatch) = meta
+        .dispatch
+        .as_ref()
+        .filter(|dispatch| !dispatch.is_empty())
+    {
+        runner_capabilities["dispatch"] = json!(dispatch);
+    }
+    if flows.iter().any(|flow| flow.qos.is_some()) {
+        let qos: JsonMap<String, JsonValue> = flows
+            .iter()
+            .map(|flow| (flow.id.clone(), json!(flow.qos.unwrap_or_default())))
+            .collect();
+        runner_capabilities["qos"] = JsonValue::Object(qos);
+    }
     let runners = vec![RunnerPlan {
         name: format!("{}-runner", meta.pack_id),
         replicas: 1,
-        capabilities: json!({
-            "flows": flows.iter().map(|flow| flow.id.clone()).collect::<Vec<_>>(),
-        }),
+        capabilities: runner_capabilities,
     }];
 
     let messaging = infer_messaging_plan(connectors);
     let channels = infer_channel_plan(connectors);
     let secrets = infer_secret_plan(components);
     let telemetry = infer_telemetry_plan(components);
+    let mut extra = JsonMap::new();
+    if let Some(quotas) = meta.quotas.as_ref().filter(|quotas| !quotas.is_empty()) {
+        extra.insert("quotas".to_string(), json!(quotas));
+    }
+    if let Some(schedule) = meta
+        .schedule
+        .as_ref()
+        .filter(|schedule| !schedule.is_empty())
+    {
+        extra.insert("schedules".to_string(), json!(schedule.bindings()));
+    }
+    if let Some(config_schema) = &meta.config_schema {
+        let defaults = config_defaults(meta, environment);
+        extra.insert(
+            "config_keys".to_string(),
+            json!(config_schema.keys_with_defaults(&defaults)),
+        );
+        if !defaults.is_empty() {
+            extra.insert("config_defaults".to_string(), JsonValue::Object(defaults));
+        }
+    }
+
+    let optional_imports: Vec<&str> = meta
+        .imports
+        .iter()
+        .filter(|import| import.optional)
+        .map(|import| import.pack_id.as_str())
+        .collect();
+    if !optional_imports.is_empty() {
+        extra.insert("optional_imports".to_string(), json!(optional_imports));
+    }
 
     DeploymentPlan {
         pack_id: meta.pack_id.clone(),
@@ -46,7 +104,7 @@ pub fn infer_base_deployment_plan(
         secrets,
         oauth: Vec::new(),
         telemetry,
-        extra: JsonValue::Object(JsonMap::new()),
+        extra: JsonValue::Object(extra),
     }
 }
 
@@ -178,7 +236,8 @@ mod tests {
     use std::str::FromStr;
 
     use super::*;
-    use crate::builder::PackMeta;
+    use crate::builder::{ImportRef, PackMeta};
+    use crate::qos::{FlowQos, QosClass};
     use greentic_types::component::{
         ComponentCapabilities, ComponentProfiles, HostCapabilities, SecretsCapabilities,
         TelemetryCapabilities, WasiCapabilities,
@@ -201,13 +260,36 @@ mod tests {
             homepage: None,
             support: None,
             vendor: None,
-            imports: Vec::new(),
+            tags: Vec::new(),
+            categories: Vec::new(),
+            imports: vec![ImportRef {
+                pack_id: "greentic:host-import/analytics@0.6".into(),
+                version_req: "*".into(),
+                optional: true,
+            }],
             entry_flows: vec!["flow.main".into()],
             created_at_utc: "2025-01-01T00:00:00Z".into(),
             events: None,
             repo: None,
             messaging: None,
             interfaces: Vec::new(),
+            media: None,
+            config: None,
+            config_schema: None,
+            config_defaults: None,
+            lifecycle: None,
+            quotas: None,
+            limits: None,
+            dispatch: None,
+            qos: None,
+            secrets: None,
+            capabilities: None,
+            metrics: None,
+            schedule: None,
+            classification: None,
+            locales: None,
+            data_handling: None,
+            permissions: None,
             annotations: JsonMap::new(),
         };
         let connectors = json!({
@@ -223,6 +305,37 @@ mod tests {
         });
         meta.annotations
             .insert("connectors".into(), connectors.clone());
+        meta.quotas = Some(
+            serde_json::from_value(json!({
+                "flow.main": { "crm.lookup": { "requests_per_minute": 120, "burst": 20 } }
+            }))
+            .unwrap(),
+        );
+        meta.limits =
+            Some(serde_json::from_value(json!({ "flow.main": { "timeout_ms": 5000 } })).unwrap());
+        meta.dispatch = Some(
+            serde_json::from_value(json!({
+                "flow.main": { "concurrency": { "mode": "serial_per_key", "key": "in.user" } }
+            }))
+            .unwrap(),
+        );
+        meta.schedule = Some(
+            serde_json::from_value(json!({
+                "flow.main": { "cron": "0 8 * * MON-FRI", "timezone": "Europe/Berlin" }
+            }))
+            .unwrap(),
+        );
+        meta.config_schema = Some(
+            serde_json::from_value(json!({
+                "type": "object",
+                "required": ["base_url"],
+                "properties": {
+                    "base_url": { "type": "string" },
+                    "beta_ui": { "type": "boolean", "default": false }
+                }
+            }))
+            .unwrap(),
+        );
 
         let flows = vec![FlowEntry {
             id: "flow.main".into(),
@@ -231,6 +344,11 @@ mod tests {
             file_yaml: "flows/flow.main/flow.ygtc".into(),
             file_json: "flows/flow.main/flow.json".into(),
             hash_blake3: "abc".into(),
+            eager: false,
+            qos: Some(FlowQos {
+                class: QosClass::Interactive,
+                deadline_ms: Some(2000),
+            }),
         }];
 
         let component_manifest = ComponentManifest {
@@ -277,6 +395,43 @@ mod tests {
         assert_eq!(plan.channels.len(), 1);
         assert_eq!(plan.secrets.len(), 1);
         assert!(plan.telemetry.is_some());
+        assert_eq!(
+            plan.extra["quotas"]["flow.main"]["crm.lookup"]["requests_per_minute"],
+            120
+        );
+        assert_eq!(
+            plan.runners[0].capabilities["limits"]["flow.main"]["timeout_ms"],
+            5000
+        );
+        assert_eq!(
+            plan.runners[0].capabilities["qos"]["flow.main"],
+            json!({ "class": "interactive", "deadline_ms": 2000 })
+        );
+        assert_eq!(
+            plan.runners[0].capabilities["dispatch"]["flow.main"]["concurrency"]["mode"],
+            "serial_per_key"
+        );
+        assert_eq!(
+            plan.extra["schedules"],
+            json!([{
+                "flow": "flow.main",
+                "cron": "0 8 * * MON-FRI",
+                "timezone": "Europe/Berlin",
+                "jitter_secs": 0
+            }])
+        );
+        assert_eq!(
+            plan.extra["config_keys"],
+            json!([
+                { "key": "base_url", "required": true, "type": "string" },
+                { "key": "beta_ui", "required": false, "type": "boolean", "default": false }
+            ])
+        );
+        assert_eq!(plan.extra["config_defaults"], json!({ "beta_ui": false }));
+        assert_eq!(
+            plan.extra["optional_imports"],
+            json!(["greentic:host-import/analytics@0.6"])
+        );
         if let Some(messaging) = plan.messaging.as_ref() {
             assert!(
                 messaging
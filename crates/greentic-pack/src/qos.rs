@@ -0,0 +1,156 @@
+//! Quality-of-service classes of flows.
+//!
+//! Hosts that queue runs schedule latency-sensitive flows ahead of batch
+//! work. Flows declare their class, and optionally how soon a run should
+//! finish, keyed by flow id:
+//!
+//! ```yaml
+//! qos:
+//!   weather_bot:
+//!     class: interactive
+//!     deadline_ms: 2000
+//!   nightly_digest:
+//!     class: batch
+//! ```
+//!
+//! Flows without an entry are `standard`. The deadline is a scheduling hint,
+//! not a limit; see [`limits`](crate::limits) for budgets runs are held to.
+//! The build copies each flow's entry onto its
+//! [`FlowEntry`](crate::builder::FlowEntry).
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Result, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// QoS declarations by flow id.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(transparent)]
+pub struct QosSection {
+    pub flows: BTreeMap<String, FlowQos>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct FlowQos {
+    pub class: QosClass,
+    /// How soon after it is queued a run should finish; a hint for
+    /// schedulers, not a limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline_ms: Option<u64>,
+}
+
+/// Scheduling class, from most to least latency-sensitive.
+#[derive(
+    Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum QosClass {
+    /// A user is waiting on the run.
+    Interactive,
+    #[default]
+    Standard,
+    /// Throughput matters more than latency.
+    Batch,
+}
+
+impl QosClass {
+    pub const ALL: [QosClass; 3] = [QosClass::Interactive, QosClass::Standard, QosClass::Batch];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QosClass::Interactive => "interactive",
+            QosClass::Standard => "standard",
+            QosClass::Batch => "batch",
+        }
+    }
+}
+
+impl fmt::Display for QosClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for QosClass {
+    type Err = anyhow::Error;
+
+    fn from_str(class: &str) -> Result<Self> {
+        match QosClass::ALL
+            .into_iter()
+            .find(|known| known.as_str() == class)
+        {
+            Some(class) => Ok(class),
+            None => bail!("unknown QoS class `{class}`; expected interactive, standard, or batch"),
+        }
+    }
+}
+
+impl QosSection {
+    pub fn validate(&self) -> Result<()> {
+        for (flow, qos) in &self.flows {
+            if flow.trim().is_empty() {
+                bail!("qos must be keyed by flow id");
+            }
+            if qos.deadline_ms == Some(0) {
+                bail!("qos.{flow}.deadline_ms must be greater than 0");
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every entry names one of `flows`, the pack's flow ids.
+    pub fn check_flows<'a>(&self, flows: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        let flows: Vec<&str> = flows.into_iter().collect();
+        if let Some(flow) = self
+            .flows
+            .keys()
+            .find(|flow| !flows.contains(&flow.as_str()))
+        {
+            bail!("qos refers to unknown flow `{flow}`");
+        }
+        Ok(())
+    }
+
+    /// The declaration of `flow`; `standard` without a deadline when it has
+    /// none.
+    pub fn flow(&self, flow: &str) -> FlowQos {
+        self.flows.get(flow).copied().unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flows.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_classes_and_defaults_to_standard() {
+        let section: QosSection = serde_yaml_bw::from_str(
+            "weather_bot:\n  class: interactive\n  deadline_ms: 2000\nnightly:\n  class: batch\n",
+        )
+        .unwrap();
+        section.validate().unwrap();
+        section.check_flows(["weather_bot", "nightly"]).unwrap();
+        assert!(section.check_flows(["weather_bot"]).is_err());
+        assert_eq!(section.flow("weather_bot").deadline_ms, Some(2000));
+        assert_eq!(section.flow("other").class, QosClass::Standard);
+        assert!(QosClass::Interactive < QosClass::Batch);
+        assert_eq!("batch".parse::<QosClass>().unwrap(), QosClass::Batch);
+        assert!("urgent".parse::<QosClass>().is_err());
+
+        let zero: QosSection =
+            serde_yaml_bw::from_str("weather_bot: { class: batch, deadline_ms: 0 }\n").unwrap();
+        assert!(zero.validate().is_err());
+    }
+}
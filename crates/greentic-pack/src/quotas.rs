@@ -0,0 +1,118 @@
+//! Rate expectations of flows towards the imports they call.
+//!
+//! Flows that call third-party APIs declare how hard they may hit each import
+//! so hosts can set up rate limiters without reading the flows:
+//!
+//! ```yaml
+//! quotas:
+//!   weather_bot:
+//!     mcp.exec: { requests_per_minute: 60, burst: 10 }
+//! ```
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Quotas by flow id, then by `imports_required` entry.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(transparent)]
+pub struct QuotaSection {
+    pub flows: BTreeMap<String, BTreeMap<String, ImportQuota>>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct ImportQuota {
+    /// Sustained rate the flow may call the import at.
+    pub requests_per_minute: u32,
+    /// Calls allowed at once above the sustained rate; defaults to none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<u32>,
+}
+
+impl QuotaSection {
+    pub fn validate(&self) -> Result<()> {
+        for (flow, imports) in &self.flows {
+            if flow.trim().is_empty() {
+                bail!("quotas must be keyed by flow id");
+            }
+            if imports.is_empty() {
+                bail!("quotas.{flow} must list at least one import");
+            }
+            for (import, quota) in imports {
+                if quota.requests_per_minute == 0 {
+                    bail!("quotas.{flow}.{import}.requests_per_minute must be greater than 0");
+                }
+                if quota.burst == Some(0) {
+                    bail!("quotas.{flow}.{import}.burst must be greater than 0 when set");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every quota names one of `flows` and one of `imports`, the
+    /// pack's flow ids and `imports_required` entries.
+    pub fn check_references<'a>(
+        &self,
+        flows: impl IntoIterator<Item = &'a str>,
+        imports: impl IntoIterator<Item = &'a str>,
+    ) -> Result<()> {
+        let flows: Vec<&str> = flows.into_iter().collect();
+        let imports: Vec<&str> = imports.into_iter().collect();
+        for (flow, quotas) in &self.flows {
+            if !flows.contains(&flow.as_str()) {
+                bail!("quotas refer to unknown flow `{flow}`");
+            }
+            for import in quotas.keys() {
+                if !imports.contains(&import.as_str()) {
+                    bail!("quotas.{flow} refers to `{import}`, which is not in imports_required");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flows.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_rates_and_references() {
+        let section: QuotaSection = serde_yaml_bw::from_str(
+            "weather_bot:\n  mcp.exec: { requests_per_minute: 60, burst: 10 }\n",
+        )
+        .unwrap();
+        section.validate().unwrap();
+        section
+            .check_references(["weather_bot"], ["mcp.exec", "secrets.get"])
+            .unwrap();
+
+        let err = section
+            .check_references(["weather_bot"], ["secrets.get"])
+            .unwrap_err();
+        assert!(err.to_string().contains("not in imports_required"), "{err}");
+        let err = section
+            .check_references(["other"], ["mcp.exec"])
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("unknown flow `weather_bot`"),
+            "{err}"
+        );
+
+        let zero: QuotaSection =
+            serde_yaml_bw::from_str("weather_bot:\n  mcp.exec: { requests_per_minute: 0 }\n")
+                .unwrap();
+        assert!(zero.validate().is_err());
+    }
+}
@@ -1,22 +1,33 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryInto;
+use std::fmt;
 use std::fs::File;
-use std::io::{Read, Seek};
+use std::io::{Cursor, Read, Seek};
 use std::path::Path;
 
 use anyhow::{Context, Result, anyhow, bail};
 use base64::Engine;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use ed25519_dalek::{Signature, Verifier, VerifyingKey};
-use serde::Deserialize;
+use rand_core_06::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
 use x509_parser::pem::parse_x509_pem;
 use x509_parser::prelude::*;
-use zip::ZipArchive;
+use zip::{CompressionMethod, ZipArchive};
 
 use crate::builder::{
-    PackManifest, SBOM_FORMAT, SIGNATURE_CHAIN_PATH, SIGNATURE_PATH, SbomEntry, SignatureEnvelope,
+    FlowEntry, MANIFEST_PATH, MANIFEST_ZSTD_PATH, PackManifest, Provenance, SBOM_FORMAT,
+    SIGNATURE_CHAIN_PATH, SIGNATURE_PATH, SbomEntry, SignatureEnvelope, decompress_manifest,
     hex_hash, signature_digest_from_entries,
 };
+use crate::capabilities::{
+    CapabilityFilter, DegradedFlow, DisabledFlow, FlowRequirements, flow_requirements,
+};
+use crate::config_schema::ConfigSchema;
+use crate::fallbacks::node_fallbacks;
+use crate::import_spec::ImportSpec;
+use crate::media::MediaRef;
+use crate::permissions::LocalizedPermission;
 
 #[cfg(test)]
 const MAX_ARCHIVE_BYTES: u64 = 256 * 1024;
@@ -34,113 +45,849 @@ pub enum SigningPolicy {
     Strict,
 }
 
+/// How many archive entries are hashed against the SBOM when a pack opens.
+///
+/// The manifest and signature are always verified, and every entry must be
+/// present and listed; this only decides which entries' bytes are hashed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BlobVerification {
+    /// Hash every entry.
+    #[default]
+    Full,
+    /// Hash the manifest and a random `percent` of the other entries, picked
+    /// afresh on every open. A mismatch escalates to hashing every entry, so
+    /// the rejection reports how many are affected.
+    Sampled { percent: u8 },
+}
+
+/// Settings for verifying a pack on open. A [`SigningPolicy`] converts into
+/// a config with [`BlobVerification::Full`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PolicyConfig {
+    pub signing: SigningPolicy,
+    pub blobs: BlobVerification,
+}
+
+impl PolicyConfig {
+    /// `signing` with `percent` of the blobs hashed on each open.
+    pub fn sampled(signing: SigningPolicy, percent: u8) -> Self {
+        Self {
+            signing,
+            blobs: BlobVerification::Sampled { percent },
+        }
+    }
+}
+
+impl From<SigningPolicy> for PolicyConfig {
+    fn from(signing: SigningPolicy) -> Self {
+        Self {
+            signing,
+            blobs: BlobVerification::Full,
+        }
+    }
+}
+
+/// Stable, machine-readable reason for a verification finding.
+///
+/// Codes serialise as `snake_case` strings and are never renamed, so hosts can
+/// aggregate them across packs without parsing messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingCode {
+    /// The file could not be opened or is not a zip archive.
+    ArchiveInvalid,
+    /// The archive, in total, exceeds the size limit.
+    ArchiveTooLarge,
+    /// A single entry exceeds the size limit.
+    EntryTooLarge,
+    /// An entry path is absolute, escapes the archive, or is malformed.
+    UnsafeEntryPath,
+    /// An entry is a symlink, device, or other non-regular file.
+    UnsupportedEntryType,
+    DuplicateEntry,
+    ManifestMissing,
+    ManifestInvalid,
+    SbomMissing,
+    SbomInvalid,
+    /// The SBOM lists a file that is not in the archive.
+    SbomFileMissing,
+    SbomHashMismatch,
+    /// The archive contains a file the SBOM does not list.
+    SbomUnlistedFile,
+    SignatureMissing,
+    CertificateChainMissing,
+    SignatureInvalid,
+    SignatureDigestMismatch,
+    SignatureAlgorithmUnsupported,
+    CertificateInvalid,
+    /// The dev self-signed certificate was used under [`SigningPolicy::Strict`].
+    DevCertificateRejected,
+    /// The chain length is unexpected for the policy (warning only).
+    CertificateChainLength,
+    /// A flow was disabled because it needs a capability the host denied
+    /// (warning only).
+    CapabilityDenied,
+    /// A flow runs with fallbacks because the host denied an optional import
+    /// (warning only).
+    ImportDegraded,
+}
+
+impl FindingCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FindingCode::ArchiveInvalid => "archive_invalid",
+            FindingCode::ArchiveTooLarge => "archive_too_large",
+            FindingCode::EntryTooLarge => "entry_too_large",
+            FindingCode::UnsafeEntryPath => "unsafe_entry_path",
+            FindingCode::UnsupportedEntryType => "unsupported_entry_type",
+            FindingCode::DuplicateEntry => "duplicate_entry",
+            FindingCode::ManifestMissing => "manifest_missing",
+            FindingCode::ManifestInvalid => "manifest_invalid",
+            FindingCode::SbomMissing => "sbom_missing",
+            FindingCode::SbomInvalid => "sbom_invalid",
+            FindingCode::SbomFileMissing => "sbom_file_missing",
+            FindingCode::SbomHashMismatch => "sbom_hash_mismatch",
+            FindingCode::SbomUnlistedFile => "sbom_unlisted_file",
+            FindingCode::SignatureMissing => "signature_missing",
+            FindingCode::CertificateChainMissing => "certificate_chain_missing",
+            FindingCode::SignatureInvalid => "signature_invalid",
+            FindingCode::SignatureDigestMismatch => "signature_digest_mismatch",
+            FindingCode::SignatureAlgorithmUnsupported => "signature_algorithm_unsupported",
+            FindingCode::CertificateInvalid => "certificate_invalid",
+            FindingCode::DevCertificateRejected => "dev_certificate_rejected",
+            FindingCode::CertificateChainLength => "certificate_chain_length",
+            FindingCode::CapabilityDenied => "capability_denied",
+            FindingCode::ImportDegraded => "import_degraded",
+        }
+    }
+}
+
+impl fmt::Display for FindingCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single verification result: errors reject the pack, warnings are
+/// reported on an otherwise verified pack.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct VerifyFinding {
+    pub code: FindingCode,
+    pub severity: Severity,
+    /// Archive entry the finding is about, when it concerns a single entry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    /// Human-readable description; wording may change between releases.
+    pub message: String,
+}
+
+impl VerifyFinding {
+    pub fn error(code: FindingCode, subject: Option<&str>, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            severity: Severity::Error,
+            subject: subject.map(str::to_string),
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(code: FindingCode, subject: Option<&str>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(code, subject, message)
+        }
+    }
+}
+
+impl fmt::Display for VerifyFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for VerifyFinding {}
+
+/// What was hashed against the SBOM when a pack was opened.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BlobSampling {
+    /// Entries the SBOM lists.
+    pub listed: usize,
+    /// Entries hashed, the manifest included.
+    pub hashed: usize,
+    pub hashed_bytes: u64,
+    /// Entries picked by [`BlobVerification::Sampled`], in SBOM order; empty
+    /// under full verification.
+    pub sampled: Vec<String>,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct VerifyReport {
+    /// Non-fatal findings for a pack that passed verification.
+    pub findings: Vec<VerifyFinding>,
+    /// Entries hashed on open.
+    pub blobs: BlobSampling,
+    #[deprecated(note = "a loaded pack always has a verified signature; use `findings`")]
     pub signature_ok: bool,
+    #[deprecated(note = "a loaded pack always has a verified SBOM; use `findings`")]
     pub sbom_ok: bool,
+    #[deprecated(note = "use `findings`, which carry a code and subject")]
     pub warnings: Vec<String>,
 }
 
+impl VerifyReport {
+    /// Builds the report for a verified pack, filling the legacy fields from
+    /// `findings`.
+    #[allow(deprecated)]
+    pub fn from_findings(findings: Vec<VerifyFinding>) -> Self {
+        let warnings = findings
+            .iter()
+            .filter(|finding| finding.severity == Severity::Warning)
+            .map(|finding| finding.message.clone())
+            .collect();
+        Self {
+            findings,
+            blobs: BlobSampling::default(),
+            signature_ok: true,
+            sbom_ok: true,
+            warnings,
+        }
+    }
+
+    pub fn has(&self, code: FindingCode) -> bool {
+        self.findings.iter().any(|finding| finding.code == code)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PackLoad {
     pub manifest: PackManifest,
     pub report: VerifyReport,
     pub sbom: Vec<SbomEntry>,
+    /// Flows removed by [`PackLoad::downgrade`]; empty for a full load.
+    pub disabled_flows: Vec<DisabledFlow>,
+    /// Flows [`PackLoad::downgrade`] kept with some optional imports denied.
+    pub degraded_flows: Vec<DegradedFlow>,
+    pub(crate) files: HashMap<String, Vec<u8>>,
+    pub(crate) stored: HashMap<String, StoredEntry>,
+}
+
+/// How an entry is stored in the zip, as recorded in its central directory.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StoredEntry {
+    compressed_size: u64,
+    compression: CompressionMethod,
+}
+
+impl StoredEntry {
+    /// An entry of `size` bytes stored uncompressed, as the builder writes them.
+    #[cfg(feature = "builder")]
+    pub(crate) fn stored(size: u64) -> Self {
+        Self {
+            compressed_size: size,
+            compression: CompressionMethod::Stored,
+        }
+    }
+}
+
+/// One archive entry of a verified pack, for file listings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ArchiveEntry {
+    pub path: String,
+    /// Uncompressed size in bytes.
+    pub size: u64,
+    pub compressed_size: u64,
+    /// Zip compression method, e.g. `stored` or `deflated`.
+    pub compression: String,
+    pub hash_blake3: String,
+    /// Listed in `sbom.json`.
+    pub in_sbom: bool,
+    /// Covered by the pack signature: the manifest, the SBOM, and every
+    /// entry the SBOM lists.
+    pub signed: bool,
+}
+
+/// Metadata of the signature a loaded pack was verified against.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SignatureInfo {
+    pub alg: String,
+    /// BLAKE3 content digest the signature covers.
+    pub digest: String,
+    pub signed_at_utc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_fingerprint: Option<String>,
+}
+
+/// Image payload resolved from the manifest's `media` section.
+#[derive(Debug, Clone, Copy)]
+pub struct MediaBlob<'a> {
+    pub path: &'a str,
+    pub caption: Option<&'a str>,
+    pub media_type: &'static str,
+    pub bytes: &'a [u8],
+}
+
+impl PackLoad {
+    /// Every archive entry, sorted by path.
+    pub fn entries(&self) -> Vec<ArchiveEntry> {
+        let listed: HashSet<&str> = self.sbom.iter().map(|entry| entry.path.as_str()).collect();
+        let mut paths: Vec<&String> = self.files.keys().collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| {
+                let bytes = &self.files[path];
+                let stored = self.stored.get(path);
+                let in_sbom = listed.contains(path.as_str());
+                ArchiveEntry {
+                    path: path.clone(),
+                    size: bytes.len() as u64,
+                    compressed_size: stored.map_or(bytes.len() as u64, |s| s.compressed_size),
+                    compression: stored
+                        .map_or_else(|| "unknown".to_string(), |s| method_name(s.compression)),
+                    hash_blake3: hex_hash(bytes),
+                    in_sbom,
+                    signed: in_sbom || path == "manifest.cbor" || path == "sbom.json",
+                }
+            })
+            .collect()
+    }
+
+    /// Reads what `flow_id` requires from its embedded YAML source.
+    pub fn flow_requirements(&self, flow_id: &str) -> Result<FlowRequirements> {
+        let flow = self
+            .manifest
+            .flows
+            .iter()
+            .find(|flow| flow.id == flow_id)
+            .ok_or_else(|| anyhow!("pack has no flow `{flow_id}`"))?;
+        let yaml = self
+            .file(&flow.file_yaml)
+            .ok_or_else(|| anyhow!("flow `{flow_id}` source {} is missing", flow.file_yaml))?;
+        let yaml = std::str::from_utf8(yaml)
+            .with_context(|| format!("flow `{flow_id}` source is not UTF-8"))?;
+        let imports: Vec<ImportSpec> = self
+            .manifest
+            .meta
+            .imports
+            .iter()
+            .filter_map(|import| {
+                let spec: ImportSpec = import.pack_id.parse().ok()?;
+                Some(spec.with_optional(import.optional))
+            })
+            .collect();
+        flow_requirements(yaml, &imports).with_context(|| format!("flow `{flow_id}`"))
+    }
+
+    /// Returns a reduced view of the pack without the flows that need a
+    /// capability `filter` denies.
+    ///
+    /// Disabled flows are dropped from `manifest.flows` and from the entry
+    /// flows, listed in [`PackLoad::disabled_flows`], and reported as
+    /// [`FindingCode::CapabilityDenied`] warnings. A flow whose only denied
+    /// capabilities are optional imports used by nodes with a fallback stays
+    /// loaded; it is listed in [`PackLoad::degraded_flows`] and reported as a
+    /// [`FindingCode::ImportDegraded`] warning. Archive entries stay readable
+    /// and the content digest is unchanged.
+    pub fn downgrade(mut self, filter: &CapabilityFilter) -> Result<Self> {
+        if filter.is_empty() {
+            return Ok(self);
+        }
+        let optional: Vec<&str> = self
+            .manifest
+            .meta
+            .imports
+            .iter()
+            .filter(|import| import.optional)
+            .map(|import| import.pack_id.as_str())
+            .collect();
+        let mut disabled = Vec::new();
+        let mut degraded = Vec::new();
+        for flow in &self.manifest.flows {
+            let requirements = self.flow_requirements(&flow.id)?;
+            let denied: Vec<String> = requirements
+                .capabilities()
+                .filter(|capability| filter.denies(capability).is_some())
+                .map(str::to_string)
+                .collect();
+            if denied.is_empty() {
+                continue;
+            }
+            if let Some(flow) = self.degrade(flow, &requirements, &denied, &optional)? {
+                degraded.push(flow);
+                continue;
+            }
+            let reason = format!(
+                "flow `{}` is disabled: it requires denied {} {}",
+                flow.id,
+                if denied.len() == 1 {
+                    "capability"
+                } else {
+                    "capabilities"
+                },
+                denied
+                    .iter()
+                    .map(|capability| format!("`{capability}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            disabled.push(DisabledFlow {
+                flow: flow.id.clone(),
+                denied,
+                reason,
+            });
+        }
+
+        let is_disabled = |id: &str| disabled.iter().any(|flow| flow.flow == id);
+        self.manifest.flows.retain(|flow| !is_disabled(&flow.id));
+        self.manifest
+            .meta
+            .entry_flows
+            .retain(|flow| !is_disabled(flow));
+        let mut findings = self.report.findings.clone();
+        findings.extend(disabled.iter().map(|flow| {
+            VerifyFinding::warning(
+                FindingCode::CapabilityDenied,
+                Some(&flow.flow),
+                flow.reason.clone(),
+            )
+        }));
+        findings.extend(degraded.iter().map(|flow| {
+            VerifyFinding::warning(
+                FindingCode::ImportDegraded,
+                Some(&flow.flow),
+                flow.reason.clone(),
+            )
+        }));
+        self.report = VerifyReport {
+            blobs: std::mem::take(&mut self.report.blobs),
+            ..VerifyReport::from_findings(findings)
+        };
+        self.disabled_flows.extend(disabled);
+        self.degraded_flows.extend(degraded);
+        Ok(self)
+    }
+
+    /// `flow` degraded to run without the optional imports among `denied`,
+    /// or `None` when it needs a denied import that is not optional, or a
+    /// node needing a denied capability uses no denied optional import or
+    /// has no fallback.
+    fn degrade(
+        &self,
+        flow: &FlowEntry,
+        requirements: &FlowRequirements,
+        denied: &[String],
+        optional: &[&str],
+    ) -> Result<Option<DegradedFlow>> {
+        let is_optional = |capability: &String| optional.contains(&capability.as_str());
+        if requirements
+            .imports
+            .iter()
+            .any(|import| denied.contains(import) && !is_optional(import))
+        {
+            return Ok(None);
+        }
+        let yaml = self
+            .file(&flow.file_yaml)
+            .and_then(|yaml| std::str::from_utf8(yaml).ok())
+            .unwrap_or_default();
+        let fallbacks = node_fallbacks(yaml).with_context(|| format!("flow `{}`", flow.id))?;
+        let mut used = BTreeMap::new();
+        for (node, capabilities) in &requirements.nodes {
+            let mut node_denied = capabilities
+                .iter()
+                .filter(|capability| denied.contains(capability))
+                .peekable();
+            if node_denied.peek().is_none() {
+                continue;
+            }
+            let Some(fallback) = fallbacks.get(node).filter(|_| node_denied.any(is_optional))
+            else {
+                return Ok(None);
+            };
+            used.insert(node.clone(), fallback.clone());
+        }
+        let denied: Vec<String> = denied.iter().filter(|c| is_optional(c)).cloned().collect();
+        let reason = format!(
+            "flow `{}` runs degraded without optional {}; nodes {} fall back",
+            flow.id,
+            denied
+                .iter()
+                .map(|import| format!("`{import}`"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            used.keys()
+                .map(|node| format!("`{node}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        Ok(Some(DegradedFlow {
+            flow: flow.id.clone(),
+            denied,
+            fallbacks: used,
+            reason,
+        }))
+    }
+
+    /// Returns the raw bytes of a verified archive entry.
+    pub fn file(&self, path: &str) -> Option<&[u8]> {
+        self.files.get(path).map(Vec::as_slice)
+    }
+
+    /// BLAKE3 digest over the SBOM, manifest, and every listed entry; this is
+    /// the value the pack signature covers, so it is stable across re-signing.
+    pub fn content_digest(&self) -> String {
+        let manifest = manifest_bytes(&self.files).unwrap_or_default();
+        let sbom = self.file("sbom.json").unwrap_or_default();
+        signature_digest_from_entries(&self.sbom, &manifest, sbom)
+            .to_hex()
+            .to_string()
+    }
+
+    /// Returns the verified signature envelope.
+    pub fn signature(&self) -> Option<SignatureInfo> {
+        let envelope: SignatureEnvelope =
+            serde_json::from_slice(self.file(SIGNATURE_PATH)?).ok()?;
+        Some(SignatureInfo {
+            alg: envelope.alg,
+            digest: envelope.digest,
+            signed_at_utc: envelope.signed_at_utc,
+            key_fingerprint: envelope.key_fingerprint,
+        })
+    }
+
+    /// Returns the build provenance recorded in `provenance.json`.
+    pub fn provenance(&self) -> Option<Provenance> {
+        serde_json::from_slice(self.file("provenance.json")?).ok()
+    }
+
+    /// Permission prompts in `locale`, falling back to the pack's default
+    /// locale; empty when the manifest carries no summary.
+    pub fn permission_summary(&self, locale: &str) -> Vec<LocalizedPermission<'_>> {
+        self.manifest
+            .meta
+            .permissions
+            .as_ref()
+            .map(|summary| summary.localized(locale))
+            .unwrap_or_default()
+    }
+
+    /// The variant of the template at `logical_path` for the first of
+    /// `locales` (BCP 47 tags, most preferred first) it comes in; the
+    /// template itself when the pack declares no `locales`.
+    pub fn template_variant<'a>(&'a self, logical_path: &'a str, locales: &[&str]) -> &'a str {
+        match &self.manifest.meta.locales {
+            Some(section) => section.variant(logical_path, locales),
+            None => logical_path,
+        }
+    }
+
+    /// JSON Schema of the pack's install-time settings, if it declares one.
+    pub fn config_schema(&self) -> Option<&ConfigSchema> {
+        self.manifest.meta.config_schema.as_ref()
+    }
+
+    /// Returns the embedded `README.md`, if the pack ships one.
+    pub fn readme(&self) -> Option<&str> {
+        self.file(crate::readme::README_PATH)
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+    }
+
+    /// Returns the pack icon declared in `meta.media`, if any.
+    pub fn icon(&self) -> Option<MediaBlob<'_>> {
+        let media = self.manifest.meta.media.as_ref()?;
+        media.icon.as_ref().and_then(|entry| self.media_blob(entry))
+    }
+
+    /// Returns the declared screenshots in manifest order.
+    pub fn screenshots(&self) -> Vec<MediaBlob<'_>> {
+        self.manifest
+            .meta
+            .media
+            .as_ref()
+            .map(|media| {
+                media
+                    .screenshots
+                    .iter()
+                    .filter_map(|entry| self.media_blob(entry))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn media_blob<'a>(&'a self, entry: &'a MediaRef) -> Option<MediaBlob<'a>> {
+        let bytes = self.file(&entry.path)?;
+        Some(MediaBlob {
+            path: &entry.path,
+            caption: entry.caption.as_deref(),
+            media_type: entry.format()?.media_type(),
+            bytes,
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct PackVerifyResult {
     pub message: String,
+    /// The finding that rejected the pack.
+    pub finding: VerifyFinding,
 }
 
 impl PackVerifyResult {
     fn from_error(err: anyhow::Error) -> Self {
-        Self {
-            message: err.to_string(),
-        }
+        let message = err.to_string();
+        let finding = err
+            .downcast_ref::<VerifyFinding>()
+            .cloned()
+            .unwrap_or_else(|| {
+                // I/O and zip errors that were not classified more precisely.
+                VerifyFinding::error(FindingCode::ArchiveInvalid, None, message.clone())
+            });
+        Self { message, finding }
     }
 }
 
-pub fn open_pack(path: &Path, policy: SigningPolicy) -> Result<PackLoad, PackVerifyResult> {
-    match open_pack_inner(path, policy) {
+/// Opens and verifies a pack under `policy`, a [`SigningPolicy`] or a
+/// [`PolicyConfig`].
+pub fn open_pack(
+    path: &Path,
+    policy: impl Into<PolicyConfig>,
+) -> Result<PackLoad, PackVerifyResult> {
+    match open_pack_inner(path, policy.into()) {
         Ok(result) => Ok(result),
         Err(err) => Err(PackVerifyResult::from_error(err)),
     }
 }
 
-fn open_pack_inner(path: &Path, policy: SigningPolicy) -> Result<PackLoad> {
-    let mut archive = ZipArchive::new(
-        File::open(path).with_context(|| format!("failed to open {}", path.display()))?,
-    )
-    .with_context(|| format!("{} is not a valid gtpack archive", path.display()))?;
+/// Opens and verifies a pack, then applies `filter` as [`PackLoad::downgrade`]
+/// does.
+pub fn open_pack_with_filter(
+    path: &Path,
+    policy: impl Into<PolicyConfig>,
+    filter: &CapabilityFilter,
+) -> Result<PackLoad, PackVerifyResult> {
+    open_pack_inner(path, policy.into())
+        .and_then(|load| load.downgrade(filter))
+        .map_err(PackVerifyResult::from_error)
+}
 
-    let (files, total) = read_archive_entries(&mut archive)?;
-    if total > MAX_ARCHIVE_BYTES {
-        bail!(
-            "gtpack archive exceeds maximum allowed size ({} bytes)",
-            MAX_ARCHIVE_BYTES
-        );
-    }
+/// Opens and verifies a pack held in memory, as [`open_pack`] does for a file.
+pub fn open_pack_bytes(
+    bytes: &[u8],
+    policy: impl Into<PolicyConfig>,
+) -> Result<PackLoad, PackVerifyResult> {
+    let policy = policy.into();
+    read_archive(Cursor::new(bytes), "in-memory pack")
+        .and_then(|contents| load_pack(contents, policy))
+        .map_err(PackVerifyResult::from_error)
+}
 
-    let manifest_bytes = files
-        .get("manifest.cbor")
-        .cloned()
-        .ok_or_else(|| anyhow!("manifest.cbor missing from archive"))?;
-    let manifest: PackManifest =
-        serde_cbor::from_slice(&manifest_bytes).context("manifest.cbor is invalid")?;
+fn open_pack_inner(path: &Path, policy: PolicyConfig) -> Result<PackLoad> {
+    load_pack(read_pack_archive(path)?, policy)
+}
 
-    let sbom_bytes = files
-        .get("sbom.json")
-        .cloned()
-        .ok_or_else(|| anyhow!("sbom.json missing from archive"))?;
-    let sbom_doc: SbomDocument =
-        serde_json::from_slice(&sbom_bytes).context("sbom.json is not valid JSON")?;
+pub(crate) fn load_pack(contents: ArchiveContents, policy: PolicyConfig) -> Result<PackLoad> {
+    let ArchiveContents { files, stored } = contents;
+
+    let manifest_bytes = manifest_bytes(&files)?;
+    let manifest: PackManifest = serde_cbor::from_slice(&manifest_bytes).map_err(|err| {
+        reject(
+            FindingCode::ManifestInvalid,
+            Some("manifest.cbor"),
+            format!("manifest.cbor is invalid: {err}"),
+        )
+    })?;
+
+    let sbom_bytes = files.get("sbom.json").cloned().ok_or_else(|| {
+        reject(
+            FindingCode::SbomMissing,
+            Some("sbom.json"),
+            "sbom.json missing from archive",
+        )
+    })?;
+    let sbom_doc: SbomDocument = serde_json::from_slice(&sbom_bytes).map_err(|err| {
+        reject(
+            FindingCode::SbomInvalid,
+            Some("sbom.json"),
+            format!("sbom.json is not valid JSON: {err}"),
+        )
+    })?;
     if sbom_doc.format != SBOM_FORMAT {
-        bail!("unexpected SBOM format: {}", sbom_doc.format);
+        return Err(reject(
+            FindingCode::SbomInvalid,
+            Some("sbom.json"),
+            format!("unexpected SBOM format: {}", sbom_doc.format),
+        ));
     }
 
-    let mut warnings = Vec::new();
-    verify_sbom(&files, &sbom_doc.files)?;
+    let mut findings = Vec::new();
+    let blobs = verify_sbom(&files, &sbom_doc.files, policy.blobs)?;
     verify_signature(
         &files,
         &manifest_bytes,
         &sbom_bytes,
         &sbom_doc.files,
-        policy,
-        &mut warnings,
+        policy.signing,
+        &mut findings,
     )?;
 
     Ok(PackLoad {
         manifest,
         report: VerifyReport {
-            signature_ok: true,
-            sbom_ok: true,
-            warnings,
+            blobs,
+            ..VerifyReport::from_findings(findings)
         },
         sbom: sbom_doc.files,
+        disabled_flows: Vec::new(),
+        degraded_flows: Vec::new(),
+        files,
+        stored,
     })
 }
 
+/// The CBOR manifest of an archive: decompressed from `manifest.cbor.zst` when
+/// present, which must then agree with any plain `manifest.cbor`, and
+/// otherwise `manifest.cbor` itself.
+pub(crate) fn manifest_bytes(files: &HashMap<String, Vec<u8>>) -> Result<Vec<u8>> {
+    let plain = files.get(MANIFEST_PATH);
+    let Some(compressed) = files.get(MANIFEST_ZSTD_PATH) else {
+        return plain.cloned().ok_or_else(|| {
+            reject(
+                FindingCode::ManifestMissing,
+                Some(MANIFEST_PATH),
+                "manifest.cbor missing from archive",
+            )
+        });
+    };
+    let manifest = decompress_manifest(compressed, MAX_FILE_BYTES).map_err(|err| {
+        reject(
+            FindingCode::ManifestInvalid,
+            Some(MANIFEST_ZSTD_PATH),
+            format!("{err:#}"),
+        )
+    })?;
+    if plain.is_some_and(|plain| *plain != manifest) {
+        return Err(reject(
+            FindingCode::ManifestInvalid,
+            Some(MANIFEST_ZSTD_PATH),
+            "manifest.cbor.zst does not match manifest.cbor",
+        ));
+    }
+    Ok(manifest)
+}
+
+/// Reads every archive entry with the usual path and size checks, but without
+/// verifying the SBOM or signature.
+pub(crate) fn read_pack_files(path: &Path) -> Result<HashMap<String, Vec<u8>>> {
+    read_pack_archive(path).map(|contents| contents.files)
+}
+
+/// Entry bytes and storage details, keyed by logical path.
+pub(crate) struct ArchiveContents {
+    pub(crate) files: HashMap<String, Vec<u8>>,
+    pub(crate) stored: HashMap<String, StoredEntry>,
+}
+
+fn read_pack_archive(path: &Path) -> Result<ArchiveContents> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    read_archive(file, &path.display().to_string())
+}
+
+fn read_archive<R: Read + Seek>(reader: R, name: &str) -> Result<ArchiveContents> {
+    let mut archive =
+        ZipArchive::new(reader).with_context(|| format!("{name} is not a valid gtpack archive"))?;
+    read_archive_entries(&mut archive)
+}
+
+/// Checks that an entry of `size` bytes may join `files`: its path is new and
+/// it fits the per-entry and whole-archive limits, counted in `total`.
+pub(crate) fn admit_entry(
+    files: &HashMap<String, Vec<u8>>,
+    logical: &str,
+    size: u64,
+    total: &mut u64,
+) -> Result<()> {
+    if files.contains_key(logical) {
+        return Err(reject(
+            FindingCode::DuplicateEntry,
+            Some(logical),
+            format!("duplicate entry detected: {logical}"),
+        ));
+    }
+    if size > MAX_FILE_BYTES {
+        return Err(reject(
+            FindingCode::EntryTooLarge,
+            Some(logical),
+            format!("entry {logical} exceeds maximum allowed size of {MAX_FILE_BYTES} bytes"),
+        ));
+    }
+    *total = total
+        .checked_add(size)
+        .ok_or_else(|| reject(FindingCode::ArchiveTooLarge, None, "archive size overflow"))?;
+    if *total > MAX_ARCHIVE_BYTES {
+        return Err(reject(
+            FindingCode::ArchiveTooLarge,
+            None,
+            format!("gtpack archive exceeds maximum allowed size ({MAX_ARCHIVE_BYTES} bytes)"),
+        ));
+    }
+    Ok(())
+}
+
+fn method_name(method: CompressionMethod) -> String {
+    match method {
+        CompressionMethod::Stored => "stored".to_string(),
+        CompressionMethod::Deflated => "deflated".to_string(),
+        other => format!("{other:?}").to_ascii_lowercase(),
+    }
+}
+
+/// Wraps a rejecting finding so it can travel through `anyhow` and be
+/// recovered by [`PackVerifyResult::from_error`].
+fn reject(code: FindingCode, subject: Option<&str>, message: impl Into<String>) -> anyhow::Error {
+    VerifyFinding::error(code, subject, message).into()
+}
+
 #[derive(Deserialize)]
 struct SbomDocument {
     format: String,
     files: Vec<SbomEntry>,
 }
 
-fn verify_sbom(files: &HashMap<String, Vec<u8>>, entries: &[SbomEntry]) -> Result<()> {
+fn verify_sbom(
+    files: &HashMap<String, Vec<u8>>,
+    entries: &[SbomEntry],
+    blobs: BlobVerification,
+) -> Result<BlobSampling> {
     let mut listed = HashSet::new();
     for entry in entries {
-        let data = files
-            .get(&entry.path)
-            .ok_or_else(|| anyhow!("sbom references missing file `{}`", entry.path))?;
-        let actual = hex_hash(data);
-        if !actual.eq_ignore_ascii_case(&entry.hash_blake3) {
-            bail!(
-                "hash mismatch for {}: expected {}, found {}",
-                entry.path,
-                entry.hash_blake3,
-                actual
-            );
+        if !files.contains_key(&entry.path) {
+            return Err(reject(
+                FindingCode::SbomFileMissing,
+                Some(&entry.path),
+                format!("sbom references missing file `{}`", entry.path),
+            ));
         }
         listed.insert(entry.path.clone());
     }
@@ -150,11 +897,93 @@ fn verify_sbom(files: &HashMap<String, Vec<u8>>, entries: &[SbomEntry]) -> Resul
             continue;
         }
         if !listed.contains(path) {
-            bail!("file `{}` missing from sbom.json", path);
+            return Err(reject(
+                FindingCode::SbomUnlistedFile,
+                Some(path),
+                format!("file `{path}` missing from sbom.json"),
+            ));
         }
     }
 
-    Ok(())
+    let sample = match blobs {
+        BlobVerification::Full => None,
+        BlobVerification::Sampled { percent } => Some(Sample::new(percent)),
+    };
+    let mut sampling = BlobSampling {
+        listed: entries.len(),
+        ..BlobSampling::default()
+    };
+    for entry in entries {
+        let always = entry.path == MANIFEST_PATH || entry.path == MANIFEST_ZSTD_PATH;
+        if let Some(sample) = &sample {
+            if !always && !sample.includes(&entry.path) {
+                continue;
+            }
+            if !always {
+                sampling.sampled.push(entry.path.clone());
+            }
+        }
+        let data = &files[&entry.path];
+        sampling.hashed += 1;
+        sampling.hashed_bytes += data.len() as u64;
+        if let Some(mismatch) = hash_mismatch(entry, data) {
+            if sample.is_none() {
+                return Err(mismatch.into());
+            }
+            let mismatched = entries
+                .iter()
+                .filter(|entry| hash_mismatch(entry, &files[&entry.path]).is_some())
+                .count();
+            return Err(VerifyFinding {
+                message: format!(
+                    "{}; sampled verification escalated to every entry and found {mismatched} mismatched",
+                    mismatch.message
+                ),
+                ..mismatch
+            }
+            .into());
+        }
+    }
+
+    Ok(sampling)
+}
+
+fn hash_mismatch(entry: &SbomEntry, data: &[u8]) -> Option<VerifyFinding> {
+    let actual = hex_hash(data);
+    (!actual.eq_ignore_ascii_case(&entry.hash_blake3)).then(|| {
+        VerifyFinding::error(
+            FindingCode::SbomHashMismatch,
+            Some(&entry.path),
+            format!(
+                "hash mismatch for {}: expected {}, found {}",
+                entry.path, entry.hash_blake3, actual
+            ),
+        )
+    })
+}
+
+/// A random pick of entries, by a keyed hash of their path.
+struct Sample {
+    key: [u8; 32],
+    percent: u64,
+}
+
+impl Sample {
+    fn new(percent: u8) -> Self {
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        Self {
+            key,
+            percent: u64::from(percent.min(100)),
+        }
+    }
+
+    fn includes(&self, path: &str) -> bool {
+        let hash = blake3::keyed_hash(&self.key, path.as_bytes());
+        let mut prefix = [0u8; 8];
+        prefix.copy_from_slice(&hash.as_bytes()[..8]);
+        u64::from_le_bytes(prefix) % 100 < self.percent
+    }
 }
 
 fn verify_signature(
@@ -163,26 +992,49 @@ fn verify_signature(
     sbom_bytes: &[u8],
     entries: &[SbomEntry],
     policy: SigningPolicy,
-    warnings: &mut Vec<String>,
+    findings: &mut Vec<VerifyFinding>,
 ) -> Result<()> {
-    let signature_bytes = files
-        .get(SIGNATURE_PATH)
-        .ok_or_else(|| anyhow!("signature file `{}` missing", SIGNATURE_PATH))?;
-    let chain_bytes = files
-        .get(SIGNATURE_CHAIN_PATH)
-        .ok_or_else(|| anyhow!("certificate chain `{}` missing", SIGNATURE_CHAIN_PATH))?;
-
-    let envelope: SignatureEnvelope =
-        serde_json::from_slice(signature_bytes).context("signatures/pack.sig is not valid JSON")?;
+    let signature_bytes = files.get(SIGNATURE_PATH).ok_or_else(|| {
+        reject(
+            FindingCode::SignatureMissing,
+            Some(SIGNATURE_PATH),
+            format!("signature file `{SIGNATURE_PATH}` missing"),
+        )
+    })?;
+    let chain_bytes = files.get(SIGNATURE_CHAIN_PATH).ok_or_else(|| {
+        reject(
+            FindingCode::CertificateChainMissing,
+            Some(SIGNATURE_CHAIN_PATH),
+            format!("certificate chain `{SIGNATURE_CHAIN_PATH}` missing"),
+        )
+    })?;
+
+    let envelope: SignatureEnvelope = serde_json::from_slice(signature_bytes).map_err(|err| {
+        reject(
+            FindingCode::SignatureInvalid,
+            Some(SIGNATURE_PATH),
+            format!("{SIGNATURE_PATH} is not valid JSON: {err}"),
+        )
+    })?;
     let digest = signature_digest_from_entries(entries, manifest_bytes, sbom_bytes);
     let digest_hex = digest.to_hex().to_string();
     if !digest_hex.eq_ignore_ascii_case(&envelope.digest) {
-        bail!("signature digest mismatch");
+        return Err(reject(
+            FindingCode::SignatureDigestMismatch,
+            Some(SIGNATURE_PATH),
+            "signature digest mismatch",
+        ));
     }
 
     match envelope.alg.to_ascii_lowercase().as_str() {
-        "ed25519" => verify_ed25519_signature(&envelope, digest, chain_bytes, policy, warnings)?,
-        other => bail!("unsupported signature algorithm: {}", other),
+        "ed25519" => verify_ed25519_signature(&envelope, digest, chain_bytes, policy, findings)?,
+        other => {
+            return Err(reject(
+                FindingCode::SignatureAlgorithmUnsupported,
+                Some(SIGNATURE_PATH),
+                format!("unsupported signature algorithm: {other}"),
+            ));
+        }
     }
 
     Ok(())
@@ -193,24 +1045,33 @@ fn verify_ed25519_signature(
     digest: blake3::Hash,
     chain_bytes: &[u8],
     policy: SigningPolicy,
-    warnings: &mut Vec<String>,
+    findings: &mut Vec<VerifyFinding>,
 ) -> Result<()> {
+    let invalid_signature =
+        |message: String| reject(FindingCode::SignatureInvalid, Some(SIGNATURE_PATH), message);
     let sig_raw = URL_SAFE_NO_PAD
         .decode(envelope.sig.as_bytes())
-        .map_err(|err| anyhow!("invalid signature encoding: {err}"))?;
+        .map_err(|err| invalid_signature(format!("invalid signature encoding: {err}")))?;
     let sig_array: [u8; 64] = sig_raw
         .as_slice()
         .try_into()
-        .map_err(|_| anyhow!("signature must be 64 bytes"))?;
+        .map_err(|_| invalid_signature("signature must be 64 bytes".into()))?;
     let signature = Signature::from_bytes(&sig_array);
 
-    let cert_der = parse_certificate_chain(chain_bytes)?;
-    enforce_policy(&cert_der, policy, warnings)?;
-    let first_cert = parse_certificate(&cert_der[0])?;
-    let verifying_key = extract_ed25519_key(&first_cert)?;
+    let invalid_certificate = |err: anyhow::Error| {
+        reject(
+            FindingCode::CertificateInvalid,
+            Some(SIGNATURE_CHAIN_PATH),
+            err.to_string(),
+        )
+    };
+    let cert_der = parse_certificate_chain(chain_bytes).map_err(invalid_certificate)?;
+    enforce_policy(&cert_der, policy, findings)?;
+    let first_cert = parse_certificate(&cert_der[0]).map_err(invalid_certificate)?;
+    let verifying_key = extract_ed25519_key(&first_cert).map_err(invalid_certificate)?;
     verifying_key
         .verify(digest.as_bytes(), &signature)
-        .map_err(|err| anyhow!("signature verification failed: {err}"))?;
+        .map_err(|err| invalid_signature(format!("signature verification failed: {err}")))?;
     Ok(())
 }
 
@@ -259,7 +1120,7 @@ fn parse_certificate_chain(mut data: &[u8]) -> Result<Vec<Vec<u8>>> {
 fn enforce_policy(
     certs: &[Vec<u8>],
     policy: SigningPolicy,
-    warnings: &mut Vec<String>,
+    findings: &mut Vec<VerifyFinding>,
 ) -> Result<()> {
     let first = certs
         .first()
@@ -270,15 +1131,23 @@ fn enforce_policy(
     match policy {
         SigningPolicy::DevOk => {
             if certs.len() != 1 {
-                warnings.push(format!(
-                    "chain contains {} certificates; dev mode expects exactly 1",
-                    certs.len()
+                findings.push(VerifyFinding::warning(
+                    FindingCode::CertificateChainLength,
+                    Some(SIGNATURE_CHAIN_PATH),
+                    format!(
+                        "chain contains {} certificates; dev mode expects exactly 1",
+                        certs.len()
+                    ),
                 ));
             }
         }
         SigningPolicy::Strict => {
             if is_dev {
-                bail!("dev self-signed certificate is not allowed under strict policy");
+                return Err(reject(
+                    FindingCode::DevCertificateRejected,
+                    Some(SIGNATURE_CHAIN_PATH),
+                    "dev self-signed certificate is not allowed under strict policy",
+                ));
             }
         }
     }
@@ -306,10 +1175,9 @@ fn trim_leading(mut data: &[u8]) -> &[u8] {
     data
 }
 
-fn read_archive_entries<R: Read + Seek>(
-    archive: &mut ZipArchive<R>,
-) -> Result<(HashMap<String, Vec<u8>>, u64)> {
+fn read_archive_entries<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<ArchiveContents> {
     let mut files = HashMap::new();
+    let mut stored = HashMap::new();
     let mut total = 0u64;
 
     for idx in 0..archive.len() {
@@ -321,41 +1189,54 @@ fn read_archive_entries<R: Read + Seek>(
             continue;
         }
         if !entry.is_file() {
-            bail!("archive entry {} is not a regular file", entry.name());
+            return Err(reject(
+                FindingCode::UnsupportedEntryType,
+                Some(entry.name()),
+                format!("archive entry {} is not a regular file", entry.name()),
+            ));
         }
 
         if let Some(mode) = entry.unix_mode() {
             let file_type = mode & 0o170000;
             if file_type != 0o100000 {
-                bail!(
-                    "unsupported file type for entry {}; only regular files are allowed",
-                    entry.name()
-                );
+                return Err(reject(
+                    FindingCode::UnsupportedEntryType,
+                    Some(entry.name()),
+                    format!(
+                        "unsupported file type for entry {}; only regular files are allowed",
+                        entry.name()
+                    ),
+                ));
             }
         }
 
         let enclosed_path = entry
             .enclosed_name()
-            .ok_or_else(|| anyhow!("archive entry contains unsafe path: {}", entry.name()))?
+            .ok_or_else(|| {
+                reject(
+                    FindingCode::UnsafeEntryPath,
+                    Some(entry.name()),
+                    format!("archive entry contains unsafe path: {}", entry.name()),
+                )
+            })?
             .to_path_buf();
-        let logical = normalize_entry_path(&enclosed_path)?;
-        if files.contains_key(&logical) {
-            bail!("duplicate entry detected: {}", logical);
-        }
-
+        let logical = normalize_entry_path(&enclosed_path).map_err(|err| {
+            reject(
+                FindingCode::UnsafeEntryPath,
+                Some(entry.name()),
+                err.to_string(),
+            )
+        })?;
         let size = entry.size();
-        if size > MAX_FILE_BYTES {
-            bail!(
-                "entry {} exceeds maximum allowed size of {} bytes",
-                logical,
-                MAX_FILE_BYTES
-            );
-        }
-
-        total = total
-            .checked_add(size)
-            .ok_or_else(|| anyhow!("archive size overflow"))?;
-
+        admit_entry(&files, &logical, size, &mut total)?;
+
+        stored.insert(
+            logical.clone(),
+            StoredEntry {
+                compressed_size: entry.compressed_size(),
+                compression: entry.compression(),
+            },
+        );
         let mut buf = Vec::with_capacity(size as usize);
         entry
             .read_to_end(&mut buf)
@@ -363,10 +1244,10 @@ fn read_archive_entries<R: Read + Seek>(
         files.insert(logical, buf);
     }
 
-    Ok((files, total))
+    Ok(ArchiveContents { files, stored })
 }
 
-fn normalize_entry_path(path: &Path) -> Result<String> {
+pub(crate) fn normalize_entry_path(path: &Path) -> Result<String> {
     if path.is_absolute() {
         bail!("archive entry uses absolute path: {}", path.display());
     }
@@ -393,7 +1274,12 @@ fn normalize_entry_path(path: &Path) -> Result<String> {
                 if segment.is_empty() {
                     bail!("entry contains empty path segment");
                 }
-                normalized.push(segment.replace('\\', "/"));
+                // A `\\` is a separator on Windows but part of the name here,
+                // so `..\\x` would get past the checks above.
+                if segment.contains('\\') {
+                    bail!("archive entry uses `\\` in its path: {}", path.display());
+                }
+                normalized.push(segment.to_string());
             }
             std::path::Component::CurDir => continue,
             _ => bail!(
@@ -410,13 +1296,22 @@ fn normalize_entry_path(path: &Path) -> Result<String> {
     Ok(normalized.join("/"))
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "builder"))]
 mod tests {
-    use super::{MAX_ARCHIVE_BYTES, MAX_FILE_BYTES, SigningPolicy, open_pack};
-    use crate::builder::SIGNATURE_CHAIN_PATH;
+    use super::{
+        FindingCode, MAX_ARCHIVE_BYTES, MAX_FILE_BYTES, PolicyConfig, Severity, SigningPolicy,
+        open_pack, open_pack_bytes, open_pack_with_filter,
+    };
     use crate::builder::{
-        ComponentArtifact, FlowBundle, PackBuilder, PackMeta, Provenance, Signing,
+        ComponentArtifact, FlowBundle, ImportRef, ManifestEncoding, PackBuilder, PackMeta,
+        Provenance, Signing, WasmSource,
     };
+    use crate::builder::{
+        MANIFEST_PATH, MANIFEST_ZSTD_PATH, SIGNATURE_CHAIN_PATH, SIGNATURE_PATH, hex_hash,
+    };
+    use crate::capabilities::CapabilityFilter;
+    use crate::fallbacks::NodeFallback;
+    use crate::media::{MediaRef, MediaSection};
     use blake3;
     use semver::Version;
     use serde_json::{Map, json};
@@ -432,7 +1327,18 @@ mod tests {
         let (_dir, path) = build_pack(true);
         let load = open_pack(&path, SigningPolicy::DevOk).expect("reader validates pack");
         assert_eq!(load.manifest.meta.pack_id, "ai.greentic.demo.reader");
-        assert!(load.report.warnings.is_empty());
+        assert!(load.report.findings.is_empty());
+    }
+
+    #[test]
+    fn open_pack_bytes_reads_archives_in_memory() {
+        let (_dir, path) = build_pack(true);
+        let bytes = fs::read(&path).unwrap();
+        let load = open_pack_bytes(&bytes, SigningPolicy::DevOk).expect("reader validates pack");
+        assert_eq!(load.manifest.meta.pack_id, "ai.greentic.demo.reader");
+
+        let err = open_pack_bytes(&bytes[..bytes.len() / 2], SigningPolicy::DevOk).unwrap_err();
+        assert_eq!(err.finding.code, FindingCode::ArchiveInvalid);
     }
 
     #[test]
@@ -440,6 +1346,8 @@ mod tests {
         let (_dir, path) = build_pack(false);
         let err = open_pack(&path, SigningPolicy::DevOk).unwrap_err();
         assert!(err.message.contains("signature"));
+        assert_eq!(err.finding.code, FindingCode::SignatureMissing);
+        assert_eq!(err.finding.severity, Severity::Error);
     }
 
     #[test]
@@ -447,21 +1355,154 @@ mod tests {
         let (_dir, path) = build_pack(true);
         let err = open_pack(&path, SigningPolicy::Strict).unwrap_err();
         assert!(err.message.contains("strict"));
+        assert_eq!(err.finding.code, FindingCode::DevCertificateRejected);
+        assert_eq!(
+            err.finding.subject.as_deref(),
+            Some(SIGNATURE_CHAIN_PATH),
+            "finding names the chain entry"
+        );
     }
 
     #[test]
+    #[allow(deprecated)]
     fn dev_policy_warns_for_multi_certificate_chain() {
         let (_dir, original) = build_pack(true);
         let (_tmp, rewritten) = duplicate_chain(&original);
         let load = open_pack(&rewritten, SigningPolicy::DevOk).expect("dev policy accepts");
+        assert!(load.report.has(FindingCode::CertificateChainLength));
+        let finding = &load.report.findings[0];
+        assert_eq!(finding.severity, Severity::Warning);
+        assert_eq!(
+            serde_json::to_value(finding).unwrap()["code"],
+            "certificate_chain_length"
+        );
+
+        // Legacy fields are still filled for older callers.
+        assert!(load.report.signature_ok && load.report.sbom_ok);
         assert!(load.report.warnings.iter().any(|msg| msg.contains("chain")));
     }
 
+    #[test]
+    fn tampered_entry_is_reported_as_hash_mismatch() {
+        let (_dir, original) = build_pack(true);
+        let (_tmp, rewritten) =
+            rewrite_entry(&original, "components/demo@1.0.0/component.wasm", |data| {
+                data.push(0)
+            });
+        let err = open_pack(&rewritten, SigningPolicy::DevOk).unwrap_err();
+        assert_eq!(err.finding.code, FindingCode::SbomHashMismatch);
+        assert_eq!(
+            err.finding.subject.as_deref(),
+            Some("components/demo@1.0.0/component.wasm")
+        );
+    }
+
+    #[test]
+    fn sampled_verification_hashes_the_manifest_and_a_share_of_blobs() {
+        let (_dir, path) = build_pack(true);
+        let full = open_pack(&path, SigningPolicy::DevOk).unwrap().report.blobs;
+        assert_eq!(full.hashed, full.listed);
+        assert!(full.sampled.is_empty());
+
+        let none = open_pack(&path, PolicyConfig::sampled(SigningPolicy::DevOk, 0))
+            .unwrap()
+            .report
+            .blobs;
+        assert_eq!(none.hashed, 1, "only the manifest is always hashed");
+        assert!(none.sampled.is_empty());
+
+        let all = open_pack(&path, PolicyConfig::sampled(SigningPolicy::DevOk, 100))
+            .unwrap()
+            .report
+            .blobs;
+        assert_eq!(all.hashed, all.listed);
+        assert_eq!(all.sampled.len(), all.listed - 1);
+
+        let (_tmp, rewritten) =
+            rewrite_entry(&path, "components/demo@1.0.0/component.wasm", |data| {
+                data.push(0)
+            });
+        let unchecked = open_pack(&rewritten, PolicyConfig::sampled(SigningPolicy::DevOk, 0));
+        assert!(unchecked.is_ok(), "unsampled blobs are not hashed");
+        let err =
+            open_pack(&rewritten, PolicyConfig::sampled(SigningPolicy::DevOk, 100)).unwrap_err();
+        assert_eq!(err.finding.code, FindingCode::SbomHashMismatch);
+        assert!(
+            err.message.contains("found 1 mismatched"),
+            "{}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn compressed_manifest_is_preferred_and_verified() {
+        let dir = tempdir().unwrap();
+        let build = |encoding: ManifestEncoding| {
+            let out = dir.path().join(format!("{encoding:?}.gtpack"));
+            let result = PackBuilder::new(sample_meta())
+                .with_flow(sample_flow())
+                .with_manifest_encoding(encoding)
+                .build(&out)
+                .unwrap();
+            (out, result.manifest_sizes)
+        };
+
+        let (plain, plain_sizes) = build(ManifestEncoding::Plain);
+        let (both, both_sizes) = build(ManifestEncoding::Both);
+        let (zstd, _) = build(ManifestEncoding::Zstd);
+        assert_eq!(plain_sizes.zstd, None);
+        assert_eq!(both_sizes.cbor, plain_sizes.cbor);
+        assert!(both_sizes.zstd.is_some());
+
+        let plain = open_pack(&plain, SigningPolicy::DevOk).unwrap();
+        let zstd_load = open_pack(&zstd, SigningPolicy::DevOk).unwrap();
+        assert!(zstd_load.file(MANIFEST_PATH).is_none());
+        assert!(zstd_load.file(MANIFEST_ZSTD_PATH).is_some());
+        assert_eq!(zstd_load.manifest.meta.pack_id, plain.manifest.meta.pack_id);
+        assert_eq!(zstd_load.manifest.flows.len(), plain.manifest.flows.len());
+        open_pack(&both, SigningPolicy::DevOk).unwrap();
+
+        let (_tmp, tampered) = rewrite_entry(&zstd, MANIFEST_ZSTD_PATH, |data| {
+            *data = b"not a zstd frame".to_vec()
+        });
+        let err = open_pack(&tampered, SigningPolicy::DevOk).unwrap_err();
+        assert_eq!(err.finding.code, FindingCode::ManifestInvalid);
+    }
+
+    #[test]
+    fn entries_report_storage_digest_and_coverage() {
+        let (_dir, path) = build_pack(true);
+        let load = open_pack(&path, SigningPolicy::DevOk).unwrap();
+        let entries = load.entries();
+
+        let paths: Vec<&str> = entries.iter().map(|entry| entry.path.as_str()).collect();
+        let mut sorted = paths.clone();
+        sorted.sort();
+        assert_eq!(paths, sorted);
+        assert_eq!(entries.len(), load.files.len());
+
+        let coverage = |path: &str| {
+            let entry = entries.iter().find(|entry| entry.path == path).unwrap();
+            (entry.in_sbom, entry.signed)
+        };
+        assert_eq!(coverage("manifest.cbor"), (true, true));
+        assert_eq!(coverage("sbom.json"), (false, true));
+        assert_eq!(coverage(SIGNATURE_PATH), (false, false));
+
+        let wasm = entries
+            .iter()
+            .find(|entry| entry.path == "components/demo@1.0.0/component.wasm")
+            .unwrap();
+        assert_eq!(wasm.hash_blake3, hex_hash(load.file(&wasm.path).unwrap()));
+        assert!(["stored", "deflated"].contains(&wasm.compression.as_str()));
+    }
+
     #[test]
     fn path_traversal_entry_is_rejected() {
         let (_dir, path) = custom_zip(&[zip_entry("../evil", b"oops")]);
         let err = open_pack(&path, SigningPolicy::DevOk).unwrap_err();
         assert!(err.message.contains("unsafe path") || err.message.contains("invalid path"));
+        assert_eq!(err.finding.code, FindingCode::UnsafeEntryPath);
     }
 
     #[test]
@@ -496,6 +1537,140 @@ mod tests {
         let (_dir, path) = custom_zip(&entries);
         let err = open_pack(&path, SigningPolicy::DevOk).unwrap_err();
         assert!(err.message.contains("archive exceeds"));
+        assert_eq!(err.finding.code, FindingCode::ArchiveTooLarge);
+    }
+
+    #[test]
+    fn media_accessors_return_icon_and_screenshots() {
+        let dir = tempdir().unwrap();
+        let png = b"\x89PNG\r\n\x1a\npixels".to_vec();
+        let mut meta = sample_meta();
+        meta.media = Some(MediaSection {
+            icon: Some(MediaRef {
+                path: "media/icon.png".into(),
+                caption: None,
+            }),
+            screenshots: vec![MediaRef {
+                path: "media/shots/home.png".into(),
+                caption: Some("Home screen".into()),
+            }],
+        });
+        let out = dir.path().join("media.gtpack");
+        PackBuilder::new(meta)
+            .with_flow(sample_flow())
+            .with_media_bytes("icon.png", png.clone())
+            .with_media_bytes("shots/home.png", png.clone())
+            .with_provenance(sample_provenance())
+            .build(&out)
+            .unwrap();
+
+        let load = open_pack(&out, SigningPolicy::DevOk).expect("pack opens");
+        let icon = load.icon().expect("icon present");
+        assert_eq!(icon.media_type, "image/png");
+        assert_eq!(icon.bytes, png.as_slice());
+        let shots = load.screenshots();
+        assert_eq!(shots.len(), 1);
+        assert_eq!(shots[0].caption, Some("Home screen"));
+    }
+
+    #[test]
+    fn readme_is_exposed_when_embedded() {
+        let dir = tempdir().unwrap();
+        let out = dir.path().join("readme.gtpack");
+        PackBuilder::new(sample_meta())
+            .with_flow(sample_flow())
+            .with_readme("# Reader Demo\r\n")
+            .with_provenance(sample_provenance())
+            .build(&out)
+            .unwrap();
+
+        let load = open_pack(&out, SigningPolicy::DevOk).expect("pack opens");
+        assert_eq!(load.readme(), Some("# Reader Demo\n"));
+
+        let (_dir, plain) = build_pack(true);
+        let load = open_pack(&plain, SigningPolicy::DevOk).expect("pack opens");
+        assert!(load.readme().is_none());
+    }
+
+    #[test]
+    fn downgrade_disables_flows_needing_denied_capabilities() {
+        let dir = tempdir().unwrap();
+        let out = dir.path().join("downgrade.gtpack");
+        let mut meta = sample_meta();
+        meta.imports = vec![ImportRef {
+            pack_id: "greentic:host-import/email@0.6".into(),
+            version_req: "*".into(),
+            optional: false,
+        }];
+        meta.entry_flows = vec!["demo".into(), "notify".into()];
+        let mut notify = sample_flow();
+        notify.id = "notify".into();
+        notify.yaml =
+            "id: notify\ntype: messaging\nnodes:\n  send:\n    email.send:\n      to: ops\n".into();
+        PackBuilder::new(meta)
+            .with_flow(sample_flow())
+            .with_flow(notify)
+            .with_provenance(sample_provenance())
+            .build(&out)
+            .unwrap();
+
+        let full = open_pack(&out, SigningPolicy::DevOk).expect("pack opens");
+        let digest = full.content_digest();
+        assert_eq!(
+            full.flow_requirements("notify")
+                .unwrap()
+                .capabilities()
+                .collect::<Vec<_>>(),
+            vec!["email.send", "greentic:host-import/email@0.6"]
+        );
+        let unchanged = full.clone().downgrade(&CapabilityFilter::new()).unwrap();
+        assert_eq!(unchanged.manifest.flows.len(), 2);
+
+        let filter = CapabilityFilter::new().deny("email");
+        let load = open_pack_with_filter(&out, SigningPolicy::DevOk, &filter).expect("pack opens");
+        let flows: Vec<&str> = load.manifest.flows.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(flows, vec!["demo"]);
+        assert_eq!(load.manifest.meta.entry_flows, vec!["demo".to_string()]);
+        assert_eq!(load.disabled_flows.len(), 1);
+        assert_eq!(load.disabled_flows[0].flow, "notify");
+        assert_eq!(
+            load.disabled_flows[0].denied,
+            vec!["email.send", "greentic:host-import/email@0.6"]
+        );
+        assert!(load.report.has(FindingCode::CapabilityDenied));
+        assert_eq!(load.content_digest(), digest);
+    }
+
+    #[test]
+    fn downgrade_degrades_flows_with_fallbacks_for_optional_imports() {
+        let dir = tempdir().unwrap();
+        let out = dir.path().join("degrade.gtpack");
+        let mut meta = sample_meta();
+        meta.imports = vec![ImportRef {
+            pack_id: "greentic:host-import/email@0.6".into(),
+            version_req: "*".into(),
+            optional: true,
+        }];
+        let mut notify = sample_flow();
+        notify.id = "notify".into();
+        notify.yaml = "id: notify\ntype: messaging\nnodes:\n  send:\n    email.send:\n      to: ops\n    fallback: skip\n".into();
+        PackBuilder::new(meta)
+            .with_flow(sample_flow())
+            .with_flow(notify)
+            .with_provenance(sample_provenance())
+            .build(&out)
+            .unwrap();
+
+        let filter = CapabilityFilter::new().deny("email");
+        let load = open_pack_with_filter(&out, SigningPolicy::DevOk, &filter).expect("pack opens");
+        assert_eq!(load.manifest.flows.len(), 2);
+        assert!(load.disabled_flows.is_empty());
+        assert_eq!(load.degraded_flows.len(), 1);
+        let degraded = &load.degraded_flows[0];
+        assert_eq!(degraded.flow, "notify");
+        assert_eq!(degraded.denied, vec!["greentic:host-import/email@0.6"]);
+        assert_eq!(degraded.fallbacks["send"], NodeFallback::Skip);
+        assert!(load.report.has(FindingCode::ImportDegraded));
     }
 
     fn temp_wasm(dir: &Path) -> PathBuf {
@@ -517,6 +1692,8 @@ mod tests {
             homepage: None,
             support: None,
             vendor: None,
+            tags: Vec::new(),
+            categories: Vec::new(),
             imports: vec![],
             entry_flows: vec!["demo".into()],
             created_at_utc: "2025-01-01T00:00:00Z".into(),
@@ -524,6 +1701,23 @@ mod tests {
             repo: None,
             messaging: None,
             interfaces: Vec::new(),
+            media: None,
+            config: None,
+            config_schema: None,
+            config_defaults: None,
+            lifecycle: None,
+            quotas: None,
+            limits: None,
+            dispatch: None,
+            qos: None,
+            secrets: None,
+            capabilities: None,
+            metrics: None,
+            schedule: None,
+            classification: None,
+            locales: None,
+            data_handling: None,
+            permissions: None,
             annotations: Map::new(),
         }
     }
@@ -557,6 +1751,7 @@ mod tests {
             built_at_utc: "2025-01-01T00:00:00Z".into(),
             host: None,
             notes: None,
+            profile_blake3: None,
         }
     }
 
@@ -569,7 +1764,7 @@ mod tests {
             .with_component(ComponentArtifact {
                 name: "demo".into(),
                 version: Version::parse("1.0.0").unwrap(),
-                wasm_path: wasm,
+                wasm: WasmSource::Path(wasm),
                 schema_json: None,
                 manifest_json: None,
                 capabilities: None,
@@ -621,6 +1816,18 @@ mod tests {
     }
 
     fn duplicate_chain(original: &Path) -> (TempDir, PathBuf) {
+        rewrite_entry(original, SIGNATURE_CHAIN_PATH, |data| {
+            let original = data.clone();
+            data.push(b'\n');
+            data.extend_from_slice(&original);
+        })
+    }
+
+    fn rewrite_entry(
+        original: &Path,
+        name: &str,
+        edit: impl Fn(&mut Vec<u8>),
+    ) -> (TempDir, PathBuf) {
         use zip::DateTime;
 
         let mut archive = ZipArchive::new(File::open(original).unwrap()).unwrap();
@@ -634,10 +1841,8 @@ mod tests {
             let mut entry = archive.by_index(i).unwrap();
             let mut data = Vec::new();
             entry.read_to_end(&mut data).unwrap();
-            if entry.name() == SIGNATURE_CHAIN_PATH {
-                let original = data.clone();
-                data.push(b'\n');
-                data.extend_from_slice(&original);
+            if entry.name() == name {
+                edit(&mut data);
             }
             let options = SimpleFileOptions::default()
                 .compression_method(CompressionMethod::Stored)
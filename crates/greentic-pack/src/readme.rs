@@ -0,0 +1,64 @@
+/// Archive path of the embedded pack README.
+pub const README_PATH: &str = "README.md";
+
+/// Collects link targets from Markdown (`[text](target)`, `![alt](target)`)
+/// and inline HTML (`src="..."`, `href="..."`) that are relative paths, i.e.
+/// not URLs, absolute paths, or in-document anchors. Fragments and query
+/// strings are stripped from the returned targets.
+pub fn relative_links(markdown: &str) -> Vec<String> {
+    let mut links = Vec::new();
+
+    let mut rest = markdown;
+    while let Some(start) = rest.find("](") {
+        rest = &rest[start + 2..];
+        let end = rest.find(')').unwrap_or(rest.len());
+        let target = rest[..end].split_whitespace().next().unwrap_or_default();
+        push_relative(&mut links, target.trim_matches(['<', '>']));
+        rest = &rest[end..];
+    }
+
+    for attr in ["src=\"", "href=\""] {
+        let mut rest = markdown;
+        while let Some(start) = rest.find(attr) {
+            rest = &rest[start + attr.len()..];
+            let end = rest.find('"').unwrap_or(rest.len());
+            push_relative(&mut links, &rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+
+    links
+}
+
+fn push_relative(links: &mut Vec<String>, target: &str) {
+    let target = target.trim();
+    if target.is_empty() || target.starts_with('#') || target.starts_with('/') {
+        return;
+    }
+    if target.contains("://") || target.starts_with("mailto:") || target.starts_with("data:") {
+        return;
+    }
+    let path = target
+        .split(['#', '?'])
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches("./");
+    if !path.is_empty() && !links.iter().any(|existing| existing == path) {
+        links.push(path.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::relative_links;
+
+    #[test]
+    fn extracts_only_relative_targets() {
+        let markdown = "See [flow](./flows/main.ygtc#start), ![icon](media/icon.png \"Icon\"), \
+            [docs](https://greentic.ai), [top](#usage) and <img src=\"media/shot.png\">.";
+        assert_eq!(
+            relative_links(markdown),
+            vec!["flows/main.ygtc", "media/icon.png", "media/shot.png"]
+        );
+    }
+}
@@ -0,0 +1,446 @@
+//! Client for a pack registry index served over HTTP.
+//!
+//! The index is a set of static JSON documents, so any web server or object
+//! store can host one:
+//!
+//! ```text
+//! <base>/index.json                          every pack, with its latest version
+//! <base>/packs/<pack_id>/index.json          every published version of a pack
+//! <base>/packs/<pack_id>/<version>.gtpack    the archives
+//! ```
+//!
+//! A version entry pins its archive by size and BLAKE3 digest, and may name
+//! the pack's content digest as well. [`RegistryClient::download`] checks all
+//! of them and verifies the archive's signature before returning it, so a
+//! mirror serving different bytes is caught before anything is installed.
+//!
+//! The client does not speak HTTP itself; requests go through a
+//! [`Transport`], leaving the choice of HTTP stack, proxies, and credentials
+//! to the caller.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result, anyhow, bail};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+use crate::builder::hex_hash;
+use crate::reader::{PackLoad, SigningPolicy, open_pack_bytes};
+
+pub const REGISTRY_FORMAT: &str = "greentic-pack-registry-v1";
+
+/// Fetches a URL and returns the response body, failing on any non-success
+/// status.
+pub trait Transport: Send + Sync {
+    fn get(&self, url: &str) -> Result<Vec<u8>>;
+}
+
+/// `<base>/index.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryIndex {
+    pub format: String,
+    pub packs: Vec<PackSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackSummary {
+    pub pack_id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Newest version that is not yanked.
+    pub latest: Version,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+}
+
+/// `<base>/packs/<pack_id>/index.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackMetadata {
+    pub format: String,
+    pub pack_id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub versions: Vec<PublishedVersion>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublishedVersion {
+    pub version: Version,
+    pub size: u64,
+    /// BLAKE3 of the archive bytes.
+    pub blake3: String,
+    /// [`PackLoad::content_digest`] of the archive, when the registry records it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_digest: Option<String>,
+    #[serde(default)]
+    pub yanked: bool,
+    /// Archive URL, absolute or relative to the registry base; defaults to
+    /// `packs/<pack_id>/<version>.gtpack`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<String>,
+}
+
+/// A downloaded archive that matched its pins and verified.
+#[derive(Debug, Clone)]
+pub struct Download {
+    pub pack_id: String,
+    pub version: Version,
+    pub bytes: Vec<u8>,
+    pub load: PackLoad,
+}
+
+#[derive(Clone)]
+pub struct RegistryClient {
+    base: String,
+    transport: Arc<dyn Transport>,
+}
+
+impl std::fmt::Debug for RegistryClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegistryClient")
+            .field("base", &self.base)
+            .finish()
+    }
+}
+
+impl RegistryClient {
+    pub fn new(base: impl Into<String>, transport: Arc<dyn Transport>) -> Self {
+        let base = base.into().trim_end_matches('/').to_string();
+        Self { base, transport }
+    }
+
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    pub fn list(&self) -> Result<Vec<PackSummary>> {
+        let index: RegistryIndex = self.get_json(&format!("{}/index.json", self.base))?;
+        check_format(&index.format, &self.base)?;
+        Ok(index.packs)
+    }
+
+    /// Packs whose id, name, description, tags, or categories contain every
+    /// whitespace-separated term of `query`, ignoring case.
+    pub fn search(&self, query: &str) -> Result<Vec<PackSummary>> {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|pack| {
+                let haystack = [
+                    pack.pack_id.as_str(),
+                    pack.name.as_str(),
+                    pack.description.as_deref().unwrap_or_default(),
+                ]
+                .into_iter()
+                .chain(pack.tags.iter().map(String::as_str))
+                .chain(pack.categories.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join("\n")
+                .to_lowercase();
+                terms.iter().all(|term| haystack.contains(term.as_str()))
+            })
+            .collect())
+    }
+
+    pub fn metadata(&self, pack_id: &str) -> Result<PackMetadata> {
+        check_pack_id(pack_id)?;
+        let url = format!("{}/packs/{pack_id}/index.json", self.base);
+        let metadata: PackMetadata = self.get_json(&url)?;
+        check_format(&metadata.format, &url)?;
+        if metadata.pack_id != pack_id {
+            bail!("{url} describes `{}`, not `{pack_id}`", metadata.pack_id);
+        }
+        Ok(metadata)
+    }
+
+    /// The newest published version of `pack_id` that `req` allows,
+    /// skipping yanked versions unless `allow_yanked` is set.
+    pub fn resolve(
+        &self,
+        pack_id: &str,
+        req: &VersionReq,
+        allow_yanked: bool,
+    ) -> Result<PublishedVersion> {
+        let metadata = self.metadata(pack_id)?;
+        metadata
+            .versions
+            .into_iter()
+            .filter(|published| req.matches(&published.version))
+            .filter(|published| allow_yanked || !published.yanked)
+            .max_by(|a, b| a.version.cmp(&b.version))
+            .ok_or_else(|| anyhow!("no published version of {pack_id} matches `{req}`"))
+    }
+
+    /// Downloads `published` and checks it against its pins, then verifies
+    /// it under `policy` and checks it is the pack and version asked for.
+    pub fn download(
+        &self,
+        pack_id: &str,
+        published: &PublishedVersion,
+        policy: SigningPolicy,
+    ) -> Result<Download> {
+        check_pack_id(pack_id)?;
+        let version = &published.version;
+        let url = match &published.archive {
+            Some(archive) if archive.contains("://") => {
+                let remote = archive.starts_with("https://") || archive.starts_with("http://");
+                let local = self.base.starts_with("file://") && archive.starts_with("file://");
+                if !remote && !local {
+                    bail!("{pack_id}@{version} points at unsupported archive URL {archive}");
+                }
+                archive.clone()
+            }
+            Some(archive) => format!("{}/{}", self.base, archive.trim_start_matches('/')),
+            None => format!("{}/packs/{pack_id}/{version}.gtpack", self.base),
+        };
+        let bytes = self.transport.get(&url)?;
+        if bytes.len() as u64 != published.size {
+            bail!(
+                "{url} is {} bytes, but the registry pins {pack_id}@{version} at {}",
+                bytes.len(),
+                published.size
+            );
+        }
+        let actual = hex_hash(&bytes);
+        if !actual.eq_ignore_ascii_case(&published.blake3) {
+            bail!(
+                "{url} has BLAKE3 {actual}, but the registry pins {pack_id}@{version} at {}",
+                published.blake3
+            );
+        }
+        let load = open_pack_bytes(&bytes, policy)
+            .map_err(|err| anyhow!("{pack_id}@{version} failed verification: {}", err.message))?;
+        let meta = &load.manifest.meta;
+        if meta.pack_id != pack_id || &meta.version != version {
+            bail!(
+                "{url} holds {}@{}, not {pack_id}@{version}",
+                meta.pack_id,
+                meta.version
+            );
+        }
+        if let Some(expected) = &published.content_digest
+            && load.content_digest() != *expected
+        {
+            bail!("{pack_id}@{version} does not have the content digest the registry pins");
+        }
+        Ok(Download {
+            pack_id: pack_id.to_string(),
+            version: version.clone(),
+            bytes,
+            load,
+        })
+    }
+
+    fn get_json<T: for<'de> Deserialize<'de>>(&self, url: &str) -> Result<T> {
+        let body = self.transport.get(url)?;
+        serde_json::from_slice(&body)
+            .with_context(|| format!("{url} is not a valid registry document"))
+    }
+}
+
+fn check_format(format: &str, source: &str) -> Result<()> {
+    if format != REGISTRY_FORMAT {
+        bail!("{source} has format `{format}`; expected `{REGISTRY_FORMAT}`");
+    }
+    Ok(())
+}
+
+/// Pack ids go into URLs verbatim, so only the characters ids are made of
+/// are accepted.
+fn check_pack_id(pack_id: &str) -> Result<()> {
+    let valid = !pack_id.is_empty()
+        && !pack_id.starts_with('.')
+        && pack_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if !valid {
+        bail!("`{pack_id}` is not a pack id");
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "builder"))]
+mod tests {
+    use super::*;
+    use crate::builder::{FlowBundle, PackBuilder, PackMeta, Signing};
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    const BASE: &str = "https://packs.example";
+
+    #[derive(Default)]
+    struct Documents(BTreeMap<String, Vec<u8>>);
+
+    impl Transport for Documents {
+        fn get(&self, url: &str) -> Result<Vec<u8>> {
+            self.0
+                .get(url)
+                .cloned()
+                .ok_or_else(|| anyhow!("{url}: 404 Not Found"))
+        }
+    }
+
+    fn pack(dir: &Path, version: &str) -> Vec<u8> {
+        let meta: PackMeta = serde_json::from_value(json!({
+            "pack_id": "demo.app",
+            "version": version,
+            "name": "Demo",
+            "entry_flows": ["main"],
+            "created_at_utc": "2025-01-01T00:00:00Z",
+        }))
+        .unwrap();
+        let flow = FlowBundle {
+            id: "main".into(),
+            kind: "flow/v1".into(),
+            entry: "start".into(),
+            yaml: "id: main\nentry: start\nnodes: {}\n".into(),
+            json: json!({"id": "main", "entry": "start", "nodes": {}}),
+            hash_blake3: hex_hash(b"main"),
+            nodes: Vec::new(),
+        };
+        let path = dir.join(format!("{version}.gtpack"));
+        PackBuilder::new(meta)
+            .with_flow(flow)
+            .with_signing(Signing::Dev)
+            .build(&path)
+            .unwrap();
+        fs::read(path).unwrap()
+    }
+
+    fn registry(archives: &[(&str, &[u8], bool)]) -> Documents {
+        let mut documents = Documents::default();
+        let versions: Vec<PublishedVersion> = archives
+            .iter()
+            .map(|(version, bytes, yanked)| {
+                documents.0.insert(
+                    format!("{BASE}/packs/demo.app/{version}.gtpack"),
+                    bytes.to_vec(),
+                );
+                PublishedVersion {
+                    version: Version::parse(version).unwrap(),
+                    size: bytes.len() as u64,
+                    blake3: hex_hash(bytes),
+                    content_digest: None,
+                    yanked: *yanked,
+                    archive: None,
+                }
+            })
+            .collect();
+        let index = json!({
+            "format": REGISTRY_FORMAT,
+            "packs": [{
+                "pack_id": "demo.app",
+                "name": "Demo",
+                "description": "Answers weather questions",
+                "latest": "0.2.0",
+                "tags": ["weather"],
+            }],
+        });
+        let metadata = PackMetadata {
+            format: REGISTRY_FORMAT.into(),
+            pack_id: "demo.app".into(),
+            name: "Demo".into(),
+            description: None,
+            versions,
+        };
+        documents.0.insert(
+            format!("{BASE}/index.json"),
+            serde_json::to_vec(&index).unwrap(),
+        );
+        documents.0.insert(
+            format!("{BASE}/packs/demo.app/index.json"),
+            serde_json::to_vec(&metadata).unwrap(),
+        );
+        documents
+    }
+
+    #[test]
+    fn resolves_ranges_and_verifies_downloads() {
+        let dir = tempdir().unwrap();
+        let (v1, v2, v3) = (
+            pack(dir.path(), "0.1.0"),
+            pack(dir.path(), "0.2.0"),
+            pack(dir.path(), "0.3.0"),
+        );
+        let client = RegistryClient::new(
+            format!("{BASE}/"),
+            Arc::new(registry(&[
+                ("0.1.0", &v1, false),
+                ("0.2.0", &v2, false),
+                ("0.3.0", &v3, true),
+            ])),
+        );
+
+        assert_eq!(client.search("WEATHER demo").unwrap().len(), 1);
+        assert!(client.search("billing").unwrap().is_empty());
+
+        let any = VersionReq::STAR;
+        let resolved = client.resolve("demo.app", &any, false).unwrap();
+        assert_eq!(resolved.version, Version::new(0, 2, 0));
+        let yanked = client.resolve("demo.app", &any, true).unwrap();
+        assert_eq!(yanked.version, Version::new(0, 3, 0));
+        let req = VersionReq::parse("~0.1").unwrap();
+        assert_eq!(
+            client.resolve("demo.app", &req, false).unwrap().version,
+            Version::new(0, 1, 0)
+        );
+
+        let download = client
+            .download("demo.app", &resolved, SigningPolicy::DevOk)
+            .unwrap();
+        assert_eq!(download.bytes, v2);
+
+        let mut wrong = resolved.clone();
+        wrong.blake3 = hex_hash(b"something else");
+        let err = client
+            .download("demo.app", &wrong, SigningPolicy::DevOk)
+            .unwrap_err();
+        assert!(err.to_string().contains("registry pins"), "{err}");
+
+        // An archive of another version under the pinned digest is caught too.
+        let mut swapped = resolved;
+        swapped.archive = Some("packs/demo.app/0.1.0.gtpack".into());
+        swapped.size = v1.len() as u64;
+        swapped.blake3 = hex_hash(&v1);
+        let err = client
+            .download("demo.app", &swapped, SigningPolicy::DevOk)
+            .unwrap_err();
+        assert!(err.to_string().contains("not demo.app@0.2.0"), "{err}");
+    }
+
+    #[test]
+    fn archive_urls_must_be_http_unless_the_registry_is_local() {
+        let client = RegistryClient::new(BASE, Arc::new(Documents::default()));
+        let mut published = PublishedVersion {
+            version: Version::new(0, 1, 0),
+            size: 0,
+            blake3: hex_hash(b""),
+            content_digest: None,
+            yanked: false,
+            archive: None,
+        };
+        for archive in ["file:///etc/passwd", "ftp://packs.example/demo.gtpack"] {
+            published.archive = Some(archive.into());
+            let err = client
+                .download("demo.app", &published, SigningPolicy::DevOk)
+                .unwrap_err();
+            assert!(err.to_string().contains("unsupported archive URL"), "{err}");
+        }
+    }
+
+    #[test]
+    fn pack_ids_cannot_escape_the_registry() {
+        let client = RegistryClient::new(BASE, Arc::new(Documents::default()));
+        for id in ["../secrets", "a/b", "", "x?y=1"] {
+            assert!(client.metadata(id).is_err(), "{id}");
+        }
+    }
+}
@@ -0,0 +1,532 @@
+//! Handlebars rendering of pack templates, for hosts (feature `render`).
+//!
+//! Every host renders templates with the same helper set:
+//!
+//! - `{{t "greeting" name=user.name}}` looks a message up in the pack's i18n
+//!   bundles (`i18n/<locale>.json`), falling back from `de-CH` to `de` and
+//!   then to `en`, and fills `{name}` placeholders from the hash arguments.
+//! - `{{date sent_at "%d %b %Y" tz="Europe/Oslo"}}` formats an RFC 3339
+//!   timestamp or Unix seconds with a strftime pattern, in UTC unless `tz`
+//!   names a time zone. Without a pattern the timestamp is printed in RFC
+//!   3339.
+//! - `{{json order}}` prints a value as compact JSON, `pretty=true` indented.
+//! - `{{get order "/items/0/sku"}}` reads a value by JSON pointer; it also
+//!   works as a subexpression.
+//!
+//! The pack's other Handlebars templates are available as partials, named
+//! as `packc lint` resolves them: relative to the rendered template, to its
+//! template directory, or to the pack root, with or without the extension.
+//!
+//! A template with locale variants (see [`crate::locales`]) renders the
+//! variant for the requested locale.
+//!
+//! Output is HTML-escaped only for `.html.hbs` and `.html.handlebars`
+//! templates. In strict mode a missing variable, message, placeholder, or
+//! pointer target is an error; otherwise it renders empty (messages render as
+//! their key).
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, RenderErrorReason,
+    ScopedJson,
+};
+use jiff::Timestamp;
+use jiff::tz::TimeZone;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::permissions::{DEFAULT_LOCALE, I18N_DIR, I18nBundle, lookup};
+use crate::reader::PackLoad;
+
+/// Archive prefix of template assets.
+const ASSETS_PREFIX: &str = "assets/";
+
+/// Renders the template at `logical_path` (as listed in the pack, e.g.
+/// `templates/welcome.hbs`) with `data`, in `locale`, without strict mode.
+pub fn render_template(
+    pack: &PackLoad,
+    logical_path: &str,
+    data: &JsonValue,
+    locale: &str,
+) -> Result<String> {
+    TemplateRenderer::new(pack).render(logical_path, data, locale)
+}
+
+/// Renders the Handlebars templates of one pack.
+#[derive(Debug, Clone)]
+pub struct TemplateRenderer<'a> {
+    pack: &'a PackLoad,
+    bundles: BTreeMap<String, I18nBundle>,
+    strict: bool,
+}
+
+impl<'a> TemplateRenderer<'a> {
+    pub fn new(pack: &'a PackLoad) -> Self {
+        let prefix = format!("{I18N_DIR}/");
+        let bundles = pack
+            .files
+            .iter()
+            .filter_map(|(path, bytes)| {
+                let locale = path.strip_prefix(&prefix)?.strip_suffix(".json")?;
+                let messages: JsonMap<String, JsonValue> = serde_json::from_slice(bytes).ok()?;
+                let bundle = messages
+                    .into_iter()
+                    .filter_map(|(key, value)| Some((key, value.as_str()?.to_string())))
+                    .collect();
+                Some((locale.to_string(), bundle))
+            })
+            .collect();
+        Self {
+            pack,
+            bundles,
+            strict: false,
+        }
+    }
+
+    /// Fails on missing variables, messages, placeholders, and pointer
+    /// targets instead of rendering them empty.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Locales the pack has i18n bundles for.
+    pub fn locales(&self) -> impl Iterator<Item = &str> {
+        self.bundles.keys().map(String::as_str)
+    }
+
+    /// Renders the template at `logical_path`, or its variant for `locale`
+    /// when the pack declares `locales`.
+    pub fn render(&self, logical_path: &str, data: &JsonValue, locale: &str) -> Result<String> {
+        let (stem, extension) = logical_path.rsplit_once('.').unwrap_or((logical_path, ""));
+        if !matches!(extension, "hbs" | "handlebars") {
+            bail!("{logical_path} is not a Handlebars template");
+        }
+        let logical_path = self.pack.template_variant(logical_path, &[locale]);
+        let bytes = self
+            .pack
+            .file(&format!("{ASSETS_PREFIX}{logical_path}"))
+            .ok_or_else(|| anyhow!("the pack has no template {logical_path}"))?;
+        let source = std::str::from_utf8(bytes)
+            .with_context(|| format!("template {logical_path} is not valid UTF-8"))?;
+
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(self.strict);
+        if !stem.ends_with(".html") {
+            registry.register_escape_fn(handlebars::no_escape);
+        }
+        self.register_partials(&mut registry, logical_path)?;
+        registry.register_helper(
+            "t",
+            Box::new(Translate {
+                messages: self.message_chain(locale),
+                locale: locale.to_string(),
+                strict: self.strict,
+            }),
+        );
+        registry.register_helper("date", Box::new(FormatDate));
+        registry.register_helper("json", Box::new(ToJson));
+        registry.register_helper(
+            "get",
+            Box::new(Pointer {
+                strict: self.strict,
+            }),
+        );
+        registry
+            .register_template_string(logical_path, source)
+            .with_context(|| format!("template {logical_path} has a syntax error"))?;
+        registry
+            .render(logical_path, data)
+            .with_context(|| format!("failed to render {logical_path}"))
+    }
+
+    /// Registers every Handlebars template under the names it can be
+    /// referenced by from `logical_path`; nearer names win.
+    fn register_partials(&self, registry: &mut Handlebars<'_>, logical_path: &str) -> Result<()> {
+        let template_dir = logical_path.split_once('/').map(|(dir, _)| dir);
+        let own_dir = logical_path.rsplit_once('/').map(|(dir, _)| dir);
+        let templates: Vec<(&str, &[u8])> = self
+            .pack
+            .files
+            .iter()
+            .filter_map(|(path, bytes)| Some((path.strip_prefix(ASSETS_PREFIX)?, bytes.as_slice())))
+            .filter(|(path, _)| path.ends_with(".hbs") || path.ends_with(".handlebars"))
+            .collect();
+        for base in [None, template_dir, own_dir] {
+            for (path, bytes) in &templates {
+                let name = match base {
+                    None => *path,
+                    Some(base) => match path
+                        .strip_prefix(base)
+                        .and_then(|rest| rest.strip_prefix('/'))
+                    {
+                        Some(name) => name,
+                        None => continue,
+                    },
+                };
+                let source = std::str::from_utf8(bytes)
+                    .with_context(|| format!("template {path} is not valid UTF-8"))?;
+                let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+                for name in [name, stem] {
+                    registry
+                        .register_partial(name, source)
+                        .with_context(|| format!("template {path} has a syntax error"))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Bundles consulted for `locale`, most specific first, ending with the
+    /// default locale.
+    fn message_chain(&self, locale: &str) -> Vec<I18nBundle> {
+        let mut chain: Vec<&str> = Vec::new();
+        let mut candidate = locale.to_string();
+        loop {
+            if let Some(name) = lookup(&self.bundles, &candidate)
+                .map(|(name, _)| name)
+                .filter(|name| !chain.contains(name))
+            {
+                chain.push(name);
+            }
+            match candidate.rfind('-') {
+                Some(end) => candidate.truncate(end),
+                None => break,
+            }
+        }
+        if let Some(name) = lookup(&self.bundles, DEFAULT_LOCALE)
+            .map(|(name, _)| name)
+            .filter(|name| !chain.contains(name))
+        {
+            chain.push(name);
+        }
+        chain
+            .into_iter()
+            .map(|name| self.bundles[name].clone())
+            .collect()
+    }
+}
+
+fn helper_error(helper: &str, message: impl std::fmt::Display) -> RenderError {
+    RenderErrorReason::Other(format!("{helper}: {message}")).into()
+}
+
+fn param<'a>(
+    h: &'a Helper<'_>,
+    name: &'static str,
+    index: usize,
+) -> Result<&'a JsonValue, RenderError> {
+    h.param(index)
+        .map(|param| param.value())
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex(name, index).into())
+}
+
+struct Translate {
+    messages: Vec<I18nBundle>,
+    locale: String,
+    strict: bool,
+}
+
+impl HelperDef for Translate {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let key = param(h, "t", 0)?
+            .as_str()
+            .ok_or_else(|| helper_error("t", "the message key must be a string"))?;
+        let Some(message) = self.messages.iter().find_map(|bundle| bundle.get(key)) else {
+            if self.strict {
+                return Err(helper_error(
+                    "t",
+                    format!("no message `{key}` for locale `{}`", self.locale),
+                ));
+            }
+            return Ok(ScopedJson::Derived(JsonValue::String(key.to_string())));
+        };
+
+        let mut text = String::with_capacity(message.len());
+        let mut rest = message.as_str();
+        while let Some(start) = rest.find('{') {
+            text.push_str(&rest[..start]);
+            let Some(len) = rest[start..].find('}') else {
+                rest = &rest[start..];
+                break;
+            };
+            let name = &rest[start + 1..start + len];
+            match h.hash_get(name).map(|value| value.value()) {
+                Some(JsonValue::String(value)) => text.push_str(value),
+                Some(JsonValue::Null) | None if self.strict => {
+                    return Err(helper_error(
+                        "t",
+                        format!("message `{key}` needs a value for `{name}`"),
+                    ));
+                }
+                Some(JsonValue::Null) | None => {}
+                Some(value) => text.push_str(&value.to_string()),
+            }
+            rest = &rest[start + len + 1..];
+        }
+        text.push_str(rest);
+        Ok(ScopedJson::Derived(JsonValue::String(text)))
+    }
+}
+
+struct FormatDate;
+
+impl HelperDef for FormatDate {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let timestamp = match param(h, "date", 0)? {
+            JsonValue::String(text) => text
+                .parse::<Timestamp>()
+                .map_err(|err| helper_error("date", format!("`{text}`: {err}")))?,
+            JsonValue::Number(seconds) => seconds
+                .as_i64()
+                .and_then(|seconds| Timestamp::from_second(seconds).ok())
+                .ok_or_else(|| helper_error("date", format!("{seconds} is out of range")))?,
+            other => {
+                return Err(helper_error(
+                    "date",
+                    format!("expected an RFC 3339 string or Unix seconds, got {other}"),
+                ));
+            }
+        };
+        let zone = match h.hash_get("tz").map(|tz| tz.value()) {
+            None => TimeZone::UTC,
+            Some(JsonValue::String(name)) => TimeZone::get(name)
+                .map_err(|_| helper_error("date", format!("unknown time zone `{name}`")))?,
+            Some(other) => {
+                return Err(helper_error(
+                    "date",
+                    format!("tz must be a time zone name, got {other}"),
+                ));
+            }
+        };
+        let zoned = timestamp.to_zoned(zone);
+        let text = match h.param(1).map(|param| param.value()) {
+            None => zoned.timestamp().to_string(),
+            Some(JsonValue::String(pattern)) => jiff::fmt::strtime::format(pattern, &zoned)
+                .map_err(|err| helper_error("date", format!("pattern `{pattern}`: {err}")))?,
+            Some(other) => {
+                return Err(helper_error(
+                    "date",
+                    format!("the pattern must be a string, got {other}"),
+                ));
+            }
+        };
+        Ok(ScopedJson::Derived(JsonValue::String(text)))
+    }
+}
+
+struct ToJson;
+
+impl HelperDef for ToJson {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let value = param(h, "json", 0)?;
+        let pretty = h
+            .hash_get("pretty")
+            .and_then(|pretty| pretty.value().as_bool())
+            .unwrap_or(false);
+        let text = if pretty {
+            serde_json::to_string_pretty(value)
+        } else {
+            serde_json::to_string(value)
+        }
+        .map_err(|err| helper_error("json", err))?;
+        Ok(ScopedJson::Derived(JsonValue::String(text)))
+    }
+}
+
+struct Pointer {
+    strict: bool,
+}
+
+impl HelperDef for Pointer {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let value = param(h, "get", 0)?;
+        let pointer = param(h, "get", 1)?
+            .as_str()
+            .ok_or_else(|| helper_error("get", "the pointer must be a string"))?;
+        match value.pointer(pointer) {
+            Some(found) => Ok(ScopedJson::Derived(found.clone())),
+            None if self.strict => Err(helper_error("get", format!("nothing at `{pointer}`"))),
+            None => Ok(ScopedJson::Derived(JsonValue::Null)),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "builder"))]
+mod tests {
+    use super::*;
+    use crate::builder::{PACK_VERSION, PackBuilder, PackMeta, Provenance};
+    use crate::reader::{SigningPolicy, open_pack};
+    use greentic_flow::flow_bundle::FlowBundle;
+    use semver::Version;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn sample_meta() -> PackMeta {
+        PackMeta {
+            pack_version: PACK_VERSION,
+            pack_id: "ai.greentic.demo.render".into(),
+            version: Version::parse("0.1.0").unwrap(),
+            name: "Render Demo".into(),
+            kind: None,
+            description: None,
+            authors: vec!["Greentic".into()],
+            license: None,
+            homepage: None,
+            support: None,
+            vendor: None,
+            tags: Vec::new(),
+            categories: Vec::new(),
+            imports: Vec::new(),
+            entry_flows: vec!["demo".into()],
+            created_at_utc: "2025-01-01T00:00:00Z".into(),
+            events: None,
+            repo: None,
+            messaging: None,
+            interfaces: Vec::new(),
+            media: None,
+            config: None,
+            config_schema: None,
+            config_defaults: None,
+            lifecycle: None,
+            quotas: None,
+            limits: None,
+            dispatch: None,
+            qos: None,
+            secrets: None,
+            capabilities: None,
+            metrics: None,
+            schedule: None,
+            classification: None,
+            locales: None,
+            data_handling: None,
+            permissions: None,
+            annotations: JsonMap::new(),
+        }
+    }
+
+    fn build_sample(dir: &std::path::Path) -> PackLoad {
+        let flow_json = json!({"id": "demo", "kind": "flow/v1", "entry": "start", "nodes": []});
+        let out = dir.join("render.gtpack");
+        PackBuilder::new(sample_meta())
+            .with_flow(FlowBundle {
+                id: "demo".into(),
+                kind: "flow/v1".into(),
+                entry: "start".into(),
+                yaml: "id: demo\nentry: start\n".into(),
+                json: flow_json.clone(),
+                hash_blake3: blake3::hash(&serde_json::to_vec(&flow_json).unwrap())
+                    .to_hex()
+                    .to_string(),
+                nodes: Vec::new(),
+            })
+            .with_asset_bytes(
+                "templates/receipt.hbs",
+                b"{{t \"thanks\" name=user.name}} <{{get order \"/items/0/sku\"}}> \
+                  {{date order.placed_at \"%Y-%m-%d %H:%M\" tz=\"Europe/Oslo\"}} {{json order.total}}"
+                    .to_vec(),
+            )
+            .with_asset_bytes(
+                "templates/page.html.hbs",
+                b"<p>{{user.name}}</p>{{> partials/sign}}".to_vec(),
+            )
+            .with_asset_bytes("templates/partials/sign.hbs", b"-- {{t \"bye\"}}".to_vec())
+            .with_i18n_bundle("en", br#"{"thanks": "Thanks, {name}!", "bye": "Bye"}"#.to_vec())
+            .with_i18n_bundle("de", br#"{"thanks": "Danke, {name}!"}"#.to_vec())
+            .with_provenance(Provenance {
+                builder: "greentic-pack@test".into(),
+                git_commit: None,
+                git_repo: None,
+                toolchain: None,
+                built_at_utc: "2025-01-01T00:00:00Z".into(),
+                host: None,
+                notes: None,
+                profile_blake3: None,
+            })
+            .build(&out)
+            .unwrap();
+        open_pack(&out, SigningPolicy::DevOk).expect("pack opens")
+    }
+
+    #[test]
+    fn renders_with_the_shared_helpers() {
+        let dir = tempdir().unwrap();
+        let pack = build_sample(dir.path());
+        let data = json!({
+            "user": {"name": "Ada & Co"},
+            "order": {
+                "items": [{"sku": "A-1"}],
+                "placed_at": "2025-03-01T12:30:00Z",
+                "total": {"amount": 12.5, "currency": "EUR"}
+            }
+        });
+
+        let renderer = TemplateRenderer::new(&pack);
+        assert_eq!(renderer.locales().collect::<Vec<_>>(), vec!["de", "en"]);
+        assert_eq!(
+            renderer
+                .render("templates/receipt.hbs", &data, "de-AT")
+                .unwrap(),
+            "Danke, Ada & Co! <A-1> 2025-03-01 13:30 {\"amount\":12.5,\"currency\":\"EUR\"}"
+        );
+        assert_eq!(
+            render_template(&pack, "templates/receipt.hbs", &data, "fr")
+                .unwrap()
+                .split(" <")
+                .next(),
+            Some("Thanks, Ada & Co!")
+        );
+        assert_eq!(
+            renderer
+                .render("templates/page.html.hbs", &data, "en")
+                .unwrap(),
+            "<p>Ada &amp; Co</p>-- Bye"
+        );
+    }
+
+    #[test]
+    fn strict_mode_rejects_missing_values() {
+        let dir = tempdir().unwrap();
+        let pack = build_sample(dir.path());
+        let partial = json!({"user": {}, "order": {"items": [], "placed_at": 0, "total": 1}});
+
+        let lenient = render_template(&pack, "templates/receipt.hbs", &partial, "en").unwrap();
+        assert_eq!(lenient, "Thanks, ! <> 1970-01-01 01:00 1");
+
+        let strict = TemplateRenderer::new(&pack).with_strict(true);
+        let err = strict
+            .render("templates/receipt.hbs", &partial, "en")
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("failed to render"), "{err:#}");
+        assert!(
+            strict
+                .render("templates/missing.hbs", &partial, "en")
+                .is_err()
+        );
+    }
+}
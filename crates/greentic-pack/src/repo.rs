@@ -1,11 +1,15 @@
 use std::fmt;
+use std::str::FromStr;
 
 use anyhow::{Result, anyhow, bail};
+#[cfg(feature = "schema")]
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 #[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
 pub enum RepoPackKind {
     SourceProvider,
     Scanner,
@@ -34,7 +38,35 @@ impl fmt::Display for RepoPackKind {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema, Default)]
+impl RepoPackKind {
+    /// Every kind, in declaration order.
+    pub const ALL: &'static [Self] = &[
+        Self::SourceProvider,
+        Self::Scanner,
+        Self::Signing,
+        Self::Attestation,
+        Self::PolicyEngine,
+        Self::OciProvider,
+        Self::BillingProvider,
+        Self::SearchProvider,
+        Self::RecommendationProvider,
+    ];
+}
+
+impl FromStr for RepoPackKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Self::ALL
+            .iter()
+            .find(|kind| kind.to_string() == value)
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown repo pack kind `{value}`"))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 #[serde(deny_unknown_fields)]
 pub struct RepoCapabilities {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -95,7 +127,8 @@ impl RepoCapabilities {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 #[serde(deny_unknown_fields)]
 pub struct RepoBindings {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -153,7 +186,9 @@ impl RepoBindings {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[non_exhaustive]
 pub struct RepoBinding {
     pub package: String,
     pub world: String,
@@ -166,6 +201,52 @@ pub struct RepoBinding {
 }
 
 impl RepoBinding {
+    pub fn new(
+        package: impl Into<String>,
+        world: impl Into<String>,
+        version: impl Into<String>,
+        component: impl Into<String>,
+        entrypoint: impl Into<String>,
+    ) -> Self {
+        Self {
+            package: package.into(),
+            world: world.into(),
+            version: version.into(),
+            component: component.into(),
+            entrypoint: entrypoint.into(),
+            profile: None,
+        }
+    }
+
+    pub fn with_profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    pub fn world(&self) -> &str {
+        &self.world
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    pub fn entrypoint(&self) -> &str {
+        &self.entrypoint
+    }
+
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
     pub fn validate(&self, label: &str) -> Result<()> {
         if self.package.trim().is_empty() {
             bail!("bindings.{label}[].package is required");
@@ -191,7 +272,8 @@ impl RepoBinding {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct RepoPackSection {
     pub kind: RepoPackKind,
     #[serde(default)]
@@ -226,7 +308,8 @@ impl RepoPackSection {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct InterfaceBinding {
     pub package: String,
     pub world: String,
@@ -760,3 +843,27 @@ fn expected_capability_key(kind: &RepoPackKind) -> &'static str {
         RepoPackKind::RecommendationProvider => "reco",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_round_trip_through_strings() {
+        for kind in RepoPackKind::ALL {
+            let name = kind.to_string();
+            assert_eq!(&name.parse::<RepoPackKind>().unwrap(), kind);
+            assert_eq!(serde_json::to_value(kind).unwrap(), name.as_str());
+        }
+        assert!("registry".parse::<RepoPackKind>().is_err());
+    }
+
+    #[test]
+    fn bindings_build_with_optional_profile() {
+        let binding = RepoBinding::new("greentic:scan", "scanner", "1.0.0", "trivy@1.0.0", "scan")
+            .with_profile("strict");
+        binding.validate("scan").unwrap();
+        assert_eq!(binding.component(), "trivy@1.0.0");
+        assert_eq!(binding.profile(), Some("strict"));
+    }
+}
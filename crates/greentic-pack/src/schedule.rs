@@ -0,0 +1,315 @@
+//! Scheduled triggers for flows.
+//!
+//! Flows that run on a timetable rather than in reply to a message declare a
+//! cron schedule, keyed by flow id:
+//!
+//! ```yaml
+//! schedule:
+//!   daily_digest:
+//!     cron: "0 8 * * MON-FRI"
+//!     timezone: Europe/Berlin
+//!     jitter_secs: 300
+//! ```
+//!
+//! Expressions use the five classic fields (minute, hour, day of month,
+//! month, day of week) or one of the `@hourly`-style shorthands. Timezones are
+//! IANA names and default to `UTC`. Hosts start each run up to `jitter_secs`
+//! late, so packs installed by many tenants do not all fire at once. The
+//! deployment plan lists the [`ScheduleBinding`]s a host has to register.
+
+use std::collections::BTreeMap;
+
+#[cfg(feature = "builder")]
+use anyhow::Context;
+use anyhow::{Result, anyhow, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Timezone of schedules that do not name one.
+pub const DEFAULT_TIMEZONE: &str = "UTC";
+/// Upper bound of `jitter_secs`.
+pub const MAX_JITTER_SECS: u32 = 3600;
+
+/// Schedules by flow id.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(transparent)]
+pub struct ScheduleSection {
+    pub flows: BTreeMap<String, FlowSchedule>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct FlowSchedule {
+    /// Cron expression, e.g. `*/15 * * * *` or `@daily`.
+    pub cron: String,
+    /// IANA timezone the expression is evaluated in; `UTC` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Random delay of up to this many seconds before each run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jitter_secs: Option<u32>,
+}
+
+/// What a host registers for one scheduled flow.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScheduleBinding {
+    pub flow: String,
+    pub cron: String,
+    pub timezone: String,
+    pub jitter_secs: u32,
+}
+
+impl ScheduleSection {
+    #[cfg(feature = "builder")]
+    pub fn validate(&self) -> Result<()> {
+        for (flow, schedule) in &self.flows {
+            if flow.trim().is_empty() {
+                bail!("schedule must be keyed by flow id");
+            }
+            CronExpr::parse(&schedule.cron).with_context(|| format!("schedule.{flow}.cron"))?;
+            if let Some(timezone) = &schedule.timezone {
+                jiff::tz::TimeZone::get(timezone).map_err(|_| {
+                    anyhow!("schedule.{flow}.timezone `{timezone}` is not an IANA timezone")
+                })?;
+            }
+            if schedule
+                .jitter_secs
+                .is_some_and(|jitter| jitter > MAX_JITTER_SECS)
+            {
+                bail!("schedule.{flow}.jitter_secs must be at most {MAX_JITTER_SECS}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every entry names one of `flows`, the pack's flow ids.
+    pub fn check_flows<'a>(&self, flows: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        let flows: Vec<&str> = flows.into_iter().collect();
+        if let Some(flow) = self
+            .flows
+            .keys()
+            .find(|flow| !flows.contains(&flow.as_str()))
+        {
+            bail!("schedule refers to unknown flow `{flow}`");
+        }
+        Ok(())
+    }
+
+    pub fn bindings(&self) -> Vec<ScheduleBinding> {
+        self.flows
+            .iter()
+            .map(|(flow, schedule)| ScheduleBinding {
+                flow: flow.clone(),
+                cron: schedule.cron.trim().to_string(),
+                timezone: schedule
+                    .timezone
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_TIMEZONE.to_string()),
+                jitter_secs: schedule.jitter_secs.unwrap_or(0),
+            })
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flows.is_empty()
+    }
+}
+
+/// A parsed cron expression; each field is a bit set of matching values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    /// Sunday is bit 0; `7` in the expression also means Sunday.
+    weekdays: u64,
+    /// Whether day of month and day of week were both restricted, in which
+    /// case a time matches if either does.
+    either_day: bool,
+}
+
+const MONTHS: &[&str] = &[
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const WEEKDAYS: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let expr = expr.trim();
+        let expanded = match expr {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other if other.starts_with('@') => bail!("unknown cron shorthand `{other}`"),
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            bail!(
+                "cron expression `{expr}` must have 5 fields (minute hour day month weekday), \
+                 found {}",
+                fields.len()
+            );
+        };
+        let mut weekdays = parse_field(weekday, "day of week", 0, 7, WEEKDAYS, 0)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays & !(1 << 7)) | 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, "minute", 0, 59, &[], 0)?,
+            hours: parse_field(hour, "hour", 0, 23, &[], 0)?,
+            days: parse_field(day, "day of month", 1, 31, &[], 1)?,
+            months: parse_field(month, "month", 1, 12, MONTHS, 1)?,
+            weekdays,
+            either_day: !day.starts_with('*') && !weekday.starts_with('*'),
+        })
+    }
+
+    /// Whether the expression fires at this local time. `weekday` counts
+    /// from Sunday = 0.
+    pub fn matches(&self, minute: u8, hour: u8, day: u8, month: u8, weekday: u8) -> bool {
+        let has = |set: u64, value: u8| value < 64 && set & (1 << value) != 0;
+        let day_of_month = has(self.days, day);
+        let day_of_week = has(self.weekdays, weekday % 7);
+        let day_matches = if self.either_day {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        };
+        has(self.minutes, minute) && has(self.hours, hour) && has(self.months, month) && day_matches
+    }
+}
+
+/// Parses one field into a bit set. `names` are accepted in place of numbers
+/// and map to `name_base + index`.
+fn parse_field(
+    field: &str,
+    label: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+    name_base: u32,
+) -> Result<u64> {
+    let value = |text: &str| -> Result<u32> {
+        if let Some(index) = names
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(text))
+        {
+            return Ok(name_base + index as u32);
+        }
+        let number: u32 = text
+            .parse()
+            .map_err(|_| anyhow!("`{text}` is not a valid {label}"))?;
+        if !(min..=max).contains(&number) {
+            bail!("{label} {number} is outside {min}-{max}");
+        }
+        Ok(number)
+    };
+
+    let mut set = 0u64;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| anyhow!("`{step}` is not a valid {label} step"))?;
+                (range, step)
+            }
+            None => (item, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/15` runs from 5 to the end of the range.
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let single = value(range)?;
+                    (single, single)
+                }
+            },
+        };
+        if start > end {
+            bail!("{label} range `{range}` runs backwards");
+        }
+        set |= (start..=end)
+            .step_by(step as usize)
+            .fold(0u64, |bits, value| bits | (1 << value));
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cron_fields_names_and_shorthands() {
+        let weekdays = CronExpr::parse("30 8 * * MON-FRI").unwrap();
+        // 2024-06-03 was a Monday.
+        assert!(weekdays.matches(30, 8, 3, 6, 1));
+        assert!(!weekdays.matches(30, 8, 2, 6, 0));
+        assert!(!weekdays.matches(31, 8, 3, 6, 1));
+
+        let quarter = CronExpr::parse("*/15 9-17 1,15 * 7").unwrap();
+        assert!(quarter.matches(45, 17, 15, 2, 4));
+        assert!(
+            quarter.matches(0, 9, 9, 2, 0),
+            "Sunday matches via day of week"
+        );
+        assert!(!quarter.matches(10, 9, 1, 2, 4));
+
+        assert_eq!(
+            CronExpr::parse("@daily").unwrap(),
+            CronExpr::parse("0 0 * * *").unwrap()
+        );
+        for bad in [
+            "* * * *",
+            "60 * * * *",
+            "* * * JANUARY *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "@often",
+        ] {
+            assert!(CronExpr::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[cfg(feature = "builder")]
+    #[test]
+    fn validates_timezones_and_jitter() {
+        let section: ScheduleSection = serde_yaml_bw::from_str(
+            r#"
+daily_digest:
+  cron: "0 8 * * MON-FRI"
+  timezone: Europe/Berlin
+  jitter_secs: 300
+cleanup:
+  cron: "@hourly"
+"#,
+        )
+        .unwrap();
+        section.validate().unwrap();
+        section.check_flows(["daily_digest", "cleanup"]).unwrap();
+        assert!(section.check_flows(["cleanup"]).is_err());
+        let bindings = section.bindings();
+        assert_eq!(bindings[0].flow, "cleanup");
+        assert_eq!(bindings[0].timezone, "UTC");
+        assert_eq!(bindings[1].jitter_secs, 300);
+
+        let mut bad = section.clone();
+        bad.flows.get_mut("cleanup").unwrap().timezone = Some("Mars/Olympus_Mons".into());
+        assert!(bad.validate().is_err());
+        let mut bad = section;
+        bad.flows.get_mut("cleanup").unwrap().jitter_secs = Some(MAX_JITTER_SECS + 1);
+        assert!(bad.validate().is_err());
+    }
+}
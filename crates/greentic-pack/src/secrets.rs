@@ -0,0 +1,238 @@
+//! Secrets a pack needs provisioned before its flows can run.
+//!
+//! Packs declare every secret their flows read, so provisioning UIs can ask
+//! for them up front:
+//!
+//! ```yaml
+//! secrets:
+//!   OPENWEATHER_API_KEY:
+//!     type: api_key
+//!     description: Key for the OpenWeather current conditions API
+//!   SLACK_TOKEN:
+//!     type: token
+//!     description: Bot token used to post forecasts
+//!     required: false
+//! ```
+//!
+//! Flows refer to a secret as `secret://<name>` in node configuration:
+//!
+//! ```yaml
+//! fetch:
+//!   http.get:
+//!     url: https://api.openweathermap.org/data/2.5/weather
+//!     headers: { x-api-key: "secret://OPENWEATHER_API_KEY" }
+//! ```
+//!
+//! [`SecretsSection::check_refs`] reports references to undeclared secrets.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, bail};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// Scheme of secret references in flows.
+pub const SECRET_SCHEME: &str = "secret://";
+
+/// Declared secrets by name.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(transparent)]
+pub struct SecretsSection {
+    pub secrets: BTreeMap<String, SecretDeclaration>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct SecretDeclaration {
+    #[serde(rename = "type", default)]
+    pub kind: SecretKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Flows fail without the secret; optional secrets enable extra features.
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+/// What a secret holds, so provisioning UIs can pick a suitable input;
+/// `certificate` is a PEM certificate or private key.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SecretKind {
+    ApiKey,
+    Token,
+    Password,
+    Certificate,
+    #[default]
+    Text,
+}
+
+impl SecretKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SecretKind::ApiKey => "api_key",
+            SecretKind::Token => "token",
+            SecretKind::Password => "password",
+            SecretKind::Certificate => "certificate",
+            SecretKind::Text => "text",
+        }
+    }
+}
+
+/// One `secret://` reference in a flow.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SecretRef {
+    pub node: String,
+    pub name: String,
+}
+
+impl SecretsSection {
+    pub fn validate(&self) -> Result<()> {
+        for (name, secret) in &self.secrets {
+            if !is_secret_name(name) {
+                bail!("secret name `{name}` must use only letters, digits, `_`, `-`, `.`, and `/`");
+            }
+            if secret
+                .description
+                .as_deref()
+                .is_some_and(|description| description.trim().is_empty())
+            {
+                bail!("secrets.{name}.description must not be empty");
+            }
+        }
+        Ok(())
+    }
+
+    /// References of `flow` to undeclared secrets, one message each.
+    pub fn check_refs(&self, flow: &str, refs: &[SecretRef]) -> Vec<String> {
+        refs.iter()
+            .filter(|secret| !self.secrets.contains_key(&secret.name))
+            .map(|secret| {
+                format!(
+                    "flow `{flow}` node `{}` reads undeclared secret `{}`",
+                    secret.node, secret.name
+                )
+            })
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.secrets.is_empty()
+    }
+}
+
+/// The `secret://` references of a flow's nodes, read from its YAML source.
+pub fn secret_refs(flow_yaml: &str) -> Result<Vec<SecretRef>> {
+    let source: JsonValue =
+        serde_yaml_bw::from_str(flow_yaml).context("flow source is not valid YAML")?;
+    let mut refs = Vec::new();
+    let Some(nodes) = source.get("nodes").and_then(JsonValue::as_object) else {
+        return Ok(refs);
+    };
+    for (node, body) in nodes {
+        let mut names = Vec::new();
+        collect_names(body, &mut names);
+        refs.extend(names.into_iter().map(|name| SecretRef {
+            node: node.clone(),
+            name,
+        }));
+    }
+    Ok(refs)
+}
+
+fn collect_names(value: &JsonValue, names: &mut Vec<String>) {
+    match value {
+        JsonValue::String(text) => {
+            for (start, _) in text.match_indices(SECRET_SCHEME) {
+                let rest = &text[start + SECRET_SCHEME.len()..];
+                let end = rest
+                    .find(|c: char| !is_secret_char(c))
+                    .unwrap_or(rest.len());
+                let name = rest[..end].trim_end_matches(['.', '/']);
+                if !name.is_empty() && !names.iter().any(|known| known == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        JsonValue::Array(items) => items.iter().for_each(|item| collect_names(item, names)),
+        JsonValue::Object(fields) => fields
+            .values()
+            .for_each(|field| collect_names(field, names)),
+        _ => {}
+    }
+}
+
+/// Secret names use letters, digits, `_`, `-`, `.`, and `/`.
+pub fn is_secret_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(is_secret_char)
+}
+
+fn is_secret_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLOW: &str = r#"
+id: weather_bot
+type: messaging
+nodes:
+  fetch:
+    http.get:
+      url: https://api.openweathermap.org/data/2.5/weather
+      headers:
+        x-api-key: "secret://OPENWEATHER_API_KEY"
+  notify:
+    slack.post:
+      token: secret://SLACK_TOKEN.
+      auth: ["Bearer secret://SLACK_TOKEN"]
+"#;
+
+    #[test]
+    fn validates_declarations_and_flags_undeclared_references() {
+        let section: SecretsSection = serde_yaml_bw::from_str(
+            "OPENWEATHER_API_KEY:\n  type: api_key\n  description: OpenWeather key\n",
+        )
+        .unwrap();
+        section.validate().unwrap();
+        let declared = &section.secrets["OPENWEATHER_API_KEY"];
+        assert_eq!(declared.kind, SecretKind::ApiKey);
+        assert!(declared.required);
+
+        let refs = secret_refs(FLOW).unwrap();
+        assert_eq!(
+            refs,
+            [
+                SecretRef {
+                    node: "fetch".into(),
+                    name: "OPENWEATHER_API_KEY".into()
+                },
+                SecretRef {
+                    node: "notify".into(),
+                    name: "SLACK_TOKEN".into()
+                },
+            ]
+        );
+        let problems = section.check_refs("weather_bot", &refs);
+        assert_eq!(
+            problems,
+            ["flow `weather_bot` node `notify` reads undeclared secret `SLACK_TOKEN`"]
+        );
+
+        for bad in ["\"api key\": {}", "KEY: { description: \" \" }"] {
+            let section: SecretsSection = serde_yaml_bw::from_str(bad).unwrap();
+            assert!(section.validate().is_err(), "{bad}");
+        }
+        assert!(serde_yaml_bw::from_str::<SecretsSection>("KEY: { type: pin }").is_err());
+    }
+}
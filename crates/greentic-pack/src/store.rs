@@ -0,0 +1,1940 @@
+//! A directory of installed packs.
+//!
+//! ```text
+//! <root>/packs/<pack_id>/<version>.gtpack         installed archive
+//! <root>/packs/<pack_id>/<version>.record.json    install record
+//! <root>/packs/<pack_id>/<version>.health.json    latest health check
+//! <root>/pending/<pack_id>/<version>.gtpack       awaiting approval
+//! <root>/pending/<pack_id>/<version>.approval.json
+//! <root>/history.jsonl                            install history
+//! ```
+//!
+//! Every archive is verified before the store accepts it. A store configured
+//! with an [`ApprovalBackend`] does not install straight away: the pack is
+//! submitted for approval and parked under `pending/` in the
+//! [`PackState::PendingApproval`] state. [`PackStore::sync_approvals`] polls
+//! the backend, installs approved packs (verifying them again and checking
+//! they are the bytes that were submitted) and drops rejected ones, keeping
+//! the backend's decision record with the install record.
+//!
+//! A store can sit on top of read-only lower layers with the same layout,
+//! such as a base set of packs on a shared volume: lookups and listings see
+//! every layer, a higher layer's copy of a version shadows a lower one's, and
+//! installs only ever write to the upper layer. [`PackStore::conflicts`]
+//! reports versions held by more than one layer.
+//!
+//! [`PackStore::install_many`] installs a suite of interdependent packs as
+//! one transaction: every pack is verified and every import resolved before
+//! anything is written, and the packs are staged and moved into place
+//! together, so a failure leaves the store as it was. Each install is
+//! appended to the history journal as one [`InstallEvent`], however many
+//! packs it brought in, and an [`InstallListener`] hears about it once.
+//!
+//! [`PackStore::export_state`] captures the upper layer, records and history
+//! included, as a `.tar.zst` stream that [`PackStore::import_state`] restores
+//! into an empty store on another node, checking every file against the
+//! digests the state lists for it.
+//!
+//! [`PackStore::run_health_check`] runs a pack's `lifecycle.health` flow
+//! through the host's runner and keeps the outcome, which
+//! [`PackStore::latest_health`] reads back and
+//! [`PackStore::health_metrics`] exports in the Prometheus text format.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result, anyhow, bail};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+use crate::atomic::{self, Durability};
+use crate::builder::hex_hash;
+use crate::reader::{PackLoad, SignatureInfo, SigningPolicy, open_pack_bytes};
+
+const PACKS_DIR: &str = "packs";
+const PENDING_DIR: &str = "pending";
+const ARCHIVE_EXT: &str = "gtpack";
+const RECORD_SUFFIX: &str = ".record.json";
+const APPROVAL_SUFFIX: &str = ".approval.json";
+const HEALTH_SUFFIX: &str = ".health.json";
+const HISTORY_FILE: &str = "history.jsonl";
+const STATE_MANIFEST: &str = "state.json";
+const STATE_FORMAT: &str = "greentic-store-state-v1";
+/// Largest decompressed state [`PackStore::import_state`] accepts.
+const MAX_STATE_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PackState {
+    Installed,
+    /// Submitted to the approval backend and not installed yet.
+    PendingApproval,
+}
+
+impl fmt::Display for PackState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PackState::Installed => "installed",
+            PackState::PendingApproval => "pending-approval",
+        })
+    }
+}
+
+/// What the store is asked to approve.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    pub pack_id: String,
+    pub version: Version,
+    /// [`PackLoad::content_digest`] of the submitted archive.
+    pub content_digest: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<SignatureInfo>,
+    /// The parked archive, for backends that upload it for review.
+    pub archive: PathBuf,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// The approval system's account of a decision, kept with the install record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecisionRecord {
+    pub ticket: String,
+    pub approved: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decided_by: Option<String>,
+    pub decided_at_utc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Backend-specific evidence, e.g. a notarization receipt.
+    #[serde(default, skip_serializing_if = "JsonValue::is_null")]
+    pub details: JsonValue,
+}
+
+/// An external notarization or approval workflow that gates installs.
+pub trait ApprovalBackend: Send + Sync {
+    /// Submits a pack for approval and returns the ticket to poll.
+    fn submit(&self, request: &ApprovalRequest) -> Result<String>;
+    fn status(&self, ticket: &str) -> Result<ApprovalStatus>;
+    /// The decision behind an approved or rejected ticket.
+    fn decision(&self, ticket: &str) -> Result<DecisionRecord>;
+}
+
+/// Packs that one install operation added to the store.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstallEvent {
+    pub installed_at_utc: String,
+    pub packs: Vec<StoredPack>,
+}
+
+/// Told about every install that wrote to the store, after it is committed.
+pub trait InstallListener: Send + Sync {
+    fn installed(&self, event: &InstallEvent);
+}
+
+/// Written next to an installed archive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstallRecord {
+    pub pack_id: String,
+    pub version: Version,
+    pub content_digest: String,
+    pub installed_at_utc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<DecisionRecord>,
+}
+
+/// Written next to a parked archive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingRecord {
+    pub pack_id: String,
+    pub version: Version,
+    pub content_digest: String,
+    pub ticket: String,
+    pub submitted_at_utc: String,
+}
+
+/// Where in a layered store a pack lives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreLayer {
+    /// The writable layer every install goes to.
+    Upper,
+    /// A read-only layer, by its position in [`PackStore::with_lower_layer`]
+    /// order.
+    Lower(usize),
+}
+
+impl fmt::Display for StoreLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreLayer::Upper => f.write_str("upper"),
+            StoreLayer::Lower(index) => write!(f, "lower {index}"),
+        }
+    }
+}
+
+/// A pack the store holds, installed or pending.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredPack {
+    pub pack_id: String,
+    pub version: Version,
+    pub state: PackState,
+    pub content_digest: String,
+    pub archive: PathBuf,
+    pub layer: StoreLayer,
+}
+
+/// The flow an installed pack names as its `lifecycle.health`, for hosts
+/// to run periodically.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub pack_id: String,
+    pub version: Version,
+    pub flow: String,
+    pub archive: PathBuf,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    Unhealthy,
+}
+
+impl fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HealthStatus::Healthy => "healthy",
+            HealthStatus::Unhealthy => "unhealthy",
+        })
+    }
+}
+
+/// The outcome of a pack's latest health check, written next to its install
+/// record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthRecord {
+    pub pack_id: String,
+    pub version: Version,
+    pub flow: String,
+    pub status: HealthStatus,
+    pub checked_at_utc: String,
+    /// Why the check failed, for an unhealthy pack.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// `state.json`, the first entry of an exported store state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateManifest {
+    pub format: String,
+    pub exported_at_utc: String,
+    pub files: Vec<StateFile>,
+}
+
+/// A file in an exported state, by its path under the store root.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateFile {
+    pub path: String,
+    pub size: u64,
+    pub blake3: String,
+}
+
+/// The same pack version held by more than one layer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LayerConflict {
+    pub pack_id: String,
+    pub version: Version,
+    /// Every copy, the one in use first.
+    pub copies: Vec<StoredPack>,
+}
+
+impl LayerConflict {
+    /// Whether every copy has the same contents, so the shadowing is harmless.
+    pub fn identical(&self) -> bool {
+        self.copies
+            .windows(2)
+            .all(|pair| pair[0].content_digest == pair[1].content_digest)
+    }
+}
+
+impl fmt::Display for LayerConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let layers: Vec<String> = self
+            .copies
+            .iter()
+            .map(|pack| pack.layer.to_string())
+            .collect();
+        write!(
+            f,
+            "{}@{} is in layers {} with {} contents; the {} layer's copy is used",
+            self.pack_id,
+            self.version,
+            layers.join(", "),
+            if self.identical() {
+                "identical"
+            } else {
+                "different"
+            },
+            self.copies[0].layer
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallOutcome {
+    Installed(StoredPack),
+    PendingApproval { pack: StoredPack, ticket: String },
+}
+
+/// What [`PackStore::sync_approvals`] did with a pending pack.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApprovalOutcome {
+    StillPending { pack: StoredPack, ticket: String },
+    Installed(StoredPack, DecisionRecord),
+    Rejected(StoredPack, DecisionRecord),
+}
+
+pub struct PackStore {
+    root: PathBuf,
+    lowers: Vec<PathBuf>,
+    policy: SigningPolicy,
+    durability: Durability,
+    approval: Option<Arc<dyn ApprovalBackend>>,
+    listener: Option<Arc<dyn InstallListener>>,
+}
+
+impl fmt::Debug for PackStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PackStore")
+            .field("root", &self.root)
+            .field("lowers", &self.lowers)
+            .field("policy", &self.policy)
+            .field("approval", &self.approval.is_some())
+            .field("listener", &self.listener.is_some())
+            .finish()
+    }
+}
+
+impl PackStore {
+    /// Opens the store at `root`, creating it if needed. Packs must verify
+    /// under [`SigningPolicy::Strict`] unless [`PackStore::with_policy`] says
+    /// otherwise.
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        for dir in [PACKS_DIR, PENDING_DIR] {
+            let path = root.join(dir);
+            fs::create_dir_all(&path)
+                .with_context(|| format!("failed to create {}", path.display()))?;
+        }
+        Ok(Self {
+            root,
+            lowers: Vec::new(),
+            policy: SigningPolicy::Strict,
+            durability: Durability::Buffered,
+            approval: None,
+            listener: None,
+        })
+    }
+
+    /// Adds a read-only store below the existing layers, such as a base set
+    /// of packs on a shared volume. Packs in higher layers shadow the same
+    /// version in lower ones; nothing is ever written to a lower layer.
+    pub fn with_lower_layer(mut self, root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        if !root.join(PACKS_DIR).is_dir() {
+            bail!("{} is not a pack store", root.display());
+        }
+        self.lowers.push(root);
+        Ok(self)
+    }
+
+    pub fn with_policy(mut self, policy: SigningPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    /// Gates every install on `backend`.
+    pub fn with_approval_backend(mut self, backend: Arc<dyn ApprovalBackend>) -> Self {
+        self.approval = Some(backend);
+        self
+    }
+
+    pub fn with_install_listener(mut self, listener: Arc<dyn InstallListener>) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Root directory of `layer`.
+    pub fn layer_root(&self, layer: StoreLayer) -> Option<&Path> {
+        match layer {
+            StoreLayer::Upper => Some(&self.root),
+            StoreLayer::Lower(index) => self.lowers.get(index).map(PathBuf::as_path),
+        }
+    }
+
+    /// Verifies and installs the archive at `path`, or submits it for
+    /// approval when the store has an approval backend.
+    pub fn install(&self, path: &Path) -> Result<InstallOutcome> {
+        let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        self.install_bytes(&bytes)
+            .with_context(|| format!("failed to install {}", path.display()))
+    }
+
+    /// A pack a lower layer already holds with the same contents counts as
+    /// installed; one it holds with different contents cannot be installed.
+    pub fn install_bytes(&self, bytes: &[u8]) -> Result<InstallOutcome> {
+        let load = self.verify(bytes)?;
+        let meta = &load.manifest.meta;
+        let (pack_id, version) = (meta.pack_id.as_str(), &meta.version);
+        let content_digest = load.content_digest();
+
+        if let Some(existing) = self.get(pack_id, version)? {
+            if existing.content_digest != content_digest {
+                bail!(
+                    "{pack_id}@{version} is already {} in the {} layer with different contents",
+                    existing.state,
+                    existing.layer
+                );
+            }
+            return Ok(match existing.state {
+                PackState::Installed => InstallOutcome::Installed(existing),
+                PackState::PendingApproval => InstallOutcome::PendingApproval {
+                    ticket: self.pending_record(pack_id, version)?.ticket,
+                    pack: existing,
+                },
+            });
+        }
+
+        let Some(backend) = &self.approval else {
+            let pack = self.commit(pack_id, version, bytes, &content_digest, None)?;
+            self.notify(vec![pack.clone()])?;
+            return Ok(InstallOutcome::Installed(pack));
+        };
+
+        let archive = archive_path(&self.root, PENDING_DIR, pack_id, version);
+        atomic::write(&archive, bytes, self.durability)?;
+        let request = ApprovalRequest {
+            pack_id: pack_id.to_string(),
+            version: version.clone(),
+            content_digest: content_digest.clone(),
+            signature: load.signature(),
+            archive: archive.clone(),
+        };
+        let ticket = match backend.submit(&request) {
+            Ok(ticket) => ticket,
+            Err(err) => {
+                let _ = fs::remove_file(&archive);
+                return Err(err.context(format!("failed to submit {pack_id}@{version}")));
+            }
+        };
+        let record = PendingRecord {
+            pack_id: pack_id.to_string(),
+            version: version.clone(),
+            content_digest: content_digest.clone(),
+            ticket: ticket.clone(),
+            submitted_at_utc: now(),
+        };
+        write_json(
+            &sidecar(&self.root, PENDING_DIR, pack_id, version, APPROVAL_SUFFIX),
+            &record,
+            self.durability,
+        )?;
+        Ok(InstallOutcome::PendingApproval {
+            pack: StoredPack {
+                pack_id: pack_id.to_string(),
+                version: version.clone(),
+                state: PackState::PendingApproval,
+                content_digest,
+                archive,
+                layer: StoreLayer::Upper,
+            },
+            ticket,
+        })
+    }
+
+    /// Polls the approval backend for every pending pack, installing the
+    /// approved ones and removing the rejected ones.
+    pub fn sync_approvals(&self) -> Result<Vec<ApprovalOutcome>> {
+        let backend = self
+            .approval
+            .as_ref()
+            .ok_or_else(|| anyhow!("the store has no approval backend"))?;
+        let mut outcomes = Vec::new();
+        let pending = self
+            .list_layer(StoreLayer::Upper, &self.root)?
+            .into_iter()
+            .filter(|pack| pack.state == PackState::PendingApproval);
+        for pack in pending {
+            let record = self.pending_record(&pack.pack_id, &pack.version)?;
+            let outcome = match backend.status(&record.ticket)? {
+                ApprovalStatus::Pending => ApprovalOutcome::StillPending {
+                    pack,
+                    ticket: record.ticket,
+                },
+                ApprovalStatus::Approved => {
+                    let decision = backend.decision(&record.ticket)?;
+                    let bytes = fs::read(&pack.archive)
+                        .with_context(|| format!("failed to read {}", pack.archive.display()))?;
+                    let load = self.verify(&bytes)?;
+                    if load.content_digest() != record.content_digest {
+                        bail!(
+                            "{}@{} changed after it was submitted for approval",
+                            pack.pack_id,
+                            pack.version
+                        );
+                    }
+                    let installed = self.commit(
+                        &pack.pack_id,
+                        &pack.version,
+                        &bytes,
+                        &record.content_digest,
+                        Some(decision.clone()),
+                    )?;
+                    self.remove_pending(&pack)?;
+                    self.notify(vec![installed.clone()])?;
+                    ApprovalOutcome::Installed(installed, decision)
+                }
+                ApprovalStatus::Rejected => {
+                    let decision = backend.decision(&record.ticket)?;
+                    self.remove_pending(&pack)?;
+                    ApprovalOutcome::Rejected(pack, decision)
+                }
+            };
+            outcomes.push(outcome);
+        }
+        Ok(outcomes)
+    }
+
+    /// Installs the archives at `sources`, verified under `policy`, all or
+    /// none.
+    ///
+    /// Every archive is verified and every import of every pack resolved,
+    /// against the other packs in the set or packs the store already has
+    /// installed, before anything is written. The new packs are then staged
+    /// under the store root and moved into place; if a move fails, the ones
+    /// already moved are removed again. Packs the store already holds with
+    /// the same contents are returned as they are. Listeners hear about the
+    /// whole set in a single event.
+    ///
+    /// Stores with an approval backend approve packs one at a time, so they
+    /// do not support this.
+    pub fn install_many<P: AsRef<Path>>(
+        &self,
+        sources: &[P],
+        policy: SigningPolicy,
+    ) -> Result<Vec<StoredPack>> {
+        if self.approval.is_some() {
+            bail!("stores with an approval backend install packs one at a time");
+        }
+
+        let mut batch: Vec<(Vec<u8>, PackLoad)> = Vec::new();
+        for source in sources {
+            let path = source.as_ref();
+            let bytes =
+                fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+            let load = open_pack_bytes(&bytes, policy).map_err(|err| {
+                anyhow!("{} failed verification: {}", path.display(), err.message)
+            })?;
+            let meta = &load.manifest.meta;
+            if let Some((_, other)) = batch.iter().find(|(_, other)| {
+                other.manifest.meta.pack_id == meta.pack_id
+                    && other.manifest.meta.version == meta.version
+            }) {
+                if other.content_digest() != load.content_digest() {
+                    bail!(
+                        "{}@{} is listed twice with different contents",
+                        meta.pack_id,
+                        meta.version
+                    );
+                }
+                continue;
+            }
+            batch.push((bytes, load));
+        }
+        self.check_imports(&batch)?;
+
+        let mut packs = Vec::new();
+        let mut fresh = Vec::new();
+        for (bytes, load) in &batch {
+            let meta = &load.manifest.meta;
+            let content_digest = load.content_digest();
+            match self.get(&meta.pack_id, &meta.version)? {
+                Some(existing) if existing.content_digest != content_digest => bail!(
+                    "{}@{} is already {} in the {} layer with different contents",
+                    meta.pack_id,
+                    meta.version,
+                    existing.state,
+                    existing.layer
+                ),
+                Some(existing) if existing.state == PackState::PendingApproval => {
+                    bail!("{}@{} is pending approval", meta.pack_id, meta.version)
+                }
+                Some(existing) => packs.push(existing),
+                None => {
+                    packs.push(StoredPack {
+                        pack_id: meta.pack_id.clone(),
+                        version: meta.version.clone(),
+                        state: PackState::Installed,
+                        content_digest,
+                        archive: archive_path(&self.root, PACKS_DIR, &meta.pack_id, &meta.version),
+                        layer: StoreLayer::Upper,
+                    });
+                    fresh.push((bytes, packs.len() - 1));
+                }
+            }
+        }
+
+        let staging = tempfile::Builder::new()
+            .prefix(".install-")
+            .tempdir_in(&self.root)
+            .with_context(|| format!("failed to stage in {}", self.root.display()))?;
+        let installed_at_utc = now();
+        // Each record is moved before its archive, as in `commit`.
+        let mut moves = Vec::new();
+        for (index, (bytes, position)) in fresh.iter().enumerate() {
+            let pack = &packs[*position];
+            let record = InstallRecord {
+                pack_id: pack.pack_id.clone(),
+                version: pack.version.clone(),
+                content_digest: pack.content_digest.clone(),
+                installed_at_utc: installed_at_utc.clone(),
+                approval: None,
+            };
+            let staged_record = staging.path().join(format!("{index}{RECORD_SUFFIX}"));
+            write_json(&staged_record, &record, self.durability)?;
+            let staged_archive = staging.path().join(format!("{index}.{ARCHIVE_EXT}"));
+            atomic::write(&staged_archive, bytes, self.durability)?;
+            let record_target = sidecar(
+                &self.root,
+                PACKS_DIR,
+                &pack.pack_id,
+                &pack.version,
+                RECORD_SUFFIX,
+            );
+            moves.push((staged_record, record_target));
+            moves.push((staged_archive, pack.archive.clone()));
+        }
+
+        move_into_place(&moves)?;
+
+        self.notify(
+            fresh
+                .iter()
+                .map(|(_, position)| packs[*position].clone())
+                .collect(),
+        )?;
+        Ok(packs)
+    }
+
+    /// Each import in `batch` must be met by a pack in `batch` or an
+    /// installed pack in any layer.
+    fn check_imports(&self, batch: &[(Vec<u8>, PackLoad)]) -> Result<()> {
+        let installed: Vec<StoredPack> = self
+            .list()?
+            .into_iter()
+            .filter(|pack| pack.state == PackState::Installed)
+            .collect();
+        for (_, load) in batch {
+            let meta = &load.manifest.meta;
+            for import in &meta.imports {
+                let req = VersionReq::parse(&import.version_req).with_context(|| {
+                    format!(
+                        "{} imports {} with invalid requirement `{}`",
+                        meta.pack_id, import.pack_id, import.version_req
+                    )
+                })?;
+                let in_batch = batch.iter().any(|(_, other)| {
+                    other.manifest.meta.pack_id == import.pack_id
+                        && req.matches(&other.manifest.meta.version)
+                });
+                let in_store = installed
+                    .iter()
+                    .any(|pack| pack.pack_id == import.pack_id && req.matches(&pack.version));
+                if !in_batch && !in_store {
+                    bail!(
+                        "{} requires {} {}, which neither the install set nor the store provides",
+                        meta.pack_id,
+                        import.pack_id,
+                        import.version_req
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the upper layer (installed and pending packs with their
+    /// records, and the history journal) to `out` as a zstd-compressed tar
+    /// stream for [`PackStore::import_state`]. Lower layers are not included.
+    pub fn export_state(&self, out: &Path) -> Result<StateManifest> {
+        let mut files = Vec::new();
+        for dir in [PACKS_DIR, PENDING_DIR] {
+            for pack_dir in read_dir_sorted(&self.root.join(dir))? {
+                for path in read_dir_sorted(&pack_dir)? {
+                    let hidden = path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .is_none_or(|name| name.starts_with('.'));
+                    if path.is_file() && !hidden {
+                        files.push(path);
+                    }
+                }
+            }
+        }
+        let history = self.root.join(HISTORY_FILE);
+        if history.is_file() {
+            files.push(history);
+        }
+
+        let mut manifest = StateManifest {
+            format: STATE_FORMAT.to_string(),
+            exported_at_utc: now(),
+            files: Vec::new(),
+        };
+        let mut entries = Vec::new();
+        for path in files {
+            let bytes =
+                fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+            let relative = path
+                .strip_prefix(&self.root)
+                .expect("store files live under the root")
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            manifest.files.push(StateFile {
+                path: relative.clone(),
+                size: bytes.len() as u64,
+                blake3: hex_hash(&bytes),
+            });
+            entries.push((relative, bytes));
+        }
+
+        let mut tar = tar::Builder::new(Vec::new());
+        let manifest_json =
+            serde_json::to_vec_pretty(&manifest).context("failed to serialise the state")?;
+        tar_append(&mut tar, STATE_MANIFEST, &manifest_json)?;
+        for (path, bytes) in &entries {
+            tar_append(&mut tar, path, bytes)?;
+        }
+        let tar = tar.into_inner().context("failed to finish the state")?;
+        let compressed = ruzstd::encoding::compress_to_vec(
+            tar.as_slice(),
+            ruzstd::encoding::CompressionLevel::Fastest,
+        );
+        atomic::write(out, &compressed, self.durability)?;
+        Ok(manifest)
+    }
+
+    /// Restores a state written by [`PackStore::export_state`] into this
+    /// store, whose upper layer must be empty.
+    ///
+    /// Every file must match the size and BLAKE3 digest the state lists for
+    /// it, and every archive must have its record, before anything is
+    /// written. With `reverify`, each archive is also verified again under
+    /// the store's signing policy and must have the content digest its record
+    /// names. Files are staged and moved into place together, as with
+    /// [`PackStore::install_many`].
+    pub fn import_state(&self, path: &Path, reverify: bool) -> Result<Vec<StoredPack>> {
+        if !self.list_layer(StoreLayer::Upper, &self.root)?.is_empty()
+            || self.root.join(HISTORY_FILE).exists()
+        {
+            bail!(
+                "{} is not empty; import into a new store",
+                self.root.display()
+            );
+        }
+        let compressed =
+            fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let decoder = ruzstd::decoding::StreamingDecoder::new(compressed.as_slice())
+            .map_err(|err| anyhow!("{} is not a zstd stream: {err}", path.display()))?;
+        let mut tar = Vec::new();
+        decoder
+            .take(MAX_STATE_BYTES + 1)
+            .read_to_end(&mut tar)
+            .with_context(|| format!("failed to decompress {}", path.display()))?;
+        if tar.len() as u64 > MAX_STATE_BYTES {
+            bail!(
+                "{} decompresses to more than {MAX_STATE_BYTES} bytes",
+                path.display()
+            );
+        }
+
+        let mut entries = tar_entries(&tar)?.into_iter();
+        let manifest: StateManifest = match entries.next() {
+            Some((name, bytes)) if name == STATE_MANIFEST => {
+                serde_json::from_slice(&bytes).context("state.json is invalid")?
+            }
+            _ => bail!("{} does not start with {STATE_MANIFEST}", path.display()),
+        };
+        if manifest.format != STATE_FORMAT {
+            bail!("unexpected state format: {}", manifest.format);
+        }
+        let mut contents: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+        for (name, bytes) in entries {
+            let Some(file) = manifest.files.iter().find(|file| file.path == name) else {
+                bail!("{name} is not listed in {STATE_MANIFEST}");
+            };
+            if bytes.len() as u64 != file.size || hex_hash(&bytes) != file.blake3 {
+                bail!("{name} does not match the digest in {STATE_MANIFEST}");
+            }
+            if contents.insert(name.clone(), bytes).is_some() {
+                bail!("{name} appears more than once");
+            }
+        }
+        for file in &manifest.files {
+            check_state_path(&file.path)?;
+            if !contents.contains_key(&file.path) {
+                bail!("{} is listed in {STATE_MANIFEST} but missing", file.path);
+            }
+        }
+
+        for (name, bytes) in &contents {
+            let Some(stem) = name.strip_suffix(&format!(".{ARCHIVE_EXT}")) else {
+                continue;
+            };
+            let parts: Vec<&str> = stem.split('/').collect();
+            let [dir, pack_id, version] = parts[..] else {
+                bail!("{name} is not where the store keeps archives");
+            };
+            let version = Version::parse(version)
+                .map_err(|_| anyhow!("{name} is not named after a version"))?;
+            let suffix = if dir == PACKS_DIR {
+                RECORD_SUFFIX
+            } else {
+                APPROVAL_SUFFIX
+            };
+            let record_name = format!("{stem}{suffix}");
+            let record = contents
+                .get(&record_name)
+                .ok_or_else(|| anyhow!("{name} has no {suffix} record"))?;
+            let (recorded_id, recorded_version, content_digest) = if dir == PACKS_DIR {
+                let record: InstallRecord = serde_json::from_slice(record)
+                    .with_context(|| format!("{record_name} is not valid"))?;
+                (record.pack_id, record.version, record.content_digest)
+            } else {
+                let record: PendingRecord = serde_json::from_slice(record)
+                    .with_context(|| format!("{record_name} is not valid"))?;
+                (record.pack_id, record.version, record.content_digest)
+            };
+            if recorded_id != pack_id || recorded_version != version {
+                bail!("{record_name} describes {recorded_id}@{recorded_version}");
+            }
+            if !reverify {
+                continue;
+            }
+            let load = self
+                .verify(bytes)
+                .with_context(|| format!("{pack_id}@{version} no longer verifies"))?;
+            let meta = &load.manifest.meta;
+            if meta.pack_id != pack_id || meta.version != version {
+                bail!("{name} holds {}@{}", meta.pack_id, meta.version);
+            }
+            if load.content_digest() != content_digest {
+                bail!("{name} does not match the content digest in {record_name}");
+            }
+        }
+
+        let staging = tempfile::Builder::new()
+            .prefix(".import-")
+            .tempdir_in(&self.root)
+            .with_context(|| format!("failed to stage in {}", self.root.display()))?;
+        // Records before archives, so an archive never appears without one.
+        let mut names: Vec<&String> = contents.keys().collect();
+        names.sort_by_key(|name| name.ends_with(&format!(".{ARCHIVE_EXT}")));
+        let mut moves = Vec::new();
+        for (index, name) in names.into_iter().enumerate() {
+            let staged = staging.path().join(index.to_string());
+            atomic::write(&staged, &contents[name], self.durability)?;
+            moves.push((staged, self.root.join(name)));
+        }
+        move_into_place(&moves)?;
+        self.list_layer(StoreLayer::Upper, &self.root)
+    }
+
+    /// Install events recorded in the history journal, oldest first.
+    pub fn history(&self) -> Result<Vec<InstallEvent>> {
+        let path = self.root.join(HISTORY_FILE);
+        let journal = match fs::read_to_string(&path) {
+            Ok(journal) => journal,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()));
+            }
+        };
+        journal
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(index, line)| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("{} line {} is invalid", path.display(), index + 1))
+            })
+            .collect()
+    }
+
+    /// Every pack in the store, installed ones first, by id and version. A
+    /// version held by several layers is listed once, from the highest.
+    pub fn list(&self) -> Result<Vec<StoredPack>> {
+        let mut packs: Vec<StoredPack> = Vec::new();
+        for copy in self.all_copies()? {
+            if !packs
+                .iter()
+                .any(|pack| pack.pack_id == copy.pack_id && pack.version == copy.version)
+            {
+                packs.push(copy);
+            }
+        }
+        packs.sort_by(|a, b| {
+            a.state
+                .cmp(&b.state)
+                .then_with(|| a.pack_id.cmp(&b.pack_id))
+                .then_with(|| a.version.cmp(&b.version))
+        });
+        Ok(packs)
+    }
+
+    /// Pack versions held by more than one layer.
+    pub fn conflicts(&self) -> Result<Vec<LayerConflict>> {
+        let mut conflicts: Vec<LayerConflict> = Vec::new();
+        for copy in self.all_copies()? {
+            match conflicts.iter_mut().find(|conflict| {
+                conflict.pack_id == copy.pack_id && conflict.version == copy.version
+            }) {
+                Some(conflict) => conflict.copies.push(copy),
+                None => conflicts.push(LayerConflict {
+                    pack_id: copy.pack_id.clone(),
+                    version: copy.version.clone(),
+                    copies: vec![copy],
+                }),
+            }
+        }
+        conflicts.retain(|conflict| conflict.copies.len() > 1);
+        conflicts.sort_by(|a, b| {
+            a.pack_id
+                .cmp(&b.pack_id)
+                .then_with(|| a.version.cmp(&b.version))
+        });
+        Ok(conflicts)
+    }
+
+    /// The pack `pack_id@version` from the highest layer holding it,
+    /// installed or pending.
+    pub fn get(&self, pack_id: &str, version: &Version) -> Result<Option<StoredPack>> {
+        for (layer, root) in self.layers() {
+            if let Some(pack) = self.get_in(layer, root, pack_id, version)? {
+                return Ok(Some(pack));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The install record of `pack_id@version`, from the highest layer that
+    /// has it installed.
+    pub fn install_record(&self, pack_id: &str, version: &Version) -> Result<InstallRecord> {
+        let root = self
+            .layers()
+            .map(|(_, root)| root)
+            .find(|root| archive_path(root, PACKS_DIR, pack_id, version).exists())
+            .ok_or_else(|| anyhow!("{pack_id}@{version} is not installed"))?;
+        read_json(&sidecar(root, PACKS_DIR, pack_id, version, RECORD_SUFFIX))
+    }
+
+    /// The health check of the installed `pack_id@version`, or `None` when
+    /// the pack names no `lifecycle.health` flow.
+    pub fn health_check(&self, pack_id: &str, version: &Version) -> Result<Option<HealthCheck>> {
+        match self.get(pack_id, version)? {
+            Some(pack) if pack.state == PackState::Installed => self.health_of(pack),
+            _ => bail!("{pack_id}@{version} is not installed"),
+        }
+    }
+
+    /// The health checks of every installed pack that names one, by id and
+    /// version. Pending packs have none until they are installed.
+    pub fn health_checks(&self) -> Result<Vec<HealthCheck>> {
+        let mut checks = Vec::new();
+        for pack in self.list()? {
+            if pack.state == PackState::Installed
+                && let Some(check) = self.health_of(pack)?
+            {
+                checks.push(check);
+            }
+        }
+        Ok(checks)
+    }
+
+    fn health_of(&self, pack: StoredPack) -> Result<Option<HealthCheck>> {
+        let bytes = fs::read(&pack.archive)
+            .with_context(|| format!("failed to read {}", pack.archive.display()))?;
+        let flow = self
+            .verify(&bytes)?
+            .manifest
+            .meta
+            .lifecycle
+            .and_then(|lifecycle| lifecycle.health);
+        Ok(flow.map(|flow| HealthCheck {
+            pack_id: pack.pack_id,
+            version: pack.version,
+            flow,
+            archive: pack.archive,
+        }))
+    }
+
+    /// Runs `check` through `run`, the host's flow runner, and records the
+    /// outcome as the pack's latest health: healthy when `run` succeeds,
+    /// unhealthy with its error otherwise.
+    pub fn run_health_check<F>(&self, check: &HealthCheck, run: F) -> Result<HealthRecord>
+    where
+        F: FnOnce(&HealthCheck) -> Result<()>,
+    {
+        let error = run(check).err().map(|err| format!("{err:#}"));
+        self.record_health(check, error)
+    }
+
+    /// Records the outcome of a health check run by the caller, `error`
+    /// being why it failed. The record goes to the upper layer, whichever
+    /// layer holds the pack.
+    pub fn record_health(
+        &self,
+        check: &HealthCheck,
+        error: Option<String>,
+    ) -> Result<HealthRecord> {
+        let record = HealthRecord {
+            pack_id: check.pack_id.clone(),
+            version: check.version.clone(),
+            flow: check.flow.clone(),
+            status: if error.is_none() {
+                HealthStatus::Healthy
+            } else {
+                HealthStatus::Unhealthy
+            },
+            checked_at_utc: now(),
+            error,
+        };
+        write_json(
+            &sidecar(
+                &self.root,
+                PACKS_DIR,
+                &check.pack_id,
+                &check.version,
+                HEALTH_SUFFIX,
+            ),
+            &record,
+            self.durability,
+        )?;
+        Ok(record)
+    }
+
+    /// The latest recorded health of `pack_id@version`, from the highest
+    /// layer that has one, or `None` when it was never checked.
+    pub fn latest_health(&self, pack_id: &str, version: &Version) -> Result<Option<HealthRecord>> {
+        for (_, root) in self.layers() {
+            let path = sidecar(root, PACKS_DIR, pack_id, version, HEALTH_SUFFIX);
+            if path.exists() {
+                return read_json(&path).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// The latest health of every installed pack that was checked, by id and
+    /// version.
+    pub fn health_records(&self) -> Result<Vec<HealthRecord>> {
+        let mut records = Vec::new();
+        for pack in self.list()? {
+            if pack.state == PackState::Installed
+                && let Some(record) = self.latest_health(&pack.pack_id, &pack.version)?
+            {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    /// [`PackStore::health_records`] as Prometheus gauges: whether each
+    /// pack's latest check passed, and when it ran.
+    pub fn health_metrics(&self) -> Result<String> {
+        let records = self.health_records()?;
+        let mut healthy = String::from(
+            "# HELP greentic_pack_healthy Whether the pack's latest health check passed.\n\
+             # TYPE greentic_pack_healthy gauge\n",
+        );
+        let mut checked = String::from(
+            "# HELP greentic_pack_health_checked_timestamp_seconds When the pack's latest health check ran.\n\
+             # TYPE greentic_pack_health_checked_timestamp_seconds gauge\n",
+        );
+        for record in &records {
+            let labels = format!(
+                "pack_id=\"{}\",version=\"{}\",flow=\"{}\"",
+                metric_label(&record.pack_id),
+                record.version,
+                metric_label(&record.flow)
+            );
+            let up = u8::from(record.status == HealthStatus::Healthy);
+            healthy.push_str(&format!("greentic_pack_healthy{{{labels}}} {up}\n"));
+            let at =
+                OffsetDateTime::parse(&record.checked_at_utc, &Rfc3339).with_context(|| {
+                    format!(
+                        "{}@{} has an invalid health check time",
+                        record.pack_id, record.version
+                    )
+                })?;
+            checked.push_str(&format!(
+                "greentic_pack_health_checked_timestamp_seconds{{{labels}}} {}\n",
+                at.unix_timestamp()
+            ));
+        }
+        healthy.push_str(&checked);
+        Ok(healthy)
+    }
+
+    pub fn pending_record(&self, pack_id: &str, version: &Version) -> Result<PendingRecord> {
+        read_json(&sidecar(
+            &self.root,
+            PENDING_DIR,
+            pack_id,
+            version,
+            APPROVAL_SUFFIX,
+        ))
+    }
+
+    /// Appends an install to the history journal and tells the listener.
+    fn notify(&self, packs: Vec<StoredPack>) -> Result<()> {
+        if packs.is_empty() {
+            return Ok(());
+        }
+        let event = InstallEvent {
+            installed_at_utc: now(),
+            packs,
+        };
+        let path = self.root.join(HISTORY_FILE);
+        let mut line = serde_json::to_vec(&event).context("failed to serialise the install")?;
+        line.push(b'\n');
+        let mut journal = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        journal
+            .write_all(&line)
+            .with_context(|| format!("failed to append to {}", path.display()))?;
+        if self.durability == Durability::Fsync {
+            journal
+                .sync_all()
+                .with_context(|| format!("failed to sync {}", path.display()))?;
+        }
+        if let Some(listener) = &self.listener {
+            listener.installed(&event);
+        }
+        Ok(())
+    }
+
+    fn verify(&self, bytes: &[u8]) -> Result<PackLoad> {
+        open_pack_bytes(bytes, self.policy)
+            .map_err(|err| anyhow!("pack failed verification: {}", err.message))
+    }
+
+    /// The upper layer, then the lower ones in order.
+    fn layers(&self) -> impl Iterator<Item = (StoreLayer, &Path)> {
+        std::iter::once((StoreLayer::Upper, self.root.as_path())).chain(
+            self.lowers
+                .iter()
+                .enumerate()
+                .map(|(index, root)| (StoreLayer::Lower(index), root.as_path())),
+        )
+    }
+
+    /// Every copy in every layer, highest layer first.
+    fn all_copies(&self) -> Result<Vec<StoredPack>> {
+        let mut copies = Vec::new();
+        for (layer, root) in self.layers() {
+            copies.extend(self.list_layer(layer, root)?);
+        }
+        Ok(copies)
+    }
+
+    /// Only the upper layer holds pending packs.
+    fn get_in(
+        &self,
+        layer: StoreLayer,
+        root: &Path,
+        pack_id: &str,
+        version: &Version,
+    ) -> Result<Option<StoredPack>> {
+        let installed = archive_path(root, PACKS_DIR, pack_id, version);
+        if installed.exists() {
+            let record: InstallRecord =
+                read_json(&sidecar(root, PACKS_DIR, pack_id, version, RECORD_SUFFIX))?;
+            return Ok(Some(StoredPack {
+                pack_id: pack_id.to_string(),
+                version: version.clone(),
+                state: PackState::Installed,
+                content_digest: record.content_digest,
+                archive: installed,
+                layer,
+            }));
+        }
+        let pending = archive_path(root, PENDING_DIR, pack_id, version);
+        if layer == StoreLayer::Upper && pending.exists() {
+            let record = self.pending_record(pack_id, version)?;
+            return Ok(Some(StoredPack {
+                pack_id: pack_id.to_string(),
+                version: version.clone(),
+                state: PackState::PendingApproval,
+                content_digest: record.content_digest,
+                archive: pending,
+                layer,
+            }));
+        }
+        Ok(None)
+    }
+
+    /// Writes the install record, then the archive, so an archive in
+    /// `packs/` always has its record.
+    fn commit(
+        &self,
+        pack_id: &str,
+        version: &Version,
+        bytes: &[u8],
+        content_digest: &str,
+        approval: Option<DecisionRecord>,
+    ) -> Result<StoredPack> {
+        let record = InstallRecord {
+            pack_id: pack_id.to_string(),
+            version: version.clone(),
+            content_digest: content_digest.to_string(),
+            installed_at_utc: now(),
+            approval,
+        };
+        write_json(
+            &sidecar(&self.root, PACKS_DIR, pack_id, version, RECORD_SUFFIX),
+            &record,
+            self.durability,
+        )?;
+        let archive = archive_path(&self.root, PACKS_DIR, pack_id, version);
+        atomic::write(&archive, bytes, self.durability)?;
+        Ok(StoredPack {
+            pack_id: pack_id.to_string(),
+            version: version.clone(),
+            state: PackState::Installed,
+            content_digest: content_digest.to_string(),
+            archive,
+            layer: StoreLayer::Upper,
+        })
+    }
+
+    fn remove_pending(&self, pack: &StoredPack) -> Result<()> {
+        let record = sidecar(
+            &self.root,
+            PENDING_DIR,
+            &pack.pack_id,
+            &pack.version,
+            APPROVAL_SUFFIX,
+        );
+        for path in [&pack.archive, &record] {
+            fs::remove_file(path)
+                .with_context(|| format!("failed to remove {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Every pack in one layer, by id and version, installed before pending.
+    fn list_layer(&self, layer: StoreLayer, root: &Path) -> Result<Vec<StoredPack>> {
+        let mut packs = Vec::new();
+        for dir in [PACKS_DIR, PENDING_DIR] {
+            for pack_dir in read_dir_sorted(&root.join(dir))? {
+                let Some(pack_id) = pack_dir.file_name().and_then(|name| name.to_str()) else {
+                    continue;
+                };
+                for archive in read_dir_sorted(&pack_dir)? {
+                    if archive.extension().and_then(|ext| ext.to_str()) != Some(ARCHIVE_EXT) {
+                        continue;
+                    }
+                    let Some(version) = archive
+                        .file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .and_then(|stem| Version::parse(stem).ok())
+                    else {
+                        continue;
+                    };
+                    // A version both pending and installed shows up once.
+                    if let Some(pack) = self.get_in(layer, root, pack_id, &version)?
+                        && pack.archive == archive
+                    {
+                        packs.push(pack);
+                    }
+                }
+            }
+        }
+        Ok(packs)
+    }
+}
+
+fn archive_path(root: &Path, dir: &str, pack_id: &str, version: &Version) -> PathBuf {
+    root.join(dir)
+        .join(pack_id)
+        .join(format!("{version}.{ARCHIVE_EXT}"))
+}
+
+fn sidecar(root: &Path, dir: &str, pack_id: &str, version: &Version, suffix: &str) -> PathBuf {
+    root.join(dir)
+        .join(pack_id)
+        .join(format!("{version}{suffix}"))
+}
+
+/// Moves each staged file onto its target, in order. If a move fails the
+/// files already moved are removed again.
+fn move_into_place(moves: &[(PathBuf, PathBuf)]) -> Result<()> {
+    let mut moved: Vec<&Path> = Vec::new();
+    for (staged, target) in moves {
+        let result = match target.parent() {
+            Some(parent) => fs::create_dir_all(parent),
+            None => Ok(()),
+        }
+        .and_then(|_| fs::rename(staged, target));
+        if let Err(err) = result {
+            for path in moved.iter().rev() {
+                let _ = fs::remove_file(path);
+            }
+            return Err(err).with_context(|| format!("failed to install {}", target.display()));
+        }
+        moved.push(target);
+    }
+    Ok(())
+}
+
+/// Only the history journal and files two levels under `packs/` or
+/// `pending/` belong in a store state.
+fn check_state_path(path: &str) -> Result<()> {
+    let parts: Vec<&str> = path.split('/').collect();
+    let valid = match parts[..] {
+        [file] => file == HISTORY_FILE,
+        [dir, pack_id, file] => {
+            (dir == PACKS_DIR || dir == PENDING_DIR)
+                && [pack_id, file]
+                    .iter()
+                    .all(|part| !part.is_empty() && !part.starts_with('.') && !part.contains('\\'))
+        }
+        _ => false,
+    };
+    if !valid {
+        bail!("{path} is not a store file");
+    }
+    Ok(())
+}
+
+/// Appends `bytes` to a tar stream as the regular file `path`.
+fn tar_append(tar: &mut tar::Builder<Vec<u8>>, path: &str, bytes: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_mode(0o644);
+    header.set_size(bytes.len() as u64);
+    tar.append_data(&mut header, path, bytes)
+        .with_context(|| format!("failed to add {path} to the state"))
+}
+
+/// The regular files of a tar stream, in order.
+fn tar_entries(tar: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut archive = tar::Archive::new(tar);
+    let mut entries = Vec::new();
+    for entry in archive.entries().context("failed to read the state")? {
+        let mut entry = entry.context("failed to read the state")?;
+        let name = entry
+            .path()
+            .context("tar entry has an invalid path")?
+            .to_str()
+            .ok_or_else(|| anyhow!("tar entry path is not UTF-8"))?
+            .to_string();
+        if !entry.header().entry_type().is_file() {
+            bail!("tar entry {name} is not a regular file");
+        }
+        let mut bytes = Vec::new();
+        entry
+            .read_to_end(&mut bytes)
+            .with_context(|| format!("tar entry {name} is truncated"))?;
+        entries.push((name, bytes));
+    }
+    Ok(entries)
+}
+
+fn read_dir_sorted(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("failed to list {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("failed to list {}", dir.display()))?;
+    entries.sort();
+    Ok(entries)
+}
+
+/// Escapes a Prometheus label value.
+fn metric_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T> {
+    let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_slice(&bytes).with_context(|| format!("{} is not valid", path.display()))
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T, durability: Durability) -> Result<()> {
+    let json = serde_json::to_vec_pretty(value)
+        .with_context(|| format!("failed to serialise {}", path.display()))?;
+    atomic::write(path, &json, durability)
+}
+
+fn now() -> String {
+    OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string())
+}
+
+#[cfg(all(test, feature = "builder"))]
+mod tests {
+    use super::*;
+    use crate::builder::{FlowBundle, PackBuilder, PackMeta, Signing, hex_hash};
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    fn pack(dir: &Path, pack_id: &str, version: &str) -> Vec<u8> {
+        pack_importing(dir, pack_id, version, &[])
+    }
+
+    fn pack_with_meta(dir: &Path, meta: JsonValue) -> Vec<u8> {
+        let meta: PackMeta = serde_json::from_value(meta).unwrap();
+        let flow = FlowBundle {
+            id: "main".into(),
+            kind: "flow/v1".into(),
+            entry: "start".into(),
+            yaml: "id: main\nentry: start\nnodes: {}\n".into(),
+            json: json!({"id": "main", "entry": "start", "nodes": {}}),
+            hash_blake3: hex_hash(b"main"),
+            nodes: Vec::new(),
+        };
+        let path = dir.join(format!("{}-{}.gtpack", meta.pack_id, meta.version));
+        PackBuilder::new(meta)
+            .with_flow(flow)
+            .with_signing(Signing::Dev)
+            .build(&path)
+            .unwrap();
+        fs::read(path).unwrap()
+    }
+
+    fn pack_importing(
+        dir: &Path,
+        pack_id: &str,
+        version: &str,
+        imports: &[(&str, &str)],
+    ) -> Vec<u8> {
+        let imports: Vec<_> = imports
+            .iter()
+            .map(|(pack_id, version_req)| json!({"pack_id": pack_id, "version_req": version_req}))
+            .collect();
+        pack_with_meta(
+            dir,
+            json!({
+                "pack_id": pack_id,
+                "version": version,
+                "name": pack_id,
+                "imports": imports,
+                "entry_flows": ["main"],
+                "created_at_utc": "2025-01-01T00:00:00Z",
+            }),
+        )
+    }
+
+    /// Approves or rejects tickets as the test decides.
+    #[derive(Default)]
+    struct Desk {
+        tickets: Mutex<BTreeMap<String, ApprovalStatus>>,
+    }
+
+    impl Desk {
+        fn decide(&self, ticket: &str, status: ApprovalStatus) {
+            self.tickets
+                .lock()
+                .unwrap()
+                .insert(ticket.to_string(), status);
+        }
+    }
+
+    impl ApprovalBackend for Desk {
+        fn submit(&self, request: &ApprovalRequest) -> Result<String> {
+            let ticket = format!("T-{}@{}", request.pack_id, request.version);
+            self.decide(&ticket, ApprovalStatus::Pending);
+            Ok(ticket)
+        }
+
+        fn status(&self, ticket: &str) -> Result<ApprovalStatus> {
+            self.tickets
+                .lock()
+                .unwrap()
+                .get(ticket)
+                .copied()
+                .ok_or_else(|| anyhow!("unknown ticket {ticket}"))
+        }
+
+        fn decision(&self, ticket: &str) -> Result<DecisionRecord> {
+            Ok(DecisionRecord {
+                ticket: ticket.to_string(),
+                approved: self.status(ticket)? == ApprovalStatus::Approved,
+                decided_by: Some("secops".into()),
+                decided_at_utc: "2025-01-02T00:00:00Z".into(),
+                reason: None,
+                details: JsonValue::Null,
+            })
+        }
+    }
+
+    #[test]
+    fn installs_wait_for_approval() {
+        let dir = tempdir().unwrap();
+        let desk = Arc::new(Desk::default());
+        let store = PackStore::open(dir.path().join("store"))
+            .unwrap()
+            .with_policy(SigningPolicy::DevOk)
+            .with_approval_backend(desk.clone());
+
+        let demo = pack(dir.path(), "demo.app", "0.1.0");
+        let InstallOutcome::PendingApproval {
+            ticket,
+            pack: pending,
+        } = store.install_bytes(&demo).unwrap()
+        else {
+            panic!("install should wait for approval");
+        };
+        assert_eq!(pending.state, PackState::PendingApproval);
+        // Submitting the same pack again keeps the ticket.
+        assert!(matches!(
+            store.install_bytes(&demo).unwrap(),
+            InstallOutcome::PendingApproval { ticket: again, .. } if again == ticket
+        ));
+        let other = pack(dir.path(), "demo.other", "1.0.0");
+        store.install_bytes(&other).unwrap();
+
+        let outcomes = store.sync_approvals().unwrap();
+        assert!(
+            outcomes
+                .iter()
+                .all(|outcome| matches!(outcome, ApprovalOutcome::StillPending { .. }))
+        );
+
+        desk.decide(&ticket, ApprovalStatus::Approved);
+        desk.decide("T-demo.other@1.0.0", ApprovalStatus::Rejected);
+        let outcomes = store.sync_approvals().unwrap();
+        assert!(
+            matches!(&outcomes[0], ApprovalOutcome::Installed(pack, _) if pack.pack_id == "demo.app")
+        );
+        assert!(
+            matches!(&outcomes[1], ApprovalOutcome::Rejected(pack, _) if pack.pack_id == "demo.other")
+        );
+
+        let listed = store.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].state, PackState::Installed);
+        let version = Version::new(0, 1, 0);
+        let record = store.install_record("demo.app", &version).unwrap();
+        assert_eq!(
+            record.approval.unwrap().decided_by.as_deref(),
+            Some("secops")
+        );
+        assert!(
+            store
+                .get("demo.other", &Version::new(1, 0, 0))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn stores_without_a_backend_install_directly() {
+        let dir = tempdir().unwrap();
+        let store = PackStore::open(dir.path().join("store"))
+            .unwrap()
+            .with_policy(SigningPolicy::DevOk);
+        let demo = pack(dir.path(), "demo.app", "0.1.0");
+        assert!(matches!(
+            store.install_bytes(&demo).unwrap(),
+            InstallOutcome::Installed(_)
+        ));
+        assert!(store.sync_approvals().is_err());
+
+        let strict = PackStore::open(dir.path().join("strict")).unwrap();
+        let err = strict.install_bytes(&demo).unwrap_err();
+        assert!(err.to_string().contains("failed verification"), "{err}");
+    }
+
+    #[test]
+    fn health_checks_list_installed_packs_naming_a_flow() {
+        let dir = tempdir().unwrap();
+        let desk = Arc::new(Desk::default());
+        let store = PackStore::open(dir.path().join("store"))
+            .unwrap()
+            .with_policy(SigningPolicy::DevOk);
+        let checked = pack_with_meta(
+            dir.path(),
+            json!({
+                "pack_id": "demo.checked",
+                "version": "1.0.0",
+                "name": "demo.checked",
+                "entry_flows": ["main"],
+                "created_at_utc": "2025-01-01T00:00:00Z",
+                "lifecycle": { "health": "main" },
+            }),
+        );
+        store.install_bytes(&checked).unwrap();
+        store
+            .install_bytes(&pack(dir.path(), "demo.plain", "1.0.0"))
+            .unwrap();
+        let gated = PackStore::open(dir.path().join("store"))
+            .unwrap()
+            .with_policy(SigningPolicy::DevOk)
+            .with_approval_backend(desk);
+        gated
+            .install_bytes(&pack(dir.path(), "demo.pending", "1.0.0"))
+            .unwrap();
+
+        let version = Version::new(1, 0, 0);
+        let checks = store.health_checks().unwrap();
+        assert_eq!(checks.len(), 1);
+        assert_eq!(
+            (checks[0].pack_id.as_str(), checks[0].flow.as_str()),
+            ("demo.checked", "main")
+        );
+        assert_eq!(
+            store.health_check("demo.checked", &version).unwrap(),
+            Some(checks[0].clone())
+        );
+        assert_eq!(store.health_check("demo.plain", &version).unwrap(), None);
+        assert!(store.health_check("demo.pending", &version).is_err());
+    }
+
+    #[test]
+    fn health_check_outcomes_are_kept_and_exported() {
+        let dir = tempdir().unwrap();
+        let store = PackStore::open(dir.path().join("store"))
+            .unwrap()
+            .with_policy(SigningPolicy::DevOk);
+        let checked = pack_with_meta(
+            dir.path(),
+            json!({
+                "pack_id": "demo.checked",
+                "version": "1.0.0",
+                "name": "demo.checked",
+                "entry_flows": ["main"],
+                "created_at_utc": "2025-01-01T00:00:00Z",
+                "lifecycle": { "health": "main" },
+            }),
+        );
+        store.install_bytes(&checked).unwrap();
+        let version = Version::new(1, 0, 0);
+        assert_eq!(store.latest_health("demo.checked", &version).unwrap(), None);
+
+        let check = store
+            .health_check("demo.checked", &version)
+            .unwrap()
+            .unwrap();
+        let mut ran = Vec::new();
+        let record = store
+            .run_health_check(&check, |check| {
+                ran.push(check.flow.clone());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(ran, ["main"]);
+        assert_eq!(record.status, HealthStatus::Healthy);
+        assert_eq!(
+            store.latest_health("demo.checked", &version).unwrap(),
+            Some(record)
+        );
+
+        let record = store
+            .run_health_check(&check, |_| bail!("upstream \"weather\" is down"))
+            .unwrap();
+        let latest = store
+            .latest_health("demo.checked", &version)
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest, record);
+        assert_eq!(latest.status, HealthStatus::Unhealthy);
+        assert_eq!(
+            latest.error.as_deref(),
+            Some("upstream \"weather\" is down")
+        );
+        assert!(OffsetDateTime::parse(&latest.checked_at_utc, &Rfc3339).is_ok());
+
+        let metrics = store.health_metrics().unwrap();
+        assert!(
+            metrics.contains(
+                "greentic_pack_healthy{pack_id=\"demo.checked\",version=\"1.0.0\",flow=\"main\"} 0\n"
+            ),
+            "{metrics}"
+        );
+        assert!(
+            metrics.contains("# TYPE greentic_pack_health_checked_timestamp_seconds gauge"),
+            "{metrics}"
+        );
+    }
+
+    #[test]
+    fn lower_layers_are_read_only_and_shadowed() {
+        let dir = tempdir().unwrap();
+        let base = PackStore::open(dir.path().join("base"))
+            .unwrap()
+            .with_policy(SigningPolicy::DevOk);
+        let shared = pack(dir.path(), "demo.shared", "1.0.0");
+        base.install_bytes(&shared).unwrap();
+        base.install_bytes(&pack(dir.path(), "demo.base", "1.0.0"))
+            .unwrap();
+
+        let node = PackStore::open(dir.path().join("node"))
+            .unwrap()
+            .with_lower_layer(dir.path().join("base"))
+            .unwrap()
+            .with_policy(SigningPolicy::DevOk);
+        // Already in the base layer: nothing is copied.
+        let InstallOutcome::Installed(existing) = node.install_bytes(&shared).unwrap() else {
+            panic!("the base copy counts as installed");
+        };
+        assert_eq!(existing.layer, StoreLayer::Lower(0));
+        node.install_bytes(&pack(dir.path(), "demo.node", "0.1.0"))
+            .unwrap();
+
+        let listed: Vec<(String, StoreLayer)> = node
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|pack| (pack.pack_id, pack.layer))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                ("demo.base".to_string(), StoreLayer::Lower(0)),
+                ("demo.node".to_string(), StoreLayer::Upper),
+                ("demo.shared".to_string(), StoreLayer::Lower(0)),
+            ]
+        );
+        assert!(node.conflicts().unwrap().is_empty());
+
+        // The same version placed in both layers: the upper copy shadows the
+        // base one and the overlap is reported.
+        let shared_upper = dir.path().join("node/packs/demo.shared");
+        fs::create_dir_all(&shared_upper).unwrap();
+        for name in ["1.0.0.gtpack", "1.0.0.record.json"] {
+            fs::copy(
+                dir.path().join("base/packs/demo.shared").join(name),
+                shared_upper.join(name),
+            )
+            .unwrap();
+        }
+        let version = Version::new(1, 0, 0);
+        let shadowed = node.get("demo.shared", &version).unwrap().unwrap();
+        assert_eq!(shadowed.layer, StoreLayer::Upper);
+        let conflicts = node.conflicts().unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].identical());
+        assert_eq!(
+            conflicts[0].to_string(),
+            "demo.shared@1.0.0 is in layers upper, lower 0 with identical contents; the upper layer's copy is used"
+        );
+
+        assert!(
+            PackStore::open(dir.path().join("other"))
+                .unwrap()
+                .with_lower_layer(dir.path().join("missing"))
+                .is_err()
+        );
+    }
+
+    #[derive(Default)]
+    struct Events(Mutex<Vec<InstallEvent>>);
+
+    impl InstallListener for Events {
+        fn installed(&self, event: &InstallEvent) {
+            self.0.lock().unwrap().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn install_many_is_all_or_nothing() {
+        let dir = tempdir().unwrap();
+        let events = Arc::new(Events::default());
+        let store = PackStore::open(dir.path().join("store"))
+            .unwrap()
+            .with_policy(SigningPolicy::DevOk)
+            .with_install_listener(events.clone());
+        let write = |name: &str, bytes: Vec<u8>| {
+            let path = dir.path().join(name);
+            fs::write(&path, bytes).unwrap();
+            path
+        };
+        let base = write("base", pack(dir.path(), "demo.base", "1.2.0"));
+        store.install(&base).unwrap();
+        events.0.lock().unwrap().clear();
+
+        let lib = write("lib", pack(dir.path(), "demo.lib", "2.0.0"));
+        let app = write(
+            "app",
+            pack_importing(
+                dir.path(),
+                "demo.app",
+                "0.1.0",
+                &[("demo.lib", "^2"), ("demo.base", "^1")],
+            ),
+        );
+        let broken = write(
+            "broken",
+            pack_importing(dir.path(), "demo.broken", "0.1.0", &[("demo.lib", "^3")]),
+        );
+
+        // An unmet import fails the set before anything is written.
+        let err = store
+            .install_many(&[&lib, &broken], SigningPolicy::DevOk)
+            .unwrap_err();
+        assert!(err.to_string().contains("demo.lib ^3"), "{err}");
+        assert_eq!(store.list().unwrap().len(), 1);
+        // So does a pack that fails verification.
+        let err = store
+            .install_many(&[&lib, &app], SigningPolicy::Strict)
+            .unwrap_err();
+        assert!(err.to_string().contains("failed verification"), "{err}");
+        assert_eq!(store.list().unwrap().len(), 1);
+        assert!(events.0.lock().unwrap().is_empty());
+
+        let installed = store
+            .install_many(&[&app, &lib, &base], SigningPolicy::DevOk)
+            .unwrap();
+        let ids: Vec<&str> = installed.iter().map(|pack| pack.pack_id.as_str()).collect();
+        assert_eq!(ids, ["demo.app", "demo.lib", "demo.base"]);
+        assert!(
+            store
+                .install_record("demo.app", &Version::new(0, 1, 0))
+                .is_ok()
+        );
+        let events = events.0.lock().unwrap();
+        assert_eq!(events.len(), 1, "one event for the whole set");
+        let announced: Vec<&str> = events[0]
+            .packs
+            .iter()
+            .map(|pack| pack.pack_id.as_str())
+            .collect();
+        assert_eq!(announced, ["demo.app", "demo.lib"]);
+        assert!(
+            fs::read_dir(store.root()).unwrap().all(|entry| !entry
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with(".install-")),
+            "staging is cleaned up"
+        );
+    }
+
+    #[test]
+    fn exported_state_restores_on_another_node() {
+        let dir = tempdir().unwrap();
+        let store = PackStore::open(dir.path().join("store"))
+            .unwrap()
+            .with_policy(SigningPolicy::DevOk);
+        store
+            .install_bytes(&pack(dir.path(), "demo.app", "0.1.0"))
+            .unwrap();
+        PackStore::open(dir.path().join("store"))
+            .unwrap()
+            .with_policy(SigningPolicy::DevOk)
+            .with_approval_backend(Arc::new(Desk::default()))
+            .install_bytes(&pack(dir.path(), "demo.review", "1.0.0"))
+            .unwrap();
+
+        let state = dir.path().join("state.tar.zst");
+        let manifest = store.export_state(&state).unwrap();
+        assert_eq!(manifest.files.len(), 5);
+
+        let node = PackStore::open(dir.path().join("node"))
+            .unwrap()
+            .with_policy(SigningPolicy::DevOk);
+        let restored: Vec<(String, PackState)> = node
+            .import_state(&state, true)
+            .unwrap()
+            .into_iter()
+            .map(|pack| (pack.pack_id, pack.state))
+            .collect();
+        assert_eq!(
+            restored,
+            [
+                ("demo.app".to_string(), PackState::Installed),
+                ("demo.review".to_string(), PackState::PendingApproval),
+            ]
+        );
+        assert_eq!(node.history().unwrap(), store.history().unwrap());
+        assert_eq!(node.history().unwrap().len(), 1);
+        assert!(
+            node.import_state(&state, false).is_err(),
+            "node is not empty"
+        );
+
+        // Re-verifying under a stricter policy rejects the dev-signed packs.
+        let strict = PackStore::open(dir.path().join("strict")).unwrap();
+        let err = strict.import_state(&state, true).unwrap_err();
+        assert!(format!("{err:#}").contains("no longer verifies"), "{err:#}");
+        assert!(strict.list().unwrap().is_empty());
+
+        // Files that do not match state.json are refused.
+        let mut tar = tar::Builder::new(Vec::new());
+        tar_append(
+            &mut tar,
+            STATE_MANIFEST,
+            &serde_json::to_vec(&manifest).unwrap(),
+        )
+        .unwrap();
+        for file in &manifest.files {
+            tar_append(&mut tar, &file.path, b"tampered").unwrap();
+        }
+        let tar = tar.into_inner().unwrap();
+        let tampered = dir.path().join("tampered.tar.zst");
+        fs::write(
+            &tampered,
+            ruzstd::encoding::compress_to_vec(
+                tar.as_slice(),
+                ruzstd::encoding::CompressionLevel::Fastest,
+            ),
+        )
+        .unwrap();
+        let fresh = PackStore::open(dir.path().join("fresh")).unwrap();
+        let err = fresh.import_state(&tampered, false).unwrap_err();
+        assert!(
+            err.to_string().contains("does not match the digest"),
+            "{err}"
+        );
+        assert!(fresh.list().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,365 @@
+//! Streaming a verified pack entry by entry, e.g. to sync it to edge nodes.
+//!
+//! [`PackLoad::export_stream`] yields each archive entry as an [`EntryHeader`]
+//! followed by a stream of byte chunks. Both levels are pull-based: the next
+//! chunk is produced only when the consumer polls for it, and the next entry
+//! only once the consumer asks for it, so a slow receiver holds back the
+//! sender instead of letting chunks pile up. Every chunk stream checks the
+//! bytes it carries against the size and BLAKE3 digest in its header and
+//! fails at the end of the entry on a mismatch.
+//!
+//! [`PackBuilder::import_stream`] (feature `builder`) consumes such a stream
+//! at the destination: it re-verifies every entry, applies the same path and
+//! size limits as reading an archive, verifies the reassembled pack like
+//! [`open_pack`](crate::reader::open_pack) does, and only then writes it.
+
+#[cfg(feature = "builder")]
+use std::collections::HashMap;
+#[cfg(feature = "builder")]
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context as TaskContext, Poll, ready};
+
+use anyhow::{Result, anyhow, bail};
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::atomic::Durability;
+use crate::builder::{MANIFEST_PATH, MANIFEST_ZSTD_PATH, SIGNATURE_CHAIN_PATH, SIGNATURE_PATH};
+#[cfg(feature = "builder")]
+use crate::builder::{PackBuilder, PendingFile, write_zip};
+#[cfg(feature = "builder")]
+use crate::reader::{ArchiveContents, StoredEntry, admit_entry, load_pack, normalize_entry_path};
+use crate::reader::{PackLoad, SigningPolicy};
+
+/// Chunk size used when none is configured.
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Entries sent first, so a receiver learns what the pack is before its bulk.
+const LEADING_ENTRIES: [&str; 5] = [
+    MANIFEST_ZSTD_PATH,
+    MANIFEST_PATH,
+    "sbom.json",
+    SIGNATURE_PATH,
+    SIGNATURE_CHAIN_PATH,
+];
+
+/// Metadata sent ahead of an entry's bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryHeader {
+    /// Logical path of the entry in the archive.
+    pub path: String,
+    /// Size in bytes.
+    pub size: u64,
+    pub hash_blake3: String,
+}
+
+/// The bytes of one entry, in chunks.
+pub type EntryStream<'a> = BoxStream<'a, Result<Vec<u8>>>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ExportOptions {
+    /// Largest chunk an entry stream yields; `0` uses [`DEFAULT_CHUNK_SIZE`].
+    pub chunk_size: usize,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImportOptions {
+    /// Signature policy the reassembled pack is verified under.
+    pub policy: SigningPolicy,
+    pub durability: Durability,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            policy: SigningPolicy::DevOk,
+            durability: Durability::Buffered,
+        }
+    }
+}
+
+impl PackLoad {
+    /// Every entry of the pack as a header and a verified chunk stream: the
+    /// manifest, SBOM, and signature first, then the rest by path.
+    pub fn export_stream(
+        &self,
+        opts: ExportOptions,
+    ) -> impl Stream<Item = (EntryHeader, EntryStream<'_>)> + Send + '_ {
+        let chunk_size = if opts.chunk_size == 0 {
+            DEFAULT_CHUNK_SIZE
+        } else {
+            opts.chunk_size
+        };
+        let mut entries = self.entries();
+        entries.sort_by_key(|entry| {
+            let rank = LEADING_ENTRIES
+                .iter()
+                .position(|leading| *leading == entry.path)
+                .unwrap_or(LEADING_ENTRIES.len());
+            (rank, entry.path.clone())
+        });
+
+        stream::iter(entries).map(move |entry| {
+            let header = EntryHeader {
+                path: entry.path,
+                size: entry.size,
+                hash_blake3: entry.hash_blake3,
+            };
+            let bytes = self.files[&header.path].as_slice();
+            let chunks = stream::iter(bytes.chunks(chunk_size).map(|chunk| Ok(chunk.to_vec())));
+            let verified = verify_entry(header.clone(), chunks.boxed());
+            (header, verified)
+        })
+    }
+}
+
+#[cfg(feature = "builder")]
+impl PackBuilder {
+    /// Reassembles a pack sent with [`PackLoad::export_stream`] into a
+    /// `.gtpack` at `out_path`.
+    ///
+    /// Each entry is read to its end before the next is requested. Nothing is
+    /// written unless every entry matches its header and the pack verifies
+    /// under `opts.policy`.
+    pub async fn import_stream<'a, S>(
+        entries: S,
+        out_path: impl AsRef<Path>,
+        opts: ImportOptions,
+    ) -> Result<PackLoad>
+    where
+        S: Stream<Item = (EntryHeader, EntryStream<'a>)>,
+    {
+        let mut entries = std::pin::pin!(entries);
+        let mut files = HashMap::new();
+        let mut stored = HashMap::new();
+        let mut order = Vec::new();
+        let mut total = 0u64;
+
+        while let Some((header, chunks)) = entries.next().await {
+            let logical = normalize_entry_path(&PathBuf::from(&header.path))?;
+            if logical != header.path {
+                bail!("entry path {} is not normalized", header.path);
+            }
+            admit_entry(&files, &logical, header.size, &mut total)?;
+
+            let mut bytes = Vec::with_capacity(header.size as usize);
+            let mut chunks = verify_entry(header, chunks);
+            while let Some(chunk) = chunks.next().await {
+                bytes.extend_from_slice(&chunk?);
+            }
+            stored.insert(logical.clone(), StoredEntry::stored(bytes.len() as u64));
+            order.push(logical.clone());
+            files.insert(logical, bytes);
+        }
+
+        let load = load_pack(ArchiveContents { files, stored }, opts.policy.into())?;
+        let pending: Vec<PendingFile> = order
+            .into_iter()
+            .map(|path| {
+                let bytes = load.files[&path].clone();
+                PendingFile::new(path, "application/octet-stream", bytes)
+            })
+            .collect();
+        write_zip(out_path.as_ref(), &pending, opts.durability)?;
+        Ok(load)
+    }
+}
+
+/// Passes `chunks` through, failing once the entry ends if its bytes do not
+/// match `header`, or as soon as they exceed its size.
+pub fn verify_entry<'a>(header: EntryHeader, chunks: EntryStream<'a>) -> EntryStream<'a> {
+    VerifiedEntry {
+        inner: chunks,
+        header,
+        hasher: blake3::Hasher::new(),
+        seen: 0,
+        done: false,
+    }
+    .boxed()
+}
+
+struct VerifiedEntry<'a> {
+    inner: EntryStream<'a>,
+    header: EntryHeader,
+    hasher: blake3::Hasher,
+    seen: u64,
+    done: bool,
+}
+
+impl VerifiedEntry<'_> {
+    fn finish(&self) -> Result<()> {
+        if self.seen != self.header.size {
+            bail!(
+                "entry {} ended after {} of {} bytes",
+                self.header.path,
+                self.seen,
+                self.header.size
+            );
+        }
+        let actual = self.hasher.finalize().to_hex();
+        if !actual.eq_ignore_ascii_case(&self.header.hash_blake3) {
+            bail!(
+                "hash mismatch for {}: expected {}, found {}",
+                self.header.path,
+                self.header.hash_blake3,
+                actual
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Stream for VerifiedEntry<'_> {
+    type Item = Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+        match ready!(this.inner.poll_next_unpin(cx)) {
+            Some(Ok(chunk)) => {
+                this.seen += chunk.len() as u64;
+                if this.seen > this.header.size {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(anyhow!(
+                        "entry {} is larger than its declared {} bytes",
+                        this.header.path,
+                        this.header.size
+                    ))));
+                }
+                this.hasher.update(&chunk);
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Some(Err(err)) => {
+                this.done = true;
+                Poll::Ready(Some(Err(err)))
+            }
+            None => {
+                this.done = true;
+                Poll::Ready(this.finish().err().map(Err))
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "builder"))]
+mod tests {
+    use super::*;
+    use crate::builder::{FlowBundle, PackMeta, Signing};
+    use crate::reader::open_pack;
+    use futures::executor::block_on;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn sample_pack(dir: &Path) -> PathBuf {
+        let meta: PackMeta = serde_json::from_value(json!({
+            "pack_id": "ai.greentic.demo.stream",
+            "version": "0.1.0",
+            "name": "Stream Demo",
+            "entry_flows": ["main"],
+            "created_at_utc": "2025-01-01T00:00:00Z",
+        }))
+        .unwrap();
+        let flow = FlowBundle {
+            id: "main".into(),
+            kind: "flow/v1".into(),
+            entry: "start".into(),
+            yaml: "id: main\nentry: start\nnodes: {}\n".into(),
+            json: json!({"id": "main", "entry": "start", "nodes": {}}),
+            hash_blake3: crate::builder::hex_hash(b"main"),
+            nodes: Vec::new(),
+        };
+        let path = dir.join("source.gtpack");
+        PackBuilder::new(meta)
+            .with_flow(flow)
+            .with_asset_bytes("big.bin", vec![7u8; 10_000])
+            .with_signing(Signing::Dev)
+            .build(&path)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn export_and_import_round_trip_in_small_chunks() {
+        let dir = tempdir().unwrap();
+        let source = open_pack(&sample_pack(dir.path()), SigningPolicy::DevOk).unwrap();
+
+        let exported = source.export_stream(ExportOptions { chunk_size: 1024 });
+        let headers: Vec<String> = block_on(
+            source
+                .export_stream(ExportOptions::default())
+                .map(|(header, _)| header.path)
+                .collect(),
+        );
+        assert_eq!(headers[0], "manifest.cbor");
+        assert_eq!(headers[1], "sbom.json");
+
+        let out = dir.path().join("copy.gtpack");
+        let imported = block_on(PackBuilder::import_stream(
+            exported,
+            &out,
+            ImportOptions::default(),
+        ))
+        .unwrap();
+        assert_eq!(imported.content_digest(), source.content_digest());
+        let reopened = open_pack(&out, SigningPolicy::DevOk).unwrap();
+        let digests = |load: &PackLoad| -> Vec<(String, String)> {
+            load.entries()
+                .into_iter()
+                .map(|entry| (entry.path, entry.hash_blake3))
+                .collect()
+        };
+        assert_eq!(digests(&reopened), digests(&source));
+    }
+
+    #[test]
+    fn import_rejects_tampered_chunks_and_writes_nothing() {
+        let dir = tempdir().unwrap();
+        let source = open_pack(&sample_pack(dir.path()), SigningPolicy::DevOk).unwrap();
+
+        let tampered = source
+            .export_stream(ExportOptions::default())
+            .map(|(header, chunks)| {
+                if header.path != "assets/big.bin" {
+                    return (header, chunks);
+                }
+                let forged: EntryStream<'_> =
+                    stream::iter([Ok(vec![8u8; header.size as usize])]).boxed();
+                (header, forged)
+            });
+        let out = dir.path().join("copy.gtpack");
+        let err = block_on(PackBuilder::import_stream(
+            tampered,
+            &out,
+            ImportOptions::default(),
+        ))
+        .unwrap_err();
+        assert!(err.to_string().contains("hash mismatch"), "{err}");
+        assert!(!out.exists());
+
+        let chunks: EntryStream<'_> = stream::iter([Ok(b"xy".to_vec())]).boxed();
+        let header = EntryHeader {
+            path: "assets/big.bin".into(),
+            size: 1,
+            hash_blake3: crate::builder::hex_hash(b"x"),
+        };
+        let oversized = stream::iter([(header, chunks)]);
+        let err = block_on(PackBuilder::import_stream(
+            oversized,
+            &out,
+            ImportOptions::default(),
+        ))
+        .unwrap_err();
+        assert!(err.to_string().contains("larger than"), "{err}");
+    }
+}
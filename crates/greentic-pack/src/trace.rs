@@ -0,0 +1,232 @@
+//! Execution traces of flow runs.
+//!
+//! A trace is a [`Cassette`] grouped by node: a runner feeds a
+//! [`TraceRecorder`] the node each progress event names, then the import
+//! calls and output chunks the node produced, and finally the result. The
+//! calls are what the node was given and what it got back, so a debugger can
+//! step through the run and rebuild the state each node saw. Values are
+//! redacted as in cassettes.
+//!
+//! [`Trace::cassette_through`] cuts a trace down to the calls up to a step,
+//! which replays the run that far.
+
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+use crate::atomic::{self, Durability};
+use crate::cassette::{CASSETTE_FORMAT, Cassette, Interaction, Outcome, RedactionRules};
+
+pub const TRACE_FORMAT: &str = "greentic-trace-v1";
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Trace {
+    pub format: String,
+    pub pack_id: String,
+    pub flow: String,
+    pub recorded_at_utc: String,
+    /// Input the run started from.
+    pub input: JsonValue,
+    pub steps: Vec<TraceStep>,
+    /// The run's result, as the runner reports it; `None` when the run
+    /// stopped without one.
+    #[serde(default)]
+    pub result: Option<JsonValue>,
+    /// Why the run stopped without a result.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// What happened while one node ran.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct TraceStep {
+    /// The node, as its progress event names it; `None` for what happened
+    /// before the first one.
+    pub node: Option<String>,
+    #[serde(default)]
+    pub calls: Vec<Interaction>,
+    /// Output streamed while the node ran.
+    #[serde(default)]
+    pub chunks: Vec<JsonValue>,
+}
+
+impl TraceStep {
+    /// The node's output: the answer to its last successful call.
+    pub fn output(&self) -> Option<&JsonValue> {
+        self.calls
+            .iter()
+            .rev()
+            .find_map(|call| match &call.response {
+                Outcome::Ok(value) => Some(value),
+                Outcome::Error(_) => None,
+            })
+    }
+}
+
+impl Trace {
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes =
+            std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let trace: Trace = serde_json::from_slice(&bytes)
+            .with_context(|| format!("{} is not an execution trace", path.display()))?;
+        if trace.format != TRACE_FORMAT {
+            bail!(
+                "{} has unsupported trace format `{}`; expected `{TRACE_FORMAT}`",
+                path.display(),
+                trace.format
+            );
+        }
+        Ok(trace)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self).context("failed to serialise trace")?;
+        atomic::write(path, &json, Durability::default())
+    }
+
+    /// A cassette of the run's input and the calls of steps `0..=step`.
+    pub fn cassette_through(&self, step: usize) -> Cassette {
+        Cassette {
+            format: CASSETTE_FORMAT.to_string(),
+            pack_id: self.pack_id.clone(),
+            flow: self.flow.clone(),
+            recorded_at_utc: self.recorded_at_utc.clone(),
+            input: self.input.clone(),
+            interactions: self
+                .steps
+                .iter()
+                .take(step + 1)
+                .flat_map(|step| step.calls.iter().cloned())
+                .collect(),
+        }
+    }
+}
+
+/// Collects the trace of one run.
+pub struct TraceRecorder {
+    trace: Trace,
+    rules: RedactionRules,
+}
+
+impl TraceRecorder {
+    pub fn new(pack_id: &str, flow: &str, input: &JsonValue, rules: RedactionRules) -> Self {
+        let trace = Trace {
+            format: TRACE_FORMAT.to_string(),
+            pack_id: pack_id.to_string(),
+            flow: flow.to_string(),
+            recorded_at_utc: OffsetDateTime::now_utc()
+                .format(&Rfc3339)
+                .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string()),
+            input: rules.redacted(input),
+            steps: Vec::new(),
+            result: None,
+            error: None,
+        };
+        Self { trace, rules }
+    }
+
+    /// Starts the step of `node`.
+    pub fn enter(&mut self, node: &str) {
+        self.trace.steps.push(TraceStep {
+            node: Some(node.to_string()),
+            ..TraceStep::default()
+        });
+    }
+
+    pub fn record(&mut self, import: &str, request: &JsonValue, response: &Outcome) {
+        let response = match response {
+            Outcome::Ok(value) => Outcome::Ok(self.rules.redacted(value)),
+            Outcome::Error(message) => Outcome::Error(message.clone()),
+        };
+        let interaction = Interaction {
+            import: import.to_string(),
+            request: self.rules.redacted(request),
+            response,
+        };
+        self.current().calls.push(interaction);
+    }
+
+    pub fn chunk(&mut self, output: &JsonValue) {
+        let output = self.rules.redacted(output);
+        self.current().chunks.push(output);
+    }
+
+    /// The trace of a run that ended with `result`, or stopped with the
+    /// error message.
+    pub fn finish(mut self, result: Result<JsonValue, String>) -> Trace {
+        match result {
+            Ok(result) => self.trace.result = Some(self.rules.redacted(&result)),
+            Err(error) => self.trace.error = Some(error),
+        }
+        self.trace
+    }
+
+    fn current(&mut self) -> &mut TraceStep {
+        if self.trace.steps.is_empty() {
+            self.trace.steps.push(TraceStep::default());
+        }
+        self.trace.steps.last_mut().expect("a step was just added")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn groups_calls_by_node_and_cuts_cassettes_at_a_step() {
+        let mut recorder = TraceRecorder::new(
+            "demo.weather",
+            "forecast",
+            &json!({ "city": "Oslo", "api_key": "k" }),
+            RedactionRules::standard(),
+        );
+        recorder.record("clock.now", &json!({}), &Outcome::Ok(json!(0)));
+        recorder.enter("fetch");
+        recorder.record(
+            "http.fetch",
+            &json!({ "url": "https://weather.example/oslo" }),
+            &Outcome::Ok(json!({ "temp": 4 })),
+        );
+        recorder.chunk(&json!({ "text": "4°" }));
+        recorder.enter("reply");
+        recorder.record(
+            "email.send",
+            &json!({ "to": "ops" }),
+            &Outcome::Error("quota".into()),
+        );
+        let trace = recorder.finish(Err("flow `forecast` trapped".into()));
+
+        assert_eq!(trace.input["api_key"], crate::cassette::REDACTED);
+        let nodes: Vec<_> = trace
+            .steps
+            .iter()
+            .map(|step| step.node.as_deref())
+            .collect();
+        assert_eq!(nodes, [None, Some("fetch"), Some("reply")]);
+        assert_eq!(trace.steps[1].output(), Some(&json!({ "temp": 4 })));
+        assert_eq!(trace.steps[1].chunks, [json!({ "text": "4°" })]);
+        assert_eq!(trace.steps[2].output(), None);
+        assert_eq!(trace.error.as_deref(), Some("flow `forecast` trapped"));
+
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("forecast.trace.json");
+        trace.save(&path).unwrap();
+        let trace = Trace::load(&path).unwrap();
+        let cassette = trace.cassette_through(1);
+        let imports: Vec<_> = cassette
+            .interactions
+            .iter()
+            .map(|call| call.import.as_str())
+            .collect();
+        assert_eq!(imports, ["clock.now", "http.fetch"]);
+        assert_eq!(cassette.format, CASSETTE_FORMAT);
+        assert!(Cassette::load(&path).is_err());
+    }
+}
@@ -4,7 +4,7 @@ use std::path::Path;
 use std::process::Command;
 
 use assert_cmd::prelude::*;
-use greentic_pack::builder::{ComponentArtifact, FlowBundle, PackBuilder, PackMeta};
+use greentic_pack::builder::{ComponentArtifact, FlowBundle, PackBuilder, PackMeta, WasmSource};
 use greentic_pack::builder::{ComponentPin, ImportRef, NodeRef};
 use greentic_pack::events::{
     EventProviderCapabilities, EventProviderKind, EventProviderSpec, EventsSection, OrderingKind,
@@ -65,30 +65,52 @@ fn build_sample_pack(out_path: &Path) {
         homepage: None,
         support: None,
         vendor: None,
+        tags: Vec::new(),
+        categories: Vec::new(),
         imports: vec![ImportRef {
             pack_id: "dependency.pack".into(),
             version_req: "*".into(),
+            optional: false,
         }],
         entry_flows: vec!["flow.main".into()],
         created_at_utc: "2025-01-01T00:00:00Z".into(),
         events: Some(EventsSection {
-            providers: vec![EventProviderSpec {
-                name: "nats-core".into(),
-                kind: EventProviderKind::Broker,
-                component: "nats-provider@1.0.0".into(),
-                default_flow: Some("flows/events/nats/default.ygtc".into()),
-                custom_flow: None,
-                capabilities: EventProviderCapabilities {
-                    transport: Some(TransportKind::Nats),
-                    reliability: Some(ReliabilityKind::AtLeastOnce),
-                    ordering: Some(OrderingKind::PerKey),
-                    topics: vec!["greentic.*".into()],
-                },
-            }],
+            providers: vec![
+                EventProviderSpec::new(
+                    "nats-core",
+                    EventProviderKind::Broker,
+                    "nats-provider@1.0.0",
+                )
+                .with_default_flow("flows/events/nats/default.ygtc")
+                .with_capabilities(
+                    EventProviderCapabilities::default()
+                        .with_transport(TransportKind::Nats)
+                        .with_reliability(ReliabilityKind::AtLeastOnce)
+                        .with_ordering(OrderingKind::PerKey)
+                        .with_topics(["greentic.*"]),
+                ),
+            ],
         }),
         repo: None,
         messaging: None,
         interfaces: Vec::new(),
+        media: None,
+        config: None,
+        config_schema: None,
+        config_defaults: None,
+        lifecycle: None,
+        quotas: None,
+        limits: None,
+        dispatch: None,
+        qos: None,
+        secrets: None,
+        capabilities: None,
+        metrics: None,
+        schedule: None,
+        classification: None,
+        locales: None,
+        data_handling: None,
+        permissions: None,
         annotations: serde_json::Map::new(),
     };
     meta.annotations.insert(
@@ -139,7 +161,7 @@ fn build_sample_pack(out_path: &Path) {
     let component = ComponentArtifact {
         name: "component.a".into(),
         version: Version::parse("1.0.0").unwrap(),
-        wasm_path: wasm_path.clone(),
+        wasm: WasmSource::Path(wasm_path.clone()),
         schema_json: None,
         manifest_json: Some(manifest_json),
         capabilities: Some(serde_json::to_value(component_manifest.capabilities).unwrap()),
@@ -37,6 +37,23 @@ pub static FLOWS: &[(&'static str, &'static str)] = &[(
     "version: 1\ngraph:\n  start:\n    type: prompt\n    prompt: \"Ask the user for a city to lookup\"\n  fetch_weather:\n    type: task\n    service: weather-api\n    input:\n      city: \"{{ user.city }}\"\n  respond:\n    type: message\n    template: \"The weather in {{ weather.city }} is {{ weather.summary }}.\"\nedges:\n  - from: start\n    to: fetch_weather\n  - from: fetch_weather\n    to: respond\n",
 )];
 
+pub static PACK_TAGS: &[&'static str] = &[];
+
+pub static PACK_CATEGORIES: &[&'static str] = &[];
+
+pub static FLOW_INDEX: &[crate::IndexedFlow] = &[crate::IndexedFlow {
+    id: "weather_bot",
+    title: Some("Weather Bot"),
+    description: Some("Ask for a location, call the MCP weather API, and reply with a forecast."),
+    trigger: "messaging",
+    imports: &[],
+    node_docs: &[],
+    input_schema: "{\"$schema\":\"https://json-schema.org/draft/2020-12/schema\"}",
+    output_schema: "{\"$schema\":\"https://json-schema.org/draft/2020-12/schema\"}",
+    qos: "standard",
+    deadline_ms: None,
+}];
+
 pub static TEMPLATES: &[(&'static str, &'static [u8])] = &[(
     "templates/greeting.txt",
     &[
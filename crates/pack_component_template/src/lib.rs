@@ -1,3 +1,4 @@
 pub const CARGO_TOML: &str = include_str!("templates/template_Cargo.toml");
 pub const LIB_RS: &str = include_str!("templates/template_lib.rs");
 pub const DATA_RS_PLACEHOLDER: &str = include_str!("templates/template_data.rs");
+pub const PACK_EXPORT_WIT: &str = include_str!("templates/template_pack_export.wit");
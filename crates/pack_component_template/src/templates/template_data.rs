@@ -4,4 +4,10 @@ pub static MANIFEST_CBOR: &[u8] = &[];
 
 pub static FLOWS: &[(&str, &str)] = &[];
 
+pub static PACK_TAGS: &[&str] = &[];
+
+pub static PACK_CATEGORIES: &[&str] = &[];
+
+pub static FLOW_INDEX: &[crate::IndexedFlow] = &[];
+
 pub static TEMPLATES: &[(&str, &[u8])] = &[];
@@ -3,10 +3,26 @@
 #[cfg(target_arch = "wasm32")]
 extern crate alloc;
 
+#[cfg(all(feature = "std", feature = "no-wasi"))]
+compile_error!("`no-wasi` replaces `std`; build with `--no-default-features --features no-wasi`");
+
+// Without std nothing provides an allocator or panic handler; both must stay
+// free of WASI imports.
+#[cfg(all(target_arch = "wasm32", feature = "no-wasi"))]
+#[global_allocator]
+static ALLOCATOR: dlmalloc::GlobalDlmalloc = dlmalloc::GlobalDlmalloc;
+
+#[cfg(all(target_arch = "wasm32", feature = "no-wasi"))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo<'_>) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
 mod data;
 
+use core::sync::atomic::{AtomicBool, Ordering};
 #[cfg(target_arch = "wasm32")]
-use alloc::{format, string::String, string::ToString, vec::Vec};
+use alloc::{boxed::Box, format, string::String, string::ToString, vec::Vec};
 #[cfg(not(target_arch = "wasm32"))]
 use greentic_interfaces_host::bindings::exports::greentic::interfaces_pack::component_api::ProviderMeta;
 #[cfg(not(target_arch = "wasm32"))]
@@ -21,6 +37,9 @@ pub struct FlowInfo {
     pub id: String,
     pub human_name: Option<String>,
     pub description: Option<String>,
+    /// QoS class: `interactive`, `standard`, or `batch`.
+    pub qos: String,
+    pub deadline_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -42,20 +61,214 @@ pub struct RunResult {
     pub error: Option<String>,
 }
 
+/// Who a flow runs for and how to trace it, as the host passes it to
+/// [`PackExport::run_flow`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunContext {
+    pub tenant: String,
+    /// Environment of the tenant, e.g. `prod` or `local`.
+    pub env: String,
+    /// BCP 47 tag of the caller's preferred locale.
+    pub locale: Option<String>,
+    /// Id tying the run to the request that caused it, for logs and traces.
+    pub correlation_id: Option<String>,
+    /// Opaque handle of the secrets the host grants the run; the pack passes
+    /// it back to the host's secrets import.
+    pub secrets: Option<String>,
+}
+
+/// One step of a streamed run, see [`PackExport::run_flow_stream`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RunEvent {
+    /// A progress note, such as the node being run.
+    Progress { message: String },
+    /// A piece of the output, as the flow produces it.
+    Chunk { output: Value },
+    /// The result of the run; the last event unless the run checkpointed.
+    Done(RunResult),
+    /// The run stopped at a safe point because the component is draining;
+    /// the last event of such runs.
+    Checkpoint(Checkpoint),
+}
+
+/// The events of a streamed run, in order.
+pub type RunEvents = Box<dyn Iterator<Item = RunEvent>>;
+
+/// Where a run stopped while the component drained, see [`PackExport::drain`].
+/// Another instance continues it with [`PackExport::resume_flow_stream`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub flow_id: String,
+    /// The node the run continues with.
+    pub node: String,
+    /// What the flow runtime needs to continue, such as node outputs so far.
+    pub state: Value,
+}
+
+/// Error of runs refused because the component is draining.
+pub const DRAINING: &str = "draining";
+
 #[derive(Debug, Clone, Serialize)]
 pub struct A2AItem {
     pub title: String,
     pub flow_id: String,
 }
 
+/// One flow of the index packc embeds at build time.
+#[derive(Debug, Clone, Copy)]
+pub struct IndexedFlow {
+    pub id: &'static str,
+    pub title: Option<&'static str>,
+    pub description: Option<&'static str>,
+    /// The flow `type`, e.g. `messaging` or `events`.
+    pub trigger: &'static str,
+    /// Pack imports the flow uses.
+    pub imports: &'static [&'static str],
+    /// `doc:` strings as `(node_id, doc)`, sorted by node id.
+    pub node_docs: &'static [(&'static str, &'static str)],
+    /// JSON Schema of the flow input, as JSON text.
+    pub input_schema: &'static str,
+    /// JSON Schema of the flow output, as JSON text.
+    pub output_schema: &'static str,
+    /// QoS class: `interactive`, `standard`, or `batch`.
+    pub qos: &'static str,
+    /// Scheduling hint from `qos.<flow>.deadline_ms`.
+    pub deadline_ms: Option<u64>,
+}
+
+/// Documentation of one flow node, for editor tooltips.
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeDoc {
+    pub node: String,
+    pub doc: String,
+}
+
+/// Selects flows for `find_flows`; empty fields match every flow.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FlowFilter {
+    /// The pack carries every listed tag.
+    pub tags: Vec<String>,
+    /// The pack belongs to at least one listed category.
+    pub categories: Vec<String>,
+    /// The flow uses every listed import.
+    pub imports: Vec<String>,
+    pub trigger: Option<String>,
+    /// The flow has this QoS class.
+    pub qos: Option<String>,
+}
+
+impl FlowFilter {
+    pub fn matches(&self, flow: &IndexedFlow) -> bool {
+        let tags_ok = self.tags.iter().all(|tag| pack_tags().contains(&tag.as_str()));
+        let categories_ok = self.categories.is_empty()
+            || self
+                .categories
+                .iter()
+                .any(|category| pack_categories().contains(&category.as_str()));
+        let imports_ok = self
+            .imports
+            .iter()
+            .all(|import| flow.imports.contains(&import.as_str()));
+        let trigger_ok = self
+            .trigger
+            .as_deref()
+            .is_none_or(|trigger| trigger == flow.trigger);
+        let qos_ok = self.qos.as_deref().is_none_or(|qos| qos == flow.qos);
+        tags_ok && categories_ok && imports_ok && trigger_ok && qos_ok
+    }
+}
+
+impl From<&IndexedFlow> for FlowInfo {
+    fn from(flow: &IndexedFlow) -> Self {
+        FlowInfo {
+            id: flow.id.to_string(),
+            human_name: flow.title.map(ToString::to_string),
+            description: flow.description.map(ToString::to_string),
+            qos: flow.qos.to_string(),
+            deadline_ms: flow.deadline_ms,
+        }
+    }
+}
+
+/// Most flows a single `list_flows_page` call returns.
+pub const MAX_FLOWS_PAGE: usize = 256;
+
 pub trait PackExport {
-    fn list_flows(&self) -> Vec<FlowInfo>;
+    fn count_flows(&self) -> usize;
+    /// Up to `limit` flows starting at `offset`, capped at [`MAX_FLOWS_PAGE`].
+    fn list_flows_page(&self, offset: usize, limit: usize) -> Vec<FlowInfo>;
+    /// Every flow, gathered page by page.
+    fn list_flows(&self) -> Vec<FlowInfo> {
+        let total = self.count_flows();
+        let mut flows = Vec::with_capacity(total);
+        while flows.len() < total {
+            let page = self.list_flows_page(flows.len(), MAX_FLOWS_PAGE);
+            if page.is_empty() {
+                break;
+            }
+            flows.extend(page);
+        }
+        flows
+    }
+    fn find_flows(&self, filter: &FlowFilter) -> Vec<FlowInfo>;
     fn get_flow_schema(&self, flow_id: &str) -> Option<SchemaDoc>;
+    /// `doc:` strings of the flow's nodes; `None` for unknown flows.
+    fn node_docs(&self, _flow_id: &str) -> Option<Vec<NodeDoc>> {
+        None
+    }
+    /// JSON Schema of the pack's install-time settings (`config_schema:` in
+    /// `pack.yaml`); `None` when the pack declares none.
+    fn config_schema(&self) -> Option<Value> {
+        None
+    }
     fn prepare_flow(&self, flow_id: &str) -> PrepareResult;
-    fn run_flow(&self, flow_id: &str, input: serde_json::Value) -> RunResult;
+    fn run_flow(&self, flow_id: &str, input: serde_json::Value, ctx: &RunContext) -> RunResult;
+    /// Runs a flow, reporting progress and output while it runs; the events
+    /// end with [`RunEvent::Done`]. Flows that cannot stream report only the
+    /// result of `run_flow`.
+    fn run_flow_stream(&self, flow_id: &str, input: serde_json::Value, ctx: &RunContext) -> RunEvents {
+        Box::new(core::iter::once(RunEvent::Done(self.run_flow(flow_id, input, ctx))))
+    }
+    /// Asks the component to stop taking work so the host can replace it:
+    /// new runs fail with [`DRAINING`], and streamed runs end with
+    /// [`RunEvent::Checkpoint`] at their next [`safe_point`].
+    fn drain(&self) {}
+    fn is_draining(&self) -> bool {
+        false
+    }
+    /// Continues a run from the checkpoint of a drained instance. Components
+    /// that never checkpoint fail the run.
+    fn resume_flow_stream(&self, checkpoint: Checkpoint, _ctx: &RunContext) -> RunEvents {
+        Box::new(core::iter::once(RunEvent::Done(RunResult {
+            status: "error".into(),
+            output: None,
+            error: Some(format!("cannot resume flow: {}", checkpoint.flow_id)),
+        })))
+    }
     fn a2a_search(&self, query: &str) -> Vec<A2AItem>;
 }
 
+/// Safe point of a streamed run, for flow runtimes to call before each node.
+/// Once `component` is draining this returns the checkpoint the run should
+/// end with instead of running `node`; `state` is only captured then.
+pub fn safe_point(
+    component: &impl PackExport,
+    flow_id: &str,
+    node: &str,
+    state: impl FnOnce() -> Value,
+) -> Option<RunEvent> {
+    component.is_draining().then(|| {
+        RunEvent::Checkpoint(Checkpoint {
+            flow_id: flow_id.to_string(),
+            node: node.to_string(),
+            state: state(),
+        })
+    })
+}
+
 pub use data::{FLOWS, MANIFEST_CBOR, TEMPLATES};
 
 pub fn manifest_cbor() -> &'static [u8] {
@@ -79,6 +292,18 @@ pub fn flows() -> &'static [(&'static str, &'static str)] {
     data::FLOWS
 }
 
+pub fn flow_index() -> &'static [IndexedFlow] {
+    data::FLOW_INDEX
+}
+
+pub fn pack_tags() -> &'static [&'static str] {
+    data::PACK_TAGS
+}
+
+pub fn pack_categories() -> &'static [&'static str] {
+    data::PACK_CATEGORIES
+}
+
 pub fn templates() -> &'static [(&'static str, &'static [u8])] {
     data::TEMPLATES
 }
@@ -93,26 +318,61 @@ pub fn template_by_path(path: &str) -> Option<&'static [u8]> {
 #[derive(Debug, Default)]
 pub struct Component;
 
+/// Set once the host asks the component to drain; there is no way back.
+static DRAINING_COMPONENT: AtomicBool = AtomicBool::new(false);
+
 impl PackExport for Component {
-    fn list_flows(&self) -> Vec<FlowInfo> {
-        flows()
+    fn count_flows(&self) -> usize {
+        flow_index().len()
+    }
+
+    fn list_flows_page(&self, offset: usize, limit: usize) -> Vec<FlowInfo> {
+        flow_index()
             .iter()
-            .map(|(id, _)| FlowInfo {
-                id: (*id).to_string(),
-                human_name: None,
-                description: None,
-            })
+            .skip(offset)
+            .take(limit.min(MAX_FLOWS_PAGE))
+            .map(FlowInfo::from)
             .collect()
     }
 
-    fn get_flow_schema(&self, flow_id: &str) -> Option<SchemaDoc> {
-        flows()
+    fn find_flows(&self, filter: &FlowFilter) -> Vec<FlowInfo> {
+        flow_index()
             .iter()
-            .find(|(id, _)| *id == flow_id)
-            .map(|(id, _)| SchemaDoc {
-                flow_id: (*id).to_string(),
-                schema_json: serde_json::json!({}),
-            })
+            .filter(|flow| filter.matches(flow))
+            .map(FlowInfo::from)
+            .collect()
+    }
+
+    fn get_flow_schema(&self, flow_id: &str) -> Option<SchemaDoc> {
+        let flow = flow_index().iter().find(|flow| flow.id == flow_id)?;
+        let parse = |text: &str| serde_json::from_str::<Value>(text).unwrap_or_default();
+        Some(SchemaDoc {
+            flow_id: flow.id.to_string(),
+            schema_json: serde_json::json!({
+                "input": parse(flow.input_schema),
+                "output": parse(flow.output_schema),
+            }),
+        })
+    }
+
+    fn node_docs(&self, flow_id: &str) -> Option<Vec<NodeDoc>> {
+        let flow = flow_index().iter().find(|flow| flow.id == flow_id)?;
+        Some(
+            flow.node_docs
+                .iter()
+                .map(|(node, doc)| NodeDoc {
+                    node: (*node).to_string(),
+                    doc: (*doc).to_string(),
+                })
+                .collect(),
+        )
+    }
+
+    fn config_schema(&self) -> Option<Value> {
+        match manifest_value() {
+            Value::Object(mut manifest) => manifest.remove("config_schema"),
+            _ => None,
+        }
     }
 
     fn prepare_flow(&self, flow_id: &str) -> PrepareResult {
@@ -129,7 +389,7 @@ impl PackExport for Component {
         }
     }
 
-    fn run_flow(&self, flow_id: &str, _input: Value) -> RunResult {
+    fn run_flow(&self, flow_id: &str, _input: Value, _ctx: &RunContext) -> RunResult {
         if flows().iter().any(|(id, _)| *id == flow_id) {
             RunResult {
                 status: "error".into(),
@@ -145,6 +405,14 @@ impl PackExport for Component {
         }
     }
 
+    fn drain(&self) {
+        DRAINING_COMPONENT.store(true, Ordering::SeqCst);
+    }
+
+    fn is_draining(&self) -> bool {
+        DRAINING_COMPONENT.load(Ordering::SeqCst)
+    }
+
     fn a2a_search(&self, _query: &str) -> Vec<A2AItem> {
         Vec::new()
     }
@@ -154,70 +422,494 @@ pub fn component() -> Component {
     Component
 }
 
-#[no_mangle]
-pub extern "C" fn greentic_pack_export__list_flows(json_buffer: *mut u8, len: usize) -> usize {
-    let component = Component;
-    let flows = component.list_flows();
-    write_json_response(&flows, json_buffer, len)
-}
-
-/// # Safety
-///
-/// The caller must ensure that `flow_id_ptr` points to `flow_id_len` bytes of
-/// valid UTF-8 and that `json_buffer` points to a writable region of at least
-/// `len` bytes when non-null.
-#[no_mangle]
-pub unsafe extern "C" fn greentic_pack_export__prepare_flow(
-    flow_id_ptr: *const u8,
-    flow_id_len: usize,
-    json_buffer: *mut u8,
-    len: usize,
-) -> usize {
-    let component = Component;
-    let flow_id = slice_to_str(flow_id_ptr, flow_id_len);
-    let result = component.prepare_flow(flow_id);
-    write_json_response(&result, json_buffer, len)
-}
-
-/// # Safety
-///
-/// The caller must ensure that `flow_id_ptr` points to `flow_id_len` bytes of
-/// valid UTF-8 and that `json_buffer` points to a writable region of at least
-/// `len` bytes when non-null.
-#[no_mangle]
-pub unsafe extern "C" fn greentic_pack_export__run_flow(
-    flow_id_ptr: *const u8,
-    flow_id_len: usize,
-    json_buffer: *mut u8,
-    len: usize,
-) -> usize {
-    let component = Component;
-    let flow_id = slice_to_str(flow_id_ptr, flow_id_len);
-    let result = component.run_flow(flow_id, serde_json::Value::Null);
-    write_json_response(&result, json_buffer, len)
-}
-
-#[no_mangle]
-pub extern "C" fn greentic_pack_export__a2a_search(json_buffer: *mut u8, len: usize) -> usize {
-    let component = Component;
-    let items = component.a2a_search("");
-    write_json_response(&items, json_buffer, len)
-}
-
-fn write_json_response<T: serde::Serialize>(value: &T, buffer: *mut u8, len: usize) -> usize {
-    let json = serde_json::to_vec(value).expect("serialisation succeeds");
-    if buffer.is_null() || len == 0 {
-        return json.len();
-    }
-
-    let copy_len = core::cmp::min(json.len(), len);
-    unsafe {
-        core::ptr::copy_nonoverlapping(json.as_ptr(), buffer, copy_len);
-    }
-    copy_len
-}
-
-unsafe fn slice_to_str<'a>(ptr: *const u8, len: usize) -> &'a str {
-    let bytes = core::slice::from_raw_parts(ptr, len);
-    core::str::from_utf8(bytes).expect("flow id is valid utf-8")
+pub fn run_flow_json(
+    component: &impl PackExport,
+    flow_id: &str,
+    input: &[u8],
+    ctx: &RunContext,
+) -> RunResult {
+    if component.is_draining() {
+        return RunResult {
+            status: "error".into(),
+            output: None,
+            error: Some(DRAINING.into()),
+        };
+    }
+    let input = if input.is_empty() {
+        Ok(Value::Null)
+    } else {
+        serde_json::from_slice::<Value>(input)
+    };
+    match input {
+        Ok(input) => component.run_flow(flow_id, input, ctx),
+        Err(_) => RunResult {
+            status: "error".into(),
+            output: None,
+            error: Some(INVALID_INPUT.into()),
+        },
+    }
+}
+
+/// [`run_flow_json`] for [`PackExport::run_flow_stream`]: input that is not
+/// JSON ends the run with an error before it starts.
+pub fn run_flow_stream_json(
+    component: &impl PackExport,
+    flow_id: &str,
+    input: &[u8],
+    ctx: &RunContext,
+) -> RunEvents {
+    if component.is_draining() {
+        return refused(DRAINING);
+    }
+    if input.is_empty() {
+        return component.run_flow_stream(flow_id, Value::Null, ctx);
+    }
+    match serde_json::from_slice::<Value>(input) {
+        Ok(input) => component.run_flow_stream(flow_id, input, ctx),
+        Err(_) => refused(INVALID_INPUT),
+    }
+}
+
+/// Continues a run from `checkpoint`, the JSON of a [`Checkpoint`]. Draining
+/// components refuse, and so does anything but a checkpoint.
+pub fn resume_flow_stream_json(
+    component: &impl PackExport,
+    checkpoint: &[u8],
+    ctx: &RunContext,
+) -> RunEvents {
+    if component.is_draining() {
+        return refused(DRAINING);
+    }
+    match serde_json::from_slice::<Checkpoint>(checkpoint) {
+        Ok(checkpoint) => component.resume_flow_stream(checkpoint, ctx),
+        Err(_) => refused(INVALID_CHECKPOINT),
+    }
+}
+
+/// A streamed run that ends with `error` before it starts.
+fn refused(error: &str) -> RunEvents {
+    Box::new(core::iter::once(RunEvent::Done(RunResult {
+        status: "error".into(),
+        output: None,
+        error: Some(error.into()),
+    })))
+}
+
+const INVALID_INPUT: &str = "flow input is not valid JSON";
+const INVALID_CHECKPOINT: &str = "checkpoint is not a valid run checkpoint";
+
+#[cfg(feature = "legacy-abi")]
+mod legacy {
+    #[cfg(target_arch = "wasm32")]
+    use alloc::{boxed::Box, vec};
+
+    use super::*;
+
+    #[no_mangle]
+    pub extern "C" fn greentic_pack_export__list_flows(json_buffer: *mut u8, len: usize) -> usize {
+        let component = Component;
+        let flows = component.list_flows();
+        write_json_response(&flows, json_buffer, len)
+    }
+
+    #[no_mangle]
+    pub extern "C" fn greentic_pack_export__count_flows() -> usize {
+        Component.count_flows()
+    }
+
+    #[no_mangle]
+    pub extern "C" fn greentic_pack_export__list_flows_page(
+        offset: usize,
+        limit: usize,
+        json_buffer: *mut u8,
+        len: usize,
+    ) -> usize {
+        let component = Component;
+        let flows = component.list_flows_page(offset, limit);
+        write_json_response(&flows, json_buffer, len)
+    }
+
+    /// # Safety
+    ///
+    /// The caller must ensure that `filter_ptr` points to `filter_len` readable
+    /// bytes when non-null and that `json_buffer` points to a writable region of at least
+    /// `len` bytes when non-null.
+    ///
+    /// The bytes are a JSON [`FlowFilter`]; anything else matches no flows.
+    #[no_mangle]
+    pub unsafe extern "C" fn greentic_pack_export__find_flows(
+        filter_ptr: *const u8,
+        filter_len: usize,
+        json_buffer: *mut u8,
+        len: usize,
+    ) -> usize {
+        let component = Component;
+        let filter = input_bytes(filter_ptr, filter_len);
+        let flows = match serde_json::from_slice::<FlowFilter>(filter) {
+            Ok(filter) => component.find_flows(&filter),
+            Err(_) => Vec::new(),
+        };
+        write_json_response(&flows, json_buffer, len)
+    }
+
+    /// # Safety
+    ///
+    /// The caller must ensure that `flow_id_ptr` points to `flow_id_len` readable
+    /// bytes when non-null and that `json_buffer` points to a writable region of at
+    /// least `len` bytes when non-null. A flow id that is not UTF-8 is reported as
+    /// an error.
+    #[no_mangle]
+    pub unsafe extern "C" fn greentic_pack_export__prepare_flow(
+        flow_id_ptr: *const u8,
+        flow_id_len: usize,
+        json_buffer: *mut u8,
+        len: usize,
+    ) -> usize {
+        let component = Component;
+        let result = match slice_to_str(flow_id_ptr, flow_id_len) {
+            Some(flow_id) => component.prepare_flow(flow_id),
+            None => PrepareResult {
+                status: "error".into(),
+                error: Some(INVALID_FLOW_ID.into()),
+            },
+        };
+        write_json_response(&result, json_buffer, len)
+    }
+
+    /// # Safety
+    ///
+    /// The caller must ensure that `flow_id_ptr` points to `flow_id_len` readable
+    /// bytes when non-null and that `json_buffer` points to a writable region of at
+    /// least `len` bytes when non-null. A flow id that is not UTF-8 is reported as
+    /// an error.
+    #[no_mangle]
+    pub unsafe extern "C" fn greentic_pack_export__run_flow(
+        flow_id_ptr: *const u8,
+        flow_id_len: usize,
+        json_buffer: *mut u8,
+        len: usize,
+    ) -> usize {
+        let component = Component;
+        let result = match slice_to_str(flow_id_ptr, flow_id_len) {
+            Some(flow_id) => {
+                component.run_flow(flow_id, serde_json::Value::Null, &RunContext::default())
+            }
+            None => RunResult {
+                status: "error".into(),
+                output: None,
+                error: Some(INVALID_FLOW_ID.into()),
+            },
+        };
+        write_json_response(&result, json_buffer, len)
+    }
+
+    /// Allocates `len` bytes of component memory for host-supplied input, such
+    /// as the flow id and input of [`greentic_pack_export__run_flow_json`].
+    /// Release them with [`greentic_pack_export__free`].
+    #[no_mangle]
+    pub extern "C" fn greentic_pack_export__alloc(len: usize) -> *mut u8 {
+        Box::into_raw(zeroed_buffer(len)) as *mut u8
+    }
+
+    /// # Safety
+    ///
+    /// `ptr` and `len` must describe a buffer returned by
+    /// [`greentic_pack_export__alloc`] or [`greentic_pack_export__run_flow_json`]
+    /// that has not been freed yet.
+    #[no_mangle]
+    pub unsafe extern "C" fn greentic_pack_export__free(ptr: *mut u8, len: usize) {
+        if !ptr.is_null() {
+            drop(Box::from_raw(core::ptr::slice_from_raw_parts_mut(ptr, len)));
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The caller must ensure that `flow_id_ptr` and `input_ptr` point to
+    /// `flow_id_len` and `input_len` readable bytes when non-null, and that
+    /// `result_len` points to a writable `usize`.
+    ///
+    /// Runs a flow with a JSON input (empty input reads as `null`) and returns
+    /// its [`RunResult`] as JSON in a buffer the component allocates; its length
+    /// is written to `result_len`, and the host releases it with
+    /// [`greentic_pack_export__free`]. Unlike the other shims the flow runs once,
+    /// with no call to size the buffer first.
+    #[no_mangle]
+    pub unsafe extern "C" fn greentic_pack_export__run_flow_json(
+        flow_id_ptr: *const u8,
+        flow_id_len: usize,
+        input_ptr: *const u8,
+        input_len: usize,
+        result_len: *mut usize,
+    ) -> *mut u8 {
+        let input = input_bytes(input_ptr, input_len);
+        let result = match slice_to_str(flow_id_ptr, flow_id_len) {
+            Some(flow_id) => run_flow_json(&Component, flow_id, input, &RunContext::default()),
+            None => RunResult {
+                status: "error".into(),
+                output: None,
+                error: Some(INVALID_FLOW_ID.into()),
+            },
+        };
+        let json = serde_json::to_vec(&result)
+            .expect("serialisation succeeds")
+            .into_boxed_slice();
+        if !result_len.is_null() {
+            *result_len = json.len();
+        }
+        Box::into_raw(json) as *mut u8
+    }
+
+    #[no_mangle]
+    pub extern "C" fn greentic_pack_export__a2a_search(json_buffer: *mut u8, len: usize) -> usize {
+        let component = Component;
+        let items = component.a2a_search("");
+        write_json_response(&items, json_buffer, len)
+    }
+
+    /// # Safety
+    ///
+    /// The caller must ensure that `flow_id_ptr` points to `flow_id_len` readable
+    /// bytes when non-null and that `json_buffer` points to a writable region of at
+    /// least `len` bytes when non-null. A flow id that is not UTF-8 is reported as
+    /// an error.
+    #[no_mangle]
+    pub unsafe extern "C" fn greentic_pack_export__node_docs(
+        flow_id_ptr: *const u8,
+        flow_id_len: usize,
+        json_buffer: *mut u8,
+        len: usize,
+    ) -> usize {
+        let component = Component;
+        let docs = slice_to_str(flow_id_ptr, flow_id_len)
+            .and_then(|flow_id| component.node_docs(flow_id));
+        write_json_response(&docs, json_buffer, len)
+    }
+
+    /// # Safety
+    ///
+    /// The caller must ensure that `flow_id_ptr` points to `flow_id_len` readable
+    /// bytes when non-null and that `json_buffer` points to a writable region of at
+    /// least `len` bytes when non-null. A flow id that is not UTF-8 is reported as
+    /// an error.
+    #[no_mangle]
+    pub unsafe extern "C" fn greentic_pack_export__get_flow_schema(
+        flow_id_ptr: *const u8,
+        flow_id_len: usize,
+        json_buffer: *mut u8,
+        len: usize,
+    ) -> usize {
+        let component = Component;
+        let schema = slice_to_str(flow_id_ptr, flow_id_len)
+            .and_then(|flow_id| component.get_flow_schema(flow_id));
+        write_json_response(&schema, json_buffer, len)
+    }
+
+    #[unsafe(no_mangle)]
+    pub extern "C" fn greentic_pack_export__config_schema(json_buffer: *mut u8, len: usize) -> usize {
+        let component = Component;
+        let schema = component.config_schema();
+        write_json_response(&schema, json_buffer, len)
+    }
+
+    pub(crate) const INVALID_FLOW_ID: &str = "flow id is not valid UTF-8";
+
+    fn zeroed_buffer(len: usize) -> Box<[u8]> {
+        vec![0u8; len].into_boxed_slice()
+    }
+
+    fn write_json_response<T: serde::Serialize>(value: &T, buffer: *mut u8, len: usize) -> usize {
+        let json = serde_json::to_vec(value).expect("serialisation succeeds");
+        if buffer.is_null() || len == 0 {
+            return json.len();
+        }
+
+        let copy_len = core::cmp::min(json.len(), len);
+        unsafe {
+            core::ptr::copy_nonoverlapping(json.as_ptr(), buffer, copy_len);
+        }
+        copy_len
+    }
+
+    unsafe fn input_bytes<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+        if ptr.is_null() {
+            return &[];
+        }
+        core::slice::from_raw_parts(ptr, len)
+    }
+
+    unsafe fn slice_to_str<'a>(ptr: *const u8, len: usize) -> Option<&'a str> {
+        core::str::from_utf8(input_bytes(ptr, len)).ok()
+    }
+}
+
+#[cfg(feature = "legacy-abi")]
+pub use legacy::*;
+
+#[cfg(target_arch = "wasm32")]
+mod wit {
+    wit_bindgen::generate!({
+        path: "wit",
+        world: "pack",
+    });
+
+    use alloc::{
+        string::{String, ToString},
+        vec::Vec,
+    };
+    use core::cell::RefCell;
+    use exports::greentic::pack_export::pack_export as api;
+
+    use super::{Component, PackExport, RunEvent, RunEvents};
+
+    /// A run started by `run-flow-stream` or `resume-flow-stream`.
+    pub struct StreamedRun(RefCell<Option<RunEvents>>);
+
+    impl api::GuestFlowRun for StreamedRun {
+        fn next(&self) -> Option<api::RunEvent> {
+            let mut events = self.0.borrow_mut();
+            let event = events.as_mut()?.next();
+            match event? {
+                RunEvent::Progress { message } => Some(api::RunEvent::Progress(message)),
+                RunEvent::Chunk { output } => Some(api::RunEvent::Chunk(output.to_string())),
+                RunEvent::Done(result) => {
+                    // Nothing follows the result, however the flow streams.
+                    *events = None;
+                    Some(api::RunEvent::Done(run_result(result)))
+                }
+                RunEvent::Checkpoint(checkpoint) => {
+                    *events = None;
+                    let checkpoint =
+                        serde_json::to_string(&checkpoint).expect("serialisation succeeds");
+                    Some(api::RunEvent::Checkpoint(checkpoint))
+                }
+            }
+        }
+    }
+
+    fn run_context(ctx: api::RunContext) -> super::RunContext {
+        super::RunContext {
+            tenant: ctx.tenant,
+            env: ctx.env,
+            locale: ctx.locale,
+            correlation_id: ctx.correlation_id,
+            secrets: ctx.secrets,
+        }
+    }
+
+    fn run_result(result: super::RunResult) -> api::RunResult {
+        api::RunResult {
+            status: result.status,
+            output: result.output.map(|output| output.to_string()),
+            error: result.error,
+        }
+    }
+
+    fn flow_infos(flows: Vec<super::FlowInfo>) -> Vec<api::FlowInfo> {
+        flows
+            .into_iter()
+            .map(|flow| api::FlowInfo {
+                id: flow.id,
+                human_name: flow.human_name,
+                description: flow.description,
+                qos: flow.qos,
+                deadline_ms: flow.deadline_ms,
+            })
+            .collect()
+    }
+
+    impl api::Guest for Component {
+        type FlowRun = StreamedRun;
+
+        fn count_flows() -> u32 {
+            u32::try_from(Component.count_flows()).unwrap_or(u32::MAX)
+        }
+
+        fn list_flows() -> Vec<api::FlowInfo> {
+            flow_infos(Component.list_flows())
+        }
+
+        fn list_flows_page(offset: u32, limit: u32) -> Vec<api::FlowInfo> {
+            flow_infos(Component.list_flows_page(offset as usize, limit as usize))
+        }
+
+        fn find_flows(filter: api::FlowFilter) -> Vec<api::FlowInfo> {
+            let filter = super::FlowFilter {
+                tags: filter.tags,
+                categories: filter.categories,
+                imports: filter.imports,
+                trigger: filter.trigger,
+                qos: filter.qos,
+            };
+            flow_infos(Component.find_flows(&filter))
+        }
+
+        fn get_flow_schema(flow_id: String) -> Option<api::SchemaDoc> {
+            Component
+                .get_flow_schema(&flow_id)
+                .map(|schema| api::SchemaDoc {
+                    flow_id: schema.flow_id,
+                    schema_json: schema.schema_json.to_string(),
+                })
+        }
+
+        fn node_docs(flow_id: String) -> Option<Vec<api::NodeDoc>> {
+            let docs = Component.node_docs(&flow_id)?;
+            Some(
+                docs.into_iter()
+                    .map(|doc| api::NodeDoc {
+                        node: doc.node,
+                        doc: doc.doc,
+                    })
+                    .collect(),
+            )
+        }
+
+        fn config_schema() -> Option<String> {
+            Component.config_schema().map(|schema| schema.to_string())
+        }
+
+        fn prepare_flow(flow_id: String) -> api::PrepareResult {
+            let result = Component.prepare_flow(&flow_id);
+            api::PrepareResult {
+                status: result.status,
+                error: result.error,
+            }
+        }
+
+        fn run_flow(flow_id: String, input: String, ctx: api::RunContext) -> api::RunResult {
+            let ctx = run_context(ctx);
+            run_result(super::run_flow_json(&Component, &flow_id, input.as_bytes(), &ctx))
+        }
+
+        fn run_flow_stream(flow_id: String, input: String, ctx: api::RunContext) -> api::FlowRun {
+            let ctx = run_context(ctx);
+            let events =
+                super::run_flow_stream_json(&Component, &flow_id, input.as_bytes(), &ctx);
+            api::FlowRun::new(StreamedRun(RefCell::new(Some(events))))
+        }
+
+        fn drain() {
+            Component.drain();
+        }
+
+        fn resume_flow_stream(checkpoint: String, ctx: api::RunContext) -> api::FlowRun {
+            let ctx = run_context(ctx);
+            let events = super::resume_flow_stream_json(&Component, checkpoint.as_bytes(), &ctx);
+            api::FlowRun::new(StreamedRun(RefCell::new(Some(events))))
+        }
+
+        fn a2a_search(query: String) -> Vec<api::A2aItem> {
+            Component
+                .a2a_search(&query)
+                .into_iter()
+                .map(|item| api::A2aItem {
+                    title: item.title,
+                    flow_id: item.flow_id,
+                })
+                .collect()
+        }
+    }
+
+    export!(Component);
 }
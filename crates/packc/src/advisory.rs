@@ -0,0 +1,276 @@
+//! Upgrade advisories published alongside a search index.
+//!
+//! When a pack version is yanked, for example over a security issue, its
+//! maintainers add an advisory to the catalog's `search-index.json`. Each
+//! advisory names the pack, the affected versions as a semver range, a
+//! severity, and optionally the version that supersedes them. Consumers run
+//! [`scan`] over the archives they have installed to find those an advisory
+//! covers, or that the index lists as yanked from their registry.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::ValueEnum;
+use greentic_pack::{SigningPolicy, open_pack};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::index::SearchIndex;
+use crate::registry::Yank;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum AdvisorySeverity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl fmt::Display for AdvisorySeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AdvisorySeverity::Low => "low",
+            AdvisorySeverity::Medium => "medium",
+            AdvisorySeverity::High => "high",
+            AdvisorySeverity::Critical => "critical",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Advisory {
+    /// Identifier unique within the index, e.g. `GPA-2026-0001`.
+    pub id: String,
+    pub pack_id: String,
+    /// Semver range of the affected versions, e.g. `>=0.2.0, <0.3.1`.
+    pub versions: String,
+    pub severity: AdvisorySeverity,
+    pub description: String,
+    /// Version to upgrade to; it must not be affected itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub superseded_by: Option<String>,
+}
+
+impl Advisory {
+    pub fn validate(&self) -> Result<()> {
+        if self.id.trim().is_empty() {
+            bail!("advisory id must not be empty");
+        }
+        if self.pack_id.trim().is_empty() {
+            bail!("advisory {} must name a pack", self.id);
+        }
+        if self.description.trim().is_empty() {
+            bail!("advisory {} must have a description", self.id);
+        }
+        let range = self.range()?;
+        if let Some(superseded_by) = &self.superseded_by {
+            let version = Version::parse(superseded_by).with_context(|| {
+                format!(
+                    "advisory {} supersedes with invalid version `{superseded_by}`",
+                    self.id
+                )
+            })?;
+            if range.matches(&version) {
+                bail!(
+                    "advisory {} supersedes with {superseded_by}, which `{}` also covers",
+                    self.id,
+                    self.versions
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn range(&self) -> Result<VersionReq> {
+        VersionReq::parse(&self.versions).with_context(|| {
+            format!(
+                "advisory {} has an invalid version range `{}`",
+                self.id, self.versions
+            )
+        })
+    }
+
+    /// Whether `pack_id@version` is covered. Advisories with a range that does
+    /// not parse cover nothing; [`Advisory::validate`] rejects them on add.
+    pub fn affects(&self, pack_id: &str, version: &Version) -> bool {
+        self.pack_id == pack_id && self.range().is_ok_and(|range| range.matches(version))
+    }
+}
+
+impl SearchIndex {
+    /// Adds `advisory` after validating it; ids must be unique.
+    pub fn add_advisory(&mut self, advisory: Advisory) -> Result<()> {
+        advisory.validate()?;
+        if self.advisories.iter().any(|known| known.id == advisory.id) {
+            bail!("the index already has an advisory {}", advisory.id);
+        }
+        self.advisories.push(advisory);
+        self.advisories.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(())
+    }
+
+    /// Advisories covering `pack_id@version`, most severe first.
+    pub fn advisories_for(&self, pack_id: &str, version: &Version) -> Vec<&Advisory> {
+        let mut matching: Vec<&Advisory> = self
+            .advisories
+            .iter()
+            .filter(|advisory| advisory.affects(pack_id, version))
+            .collect();
+        matching.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.id.cmp(&b.id)));
+        matching
+    }
+
+    /// The yank marker of `pack_id@version`, if an indexed copy was yanked.
+    pub fn yank_of(&self, pack_id: &str, version: &str) -> Option<&Yank> {
+        self.packs
+            .iter()
+            .filter(|pack| pack.pack_id == pack_id && pack.version == version)
+            .find_map(|pack| pack.yanked.as_ref())
+    }
+}
+
+/// An installed archive that is yanked or covered by at least one advisory.
+#[derive(Debug, Clone, Serialize)]
+pub struct AffectedPack {
+    pub path: PathBuf,
+    pub pack_id: String,
+    pub version: String,
+    pub advisories: Vec<Advisory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub yanked: Option<Yank>,
+}
+
+impl AffectedPack {
+    /// Severity of the most severe advisory, if any.
+    pub fn severity(&self) -> Option<AdvisorySeverity> {
+        self.advisories
+            .iter()
+            .map(|advisory| advisory.severity)
+            .max()
+    }
+}
+
+/// Opens every `.gtpack` in `paths` (files, or directories searched
+/// recursively) and returns the ones `index` has advisories for or lists as
+/// yanked.
+pub fn scan(index: &SearchIndex, paths: &[PathBuf]) -> Result<Vec<AffectedPack>> {
+    let mut archives = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            archives.extend(
+                WalkDir::new(path)
+                    .into_iter()
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.file_type().is_file())
+                    .map(|entry| entry.into_path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "gtpack")),
+            );
+        } else {
+            archives.push(path.clone());
+        }
+    }
+    archives.sort();
+    archives.dedup();
+
+    let mut affected = Vec::new();
+    for path in archives {
+        if let Some(pack) = check_archive(index, &path)? {
+            affected.push(pack);
+        }
+    }
+    Ok(affected)
+}
+
+fn check_archive(index: &SearchIndex, path: &Path) -> Result<Option<AffectedPack>> {
+    let load = open_pack(path, SigningPolicy::DevOk)
+        .map_err(|err| anyhow!("failed to open {}: {}", path.display(), err.message))?;
+    let meta = &load.manifest.meta;
+    let advisories: Vec<Advisory> = index
+        .advisories_for(&meta.pack_id, &meta.version)
+        .into_iter()
+        .cloned()
+        .collect();
+    let version = meta.version.to_string();
+    let yanked = index.yank_of(&meta.pack_id, &version).cloned();
+    if advisories.is_empty() && yanked.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(AffectedPack {
+        path: path.to_path_buf(),
+        pack_id: meta.pack_id.clone(),
+        version,
+        advisories,
+        yanked,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::INDEX_FORMAT;
+
+    fn advisory(id: &str, versions: &str, severity: AdvisorySeverity) -> Advisory {
+        Advisory {
+            id: id.into(),
+            pack_id: "greentic.weather.demo".into(),
+            versions: versions.into(),
+            severity,
+            description: "forecast template leaks the API key".into(),
+            superseded_by: Some("0.3.1".into()),
+        }
+    }
+
+    #[test]
+    fn advisories_match_versions_in_their_range() {
+        let mut index = SearchIndex {
+            format: INDEX_FORMAT.into(),
+            packs: Vec::new(),
+            terms: Default::default(),
+            advisories: Vec::new(),
+        };
+        index
+            .add_advisory(advisory(
+                "GPA-1",
+                ">=0.2.0, <0.3.1",
+                AdvisorySeverity::Medium,
+            ))
+            .unwrap();
+        index
+            .add_advisory(advisory("GPA-2", "<0.3.0", AdvisorySeverity::Critical))
+            .unwrap();
+
+        let ids = |version: &str| -> Vec<String> {
+            index
+                .advisories_for("greentic.weather.demo", &Version::parse(version).unwrap())
+                .into_iter()
+                .map(|advisory| advisory.id.clone())
+                .collect()
+        };
+        assert_eq!(ids("0.2.5"), ["GPA-2", "GPA-1"]);
+        assert_eq!(ids("0.3.0"), ["GPA-1"]);
+        assert!(ids("0.3.1").is_empty());
+        assert!(
+            index
+                .advisories_for("other.pack", &Version::parse("0.2.5").unwrap())
+                .is_empty()
+        );
+
+        let err = index
+            .add_advisory(advisory("GPA-1", "<0.1.0", AdvisorySeverity::Low))
+            .unwrap_err();
+        assert!(err.to_string().contains("already has"), "{err}");
+        let err = index
+            .add_advisory(advisory("GPA-3", "<0.4.0", AdvisorySeverity::Low))
+            .unwrap_err();
+        assert!(err.to_string().contains("also covers"), "{err}");
+        let err = index
+            .add_advisory(advisory("GPA-4", "not a range", AdvisorySeverity::Low))
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid version range"), "{err}");
+    }
+}
@@ -0,0 +1,197 @@
+//! Static analysis of what each flow touches.
+//!
+//! Nothing is executed: the pass reads each flow's nodes and their
+//! configuration. Components and imports come from
+//! [`greentic_pack::capabilities::flow_requirements`], the same rules hosts
+//! apply when they deny capabilities. A flow references a template when a
+//! configuration string names it by logical path, by its path inside a
+//! template directory, or by that path without the extension; partials and
+//! assets pulled in by those templates are included.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use greentic_pack::capabilities::flow_requirements;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::assets::AssetGraph;
+use crate::flows::FlowAsset;
+use crate::manifest::PackSpec;
+use crate::templates::TemplateAsset;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FlowUsage {
+    pub flow: String,
+    /// Flow file relative to the pack root.
+    pub path: String,
+    pub entry: bool,
+    /// Named by `lifecycle.health`.
+    pub health: bool,
+    /// Nodes declared by the flow; branches taken at runtime may visit fewer.
+    pub nodes: usize,
+    /// Node count per component, e.g. `mcp.exec`.
+    pub components: BTreeMap<String, usize>,
+    /// `imports_required` entries the flow relies on, in `pack.yaml` order.
+    pub imports: Vec<String>,
+    /// Template assets the flow references, directly or through other templates.
+    pub templates: Vec<String>,
+    /// `doc:` strings by node id.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub node_docs: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageReport {
+    pub flows: Vec<FlowUsage>,
+    /// Declared imports no flow was found to use.
+    pub unattributed_imports: Vec<String>,
+}
+
+pub fn analyze(spec: &PackSpec, flows: &[FlowAsset], templates: &[TemplateAsset]) -> UsageReport {
+    let graph = AssetGraph::build(templates, &spec.template_dirs);
+    let names = template_names(templates, &spec.template_dirs);
+
+    let flows: Vec<FlowUsage> = flows
+        .iter()
+        .map(|flow| analyze_flow(spec, flow, &graph, &names))
+        .collect();
+    let used: BTreeSet<&str> = flows
+        .iter()
+        .flat_map(|flow| flow.imports.iter().map(String::as_str))
+        .collect();
+    let unattributed_imports = spec
+        .imports_required
+        .iter()
+        .map(ToString::to_string)
+        .filter(|import| !used.contains(import.as_str()))
+        .collect();
+
+    UsageReport {
+        flows,
+        unattributed_imports,
+    }
+}
+
+fn analyze_flow(
+    spec: &PackSpec,
+    flow: &FlowAsset,
+    graph: &AssetGraph,
+    names: &BTreeMap<String, String>,
+) -> FlowUsage {
+    // The bundle keeps only routing per node, so read configuration from the source.
+    let source: Value = serde_yaml_bw::from_str(&flow.raw).unwrap_or_default();
+    let mut strings = Vec::new();
+    if let Some(nodes) = source.get("nodes") {
+        collect_strings(nodes, &mut strings);
+    }
+    let requirements = flow_requirements(&flow.raw, &spec.imports_required).unwrap_or_default();
+
+    let mut templates: BTreeSet<String> = strings
+        .iter()
+        .filter_map(|value| names.get(value.trim()).cloned())
+        .collect();
+    // Follow partials and assets pulled in by the referenced templates.
+    let mut pending: Vec<String> = templates.iter().cloned().collect();
+    while let Some(current) = pending.pop() {
+        for reference in graph.references.iter().filter(|r| r.from == current) {
+            if let Some(target) = &reference.resolved
+                && templates.insert(target.clone())
+            {
+                pending.push(target.clone());
+            }
+        }
+    }
+
+    FlowUsage {
+        flow: flow.bundle.id.clone(),
+        path: flow.relative_path.to_string_lossy().replace('\\', "/"),
+        entry: spec.entry_flows.contains(&flow.bundle.id),
+        health: spec
+            .lifecycle
+            .as_ref()
+            .and_then(|lifecycle| lifecycle.health.as_ref())
+            .is_some_and(|health| *health == flow.bundle.id),
+        nodes: flow_node_count(flow, &source),
+        components: requirements.components,
+        imports: requirements.imports,
+        templates: templates.into_iter().collect(),
+        node_docs: flow.node_docs.clone(),
+    }
+}
+
+/// Names a flow may use for each template: the logical path, the path inside
+/// its template directory, and either of those without the extension.
+fn template_names(
+    templates: &[TemplateAsset],
+    template_dirs: &[String],
+) -> BTreeMap<String, String> {
+    let mut names = BTreeMap::new();
+    for template in templates {
+        let logical = template.logical_path.as_str();
+        let mut forms = vec![logical];
+        for dir in template_dirs {
+            let dir = dir.trim_start_matches("./").trim_end_matches('/');
+            if let Some(rest) = logical
+                .strip_prefix(dir)
+                .and_then(|rest| rest.strip_prefix('/'))
+            {
+                forms.push(rest);
+            }
+        }
+        for form in forms {
+            names.insert(form.to_string(), template.logical_path.clone());
+            if let Some((stem, _)) = form.rsplit_once('.') {
+                names
+                    .entry(stem.to_string())
+                    .or_insert_with(|| template.logical_path.clone());
+            }
+        }
+    }
+    names
+}
+
+/// Prefers the nodes written in the flow source; the bundle only lists nodes
+/// whose component it could pin.
+fn flow_node_count(flow: &FlowAsset, source: &Value) -> usize {
+    match source.get("nodes") {
+        Some(Value::Object(nodes)) => nodes.len().max(flow.bundle.nodes.len()),
+        Some(Value::Array(nodes)) => nodes.len().max(flow.bundle.nodes.len()),
+        _ => flow.bundle.nodes.len(),
+    }
+}
+
+fn collect_strings(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::String(text) => out.push(text.clone()),
+        Value::Array(items) => items.iter().for_each(|item| collect_strings(item, out)),
+        Value::Object(map) => map.values().for_each(|item| collect_strings(item, out)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_names_cover_short_forms() {
+        let asset = TemplateAsset {
+            logical_path: "templates/email/welcome.hbs".to_string(),
+            absolute_path: "templates/email/welcome.hbs".into(),
+            bytes: Vec::new(),
+            digest: String::new(),
+            size: 0,
+        };
+        let names = template_names(&[asset], &["./templates/".to_string()]);
+        let forms: Vec<&str> = names.keys().map(String::as_str).collect();
+        assert_eq!(
+            forms,
+            vec![
+                "email/welcome",
+                "email/welcome.hbs",
+                "templates/email/welcome",
+                "templates/email/welcome.hbs",
+            ]
+        );
+    }
+}
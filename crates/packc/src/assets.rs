@@ -0,0 +1,329 @@
+//! Reference graph between template assets.
+//!
+//! Templates pull in Handlebars partials (`{{> partials/header}}`) and point at
+//! images, stylesheets, and other templates through `src`/`href` attributes.
+//! Targets are logical paths, resolved against the referencing file's
+//! directory first and then against its template directory.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::templates::TemplateAsset;
+
+/// Extensions treated as templates. Templates may legitimately be entry points
+/// nothing else references, so they are never reported as orphaned.
+const TEMPLATE_EXTENSIONS: &[&str] = &["hbs", "handlebars", "mustache", "html", "htm", "txt"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RefKind {
+    Partial,
+    Src,
+    Href,
+}
+
+impl RefKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RefKind::Partial => "partial",
+            RefKind::Src => "src",
+            RefKind::Href => "href",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AssetRef {
+    /// Logical path of the referencing asset.
+    pub from: String,
+    pub kind: RefKind,
+    /// Target as written in the template.
+    pub target: String,
+    /// Logical path the target resolved to; `None` when nothing matched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AssetGraph {
+    /// Every template asset, by logical path.
+    pub assets: Vec<String>,
+    pub references: Vec<AssetRef>,
+}
+
+impl AssetGraph {
+    /// Builds the graph from the collected templates; `template_dirs` are the
+    /// roots that bare targets are also resolved against.
+    pub fn build(templates: &[TemplateAsset], template_dirs: &[String]) -> Self {
+        let assets: BTreeSet<&str> = templates
+            .iter()
+            .map(|asset| asset.logical_path.as_str())
+            .collect();
+
+        let mut references = Vec::new();
+        for asset in templates {
+            let Ok(source) = std::str::from_utf8(&asset.bytes) else {
+                continue;
+            };
+            let root = template_root(&asset.logical_path, template_dirs);
+            for (kind, target) in extract_references(source) {
+                let resolved = resolve(&assets, &asset.logical_path, root, kind, &target);
+                references.push(AssetRef {
+                    from: asset.logical_path.clone(),
+                    kind,
+                    target,
+                    resolved,
+                });
+            }
+        }
+
+        Self {
+            assets: assets.into_iter().map(str::to_string).collect(),
+            references,
+        }
+    }
+
+    /// References whose target is not part of the pack.
+    pub fn missing(&self) -> impl Iterator<Item = &AssetRef> {
+        self.references.iter().filter(|r| r.resolved.is_none())
+    }
+
+    /// Non-template assets that nothing references. `external` lists paths
+    /// referenced from outside the templates (e.g. by the README).
+    pub fn orphans<'a>(&'a self, external: &[String]) -> Vec<&'a str> {
+        let referenced: BTreeSet<&str> = self
+            .references
+            .iter()
+            .filter_map(|r| r.resolved.as_deref())
+            .chain(external.iter().map(String::as_str))
+            .collect();
+        self.assets
+            .iter()
+            .map(String::as_str)
+            .filter(|path| !is_template(path) && !referenced.contains(path))
+            .collect()
+    }
+
+    /// Outgoing references grouped by referencing asset.
+    pub fn by_source(&self) -> BTreeMap<&str, Vec<&AssetRef>> {
+        let mut grouped: BTreeMap<&str, Vec<&AssetRef>> = BTreeMap::new();
+        for reference in &self.references {
+            grouped
+                .entry(reference.from.as_str())
+                .or_default()
+                .push(reference);
+        }
+        grouped
+    }
+}
+
+/// Extracts partial names and relative `src`/`href` targets from a template,
+/// in source order and without duplicates. URLs, absolute paths, anchors, and
+/// values computed by template expressions are skipped.
+pub fn extract_references(source: &str) -> Vec<(RefKind, String)> {
+    let mut refs = Vec::new();
+    let mut push = |kind: RefKind, target: &str| {
+        let entry = (kind, target.to_string());
+        if !refs.contains(&entry) {
+            refs.push(entry);
+        }
+    };
+
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        rest = rest[start + 2..].trim_start_matches(['{', '~']);
+        // `{{> name}}` and block partials `{{#> name}}`.
+        let body = rest.strip_prefix('#').unwrap_or(rest);
+        if let Some(body) = body.strip_prefix('>') {
+            let name = body
+                .trim_start()
+                .split(|c: char| c.is_whitespace() || c == '}' || c == '~')
+                .next()
+                .unwrap_or_default()
+                .trim_matches(['"', '\'']);
+            // Dynamic partials (`{{> (lookup ...)}}`) cannot be resolved statically.
+            if !name.is_empty() && !name.starts_with('(') {
+                push(RefKind::Partial, name);
+            }
+        }
+    }
+
+    for (kind, attr) in [(RefKind::Src, "src="), (RefKind::Href, "href=")] {
+        let mut rest = source;
+        while let Some(start) = rest.find(attr) {
+            let preceded_by_name = source[..source.len() - rest.len() + start]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            rest = &rest[start + attr.len()..];
+            if preceded_by_name {
+                // e.g. `data-src=`; not a reference the host resolves.
+                continue;
+            }
+            let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+                continue;
+            };
+            rest = &rest[1..];
+            let end = rest.find(quote).unwrap_or(rest.len());
+            if let Some(target) = relative_target(&rest[..end]) {
+                push(kind, target);
+            }
+            rest = &rest[end..];
+        }
+    }
+
+    refs
+}
+
+fn relative_target(value: &str) -> Option<&str> {
+    let value = value.trim();
+    if value.is_empty()
+        || value.contains("{{")
+        || value.starts_with(['#', '/'])
+        || value.contains("://")
+        || value.starts_with("mailto:")
+        || value.starts_with("data:")
+        || value.starts_with("tel:")
+    {
+        return None;
+    }
+    let path = value.split(['#', '?']).next().unwrap_or_default();
+    (!path.is_empty()).then_some(path)
+}
+
+fn template_root<'a>(logical_path: &str, template_dirs: &'a [String]) -> Option<&'a str> {
+    template_dirs
+        .iter()
+        .map(|dir| dir.trim_start_matches("./").trim_end_matches('/'))
+        .filter(|dir| {
+            logical_path
+                .strip_prefix(dir)
+                .is_some_and(|rest| rest.starts_with('/'))
+        })
+        .max_by_key(|dir| dir.len())
+}
+
+fn resolve(
+    assets: &BTreeSet<&str>,
+    from: &str,
+    root: Option<&str>,
+    kind: RefKind,
+    target: &str,
+) -> Option<String> {
+    let dir = from.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+    let mut candidates = vec![join(dir, target)];
+    if let Some(root) = root {
+        candidates.push(join(root, target));
+    }
+    candidates.push(join("", target));
+
+    candidates.into_iter().flatten().find_map(|candidate| {
+        if assets.contains(candidate.as_str()) {
+            return Some(candidate);
+        }
+        // Partials are named without their extension.
+        if kind == RefKind::Partial {
+            return assets
+                .iter()
+                .find(|asset| {
+                    asset
+                        .rsplit_once('.')
+                        .is_some_and(|(stem, _)| stem == candidate)
+                })
+                .map(|asset| asset.to_string());
+        }
+        None
+    })
+}
+
+/// Joins `target` onto `base`, resolving `.` and `..`. Returns `None` when
+/// the path would climb above the pack root.
+fn join(base: &str, target: &str) -> Option<String> {
+    let mut segments: Vec<&str> = base.split('/').filter(|s| !s.is_empty()).collect();
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            other => segments.push(other),
+        }
+    }
+    Some(segments.join("/"))
+}
+
+fn is_template(path: &str) -> bool {
+    path.rsplit_once('.').is_some_and(|(_, ext)| {
+        TEMPLATE_EXTENSIONS
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(ext))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(path: &str, body: &str) -> TemplateAsset {
+        TemplateAsset {
+            logical_path: path.to_string(),
+            absolute_path: path.into(),
+            bytes: body.as_bytes().to_vec(),
+            digest: String::new(),
+            size: body.len() as u64,
+        }
+    }
+
+    #[test]
+    fn extracts_partials_and_attributes() {
+        let source = r##"{{> partials/header}} {{#> layouts/base title="x"}}
+            <img src="img/logo.png"><link href='css/site.css?v=2' rel="stylesheet">
+            <a href="https://greentic.ai">site</a> <a href="#top">top</a>
+            <img src="{{icon_url}}"> <div data-src="lazy.png"></div> {{> (lookup . "p")}}"##;
+        assert_eq!(
+            extract_references(source),
+            vec![
+                (RefKind::Partial, "partials/header".to_string()),
+                (RefKind::Partial, "layouts/base".to_string()),
+                (RefKind::Src, "img/logo.png".to_string()),
+                (RefKind::Href, "css/site.css".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_targets_and_reports_missing_and_orphans() {
+        let templates = [
+            asset(
+                "templates/email/welcome.hbs",
+                r#"{{> partials/footer}}<img src="../img/logo.png"><img src="img/gone.png">"#,
+            ),
+            asset("templates/partials/footer.hbs", "bye"),
+            asset("templates/img/logo.png", ""),
+            asset("templates/img/unused.png", ""),
+            asset("templates/img/readme-only.png", ""),
+        ];
+        let graph = AssetGraph::build(&templates, &["templates".to_string()]);
+
+        let resolved: Vec<_> = graph
+            .references
+            .iter()
+            .map(|r| r.resolved.as_deref())
+            .collect();
+        assert_eq!(
+            resolved,
+            vec![
+                Some("templates/partials/footer.hbs"),
+                Some("templates/img/logo.png"),
+                None,
+            ]
+        );
+        let missing: Vec<_> = graph.missing().map(|r| r.target.as_str()).collect();
+        assert_eq!(missing, vec!["img/gone.png"]);
+        assert_eq!(
+            graph.orphans(&["templates/img/readme-only.png".to_string()]),
+            vec!["templates/img/unused.png"]
+        );
+    }
+}
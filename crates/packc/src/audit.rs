@@ -0,0 +1,616 @@
+//! Security-review bundle of a pack.
+//!
+//! An audit collects what reviewers otherwise gather from several commands
+//! into one JSON document: signature status, SBOM, provenance, declared
+//! capabilities and secrets, the data-handling summary, policy results, and
+//! scanner findings. Signing the document with `--key` lets it be filed as
+//! evidence of what was reviewed.
+//!
+//! The [`Attestation`] covers the compact JSON serialisation of the document
+//! without its `attestation` field, object keys sorted.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use base64::Engine as _;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use greentic_pack::capabilities::{CapabilityFilter, FlowRequirements, flow_requirements};
+use greentic_pack::classification::{DataHandling, unclassified_flows};
+use greentic_pack::config::SecretMapping;
+use greentic_pack::{PackLoad, SigningPolicy, VerifyFinding, open_pack};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+use crate::assets::AssetGraph;
+use crate::digest::{self, DigestAlgorithm};
+use crate::signing::keys::PrivateKey;
+use crate::signing::verify::verify_signatures;
+use crate::signing::{VerifyOptions, canonicalize_pack_dir_with};
+use crate::{flows, manifest, media, readme, sbom, templates};
+
+pub const AUDIT_FORMAT: &str = "greentic-pack-audit-v1";
+
+#[derive(Debug, Serialize)]
+pub struct AuditReport {
+    pub format: &'static str,
+    pub generated_at_utc: String,
+    pub source: AuditSource,
+    pub pack: AuditedPack,
+    pub signature: SignatureAudit,
+    /// The SBOM as shipped: `sbom.json` for archives, CycloneDX for sources.
+    pub sbom: JsonValue,
+    pub provenance: Option<JsonValue>,
+    pub capabilities: CapabilityAudit,
+    /// Secrets the pack expects, by logical name.
+    pub secrets: BTreeMap<String, SecretMapping>,
+    pub data_handling: Option<DataHandling>,
+    pub policy: Vec<PolicyResult>,
+    pub findings: Vec<AuditFinding>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<Attestation>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditSource {
+    pub kind: SourceKind,
+    pub path: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Directory,
+    Gtpack,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditedPack {
+    pub id: String,
+    pub version: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SignatureAudit {
+    pub status: SignatureStatus,
+    pub detail: String,
+    pub signatures: Vec<SignatureEntry>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureStatus {
+    /// Every signature was checked and is valid.
+    Verified,
+    /// Signatures match the pack contents but no key was given to check them.
+    Unverified,
+    Invalid,
+    Unsigned,
+}
+
+impl SignatureStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SignatureStatus::Verified => "verified",
+            SignatureStatus::Unverified => "unverified",
+            SignatureStatus::Invalid => "invalid",
+            SignatureStatus::Unsigned => "unsigned",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SignatureEntry {
+    pub alg: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    pub digest: String,
+    pub signed_at_utc: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CapabilityAudit {
+    pub imports_required: Vec<String>,
+    /// Components and imports each flow requires.
+    pub flows: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PolicyResult {
+    pub rule: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditFinding {
+    pub code: String,
+    pub severity: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    pub message: String,
+}
+
+impl From<&VerifyFinding> for AuditFinding {
+    fn from(finding: &VerifyFinding) -> Self {
+        Self {
+            code: finding.code.as_str().to_string(),
+            severity: finding.severity.as_str().to_string(),
+            subject: finding.subject.clone(),
+            message: finding.message.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Attestation {
+    pub alg: String,
+    pub key_id: String,
+    /// Digest of the signed bytes, `<alg>:<hex>`.
+    pub digest: String,
+    pub sig: String,
+}
+
+/// Inputs that are not part of the pack itself.
+#[derive(Default)]
+pub struct AuditOptions<'a> {
+    /// Public keys checking the signatures of a pack directory.
+    pub public_keys_pem: &'a [&'a str],
+    /// Capabilities the target host denies; flows needing them fail the
+    /// `capabilities` rule.
+    pub deny: CapabilityFilter,
+}
+
+impl AuditReport {
+    /// Signs the document, replacing any previous attestation.
+    pub fn sign(&mut self, private_key_pem: &str) -> Result<()> {
+        self.attestation = None;
+        let bytes = self.signed_bytes()?;
+        let key = PrivateKey::from_pem(private_key_pem)?;
+        let sig = key.sign(&bytes)?;
+        self.attestation = Some(Attestation {
+            alg: key.algorithm().to_string(),
+            key_id: key.key_id(),
+            digest: DigestAlgorithm::default().digest(&bytes),
+            sig: URL_SAFE_NO_PAD.encode(sig),
+        });
+        Ok(())
+    }
+
+    /// Bytes the attestation covers.
+    pub fn signed_bytes(&self) -> Result<Vec<u8>> {
+        let mut value = serde_json::to_value(self)?;
+        if let Some(map) = value.as_object_mut() {
+            map.remove("attestation");
+        }
+        Ok(serde_json::to_vec(&value)?)
+    }
+}
+
+/// Audits a pack directory or `.gtpack` archive.
+pub fn audit(path: &Path, opts: &AuditOptions<'_>) -> Result<AuditReport> {
+    if path.is_file() {
+        audit_archive(path, opts)
+    } else {
+        audit_dir(path, opts)
+    }
+}
+
+fn audit_archive(path: &Path, opts: &AuditOptions<'_>) -> Result<AuditReport> {
+    let load = open_pack(path, SigningPolicy::DevOk).map_err(|err| {
+        anyhow::anyhow!("{} failed verification: {}", path.display(), err.message)
+    })?;
+    let meta = &load.manifest.meta;
+
+    let signature = SignatureAudit {
+        status: SignatureStatus::Verified,
+        detail: "signature matches the archive contents".to_string(),
+        signatures: load
+            .signature()
+            .map(|info| SignatureEntry {
+                alg: info.alg,
+                key_id: info.key_fingerprint,
+                digest: info.digest,
+                signed_at_utc: info.signed_at_utc,
+            })
+            .into_iter()
+            .collect(),
+    };
+    let strict = match open_pack(path, SigningPolicy::Strict) {
+        Ok(_) => PolicyResult {
+            rule: "signing.strict",
+            passed: true,
+            detail: "certificate chain satisfies the strict signing policy".to_string(),
+        },
+        Err(err) => PolicyResult {
+            rule: "signing.strict",
+            passed: false,
+            detail: err.message,
+        },
+    };
+
+    let requirements = meta_requirements(&load)?;
+    let sbom = load
+        .file("sbom.json")
+        .map(serde_json::from_slice)
+        .transpose()
+        .context("sbom.json is not valid JSON")?
+        .unwrap_or(JsonValue::Null);
+    let provenance = load
+        .provenance()
+        .map(|provenance| serde_json::to_value(&provenance))
+        .transpose()?;
+
+    let mut policy = vec![signature_rule(&signature), strict];
+    policy.extend(flow_rules(
+        meta.classification.as_ref(),
+        &requirements,
+        &opts.deny,
+    ));
+
+    Ok(AuditReport {
+        format: AUDIT_FORMAT,
+        generated_at_utc: now(),
+        source: AuditSource {
+            kind: SourceKind::Gtpack,
+            path: path.display().to_string(),
+        },
+        pack: AuditedPack {
+            id: meta.pack_id.clone(),
+            version: meta.version.to_string(),
+        },
+        signature,
+        sbom,
+        provenance,
+        capabilities: CapabilityAudit {
+            imports_required: meta
+                .imports
+                .iter()
+                .map(|import| import.pack_id.clone())
+                .collect(),
+            flows: capability_lists(&requirements),
+        },
+        secrets: meta
+            .config
+            .as_ref()
+            .map(|config| config.secrets.clone())
+            .unwrap_or_default(),
+        data_handling: meta.data_handling.clone(),
+        policy,
+        findings: load
+            .report
+            .findings
+            .iter()
+            .map(AuditFinding::from)
+            .collect(),
+        attestation: None,
+    })
+}
+
+fn meta_requirements(load: &PackLoad) -> Result<BTreeMap<String, FlowRequirements>> {
+    load.manifest
+        .flows
+        .iter()
+        .map(|flow| Ok((flow.id.clone(), load.flow_requirements(&flow.id)?)))
+        .collect()
+}
+
+fn audit_dir(pack_dir: &Path, opts: &AuditOptions<'_>) -> Result<AuditReport> {
+    let spec_bundle = manifest::load_spec(pack_dir)?;
+    let spec = &spec_bundle.spec;
+    let flows = flows::load_flows(pack_dir, spec, DigestAlgorithm::default())?;
+    let templates = templates::collect_templates(
+        pack_dir,
+        spec,
+        DigestAlgorithm::default(),
+        templates::SyntaxCheck::Skip,
+    )?;
+    let media_assets = media::collect_media(pack_dir, spec)?;
+
+    let requirements = flows
+        .iter()
+        .map(|flow| {
+            let requirements = flow_requirements(&flow.raw, &spec.imports_required)
+                .with_context(|| format!("flow `{}`", flow.bundle.id))?;
+            Ok((flow.bundle.id.clone(), requirements))
+        })
+        .collect::<Result<BTreeMap<_, _>>>()?;
+
+    let signature = dir_signature(pack_dir, opts.public_keys_pem)?;
+    let mut policy = vec![signature_rule(&signature)];
+    policy.extend(flow_rules(
+        spec.classification.as_ref(),
+        &requirements,
+        &opts.deny,
+    ));
+
+    let graph = AssetGraph::build(&templates, &spec.template_dirs);
+    let readme = readme::load_readme(pack_dir, &flows, &templates, &media_assets)?;
+    let readme_links = readme
+        .as_deref()
+        .map(greentic_pack::readme::relative_links)
+        .unwrap_or_default();
+    let mut findings: Vec<AuditFinding> = graph
+        .missing()
+        .map(|reference| AuditFinding {
+            code: "asset_missing".to_string(),
+            severity: "error".to_string(),
+            subject: Some(reference.from.clone()),
+            message: format!(
+                "references `{}`, which is not in the pack",
+                reference.target
+            ),
+        })
+        .collect();
+    findings.extend(
+        graph
+            .orphans(&readme_links)
+            .into_iter()
+            .map(|path| AuditFinding {
+                code: "asset_unreferenced".to_string(),
+                severity: "warning".to_string(),
+                message: format!("asset `{path}` is not referenced by any template"),
+                subject: Some(path.to_string()),
+            }),
+    );
+    findings.extend(
+        media::size_warnings(&media_assets)
+            .into_iter()
+            .map(|message| AuditFinding {
+                code: "media_size".to_string(),
+                severity: "warning".to_string(),
+                subject: None,
+                message,
+            }),
+    );
+
+    let generated_at = now();
+    let sbom = sbom::generate(&spec_bundle, &flows, &templates, &generated_at);
+    Ok(AuditReport {
+        format: AUDIT_FORMAT,
+        generated_at_utc: generated_at,
+        source: AuditSource {
+            kind: SourceKind::Directory,
+            path: pack_dir.display().to_string(),
+        },
+        pack: AuditedPack {
+            id: spec.id.clone(),
+            version: spec.version.clone(),
+        },
+        signature,
+        sbom: serde_json::to_value(sbom)?,
+        provenance: None,
+        capabilities: CapabilityAudit {
+            imports_required: spec
+                .imports_required
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            flows: capability_lists(&requirements),
+        },
+        secrets: spec
+            .config
+            .as_ref()
+            .map(|config| config.secrets.clone())
+            .unwrap_or_default(),
+        data_handling: spec
+            .classification
+            .as_ref()
+            .map(|section| section.summarize()),
+        policy,
+        findings,
+        attestation: None,
+    })
+}
+
+/// Checks the manifest signatures against `public_keys_pem`, or only against
+/// the pack contents when no key is given.
+fn dir_signature(pack_dir: &Path, public_keys_pem: &[&str]) -> Result<SignatureAudit> {
+    let signatures = manifest::read_signatures(pack_dir)?;
+    let entries = signatures
+        .iter()
+        .map(|signature| SignatureEntry {
+            alg: signature.alg.clone(),
+            key_id: Some(signature.key_id.clone()),
+            digest: signature.digest.clone(),
+            signed_at_utc: signature
+                .created_at
+                .format(&Rfc3339)
+                .unwrap_or_else(|_| signature.created_at.to_string()),
+        })
+        .collect();
+    let (status, detail) = if signatures.is_empty() {
+        (
+            SignatureStatus::Unsigned,
+            "pack manifest carries no signature".to_string(),
+        )
+    } else if let Some((first, rest)) = public_keys_pem.split_first() {
+        let opts = VerifyOptions {
+            public_key_pem: Some(first),
+            additional_public_keys_pem: rest,
+            ..VerifyOptions::default()
+        };
+        match verify_signatures(pack_dir, opts) {
+            Ok(verified) => (
+                SignatureStatus::Verified,
+                format!("{} signature(s) verified", verified.len()),
+            ),
+            Err(err) => (SignatureStatus::Invalid, err.to_string()),
+        }
+    } else {
+        let mut stale = Vec::new();
+        for signature in &signatures {
+            let (alg, _) = digest::parse(&signature.digest)?;
+            let canonical = canonicalize_pack_dir_with(pack_dir, alg)?;
+            if canonical.digest() != signature.digest {
+                stale.push(signature.key_id.as_str());
+            }
+        }
+        if stale.is_empty() {
+            (
+                SignatureStatus::Unverified,
+                "signature digests match the pack contents; pass --pub to check the signatures"
+                    .to_string(),
+            )
+        } else {
+            (
+                SignatureStatus::Invalid,
+                format!(
+                    "pack contents changed since signing by {}",
+                    stale.join(", ")
+                ),
+            )
+        }
+    };
+    Ok(SignatureAudit {
+        status,
+        detail,
+        signatures: entries,
+    })
+}
+
+fn signature_rule(signature: &SignatureAudit) -> PolicyResult {
+    PolicyResult {
+        rule: "signed",
+        passed: signature.status == SignatureStatus::Verified,
+        detail: signature.detail.clone(),
+    }
+}
+
+/// The classification rule, and the capability rule when `deny` is set.
+fn flow_rules(
+    classification: Option<&greentic_pack::classification::ClassificationSection>,
+    requirements: &BTreeMap<String, FlowRequirements>,
+    deny: &CapabilityFilter,
+) -> Vec<PolicyResult> {
+    let unclassified = unclassified_flows(
+        classification,
+        requirements
+            .iter()
+            .map(|(flow, requirements)| (flow.as_str(), requirements)),
+    );
+    let mut rules = vec![PolicyResult {
+        rule: "classification",
+        passed: unclassified.is_empty(),
+        detail: if unclassified.is_empty() {
+            "flows handling personal data classify their inputs and outputs".to_string()
+        } else {
+            format!(
+                "unclassified flows: {}",
+                unclassified
+                    .iter()
+                    .map(|(flow, capability)| format!("{flow} (uses `{capability}`)"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        },
+    }];
+
+    if !deny.is_empty() {
+        let blocked: Vec<String> = requirements
+            .iter()
+            .filter_map(|(flow, requirements)| {
+                let denied: Vec<&str> = requirements
+                    .capabilities()
+                    .filter(|capability| deny.denies(capability).is_some())
+                    .collect();
+                (!denied.is_empty()).then(|| format!("{flow} (needs {})", denied.join(", ")))
+            })
+            .collect();
+        rules.push(PolicyResult {
+            rule: "capabilities",
+            passed: blocked.is_empty(),
+            detail: if blocked.is_empty() {
+                "no flow needs a denied capability".to_string()
+            } else {
+                format!("flows needing denied capabilities: {}", blocked.join(", "))
+            },
+        });
+    }
+    rules
+}
+
+fn capability_lists(
+    requirements: &BTreeMap<String, FlowRequirements>,
+) -> BTreeMap<String, Vec<String>> {
+    requirements
+        .iter()
+        .map(|(flow, requirements)| {
+            (
+                flow.clone(),
+                requirements.capabilities().map(str::to_string).collect(),
+            )
+        })
+        .collect()
+}
+
+fn now() -> String {
+    OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string())
+}
+
+/// Fails unless every policy rule passed; used by `--fail-on-policy`.
+pub fn ensure_policy_passed(report: &AuditReport) -> Result<()> {
+    let failed: Vec<&str> = report
+        .policy
+        .iter()
+        .filter(|result| !result.passed)
+        .map(|result| result.rule)
+        .collect();
+    if !failed.is_empty() {
+        bail!("audit policy rules failed: {}", failed.join(", "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ed25519_dalek::pkcs8::EncodePrivateKey;
+    use ed25519_dalek::{Signature, SigningKey, Verifier};
+    use pkcs8::LineEnding;
+
+    use super::*;
+
+    fn weather_demo() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/weather-demo")
+    }
+
+    #[test]
+    fn signed_audit_of_a_source_pack_verifies() {
+        let opts = AuditOptions {
+            deny: ["mcp"].into_iter().collect(),
+            ..AuditOptions::default()
+        };
+        let mut report = audit(&weather_demo(), &opts).unwrap();
+        assert_eq!(report.source.kind, SourceKind::Directory);
+        assert_eq!(report.signature.status, SignatureStatus::Unsigned);
+        assert!(!report.capabilities.flows.is_empty());
+        let capabilities = report
+            .policy
+            .iter()
+            .find(|result| result.rule == "capabilities")
+            .unwrap();
+        assert!(!capabilities.passed);
+        assert!(ensure_policy_passed(&report).is_err());
+
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let pem = key.to_pkcs8_pem(LineEnding::LF).unwrap();
+        report.sign(&pem).unwrap();
+        let attestation = report.attestation.as_ref().unwrap();
+        assert_eq!(attestation.alg, "ed25519");
+
+        let sig = URL_SAFE_NO_PAD.decode(&attestation.sig).unwrap();
+        let sig = Signature::from_slice(&sig).unwrap();
+        let bytes = report.signed_bytes().unwrap();
+        key.verifying_key().verify(&bytes, &sig).unwrap();
+
+        report.pack.version = "9.9.9".to_string();
+        let tampered = report.signed_bytes().unwrap();
+        assert!(key.verifying_key().verify(&tampered, &sig).is_err());
+    }
+}
@@ -4,6 +4,7 @@ use std::path::PathBuf;
 use anyhow::{Context, Result};
 use schemars::schema_for;
 
+use packc::imports::InterfaceCatalog;
 use packc::manifest::PackSpec;
 
 fn main() -> Result<()> {
@@ -29,5 +30,12 @@ fn main() -> Result<()> {
     fs::write(&yaml_v1_named, serde_yaml_bw::to_string(&schema)?)
         .with_context(|| format!("failed to write {}", yaml_v1_named.display()))?;
 
+    let catalog_path = dir.join("interfaces.catalog.schema.json");
+    fs::write(
+        &catalog_path,
+        serde_json::to_string_pretty(&schema_for!(InterfaceCatalog))?,
+    )
+    .with_context(|| format!("failed to write {}", catalog_path.display()))?;
+
     Ok(())
 }
@@ -1,16 +1,46 @@
+use crate::build_cache::{BuildCache, CacheEntry};
+use crate::build_lock::{BuildLock, WaitPolicy};
+use crate::digest::DigestAlgorithm;
 use crate::flows::FlowAsset;
-use crate::templates::TemplateAsset;
-use crate::{BuildArgs, embed, flows, manifest, mcp, sbom, templates};
-use anyhow::{Context, Result};
+use crate::hermetic::{self, HermeticOptions};
+use crate::imports::InterfaceCatalog;
+use crate::imports::ResolvedImport;
+use crate::lockfile::{LOCK_FILE, PackLock};
+use crate::manifest::PackSpec;
+use crate::media::MediaAsset;
+use crate::profile::ProfileGuide;
+use crate::remote::{self, RemoteBuildOptions, RemoteOutcome};
+use crate::taxonomy::Taxonomy;
+use crate::templates::{SyntaxCheck, TemplateAsset};
+use crate::{
+    BuildArgs, embed, flows, manifest, mcp, media, permissions, readme, sbom, templates, wasi,
+};
+use anyhow::{Context, Result, bail};
+use greentic_pack::atomic::{self, Durability};
 use greentic_pack::builder::{
-    ComponentArtifact, ImportRef, PACK_VERSION, PackBuilder, PackMeta, Provenance, Signing,
+    ComponentArtifact, ImportRef, ManifestEncoding, PACK_VERSION, PackBuilder, PackMeta,
+    Provenance, Signing, WasmSource,
 };
+use greentic_pack::classification::ClassificationSection;
+use greentic_pack::locales::LocalesSection;
+use greentic_pack::permissions::PermissionSummary;
 use semver::Version;
 use serde_json::Value as JsonValue;
 use std::fs;
 use std::path::{Path, PathBuf};
 use time::{OffsetDateTime, format_description::well_known::Rfc3339};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// Environment variable carrying a fixed build time (seconds since the Unix
+/// epoch), following the reproducible-builds convention.
+pub const SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
+
+/// An already compiled `pack_component` used instead of invoking cargo.
+#[derive(Debug, Clone)]
+pub struct PrebuiltComponent {
+    pub wasm: PathBuf,
+    pub version: Version,
+}
 
 #[derive(Debug, Clone)]
 pub struct BuildOptions {
@@ -19,8 +49,40 @@ pub struct BuildOptions {
     pub manifest_out: PathBuf,
     pub sbom_out: PathBuf,
     pub gtpack_out: Option<PathBuf>,
+    /// Which manifest entries the `.gtpack` carries.
+    pub manifest_encoding: ManifestEncoding,
     pub component_data: PathBuf,
+    /// Host profile data guiding the layout of the flows and templates.
+    pub profile_data: Option<PathBuf>,
+    /// Overrides the layout derived from `profile_data`.
+    pub profile: Option<ProfileGuide>,
+    pub taxonomy: Option<PathBuf>,
+    pub interface_catalog: Option<PathBuf>,
+    /// Fail when the current inputs differ from `pack.lock`.
+    pub locked: bool,
     pub dry_run: bool,
+    /// Whether Handlebars and Liquid templates must parse.
+    pub template_syntax: SyntaxCheck,
+    pub wait: WaitPolicy,
+    pub durability: Durability,
+    /// Algorithm for the flow and template digests in the manifest and SBOM.
+    pub digest: DigestAlgorithm,
+    pub prebuilt_component: Option<PrebuiltComponent>,
+    /// Overrides the build timestamp (otherwise `SOURCE_DATE_EPOCH`, then now).
+    pub created_at: Option<OffsetDateTime>,
+    /// Overrides the provenance recorded in the `.gtpack`.
+    pub provenance: Option<Provenance>,
+    /// Compile the component in a builder image instead of with the host toolchain.
+    pub hermetic: Option<HermeticOptions>,
+    /// Compile the component on a build service, falling back to a local build
+    /// when it is unreachable.
+    pub remote_build: Option<RemoteBuildOptions>,
+    /// Recompile the component even when the build cache holds one for the
+    /// same inputs.
+    pub force: bool,
+    /// Compile the component with its `no-wasi` feature and fail if it
+    /// imports WASI filesystem or clock interfaces.
+    pub assert_no_wasi: bool,
 }
 
 impl From<BuildArgs> for BuildOptions {
@@ -39,6 +101,13 @@ impl From<BuildArgs> for BuildOptions {
             .component_data
             .map(normalize)
             .unwrap_or(default_component_data);
+        let hermetic = args.hermetic.then(|| {
+            HermeticOptions::resolve(
+                args.builder_image,
+                args.build_cache.map(normalize),
+                &pack_dir,
+            )
+        });
 
         Self {
             pack_dir,
@@ -46,8 +115,32 @@ impl From<BuildArgs> for BuildOptions {
             manifest_out,
             sbom_out,
             gtpack_out,
+            manifest_encoding: args.manifest_encoding,
             component_data,
+            profile_data: args.profile_data.map(normalize),
+            profile: None,
+            taxonomy: args.taxonomy.map(normalize),
+            interface_catalog: args.interface_catalog.map(normalize),
+            locked: args.locked,
             dry_run: args.dry_run,
+            template_syntax: if args.no_validate_templates {
+                SyntaxCheck::Skip
+            } else {
+                SyntaxCheck::Parse
+            },
+            wait: WaitPolicy::from_flags(args.wait),
+            durability: Durability::from_flag(args.fsync),
+            digest: args.digest_alg,
+            prebuilt_component: args.component_wasm.map(|wasm| PrebuiltComponent {
+                wasm: normalize(wasm),
+                version: packc_version(),
+            }),
+            created_at: args.timestamp,
+            provenance: None,
+            hermetic,
+            remote_build: args.remote_build.map(|url| RemoteBuildOptions { url }),
+            force: args.force,
+            assert_no_wasi: args.assert_no_wasi,
         }
     }
 }
@@ -64,26 +157,114 @@ pub fn run(opts: &BuildOptions) -> Result<()> {
         "building greentic pack"
     );
 
-    let spec_bundle = manifest::load_spec(&opts.pack_dir)?;
+    let mut spec_bundle = manifest::load_spec(&opts.pack_dir)?;
     info!(id = %spec_bundle.spec.id, version = %spec_bundle.spec.version, "loaded pack spec");
 
-    let flows = flows::load_flows(&opts.pack_dir, &spec_bundle.spec)?;
+    // Dry runs write nothing, so they never contend for the pack directory.
+    let _lock = if opts.dry_run {
+        None
+    } else {
+        Some(BuildLock::acquire(
+            &opts.pack_dir,
+            "packc build",
+            opts.wait,
+        )?)
+    };
+
+    let taxonomy = Taxonomy::load(&opts.pack_dir, opts.taxonomy.as_deref())?;
+    taxonomy
+        .apply(&mut spec_bundle.spec)
+        .with_context(|| format!("pack {} violates the taxonomy", spec_bundle.spec.id))?;
+
+    let mut flows = flows::load_flows(&opts.pack_dir, &spec_bundle.spec, opts.digest)?;
     info!(count = flows.len(), "loaded flows");
 
-    let templates = templates::collect_templates(&opts.pack_dir, &spec_bundle.spec)?;
+    let mut templates = templates::collect_templates(
+        &opts.pack_dir,
+        &spec_bundle.spec,
+        opts.digest,
+        opts.template_syntax,
+    )?;
     info!(count = templates.len(), "collected templates");
 
+    let profile = match (&opts.profile, &opts.profile_data) {
+        (Some(guide), _) => Some(guide.clone()),
+        (None, Some(path)) => Some(ProfileGuide::load(path)?),
+        (None, None) => None,
+    };
+    if let Some(guide) = &profile {
+        guide.order_flows(&mut flows);
+        guide.order_templates(&mut templates);
+        info!(eager = ?guide.eager_flows, "laid out flows and templates by profile data");
+    }
+
+    let media_assets = media::collect_media(&opts.pack_dir, &spec_bundle.spec)?;
+    if !media_assets.is_empty() {
+        info!(count = media_assets.len(), "collected media");
+    }
+
+    let readme = readme::load_readme(&opts.pack_dir, &flows, &templates, &media_assets)?;
+
+    let locales = spec_bundle
+        .spec
+        .locales
+        .as_ref()
+        .map(|locales| {
+            locales.with_variants(
+                templates
+                    .iter()
+                    .map(|template| template.logical_path.as_str()),
+            )
+        })
+        .transpose()?;
+
     let pack_version = Version::parse(&spec_bundle.spec.version)
         .with_context(|| format!("invalid pack version {}", spec_bundle.spec.version))?;
 
     let mcp_components = mcp::compose_all(&opts.pack_dir, &spec_bundle, &pack_version)?;
 
-    let pack_manifest = manifest::build_manifest(&spec_bundle, &flows, &templates);
+    let pinned_at = match pinned_timestamp(opts)? {
+        Some(pinned) => Some(format_timestamp(pinned)?),
+        None => None,
+    };
+
+    let catalog = InterfaceCatalog::load(&opts.pack_dir, opts.interface_catalog.as_deref())?;
+    let imports_resolved = update_lock(opts, &catalog, &spec_bundle, &templates)?;
+
+    let mut pack_manifest = manifest::build_manifest(
+        &spec_bundle,
+        &flows,
+        &templates,
+        pinned_at.as_deref().unwrap_or_default(),
+    );
+    if let Some(guide) = &profile {
+        for flow in &mut pack_manifest.flows {
+            flow.eager = guide.is_eager(&flow.id);
+        }
+    }
+    pack_manifest.imports_resolved = imports_resolved;
+    pack_manifest.permissions = permissions::summarize(&opts.pack_dir, &spec_bundle.spec, &flows)?;
+
+    // Keyed before the timestamp is filled in, so unchanged inputs hit.
+    let component_cache = component_cache(opts, &pack_manifest)?;
+    let cached = component_cache
+        .as_ref()
+        .filter(|_| !opts.force)
+        .and_then(|(cache, key)| cache.lookup(key));
+    let reproducible = pinned_at.is_some();
+    let created_at = match (&cached, pinned_at) {
+        (Some(entry), _) => entry.created_at.clone(),
+        (None, Some(pinned)) => pinned,
+        (None, None) => format_timestamp(OffsetDateTime::now_utc())?,
+    };
+    pack_manifest.created_at = created_at.clone();
     let manifest_bytes = manifest::encode_manifest(&pack_manifest)?;
     info!(len = manifest_bytes.len(), "encoded manifest");
 
-    let component_src = embed::generate_component_data(&manifest_bytes, &flows, &templates)?;
-    let sbom_model = sbom::generate(&spec_bundle, &flows, &templates);
+    let flow_index = embed::FlowIndex::build(&spec_bundle.spec, &flows)?;
+    let component_src =
+        embed::generate_component_data(&manifest_bytes, &flow_index, &flows, &templates)?;
+    let sbom_model = sbom::generate(&spec_bundle, &flows, &templates, &created_at);
     let sbom_json = serde_json::to_string_pretty(&sbom_model)?;
 
     if opts.dry_run {
@@ -92,25 +273,247 @@ pub fn run(opts: &BuildOptions) -> Result<()> {
         return Ok(());
     }
 
-    write_if_changed(&opts.manifest_out, &manifest_bytes)?;
-    write_if_changed(&opts.sbom_out, sbom_json.as_bytes())?;
-    write_if_changed(&opts.component_data, component_src.as_bytes())?;
+    write_if_changed(&opts.manifest_out, &manifest_bytes, opts.durability)?;
+    write_if_changed(&opts.sbom_out, sbom_json.as_bytes(), opts.durability)?;
+    write_if_changed(
+        &opts.component_data,
+        component_src.as_bytes(),
+        opts.durability,
+    )?;
 
-    embed::compile_component(&opts.component_data, &opts.component_out)?;
+    let compilation = match (&component_cache, cached) {
+        (Some((cache, key)), Some(entry)) => {
+            info!(key = %key, "pack component unchanged; reusing cached build");
+            cache.restore(key, &opts.component_out, opts.durability)?;
+            entry.notes.map(|notes| Compilation {
+                toolchain: entry.toolchain,
+                notes,
+            })
+        }
+        _ => {
+            let compilation = compile_component(opts)?;
+            if let Some((cache, key)) = &component_cache {
+                let entry = CacheEntry {
+                    created_at: created_at.clone(),
+                    toolchain: compilation.as_ref().and_then(|c| c.toolchain.clone()),
+                    notes: compilation.as_ref().map(|c| c.notes.clone()),
+                };
+                if let Err(err) = cache.store(key, &opts.component_out, &entry) {
+                    warn!(%err, "failed to cache the pack component");
+                }
+            }
+            compilation
+        }
+    };
+    if opts.assert_no_wasi {
+        wasi::assert_no_wasi(&opts.component_out)?;
+        info!("pack component imports no WASI filesystem or clock interfaces");
+    }
 
     maybe_build_gtpack(
         opts,
         &spec_bundle,
-        &flows,
-        &templates,
         &pack_version,
-        &mcp_components,
+        &GtpackInputs {
+            flows: &flows,
+            templates: &templates,
+            media: &media_assets,
+            readme: readme.as_deref(),
+            mcp_components: &mcp_components,
+            imports_resolved: &pack_manifest.imports_resolved,
+            permissions: pack_manifest.permissions.as_ref(),
+            locales: locales.as_ref(),
+            created_at: &created_at,
+            reproducible,
+            compilation: compilation.as_ref(),
+            profile: profile.as_ref(),
+        },
     )?;
 
     info!("build complete");
     Ok(())
 }
 
+/// Resolves the imports against `pack.lock`, then writes the lock back when
+/// the current inputs changed it, or fails under `--locked`.
+fn update_lock(
+    opts: &BuildOptions,
+    catalog: &InterfaceCatalog,
+    spec_bundle: &manifest::SpecBundle,
+    templates: &[TemplateAsset],
+) -> Result<Vec<ResolvedImport>> {
+    let lock = PackLock::load(&opts.pack_dir)?;
+    if opts.locked && lock.is_none() {
+        bail!(
+            "--locked requires {} in {}",
+            LOCK_FILE,
+            opts.pack_dir.display()
+        );
+    }
+    let updated = PackLock::current(
+        &opts.pack_dir,
+        spec_bundle,
+        templates,
+        catalog,
+        lock.as_ref(),
+    )?;
+    let changes = lock
+        .unwrap_or_else(|| PackLock::new(Vec::new()))
+        .changes(&updated);
+    if changes.is_empty() {
+        return Ok(updated.imports);
+    }
+    if opts.locked {
+        bail!(
+            "{} is out of date; rerun without --locked to update it:\n  {}",
+            LOCK_FILE,
+            changes.join("\n  ")
+        );
+    }
+    if !opts.dry_run {
+        updated.write(&opts.pack_dir, opts.durability)?;
+        info!(changes = ?changes, "updated {LOCK_FILE}");
+    }
+    Ok(updated.imports)
+}
+
+/// Provenance details of a component compiled in a builder image or remotely.
+struct Compilation {
+    toolchain: Option<String>,
+    notes: String,
+}
+
+/// The build cache and key for the component, unless it is prebuilt or the
+/// build writes nothing. `manifest` must not carry an unpinned timestamp.
+fn component_cache(
+    opts: &BuildOptions,
+    manifest: &manifest::PackManifest,
+) -> Result<Option<(BuildCache, String)>> {
+    if opts.dry_run || opts.prebuilt_component.is_some() {
+        return Ok(None);
+    }
+    let mut mode = match (&opts.remote_build, &opts.hermetic) {
+        (Some(remote), _) => format!("remote:{}", remote.url),
+        (None, Some(hermetic)) => format!("hermetic:{}", hermetic.image),
+        (None, None) => "host".to_string(),
+    };
+    if opts.assert_no_wasi {
+        mode.push_str("+no-wasi");
+    }
+    let key = BuildCache::key(manifest, &mode)?;
+    Ok(Some((BuildCache::new(&opts.pack_dir), key)))
+}
+
+fn compile_component(opts: &BuildOptions) -> Result<Option<Compilation>> {
+    Ok(match (&opts.prebuilt_component, &opts.remote_build) {
+        (Some(prebuilt), _) => {
+            info!(wasm = %prebuilt.wasm.display(), "using prebuilt pack component");
+            atomic::copy(&prebuilt.wasm, &opts.component_out, opts.durability)?;
+            None
+        }
+        (None, Some(remote_build)) => match remote::compile_component(
+            &opts.component_data,
+            &opts.component_out,
+            remote_build,
+            opts.assert_no_wasi,
+            opts.durability,
+        )? {
+            RemoteOutcome::Built(report) => Some(Compilation {
+                toolchain: report.toolchain,
+                notes: format!("remote build via {}", remote_build.url),
+            }),
+            RemoteOutcome::Unreachable(reason) => {
+                warn!(url = %remote_build.url, %reason, "build service unreachable; compiling locally");
+                compile_locally(opts)?
+            }
+        },
+        (None, None) => compile_locally(opts)?,
+    })
+}
+
+fn compile_locally(opts: &BuildOptions) -> Result<Option<Compilation>> {
+    match &opts.hermetic {
+        Some(hermetic) => {
+            let image = hermetic::compile_component(
+                &opts.pack_dir,
+                &opts.component_data,
+                &opts.component_out,
+                hermetic,
+                opts.assert_no_wasi,
+                pinned_timestamp(opts)?.map(OffsetDateTime::unix_timestamp),
+                opts.durability,
+            )?;
+            Ok(Some(Compilation {
+                toolchain: Some(image),
+                notes: "hermetic build".to_string(),
+            }))
+        }
+        None => {
+            embed::compile_component(
+                &opts.component_data,
+                &opts.component_out,
+                opts.assert_no_wasi,
+                opts.durability,
+            )?;
+            Ok(None)
+        }
+    }
+}
+
+/// The build time fixed by the options or `SOURCE_DATE_EPOCH`, if any.
+fn pinned_timestamp(opts: &BuildOptions) -> Result<Option<OffsetDateTime>> {
+    if let Some(created_at) = opts.created_at {
+        return Ok(Some(created_at));
+    }
+    match std::env::var(SOURCE_DATE_EPOCH) {
+        Ok(raw) => {
+            let seconds: i64 = raw
+                .trim()
+                .parse()
+                .with_context(|| format!("{SOURCE_DATE_EPOCH}={raw} is not a Unix timestamp"))?;
+            OffsetDateTime::from_unix_timestamp(seconds)
+                .map(Some)
+                .with_context(|| format!("{SOURCE_DATE_EPOCH}={raw} is out of range"))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Parses a `--timestamp` value: RFC 3339, or seconds since the Unix epoch
+/// as in `SOURCE_DATE_EPOCH`.
+pub fn parse_timestamp(value: &str) -> Result<OffsetDateTime, String> {
+    if let Ok(seconds) = value.trim().parse::<i64>() {
+        return OffsetDateTime::from_unix_timestamp(seconds)
+            .map_err(|err| format!("`{value}` is out of range: {err}"));
+    }
+    OffsetDateTime::parse(value, &Rfc3339).map_err(|err| {
+        format!("`{value}` is neither an RFC 3339 timestamp nor Unix seconds: {err}")
+    })
+}
+
+fn format_timestamp(timestamp: OffsetDateTime) -> Result<String> {
+    timestamp
+        .format(&Rfc3339)
+        .context("failed to format build timestamp")
+}
+
+fn packc_version() -> Version {
+    Version::parse(env!("CARGO_PKG_VERSION")).expect("packc version is valid semver")
+}
+
+/// Spec embedded in the `.gtpack`, with flow and media paths rewritten to the
+/// layout `greentic-pack extract` produces, so an extracted archive rebuilds
+/// without edits.
+fn archive_spec(spec: &PackSpec, flows: &[FlowAsset]) -> Result<String> {
+    let mut spec = spec.clone();
+    spec.flow_files = flows
+        .iter()
+        .map(|flow| format!("flows/{}/flow.ygtc", flow.bundle.id))
+        .collect();
+    spec.media = spec.media.as_ref().map(media::archive_section);
+    serde_yaml_bw::to_string(&spec).context("failed to serialise embedded pack spec")
+}
+
 fn normalize(path: PathBuf) -> PathBuf {
     if path.is_absolute() {
         path
@@ -120,12 +523,7 @@ fn normalize(path: PathBuf) -> PathBuf {
     }
 }
 
-fn write_if_changed(path: &Path, contents: &[u8]) -> Result<()> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("failed to create directory {}", parent.display()))?;
-    }
-
+fn write_if_changed(path: &Path, contents: &[u8], durability: Durability) -> Result<()> {
     let mut needs_write = true;
     if let Ok(current) = fs::read(path)
         && current == contents
@@ -134,7 +532,7 @@ fn write_if_changed(path: &Path, contents: &[u8]) -> Result<()> {
     }
 
     if needs_write {
-        fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))?;
+        atomic::write(path, contents, durability)?;
         info!(path = %path.display(), "wrote file");
     } else {
         debug!(path = %path.display(), "unchanged");
@@ -143,14 +541,46 @@ fn write_if_changed(path: &Path, contents: &[u8]) -> Result<()> {
     Ok(())
 }
 
+/// Pack contents collected by `run` that end up inside the `.gtpack` archive.
+struct GtpackInputs<'a> {
+    flows: &'a [FlowAsset],
+    templates: &'a [TemplateAsset],
+    media: &'a [MediaAsset],
+    readme: Option<&'a str>,
+    mcp_components: &'a [mcp::ComposedMcpComponent],
+    imports_resolved: &'a [ResolvedImport],
+    permissions: Option<&'a PermissionSummary>,
+    /// `locales` of the spec with every template variant recorded.
+    locales: Option<&'a LocalesSection>,
+    created_at: &'a str,
+    /// Whether `created_at` was pinned, so the archive must be reproducible.
+    reproducible: bool,
+    /// How the component was compiled, when not with the host toolchain.
+    compilation: Option<&'a Compilation>,
+    profile: Option<&'a ProfileGuide>,
+}
+
 fn maybe_build_gtpack(
     opts: &BuildOptions,
     spec_bundle: &manifest::SpecBundle,
-    flows: &[FlowAsset],
-    templates: &[TemplateAsset],
     pack_version: &Version,
-    mcp_components: &[mcp::ComposedMcpComponent],
+    inputs: &GtpackInputs<'_>,
 ) -> Result<()> {
+    let GtpackInputs {
+        flows,
+        templates,
+        media: media_assets,
+        readme,
+        mcp_components,
+        imports_resolved,
+        permissions,
+        locales,
+        created_at,
+        reproducible,
+        compilation,
+        profile,
+    } = *inputs;
+
     if opts.dry_run {
         info!("dry-run requested; skipping .gtpack generation");
         return Ok(());
@@ -162,10 +592,6 @@ fn maybe_build_gtpack(
 
     info!(gtpack_out = %gtpack_path.display(), "packaging .gtpack archive");
 
-    let created_at = OffsetDateTime::now_utc()
-        .format(&Rfc3339)
-        .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string());
-
     let entry_flows = if spec_bundle.spec.entry_flows.is_empty() {
         flows.iter().map(|flow| flow.bundle.id.clone()).collect()
     } else {
@@ -178,12 +604,17 @@ fn maybe_build_gtpack(
     {
         annotations.insert(
             "imports_required".to_string(),
+            serde_json::to_value(&spec_bundle.spec.imports_required)?,
+        );
+    }
+
+    if !imports_resolved.is_empty() {
+        annotations.insert(
+            "imports_resolved".to_string(),
             JsonValue::Array(
-                spec_bundle
-                    .spec
-                    .imports_required
+                imports_resolved
                     .iter()
-                    .map(|entry| JsonValue::String(entry.clone()))
+                    .map(|entry| JsonValue::String(entry.to_string()))
                     .collect(),
             ),
         );
@@ -194,8 +625,9 @@ fn maybe_build_gtpack(
         .imports_required
         .iter()
         .map(|entry| ImportRef {
-            pack_id: entry.clone(),
+            pack_id: entry.to_string(),
             version_req: "*".into(),
+            optional: entry.optional,
         })
         .collect();
 
@@ -216,27 +648,53 @@ fn maybe_build_gtpack(
         homepage: spec_bundle.spec.homepage.clone(),
         support: spec_bundle.spec.support.clone(),
         vendor: spec_bundle.spec.vendor.clone(),
+        tags: spec_bundle.spec.tags.clone(),
+        categories: spec_bundle.spec.categories.clone(),
         imports,
         entry_flows,
-        created_at_utc: created_at.clone(),
+        created_at_utc: created_at.to_string(),
         events: spec_bundle.spec.events.clone(),
         repo: spec_bundle.spec.repo.clone(),
         messaging: spec_bundle.spec.messaging.clone(),
         interfaces: spec_bundle.spec.interfaces.clone(),
+        media: spec_bundle.spec.media.as_ref().map(media::archive_section),
+        config: spec_bundle.spec.config.clone(),
+        config_schema: spec_bundle.spec.settings_schema(),
+        config_defaults: spec_bundle.spec.config_defaults.clone(),
+        lifecycle: spec_bundle.spec.lifecycle.clone(),
+        quotas: spec_bundle.spec.quotas.clone(),
+        limits: spec_bundle.spec.limits.clone(),
+        dispatch: spec_bundle.spec.dispatch.clone(),
+        qos: spec_bundle.spec.qos.clone(),
+        capabilities: spec_bundle.spec.capabilities.clone(),
+        secrets: spec_bundle.spec.secrets.clone(),
+        metrics: spec_bundle.spec.metrics.clone(),
+        schedule: spec_bundle.spec.schedule.clone(),
+        classification: spec_bundle.spec.classification.clone(),
+        locales: locales.cloned(),
+        data_handling: spec_bundle
+            .spec
+            .classification
+            .as_ref()
+            .map(ClassificationSection::summarize),
+        permissions: permissions.cloned(),
         annotations,
     };
 
-    let mut builder = PackBuilder::new(meta);
+    let mut builder = PackBuilder::new(meta).with_durability(opts.durability);
     for flow in flows {
         builder = builder.with_flow(flow.bundle.clone());
     }
 
-    let component_version = Version::parse(env!("CARGO_PKG_VERSION"))
-        .context("failed to parse package version for component metadata")?;
+    let component_version = opts
+        .prebuilt_component
+        .as_ref()
+        .map(|prebuilt| prebuilt.version.clone())
+        .unwrap_or_else(packc_version);
     let component = ComponentArtifact {
         name: "pack_component".into(),
         version: component_version,
-        wasm_path: opts.component_out.clone(),
+        wasm: WasmSource::Path(opts.component_out.clone()),
         schema_json: None,
         manifest_json: None,
         capabilities: None,
@@ -249,7 +707,7 @@ fn maybe_build_gtpack(
         builder = builder.with_component(ComponentArtifact {
             name: mcp.id.clone(),
             version: mcp.version.clone(),
-            wasm_path: mcp.artifact_path.clone(),
+            wasm: WasmSource::Path(mcp.artifact_path.clone()),
             schema_json: None,
             manifest_json: None,
             capabilities: None,
@@ -262,22 +720,62 @@ fn maybe_build_gtpack(
         builder = builder.with_asset_bytes(template.logical_path.clone(), template.bytes.clone());
     }
 
-    let provenance = Provenance {
+    for (locale, bytes) in permissions::bundle_files(&opts.pack_dir)? {
+        builder = builder.with_i18n_bundle(locale, bytes);
+    }
+
+    for asset in media_assets {
+        builder = builder.with_media_bytes(
+            media::media_relative(&asset.media.path).to_string(),
+            asset.bytes.clone(),
+        );
+    }
+
+    if let Some(readme) = readme {
+        builder = builder.with_readme(readme);
+    }
+
+    if let Some(guide) = profile {
+        for flow in &guide.eager_flows {
+            builder = builder.with_eager_flow(flow.clone());
+        }
+    }
+
+    builder = builder.with_source_spec(archive_spec(&spec_bundle.spec, flows)?);
+
+    let provenance = opts.provenance.clone().unwrap_or_else(|| Provenance {
         builder: format!("packc@{}", env!("CARGO_PKG_VERSION")),
         git_commit: None,
         git_repo: None,
-        toolchain: None,
-        built_at_utc: created_at,
+        toolchain: compilation.and_then(|c| c.toolchain.clone()),
+        built_at_utc: created_at.to_string(),
         host: None,
-        notes: None,
-    };
+        notes: compilation.map(|c| c.notes.clone()),
+        profile_blake3: profile.map(|guide| guide.profile_blake3.clone()),
+    });
 
     builder = builder
         .with_provenance(provenance)
         .with_signing(Signing::Dev);
+    if reproducible {
+        builder = builder.with_timestamp(created_at);
+    }
 
-    builder.build(gtpack_path)?;
-
+    let result = builder
+        .with_manifest_encoding(opts.manifest_encoding)
+        .build(gtpack_path)?;
+
+    let sizes = result.manifest_sizes;
+    match sizes.zstd {
+        Some(zstd) => info!(
+            encoding = ?opts.manifest_encoding,
+            cbor_bytes = sizes.cbor,
+            zstd_bytes = zstd,
+            saved_percent = 100 - (zstd * 100 / sizes.cbor.max(1)).min(100),
+            "manifest sizes"
+        ),
+        None => info!(cbor_bytes = sizes.cbor, "manifest sizes"),
+    }
     info!(gtpack_out = %gtpack_path.display(), "gtpack archive ready");
     Ok(())
 }
@@ -0,0 +1,478 @@
+//! Content-addressed cache of compiled pack components.
+//!
+//! Compiling `pack_component` dominates `packc build`, yet its only
+//! pack-specific input is the generated `data.rs`, which is fully determined
+//! by the manifest: flow and template digests, the index metadata, and the
+//! build timestamp. Entries live under `<pack>/.packc/cache/components/<key>/`
+//! and are keyed by a SHA-256 over the manifest with its timestamp cleared,
+//! the packc version, and how the component was compiled. A hit reuses the
+//! entry's timestamp so the manifest, SBOM, and component stay consistent
+//! with each other. The cache is held to the `components` quota of
+//! [`crate::cache`], least recently used entries first.
+//!
+//! CI runners can share entries through artifact storage: [`BuildCache::export`]
+//! writes them to a zip bundle whose `index.json` lists every entry with the
+//! SHA-256 of its component and is signed with the CI key, and
+//! [`BuildCache::import`] only adds entries once the index signature verifies
+//! against a trusted key and every component matches its digest. A bundle
+//! from anyone else, or a component swapped after signing, is rejected before
+//! anything reaches the cache.
+
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use base64::Engine as _;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use greentic_pack::atomic::{self, Durability};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::cache::{self, CacheKind};
+use crate::manifest::{self, PackManifest};
+use crate::signing::TrustStore;
+use crate::signing::keys::PrivateKey;
+
+const COMPONENT_FILE: &str = "component.wasm";
+const ENTRY_FILE: &str = "entry.json";
+
+/// Format of exported cache bundles.
+pub const BUNDLE_FORMAT: &str = "greentic-build-cache-v1";
+const BUNDLE_INDEX: &str = "index.json";
+const BUNDLE_SIGNATURE: &str = "index.sig.json";
+
+/// Metadata stored next to a cached component.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CacheEntry {
+    /// RFC 3339 build timestamp embedded in the cached component.
+    pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub toolchain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// An entry of a cache bundle.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BundleEntry {
+    pub key: String,
+    /// `sha256:<hex>` of the component.
+    pub component_digest: String,
+    pub size: u64,
+    #[serde(flatten)]
+    pub entry: CacheEntry,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleIndex {
+    format: String,
+    packc_version: String,
+    entries: Vec<BundleEntry>,
+}
+
+/// Signature over the exact bytes of a bundle's `index.json`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BundleSignature {
+    alg: String,
+    key_id: String,
+    sig: String,
+}
+
+/// Outcome of importing a cache bundle.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    /// Key id the bundle was signed with.
+    pub key_id: String,
+    /// Name of that key in the trust store, if it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    pub imported: Vec<String>,
+    /// Entries the cache already held.
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BuildCache {
+    dir: PathBuf,
+}
+
+impl BuildCache {
+    pub fn new(pack_dir: &Path) -> Self {
+        Self {
+            dir: pack_dir.join(".packc").join("cache").join("components"),
+        }
+    }
+
+    /// Where the entries live.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Cache key of a build. `manifest` must not carry a timestamp unless the
+    /// build pins one; `mode` names the compiler, e.g. the hermetic image.
+    pub fn key(manifest: &PackManifest, mode: &str) -> Result<String> {
+        let mut hasher = Sha256::new();
+        hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+        hasher.update([0]);
+        hasher.update(mode.as_bytes());
+        hasher.update([0]);
+        hasher.update(manifest::encode_manifest(manifest)?);
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// The cached entry for `key`, if both its metadata and component exist.
+    pub fn lookup(&self, key: &str) -> Option<CacheEntry> {
+        let dir = self.dir.join(key);
+        if !dir.join(COMPONENT_FILE).is_file() {
+            return None;
+        }
+        let entry = fs::read(dir.join(ENTRY_FILE)).ok()?;
+        match serde_json::from_slice(&entry) {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                debug!(key, %err, "ignoring unreadable build cache entry");
+                None
+            }
+        }
+    }
+
+    /// Copies the cached component for `key` to `out`.
+    pub fn restore(&self, key: &str, out: &Path, durability: Durability) -> Result<()> {
+        let dir = self.dir.join(key);
+        atomic::copy(&dir.join(COMPONENT_FILE), out, durability)?;
+        cache::touch(&dir.join(ENTRY_FILE))
+    }
+
+    /// Stores `component` under `key` and evicts least recently used entries
+    /// beyond the cache's quota.
+    pub fn store(&self, key: &str, component: &Path, entry: &CacheEntry) -> Result<()> {
+        let dir = self.dir.join(key);
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create directory {}", dir.display()))?;
+        atomic::copy(component, &dir.join(COMPONENT_FILE), Durability::Buffered)?;
+        self.finish_store(&dir, entry)
+    }
+
+    fn store_bytes(&self, key: &str, component: &[u8], entry: &CacheEntry) -> Result<()> {
+        let dir = self.dir.join(key);
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create directory {}", dir.display()))?;
+        atomic::write(&dir.join(COMPONENT_FILE), component, Durability::Buffered)?;
+        self.finish_store(&dir, entry)
+    }
+
+    fn finish_store(&self, dir: &Path, entry: &CacheEntry) -> Result<()> {
+        // Written last: an entry without metadata is never looked up.
+        atomic::write(
+            &dir.join(ENTRY_FILE),
+            &serde_json::to_vec_pretty(entry)?,
+            Durability::Buffered,
+        )?;
+        cache::enforce_configured(CacheKind::Components, &self.dir)?;
+        Ok(())
+    }
+
+    /// Keys of the complete entries, sorted.
+    pub fn keys(&self) -> Result<Vec<String>> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut keys: Vec<String> = fs::read_dir(&self.dir)
+            .with_context(|| format!("failed to read {}", self.dir.display()))?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|key| self.lookup(key).is_some())
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    /// Writes every entry to a bundle at `out`, signed with `private_key_pem`.
+    pub fn export(&self, out: &Path, private_key_pem: &str) -> Result<Vec<BundleEntry>> {
+        let key = PrivateKey::from_pem(private_key_pem)?;
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        let mut entries = Vec::new();
+        for cache_key in self.keys()? {
+            let Some(entry) = self.lookup(&cache_key) else {
+                continue;
+            };
+            let path = self.dir.join(&cache_key).join(COMPONENT_FILE);
+            let component =
+                fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+            zip.start_file(bundle_component_path(&cache_key), options)?;
+            zip.write_all(&component)?;
+            entries.push(BundleEntry {
+                key: cache_key,
+                component_digest: sha256_digest(&component),
+                size: component.len() as u64,
+                entry,
+            });
+        }
+
+        let index = serde_json::to_vec_pretty(&BundleIndex {
+            format: BUNDLE_FORMAT.to_string(),
+            packc_version: env!("CARGO_PKG_VERSION").to_string(),
+            entries: entries.clone(),
+        })?;
+        let signature = BundleSignature {
+            alg: key.algorithm().to_string(),
+            key_id: key.key_id(),
+            sig: URL_SAFE_NO_PAD.encode(key.sign(&index)?),
+        };
+        zip.start_file(BUNDLE_INDEX, options)?;
+        zip.write_all(&index)?;
+        zip.start_file(BUNDLE_SIGNATURE, options)?;
+        zip.write_all(&serde_json::to_vec_pretty(&signature)?)?;
+        let bytes = zip.finish()?.into_inner();
+        atomic::write(out, &bytes, Durability::Buffered)?;
+        Ok(entries)
+    }
+
+    /// Adds the entries of the bundle at `bundle`, which must be signed by a
+    /// key in `trusted`. Nothing is stored unless the signature and every
+    /// component verify.
+    pub fn import(&self, bundle: &Path, trusted: &TrustStore) -> Result<ImportReport> {
+        let file = fs::File::open(bundle)
+            .with_context(|| format!("failed to open {}", bundle.display()))?;
+        let mut zip = ZipArchive::new(file)
+            .with_context(|| format!("{} is not a build cache bundle", bundle.display()))?;
+        let index_bytes = read_zip_file(&mut zip, BUNDLE_INDEX, None)?;
+        let signature: BundleSignature =
+            serde_json::from_slice(&read_zip_file(&mut zip, BUNDLE_SIGNATURE, None)?)
+                .with_context(|| format!("{BUNDLE_SIGNATURE} is not valid"))?;
+
+        let Some(public_key) = trusted.public_key(&signature.key_id) else {
+            bail!(
+                "bundle is signed by untrusted key {}; refusing to import it",
+                signature.key_id
+            );
+        };
+        if public_key.algorithm() != signature.alg {
+            bail!(
+                "bundle signature uses {} but key {} is {}",
+                signature.alg,
+                signature.key_id,
+                public_key.algorithm()
+            );
+        }
+        let raw_signature = URL_SAFE_NO_PAD
+            .decode(&signature.sig)
+            .context("bundle signature is not valid base64")?;
+        public_key
+            .verify(&index_bytes, &raw_signature, &signature.key_id)
+            .context("bundle index does not match its signature")?;
+
+        let index: BundleIndex =
+            serde_json::from_slice(&index_bytes).context("index.json is not valid")?;
+        if index.format != BUNDLE_FORMAT {
+            bail!(
+                "unsupported build cache bundle format `{}` (expected `{BUNDLE_FORMAT}`)",
+                index.format
+            );
+        }
+
+        let mut verified = Vec::new();
+        for entry in index.entries {
+            if !is_cache_key(&entry.key) {
+                bail!("bundle entry `{}` is not a valid cache key", entry.key);
+            }
+            let component = read_zip_file(
+                &mut zip,
+                &bundle_component_path(&entry.key),
+                Some(entry.size),
+            )?;
+            let digest = sha256_digest(&component);
+            if digest != entry.component_digest {
+                bail!(
+                    "component of entry {} does not match the signed digest: expected {}, found {digest}",
+                    entry.key,
+                    entry.component_digest
+                );
+            }
+            verified.push((entry, component));
+        }
+
+        let mut report = ImportReport {
+            signer: trusted
+                .get(&signature.key_id)
+                .and_then(|key| key.name.clone()),
+            key_id: signature.key_id,
+            imported: Vec::new(),
+            skipped: Vec::new(),
+        };
+        for (entry, component) in verified {
+            if self.lookup(&entry.key).is_some() {
+                report.skipped.push(entry.key);
+                continue;
+            }
+            self.store_bytes(&entry.key, &component, &entry.entry)?;
+            report.imported.push(entry.key);
+        }
+        Ok(report)
+    }
+}
+
+fn bundle_component_path(key: &str) -> String {
+    format!("components/{key}.wasm")
+}
+
+/// Keys are hex SHA-256 digests; anything else could escape the cache
+/// directory.
+fn is_cache_key(key: &str) -> bool {
+    key.len() == 64
+        && key
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+}
+
+fn sha256_digest(bytes: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(bytes)))
+}
+
+/// Reads `name` from `zip`, refusing more than `size` bytes when given.
+fn read_zip_file<R: Read + std::io::Seek>(
+    zip: &mut ZipArchive<R>,
+    name: &str,
+    size: Option<u64>,
+) -> Result<Vec<u8>> {
+    let file = zip
+        .by_name(name)
+        .with_context(|| format!("bundle has no {name}"))?;
+    let limit = size.unwrap_or(file.size());
+    let mut bytes = Vec::new();
+    file.take(limit + 1)
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("failed to read {name}"))?;
+    if bytes.len() as u64 > limit {
+        bail!("{name} is larger than the bundle index says");
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn manifest(template_digest: &str) -> PackManifest {
+        serde_json::from_value(serde_json::json!({
+            "pack_id": "demo",
+            "version": "0.1.0",
+            "created_at": "",
+            "flows": [],
+            "templates": [{
+                "logical_path": "templates/a.hbs",
+                "digest": template_digest,
+                "size": 1,
+            }],
+            "imports_required": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn stores_and_restores_by_input_digest() {
+        let temp = tempdir().unwrap();
+        let cache = BuildCache::new(temp.path());
+        let key = BuildCache::key(&manifest("sha256:aa"), "host").unwrap();
+        assert_eq!(
+            key,
+            BuildCache::key(&manifest("sha256:aa"), "host").unwrap()
+        );
+        assert_ne!(
+            key,
+            BuildCache::key(&manifest("sha256:bb"), "host").unwrap()
+        );
+        assert_ne!(
+            key,
+            BuildCache::key(&manifest("sha256:aa"), "remote").unwrap()
+        );
+        assert_eq!(cache.lookup(&key), None);
+
+        let wasm = temp.path().join("pack.wasm");
+        fs::write(&wasm, b"\0asm").unwrap();
+        let entry = CacheEntry {
+            created_at: "2024-01-01T00:00:00Z".into(),
+            toolchain: None,
+            notes: None,
+        };
+        cache.store(&key, &wasm, &entry).unwrap();
+        assert_eq!(cache.lookup(&key), Some(entry));
+
+        let out = temp.path().join("restored.wasm");
+        cache.restore(&key, &out, Durability::Buffered).unwrap();
+        assert_eq!(fs::read(out).unwrap(), b"\0asm");
+    }
+
+    #[test]
+    fn bundles_import_only_when_signed_by_a_trusted_key() {
+        use ed25519_dalek::SigningKey;
+        use ed25519_dalek::pkcs8::{EncodePrivateKey, EncodePublicKey};
+        use pkcs8::LineEnding;
+
+        let ci_key = SigningKey::from_bytes(&[7; 32]);
+        let ci_private = ci_key.to_pkcs8_pem(LineEnding::LF).unwrap();
+        let ci_public = ci_key
+            .verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .unwrap();
+        let mut trusted = TrustStore::new();
+        trusted.insert(&ci_public, Some("ci".into())).unwrap();
+
+        let runner_a = tempdir().unwrap();
+        let cache = BuildCache::new(runner_a.path());
+        let key = BuildCache::key(&manifest("sha256:aa"), "host").unwrap();
+        let wasm = runner_a.path().join("pack.wasm");
+        fs::write(&wasm, b"\0asm").unwrap();
+        let entry = CacheEntry {
+            created_at: "2024-01-01T00:00:00Z".into(),
+            toolchain: None,
+            notes: None,
+        };
+        cache.store(&key, &wasm, &entry).unwrap();
+        let bundle = runner_a.path().join("cache.zip");
+        let exported = cache.export(&bundle, &ci_private).unwrap();
+        assert_eq!(exported.len(), 1);
+
+        let runner_b = tempdir().unwrap();
+        let shared = BuildCache::new(runner_b.path());
+        let err = shared.import(&bundle, &TrustStore::new()).unwrap_err();
+        assert!(err.to_string().contains("untrusted key"), "{err}");
+        assert_eq!(shared.lookup(&key), None);
+
+        let report = shared.import(&bundle, &trusted).unwrap();
+        assert_eq!(report.imported, std::slice::from_ref(&key));
+        assert_eq!(report.signer.as_deref(), Some("ci"));
+        assert_eq!(shared.lookup(&key), Some(entry));
+        let again = shared.import(&bundle, &trusted).unwrap();
+        assert_eq!(again.skipped, std::slice::from_ref(&key));
+
+        // A component swapped after signing is caught by its digest.
+        let mut zip = ZipArchive::new(fs::File::open(&bundle).unwrap()).unwrap();
+        let index = read_zip_file(&mut zip, BUNDLE_INDEX, None).unwrap();
+        let sig = read_zip_file(&mut zip, BUNDLE_SIGNATURE, None).unwrap();
+        let mut poisoned = ZipWriter::new(fs::File::create(&bundle).unwrap());
+        for (name, bytes) in [
+            (bundle_component_path(&key), &b"\0evl"[..]),
+            (BUNDLE_INDEX.to_string(), &index[..]),
+            (BUNDLE_SIGNATURE.to_string(), &sig[..]),
+        ] {
+            poisoned
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            poisoned.write_all(bytes).unwrap();
+        }
+        poisoned.finish().unwrap();
+        let runner_c = tempdir().unwrap();
+        let err = BuildCache::new(runner_c.path())
+            .import(&bundle, &trusted)
+            .unwrap_err();
+        assert!(err.to_string().contains("signed digest"), "{err}");
+    }
+}
@@ -0,0 +1,366 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tracing::{debug, info, warn};
+
+/// Lock file created under `<pack>/.packc/` while a build or sign is running.
+pub const LOCK_FILE: &str = "build.lock";
+
+/// Locks older than this are treated as abandoned even when the owner's
+/// liveness cannot be checked (e.g. the lock was taken on another host).
+pub const STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Numbers the names locks are renamed to while they are checked.
+static ASIDE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// How to behave when another process already holds the lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaitPolicy {
+    /// Fail immediately, naming the competing process.
+    #[default]
+    NoWait,
+    /// Poll until the lock is released or becomes stale.
+    Wait,
+}
+
+impl WaitPolicy {
+    pub fn from_flags(wait: bool) -> Self {
+        if wait { Self::Wait } else { Self::NoWait }
+    }
+}
+
+/// Identity of the process holding a pack directory lock.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LockOwner {
+    pub pid: u32,
+    pub host: String,
+    pub command: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub acquired_at: OffsetDateTime,
+}
+
+impl LockOwner {
+    fn current(command: &str) -> Self {
+        Self {
+            pid: std::process::id(),
+            host: hostname(),
+            command: command.to_string(),
+            acquired_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        let since = self
+            .acquired_at
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap_or_else(|_| self.acquired_at.to_string());
+        format!(
+            "`{}` (pid {} on {}, since {})",
+            self.command, self.pid, self.host, since
+        )
+    }
+
+    /// Whether `other` is the same holder: one process and one acquisition.
+    fn same_holder(&self, other: &LockOwner) -> bool {
+        self.pid == other.pid && self.host == other.host && self.acquired_at == other.acquired_at
+    }
+
+    /// A lock is stale when its owner is known to be gone, or when it has
+    /// outlived [`STALE_AFTER`].
+    fn is_stale(&self) -> bool {
+        if self.host == hostname()
+            && let Some(alive) = process_alive(self.pid)
+        {
+            return !alive;
+        }
+        let age = OffsetDateTime::now_utc() - self.acquired_at;
+        age > STALE_AFTER
+    }
+}
+
+/// Advisory lock over a pack directory, released when dropped.
+#[derive(Debug)]
+pub struct BuildLock {
+    path: PathBuf,
+    owner: LockOwner,
+}
+
+impl BuildLock {
+    /// Acquires `<pack_dir>/.packc/build.lock` on behalf of `command`.
+    pub fn acquire(pack_dir: &Path, command: &str, policy: WaitPolicy) -> Result<Self> {
+        let dir = pack_dir.join(".packc");
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create directory {}", dir.display()))?;
+        let path = dir.join(LOCK_FILE);
+        let owner = LockOwner::current(command);
+        let mut announced = false;
+
+        loop {
+            match try_create(&path, &owner) {
+                Ok(()) => {
+                    debug!(path = %path.display(), "acquired build lock");
+                    return Ok(Self { path, owner });
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("failed to create lock {}", path.display()));
+                }
+            }
+
+            let Some(holder) = read_owner(&path)? else {
+                // Released between our attempt and the read; try again.
+                continue;
+            };
+
+            if holder.is_stale() {
+                warn!(
+                    path = %path.display(),
+                    holder = %holder.describe(),
+                    "removing stale build lock"
+                );
+                remove_if_held_by(&path, &holder)?;
+                continue;
+            }
+
+            match policy {
+                WaitPolicy::NoWait => bail!(
+                    "pack directory {} is locked by {}; rerun with --wait to queue behind it, \
+                     or delete {} if that process no longer exists",
+                    pack_dir.display(),
+                    holder.describe(),
+                    path.display()
+                ),
+                WaitPolicy::Wait => {
+                    if !announced {
+                        info!(holder = %holder.describe(), "waiting for build lock");
+                        announced = true;
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for BuildLock {
+    fn drop(&mut self) {
+        match remove_if_held_by(&self.path, &self.owner) {
+            Ok(true) => {}
+            Ok(false) => warn!(
+                path = %self.path.display(),
+                "build lock is no longer ours; leaving it in place"
+            ),
+            Err(err) => {
+                warn!(path = %self.path.display(), error = %err, "failed to release build lock")
+            }
+        }
+    }
+}
+
+/// Returns `true` when `rel_path` (relative to the pack root) is the lock file.
+pub fn is_lock_path(rel_path: &Path) -> bool {
+    rel_path == Path::new(".packc").join(LOCK_FILE)
+}
+
+fn try_create(path: &Path, owner: &LockOwner) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    let payload = serde_json::to_vec_pretty(owner).map_err(std::io::Error::other)?;
+    file.write_all(&payload)?;
+    file.sync_all()
+}
+
+fn read_owner(path: &Path) -> Result<Option<LockOwner>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read lock {}", path.display()));
+        }
+    };
+    match serde_json::from_slice(&bytes) {
+        Ok(owner) => Ok(Some(owner)),
+        // A half-written or foreign lock file carries no owner we can check;
+        // fall back to the file's age so it cannot block builds forever.
+        Err(_) => {
+            let modified = fs::metadata(path)
+                .and_then(|meta| meta.modified())
+                .map(OffsetDateTime::from)
+                .unwrap_or_else(|_| OffsetDateTime::now_utc());
+            Ok(Some(LockOwner {
+                pid: 0,
+                host: "unknown host".to_string(),
+                command: "unknown command".to_string(),
+                acquired_at: modified,
+            }))
+        }
+    }
+}
+
+/// Removes the lock at `path` if `expected` still holds it, returning
+/// whether it did. The lock is renamed aside before its owner is read, so a
+/// lock another process took in the meantime is never deleted: it is linked
+/// back into place, unless yet another process has taken the lock since.
+fn remove_if_held_by(path: &Path, expected: &LockOwner) -> Result<bool> {
+    let aside = path.with_file_name(format!(
+        "{LOCK_FILE}.{}-{}",
+        std::process::id(),
+        ASIDE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    match fs::rename(path, &aside) {
+        Ok(()) => {}
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(false),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to move lock {}", path.display()));
+        }
+    }
+    let held = read_owner(&aside)?.is_some_and(|owner| owner.same_holder(expected));
+    if !held {
+        match fs::hard_link(&aside, path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to restore lock {}", path.display()));
+            }
+        }
+    }
+    remove_if_present(&aside)?;
+    Ok(held)
+}
+
+fn remove_if_present(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err).with_context(|| format!("failed to remove lock {}", path.display())),
+    }
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// Liveness check for a local pid; `None` when the platform offers no cheap probe.
+fn process_alive(pid: u32) -> Option<bool> {
+    if cfg!(target_os = "linux") {
+        Some(Path::new("/proc").join(pid.to_string()).exists())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+    use std::sync::atomic::AtomicUsize;
+    use tempfile::tempdir;
+
+    /// A lock left behind by a process that is gone.
+    fn abandoned(dir: &Path) {
+        let owner = LockOwner {
+            pid: u32::MAX,
+            host: hostname(),
+            command: "packc build".into(),
+            acquired_at: OffsetDateTime::now_utc() - (STALE_AFTER + Duration::from_secs(1)),
+        };
+        fs::create_dir_all(dir.join(".packc")).unwrap();
+        fs::write(
+            dir.join(".packc").join(LOCK_FILE),
+            serde_json::to_vec(&owner).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn second_acquire_names_the_holder() {
+        let temp = tempdir().unwrap();
+        let lock = BuildLock::acquire(temp.path(), "packc build", WaitPolicy::NoWait).unwrap();
+        let err = BuildLock::acquire(temp.path(), "packc sign", WaitPolicy::NoWait).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("packc build"), "{message}");
+        assert!(
+            message.contains(&format!("pid {}", std::process::id())),
+            "{message}"
+        );
+
+        let path = lock.path().to_path_buf();
+        drop(lock);
+        assert!(!path.exists(), "lock is released on drop");
+        BuildLock::acquire(temp.path(), "packc sign", WaitPolicy::NoWait).unwrap();
+    }
+
+    #[test]
+    fn stale_lock_is_reclaimed() {
+        let temp = tempdir().unwrap();
+        abandoned(temp.path());
+
+        let lock = BuildLock::acquire(temp.path(), "packc build", WaitPolicy::NoWait).unwrap();
+        let owner = read_owner(lock.path()).unwrap().unwrap();
+        assert_eq!(owner.pid, std::process::id());
+    }
+
+    #[test]
+    fn racing_waiters_reclaim_a_stale_lock_one_at_a_time() {
+        let temp = tempdir().unwrap();
+        let root = temp.path();
+        let holders = &AtomicUsize::new(0);
+        let start = &Barrier::new(2);
+        for _ in 0..10 {
+            abandoned(root);
+            thread::scope(|scope| {
+                for command in ["packc build", "packc sign"] {
+                    scope.spawn(move || {
+                        start.wait();
+                        let lock = BuildLock::acquire(root, command, WaitPolicy::Wait).unwrap();
+                        assert_eq!(
+                            holders.fetch_add(1, Ordering::SeqCst),
+                            0,
+                            "two guards held the lock at once"
+                        );
+                        thread::sleep(Duration::from_millis(5));
+                        holders.fetch_sub(1, Ordering::SeqCst);
+                        drop(lock);
+                    });
+                }
+            });
+            assert!(!root.join(".packc").join(LOCK_FILE).exists());
+        }
+        let leftovers = fs::read_dir(root.join(".packc")).unwrap().count();
+        assert_eq!(leftovers, 0, "no lock is left aside");
+    }
+
+    #[test]
+    fn drop_leaves_a_lock_taken_over_by_another_process() {
+        let temp = tempdir().unwrap();
+        let lock = BuildLock::acquire(temp.path(), "packc build", WaitPolicy::NoWait).unwrap();
+        let path = lock.path().to_path_buf();
+        let other = LockOwner {
+            pid: std::process::id() + 1,
+            ..LockOwner::current("packc sign")
+        };
+        fs::write(&path, serde_json::to_vec(&other).unwrap()).unwrap();
+
+        drop(lock);
+        assert_eq!(read_owner(&path).unwrap(), Some(other));
+    }
+}
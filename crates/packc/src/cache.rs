@@ -0,0 +1,354 @@
+//! Size limits and housekeeping for packc's local caches.
+//!
+//! packc fills several caches as it works: the chunk cache `packc pull`
+//! stores registry chunks in, the build cache hermetic builds mount as their
+//! cargo home and target directory, each pack's compiled component cache,
+//! the MCP adapter cache, and the tools directory `packc tools install`
+//! fills. Each has a size quota, configured in the `[cache]` table of the
+//! user config (`packc.toml`):
+//!
+//! ```toml
+//! [cache.chunks]
+//! max_mb = 2048
+//!
+//! [cache.components]
+//! max_mb = 1024
+//! ```
+//!
+//! When a cache outgrows its quota, the least recently used entries are
+//! evicted. Entries with content that no longer matches its digest are
+//! evicted too, rather than failing the command that found them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::{registry, tools};
+
+/// The `[cache]` table of `packc.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CacheConfig {
+    #[serde(default, skip_serializing_if = "CacheQuota::is_unset")]
+    pub chunks: CacheQuota,
+    #[serde(default, skip_serializing_if = "CacheQuota::is_unset")]
+    pub builds: CacheQuota,
+    #[serde(default, skip_serializing_if = "CacheQuota::is_unset")]
+    pub components: CacheQuota,
+    #[serde(default, skip_serializing_if = "CacheQuota::is_unset")]
+    pub adapters: CacheQuota,
+    #[serde(default, skip_serializing_if = "CacheQuota::is_unset")]
+    pub tools: CacheQuota,
+}
+
+impl CacheConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CacheQuota {
+    /// Size limit in MiB; the cache's default applies when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_mb: Option<u64>,
+}
+
+impl CacheQuota {
+    fn is_unset(&self) -> bool {
+        self.max_mb.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheKind {
+    /// Registry chunks fetched by `packc pull`.
+    Chunks,
+    /// Cargo home and target directory of hermetic builds.
+    Builds,
+    /// Compiled pack components, under each pack's `.packc/cache/components`.
+    Components,
+    /// MCP adapter components.
+    Adapters,
+    /// Native helpers installed by `packc tools install`.
+    Tools,
+}
+
+impl CacheKind {
+    pub const ALL: [CacheKind; 5] = [
+        CacheKind::Chunks,
+        CacheKind::Builds,
+        CacheKind::Components,
+        CacheKind::Adapters,
+        CacheKind::Tools,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CacheKind::Chunks => "chunks",
+            CacheKind::Builds => "builds",
+            CacheKind::Components => "components",
+            CacheKind::Adapters => "adapters",
+            CacheKind::Tools => "tools",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == name)
+    }
+
+    /// Default location of the cache. The components cache has none: each
+    /// pack has its own, see [`BuildCache::dir`](crate::build_cache::BuildCache::dir).
+    pub fn dir(self) -> Option<PathBuf> {
+        match self {
+            CacheKind::Chunks => registry::default_cache(),
+            CacheKind::Builds => tools::cache_root().map(|root| root.join("hermetic")),
+            CacheKind::Components => None,
+            CacheKind::Adapters => tools::cache_root().map(|root| root.join("adapters")),
+            CacheKind::Tools => tools::tools_dir(),
+        }
+    }
+
+    fn default_max_mb(self) -> u64 {
+        match self {
+            CacheKind::Chunks => 2 * 1024,
+            CacheKind::Builds => 10 * 1024,
+            CacheKind::Components => 1024,
+            CacheKind::Adapters => 256,
+            CacheKind::Tools => 2 * 1024,
+        }
+    }
+
+    /// The quota in bytes, from `config` or the default.
+    pub fn quota_bytes(self, config: &CacheConfig) -> u64 {
+        let quota = match self {
+            CacheKind::Chunks => config.chunks,
+            CacheKind::Builds => config.builds,
+            CacheKind::Components => config.components,
+            CacheKind::Adapters => config.adapters,
+            CacheKind::Tools => config.tools,
+        };
+        quota.max_mb.unwrap_or(self.default_max_mb()) * 1024 * 1024
+    }
+}
+
+/// One unit of eviction: a chunk file, a top-level directory of the build
+/// cache (`cargo`, `target`), a compiled component's directory, an adapter
+/// protocol's directory, or a tool's directory.
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub last_used: SystemTime,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct Eviction {
+    pub entries: usize,
+    pub bytes: u64,
+}
+
+/// The entries of the `kind` cache at `dir`, least recently used first.
+pub fn entries(kind: CacheKind, dir: &Path) -> Result<Vec<CacheEntry>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let depth = match kind {
+        CacheKind::Chunks => 2,
+        CacheKind::Builds | CacheKind::Components | CacheKind::Adapters | CacheKind::Tools => 1,
+    };
+    let mut entries = Vec::new();
+    for entry in WalkDir::new(dir).min_depth(depth).max_depth(depth) {
+        let entry = entry.with_context(|| format!("failed to walk {}", dir.display()))?;
+        // Staging directories and the tools index are not entries.
+        let name = entry.file_name().to_string_lossy();
+        if name.starts_with('.') || (kind == CacheKind::Tools && name == tools::INDEX_FILE) {
+            continue;
+        }
+        let (bytes, last_used) = usage(entry.path())?;
+        entries.push(CacheEntry {
+            path: entry.into_path(),
+            bytes,
+            last_used,
+        });
+    }
+    entries.sort_by_key(|entry| entry.last_used);
+    Ok(entries)
+}
+
+/// Total size and newest modification time of the files under `path`.
+fn usage(path: &Path) -> Result<(u64, SystemTime)> {
+    let mut bytes = 0;
+    let mut last_used = SystemTime::UNIX_EPOCH;
+    for entry in WalkDir::new(path) {
+        let entry = entry.with_context(|| format!("failed to walk {}", path.display()))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let metadata = entry
+            .metadata()
+            .with_context(|| format!("failed to stat {}", entry.path().display()))?;
+        bytes += metadata.len();
+        if let Ok(modified) = metadata.modified() {
+            last_used = last_used.max(modified);
+        }
+    }
+    Ok((bytes, last_used))
+}
+
+/// Evicts least recently used entries until the cache fits `quota_bytes`.
+pub fn enforce(kind: CacheKind, dir: &Path, quota_bytes: u64) -> Result<Eviction> {
+    let entries = entries(kind, dir)?;
+    let mut total: u64 = entries.iter().map(|entry| entry.bytes).sum();
+    let mut eviction = Eviction::default();
+    for entry in entries {
+        if total <= quota_bytes {
+            break;
+        }
+        remove(&entry.path)?;
+        total -= entry.bytes;
+        eviction.entries += 1;
+        eviction.bytes += entry.bytes;
+    }
+    Ok(eviction)
+}
+
+/// Removes every entry of the cache.
+pub fn clear(kind: CacheKind, dir: &Path) -> Result<Eviction> {
+    enforce(kind, dir, 0)
+}
+
+/// Evicts entries whose content does not match their digest. Only chunks
+/// carry a digest; build cache entries are checked by cargo itself.
+pub fn verify(kind: CacheKind, dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut evicted = Vec::new();
+    if kind != CacheKind::Chunks {
+        return Ok(evicted);
+    }
+    for entry in entries(kind, dir)? {
+        let name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+        let bytes = fs::read(&entry.path)
+            .with_context(|| format!("failed to read {}", entry.path.display()))?;
+        if blake3::hash(&bytes).to_hex().as_str() != name {
+            remove(&entry.path)?;
+            evicted.push(entry.path);
+        }
+    }
+    Ok(evicted)
+}
+
+/// Applies the configured quota of `kind` to the cache at `dir`, as commands
+/// do after filling it.
+pub fn enforce_configured(kind: CacheKind, dir: &Path) -> Result<Eviction> {
+    let config = crate::usage::load_config()?;
+    let eviction = enforce(kind, dir, kind.quota_bytes(&config.cache))?;
+    if eviction.entries > 0 {
+        tracing::info!(
+            cache = kind.as_str(),
+            entries = eviction.entries,
+            bytes = eviction.bytes,
+            "evicted least recently used cache entries"
+        );
+    }
+    Ok(eviction)
+}
+
+/// Marks the cache entry file at `path` as just used, so eviction keeps it.
+pub fn touch(path: &Path) -> Result<()> {
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(SystemTime::now()))
+        .with_context(|| format!("failed to touch {}", path.display()))
+}
+
+fn remove(path: &Path) -> Result<()> {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    match result {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err).with_context(|| format!("failed to evict {}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used_chunks_and_corrupt_ones() {
+        let temp = tempfile::tempdir().unwrap();
+        let store = greentic_pack::chunking::ChunkStore::new(temp.path());
+        let now = SystemTime::now();
+        let mut digests = Vec::new();
+        for (age, fill) in [(30, b'a'), (10, b'b'), (20, b'c')] {
+            let digest = store.write(&[fill; 1000]).unwrap();
+            let path = temp.path().join(&digest[..2]).join(&digest);
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age))
+                .unwrap();
+            digests.push(digest);
+        }
+
+        let eviction = enforce(CacheKind::Chunks, temp.path(), 1500).unwrap();
+        assert_eq!(
+            eviction,
+            Eviction {
+                entries: 2,
+                bytes: 2000
+            }
+        );
+        assert!(!store.contains(&digests[0]));
+        assert!(store.contains(&digests[1]));
+        assert!(!store.contains(&digests[2]));
+
+        let path = temp.path().join(&digests[1][..2]).join(&digests[1]);
+        fs::write(&path, b"bit rot").unwrap();
+        assert_eq!(verify(CacheKind::Chunks, temp.path()).unwrap(), [path]);
+        assert!(entries(CacheKind::Chunks, temp.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn evicts_whole_component_and_tool_directories() {
+        let temp = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        for (key, age) in [("old", 20), ("new", 10)] {
+            let dir = temp.path().join(key);
+            fs::create_dir_all(&dir).unwrap();
+            for file in ["component.wasm", "entry.json"] {
+                fs::write(dir.join(file), [0; 500]).unwrap();
+                fs::File::options()
+                    .write(true)
+                    .open(dir.join(file))
+                    .unwrap()
+                    .set_modified(now - Duration::from_secs(age))
+                    .unwrap();
+            }
+        }
+        let eviction = enforce(CacheKind::Components, temp.path(), 1000).unwrap();
+        assert_eq!(eviction.entries, 1);
+        assert!(!temp.path().join("old").exists());
+        assert!(temp.path().join("new/component.wasm").exists());
+
+        // The index and staging directories of the tools directory stay.
+        fs::write(temp.path().join(tools::INDEX_FILE), b"{}").unwrap();
+        fs::create_dir_all(temp.path().join(".tmpXYZ")).unwrap();
+        let eviction = clear(CacheKind::Tools, temp.path()).unwrap();
+        assert_eq!(eviction.entries, 1);
+        assert!(temp.path().join(tools::INDEX_FILE).exists());
+        assert!(temp.path().join(".tmpXYZ").exists());
+    }
+}
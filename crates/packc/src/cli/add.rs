@@ -0,0 +1,96 @@
+#![forbid(unsafe_code)]
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use greentic_pack::SigningPolicy;
+use greentic_pack::atomic::{self, Durability};
+use greentic_pack::registry::RegistryClient;
+use semver::VersionReq;
+use serde_json::json;
+
+use crate::http::CurlTransport;
+
+#[derive(Debug, Parser)]
+pub struct AddArgs {
+    /// Pack to fetch, as <PACK_ID> or <PACK_ID>@<VERSION_REQ> (e.g. demo.app@^1.2)
+    #[arg(value_name = "PACK")]
+    pub pack: String,
+
+    /// Base URL of the registry index (http(s):// or file://)
+    #[arg(long, value_name = "URL")]
+    pub index: String,
+
+    /// Directory the archive is written to, as <PACK_ID>-<VERSION>.gtpack
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    pub out_dir: PathBuf,
+
+    /// Consider yanked versions when resolving the version requirement
+    #[arg(long)]
+    pub allow_yanked: bool,
+
+    /// Reject archives signed with the dev certificate
+    #[arg(long)]
+    pub strict: bool,
+}
+
+/// Splits `<PACK_ID>[@<VERSION_REQ>]`; a bare id accepts any version.
+fn parse_pack_req(pack: &str) -> Result<(&str, VersionReq)> {
+    let (pack_id, req) = match pack.split_once('@') {
+        Some((pack_id, req)) => (
+            pack_id,
+            VersionReq::parse(req)
+                .with_context(|| format!("`{req}` is not a semver version requirement"))?,
+        ),
+        None => (pack, VersionReq::STAR),
+    };
+    if pack_id.is_empty() {
+        bail!("`{pack}` must be written as <PACK_ID>[@<VERSION_REQ>]");
+    }
+    Ok((pack_id, req))
+}
+
+pub fn handle(args: AddArgs, json: bool) -> Result<()> {
+    let (pack_id, req) = parse_pack_req(&args.pack)?;
+    let policy = if args.strict {
+        SigningPolicy::Strict
+    } else {
+        SigningPolicy::DevOk
+    };
+    let client = RegistryClient::new(&args.index, Arc::new(CurlTransport));
+    let published = client.resolve(pack_id, &req, args.allow_yanked)?;
+    let download = client.download(pack_id, &published, policy)?;
+
+    let out = args
+        .out_dir
+        .join(format!("{pack_id}-{}.gtpack", download.version));
+    let unchanged = fs::read(&out).is_ok_and(|existing| existing == download.bytes);
+    if !unchanged {
+        fs::create_dir_all(&args.out_dir)
+            .with_context(|| format!("failed to create {}", args.out_dir.display()))?;
+        atomic::write(&out, &download.bytes, Durability::Buffered)?;
+    }
+
+    if json {
+        let payload = json!({
+            "status": if unchanged { "unchanged" } else { "added" },
+            "pack_id": pack_id,
+            "version": download.version.to_string(),
+            "path": out,
+            "size": published.size,
+            "blake3": published.blake3,
+            "yanked": published.yanked,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        let verb = if unchanged { "kept" } else { "added" };
+        println!("{verb} {pack_id}@{} -> {}", download.version, out.display());
+        if published.yanked {
+            println!("  warning: this version was yanked");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,178 @@
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use clap::{Parser, Subcommand};
+use greentic_pack::atomic::{self, Durability};
+use serde_json::json;
+
+use crate::advisory::{self, Advisory, AdvisorySeverity};
+use crate::index;
+
+#[derive(Debug, Subcommand)]
+pub enum AdvisoryCommand {
+    /// Record an advisory against versions of a pack in a search index
+    Add(AddArgs),
+    /// Report installed .gtpack archives that a search index lists as yanked or covers with advisories
+    Check(CheckArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct AddArgs {
+    /// Search index written by `packc index`
+    #[arg(long, value_name = "FILE", default_value = "search-index.json")]
+    pub index: PathBuf,
+
+    /// Identifier of the advisory, unique within the index
+    #[arg(long, value_name = "ID")]
+    pub id: String,
+
+    /// Pack the advisory is about
+    #[arg(long = "pack-id", value_name = "PACK_ID")]
+    pub pack_id: String,
+
+    /// Semver range of the affected versions, e.g. ">=0.2.0, <0.3.1"
+    #[arg(long, value_name = "RANGE")]
+    pub versions: String,
+
+    /// How urgently consumers should upgrade
+    #[arg(long, value_enum)]
+    pub severity: AdvisorySeverity,
+
+    /// What is wrong with the affected versions
+    #[arg(long, value_name = "TEXT")]
+    pub description: String,
+
+    /// Version consumers should upgrade to
+    #[arg(long = "superseded-by", value_name = "VERSION")]
+    pub superseded_by: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct CheckArgs {
+    /// Installed .gtpack archives, or directories searched for them
+    #[arg(value_name = "PATH", required = true)]
+    pub paths: Vec<PathBuf>,
+
+    /// Search index holding the advisories
+    #[arg(long, value_name = "FILE", default_value = "search-index.json")]
+    pub index: PathBuf,
+
+    /// Lowest advisory severity that makes the check fail (yanked packs always do)
+    #[arg(long = "fail-on", value_enum, default_value = "low")]
+    pub fail_on: AdvisorySeverity,
+}
+
+pub fn handle(command: AdvisoryCommand, json: bool) -> Result<()> {
+    match command {
+        AdvisoryCommand::Add(args) => add(args, json),
+        AdvisoryCommand::Check(args) => check(args, json),
+    }
+}
+
+fn load(path: &std::path::Path) -> Result<index::SearchIndex> {
+    index::load_index(path)?
+        .with_context(|| format!("{} does not exist; run `packc index` first", path.display()))
+}
+
+fn add(args: AddArgs, json: bool) -> Result<()> {
+    let mut search_index = load(&args.index)?;
+    let advisory = Advisory {
+        id: args.id,
+        pack_id: args.pack_id,
+        versions: args.versions,
+        severity: args.severity,
+        description: args.description,
+        superseded_by: args.superseded_by,
+    };
+    search_index.add_advisory(advisory.clone())?;
+    let serialized = serde_json::to_string_pretty(&search_index)?;
+    atomic::write(&args.index, serialized.as_bytes(), Durability::Buffered)?;
+
+    let listed: Vec<&str> = search_index
+        .packs
+        .iter()
+        .filter(|pack| {
+            semver::Version::parse(&pack.version)
+                .is_ok_and(|version| advisory.affects(&pack.pack_id, &version))
+        })
+        .map(|pack| pack.key.as_str())
+        .collect();
+    if json {
+        let payload = json!({
+            "index": args.index.display().to_string(),
+            "advisory": advisory,
+            "indexed_packs_affected": listed,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        println!(
+            "added {} ({}) for {} {} to {}",
+            advisory.id,
+            advisory.severity,
+            advisory.pack_id,
+            advisory.versions,
+            args.index.display()
+        );
+        for key in listed {
+            println!("  affects {key}");
+        }
+    }
+    Ok(())
+}
+
+fn check(args: CheckArgs, json: bool) -> Result<()> {
+    let search_index = load(&args.index)?;
+    let affected = advisory::scan(&search_index, &args.paths)?;
+    let failing = affected
+        .iter()
+        .filter(|pack| pack.yanked.is_some() || pack.severity() >= Some(args.fail_on))
+        .count();
+
+    if json {
+        let payload = json!({
+            "status": if failing > 0 { "failed" } else { "ok" },
+            "affected": affected,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else if affected.is_empty() {
+        println!("no installed packs are yanked or covered by an advisory");
+    } else {
+        for pack in &affected {
+            println!(
+                "{}@{} ({})",
+                pack.pack_id,
+                pack.version,
+                pack.path.display()
+            );
+            if let Some(yank) = &pack.yanked {
+                let reason = yank
+                    .reason
+                    .as_deref()
+                    .map(|reason| format!(": {reason}"))
+                    .unwrap_or_default();
+                println!("  yanked on {}{reason}", yank.yanked_at);
+            }
+            for advisory in &pack.advisories {
+                let upgrade = advisory
+                    .superseded_by
+                    .as_deref()
+                    .map(|version| format!("; upgrade to {version}"))
+                    .unwrap_or_default();
+                println!(
+                    "  {}[{}] {}{upgrade}",
+                    advisory.severity, advisory.id, advisory.description
+                );
+            }
+        }
+    }
+
+    if failing > 0 {
+        bail!(
+            "{failing} installed pack(s) yanked or covered by advisories of severity {} or higher",
+            args.fail_on
+        );
+    }
+    Ok(())
+}
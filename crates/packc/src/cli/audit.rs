@@ -0,0 +1,119 @@
+#![forbid(unsafe_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use greentic_pack::atomic::{self, Durability};
+use serde_json::json;
+
+use crate::audit::{self, AuditOptions, AuditReport};
+
+#[derive(Debug, Parser)]
+pub struct AuditArgs {
+    /// Pack directory or .gtpack archive to audit
+    #[arg(long = "in", value_name = "PATH")]
+    pub input: PathBuf,
+
+    /// Where to write the audit document (prints it when omitted)
+    #[arg(long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+
+    /// Private key (Ed25519 PKCS#8 or ML-DSA-65 PEM) signing the audit document
+    #[arg(long, value_name = "FILE")]
+    pub key: Option<PathBuf>,
+
+    /// Public key checking the signatures of a pack directory; repeatable
+    #[arg(long = "pub", value_name = "FILE")]
+    pub public_key: Vec<PathBuf>,
+
+    /// Capability the target host denies; repeatable
+    #[arg(long, value_name = "CAPABILITY")]
+    pub deny: Vec<String>,
+
+    /// Exit with an error when a policy rule fails (the document is still written)
+    #[arg(long)]
+    pub fail_on_policy: bool,
+}
+
+pub fn handle(args: AuditArgs, json: bool) -> Result<()> {
+    let public_keys = args
+        .public_key
+        .iter()
+        .map(|path| {
+            fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let public_keys: Vec<&str> = public_keys.iter().map(String::as_str).collect();
+    let opts = AuditOptions {
+        public_keys_pem: &public_keys,
+        deny: args.deny.iter().cloned().collect(),
+    };
+
+    let mut report = audit::audit(&args.input, &opts)?;
+    if let Some(key) = &args.key {
+        let pem =
+            fs::read_to_string(key).with_context(|| format!("failed to read {}", key.display()))?;
+        report.sign(&pem)?;
+    }
+
+    let document = serde_json::to_string_pretty(&report)?;
+    match &args.out {
+        Some(out) => {
+            atomic::write(out, document.as_bytes(), Durability::default())?;
+            if json {
+                print_json(&report, out)?;
+            } else {
+                print_human(&report, out);
+            }
+        }
+        None => println!("{document}"),
+    }
+
+    if args.fail_on_policy {
+        audit::ensure_policy_passed(&report)?;
+    }
+    Ok(())
+}
+
+fn print_human(report: &AuditReport, out: &Path) {
+    println!(
+        "audited {}@{} -> {}",
+        report.pack.id,
+        report.pack.version,
+        out.display()
+    );
+    println!(
+        "  signature: {} ({})",
+        report.signature.status.as_str(),
+        report.signature.detail
+    );
+    for result in &report.policy {
+        let status = if result.passed { "pass" } else { "FAIL" };
+        println!("  {status} {}: {}", result.rule, result.detail);
+    }
+    println!("  findings: {}", report.findings.len());
+    if let Some(attestation) = &report.attestation {
+        println!("  signed by {} ({})", attestation.key_id, attestation.alg);
+    }
+}
+
+fn print_json(report: &AuditReport, out: &Path) -> Result<()> {
+    let payload = json!({
+        "out": out,
+        "pack_id": report.pack.id,
+        "version": report.pack.version,
+        "signature": report.signature.status,
+        "policy_failed": report
+            .policy
+            .iter()
+            .filter(|result| !result.passed)
+            .map(|result| result.rule)
+            .collect::<Vec<_>>(),
+        "findings": report.findings.len(),
+        "signed": report.attestation.is_some(),
+    });
+    println!("{}", serde_json::to_string_pretty(&payload)?);
+    Ok(())
+}
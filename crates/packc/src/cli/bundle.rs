@@ -0,0 +1,98 @@
+#![forbid(unsafe_code)]
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, anyhow};
+use clap::Parser;
+use greentic_pack::atomic::Durability;
+use greentic_pack::chunking::ChunkStore;
+use greentic_pack::closure::write_closure;
+use greentic_pack::{SigningPolicy, open_pack_bytes};
+use serde_json::json;
+
+use crate::cache::{self, CacheKind};
+use crate::{closure, registry};
+
+#[derive(Debug, Parser)]
+pub struct BundleArgs {
+    /// .gtpack archive to bundle
+    #[arg(long = "in", value_name = "FILE")]
+    pub input: PathBuf,
+
+    /// Output path (defaults to <PACK_ID>-<VERSION>.gtbundle)
+    #[arg(long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+
+    /// Also embed every pack the pack depends on, resolved from --registry,
+    /// so the bundle installs without network access
+    #[arg(long = "self-contained", requires = "registry")]
+    pub self_contained: bool,
+
+    /// Registry directory dependencies are resolved from
+    #[arg(long, value_name = "DIR", requires = "self_contained")]
+    pub registry: Option<PathBuf>,
+
+    /// Local chunk cache (defaults to ~/.cache/greentic/chunks)
+    #[arg(long, value_name = "DIR")]
+    pub cache: Option<PathBuf>,
+
+    /// Resolve dependencies to yanked versions too
+    #[arg(long, requires = "self_contained")]
+    pub allow_yanked: bool,
+}
+
+pub fn handle(args: BundleArgs, json: bool) -> Result<()> {
+    let root = fs::read(&args.input)
+        .with_context(|| format!("failed to read {}", args.input.display()))?;
+    let archives = if args.self_contained {
+        let registry_dir = args.registry.as_deref().expect("clap requires --registry");
+        let cache_dir = args
+            .cache
+            .or_else(registry::default_cache)
+            .context("cannot determine the chunk cache directory; pass --cache")?;
+        let archives = closure::resolve(
+            root,
+            registry_dir,
+            &ChunkStore::new(&cache_dir),
+            args.allow_yanked,
+        )?;
+        cache::enforce_configured(CacheKind::Chunks, &cache_dir)?;
+        archives
+    } else {
+        vec![root]
+    };
+
+    let out = match args.out {
+        Some(out) => out,
+        None => {
+            let load = open_pack_bytes(&archives[0], SigningPolicy::DevOk).map_err(|err| {
+                anyhow!(
+                    "{} failed verification: {}",
+                    args.input.display(),
+                    err.message
+                )
+            })?;
+            let meta = &load.manifest.meta;
+            PathBuf::from(format!("{}-{}.gtbundle", meta.pack_id, meta.version))
+        }
+    };
+    let manifest = write_closure(&out, &archives, SigningPolicy::DevOk, Durability::default())?;
+
+    if json {
+        let payload = json!({
+            "out": out.display().to_string(),
+            "closure": manifest,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        println!("bundled {} -> {}", manifest.root, out.display());
+        for member in manifest.packs.iter().skip(1) {
+            println!("  with {}", member.key());
+        }
+        for import in &manifest.host_imports {
+            println!("  host provides {import}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,318 @@
+#![forbid(unsafe_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use clap::{Parser, Subcommand};
+use serde_json::json;
+
+use crate::build_cache::BuildCache;
+use crate::cache::{self, CacheKind};
+use crate::signing::TrustStore;
+use crate::signing::trust::TRUST_STORE_ENV;
+use crate::usage;
+
+#[derive(Debug, Subcommand)]
+pub enum CacheCommand {
+    /// Show the size, entry count, and quota of each cache
+    Stats(StatsArgs),
+    /// Evict least recently used entries until each cache fits its quota
+    Clean(CleanArgs),
+    /// Check cached chunks against their digests and evict corrupt ones
+    Verify(VerifyArgs),
+    /// Write a pack's compiled component cache to a signed bundle
+    Export(ExportArgs),
+    /// Add the entries of a signed bundle to a pack's compiled component cache
+    Import(ImportArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct StatsArgs {
+    /// Root directory of the pack whose compiled component cache to include
+    #[arg(long = "in", value_name = "DIR", default_value = ".")]
+    pub input: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+pub struct CleanArgs {
+    /// Caches to clean: chunks, builds, components, adapters, tools (defaults to all)
+    #[arg(value_name = "CACHE")]
+    pub names: Vec<String>,
+
+    /// Root directory of the pack whose compiled component cache to clean
+    #[arg(long = "in", value_name = "DIR", default_value = ".")]
+    pub input: PathBuf,
+
+    /// Remove every entry instead of only what exceeds the quota
+    #[arg(long)]
+    pub all: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct VerifyArgs {
+    /// Caches to verify: chunks, builds, components, adapters, tools (defaults to all)
+    #[arg(value_name = "CACHE")]
+    pub names: Vec<String>,
+
+    /// Root directory of the pack whose compiled component cache to verify
+    #[arg(long = "in", value_name = "DIR", default_value = ".")]
+    pub input: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+pub struct ExportArgs {
+    /// Root directory of the pack whose build cache to export
+    #[arg(long = "in", value_name = "DIR")]
+    pub input: PathBuf,
+
+    /// Bundle to write
+    #[arg(long, value_name = "FILE")]
+    pub out: PathBuf,
+
+    /// Private key (PEM) signing the bundle, e.g. the CI key
+    #[arg(long, value_name = "FILE")]
+    pub key: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+pub struct ImportArgs {
+    /// Bundle written by `packc cache export`
+    #[arg(value_name = "BUNDLE")]
+    pub bundle: PathBuf,
+
+    /// Root directory of the pack whose build cache to fill
+    #[arg(long = "in", value_name = "DIR")]
+    pub input: PathBuf,
+
+    /// Public key (PEM) trusted to sign bundles; repeatable
+    #[arg(long = "pub", value_name = "FILE")]
+    pub public_key: Vec<PathBuf>,
+
+    /// Trust store of keys trusted to sign bundles (defaults to $PACKC_TRUST_STORE)
+    #[arg(long = "trust-store", value_name = "PATH")]
+    pub trust_store: Option<PathBuf>,
+}
+
+pub fn handle(command: CacheCommand, json: bool) -> Result<()> {
+    match command {
+        CacheCommand::Stats(args) => stats(args, json),
+        CacheCommand::Clean(args) => clean(args, json),
+        CacheCommand::Verify(args) => verify(args, json),
+        CacheCommand::Export(args) => export(args, json),
+        CacheCommand::Import(args) => import(args, json),
+    }
+}
+
+/// The caches named by `names` and their directories; the components cache
+/// is the one of the pack at `pack_dir`.
+fn selected(names: &[String], pack_dir: &Path) -> Result<Vec<(CacheKind, PathBuf)>> {
+    let kinds = if names.is_empty() {
+        CacheKind::ALL.to_vec()
+    } else {
+        names
+            .iter()
+            .map(|name| {
+                CacheKind::parse(name).with_context(|| {
+                    let known: Vec<&str> =
+                        CacheKind::ALL.iter().map(|kind| kind.as_str()).collect();
+                    format!(
+                        "unknown cache `{name}`; expected one of: {}",
+                        known.join(", ")
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+    kinds
+        .into_iter()
+        .map(|kind| {
+            if kind == CacheKind::Components {
+                return Ok((kind, BuildCache::new(pack_dir).dir().to_path_buf()));
+            }
+            let dir = kind.dir().with_context(|| {
+                format!(
+                    "cannot determine the {} cache directory; set XDG_CACHE_HOME",
+                    kind.as_str()
+                )
+            })?;
+            Ok((kind, dir))
+        })
+        .collect()
+}
+
+fn stats(args: StatsArgs, json: bool) -> Result<()> {
+    let config = usage::load_config()?;
+    let mut rows = Vec::new();
+    for (kind, dir) in selected(&[], &args.input)? {
+        let entries = cache::entries(kind, &dir)?;
+        let bytes: u64 = entries.iter().map(|entry| entry.bytes).sum();
+        rows.push((
+            kind,
+            dir,
+            entries.len(),
+            bytes,
+            kind.quota_bytes(&config.cache),
+        ));
+    }
+    if json {
+        let payload: Vec<_> = rows
+            .iter()
+            .map(|(kind, dir, entries, bytes, quota)| {
+                json!({
+                    "cache": kind,
+                    "dir": dir.display().to_string(),
+                    "entries": entries,
+                    "bytes": bytes,
+                    "quota_bytes": quota,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        for (kind, dir, entries, bytes, quota) in &rows {
+            println!(
+                "{:<10} {entries} entries, {} of {} MiB  {}",
+                kind.as_str(),
+                mib(*bytes),
+                quota / (1024 * 1024),
+                dir.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn clean(args: CleanArgs, json: bool) -> Result<()> {
+    let config = usage::load_config()?;
+    let mut results = Vec::new();
+    for (kind, dir) in selected(&args.names, &args.input)? {
+        let eviction = if args.all {
+            cache::clear(kind, &dir)?
+        } else {
+            cache::enforce(kind, &dir, kind.quota_bytes(&config.cache))?
+        };
+        results.push((kind, eviction));
+    }
+    if json {
+        let payload: Vec<_> = results
+            .iter()
+            .map(|(kind, eviction)| {
+                json!({
+                    "cache": kind,
+                    "evicted_entries": eviction.entries,
+                    "evicted_bytes": eviction.bytes,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        for (kind, eviction) in &results {
+            println!(
+                "{}: evicted {} entries ({} MiB)",
+                kind.as_str(),
+                eviction.entries,
+                mib(eviction.bytes)
+            );
+        }
+    }
+    Ok(())
+}
+
+fn verify(args: VerifyArgs, json: bool) -> Result<()> {
+    let mut results = Vec::new();
+    for (kind, dir) in selected(&args.names, &args.input)? {
+        results.push((kind, cache::verify(kind, &dir)?));
+    }
+    if json {
+        let payload: Vec<_> = results
+            .iter()
+            .map(|(kind, evicted)| {
+                json!({
+                    "cache": kind,
+                    "evicted": evicted
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        for (kind, evicted) in &results {
+            if evicted.is_empty() {
+                println!("{}: ok", kind.as_str());
+                continue;
+            }
+            println!(
+                "{}: evicted {} corrupt entries",
+                kind.as_str(),
+                evicted.len()
+            );
+            for path in evicted {
+                println!("  {}", path.display());
+            }
+        }
+    }
+    // Evicted chunks are fetched again on the next pull, so finding
+    // corruption is not a failure.
+    Ok(())
+}
+
+fn export(args: ExportArgs, json: bool) -> Result<()> {
+    let private_key_pem = fs::read_to_string(&args.key)
+        .with_context(|| format!("failed to read {}", args.key.display()))?;
+    let entries = BuildCache::new(&args.input).export(&args.out, &private_key_pem)?;
+    if json {
+        let payload = json!({
+            "bundle": args.out.display().to_string(),
+            "entries": entries,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        println!(
+            "exported {} build cache entries to {}",
+            entries.len(),
+            args.out.display()
+        );
+    }
+    Ok(())
+}
+
+fn import(args: ImportArgs, json: bool) -> Result<()> {
+    let trust_store = args
+        .trust_store
+        .or_else(|| std::env::var_os(TRUST_STORE_ENV).map(PathBuf::from));
+    let mut trusted = match &trust_store {
+        Some(path) => TrustStore::load(path)
+            .with_context(|| format!("failed to load trust store {}", path.display()))?,
+        None => TrustStore::new(),
+    };
+    for path in &args.public_key {
+        let pem = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        trusted
+            .insert(&pem, None)
+            .with_context(|| format!("{} is not a public key", path.display()))?;
+    }
+    if trusted.is_empty() {
+        bail!("no trusted keys; pass --pub or --trust-store, or set {TRUST_STORE_ENV}");
+    }
+
+    let report = BuildCache::new(&args.input).import(&args.bundle, &trusted)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        let signer = report.signer.as_deref().unwrap_or(&report.key_id);
+        println!(
+            "imported {} build cache entries signed by {signer} ({} already cached)",
+            report.imported.len(),
+            report.skipped.len()
+        );
+    }
+    Ok(())
+}
+
+fn mib(bytes: u64) -> String {
+    format!("{:.1}", bytes as f64 / (1024.0 * 1024.0))
+}
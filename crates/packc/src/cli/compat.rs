@@ -0,0 +1,85 @@
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use clap::Parser;
+use greentic_pack::atomic::{self, Durability};
+
+use crate::compat::{self, Bump, CompatReport};
+
+#[derive(Debug, Parser)]
+pub struct CompatArgs {
+    /// The released .gtpack archive
+    #[arg(long, value_name = "FILE")]
+    pub old: PathBuf,
+
+    /// The candidate .gtpack archive
+    #[arg(long, value_name = "FILE")]
+    pub new: PathBuf,
+
+    /// Also write the JSON report to FILE
+    #[arg(long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+
+    /// Exit with an error when the new version is too small a bump for the changes
+    #[arg(long)]
+    pub strict: bool,
+}
+
+pub fn handle(args: CompatArgs, json: bool) -> Result<()> {
+    let report = compat::check(&args.old, &args.new)?;
+
+    if let Some(out) = &args.out {
+        let serialized = serde_json::to_string_pretty(&report)?;
+        atomic::write(out, serialized.as_bytes(), Durability::default())?;
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_human(&report);
+    }
+
+    if args.strict && !report.sufficient {
+        bail!(
+            "{} -> {} is a {} release but the changes need a {} release (at least {})",
+            report.old_version,
+            report.new_version,
+            report.declared_bump.as_str(),
+            report.suggested_bump.as_str(),
+            report.suggested_version
+        );
+    }
+    Ok(())
+}
+
+fn print_human(report: &CompatReport) {
+    println!(
+        "{} {} -> {}",
+        report.pack_id, report.old_version, report.new_version
+    );
+    for change in &report.changes {
+        let label = if change.bump == Bump::Major {
+            "breaking"
+        } else {
+            change.bump.as_str()
+        };
+        println!("  {label:<8}  {}: {}", change.subject, change.message);
+    }
+    if report.suggested_bump == Bump::None {
+        println!("no changes");
+    } else {
+        println!(
+            "suggested bump: {} (at least {})",
+            report.suggested_bump.as_str(),
+            report.suggested_version
+        );
+    }
+    if !report.sufficient {
+        println!(
+            "warning: {} is a {} release",
+            report.new_version,
+            report.declared_bump.as_str()
+        );
+    }
+}
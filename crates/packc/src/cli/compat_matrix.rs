@@ -0,0 +1,91 @@
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use clap::Parser;
+use greentic_pack::atomic::{self, Durability};
+
+use crate::compat_matrix::{self, CompatMatrix, HostStatus, HostsFile};
+
+#[derive(Debug, Parser)]
+pub struct CompatMatrixArgs {
+    /// The .gtpack archive to check
+    #[arg(long = "in", value_name = "FILE")]
+    pub input: PathBuf,
+
+    /// Host descriptors to check the pack against
+    #[arg(long, value_name = "FILE", default_value = "hosts.yaml")]
+    pub hosts: PathBuf,
+
+    /// Also write the JSON matrix to FILE
+    #[arg(long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+
+    /// Also write the matrix as a Markdown table to FILE
+    #[arg(long, value_name = "FILE")]
+    pub markdown: Option<PathBuf>,
+
+    /// Exit with an error when any host is at least this bad
+    #[arg(long = "fail-on", value_enum)]
+    pub fail_on: Option<HostStatus>,
+}
+
+pub fn handle(args: CompatMatrixArgs, json: bool) -> Result<()> {
+    let hosts = HostsFile::load(&args.hosts)?;
+    let matrix = compat_matrix::check(&args.input, &hosts)?;
+
+    if let Some(out) = &args.out {
+        let serialized = serde_json::to_string_pretty(&matrix)?;
+        atomic::write(out, serialized.as_bytes(), Durability::default())?;
+    }
+    if let Some(markdown) = &args.markdown {
+        atomic::write(
+            markdown,
+            matrix.to_markdown().as_bytes(),
+            Durability::default(),
+        )?;
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&matrix)?);
+    } else {
+        print_human(&matrix);
+    }
+
+    if let Some(fail_on) = args.fail_on {
+        let failing: Vec<&str> = matrix
+            .hosts
+            .iter()
+            .filter(|host| host.status >= fail_on && host.status != HostStatus::Compatible)
+            .map(|host| host.host.as_str())
+            .collect();
+        if !failing.is_empty() {
+            bail!(
+                "{}@{} is {fail_on} or worse on {}",
+                matrix.pack_id,
+                matrix.version,
+                failing.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_human(matrix: &CompatMatrix) {
+    println!(
+        "{}@{} (pack format {})",
+        matrix.pack_id, matrix.version, matrix.min_reader_version
+    );
+    let width = matrix
+        .hosts
+        .iter()
+        .map(|host| host.host.len())
+        .max()
+        .unwrap_or(0);
+    for host in &matrix.hosts {
+        println!("  {:<width$}  {}", host.host, host.status);
+        for reason in &host.reasons {
+            println!("  {:<width$}    {reason}", "");
+        }
+    }
+}
@@ -0,0 +1,44 @@
+#![forbid(unsafe_code)]
+
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+use greentic_pack::trace::Trace;
+
+use crate::debug::{Control, Debugger};
+
+#[derive(Debug, Parser)]
+pub struct DebugArgs {
+    /// Execution trace written by `packc run --trace`
+    #[arg(long, value_name = "FILE")]
+    pub trace: PathBuf,
+
+    /// Root directory of the pack, for the flow's parameters and exported tests
+    #[arg(long = "in", value_name = "DIR", default_value = ".")]
+    pub input: PathBuf,
+}
+
+pub fn handle(args: DebugArgs, _json: bool) -> Result<()> {
+    let trace = Trace::load(&args.trace)?;
+    let mut debugger = Debugger::new(trace, &args.input);
+    let mut out = std::io::stdout().lock();
+    writeln!(out, "{}", debugger.summary())?;
+    debugger.command("show", &mut out)?;
+
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        eprint!("{}", debugger.prompt());
+        let Some(line) = lines.next().transpose()? else {
+            break;
+        };
+        match debugger.command(&line, &mut out) {
+            Ok(Control::Continue) => {}
+            Ok(Control::Quit) => break,
+            Err(err) => writeln!(out, "{err:#}")?,
+        }
+        out.flush()?;
+    }
+    Ok(())
+}
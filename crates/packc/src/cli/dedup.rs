@@ -0,0 +1,146 @@
+#![forbid(unsafe_code)]
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use clap::Parser;
+use greentic_pack::atomic::{self, Durability};
+use serde_json::json;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tracing::{info, warn};
+
+use crate::dedup::{self, DuplicateGroup, DuplicateKind, PackAsset};
+use crate::digest::DigestAlgorithm;
+use crate::{manifest, templates, workspace};
+
+#[derive(Debug, Parser)]
+pub struct DedupArgs {
+    /// Directory containing the packs to compare
+    #[arg(long, value_name = "DIR")]
+    pub workspace: PathBuf,
+
+    /// Similarity (0-1) from which differing text assets count as near-identical
+    #[arg(long, value_name = "RATIO", default_value_t = 0.85)]
+    pub threshold: f64,
+
+    /// Ignore assets smaller than this many bytes
+    #[arg(long, value_name = "BYTES", default_value_t = 64)]
+    pub min_size: u64,
+
+    /// Also write the JSON report to FILE
+    #[arg(long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+
+    /// Packs read at once (defaults to the number of CPUs)
+    #[arg(long, value_name = "N")]
+    pub jobs: Option<usize>,
+}
+
+pub fn handle(args: DedupArgs, json: bool) -> Result<()> {
+    if !(0.0..=1.0).contains(&args.threshold) {
+        bail!("--threshold must be between 0 and 1");
+    }
+    let dirs = workspace::discover(&args.workspace)?;
+    let jobs = args.jobs.unwrap_or_else(workspace::default_jobs);
+    info!(packs = dirs.len(), jobs, "hashing workspace templates");
+
+    let loaded = workspace::map_parallel(&dirs, jobs, |dir| load_assets(dir));
+    let mut assets = Vec::new();
+    let mut skipped = Vec::new();
+    for (dir, result) in dirs.iter().zip(loaded) {
+        match result {
+            Ok(pack_assets) => assets.extend(pack_assets),
+            Err(err) => {
+                warn!(path = %dir.display(), "skipping pack: {err:#}");
+                skipped.push(json!({ "path": dir, "error": format!("{err:#}") }));
+            }
+        }
+    }
+
+    let groups = dedup::find_duplicates(&assets, args.threshold, args.min_size);
+    let duplicated_bytes: u64 = groups
+        .iter()
+        .filter(|group| group.kind == DuplicateKind::Identical)
+        .map(|group| group.duplicated_bytes)
+        .sum();
+    let report = json!({
+        "generated_at_utc": OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string()),
+        "workspace": args.workspace,
+        "threshold": args.threshold,
+        "packs": dirs.len() - skipped.len(),
+        "templates": assets.len(),
+        "identical_groups": groups.iter().filter(|g| g.kind == DuplicateKind::Identical).count(),
+        "similar_groups": groups.iter().filter(|g| g.kind == DuplicateKind::Similar).count(),
+        "duplicated_bytes": duplicated_bytes,
+        "groups": groups,
+        "skipped": skipped,
+    });
+
+    if let Some(out) = &args.out {
+        let serialized = serde_json::to_string_pretty(&report)?;
+        atomic::write(out, serialized.as_bytes(), Durability::default())?;
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_human(
+            &groups,
+            dirs.len() - skipped.len(),
+            assets.len(),
+            duplicated_bytes,
+        );
+        if let Some(out) = &args.out {
+            println!("report written to {}", out.display());
+        }
+    }
+    Ok(())
+}
+
+/// Template assets of the pack in `dir`, tagged with its id.
+fn load_assets(dir: &Path) -> Result<Vec<PackAsset>> {
+    let spec_bundle = manifest::load_spec(dir)?;
+    let spec = &spec_bundle.spec;
+    let templates = templates::collect_templates(
+        dir,
+        spec,
+        DigestAlgorithm::default(),
+        templates::SyntaxCheck::Skip,
+    )?;
+    Ok(templates
+        .into_iter()
+        .map(|template| PackAsset {
+            pack: spec.id.clone(),
+            path: template.logical_path,
+            digest: template.digest,
+            bytes: template.bytes,
+        })
+        .collect())
+}
+
+fn print_human(groups: &[DuplicateGroup], packs: usize, templates: usize, bytes: u64) {
+    println!("{templates} template asset(s) across {packs} pack(s)");
+    if groups.is_empty() {
+        println!("no duplicated templates");
+        return;
+    }
+    for group in groups {
+        match group.kind {
+            DuplicateKind::Identical => {
+                println!("identical ({} bytes duplicated)", group.duplicated_bytes)
+            }
+            DuplicateKind::Similar => println!(
+                "similar, {:.0}% alike ({} bytes duplicated)",
+                group.similarity * 100.0,
+                group.duplicated_bytes
+            ),
+        }
+        for asset in &group.assets {
+            println!("  {}: {} ({} bytes)", asset.pack, asset.path, asset.size);
+        }
+        println!("  suggestion: {}", group.suggestion);
+    }
+    println!("{bytes} byte(s) in identical copies");
+}
@@ -0,0 +1,116 @@
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+use serde_json::json;
+
+use super::index::FilterArgs;
+use crate::analysis::{self, FlowUsage};
+use crate::digest::DigestAlgorithm;
+use crate::index::IndexFilter;
+use crate::taxonomy::Taxonomy;
+use crate::{flows, manifest, templates};
+
+#[derive(Debug, Parser)]
+pub struct DescribeArgs {
+    /// Root directory of the pack (must contain pack.yaml)
+    #[arg(long = "in", value_name = "DIR")]
+    pub input: PathBuf,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+}
+
+pub fn handle(args: DescribeArgs, json: bool) -> Result<()> {
+    let mut spec_bundle = manifest::load_spec(&args.input)?;
+    // Filters compare against the normalised labels the index records.
+    Taxonomy::load(&args.input, None)?.apply(&mut spec_bundle.spec)?;
+    let spec = &spec_bundle.spec;
+    let filter = IndexFilter::from(args.filter);
+    if !filter.matches_labels(&spec.tags, &spec.categories) {
+        // Nothing goes to stdout, so describing a set of packs lists only the matches.
+        eprintln!(
+            "{}@{} does not match the --tag/--category filter",
+            spec.id, spec.version
+        );
+        return Ok(());
+    }
+    let flows = flows::load_flows(&args.input, spec, DigestAlgorithm::default())?;
+    let templates = templates::collect_templates(
+        &args.input,
+        spec,
+        DigestAlgorithm::default(),
+        templates::SyntaxCheck::Skip,
+    )?;
+    let report = analysis::analyze(spec, &flows, &templates);
+
+    if json {
+        let payload = json!({
+            "pack": {
+                "id": spec.id,
+                "version": spec.version,
+                "kind": spec.kind,
+                "tags": spec.tags,
+                "categories": spec.categories,
+                "entry_flows": spec.entry_flows,
+                "imports_required": spec.imports_required,
+            },
+            "flows": report.flows,
+            "unattributed_imports": report.unattributed_imports,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+        return Ok(());
+    }
+
+    println!("{}@{}", spec.id, spec.version);
+    println!(
+        "  {} flow(s), {} template(s), {} import(s)",
+        report.flows.len(),
+        templates.len(),
+        spec.imports_required.len()
+    );
+    for flow in &report.flows {
+        print_flow(flow);
+    }
+    if !report.unattributed_imports.is_empty() {
+        println!(
+            "imports not attributed to a flow: {}",
+            report.unattributed_imports.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn print_flow(flow: &FlowUsage) {
+    let entry = if flow.entry { " (entry)" } else { "" };
+    let health = if flow.health { " (health)" } else { "" };
+    println!("flow {}{entry}{health} [{}]", flow.flow, flow.path);
+    let components: Vec<String> = flow
+        .components
+        .iter()
+        .map(|(name, count)| format!("{name} x{count}"))
+        .collect();
+    if components.is_empty() {
+        println!("  nodes: {}", flow.nodes);
+    } else {
+        println!("  nodes: {} ({})", flow.nodes, components.join(", "));
+    }
+    println!("  imports: {}", list(&flow.imports));
+    println!("  templates: {}", list(&flow.templates));
+    if !flow.node_docs.is_empty() {
+        println!("  docs:");
+        for (node, doc) in &flow.node_docs {
+            println!("    {node}: {doc}");
+        }
+    }
+}
+
+fn list(items: &[String]) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.join(", ")
+    }
+}
@@ -0,0 +1,82 @@
+#![forbid(unsafe_code)]
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow, bail};
+use clap::Parser;
+use greentic_pack::{SigningPolicy, open_pack};
+use serde_json::json;
+
+use crate::diff::{self, Change, PackSnapshot, Severity};
+
+#[derive(Debug, Parser)]
+pub struct DiffArgs {
+    /// Earlier .gtpack archive
+    #[arg(long, value_name = "FILE")]
+    pub from: PathBuf,
+
+    /// Later .gtpack archive
+    #[arg(long, value_name = "FILE")]
+    pub to: PathBuf,
+
+    /// Exit with an error when a change is at least this severe
+    #[arg(long, value_name = "SEVERITY", value_enum)]
+    pub fail_on: Option<Severity>,
+}
+
+pub fn handle(args: DiffArgs, json: bool) -> Result<()> {
+    let old = snapshot(&args.from)?;
+    let new = snapshot(&args.to)?;
+    let changes = diff::diff(&old, &new);
+
+    if json {
+        let payload = json!({
+            "from": { "path": args.from, "pack_id": old.meta.pack_id, "version": old.meta.version },
+            "to": { "path": args.to, "pack_id": new.meta.pack_id, "version": new.meta.version },
+            "highest_severity": changes.first().map(|change| change.severity),
+            "changes": changes,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        print_human(&changes, &old, &new);
+    }
+
+    if let Some(threshold) = args.fail_on {
+        let blocking = changes.iter().filter(|c| c.severity >= threshold).count();
+        if blocking > 0 {
+            bail!("{blocking} change(s) at or above `{threshold}` severity");
+        }
+    }
+    Ok(())
+}
+
+fn snapshot(path: &Path) -> Result<PackSnapshot> {
+    let load = open_pack(path, SigningPolicy::DevOk)
+        .map_err(|err| anyhow!("{} failed verification: {}", path.display(), err.message))?;
+    PackSnapshot::from_load(&load)
+}
+
+fn print_human(changes: &[Change], old: &PackSnapshot, new: &PackSnapshot) {
+    println!(
+        "{}@{} -> {}@{}",
+        old.meta.pack_id, old.meta.version, new.meta.pack_id, new.meta.version
+    );
+    if changes.is_empty() {
+        println!("no changes");
+        return;
+    }
+    let width = changes
+        .iter()
+        .map(|change| change.category.as_str().len())
+        .max()
+        .unwrap_or(0);
+    for change in changes {
+        println!(
+            "{:<6}  {:<width$}  {}: {}",
+            change.severity.as_str(),
+            change.category.as_str(),
+            change.subject,
+            change.message
+        );
+    }
+}
@@ -0,0 +1,127 @@
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use serde_json::json;
+
+use crate::fuzz::{self, DEFAULT_TOOLCHAIN, FuzzOptions, FuzzRun, FuzzTarget};
+
+#[derive(Debug, Parser)]
+pub struct FuzzArgs {
+    /// Targets to run (defaults to every target)
+    #[arg(value_enum, value_name = "TARGET")]
+    pub targets: Vec<FuzzTarget>,
+
+    /// Seconds to fuzz each target for
+    #[arg(long, value_name = "SECS", default_value_t = 60)]
+    pub duration: u64,
+
+    /// Parallel fuzzing processes per target
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub jobs: u32,
+
+    /// The fuzz crate (defaults to the nearest fuzz/ above the working directory)
+    #[arg(long, value_name = "DIR")]
+    pub fuzz_dir: Option<PathBuf>,
+
+    /// Add a built .gtpack, and its manifest.cbor, to the corpora before fuzzing
+    #[arg(long = "seed", value_name = "FILE")]
+    pub seeds: Vec<PathBuf>,
+
+    /// Toolchain cargo fuzz runs with
+    #[arg(long, value_name = "TOOLCHAIN", default_value = DEFAULT_TOOLCHAIN)]
+    pub toolchain: String,
+
+    /// List the targets and exit
+    #[arg(long)]
+    pub list: bool,
+}
+
+pub fn handle(args: FuzzArgs, json: bool) -> Result<()> {
+    if args.list {
+        return list(json);
+    }
+
+    let fuzz_dir = match args.fuzz_dir {
+        Some(dir) => dir,
+        None => {
+            let cwd = std::env::current_dir().context("failed to read the working directory")?;
+            fuzz::locate_fuzz_dir(&cwd).with_context(|| {
+                format!(
+                    "no {}/Cargo.toml above {}; pass --fuzz-dir",
+                    fuzz::FUZZ_DIR,
+                    cwd.display()
+                )
+            })?
+        }
+    };
+    let opts = FuzzOptions {
+        fuzz_dir,
+        duration_secs: args.duration,
+        jobs: args.jobs,
+        toolchain: args.toolchain,
+    };
+    fuzz::ensure_cargo_fuzz(&opts)?;
+
+    for archive in &args.seeds {
+        let seeds = fuzz::add_archive_seed(&opts.fuzz_dir, archive)?;
+        if !json {
+            println!("seeded {} input(s) from {}", seeds.len(), archive.display());
+        }
+    }
+
+    let targets = if args.targets.is_empty() {
+        FuzzTarget::ALL.to_vec()
+    } else {
+        args.targets
+    };
+    let mut runs = Vec::new();
+    for target in targets {
+        let run = fuzz::run_target(&opts, target)?;
+        if !json {
+            print_run(&run);
+        }
+        runs.push(run);
+    }
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "runs": &runs }))?
+        );
+    }
+    let failed: Vec<&str> = runs
+        .iter()
+        .filter(|run| !run.passed)
+        .map(|run| run.target.name())
+        .collect();
+    if !failed.is_empty() {
+        bail!("fuzz target(s) failed: {}", failed.join(", "));
+    }
+    Ok(())
+}
+
+fn list(json: bool) -> Result<()> {
+    if json {
+        let targets: Vec<_> = FuzzTarget::ALL
+            .iter()
+            .map(|target| json!({ "name": target.name(), "description": target.description() }))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&targets)?);
+    } else {
+        for target in FuzzTarget::ALL {
+            println!("{:<16} {}", target.name(), target.description());
+        }
+    }
+    Ok(())
+}
+
+fn print_run(run: &FuzzRun) {
+    let status = if run.passed { "ok" } else { "FAILED" };
+    println!("{:<16} {status}", run.target.name());
+    for artifact in &run.artifacts {
+        println!("  {}", artifact.display());
+    }
+}
@@ -0,0 +1,217 @@
+#![forbid(unsafe_code)]
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use greentic_pack::fallbacks::FALLBACK_KEY;
+use greentic_pack::node_docs::{DOC_KEY, node_docs};
+use greentic_pack::readme::{README_PATH, relative_links};
+use serde::Serialize;
+use serde_json::{Map, json};
+use serde_yaml_bw::Value as YamlValue;
+
+use crate::assets::AssetGraph;
+use crate::digest::DigestAlgorithm;
+use crate::flows::FlowAsset;
+use crate::{flows, manifest, templates};
+
+#[derive(Debug, Parser)]
+pub struct GraphArgs {
+    /// Root directory of the pack (must contain pack.yaml)
+    #[arg(long = "in", value_name = "DIR")]
+    pub input: PathBuf,
+
+    /// Show references between template assets (partials, `src`, `href`)
+    #[arg(long)]
+    pub assets: bool,
+
+    /// Show each flow's nodes, their routes, and their `doc:` strings
+    #[arg(long)]
+    pub flows: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FlowGraph {
+    pub flow: String,
+    pub start: String,
+    pub nodes: Vec<GraphNode>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GraphNode {
+    pub id: String,
+    pub component: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    /// Target node ids; `out` ends the flow.
+    pub routes: Vec<String>,
+}
+
+pub fn handle(args: GraphArgs, json: bool) -> Result<()> {
+    if !args.assets && !args.flows {
+        bail!("select a graph to show (--assets or --flows)");
+    }
+
+    let spec_bundle = manifest::load_spec(&args.input)?;
+    let mut payload = Map::new();
+
+    if args.assets {
+        let templates = templates::collect_templates(
+            &args.input,
+            &spec_bundle.spec,
+            DigestAlgorithm::default(),
+            templates::SyntaxCheck::Skip,
+        )?;
+        let graph = AssetGraph::build(&templates, &spec_bundle.spec.template_dirs);
+        let readme_links = fs::read_to_string(args.input.join(README_PATH))
+            .map(|markdown| relative_links(&markdown))
+            .unwrap_or_default();
+        let orphans = graph.orphans(&readme_links);
+        if json {
+            payload.insert("assets".into(), json!(graph.assets));
+            payload.insert("references".into(), json!(graph.references));
+            payload.insert("orphans".into(), json!(orphans));
+        } else {
+            print_assets(&graph, &orphans);
+        }
+    }
+
+    if args.flows {
+        let flows = flows::load_flows(&args.input, &spec_bundle.spec, DigestAlgorithm::default())?;
+        let graphs: Vec<FlowGraph> = flows.iter().map(flow_graph).collect();
+        if json {
+            payload.insert("flows".into(), serde_json::to_value(&graphs)?);
+        } else {
+            print_flows(&graphs);
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    }
+    Ok(())
+}
+
+fn print_assets(graph: &AssetGraph, orphans: &[&str]) {
+    for (from, references) in graph.by_source() {
+        println!("{from}");
+        for reference in references {
+            match &reference.resolved {
+                Some(target) => println!("  {} -> {target}", reference.kind.as_str()),
+                None => println!(
+                    "  {} -> {} (missing)",
+                    reference.kind.as_str(),
+                    reference.target
+                ),
+            }
+        }
+    }
+    for orphan in orphans {
+        println!("{orphan} (unreferenced)");
+    }
+}
+
+fn flow_graph(flow: &FlowAsset) -> FlowGraph {
+    let nodes = flow
+        .bundle
+        .nodes
+        .iter()
+        .map(|node| {
+            let routing = flow.bundle.json["nodes"][&node.node_id]["routing"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+            let routes = routing
+                .iter()
+                .filter_map(|route| {
+                    if route["out"].as_bool() == Some(true) {
+                        return Some("out".to_string());
+                    }
+                    route["to"].as_str().map(str::to_string)
+                })
+                .collect();
+            GraphNode {
+                id: node.node_id.clone(),
+                component: node.component.name.clone(),
+                doc: flow.node_docs.get(&node.node_id).cloned(),
+                routes,
+            }
+        })
+        .collect();
+    FlowGraph {
+        flow: flow.bundle.id.clone(),
+        start: flow.bundle.entry.clone(),
+        nodes,
+    }
+}
+
+/// The graph of a flow stored in a `.gtpack`, read from its YAML source, so
+/// archives can be shown without parsing their flows again.
+pub fn archived_flow_graph(flow: &str, start: &str, yaml: &str) -> Result<FlowGraph> {
+    let source: YamlValue = serde_yaml_bw::from_str(yaml)
+        .with_context(|| format!("flow `{flow}` source is not valid YAML"))?;
+    let mut docs = node_docs(yaml).with_context(|| format!("flow `{flow}`"))?;
+    let mut nodes = Vec::new();
+    for (id, body) in source
+        .get("nodes")
+        .and_then(YamlValue::as_mapping)
+        .into_iter()
+        .flatten()
+    {
+        let id = id.as_str().unwrap_or_default().to_string();
+        let Some(body) = body.as_mapping() else {
+            continue;
+        };
+        let component = body
+            .keys()
+            .filter_map(YamlValue::as_str)
+            .find(|key| *key != "routing" && *key != DOC_KEY && *key != FALLBACK_KEY)
+            .unwrap_or_default()
+            .to_string();
+        let routes = body
+            .get("routing")
+            .and_then(YamlValue::as_sequence)
+            .into_iter()
+            .flatten()
+            .filter_map(|route| {
+                if route.get("out").and_then(YamlValue::as_bool) == Some(true) {
+                    return Some("out".to_string());
+                }
+                route
+                    .get("to")
+                    .and_then(YamlValue::as_str)
+                    .map(str::to_string)
+            })
+            .collect();
+        nodes.push(GraphNode {
+            doc: docs.remove(&id),
+            id,
+            component,
+            routes,
+        });
+    }
+    Ok(FlowGraph {
+        flow: flow.to_string(),
+        start: start.to_string(),
+        nodes,
+    })
+}
+
+fn print_flows(graphs: &[FlowGraph]) {
+    for graph in graphs {
+        println!("flow {} (start: {})", graph.flow, graph.start);
+        for node in &graph.nodes {
+            let routes = if node.routes.is_empty() {
+                String::new()
+            } else {
+                format!(" -> {}", node.routes.join(", "))
+            };
+            println!("  {} [{}]{routes}", node.id, node.component);
+            if let Some(doc) = &node.doc {
+                println!("    {doc}");
+            }
+        }
+    }
+}
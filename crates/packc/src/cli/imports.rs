@@ -0,0 +1,231 @@
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use clap::{Parser, Subcommand};
+use greentic_pack::atomic::{self, Durability};
+use serde_json::json;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+use crate::imports::catalog::{CATALOG_FILE, catalog_source};
+use crate::imports::deterministic::{self, DeterministicHost};
+use crate::imports::mocks::{self, MOCKS_DIR, WitLibrary};
+use crate::imports::{self as resolver, InterfaceCatalog};
+use crate::lockfile::PackLock;
+use crate::manifest;
+
+#[derive(Debug, Subcommand)]
+pub enum ImportsCommand {
+    /// Refresh a pack's local interface catalog (interfaces.catalog.toml)
+    UpdateCatalog(UpdateCatalogArgs),
+    /// Generate mock skeletons for the pack's interface imports from WIT, and check existing ones
+    Mocks(MocksArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct UpdateCatalogArgs {
+    /// Root directory of the pack whose catalog is refreshed
+    #[arg(long = "in", value_name = "DIR", default_value = ".")]
+    pub input: PathBuf,
+
+    /// Catalog to copy: a .toml file or a registry .gtpack (defaults to the one built into packc)
+    #[arg(long, value_name = "FILE")]
+    pub from: Option<PathBuf>,
+
+    /// Write the catalog here instead of <DIR>/interfaces.catalog.toml
+    #[arg(long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+pub struct MocksArgs {
+    /// Root directory of the pack (must contain pack.yaml)
+    #[arg(long = "in", value_name = "DIR", default_value = ".")]
+    pub input: PathBuf,
+
+    /// Directory holding the WIT packages of the imported interfaces (defaults to <DIR>/wit)
+    #[arg(long, value_name = "DIR")]
+    pub wit: Option<PathBuf>,
+
+    /// Directory for mock files (defaults to <DIR>/mocks)
+    #[arg(long, value_name = "DIR")]
+    pub out: Option<PathBuf>,
+
+    /// Interface catalog for imports_required (defaults to <DIR>/interfaces.catalog.toml, then the built-in one)
+    #[arg(long, value_name = "FILE")]
+    pub interface_catalog: Option<PathBuf>,
+
+    /// Only check existing mocks; do not write skeletons for missing ones
+    #[arg(long)]
+    pub check: bool,
+
+    /// Seed of the random values written into wasi:random mocks
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub seed: u64,
+
+    /// Instant wasi:clocks mocks freeze the wall clock at (RFC 3339)
+    #[arg(long, value_name = "TIMESTAMP", value_parser = parse_timestamp, default_value = "1970-01-01T00:00:00Z")]
+    pub now: OffsetDateTime,
+}
+
+pub fn handle(command: ImportsCommand, json: bool) -> Result<()> {
+    match command {
+        ImportsCommand::UpdateCatalog(args) => update_catalog(args, json),
+        ImportsCommand::Mocks(args) => generate_mocks(args, json),
+    }
+}
+
+fn update_catalog(args: UpdateCatalogArgs, json: bool) -> Result<()> {
+    let source = catalog_source(args.from.as_deref())?;
+    let origin = args
+        .from
+        .as_ref()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| "built-in catalog".to_string());
+    let catalog = InterfaceCatalog::parse(&source)
+        .with_context(|| format!("{origin} is not a valid interface catalog"))?;
+
+    let out = args.out.unwrap_or_else(|| args.input.join(CATALOG_FILE));
+    // A missing or unreadable local copy counts as empty.
+    let previous = InterfaceCatalog::from_path(&out)
+        .map(|catalog| catalog.package_ids())
+        .unwrap_or_default();
+    let current = catalog.package_ids();
+    let added: Vec<&String> = current.difference(&previous).collect();
+    let removed: Vec<&String> = previous.difference(&current).collect();
+
+    atomic::write(&out, source.as_bytes(), Durability::default())?;
+
+    if json {
+        let payload = json!({
+            "catalog": out.display().to_string(),
+            "source": origin,
+            "packages": current.len(),
+            "added": added,
+            "removed": removed,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        println!(
+            "updated {} from {origin} ({} packages)",
+            out.display(),
+            current.len()
+        );
+        for id in added {
+            println!("  + {id}");
+        }
+        for id in removed {
+            println!("  - {id}");
+        }
+    }
+    Ok(())
+}
+
+fn generate_mocks(args: MocksArgs, json: bool) -> Result<()> {
+    let spec_bundle = manifest::load_spec(&args.input)?;
+    let catalog = InterfaceCatalog::load(&args.input, args.interface_catalog.as_deref())?;
+    let imports = resolver::resolve_locked(
+        &catalog,
+        &spec_bundle.spec.imports_required,
+        PackLock::load(&args.input)?.as_ref(),
+    )?;
+    let wit_root = args.wit.unwrap_or_else(|| args.input.join("wit"));
+    let out = args.out.unwrap_or_else(|| args.input.join(MOCKS_DIR));
+    let (builtin, imports): (Vec<_>, Vec<_>) =
+        imports.into_iter().partition(deterministic::is_builtin);
+    let wit = if imports.is_empty() {
+        None
+    } else {
+        Some(WitLibrary::load(&wit_root, &imports)?)
+    };
+    let mut host = DeterministicHost::new(args.seed, args.now);
+
+    let mut report = Vec::new();
+    let mut failures = Vec::new();
+    for import in mocks::unique(&builtin) {
+        let path = mocks::mock_path(&out, import);
+        let status = if path.exists() {
+            match mocks::read_mock(&path).and_then(|mock| mocks::check_target(import, &mock)) {
+                Ok(()) => "ok",
+                Err(err) => {
+                    failures.push(format!("{}: {err:#}", path.display()));
+                    "invalid"
+                }
+            }
+        } else if args.check {
+            "missing"
+        } else {
+            let mock = host
+                .mock(import)
+                .expect("built-in interfaces have deterministic mocks");
+            write_mock(&path, &mocks::to_yaml(&mock)?)?;
+            "builtin"
+        };
+        report.push((import.to_string(), path, status));
+    }
+    for import in wit.iter().flat_map(|_| mocks::unique(&imports)) {
+        let wit = wit.as_ref().expect("WIT is loaded when there are imports");
+        let path = mocks::mock_path(&out, import);
+        let status = if path.exists() {
+            match mocks::read_mock(&path).and_then(|mock| wit.check(import, &mock)) {
+                Ok(()) => "ok",
+                Err(err) => {
+                    failures.push(format!("{}: {err:#}", path.display()));
+                    "invalid"
+                }
+            }
+        } else if args.check {
+            "missing"
+        } else {
+            write_mock(&path, &mocks::to_yaml(&wit.skeleton(import)?)?)?;
+            "created"
+        };
+        report.push((import.to_string(), path, status));
+    }
+
+    if json {
+        let entries: Vec<_> = report
+            .iter()
+            .map(|(interface, path, status)| {
+                json!({
+                    "interface": interface,
+                    "path": path.display().to_string(),
+                    "status": status,
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "mocks": entries }))?
+        );
+    } else if report.is_empty() {
+        println!("no WIT interface imports to mock");
+    } else {
+        for (interface, path, status) in &report {
+            println!("{status:>7} {interface} ({})", path.display());
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!(
+            "mocks do not match their WIT definitions:\n  {}",
+            failures.join("\n  ")
+        );
+    }
+    Ok(())
+}
+
+fn write_mock(path: &std::path::Path, yaml: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    atomic::write(path, yaml.as_bytes(), Durability::default())
+}
+
+pub(crate) fn parse_timestamp(value: &str) -> Result<OffsetDateTime, String> {
+    OffsetDateTime::parse(value, &Rfc3339)
+        .map_err(|err| format!("`{value}` is not an RFC 3339 timestamp: {err}"))
+}
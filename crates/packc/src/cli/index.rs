@@ -0,0 +1,101 @@
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Args, Parser};
+use greentic_pack::atomic::{self, Durability};
+use serde_json::json;
+use tracing::info;
+
+use crate::index::{self, IndexFilter, IndexStats};
+
+#[derive(Debug, Parser)]
+pub struct IndexArgs {
+    /// Directory scanned (recursively) for built `.gtpack` archives
+    #[arg(long = "dir", value_name = "DIR")]
+    pub dir: PathBuf,
+
+    /// Output path for the search index JSON
+    #[arg(long = "out", value_name = "FILE", default_value = "search-index.json")]
+    pub out: PathBuf,
+
+    #[command(flatten)]
+    pub filter: FilterArgs,
+
+    /// Rebuild every entry instead of reusing unchanged ones from --out
+    #[arg(long)]
+    pub full: bool,
+}
+
+/// `--tag`/`--category` filtering, shared by `index` and `describe`.
+#[derive(Debug, Args)]
+pub struct FilterArgs {
+    /// Only include packs carrying this tag (repeatable; all must match)
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// Only include packs in this category (repeatable; any may match)
+    #[arg(long = "category", value_name = "CATEGORY")]
+    pub categories: Vec<String>,
+}
+
+impl From<FilterArgs> for IndexFilter {
+    fn from(args: FilterArgs) -> Self {
+        Self {
+            tags: args.tags,
+            categories: args.categories,
+        }
+    }
+}
+
+pub fn handle(args: IndexArgs, json: bool) -> Result<()> {
+    info!(dir = %args.dir.display(), out = %args.out.display(), "building search index");
+
+    let existing = index::load_index(&args.out)?;
+    let previous = existing.as_ref().filter(|_| !args.full);
+    let filter = IndexFilter::from(args.filter);
+    let (mut search_index, stats) = index::build_index(&args.dir, previous, &filter)?;
+    if let Some(existing) = existing.filter(|_| args.full) {
+        // --full rebuilds the pack entries, not the advisories authored on top.
+        search_index.advisories = existing.advisories;
+    }
+
+    let serialized = serde_json::to_string_pretty(&search_index)?;
+    atomic::write(&args.out, serialized.as_bytes(), Durability::Buffered)?;
+
+    if json {
+        print_json(&args.out, &search_index, stats)?;
+    } else {
+        print_human(&args.out, &search_index, stats);
+    }
+    Ok(())
+}
+
+fn print_human(out: &std::path::Path, search_index: &index::SearchIndex, stats: IndexStats) {
+    println!(
+        "wrote {}\n  packs: {}\n  terms: {}\n  indexed: {}\n  reused: {}\n  removed: {}\n  filtered: {}",
+        out.display(),
+        search_index.packs.len(),
+        search_index.terms.len(),
+        stats.indexed,
+        stats.reused,
+        stats.removed,
+        stats.filtered
+    );
+}
+
+fn print_json(
+    out: &std::path::Path,
+    search_index: &index::SearchIndex,
+    stats: IndexStats,
+) -> Result<()> {
+    let payload = json!({
+        "out": out,
+        "packs": search_index.packs.len(),
+        "terms": search_index.terms.len(),
+        "stats": stats,
+    });
+    println!("{}", serde_json::to_string_pretty(&payload)?);
+    Ok(())
+}
@@ -0,0 +1,86 @@
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+use greentic_pack::SigningPolicy;
+
+use crate::inspect::{self, ArtifactKind, Inspection, SignatureStatus};
+
+#[derive(Debug, Parser)]
+pub struct InspectArgs {
+    /// Built `dist/manifest.cbor` or `.gtpack` archive
+    #[arg(value_name = "FILE")]
+    pub path: PathBuf,
+
+    /// Reject archives signed with the dev certificate
+    #[arg(long)]
+    pub strict: bool,
+}
+
+pub fn handle(args: InspectArgs, json: bool) -> Result<()> {
+    let policy = if args.strict {
+        SigningPolicy::Strict
+    } else {
+        SigningPolicy::DevOk
+    };
+    let inspection = inspect::inspect(&args.path, policy)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&inspection)?);
+    } else {
+        print_human(&inspection);
+    }
+    Ok(())
+}
+
+fn print_human(inspection: &Inspection) {
+    let artifact = match inspection.artifact {
+        ArtifactKind::Manifest => "manifest",
+        ArtifactKind::Gtpack => "gtpack",
+    };
+    println!("{}@{} ({artifact})", inspection.pack_id, inspection.version);
+    println!("flows:");
+    for flow in &inspection.flows {
+        match &flow.start {
+            Some(start) => println!("  {} [{}] start: {start}", flow.id, flow.flow_type),
+            None => println!("  {} [{}]", flow.id, flow.flow_type),
+        }
+    }
+    println!("templates: {}", list(&inspection.templates));
+    println!("imports: {}", list(&inspection.imports));
+    match &inspection.signature {
+        SignatureStatus::Verified {
+            alg,
+            signed_at_utc,
+            key_fingerprint,
+        } => {
+            let key = key_fingerprint
+                .as_deref()
+                .map(|key| format!(", key {key}"))
+                .unwrap_or_default();
+            println!("signature: verified ({alg}, signed {signed_at_utc}{key})");
+        }
+        SignatureStatus::Signed { algs } => {
+            println!(
+                "signature: {} on the pack manifest (check with `packc verify`)",
+                algs.join(" + ")
+            );
+        }
+        SignatureStatus::Unsigned => println!("signature: none"),
+    }
+    for finding in &inspection.findings {
+        println!(
+            "  {} {}: {}",
+            finding.severity, finding.code, finding.message
+        );
+    }
+}
+
+fn list(items: &[String]) -> String {
+    if items.is_empty() {
+        "-".to_string()
+    } else {
+        items.join(", ")
+    }
+}
@@ -1,62 +1,318 @@
 #![forbid(unsafe_code)]
 
-use std::path::PathBuf;
+use std::fmt;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
 use clap::Parser;
+use greentic_pack::capabilities::{FlowRequirements, flow_requirements};
+use greentic_pack::classification;
+use greentic_pack::metrics::metric_emissions;
+use greentic_pack::secrets::secret_refs;
+use serde::Serialize;
 use serde_json::json;
 use tracing::info;
 
-use crate::{flows, manifest, templates};
+use crate::assets::AssetGraph;
+use crate::digest::DigestAlgorithm;
+use crate::imports::{InterfaceCatalog, ResolvedImport};
+use crate::lockfile::PackLock;
+use crate::taxonomy::Taxonomy;
+use crate::{flows, imports, manifest, media, readme, templates};
+
+pub mod rules;
+mod workspace;
 
 #[derive(Debug, Parser)]
 pub struct LintArgs {
     /// Root directory of the pack (must contain pack.yaml)
-    #[arg(long = "in", value_name = "DIR")]
-    pub input: PathBuf,
+    #[arg(
+        long = "in",
+        value_name = "DIR",
+        required_unless_present = "workspace",
+        conflicts_with = "workspace"
+    )]
+    pub input: Option<PathBuf>,
+
+    /// Lint every pack under DIR in parallel and check them against each other
+    #[arg(long, value_name = "DIR")]
+    pub workspace: Option<PathBuf>,
+
+    /// Packs linted at once with --workspace (defaults to the number of CPUs)
+    #[arg(long, value_name = "N", requires = "workspace")]
+    pub jobs: Option<usize>,
+
+    /// Print --workspace diagnostics as SARIF 2.1.0
+    #[arg(long, requires = "workspace")]
+    pub sarif: bool,
+
+    /// Taxonomy file for tags/categories (defaults to <DIR>/taxonomy.toml, then the built-in one)
+    #[arg(long, value_name = "FILE")]
+    pub taxonomy: Option<PathBuf>,
+
+    /// Interface catalog for imports_required (defaults to <DIR>/interfaces.catalog.toml, then the built-in one)
+    #[arg(long, value_name = "FILE")]
+    pub interface_catalog: Option<PathBuf>,
 }
 
 pub fn handle(args: LintArgs, json: bool) -> Result<()> {
-    let pack_dir = normalize(args.input);
+    if let Some(root) = &args.workspace {
+        return workspace::handle(&normalize(root.clone()), &args, json);
+    }
+    let pack_dir = normalize(args.input.clone().expect("clap requires --in"));
     info!(path = %pack_dir.display(), "linting pack");
 
-    let spec_bundle = manifest::load_spec(&pack_dir)?;
-    let flows = flows::load_flows(&pack_dir, &spec_bundle.spec)?;
-    let templates = templates::collect_templates(&pack_dir, &spec_bundle.spec)?;
-    let events = spec_bundle
-        .spec
-        .events
-        .as_ref()
-        .map(|section| section.providers.len())
-        .unwrap_or(0);
+    let lint = lint_pack(
+        &pack_dir,
+        args.taxonomy.as_deref(),
+        args.interface_catalog.as_deref(),
+    )?;
+    let spec = &lint.spec;
 
-    // Building the manifest ensures flow/template metadata is well-formed.
-    let _manifest = manifest::build_manifest(&spec_bundle, &flows, &templates);
+    let errors = lint.errors();
+    let status = if errors > 0 { "failed" } else { "ok" };
 
     if json {
         let payload = json!({
-            "status": "ok",
-            "pack_id": spec_bundle.spec.id,
-            "version": spec_bundle.spec.version,
-            "flows": flows.len(),
-            "templates": templates.len(),
-            "events_providers": events,
+            "status": status,
+            "pack_id": spec.id,
+            "version": spec.version,
+            "flows": lint.flows,
+            "templates": lint.templates,
+            "events_providers": lint.events_providers,
+            "media": lint.media,
+            "readme": lint.readme,
+            "tags": spec.tags,
+            "categories": spec.categories,
+            "imports_resolved": lint.imports_resolved,
+            "warnings": lint.warnings().map(|warning| &warning.message).collect::<Vec<_>>(),
+            "diagnostics": lint.diagnostics,
         });
         println!("{}", serde_json::to_string_pretty(&payload)?);
     } else {
         println!(
-            "lint ok\n  pack: {}@{}\n  flows: {}\n  templates: {}\n  events.providers: {}",
-            spec_bundle.spec.id,
-            spec_bundle.spec.version,
-            flows.len(),
-            templates.len(),
-            events
+            "lint {}\n  pack: {}@{}\n  flows: {}\n  templates: {}\n  events.providers: {}",
+            status, spec.id, spec.version, lint.flows, lint.templates, lint.events_providers
         );
+        for import in &lint.imports_resolved {
+            println!("  import: {} -> {import}", import.import);
+        }
+        for diagnostic in &lint.diagnostics {
+            println!(
+                "  {}[{}] {diagnostic}",
+                diagnostic.severity, diagnostic.rule
+            );
+        }
     }
 
+    if errors > 0 {
+        bail!("{errors} lint error(s)");
+    }
     Ok(())
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+/// A lint finding in one file of the pack.
+#[derive(Debug, Clone, Serialize)]
+pub struct LintDiagnostic {
+    pub rule: &'static str,
+    pub severity: Severity,
+    /// Path relative to the pack root.
+    pub file: String,
+    /// 1-based line, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for LintDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{line}: {}", self.file, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+/// Outcome of linting one pack that passed every hard check.
+pub(crate) struct PackLint {
+    /// The spec after taxonomy normalisation.
+    pub spec: manifest::PackSpec,
+    pub flows: usize,
+    pub templates: usize,
+    pub events_providers: usize,
+    pub media: usize,
+    pub readme: bool,
+    pub imports_resolved: Vec<ResolvedImport>,
+    /// Rule findings at their configured levels; the pack fails lint if any
+    /// is an error.
+    pub diagnostics: Vec<LintDiagnostic>,
+}
+
+impl PackLint {
+    pub fn errors(&self) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .count()
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &LintDiagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Warning)
+    }
+}
+
+/// Runs every lint check on the pack in `pack_dir`. Errors are the first
+/// failure of a check that is not a rule; rule findings are collected.
+pub(crate) fn lint_pack(
+    pack_dir: &Path,
+    taxonomy: Option<&Path>,
+    interface_catalog: Option<&Path>,
+) -> Result<PackLint> {
+    let mut spec_bundle = manifest::load_spec(pack_dir)?;
+    let config = rules::LintConfig::load(pack_dir)?;
+    let taxonomy = Taxonomy::load(pack_dir, taxonomy)?;
+    let rewrites = taxonomy.apply(&mut spec_bundle.spec)?;
+    let catalog = InterfaceCatalog::load(pack_dir, interface_catalog)?;
+    let imports_resolved = imports::resolve_locked(
+        &catalog,
+        &spec_bundle.spec.imports_required,
+        PackLock::load(pack_dir)?.as_ref(),
+    )?;
+    let flows = flows::load_flows(pack_dir, &spec_bundle.spec, DigestAlgorithm::default())?;
+    let templates = templates::collect_templates(
+        pack_dir,
+        &spec_bundle.spec,
+        DigestAlgorithm::default(),
+        templates::SyntaxCheck::Parse,
+    )?;
+    let media_assets = media::collect_media(pack_dir, &spec_bundle.spec)?;
+    let readme = readme::load_readme(pack_dir, &flows, &templates, &media_assets)?;
+    let graph = AssetGraph::build(&templates, &spec_bundle.spec.template_dirs);
+    let missing: Vec<String> = graph
+        .missing()
+        .map(|r| format!("{} -> {}", r.from, r.target))
+        .collect();
+    if !missing.is_empty() {
+        bail!(
+            "templates reference assets that are not in the pack: {}",
+            missing.join(", ")
+        );
+    }
+    let unclassified = unclassified_flows(&spec_bundle.spec, &flows);
+    if !unclassified.is_empty() {
+        bail!(
+            "flows handling personal data must classify their inputs and outputs under `classification`: {}",
+            unclassified.join(", ")
+        );
+    }
+    let metric_problems = metric_problems(&spec_bundle.spec, &flows)?;
+    if !metric_problems.is_empty() {
+        bail!(
+            "flows emit metrics that are not declared under `metrics`: {}",
+            metric_problems.join("; ")
+        );
+    }
+    let secret_problems = secret_problems(&spec_bundle.spec, &flows)?;
+    if !secret_problems.is_empty() {
+        bail!(
+            "flows read secrets that are not declared under `secrets`: {}",
+            secret_problems.join("; ")
+        );
+    }
+    let readme_links = readme
+        .as_deref()
+        .map(greentic_pack::readme::relative_links)
+        .unwrap_or_default();
+    let mut findings = rules::missing_descriptions(&spec_bundle.spec, &flows);
+    findings.extend(rules::empty_flows(&flows));
+    findings.extend(rules::unused_templates(&graph.orphans(&readme_links)));
+    findings.extend(rules::oversized_assets(&media_assets));
+    findings.extend(rules::noncanonical_tags(&rewrites));
+    findings.extend(rules::flow_graphs(&flows)?);
+    let diagnostics = config.apply(findings);
+    let events_providers = spec_bundle
+        .spec
+        .events
+        .as_ref()
+        .map(|section| section.providers.len())
+        .unwrap_or(0);
+
+    // Building the manifest ensures flow/template metadata is well-formed.
+    let _manifest = manifest::build_manifest(&spec_bundle, &flows, &templates, "");
+
+    Ok(PackLint {
+        flows: flows.len(),
+        templates: templates.len(),
+        events_providers,
+        media: media_assets.len(),
+        readme: readme.is_some(),
+        imports_resolved,
+        diagnostics,
+        spec: spec_bundle.spec,
+    })
+}
+
+/// Flows that must classify their data but do not, as "flow (uses `cap`)".
+fn unclassified_flows(spec: &manifest::PackSpec, flows: &[flows::FlowAsset]) -> Vec<String> {
+    let requirements: Vec<(&str, FlowRequirements)> = flows
+        .iter()
+        .filter_map(|flow| {
+            let requirements = flow_requirements(&flow.raw, &spec.imports_required).ok()?;
+            Some((flow.bundle.id.as_str(), requirements))
+        })
+        .collect();
+    classification::unclassified_flows(
+        spec.classification.as_ref(),
+        requirements
+            .iter()
+            .map(|(flow, requirements)| (*flow, requirements)),
+    )
+    .into_iter()
+    .map(|(flow, capability)| format!("{flow} (uses `{capability}`)"))
+    .collect()
+}
+
+/// Metric nodes that name undeclared instruments or labels.
+fn metric_problems(spec: &manifest::PackSpec, flows: &[flows::FlowAsset]) -> Result<Vec<String>> {
+    let declared = spec.metrics.clone().unwrap_or_default();
+    let mut problems = Vec::new();
+    for flow in flows {
+        let emissions =
+            metric_emissions(&flow.raw).with_context(|| format!("flow `{}`", flow.bundle.id))?;
+        problems.extend(declared.check_emissions(&flow.bundle.id, &emissions));
+    }
+    Ok(problems)
+}
+
+/// `secret://` references to undeclared secrets.
+fn secret_problems(spec: &manifest::PackSpec, flows: &[flows::FlowAsset]) -> Result<Vec<String>> {
+    let declared = spec.secrets.clone().unwrap_or_default();
+    let mut problems = Vec::new();
+    for flow in flows {
+        let refs = secret_refs(&flow.raw).with_context(|| format!("flow `{}`", flow.bundle.id))?;
+        problems.extend(declared.check_refs(&flow.bundle.id, &refs));
+    }
+    Ok(problems)
+}
+
 fn normalize(path: PathBuf) -> PathBuf {
     if path.is_absolute() {
         path
@@ -0,0 +1,359 @@
+//! Named lint rules and the levels a pack configures for them.
+//!
+//! Checks a pack may reasonably tune are rules with a default level. A
+//! `packlint.toml` next to `pack.yaml` overrides the levels:
+//!
+//! ```toml
+//! [rules]
+//! missing-description = "error"
+//! unused-template = "off"
+//! ```
+//!
+//! Findings of a rule at `warn` are reported as warnings, at `error` they
+//! fail `packc lint`, and at `off` they are dropped. Checks that make a pack
+//! unbuildable, such as a template referencing a missing asset, are not rules
+//! and always fail.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_yaml_bw::Value as YamlValue;
+
+use super::{LintDiagnostic, Severity};
+use crate::flow_graph::GraphRule;
+use crate::flows::FlowAsset;
+use crate::manifest::PackSpec;
+use crate::media::{self, MediaAsset};
+use crate::taxonomy::TagRewrite;
+
+pub const CONFIG_FILE: &str = "packlint.toml";
+
+pub const MISSING_DESCRIPTION: &str = "missing-description";
+pub const EMPTY_FLOW: &str = "empty-flow";
+pub const UNUSED_TEMPLATE: &str = "unused-template";
+pub const OVERSIZED_ASSET: &str = "oversized-asset";
+pub const NONCANONICAL_TAG: &str = "noncanonical-tag";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Off,
+    Warn,
+    Error,
+}
+
+impl Level {
+    /// Severity findings are reported with, or `None` when the rule is off.
+    pub fn severity(self) -> Option<Severity> {
+        match self {
+            Level::Off => None,
+            Level::Warn => Some(Severity::Warning),
+            Level::Error => Some(Severity::Error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Rule {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub default: Level,
+}
+
+/// Every rule, in the order they are documented.
+pub const RULES: &[Rule] = &[
+    Rule {
+        name: MISSING_DESCRIPTION,
+        description: "The pack or a flow has no description",
+        default: Level::Warn,
+    },
+    Rule {
+        name: EMPTY_FLOW,
+        description: "A flow has no nodes",
+        default: Level::Warn,
+    },
+    Rule {
+        name: UNUSED_TEMPLATE,
+        description: "A template asset is not referenced by any template or the README",
+        default: Level::Warn,
+    },
+    Rule {
+        name: OVERSIZED_ASSET,
+        description: "An icon or screenshot exceeds the recommended size for listings",
+        default: Level::Warn,
+    },
+    Rule {
+        name: NONCANONICAL_TAG,
+        description: "A tag or category is not written in its canonical form",
+        default: Level::Warn,
+    },
+    Rule {
+        name: GraphRule::MissingStart.name(),
+        description: GraphRule::MissingStart.description(),
+        default: Level::Error,
+    },
+    Rule {
+        name: GraphRule::DanglingEdge.name(),
+        description: GraphRule::DanglingEdge.description(),
+        default: Level::Error,
+    },
+    Rule {
+        name: GraphRule::UnreachableNode.name(),
+        description: GraphRule::UnreachableNode.description(),
+        default: Level::Warn,
+    },
+    Rule {
+        name: GraphRule::InescapableCycle.name(),
+        description: GraphRule::InescapableCycle.description(),
+        default: Level::Error,
+    },
+];
+
+pub fn rule(name: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.name == name)
+}
+
+/// A rule violation before the configured level is applied.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub rule: &'static str,
+    /// Path relative to the pack root.
+    pub file: String,
+    /// 1-based line, when known.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Finding {
+    fn new(rule: &'static str, file: impl Into<String>, message: String) -> Self {
+        Self {
+            rule,
+            file: file.into(),
+            line: None,
+            message,
+        }
+    }
+}
+
+/// The `packlint.toml` of a pack.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LintConfig {
+    /// Levels by rule name; rules not listed keep their default.
+    #[serde(default)]
+    pub rules: BTreeMap<String, Level>,
+}
+
+impl LintConfig {
+    /// Loads `<pack_dir>/packlint.toml`, or the defaults when there is none.
+    pub fn load(pack_dir: &Path) -> Result<Self> {
+        let path = pack_dir.join(CONFIG_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let config: Self =
+            toml::from_str(&text).with_context(|| format!("{} is not valid", path.display()))?;
+        let unknown: Vec<&str> = config
+            .rules
+            .keys()
+            .map(String::as_str)
+            .filter(|name| rule(name).is_none())
+            .collect();
+        if !unknown.is_empty() {
+            bail!(
+                "{} configures unknown rule(s): {}",
+                path.display(),
+                unknown.join(", ")
+            );
+        }
+        Ok(config)
+    }
+
+    pub fn level(&self, rule_name: &str) -> Level {
+        self.rules
+            .get(rule_name)
+            .copied()
+            .or_else(|| rule(rule_name).map(|rule| rule.default))
+            .unwrap_or(Level::Warn)
+    }
+
+    /// Reports `findings` at their configured levels, dropping those of rules
+    /// that are off.
+    pub fn apply(&self, findings: Vec<Finding>) -> Vec<LintDiagnostic> {
+        findings
+            .into_iter()
+            .filter_map(|finding| {
+                let severity = self.level(finding.rule).severity()?;
+                Some(LintDiagnostic {
+                    rule: finding.rule,
+                    severity,
+                    file: finding.file,
+                    line: finding.line,
+                    message: finding.message,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The pack and flows without a description.
+pub fn missing_descriptions(spec: &PackSpec, flows: &[FlowAsset]) -> Vec<Finding> {
+    let blank = |text: Option<&str>| text.is_none_or(|text| text.trim().is_empty());
+    let mut findings = Vec::new();
+    if blank(spec.description.as_deref()) {
+        findings.push(Finding::new(
+            MISSING_DESCRIPTION,
+            "pack.yaml",
+            "pack has no `description`".to_string(),
+        ));
+    }
+    for flow in flows {
+        let source: Option<YamlValue> = serde_yaml_bw::from_str(&flow.raw).ok();
+        let description = source
+            .as_ref()
+            .and_then(|source| source.get("description"))
+            .and_then(YamlValue::as_str);
+        if blank(description) {
+            findings.push(Finding::new(
+                MISSING_DESCRIPTION,
+                flow_file(flow),
+                format!("flow `{}` has no `description`", flow.bundle.id),
+            ));
+        }
+    }
+    findings
+}
+
+pub fn empty_flows(flows: &[FlowAsset]) -> Vec<Finding> {
+    flows
+        .iter()
+        .filter(|flow| flow.bundle.nodes.is_empty())
+        .map(|flow| {
+            Finding::new(
+                EMPTY_FLOW,
+                flow_file(flow),
+                format!("flow `{}` has no nodes", flow.bundle.id),
+            )
+        })
+        .collect()
+}
+
+pub fn unused_templates(orphans: &[&str]) -> Vec<Finding> {
+    orphans
+        .iter()
+        .map(|path| {
+            Finding::new(
+                UNUSED_TEMPLATE,
+                *path,
+                format!("asset `{path}` is not referenced by any template"),
+            )
+        })
+        .collect()
+}
+
+pub fn oversized_assets(assets: &[MediaAsset]) -> Vec<Finding> {
+    assets
+        .iter()
+        .filter_map(|asset| {
+            let message = media::size_warning(asset)?;
+            Some(Finding::new(
+                OVERSIZED_ASSET,
+                asset.media.path.as_str(),
+                message,
+            ))
+        })
+        .collect()
+}
+
+pub fn noncanonical_tags(rewrites: &[TagRewrite]) -> Vec<Finding> {
+    rewrites
+        .iter()
+        .map(|rewrite| {
+            Finding::new(
+                NONCANONICAL_TAG,
+                "pack.yaml",
+                format!(
+                    "tag `{}` is normalised to `{}`; use the canonical form in pack.yaml",
+                    rewrite.original, rewrite.normalized
+                ),
+            )
+        })
+        .collect()
+}
+
+/// Problems with the node graphs of the flows.
+pub fn flow_graphs(flows: &[FlowAsset]) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    for flow in flows {
+        let file = flow_file(flow);
+        let graph = crate::flow_graph::FlowGraph::parse(&flow.raw)
+            .with_context(|| format!("flow `{file}`"))?;
+        findings.extend(
+            graph
+                .check(&flow.bundle.entry)
+                .into_iter()
+                .map(|finding| Finding {
+                    rule: finding.rule.name(),
+                    file: file.clone(),
+                    line: finding.line,
+                    message: finding.message,
+                }),
+        );
+    }
+    Ok(findings)
+}
+
+fn flow_file(flow: &FlowAsset) -> String {
+    flow.relative_path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn packlint_overrides_rule_levels() {
+        let dir = tempdir().unwrap();
+        assert_eq!(
+            LintConfig::load(dir.path()).unwrap().level(EMPTY_FLOW),
+            Level::Warn
+        );
+
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            "[rules]\nempty-flow = \"error\"\nunused-template = \"off\"\n",
+        )
+        .unwrap();
+        let config = LintConfig::load(dir.path()).unwrap();
+        let diagnostics = config.apply(vec![
+            Finding::new(EMPTY_FLOW, "flows/a.ygtc", "empty".into()),
+            Finding::new(UNUSED_TEMPLATE, "templates/x.png", "unused".into()),
+            Finding::new(MISSING_DESCRIPTION, "pack.yaml", "none".into()),
+        ]);
+        let levels: Vec<_> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.rule, diagnostic.severity))
+            .collect();
+        assert_eq!(
+            levels,
+            [
+                (EMPTY_FLOW, Severity::Error),
+                (MISSING_DESCRIPTION, Severity::Warning),
+            ]
+        );
+
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            "[rules]\nmissing-docs = \"warn\"\n",
+        )
+        .unwrap();
+        let err = LintConfig::load(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("missing-docs"), "{err}");
+    }
+}
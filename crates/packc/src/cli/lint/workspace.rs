@@ -0,0 +1,380 @@
+//! `packc lint --workspace`: lints every pack under a root in parallel, then
+//! checks the packs against each other for problems no single pack shows.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use greentic_pack::events::EventProviderCapabilities;
+use serde::Serialize;
+use serde_json::json;
+use tracing::info;
+
+use super::{LintArgs, PackLint, Severity, lint_pack, rules};
+use crate::{manifest, workspace};
+
+/// Rules reported by workspace lint besides the pack rules, with their SARIF
+/// descriptions.
+const RULES: &[(&str, &str)] = &[
+    ("pack-invalid", "The pack fails lint"),
+    (
+        "duplicate-pack-id",
+        "Several packs in the workspace share an id",
+    ),
+    (
+        "conflicting-topic",
+        "Packs declare the same event topic with different delivery guarantees",
+    ),
+];
+
+#[derive(Debug, Serialize)]
+struct Diagnostic {
+    /// Pack id, or the pack path when the id could not be read.
+    pack: String,
+    /// Pack directory relative to the workspace root.
+    path: String,
+    severity: Severity,
+    rule: &'static str,
+    message: String,
+    /// File the diagnostic is about, relative to the pack directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct PackStatus {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    errors: usize,
+    warnings: usize,
+}
+
+impl PackStatus {
+    fn label(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.path)
+    }
+
+    fn status(&self) -> &'static str {
+        if self.errors > 0 {
+            "failed"
+        } else if self.warnings > 0 {
+            "warn"
+        } else {
+            "ok"
+        }
+    }
+}
+
+pub(super) fn handle(root: &Path, args: &LintArgs, json: bool) -> Result<()> {
+    let dirs = workspace::discover(root)?;
+    if dirs.is_empty() {
+        bail!(
+            "no packs (directories with pack.yaml) under {}",
+            root.display()
+        );
+    }
+    let jobs = args.jobs.unwrap_or_else(workspace::default_jobs);
+    info!(root = %root.display(), packs = dirs.len(), jobs, "linting workspace");
+
+    let results = workspace::map_parallel(&dirs, jobs, |dir| {
+        lint_pack(
+            dir,
+            args.taxonomy.as_deref(),
+            args.interface_catalog.as_deref(),
+        )
+    });
+
+    let mut statuses = Vec::new();
+    let mut diagnostics = Vec::new();
+    let mut linted = Vec::new();
+    for (dir, result) in dirs.iter().zip(results) {
+        let path = relative(root, dir);
+        let mut status = PackStatus {
+            path: path.clone(),
+            id: None,
+            version: None,
+            errors: 0,
+            warnings: 0,
+        };
+        match result {
+            Ok(lint) => {
+                status.id = Some(lint.spec.id.clone());
+                status.version = Some(lint.spec.version.clone());
+                for diagnostic in &lint.diagnostics {
+                    diagnostics.push(Diagnostic {
+                        pack: lint.spec.id.clone(),
+                        path: path.clone(),
+                        severity: diagnostic.severity,
+                        rule: diagnostic.rule,
+                        message: diagnostic.to_string(),
+                        file: Some(diagnostic.file.clone()),
+                        line: diagnostic.line,
+                    });
+                }
+                linted.push((statuses.len(), lint));
+            }
+            Err(err) => {
+                status.id = manifest::pack_id(dir).ok().flatten();
+                diagnostics.push(Diagnostic {
+                    pack: status.label().to_string(),
+                    path: path.clone(),
+                    severity: Severity::Error,
+                    rule: "pack-invalid",
+                    message: format!("{err:#}"),
+                    file: None,
+                    line: None,
+                });
+            }
+        }
+        statuses.push(status);
+    }
+    diagnostics.extend(duplicate_ids(&statuses));
+    diagnostics.extend(conflicting_topics(&statuses, &linted));
+
+    for diagnostic in &diagnostics {
+        if let Some(status) = statuses.iter_mut().find(|s| s.path == diagnostic.path) {
+            match diagnostic.severity {
+                Severity::Error => status.errors += 1,
+                Severity::Warning => status.warnings += 1,
+            }
+        }
+    }
+
+    if args.sarif {
+        println!("{}", serde_json::to_string_pretty(&sarif(&diagnostics))?);
+    } else if json {
+        let payload = json!({
+            "status": if statuses.iter().any(|s| s.errors > 0) { "failed" } else { "ok" },
+            "packs": statuses,
+            "diagnostics": diagnostics,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        print_human(&statuses, &diagnostics);
+    }
+
+    let failed = statuses.iter().filter(|s| s.errors > 0).count();
+    if failed > 0 {
+        bail!("{failed} of {} pack(s) failed lint", statuses.len());
+    }
+    Ok(())
+}
+
+fn duplicate_ids(statuses: &[PackStatus]) -> Vec<Diagnostic> {
+    let mut by_id: BTreeMap<&str, Vec<&PackStatus>> = BTreeMap::new();
+    for status in statuses {
+        if let Some(id) = &status.id {
+            by_id.entry(id).or_default().push(status);
+        }
+    }
+    by_id
+        .into_iter()
+        .filter(|(_, packs)| packs.len() > 1)
+        .flat_map(|(id, packs)| {
+            let paths: Vec<&str> = packs.iter().map(|s| s.path.as_str()).collect();
+            packs.into_iter().map(move |status| Diagnostic {
+                pack: id.to_string(),
+                path: status.path.clone(),
+                severity: Severity::Error,
+                rule: "duplicate-pack-id",
+                message: format!("pack id `{id}` is used by {}", paths.join(", ")),
+                file: None,
+                line: None,
+            })
+        })
+        .collect()
+}
+
+/// Topics that providers in different packs declare with different
+/// transport, reliability, or ordering.
+fn conflicting_topics(statuses: &[PackStatus], linted: &[(usize, PackLint)]) -> Vec<Diagnostic> {
+    struct Declaration<'a> {
+        status: &'a PackStatus,
+        provider: &'a str,
+        capabilities: &'a EventProviderCapabilities,
+    }
+
+    let mut by_topic: BTreeMap<&str, Vec<Declaration<'_>>> = BTreeMap::new();
+    for (index, lint) in linted {
+        let Some(events) = &lint.spec.events else {
+            continue;
+        };
+        for provider in &events.providers {
+            for topic in &provider.capabilities.topics {
+                by_topic.entry(topic).or_default().push(Declaration {
+                    status: &statuses[*index],
+                    provider: &provider.name,
+                    capabilities: &provider.capabilities,
+                });
+            }
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    for (topic, declarations) in by_topic {
+        let conflict = declarations.iter().any(|a| {
+            declarations.iter().any(|b| {
+                a.status.path != b.status.path && !same_guarantees(a.capabilities, b.capabilities)
+            })
+        });
+        if !conflict {
+            continue;
+        }
+        let described: Vec<String> = declarations
+            .iter()
+            .map(|declaration| {
+                format!(
+                    "{} ({}: {})",
+                    declaration.status.label(),
+                    declaration.provider,
+                    guarantees(declaration.capabilities)
+                )
+            })
+            .collect();
+        for declaration in &declarations {
+            diagnostics.push(Diagnostic {
+                pack: declaration.status.label().to_string(),
+                path: declaration.status.path.clone(),
+                severity: Severity::Error,
+                rule: "conflicting-topic",
+                message: format!(
+                    "topic `{topic}` is declared with conflicting guarantees: {}",
+                    described.join("; ")
+                ),
+                file: None,
+                line: None,
+            });
+        }
+    }
+    diagnostics
+}
+
+fn same_guarantees(a: &EventProviderCapabilities, b: &EventProviderCapabilities) -> bool {
+    a.transport == b.transport && a.reliability == b.reliability && a.ordering == b.ordering
+}
+
+fn guarantees(capabilities: &EventProviderCapabilities) -> String {
+    let unset = || "unset".to_string();
+    format!(
+        "transport {}, reliability {}, ordering {}",
+        capabilities
+            .transport
+            .as_ref()
+            .map_or_else(unset, ToString::to_string),
+        capabilities
+            .reliability
+            .as_ref()
+            .map_or_else(unset, ToString::to_string),
+        capabilities
+            .ordering
+            .as_ref()
+            .map_or_else(unset, ToString::to_string),
+    )
+}
+
+fn print_human(statuses: &[PackStatus], diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        println!(
+            "{}[{}] {}: {}",
+            diagnostic.severity, diagnostic.rule, diagnostic.pack, diagnostic.message
+        );
+    }
+    if !diagnostics.is_empty() {
+        println!();
+    }
+
+    let rows: Vec<[String; 6]> = statuses
+        .iter()
+        .map(|status| {
+            [
+                status.label().to_string(),
+                status.version.clone().unwrap_or_else(|| "-".to_string()),
+                status.status().to_string(),
+                status.errors.to_string(),
+                status.warnings.to_string(),
+                status.path.clone(),
+            ]
+        })
+        .collect();
+    let header = ["PACK", "VERSION", "STATUS", "ERRORS", "WARNINGS", "PATH"].map(String::from);
+    let mut widths = header.clone().map(|cell| cell.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    for row in std::iter::once(&header).chain(&rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+}
+
+fn sarif(diagnostics: &[Diagnostic]) -> serde_json::Value {
+    let rules: Vec<_> = RULES
+        .iter()
+        .copied()
+        .chain(
+            rules::RULES
+                .iter()
+                .map(|rule| (rule.name, rule.description)),
+        )
+        .map(|(id, description)| json!({ "id": id, "shortDescription": { "text": description } }))
+        .collect();
+    let results: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let file = diagnostic.file.as_deref().unwrap_or("pack.yaml");
+            let uri = if diagnostic.path == "." {
+                file.to_string()
+            } else {
+                format!("{}/{file}", diagnostic.path)
+            };
+            let mut location = json!({ "artifactLocation": { "uri": uri } });
+            if let Some(line) = diagnostic.line {
+                location["region"] = json!({ "startLine": line });
+            }
+            json!({
+                "ruleId": diagnostic.rule,
+                "level": diagnostic.severity,
+                "message": { "text": diagnostic.message },
+                "locations": [{ "physicalLocation": location }],
+                "properties": { "pack": diagnostic.pack },
+            })
+        })
+        .collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "packc",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn relative(root: &Path, dir: &Path) -> String {
+    let relative = dir.strip_prefix(root).unwrap_or(dir);
+    if relative.as_os_str().is_empty() {
+        return ".".to_string();
+    }
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
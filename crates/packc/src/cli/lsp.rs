@@ -0,0 +1,186 @@
+#![forbid(unsafe_code)]
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+    Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{Completion, GotoDefinition, Request as _};
+use lsp_types::{
+    CompletionOptions, CompletionParams, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DidSaveTextDocumentParams, GotoDefinitionParams,
+    GotoDefinitionResponse, Location, OneOf, PublishDiagnosticsParams, SaveOptions,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
+    TextDocumentSyncSaveOptions, Uri,
+};
+use tracing::warn;
+
+use crate::lsp;
+
+#[derive(Debug, Parser)]
+pub struct LspArgs {
+    /// Talk over stdin/stdout (the default and only transport; accepted
+    /// because editors pass it)
+    #[arg(long)]
+    pub stdio: bool,
+}
+
+pub fn handle(_args: LspArgs, _json: bool) -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    serve(&connection)?;
+    drop(connection);
+    io_threads.join().context("language server I/O failed")?;
+    Ok(())
+}
+
+fn serve(connection: &Connection) -> Result<()> {
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Options(
+            TextDocumentSyncOptions {
+                open_close: Some(true),
+                change: Some(TextDocumentSyncKind::FULL),
+                save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
+                    include_text: Some(false),
+                })),
+                ..TextDocumentSyncOptions::default()
+            },
+        )),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![" ".into(), ":".into()]),
+            ..CompletionOptions::default()
+        }),
+        definition_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    };
+    connection
+        .initialize(serde_json::to_value(capabilities)?)
+        .context("language server initialisation failed")?;
+
+    let mut documents: HashMap<String, String> = HashMap::new();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                let response = respond(&documents, request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                if let Some(uri) = update(&mut documents, notification) {
+                    publish(connection, &documents, uri)?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Applies a document notification; returns the URI whose diagnostics changed.
+fn update(documents: &mut HashMap<String, String>, notification: Notification) -> Option<Uri> {
+    let method = notification.method.clone();
+    match method.as_str() {
+        DidOpenTextDocument::METHOD => {
+            let params: DidOpenTextDocumentParams = notification.extract(&method).ok()?;
+            let uri = params.text_document.uri;
+            documents.insert(uri.to_string(), params.text_document.text);
+            Some(uri)
+        }
+        DidChangeTextDocument::METHOD => {
+            let params: DidChangeTextDocumentParams = notification.extract(&method).ok()?;
+            let text = params.content_changes.into_iter().last()?.text;
+            let uri = params.text_document.uri;
+            documents.insert(uri.to_string(), text);
+            Some(uri)
+        }
+        DidSaveTextDocument::METHOD => {
+            let params: DidSaveTextDocumentParams = notification.extract(&method).ok()?;
+            Some(params.text_document.uri)
+        }
+        DidCloseTextDocument::METHOD => {
+            let params: DidCloseTextDocumentParams = notification.extract(&method).ok()?;
+            documents.remove(params.text_document.uri.as_str());
+            None
+        }
+        _ => None,
+    }
+}
+
+fn publish(connection: &Connection, documents: &HashMap<String, String>, uri: Uri) -> Result<()> {
+    let diagnostics = document(documents, &uri)
+        .map(|(path, text)| lsp::diagnostics(&path, text))
+        .unwrap_or_default();
+    let params = PublishDiagnosticsParams {
+        uri,
+        diagnostics,
+        version: None,
+    };
+    connection
+        .sender
+        .send(Message::Notification(Notification::new(
+            PublishDiagnostics::METHOD.to_string(),
+            params,
+        )))?;
+    Ok(())
+}
+
+fn respond(documents: &HashMap<String, String>, request: Request) -> Response {
+    let id = request.id.clone();
+    let result = match request.method.as_str() {
+        Completion::METHOD => {
+            request
+                .extract::<CompletionParams>(Completion::METHOD)
+                .map(|(_, params)| {
+                    let position = params.text_document_position;
+                    let items = document(documents, &position.text_document.uri)
+                        .map(|(path, text)| lsp::completions(&path, text, position.position))
+                        .unwrap_or_default();
+                    serde_json::to_value(items)
+                })
+        }
+        GotoDefinition::METHOD => request
+            .extract::<GotoDefinitionParams>(GotoDefinition::METHOD)
+            .map(|(_, params)| {
+                let position = params.text_document_position_params;
+                let uri = position.text_document.uri;
+                let target = documents
+                    .get(uri.as_str())
+                    .and_then(|text| lsp::definition(text, position.position))
+                    .map(|range| GotoDefinitionResponse::Scalar(Location { uri, range }));
+                serde_json::to_value(target)
+            }),
+        method => {
+            warn!(method, "unsupported language server request");
+            return Response::new_err(
+                id,
+                lsp_server::ErrorCode::MethodNotFound as i32,
+                format!("unsupported request `{method}`"),
+            );
+        }
+    };
+    match result {
+        Ok(Ok(value)) => Response::new_ok(id, value),
+        Ok(Err(err)) => Response::new_err(
+            id,
+            lsp_server::ErrorCode::InternalError as i32,
+            err.to_string(),
+        ),
+        Err(err) => Response::new_err(
+            id,
+            lsp_server::ErrorCode::InvalidParams as i32,
+            err.to_string(),
+        ),
+    }
+}
+
+/// The local path and open text of `uri`.
+fn document<'a>(documents: &'a HashMap<String, String>, uri: &Uri) -> Option<(PathBuf, &'a str)> {
+    let text = documents.get(uri.as_str())?;
+    Some((lsp::uri_to_path(uri.as_str())?, text))
+}
@@ -1,17 +1,46 @@
 #![forbid(unsafe_code)]
 
-use std::{convert::TryFrom, path::PathBuf};
+use std::{convert::TryFrom, path::PathBuf, time::Instant};
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use greentic_types::{EnvId, TenantCtx, TenantId};
 
+use crate::digest::DigestAlgorithm;
 use crate::telemetry::set_current_tenant_ctx;
+use greentic_pack::builder::ManifestEncoding;
 
-use crate::{build, new};
+use crate::{build, new, usage};
 
+pub mod add;
+pub mod advisory;
+pub mod audit;
+pub mod bundle;
+pub mod cache;
+pub mod compat;
+pub mod compat_matrix;
+pub mod debug;
+pub mod dedup;
+pub mod describe;
+pub mod diff;
+pub mod fuzz;
+pub mod graph;
+pub mod imports;
+pub mod index;
+pub mod inspect;
 pub mod lint;
+pub mod lsp;
+pub mod publish;
+pub mod pull;
+pub mod rebuild;
+pub mod run;
+pub mod schema;
+pub mod search;
+pub mod serve_ui;
 pub mod sign;
+pub mod store;
+pub mod telemetry;
+pub mod tools;
 pub mod verify;
 
 #[derive(Debug, Parser)]
@@ -31,16 +60,119 @@ pub struct Cli {
 
 #[derive(Debug, Subcommand)]
 pub enum Command {
+    /// Fetch a pack from a registry index, resolving a version range and verifying its digests
+    Add(add::AddArgs),
+    /// Scaffold a flow from a template, list it in pack.yaml, and lint the pack
+    AddFlow(new::AddFlowArgs),
+    /// Author upgrade advisories in a search index and check installed packs against them
+    #[command(subcommand)]
+    Advisory(advisory::AdvisoryCommand),
+    /// Bundle a pack's signature status, SBOM, capabilities, and policy results for review
+    Audit(audit::AuditArgs),
     /// Build a pack component and supporting artifacts
     Build(BuildArgs),
+    /// Package a .gtpack, and with --self-contained the packs it depends on, into one installable bundle
+    Bundle(bundle::BundleArgs),
+    /// Inspect, clean, and verify packc's local caches
+    #[command(subcommand)]
+    Cache(cache::CacheCommand),
+    /// Check whether a new build of a pack is backwards compatible and suggest a version bump
+    Compat(compat::CompatArgs),
+    /// Check a pack against several host versions and report which can run it
+    CompatMatrix(compat_matrix::CompatMatrixArgs),
+    /// Step through a run recorded with `packc run --trace` and export a replay test from it
+    Debug(debug::DebugArgs),
+    /// Report template assets copied between the packs of a workspace
+    Dedup(dedup::DedupArgs),
+    /// Summarise a pack and what each flow uses
+    Describe(describe::DescribeArgs),
+    /// Compare two builds of a pack, classifying manifest changes by severity
+    Diff(diff::DiffArgs),
+    /// Run the workspace's fuzz targets (C ABI shims, manifest and archive decoders)
+    Fuzz(fuzz::FuzzArgs),
+    /// Show the reference graph between a pack's assets, or its flow nodes
+    Graph(graph::GraphArgs),
+    /// Manage the interface catalog used to resolve imports_required
+    #[command(subcommand)]
+    Imports(imports::ImportsCommand),
+    /// Build a search index across a directory of .gtpack archives
+    Index(index::IndexArgs),
+    /// Show the flows, templates, imports, and signature of a built manifest or .gtpack
+    Inspect(inspect::InspectArgs),
     /// Lint a pack manifest, flows, and templates
     Lint(lint::LintArgs),
+    /// Run a language server for flows and pack.yaml over stdio
+    Lsp(lsp::LspArgs),
     /// Scaffold a new pack directory
     New(new::NewArgs),
+    /// Publish a .gtpack to a registry directory, uploading only chunks it lacks, or yank a version
+    Publish(publish::PublishArgs),
+    /// Fetch a pack from a registry directory, downloading only chunks not held locally
+    Pull(pull::PullArgs),
+    /// Rebuild a .gtpack archive from its own contents
+    Rebuild(rebuild::RebuildArgs),
+    /// Run a flow of a built pack component locally with JSON input
+    Run(run::RunArgs),
+    /// Print or write the JSON Schema for flows or pack.yaml, with editor settings
+    Schema(schema::SchemaArgs),
+    /// Search a registry index for packs
+    Search(search::SearchArgs),
+    /// Serve a web page showing a .gtpack's manifest, flow graphs, SBOM, signature, and files
+    ServeUi(serve_ui::ServeUiArgs),
     /// Sign a pack manifest using an Ed25519 private key
     Sign(sign::SignArgs),
+    /// Export or import a pack store's state, or report its packs' health
+    #[command(subcommand)]
+    Store(store::StoreCommand),
     /// Verify a pack's manifest signature
     Verify(verify::VerifyArgs),
+    /// Manage anonymous usage reporting consent
+    #[command(subcommand)]
+    Telemetry(telemetry::TelemetryCommand),
+    /// Install and list the native helper tools pinned in toolchain.toml
+    #[command(subcommand)]
+    Tools(tools::ToolsCommand),
+}
+
+impl Command {
+    /// Subcommand name recorded by usage reporting.
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Add(_) => "add",
+            Command::AddFlow(_) => "add-flow",
+            Command::Advisory(_) => "advisory",
+            Command::Audit(_) => "audit",
+            Command::Build(_) => "build",
+            Command::Bundle(_) => "bundle",
+            Command::Cache(_) => "cache",
+            Command::Compat(_) => "compat",
+            Command::CompatMatrix(_) => "compat-matrix",
+            Command::Debug(_) => "debug",
+            Command::Dedup(_) => "dedup",
+            Command::Describe(_) => "describe",
+            Command::Diff(_) => "diff",
+            Command::Fuzz(_) => "fuzz",
+            Command::Graph(_) => "graph",
+            Command::Imports(_) => "imports",
+            Command::Index(_) => "index",
+            Command::Inspect(_) => "inspect",
+            Command::Lint(_) => "lint",
+            Command::Lsp(_) => "lsp",
+            Command::New(_) => "new",
+            Command::Publish(_) => "publish",
+            Command::Pull(_) => "pull",
+            Command::Rebuild(_) => "rebuild",
+            Command::Run(_) => "run",
+            Command::Schema(_) => "schema",
+            Command::Search(_) => "search",
+            Command::ServeUi(_) => "serve-ui",
+            Command::Sign(_) => "sign",
+            Command::Store(_) => "store",
+            Command::Verify(_) => "verify",
+            Command::Telemetry(_) => "telemetry",
+            Command::Tools(_) => "tools",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Parser)]
@@ -65,13 +197,94 @@ pub struct BuildArgs {
     #[arg(long = "gtpack-out", value_name = "FILE")]
     pub gtpack_out: Option<PathBuf>,
 
+    /// Manifest entries in the .gtpack: plain (manifest.cbor), zstd (manifest.cbor.zst), or both
+    #[arg(
+        long = "manifest-encoding",
+        value_name = "ENCODING",
+        default_value = "plain",
+        requires = "gtpack_out"
+    )]
+    pub manifest_encoding: ManifestEncoding,
+
+    /// Host profile data (JSON flow run and template render counts) that orders the embedded
+    /// flows and templates and marks hot flows for eager instantiation
+    #[arg(long = "profile-data", value_name = "FILE")]
+    pub profile_data: Option<PathBuf>,
+
     /// Optional override for the generated component data source file
     #[arg(long = "component-data", value_name = "FILE")]
     pub component_data: Option<PathBuf>,
 
+    /// Use an already compiled pack component instead of building one with cargo
+    #[arg(long = "component-wasm", value_name = "FILE")]
+    pub component_wasm: Option<PathBuf>,
+
+    /// Compile the pack component inside a pinned builder image instead of with the host toolchain
+    #[arg(long, conflicts_with = "component_wasm")]
+    pub hermetic: bool,
+
+    /// Builder image for --hermetic (defaults to $PACKC_BUILDER_IMAGE, then rust:1.89-slim)
+    #[arg(long, value_name = "IMAGE", requires = "hermetic")]
+    pub builder_image: Option<String>,
+
+    /// Cache directory mounted into the builder image (defaults to ~/.cache/greentic/hermetic)
+    #[arg(long, value_name = "DIR", requires = "hermetic")]
+    pub build_cache: Option<PathBuf>,
+
+    /// Compile the pack component on a build service, building locally when it is unreachable
+    #[arg(long, value_name = "URL", conflicts_with = "component_wasm")]
+    pub remote_build: Option<String>,
+
+    /// Taxonomy file for tags/categories (defaults to <DIR>/taxonomy.toml, then the built-in one)
+    #[arg(long, value_name = "FILE")]
+    pub taxonomy: Option<PathBuf>,
+
+    /// Interface catalog (.toml or registry .gtpack) for imports_required
+    /// (defaults to <DIR>/interfaces.catalog.toml, then the built-in one)
+    #[arg(long = "interface-catalog", value_name = "FILE")]
+    pub interface_catalog: Option<PathBuf>,
+
     /// When set, the command validates input without writing artifacts
     #[arg(long)]
     pub dry_run: bool,
+
+    /// Fail instead of updating pack.lock when the resolved imports, components, or templates differ from it
+    #[arg(long)]
+    pub locked: bool,
+
+    /// Build timestamp (RFC 3339 or Unix seconds) recorded in the manifest, SBOM, and .gtpack;
+    /// overrides $SOURCE_DATE_EPOCH and makes the archive byte-for-byte reproducible
+    #[arg(long, value_name = "TIMESTAMP", value_parser = build::parse_timestamp)]
+    pub timestamp: Option<time::OffsetDateTime>,
+
+    /// Package Handlebars and Liquid templates without checking that they parse
+    #[arg(long)]
+    pub no_validate_templates: bool,
+
+    /// fsync each artifact before it atomically replaces the previous one
+    #[arg(long)]
+    pub fsync: bool,
+
+    /// Recompile the pack component even when .packc/cache holds one built from the same inputs
+    #[arg(long)]
+    pub force: bool,
+
+    /// Build the pack component with its `no-wasi` feature and fail if it still imports WASI
+    /// filesystem or clock interfaces
+    #[arg(long)]
+    pub assert_no_wasi: bool,
+
+    /// Digest algorithm for flow and template entries in the manifest and SBOM
+    #[arg(long = "digest-alg", value_enum, value_name = "ALG", default_value_t = DigestAlgorithm::Sha256)]
+    pub digest_alg: DigestAlgorithm,
+
+    /// Wait for a concurrent build/sign of the same pack directory to finish
+    #[arg(long, overrides_with = "no_wait")]
+    pub wait: bool,
+
+    /// Fail immediately if another build/sign holds the pack directory (default)
+    #[arg(long = "no-wait", overrides_with = "wait")]
+    pub no_wait: bool,
 }
 
 pub fn run() -> Result<()> {
@@ -90,13 +303,49 @@ pub fn run_with_cli(cli: Cli) -> Result<()> {
         TenantId::try_from("packc").expect("static tenant id"),
     ));
 
-    match cli.command {
-        Command::Build(args) => build::run(&build::BuildOptions::from(args))?,
-        Command::Lint(args) => lint::handle(args, cli.json)?,
-        Command::New(args) => new::handle(args, cli.json)?,
-        Command::Sign(args) => sign::handle(args, cli.json)?,
-        Command::Verify(args) => verify::handle(args, cli.json)?,
+    let command_name = cli.command.name();
+    let report_usage = !matches!(cli.command, Command::Telemetry(_)) && usage::reporting_enabled();
+    let started = Instant::now();
+
+    let result = match cli.command {
+        Command::Add(args) => add::handle(args, cli.json),
+        Command::AddFlow(args) => new::handle_add_flow(args, cli.json),
+        Command::Advisory(command) => advisory::handle(command, cli.json),
+        Command::Audit(args) => audit::handle(args, cli.json),
+        Command::Build(args) => build::run(&build::BuildOptions::from(args)),
+        Command::Bundle(args) => bundle::handle(args, cli.json),
+        Command::Cache(command) => cache::handle(command, cli.json),
+        Command::Compat(args) => compat::handle(args, cli.json),
+        Command::CompatMatrix(args) => compat_matrix::handle(args, cli.json),
+        Command::Debug(args) => debug::handle(args, cli.json),
+        Command::Dedup(args) => dedup::handle(args, cli.json),
+        Command::Describe(args) => describe::handle(args, cli.json),
+        Command::Diff(args) => diff::handle(args, cli.json),
+        Command::Fuzz(args) => fuzz::handle(args, cli.json),
+        Command::Graph(args) => graph::handle(args, cli.json),
+        Command::Imports(command) => imports::handle(command, cli.json),
+        Command::Index(args) => index::handle(args, cli.json),
+        Command::Inspect(args) => inspect::handle(args, cli.json),
+        Command::Lint(args) => lint::handle(args, cli.json),
+        Command::Lsp(args) => lsp::handle(args, cli.json),
+        Command::New(args) => new::handle(args, cli.json),
+        Command::Publish(args) => publish::handle(args, cli.json),
+        Command::Pull(args) => pull::handle(args, cli.json),
+        Command::Rebuild(args) => rebuild::handle(args, cli.json),
+        Command::Run(args) => run::handle(args, cli.json),
+        Command::Schema(args) => schema::handle(args, cli.json),
+        Command::Search(args) => search::handle(args, cli.json),
+        Command::ServeUi(args) => serve_ui::handle(args, cli.json),
+        Command::Sign(args) => sign::handle(args, cli.json),
+        Command::Store(command) => store::handle(command, cli.json),
+        Command::Verify(args) => verify::handle(args, cli.json),
+        Command::Telemetry(command) => telemetry::handle(command, cli.json),
+        Command::Tools(command) => tools::handle(command, cli.json),
+    };
+
+    if report_usage {
+        usage::record(command_name, started.elapsed(), result.is_ok());
     }
 
-    Ok(())
+    result
 }
@@ -0,0 +1,80 @@
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+use serde_json::json;
+
+use super::pull::parse_pack_ref;
+use crate::registry::{self, Transfer, TransferStatus};
+
+#[derive(Debug, Parser)]
+pub struct PublishArgs {
+    /// .gtpack archive to publish
+    #[arg(
+        long = "in",
+        value_name = "FILE",
+        required_unless_present = "yank",
+        conflicts_with = "yank"
+    )]
+    pub input: Option<PathBuf>,
+
+    /// Registry directory
+    #[arg(long, value_name = "DIR")]
+    pub registry: PathBuf,
+
+    /// Mark a published version, given as <PACK_ID>@<VERSION>, as yanked
+    #[arg(long, value_name = "PACK")]
+    pub yank: Option<String>,
+
+    /// Why the version is yanked, shown to anyone pulling it
+    #[arg(long, value_name = "TEXT", requires = "yank")]
+    pub reason: Option<String>,
+}
+
+pub fn handle(args: PublishArgs, json: bool) -> Result<()> {
+    if let Some(pack) = &args.yank {
+        let (pack_id, version) = parse_pack_ref(pack)?;
+        let yank = registry::yank(&args.registry, pack_id, version, args.reason)?;
+        if json {
+            let payload = json!({
+                "status": "yanked",
+                "pack_id": pack_id,
+                "version": version,
+                "yanked_at": yank.yanked_at,
+                "reason": yank.reason,
+            });
+            println!("{}", serde_json::to_string_pretty(&payload)?);
+        } else {
+            println!("yanked {pack_id}@{version} (since {})", yank.yanked_at);
+        }
+        return Ok(());
+    }
+    let input = args.input.expect("clap requires --in");
+    let transfer = registry::publish(&args.registry, &input)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&transfer)?);
+    } else if transfer.status == TransferStatus::Unchanged {
+        println!(
+            "{}@{} is already published with the same contents",
+            transfer.pack_id, transfer.version
+        );
+    } else {
+        print_transfer("published", "uploaded", &transfer);
+    }
+    Ok(())
+}
+
+pub(crate) fn print_transfer(verb: &str, moved: &str, transfer: &Transfer) {
+    println!(
+        "{verb} {}@{} -> {}\n  {moved} {} of {} chunks ({} of {} bytes)",
+        transfer.pack_id,
+        transfer.version,
+        transfer.path.display(),
+        transfer.transferred_chunks,
+        transfer.chunks,
+        transfer.transferred_bytes,
+        transfer.size
+    );
+}
@@ -0,0 +1,78 @@
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use greentic_pack::chunking::ChunkStore;
+
+use super::publish::print_transfer;
+use crate::cache::{self, CacheKind};
+use crate::registry;
+
+#[derive(Debug, Parser)]
+pub struct PullArgs {
+    /// Pack to fetch, as <PACK_ID>@<VERSION>
+    #[arg(value_name = "PACK")]
+    pub pack: String,
+
+    /// Registry directory
+    #[arg(long, value_name = "DIR")]
+    pub registry: PathBuf,
+
+    /// Output path (defaults to <PACK_ID>-<VERSION>.gtpack); an older copy
+    /// here is reused for the chunks it shares with the new one
+    #[arg(long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+
+    /// Local chunk cache (defaults to ~/.cache/greentic/chunks)
+    #[arg(long, value_name = "DIR")]
+    pub cache: Option<PathBuf>,
+
+    /// Pull the version even if it was yanked
+    #[arg(long)]
+    pub allow_yanked: bool,
+}
+
+/// Splits `<PACK_ID>@<VERSION>` and checks both halves.
+pub(crate) fn parse_pack_ref(pack: &str) -> Result<(&str, &str)> {
+    let Some((pack_id, version)) = pack.split_once('@') else {
+        bail!("`{pack}` must be written as <PACK_ID>@<VERSION>");
+    };
+    if pack_id.is_empty() || pack_id.contains(['/', '\\']) || pack_id.starts_with('.') {
+        bail!("`{pack_id}` is not a pack id");
+    }
+    semver::Version::parse(version)
+        .with_context(|| format!("`{version}` is not a semver version"))?;
+    Ok((pack_id, version))
+}
+
+pub fn handle(args: PullArgs, json: bool) -> Result<()> {
+    let (pack_id, version) = parse_pack_ref(&args.pack)?;
+    let out = args
+        .out
+        .unwrap_or_else(|| PathBuf::from(format!("{pack_id}-{version}.gtpack")));
+    let cache = args
+        .cache
+        .or_else(registry::default_cache)
+        .context("cannot determine the chunk cache directory; pass --cache")?;
+
+    let transfer = registry::pull(
+        &args.registry,
+        pack_id,
+        version,
+        &out,
+        &ChunkStore::new(&cache),
+        args.allow_yanked,
+    )?;
+    cache::enforce_configured(CacheKind::Chunks, &cache)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&transfer)?);
+    } else {
+        print_transfer("pulled", "downloaded", &transfer);
+        if let Some(yank) = &transfer.yanked {
+            println!("  warning: this version was yanked on {}", yank.yanked_at);
+        }
+    }
+    Ok(())
+}
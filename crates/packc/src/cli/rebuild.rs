@@ -0,0 +1,65 @@
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use clap::Parser;
+use greentic_pack::atomic::Durability;
+
+use crate::rebuild::{self, RebuildOutcome};
+
+#[derive(Debug, Parser)]
+pub struct RebuildArgs {
+    /// Existing .gtpack archive to rebuild
+    #[arg(long = "from", value_name = "FILE")]
+    pub from: PathBuf,
+
+    /// Output path for the rebuilt archive
+    #[arg(
+        long = "out",
+        value_name = "FILE",
+        default_value = "dist/rebuilt.gtpack"
+    )]
+    pub out: PathBuf,
+
+    /// Fail unless the rebuilt archive has the same content digest as the input
+    #[arg(long)]
+    pub check: bool,
+
+    /// fsync the rebuilt archive before it replaces any existing file
+    #[arg(long)]
+    pub fsync: bool,
+}
+
+pub fn handle(args: RebuildArgs, json: bool) -> Result<()> {
+    let outcome = rebuild::rebuild(&args.from, &args.out, Durability::from_flag(args.fsync))?;
+
+    if json {
+        let mut payload = serde_json::to_value(&outcome)?;
+        payload["equivalent"] = outcome.is_equivalent().into();
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        print_human(&outcome);
+    }
+
+    if args.check && !outcome.is_equivalent() {
+        bail!(
+            "rebuilt archive differs from {} (digest {} vs {})",
+            outcome.from.display(),
+            outcome.rebuilt_digest,
+            outcome.original_digest
+        );
+    }
+    Ok(())
+}
+
+fn print_human(outcome: &RebuildOutcome) {
+    println!(
+        "rebuilt {}\n  out: {}\n  original digest: {}\n  rebuilt digest: {}\n  equivalent: {}",
+        outcome.from.display(),
+        outcome.out.display(),
+        outcome.original_digest,
+        outcome.rebuilt_digest,
+        outcome.is_equivalent()
+    );
+}
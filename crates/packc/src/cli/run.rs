@@ -0,0 +1,487 @@
+#![forbid(unsafe_code)]
+
+use std::ffi::OsString;
+use std::fs;
+use std::io::{BufRead, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::Parser;
+use greentic_pack::limits::LimitExceeded;
+use greentic_pack::store::{HealthCheck, PackState, PackStore};
+use semver::Version;
+use serde_json::Value as JsonValue;
+use time::OffsetDateTime;
+use tracing::info;
+
+use crate::build::{self, BuildOptions};
+use crate::cli::BuildArgs;
+use crate::cli::imports::parse_timestamp;
+use crate::imports::deterministic::DeterministicHost;
+use crate::imports::mocks::MOCKS_DIR;
+use crate::manifest::{self, PackSpec};
+use crate::reload::{self, FlowWatch};
+use crate::run::{self, RunContext, RunEvent, RunOptions, RunResult, Session, Turn};
+
+#[derive(Debug, Parser)]
+pub struct RunArgs {
+    /// Id of the flow to run
+    #[arg(value_name = "FLOW", required_unless_present = "health")]
+    pub flow: Option<String>,
+
+    /// Run the flow the pack names as its `lifecycle.health`
+    #[arg(long, conflicts_with = "flow")]
+    pub health: bool,
+
+    /// Pack store to record the health check's outcome in; the pack must be installed there
+    #[arg(long, value_name = "DIR", requires = "health")]
+    pub store: Option<PathBuf>,
+
+    /// Root directory of the pack
+    #[arg(long = "in", value_name = "DIR", default_value = ".")]
+    pub input: PathBuf,
+
+    /// Built pack component; defaults to <DIR>/dist/pack.wasm, built first if missing
+    #[arg(long, value_name = "FILE")]
+    pub wasm: Option<PathBuf>,
+
+    /// Build the pack before running, even if the component exists
+    #[arg(long)]
+    pub build: bool,
+
+    /// JSON input for the flow; `-` reads stdin, and without it the input is null
+    #[arg(long = "input", value_name = "FILE")]
+    pub flow_input: Option<PathBuf>,
+
+    /// Tenant the flow runs for
+    #[arg(long, default_value = "packc")]
+    pub tenant: String,
+
+    /// Environment of the tenant
+    #[arg(long, default_value = "local")]
+    pub env: String,
+
+    /// Preferred locale of the caller, as a BCP 47 tag
+    #[arg(long)]
+    pub locale: Option<String>,
+
+    /// Id tying the run to a request, for logs and traces
+    #[arg(long)]
+    pub correlation_id: Option<String>,
+
+    /// Print progress and output while the flow runs
+    #[arg(long)]
+    pub stream: bool,
+
+    /// Seed of the random values wasi:random imports return
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub seed: u64,
+
+    /// Instant the wall clock of wasi:clocks imports is frozen at (RFC 3339)
+    #[arg(long, value_name = "TIMESTAMP", value_parser = parse_timestamp, default_value = "1970-01-01T00:00:00Z")]
+    pub now: OffsetDateTime,
+
+    /// Save the flow's import calls and their answers to a cassette
+    #[arg(long, value_name = "CASSETTE", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Answer the flow's import calls from a recorded cassette
+    #[arg(long, value_name = "CASSETTE")]
+    pub replay: Option<PathBuf>,
+
+    /// Save an execution trace of the run, for `packc debug`
+    #[arg(long, value_name = "FILE")]
+    pub trace: Option<PathBuf>,
+
+    /// Keep a session open: run a turn for each JSON line on stdin, and
+    /// rebuild and swap in the pack when its flows change
+    #[arg(long, conflicts_with_all = ["health", "record", "replay", "trace"])]
+    pub watch: bool,
+}
+
+pub fn handle(args: RunArgs, json: bool) -> Result<()> {
+    let manifest_path = args.input.join("pack.yaml");
+    let spec = if manifest_path.is_file() {
+        Some(manifest::load_spec(&args.input)?.spec)
+    } else {
+        None
+    };
+    let flow = match &args.flow {
+        Some(flow) => flow.clone(),
+        None => spec
+            .as_ref()
+            .and_then(|spec| spec.lifecycle.as_ref())
+            .and_then(|lifecycle| lifecycle.health.clone())
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} names no `lifecycle.health` flow",
+                    manifest_path.display()
+                )
+            })?,
+    };
+    if args.watch {
+        return watch(&args, spec, &flow, json);
+    }
+    let recording = match &args.store {
+        Some(root) => Some(installed_health_check(root, spec.as_ref(), &flow)?),
+        None => None,
+    };
+    let outcome = execute(&args, spec, &flow, json);
+    if let Some((store, check)) = recording {
+        let record =
+            store.record_health(&check, outcome.as_ref().err().map(|err| format!("{err:#}")))?;
+        info!(
+            pack = %format!("{}@{}", record.pack_id, record.version),
+            status = %record.status,
+            "recorded health check"
+        );
+    }
+    outcome
+}
+
+/// The store and health check that `packc run --health --store` records
+/// into, checked before the flow runs.
+fn installed_health_check(
+    root: &Path,
+    spec: Option<&PackSpec>,
+    flow: &str,
+) -> Result<(PackStore, HealthCheck)> {
+    let spec = spec.ok_or_else(|| anyhow!("--store needs the pack's pack.yaml"))?;
+    let version = Version::parse(&spec.version)
+        .with_context(|| format!("{} is not a semver version", spec.version))?;
+    let store = PackStore::open(root)?;
+    match store.get(&spec.id, &version)? {
+        Some(pack) if pack.state == PackState::Installed => {
+            let check = HealthCheck {
+                pack_id: pack.pack_id,
+                version: pack.version,
+                flow: flow.to_string(),
+                archive: pack.archive,
+            };
+            Ok((store, check))
+        }
+        _ => bail!(
+            "{}@{version} is not installed in {}",
+            spec.id,
+            root.display()
+        ),
+    }
+}
+
+fn execute(args: &RunArgs, spec: Option<PackSpec>, flow: &str, json: bool) -> Result<()> {
+    let wasm = build_component(args, args.build)?;
+    let input = match &args.flow_input {
+        None => JsonValue::Null,
+        Some(path) => {
+            let text = if path.as_os_str() == "-" {
+                let mut text = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut text)
+                    .context("failed to read the input from stdin")?;
+                text
+            } else {
+                fs::read_to_string(path)
+                    .with_context(|| format!("failed to read {}", path.display()))?
+            };
+            serde_json::from_str(&text).context("the input is not valid JSON")?
+        }
+    };
+
+    let ctx = run_context(args);
+    let opts = run_options(args, spec, flow);
+    let bytes = fs::read(&wasm).with_context(|| format!("failed to read {}", wasm.display()))?;
+    let ran = if args.stream {
+        run::run_flow_stream(&bytes, flow, &input, &ctx, &opts, &mut |event| {
+            print_event(&event, json)
+        })
+    } else {
+        run::run_flow(&bytes, flow, &input, &ctx, &opts)
+    };
+    let result = ran.map_err(|err| over_limits(err, flow, json))?;
+    if json && args.stream {
+        println!("{}", serde_json::to_string(&result)?);
+    } else {
+        print_result(flow, &result, json)?;
+    }
+    if result.status == "error" {
+        bail!(
+            "flow `{flow}` failed: {}",
+            result.error.as_deref().unwrap_or("no error message")
+        );
+    }
+    Ok(())
+}
+
+/// The pack component to run, built first when `build` is set or it is
+/// missing.
+fn build_component(args: &RunArgs, build: bool) -> Result<PathBuf> {
+    let dist = args.input.join("dist");
+    let wasm = args.wasm.clone().unwrap_or_else(|| dist.join("pack.wasm"));
+    if build || !wasm.exists() {
+        let build_args = BuildArgs::try_parse_from([
+            OsString::from("build"),
+            "--in".into(),
+            args.input.clone().into(),
+            "--out".into(),
+            wasm.clone().into(),
+            "--manifest".into(),
+            dist.join("manifest.cbor").into(),
+            "--sbom".into(),
+            dist.join("sbom.cdx.json").into(),
+        ])?;
+        build::run(&BuildOptions::from(build_args))?;
+    }
+    Ok(wasm)
+}
+
+fn run_context(args: &RunArgs) -> RunContext {
+    RunContext {
+        tenant: args.tenant.clone(),
+        env: args.env.clone(),
+        locale: args.locale.clone(),
+        correlation_id: args.correlation_id.clone(),
+        secrets: None,
+    }
+}
+
+/// The flow runs under the limits its pack declares, as on a host, and its
+/// imports are answered by the pack's mocks and a seeded clock.
+fn run_options(args: &RunArgs, spec: Option<PackSpec>, flow: &str) -> RunOptions {
+    let mut opts = RunOptions {
+        builtins: DeterministicHost::new(args.seed, args.now),
+        mocks: Some(args.input.join(MOCKS_DIR)),
+        record: args.record.clone(),
+        replay: args.replay.clone(),
+        trace: args.trace.clone(),
+        ..RunOptions::default()
+    };
+    if let Some(spec) = spec {
+        if let Some(limits) = &spec.limits {
+            opts.limits = limits.flow(flow);
+        }
+        opts.pack_id = spec.id;
+    }
+    opts
+}
+
+/// `err`, printing a [`LimitExceeded`] behind it first with `--json`.
+fn over_limits(err: anyhow::Error, flow: &str, json: bool) -> anyhow::Error {
+    match err.downcast_ref::<LimitExceeded>() {
+        Some(exceeded) => {
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string(exceeded).expect("limit errors serialise")
+                );
+            }
+            anyhow!("flow `{flow}` went over its limits: {exceeded}")
+        }
+        None => err,
+    }
+}
+
+fn print_result(flow: &str, result: &RunResult, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(result)?);
+    } else {
+        println!("{flow}: {}", result.status);
+        if let Some(output) = &result.output {
+            println!("{}", serde_json::to_string_pretty(output)?);
+        }
+    }
+    Ok(())
+}
+
+/// What the threads of a watch session report.
+enum Watched {
+    Line(String),
+    Eof,
+    Changed(Vec<PathBuf>),
+}
+
+/// How often a watch session polls the pack's flows.
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// `packc run --watch`: one [`Session`] of the flow, fed a turn per line of
+/// stdin, into which the pack is rebuilt and swapped when its flows change.
+fn watch(args: &RunArgs, spec: Option<PackSpec>, flow: &str, json: bool) -> Result<()> {
+    if args
+        .flow_input
+        .as_ref()
+        .is_some_and(|path| path.as_os_str() == "-")
+    {
+        bail!("--watch reads its turns from stdin; pass --input a file");
+    }
+    if spec.is_none() {
+        bail!(
+            "--watch needs the pack's pack.yaml in {}",
+            args.input.display()
+        );
+    }
+    let first = match &args.flow_input {
+        Some(path) => Some(read_json(
+            &fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?,
+        )?),
+        None => None,
+    };
+    let wasm = build_component(args, args.build)?;
+    let wasm = fs::read(&wasm).with_context(|| format!("failed to read {}", wasm.display()))?;
+    let opts = run_options(args, spec, flow);
+    let mut live = LiveSession {
+        session: Session::start(&wasm, flow, &run_context(args), &opts)?,
+        wasm,
+        source: reload::flow_source(&args.input, flow)?,
+        changed: Arc::new(AtomicBool::new(false)),
+    };
+
+    let (sender, messages) = mpsc::channel();
+    let mut flows = FlowWatch::new(&args.input)?;
+    let (changes, changed) = (sender.clone(), Arc::clone(&live.changed));
+    thread::spawn(move || {
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let paths = flows.poll();
+            if !paths.is_empty() {
+                changed.store(true, Ordering::SeqCst);
+                if changes.send(Watched::Changed(paths)).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if sender.send(Watched::Line(line)).is_err() {
+                return;
+            }
+        }
+        let _ = sender.send(Watched::Eof);
+    });
+
+    eprintln!(
+        "watching the flows of {}; enter one JSON input per line",
+        args.input.display()
+    );
+    if let Some(input) = first {
+        live.turn(args, flow, &input, json)?;
+    }
+    for message in messages {
+        match message {
+            Watched::Line(line) if line.trim().is_empty() => {}
+            Watched::Line(line) => match read_json(&line) {
+                Ok(input) => live.turn(args, flow, &input, json)?,
+                Err(err) => eprintln!("{err:#}"),
+            },
+            Watched::Changed(paths) => {
+                for path in &paths {
+                    info!(path = %path.display(), "changed");
+                }
+                // A turn that was running has reloaded already.
+                if live.changed.swap(false, Ordering::SeqCst) {
+                    live.reload(args, flow);
+                }
+            }
+            Watched::Eof => break,
+        }
+    }
+    Ok(())
+}
+
+fn read_json(text: &str) -> Result<JsonValue> {
+    serde_json::from_str(text).context("the input is not valid JSON")
+}
+
+/// A watch session with the build it runs and the source of its flow.
+struct LiveSession {
+    session: Session,
+    wasm: Vec<u8>,
+    source: String,
+    /// Raised when the pack's flows change, to drain a running turn.
+    changed: Arc<AtomicBool>,
+}
+
+impl LiveSession {
+    /// Runs a turn with `input`. A turn whose flow changes while it runs
+    /// continues on the rebuilt pack from where it was drained when the flow
+    /// kept its schema, and starts over otherwise. Failed turns are reported
+    /// without ending the session.
+    fn turn(&mut self, args: &RunArgs, flow: &str, input: &JsonValue, json: bool) -> Result<()> {
+        let mut on_event = |event: RunEvent| {
+            if args.stream {
+                print_event(&event, json)
+            }
+        };
+        let mut turn = self.session.run(input, &self.changed, &mut on_event);
+        loop {
+            match turn {
+                Ok(Turn::Done(result)) => {
+                    print_result(flow, &result, json)?;
+                    if let Some(error) = result.error.filter(|_| result.status == "error") {
+                        eprintln!("flow `{flow}` failed: {error}");
+                    }
+                    return Ok(());
+                }
+                Ok(Turn::Drained(checkpoint)) => {
+                    self.changed.store(false, Ordering::SeqCst);
+                    let before = self.source.clone();
+                    self.reload(args, flow);
+                    turn = if reload::resumable(&before, &self.source, &checkpoint) {
+                        self.session
+                            .resume(&checkpoint, &self.changed, &mut on_event)
+                    } else {
+                        eprintln!("flow `{flow}` changed its schema; starting the turn over");
+                        self.session.run(input, &self.changed, &mut on_event)
+                    };
+                }
+                Err(err) => {
+                    eprintln!("{:#}", over_limits(err, flow, json));
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Rebuilds the pack and swaps it into the session. When that fails the
+    /// session keeps running the previous build, in a fresh instance since
+    /// the old one may have been drained.
+    fn reload(&mut self, args: &RunArgs, flow: &str) {
+        let rebuilt = build_component(args, true).and_then(|wasm| {
+            let source = reload::flow_source(&args.input, flow)?;
+            let wasm =
+                fs::read(&wasm).with_context(|| format!("failed to read {}", wasm.display()))?;
+            self.session.swap(&wasm)?;
+            Ok((wasm, source))
+        });
+        match rebuilt {
+            Ok((wasm, source)) => {
+                (self.wasm, self.source) = (wasm, source);
+                eprintln!("reloaded flow `{flow}`");
+            }
+            Err(err) => {
+                eprintln!("reload failed, keeping the previous build: {err:#}");
+                if let Err(err) = self.session.swap(&self.wasm) {
+                    eprintln!("failed to restart the previous build: {err:#}");
+                }
+            }
+        }
+    }
+}
+
+/// Prints a streamed event: one JSON object per line with `--json`,
+/// otherwise progress notes to stderr and output chunks to stdout.
+fn print_event(event: &RunEvent, json: bool) {
+    match event {
+        _ if json => println!(
+            "{}",
+            serde_json::to_string(event).expect("run events serialise")
+        ),
+        RunEvent::Progress { message } => eprintln!("{message}"),
+        RunEvent::Chunk { output } => println!("{output}"),
+    }
+}
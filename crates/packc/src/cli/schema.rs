@@ -0,0 +1,136 @@
+#![forbid(unsafe_code)]
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use greentic_pack::atomic::{self, Durability};
+use serde_json::json;
+
+use crate::schema::{self, EditorMapping, SchemaKind};
+
+#[derive(Debug, Parser)]
+pub struct SchemaArgs {
+    /// Which document or pack.yaml section the schema describes
+    #[arg(
+        long,
+        value_enum,
+        value_name = "KIND",
+        required_unless_present = "out_dir",
+        conflicts_with = "out_dir"
+    )]
+    pub what: Option<SchemaKind>,
+
+    /// Write the schema here and print the editor settings that use it
+    /// (prints the schema to stdout when omitted)
+    #[arg(long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+
+    /// Reference the schema by this path or URL in the editor settings
+    /// instead of --out (e.g. where it is published)
+    #[arg(long, value_name = "URL", requires = "out")]
+    pub url: Option<String>,
+
+    /// Write the schema of every kind into DIR and print the editor settings
+    /// for flows and pack.yaml
+    #[arg(long = "out-dir", value_name = "DIR", conflicts_with = "out")]
+    pub out_dir: Option<PathBuf>,
+}
+
+pub fn handle(args: SchemaArgs, json: bool) -> Result<()> {
+    if let Some(dir) = &args.out_dir {
+        return write_all(dir, json);
+    }
+    let what = args.what.expect("clap requires --what");
+    let schema = schema::schema(what)?;
+    let serialized = serde_json::to_string_pretty(&schema)?;
+    let Some(out) = args.out else {
+        println!("{serialized}");
+        return Ok(());
+    };
+
+    atomic::write(&out, serialized.as_bytes(), Durability::Buffered)?;
+    let schema_ref = args
+        .url
+        .unwrap_or_else(|| out.to_string_lossy().replace('\\', "/"));
+    let mapping = schema::editor_mapping(what, &schema_ref);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&mapping)?);
+    } else {
+        print_human(&out, &mapping)?;
+    }
+    Ok(())
+}
+
+fn write_all(dir: &std::path::Path, json: bool) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let mut written = Vec::new();
+    let mut mappings = Vec::new();
+    for kind in SchemaKind::ALL {
+        let out = dir.join(kind.file_name());
+        let serialized = serde_json::to_string_pretty(&schema::schema(kind)?)?;
+        atomic::write(&out, serialized.as_bytes(), Durability::Buffered)?;
+        if !kind.globs().is_empty() {
+            let schema_ref = out.to_string_lossy().replace('\\', "/");
+            mappings.push(schema::editor_mapping(kind, &schema_ref));
+        }
+        written.push((kind, out));
+    }
+
+    let mut yaml_schemas = BTreeMap::new();
+    let mut file_associations = BTreeMap::new();
+    for mapping in &mappings {
+        yaml_schemas.extend(mapping.yaml_schemas.clone());
+        file_associations.extend(mapping.file_associations.clone());
+    }
+    if json {
+        let schemas: Vec<_> = written
+            .iter()
+            .map(|(kind, path)| json!({ "kind": kind, "path": path.display().to_string() }))
+            .collect();
+        let payload = json!({
+            "dir": dir.display().to_string(),
+            "schemas": schemas,
+            "yaml_schemas": yaml_schemas,
+            "file_associations": file_associations,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        println!("wrote {} schemas to {}", written.len(), dir.display());
+        for (_, path) in &written {
+            println!("  {}", path.display());
+        }
+        print_settings(&yaml_schemas, &file_associations)?;
+    }
+    Ok(())
+}
+
+fn print_human(out: &std::path::Path, mapping: &EditorMapping) -> Result<()> {
+    println!("wrote {}", out.display());
+    println!("first line of a document:\n  {}", mapping.modeline);
+    print_settings(&mapping.yaml_schemas, &mapping.file_associations)
+}
+
+fn print_settings(
+    yaml_schemas: &BTreeMap<String, Vec<String>>,
+    file_associations: &BTreeMap<String, String>,
+) -> Result<()> {
+    let mut settings = serde_json::Map::new();
+    settings.insert(
+        "yaml.schemas".to_string(),
+        serde_json::to_value(yaml_schemas)?,
+    );
+    if !file_associations.is_empty() {
+        settings.insert(
+            "files.associations".to_string(),
+            serde_json::to_value(file_associations)?,
+        );
+    }
+    println!(
+        "editor settings:\n{}",
+        serde_json::to_string_pretty(&settings)?
+    );
+    Ok(())
+}
@@ -0,0 +1,39 @@
+#![forbid(unsafe_code)]
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use clap::Parser;
+use greentic_pack::registry::RegistryClient;
+
+use crate::http::CurlTransport;
+
+#[derive(Debug, Parser)]
+pub struct SearchArgs {
+    /// Words that must all appear in a pack's id, name, description, tags, or categories
+    #[arg(value_name = "QUERY", required = true)]
+    pub query: Vec<String>,
+
+    /// Base URL of the registry index (http(s):// or file://)
+    #[arg(long, value_name = "URL")]
+    pub index: String,
+}
+
+pub fn handle(args: SearchArgs, json: bool) -> Result<()> {
+    let client = RegistryClient::new(args.index, Arc::new(CurlTransport));
+    let packs = client.search(&args.query.join(" "))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&packs)?);
+        return Ok(());
+    }
+    if packs.is_empty() {
+        println!("no packs match `{}`", args.query.join(" "));
+    }
+    for pack in &packs {
+        println!("{}@{}  {}", pack.pack_id, pack.latest, pack.name);
+        if let Some(description) = &pack.description {
+            println!("  {description}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+#![forbid(unsafe_code)]
+
+use std::io::Write;
+use std::net::{SocketAddr, TcpListener};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use greentic_pack::SigningPolicy;
+use serde_json::json;
+
+use crate::serve_ui::{self, PackView};
+
+#[derive(Debug, Parser)]
+pub struct ServeUiArgs {
+    /// The .gtpack archive to show
+    #[arg(value_name = "FILE")]
+    pub path: PathBuf,
+
+    /// Address to listen on; port 0 picks a free port
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:0")]
+    pub listen: SocketAddr,
+
+    /// Reject archives signed with the dev certificate
+    #[arg(long)]
+    pub strict: bool,
+}
+
+pub fn handle(args: ServeUiArgs, json: bool) -> Result<()> {
+    let policy = if args.strict {
+        SigningPolicy::Strict
+    } else {
+        SigningPolicy::DevOk
+    };
+    let view = PackView::load(&args.path, policy)?;
+    let listener = TcpListener::bind(args.listen)
+        .with_context(|| format!("failed to listen on {}", args.listen))?;
+    let url = format!("http://{}/", listener.local_addr()?);
+
+    let mut stdout = std::io::stdout();
+    if json {
+        writeln!(stdout, "{}", json!({ "url": url, "pack": args.path }))?;
+    } else {
+        writeln!(stdout, "serving {} at {url}", args.path.display())?;
+    }
+    // Callers wait for the URL before connecting.
+    stdout.flush()?;
+
+    serve_ui::serve(listener, &view)
+}
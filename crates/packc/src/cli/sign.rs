@@ -3,15 +3,21 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use clap::Parser;
 use serde::Serialize;
 use serde_json;
 use time::OffsetDateTime;
 use time::format_description::well_known::Rfc3339;
 
+use crate::build_lock::{BuildLock, WaitPolicy};
+use crate::digest::DigestAlgorithm;
 use crate::manifest::{self, PackSignature};
-use crate::signing::signer;
+use crate::signing::sigstore::{self, DEFAULT_FULCIO_URL, DEFAULT_REKOR_URL, KeylessOptions};
+use crate::signing::{
+    CANONICALIZATION_VERSION, CanonicalizedPack, KeyConstraints, append_signatures,
+    canonicalize_pack_dir_with, signer,
+};
 
 #[derive(Debug, Parser)]
 pub struct SignArgs {
@@ -19,17 +25,68 @@ pub struct SignArgs {
     #[arg(long = "pack", value_name = "DIR")]
     pub pack: PathBuf,
 
-    /// Ed25519 private key in PKCS#8 PEM format
-    #[arg(long = "key", value_name = "FILE")]
-    pub key: PathBuf,
+    /// Private key (Ed25519 PKCS#8 PEM, or ML-DSA-65 with the `ml-dsa` feature);
+    /// repeat to add a hybrid signature, e.g. `--key ed25519.pem --key ml-dsa.pem`
+    #[arg(
+        long = "key",
+        value_name = "FILE",
+        required_unless_present_any = ["dry_run", "keyless"]
+    )]
+    pub key: Vec<PathBuf>,
 
-    /// Optional override for the signature key identifier
+    /// Sign without a key file: certify a throwaway key for the OIDC identity in
+    /// $SIGSTORE_ID_TOKEN (or of the GitHub Actions job) with Fulcio, and record
+    /// the signature in Rekor
+    #[arg(long, conflicts_with_all = ["key", "key_id"])]
+    pub keyless: bool,
+
+    /// Fulcio instance issuing keyless certificates
+    #[arg(long, value_name = "URL", default_value = DEFAULT_FULCIO_URL, requires = "keyless")]
+    pub fulcio_url: String,
+
+    /// Rekor transparency log recording keyless signatures
+    #[arg(long, value_name = "URL", default_value = DEFAULT_REKOR_URL, requires = "keyless")]
+    pub rekor_url: String,
+
+    /// Optional override for the signature key identifier (single key only)
     #[arg(long = "kid", value_name = "ID")]
     pub key_id: Option<String>,
 
     /// When set, writes the updated manifest to the provided path instead of in-place
     #[arg(long = "out", value_name = "FILE")]
     pub out: Option<PathBuf>,
+
+    /// Digest algorithm recorded in the signature (verification accepts any supported one)
+    #[arg(long = "digest-alg", value_enum, value_name = "ALG", default_value_t = DigestAlgorithm::Sha256)]
+    pub digest_alg: DigestAlgorithm,
+
+    /// Only accept this signature for pack ids matching PATTERN (`*` is a wildcard)
+    #[arg(long = "allow-pack-id", value_name = "PATTERN")]
+    pub allow_pack_id: Option<String>,
+
+    /// Reject this signature after the given RFC 3339 timestamp
+    #[arg(long = "expires-at", value_name = "TIMESTAMP", value_parser = parse_timestamp)]
+    pub expires_at: Option<OffsetDateTime>,
+
+    /// Only accept this signature when verifying for ENV; repeatable
+    #[arg(long = "allow-env", value_name = "ENV")]
+    pub allow_env: Vec<String>,
+
+    /// Discard the signatures already on the manifest instead of adding to them
+    #[arg(long)]
+    pub replace: bool,
+
+    /// Print the digest that would be signed without reading any key or writing the manifest
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Wait for a concurrent build/sign of the same pack directory to finish
+    #[arg(long, overrides_with = "no_wait")]
+    pub wait: bool,
+
+    /// Fail immediately if another build/sign holds the pack directory (default)
+    #[arg(long = "no-wait", overrides_with = "wait")]
+    pub no_wait: bool,
 }
 
 pub fn handle(args: SignArgs, json: bool) -> Result<()> {
@@ -37,52 +94,127 @@ pub fn handle(args: SignArgs, json: bool) -> Result<()> {
         pack,
         key,
         key_id,
+        keyless,
+        fulcio_url,
+        rekor_url,
         out,
+        digest_alg,
+        allow_pack_id,
+        expires_at,
+        allow_env,
+        replace,
+        dry_run,
+        wait,
+        ..
     } = args;
+    let constraints = KeyConstraints {
+        pack_id: allow_pack_id,
+        expires_at,
+        environments: allow_env,
+    };
 
     let pack_dir = pack
         .canonicalize()
         .with_context(|| format!("failed to resolve {}", pack.display()))?;
 
-    let private_key =
-        fs::read_to_string(&key).with_context(|| format!("failed to read {}", key.display()))?;
+    let _lock = BuildLock::acquire(&pack_dir, "packc sign", WaitPolicy::from_flags(wait))?;
+
+    if dry_run {
+        if !constraints.is_empty() {
+            signer::check_constraints_cover(&pack_dir, &constraints)?;
+        }
+        let canonical = canonicalize_pack_dir_with(&pack_dir, digest_alg)?;
+        return print_dry_run(&pack_dir, &canonical, &constraints, json);
+    }
+
+    if key_id.is_some() && key.len() > 1 {
+        bail!("--kid cannot be combined with multiple --key values");
+    }
+    let private_keys = key
+        .iter()
+        .map(|path| {
+            fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
 
     let target_manifest_path = match &out {
         Some(path) => path.clone(),
         None => manifest::manifest_path(&pack_dir)?,
     };
 
-    let outcome = signer::sign_pack(&pack_dir, &private_key, key_id.as_deref())?;
+    if !constraints.is_empty() {
+        signer::check_constraints_cover(&pack_dir, &constraints)?;
+    }
+    let canonical = canonicalize_pack_dir_with(&pack_dir, digest_alg)?;
+    let signatures = if keyless {
+        let opts = KeylessOptions {
+            fulcio_url: &fulcio_url,
+            rekor_url: &rekor_url,
+            identity_token: None,
+        };
+        vec![sigstore::sign_canonical(
+            &canonical,
+            Some(&constraints),
+            &opts,
+        )?]
+    } else {
+        private_keys
+            .iter()
+            .map(|pem| {
+                signer::sign_canonical(&canonical, pem, key_id.as_deref(), Some(&constraints))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
 
-    manifest::write_signature(&pack_dir, &outcome.signature, out.as_deref())?;
+    let total = if replace {
+        manifest::write_signatures(&pack_dir, &signatures, out.as_deref())?;
+        signatures.len()
+    } else {
+        append_signatures(&pack_dir, &signatures, out.as_deref())?.len()
+    };
 
     if json {
-        print_json(&outcome.signature, &target_manifest_path)?;
+        print_json(&signatures, total, &target_manifest_path)?;
     } else {
-        print_human(&outcome.signature, &target_manifest_path)?;
+        print_human(&signatures, total, &target_manifest_path)?;
     }
 
     Ok(())
 }
 
-fn print_human(signature: &PackSignature, manifest_path: &Path) -> Result<()> {
-    let created_at = signature
-        .created_at
-        .format(&Rfc3339)
-        .unwrap_or_else(|_| signature.created_at.to_string());
-
+fn print_human(signatures: &[PackSignature], total: usize, manifest_path: &Path) -> Result<()> {
     println!(
-        "signed pack manifest\n  manifest: {}\n  key_id: {}\n  digest: {}\n  created_at: {}",
-        manifest_path.display(),
-        signature.key_id,
-        signature.digest,
-        created_at
+        "signed pack manifest\n  manifest: {}",
+        manifest_path.display()
     );
+    for signature in signatures {
+        let created_at = signature
+            .created_at
+            .format(&Rfc3339)
+            .unwrap_or_else(|_| signature.created_at.to_string());
+
+        println!(
+            "  alg: {}\n  key_id: {}\n  digest: {}\n  created_at: {}",
+            signature.alg, signature.key_id, signature.digest, created_at
+        );
+        if let Some(proof) = &signature.sigstore {
+            println!(
+                "  identity: {} ({})\n  rekor: {} (log index {})",
+                proof.identity, proof.issuer, proof.rekor.url, proof.rekor.log_index
+            );
+        }
+    }
+    if let Some(constraints) = &signatures[0].constraints {
+        println!("  constraints: {}", serde_json::to_string(constraints)?);
+    }
+    println!("  signatures on manifest: {total}");
 
     Ok(())
 }
 
-fn print_json(signature: &PackSignature, manifest_path: &Path) -> Result<()> {
+/// Prints the first new signature as before, plus every new signature under
+/// `signatures` and the number now on the manifest.
+fn print_json(signatures: &[PackSignature], total: usize, manifest_path: &Path) -> Result<()> {
     #[derive(Serialize)]
     struct Payload<'a> {
         manifest: &'a Path,
@@ -92,8 +224,11 @@ fn print_json(signature: &PackSignature, manifest_path: &Path) -> Result<()> {
         #[serde(with = "time::serde::rfc3339")]
         created_at: OffsetDateTime,
         sig: &'a str,
+        signatures: &'a [PackSignature],
+        total_signatures: usize,
     }
 
+    let signature = &signatures[0];
     let payload = Payload {
         manifest: manifest_path,
         key_id: &signature.key_id,
@@ -101,8 +236,46 @@ fn print_json(signature: &PackSignature, manifest_path: &Path) -> Result<()> {
         digest: &signature.digest,
         created_at: signature.created_at,
         sig: &signature.sig,
+        signatures,
+        total_signatures: total,
     };
 
     println!("{}", serde_json::to_string(&payload)?);
     Ok(())
 }
+
+fn print_dry_run(
+    pack_dir: &Path,
+    canonical: &CanonicalizedPack,
+    constraints: &KeyConstraints,
+    json: bool,
+) -> Result<()> {
+    let constraints = (!constraints.is_empty()).then_some(constraints);
+    if json {
+        let payload = serde_json::json!({
+            "dry_run": true,
+            "pack": pack_dir,
+            "canonicalization_version": CANONICALIZATION_VERSION,
+            "files": canonical.files,
+            "digest": canonical.digest(),
+            "constraints": constraints,
+        });
+        println!("{}", serde_json::to_string(&payload)?);
+    } else {
+        println!(
+            "dry run: nothing was signed\n  pack: {}\n  canonicalization: v{CANONICALIZATION_VERSION}\n  files: {}\n  digest: {}",
+            pack_dir.display(),
+            canonical.files,
+            canonical.digest()
+        );
+        if let Some(constraints) = constraints {
+            println!("  constraints: {}", serde_json::to_string(constraints)?);
+        }
+    }
+    Ok(())
+}
+
+fn parse_timestamp(value: &str) -> Result<OffsetDateTime, String> {
+    OffsetDateTime::parse(value, &Rfc3339)
+        .map_err(|err| format!("`{value}` is not an RFC 3339 timestamp: {err}"))
+}
@@ -0,0 +1,166 @@
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use greentic_pack::SigningPolicy;
+use greentic_pack::atomic::Durability;
+use greentic_pack::store::{PackState, PackStore};
+use serde_json::json;
+
+#[derive(Debug, Subcommand)]
+pub enum StoreCommand {
+    /// Write the store's packs, records, approval states, and history to one file
+    Export(ExportArgs),
+    /// Restore an exported state into an empty store
+    Import(ImportArgs),
+    /// Show the latest health check of every installed pack
+    Health(HealthArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct ExportArgs {
+    /// Root directory of the pack store
+    #[arg(long, value_name = "DIR")]
+    pub store: PathBuf,
+
+    /// State file to write, conventionally state.tar.zst
+    #[arg(long, value_name = "FILE")]
+    pub out: PathBuf,
+
+    /// fsync the state file before it replaces any existing file
+    #[arg(long)]
+    pub fsync: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct ImportArgs {
+    /// State file written by `packc store export`
+    #[arg(value_name = "STATE")]
+    pub state: PathBuf,
+
+    /// Root directory of the pack store to fill; it must hold no packs
+    #[arg(long, value_name = "DIR")]
+    pub store: PathBuf,
+
+    /// Verify every archive again under the current signing policy
+    #[arg(long)]
+    pub reverify: bool,
+
+    /// With --reverify, reject archives signed with the dev certificate
+    #[arg(long)]
+    pub strict: bool,
+
+    /// fsync each restored file before it is moved into place
+    #[arg(long)]
+    pub fsync: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct HealthArgs {
+    /// Root directory of the pack store
+    #[arg(long, value_name = "DIR")]
+    pub store: PathBuf,
+
+    /// Print the outcomes as Prometheus gauges, for a metrics scrape
+    #[arg(long)]
+    pub metrics: bool,
+}
+
+pub fn handle(command: StoreCommand, json: bool) -> Result<()> {
+    match command {
+        StoreCommand::Export(args) => export(args, json),
+        StoreCommand::Import(args) => import(args, json),
+        StoreCommand::Health(args) => health(args, json),
+    }
+}
+
+fn export(args: ExportArgs, json: bool) -> Result<()> {
+    let store = PackStore::open(&args.store)?.with_durability(Durability::from_flag(args.fsync));
+    let manifest = store.export_state(&args.out)?;
+    let bytes: u64 = manifest.files.iter().map(|file| file.size).sum();
+    if json {
+        let payload = json!({
+            "store": args.store,
+            "out": args.out,
+            "exported_at_utc": manifest.exported_at_utc,
+            "files": manifest.files,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        println!(
+            "exported {} files ({bytes} bytes) from {} -> {}",
+            manifest.files.len(),
+            args.store.display(),
+            args.out.display()
+        );
+    }
+    Ok(())
+}
+
+fn import(args: ImportArgs, json: bool) -> Result<()> {
+    let policy = if args.strict {
+        SigningPolicy::Strict
+    } else {
+        SigningPolicy::DevOk
+    };
+    let store = PackStore::open(&args.store)?
+        .with_policy(policy)
+        .with_durability(Durability::from_flag(args.fsync));
+    let packs = store.import_state(&args.state, args.reverify)?;
+    let history = store.history()?.len();
+    if json {
+        let payload = json!({
+            "store": args.store,
+            "reverified": args.reverify,
+            "packs": packs,
+            "history_entries": history,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        println!(
+            "imported {} packs into {}{}",
+            packs.len(),
+            args.store.display(),
+            if args.reverify { " (re-verified)" } else { "" }
+        );
+        for pack in &packs {
+            let pending = if pack.state == PackState::PendingApproval {
+                " (pending approval)"
+            } else {
+                ""
+            };
+            println!("  {}@{}{pending}", pack.pack_id, pack.version);
+        }
+        println!("  {history} history entries");
+    }
+    Ok(())
+}
+
+fn health(args: HealthArgs, json: bool) -> Result<()> {
+    let store = PackStore::open(&args.store)?;
+    if args.metrics {
+        print!("{}", store.health_metrics()?);
+        return Ok(());
+    }
+    let records = store.health_records()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&records)?);
+    } else if records.is_empty() {
+        println!("no health checks recorded in {}", args.store.display());
+    } else {
+        for record in &records {
+            let error = record
+                .error
+                .as_deref()
+                .map(|error| format!(": {error}"))
+                .unwrap_or_default();
+            println!(
+                "{}@{} {} at {} ({}){error}",
+                record.pack_id, record.version, record.status, record.checked_at_utc, record.flow
+            );
+        }
+    }
+    Ok(())
+}
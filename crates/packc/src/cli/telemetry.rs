@@ -0,0 +1,60 @@
+#![forbid(unsafe_code)]
+
+use anyhow::Result;
+use clap::Subcommand;
+use serde_json::json;
+
+use crate::usage;
+
+#[derive(Debug, Subcommand)]
+pub enum TelemetryCommand {
+    /// Opt in to anonymous usage reporting
+    Enable,
+    /// Opt out of anonymous usage reporting
+    Disable,
+    /// Show the current usage reporting consent
+    Status,
+}
+
+pub fn handle(command: TelemetryCommand, json: bool) -> Result<()> {
+    match command {
+        TelemetryCommand::Enable => {
+            let path = usage::store_consent(true)?;
+            report(json, "enabled", Some(path.display().to_string()))
+        }
+        TelemetryCommand::Disable => {
+            let path = usage::store_consent(false)?;
+            report(json, "disabled", Some(path.display().to_string()))
+        }
+        TelemetryCommand::Status => {
+            let config = usage::load_config()?;
+            let status = match config.telemetry.usage_reporting {
+                _ if usage::disabled_by_env() => "disabled (environment)",
+                Some(true) => "enabled",
+                Some(false) => "disabled",
+                None => "not configured (disabled)",
+            };
+            report(
+                json,
+                status,
+                usage::config_path().map(|path| path.display().to_string()),
+            )
+        }
+    }
+}
+
+fn report(json: bool, status: &str, config: Option<String>) -> Result<()> {
+    if json {
+        let payload = json!({
+            "usage_reporting": status,
+            "config": config,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        println!("usage reporting: {status}");
+        if let Some(config) = config {
+            println!("  config: {config}");
+        }
+    }
+    Ok(())
+}
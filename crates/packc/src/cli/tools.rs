@@ -0,0 +1,139 @@
+#![forbid(unsafe_code)]
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use clap::{Parser, Subcommand};
+use serde_json::json;
+
+use crate::tools::{self, TOOLCHAIN_FILE, ToolIndex, Toolchain};
+
+#[derive(Debug, Subcommand)]
+pub enum ToolsCommand {
+    /// Download the native helpers pinned in toolchain.toml for this platform
+    Install(InstallArgs),
+    /// List the helpers installed in the tools directory
+    List(ListArgs),
+}
+
+#[derive(Debug, Parser)]
+pub struct InstallArgs {
+    /// Tools to install (defaults to every tool in the manifest)
+    #[arg(value_name = "NAME")]
+    pub names: Vec<String>,
+
+    /// Toolchain manifest pinning the tools
+    #[arg(long, value_name = "FILE", default_value = TOOLCHAIN_FILE)]
+    pub manifest: PathBuf,
+
+    /// Install directory (defaults to $PACKC_TOOLS_DIR, then ~/.cache/greentic/tools)
+    #[arg(long, value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+
+    /// Platform to install for, as <arch>-<os> (defaults to the running one)
+    #[arg(long, value_name = "PLATFORM")]
+    pub platform: Option<String>,
+
+    /// Download and unpack again even when the pinned archive is installed
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct ListArgs {
+    /// Install directory (defaults to $PACKC_TOOLS_DIR, then ~/.cache/greentic/tools)
+    #[arg(long, value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+}
+
+pub fn handle(command: ToolsCommand, json: bool) -> Result<()> {
+    match command {
+        ToolsCommand::Install(args) => install(args, json),
+        ToolsCommand::List(args) => list(args, json),
+    }
+}
+
+fn resolve_dir(dir: Option<PathBuf>) -> Result<PathBuf> {
+    dir.or_else(tools::tools_dir)
+        .context("cannot determine the tools directory; pass --dir or set PACKC_TOOLS_DIR")
+}
+
+fn install(args: InstallArgs, json: bool) -> Result<()> {
+    let dir = resolve_dir(args.dir)?;
+    let toolchain = Toolchain::load(&args.manifest)?;
+    let base = args.manifest.parent().unwrap_or(Path::new("."));
+    let platform = args.platform.unwrap_or_else(tools::current_platform);
+
+    let names: Vec<String> = if args.names.is_empty() {
+        toolchain.tools.keys().cloned().collect()
+    } else {
+        args.names
+    };
+    if names.is_empty() {
+        bail!("{} pins no tools", args.manifest.display());
+    }
+
+    let mut results = Vec::new();
+    for name in &names {
+        let spec = toolchain.tools.get(name).with_context(|| {
+            format!(
+                "{} does not pin a tool named `{name}`",
+                args.manifest.display()
+            )
+        })?;
+        let (tool, status) = tools::install(&dir, base, name, spec, &platform, args.force)
+            .with_context(|| format!("failed to install {name}"))?;
+        results.push((name, tool, status));
+    }
+
+    if json {
+        let payload = json!({
+            "dir": dir.display().to_string(),
+            "platform": platform,
+            "tools": results
+                .iter()
+                .map(|(name, tool, status)| json!({
+                    "name": name,
+                    "version": tool.version,
+                    "status": status,
+                    "path": dir.join(&tool.path).display().to_string(),
+                }))
+                .collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        for (name, tool, status) in &results {
+            println!(
+                "{name} {} ({}): {}",
+                tool.version,
+                status.as_str(),
+                dir.join(&tool.path).display()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn list(args: ListArgs, json: bool) -> Result<()> {
+    let dir = resolve_dir(args.dir)?;
+    let index = ToolIndex::load(&dir)?;
+    if json {
+        let payload = json!({
+            "dir": dir.display().to_string(),
+            "tools": index.tools,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else if index.tools.is_empty() {
+        println!("no tools installed in {}", dir.display());
+    } else {
+        for (name, tool) in &index.tools {
+            println!(
+                "{name} {} [{}] {}",
+                tool.version,
+                tool.platform,
+                dir.join(&tool.path).display()
+            );
+        }
+    }
+    Ok(())
+}
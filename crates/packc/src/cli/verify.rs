@@ -3,69 +3,250 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use clap::Parser;
 use serde::Serialize;
 use serde_json;
 use time::OffsetDateTime;
 use time::format_description::well_known::Rfc3339;
 
-use crate::manifest::PackSignature;
-use crate::signing::{VerifyOptions, verify_pack_dir};
+use crate::digest::DigestAlgorithm;
+use crate::imports::InterfaceCatalog;
+use crate::lockfile::{LOCK_FILE, PackLock};
+use crate::manifest::{self, PackSignature};
+use crate::signing::sigstore::DEFAULT_REKOR_URL;
+use crate::signing::trust::TRUST_STORE_ENV;
+use crate::signing::verify::verify_signatures;
+use crate::signing::{KeylessPolicy, TrustStore, VerifyOptions};
+use crate::templates::{self, SyntaxCheck};
 
 #[derive(Debug, Parser)]
 pub struct VerifyArgs {
-    /// Path to the pack directory containing pack.toml
+    /// Path to the pack directory containing pack.toml (pack.yaml with --locked)
     #[arg(long = "pack", value_name = "DIR")]
     pub pack: PathBuf,
 
-    /// Public key to verify against (PKCS#8 PEM)
+    /// Check that pack.lock still matches the pack's imports, components, and
+    /// templates, instead of verifying signatures
+    #[arg(long)]
+    pub locked: bool,
+
+    /// Interface catalog to resolve imports against with --locked (defaults
+    /// as for `packc build`)
+    #[arg(long = "interface-catalog", value_name = "FILE", requires = "locked")]
+    pub interface_catalog: Option<PathBuf>,
+
+    /// Public key to verify against (Ed25519 SPKI PEM or ML-DSA-65 PEM); repeat
+    /// for hybrid signatures
     #[arg(long = "pub", value_name = "FILE")]
-    pub public_key: Option<PathBuf>,
+    pub public_key: Vec<PathBuf>,
+
+    /// Trust store to look signing keys up in by key id: a directory of public
+    /// key PEMs or a JSON keyring (defaults to $PACKC_TRUST_STORE)
+    #[arg(long = "trust-store", value_name = "PATH")]
+    pub trust_store: Option<PathBuf>,
 
     /// Allow verification to succeed when no signature is present
     #[arg(long = "allow-unsigned")]
     pub allow_unsigned: bool,
+
+    /// Require a valid post-quantum (ML-DSA) signature
+    #[arg(long = "require-pq")]
+    pub require_pq: bool,
+
+    /// Environment the pack is being verified for (matched against --require-pq-env
+    /// and environment-restricted signatures)
+    #[arg(long = "env", value_name = "ENV")]
+    pub env: Option<String>,
+
+    /// Accept the pack once N signatures by distinct --pub or trust store keys
+    /// verify (e.g. 2 of 3), instead of requiring every signature to verify
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub threshold: Option<u32>,
+
+    /// Trusted Fulcio root certificate (PEM) for keyless signatures; repeatable
+    #[arg(long = "fulcio-root", value_name = "FILE")]
+    pub fulcio_root: Vec<PathBuf>,
+
+    /// Require keyless signatures to be issued to this identity (email or URI)
+    #[arg(long, value_name = "IDENTITY")]
+    pub certificate_identity: Option<String>,
+
+    /// Require keyless signatures to be issued for this OIDC issuer
+    #[arg(long, value_name = "URL")]
+    pub certificate_oidc_issuer: Option<String>,
+
+    /// Transparency log to look keyless signatures up in
+    #[arg(long, value_name = "URL", default_value = DEFAULT_REKOR_URL)]
+    pub rekor_url: String,
+
+    /// Public key (PEM) of the --rekor-url log, which keyless signatures' log
+    /// entries must be signed with
+    #[arg(long, value_name = "FILE")]
+    pub rekor_public_key: Option<PathBuf>,
+
+    /// Environment in which a post-quantum signature is mandatory; repeatable
+    #[arg(long = "require-pq-env", value_name = "ENV")]
+    pub require_pq_env: Vec<String>,
+}
+
+impl VerifyArgs {
+    fn requires_post_quantum(&self) -> bool {
+        self.require_pq
+            || self
+                .env
+                .as_deref()
+                .is_some_and(|env| self.require_pq_env.iter().any(|required| required == env))
+    }
 }
 
 pub fn handle(args: VerifyArgs, json: bool) -> Result<()> {
+    if args.locked {
+        return verify_lock(&args.pack, args.interface_catalog.as_deref(), json);
+    }
+    let require_post_quantum = args.requires_post_quantum();
     let VerifyArgs {
         pack,
         public_key,
+        trust_store,
         allow_unsigned,
+        env,
+        threshold,
+        fulcio_root,
+        certificate_identity,
+        certificate_oidc_issuer,
+        rekor_url,
+        rekor_public_key,
+        ..
     } = args;
 
     let pack_dir = pack
         .canonicalize()
         .with_context(|| format!("failed to resolve {}", pack.display()))?;
 
-    let public_key_pem = match public_key {
-        Some(path) => Some(
-            fs::read_to_string(&path)
-                .with_context(|| format!("failed to read {}", path.display()))?,
-        ),
-        None => None,
+    let public_keys_pem = public_key
+        .iter()
+        .map(|path| {
+            fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let public_keys_pem = public_keys_pem
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    let (public_key_pem, additional_public_keys_pem) = match public_keys_pem.split_first() {
+        Some((first, rest)) => (Some(*first), rest),
+        None => (None, &[][..]),
     };
 
-    let signature = verify_pack_dir(
+    let trust_store = trust_store
+        .or_else(|| std::env::var_os(TRUST_STORE_ENV).map(PathBuf::from))
+        .map(|path| {
+            TrustStore::load(&path)
+                .with_context(|| format!("failed to load trust store {}", path.display()))
+        })
+        .transpose()?;
+
+    let fulcio_roots_pem = fulcio_root
+        .iter()
+        .map(|path| {
+            fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let fulcio_roots_pem = fulcio_roots_pem
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+
+    let rekor_public_key_pem = rekor_public_key
+        .map(|path| {
+            fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))
+        })
+        .transpose()?;
+
+    let signatures = verify_signatures(
         &pack_dir,
         VerifyOptions {
-            public_key_pem: public_key_pem.as_deref(),
+            public_key_pem,
+            additional_public_keys_pem,
+            trust_store: trust_store.as_ref(),
             allow_unsigned,
+            require_post_quantum,
+            environment: env.as_deref(),
+            threshold: threshold.map(|threshold| threshold as usize),
+            keyless: KeylessPolicy {
+                trusted_roots_pem: &fulcio_roots_pem,
+                identity: certificate_identity.as_deref(),
+                issuer: certificate_oidc_issuer.as_deref(),
+                rekor_url: Some(&rekor_url),
+                rekor_public_key_pem: rekor_public_key_pem.as_deref(),
+            },
         },
     )?;
 
     if json {
-        print_json(&signature, &pack_dir)?;
+        print_json(&signatures, &pack_dir)?;
     } else {
-        print_human(&signature, &pack_dir)?;
+        print_human(&signatures, &pack_dir, trust_store.as_ref())?;
     }
 
     Ok(())
 }
 
-fn print_human(signature: &PackSignature, pack_dir: &Path) -> Result<()> {
-    if signature.alg == "none" {
+/// Fails when the inputs of the pack in `pack_dir` no longer match its
+/// `pack.lock`.
+fn verify_lock(pack_dir: &Path, interface_catalog: Option<&Path>, json: bool) -> Result<()> {
+    let lock = PackLock::load(pack_dir)?.with_context(|| {
+        format!(
+            "{} has no {LOCK_FILE}; run `packc build` first",
+            pack_dir.display()
+        )
+    })?;
+    let spec_bundle = manifest::load_spec(pack_dir)?;
+    let templates = templates::collect_templates(
+        pack_dir,
+        &spec_bundle.spec,
+        DigestAlgorithm::default(),
+        SyntaxCheck::Skip,
+    )?;
+    let catalog = InterfaceCatalog::load(pack_dir, interface_catalog)?;
+    let current = PackLock::current(pack_dir, &spec_bundle, &templates, &catalog, Some(&lock))?;
+    let changes = lock.changes(&current);
+    if !changes.is_empty() {
+        bail!(
+            "{LOCK_FILE} does not match the current inputs of {}:\n  {}",
+            pack_dir.display(),
+            changes.join("\n  ")
+        );
+    }
+
+    if json {
+        let payload = serde_json::json!({
+            "pack": pack_dir,
+            "lock": pack_dir.join(LOCK_FILE),
+            "imports": lock.imports.len(),
+            "components": lock.components.len(),
+            "templates": lock.templates.len(),
+        });
+        println!("{}", serde_json::to_string(&payload)?);
+    } else {
+        println!(
+            "{LOCK_FILE} matches {} ({} imports, {} components, {} templates)",
+            pack_dir.display(),
+            lock.imports.len(),
+            lock.components.len(),
+            lock.templates.len()
+        );
+    }
+    Ok(())
+}
+
+fn print_human(
+    signatures: &[PackSignature],
+    pack_dir: &Path,
+    trust_store: Option<&TrustStore>,
+) -> Result<()> {
+    if signatures[0].alg == "none" {
         println!(
             "verified pack manifest in {} (unsigned manifest accepted)",
             pack_dir.display()
@@ -73,23 +254,40 @@ fn print_human(signature: &PackSignature, pack_dir: &Path) -> Result<()> {
         return Ok(());
     }
 
-    let created_at = signature
-        .created_at
-        .format(&Rfc3339)
-        .unwrap_or_else(|_| signature.created_at.to_string());
+    println!("verified pack manifest in {}", pack_dir.display());
+    for signature in signatures {
+        let created_at = signature
+            .created_at
+            .format(&Rfc3339)
+            .unwrap_or_else(|_| signature.created_at.to_string());
 
-    println!(
-        "verified pack manifest in {}\n  key_id: {}\n  digest: {}\n  created_at: {}",
-        pack_dir.display(),
-        signature.key_id,
-        signature.digest,
-        created_at
-    );
+        println!(
+            "  alg: {}\n  key_id: {}\n  digest: {}\n  created_at: {}",
+            signature.alg, signature.key_id, signature.digest, created_at
+        );
+        let trusted_name = trust_store
+            .and_then(|store| store.get(&signature.key_id))
+            .and_then(|key| key.name.as_deref());
+        if let Some(name) = trusted_name {
+            println!("  trusted key: {name}");
+        }
+        if let Some(constraints) = &signature.constraints {
+            println!("  constraints: {}", serde_json::to_string(constraints)?);
+        }
+        if let Some(proof) = &signature.sigstore {
+            println!(
+                "  identity: {} ({})\n  rekor log index: {}",
+                proof.identity, proof.issuer, proof.rekor.log_index
+            );
+        }
+    }
 
     Ok(())
 }
 
-fn print_json(signature: &PackSignature, pack_dir: &Path) -> Result<()> {
+/// Prints the primary signature as before, plus every verified signature under
+/// `signatures`.
+fn print_json(signatures: &[PackSignature], pack_dir: &Path) -> Result<()> {
     #[derive(Serialize)]
     struct Payload<'a> {
         pack: &'a Path,
@@ -99,8 +297,10 @@ fn print_json(signature: &PackSignature, pack_dir: &Path) -> Result<()> {
         #[serde(with = "time::serde::rfc3339")]
         created_at: OffsetDateTime,
         sig: &'a str,
+        signatures: &'a [PackSignature],
     }
 
+    let signature = &signatures[0];
     let payload = Payload {
         pack: pack_dir,
         alg: &signature.alg,
@@ -108,6 +308,7 @@ fn print_json(signature: &PackSignature, pack_dir: &Path) -> Result<()> {
         digest: &signature.digest,
         created_at: signature.created_at,
         sig: &signature.sig,
+        signatures,
     };
 
     println!("{}", serde_json::to_string(&payload)?);
@@ -0,0 +1,87 @@
+//! Resolving the dependency closure of a pack for `packc bundle`.
+//!
+//! A pack depends on another pack when one of its `imports` names a pack id
+//! published in the registry; every other import is left to the host. Each
+//! dependency resolves to the newest published version its requirement
+//! allows, skipping yanked versions unless asked not to, and the packs it
+//! depends on are resolved in turn. The result is written as a
+//! [`greentic_pack::closure`] bundle.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use greentic_pack::chunking::ChunkStore;
+use greentic_pack::{SigningPolicy, open_pack_bytes};
+use semver::{Version, VersionReq};
+use tracing::info;
+
+use crate::registry;
+
+/// Archives of `root` and every pack it transitively depends on, the root
+/// first and the rest in the order they were reached.
+pub fn resolve(
+    root: Vec<u8>,
+    registry_dir: &Path,
+    cache: &ChunkStore,
+    allow_yanked: bool,
+) -> Result<Vec<Vec<u8>>> {
+    let staging = tempfile::tempdir().context("failed to create a staging directory")?;
+    let mut chosen: BTreeMap<String, Version> = BTreeMap::new();
+    let mut archives = Vec::new();
+    let mut queue = VecDeque::from([root]);
+
+    while let Some(bytes) = queue.pop_front() {
+        let load = open_pack_bytes(&bytes, SigningPolicy::DevOk)
+            .map_err(|err| anyhow!("closure member failed verification: {}", err.message))?;
+        let meta = &load.manifest.meta;
+        chosen.insert(meta.pack_id.clone(), meta.version.clone());
+
+        for import in &meta.imports {
+            // Already resolved; `write_closure` checks the requirement.
+            if chosen.contains_key(&import.pack_id) {
+                continue;
+            }
+            let published = registry::published_versions(registry_dir, &import.pack_id)?;
+            if published.is_empty() {
+                continue;
+            }
+            let req = VersionReq::parse(&import.version_req).with_context(|| {
+                format!(
+                    "{} imports {} with invalid requirement `{}`",
+                    meta.pack_id, import.pack_id, import.version_req
+                )
+            })?;
+            let candidates = published
+                .iter()
+                .filter(|(version, yank)| req.matches(version) && (allow_yanked || yank.is_none()));
+            let Some((version, _)) = candidates.max_by(|a, b| a.0.cmp(&b.0)) else {
+                bail!(
+                    "no published version of {} matches {} (required by {})",
+                    import.pack_id,
+                    import.version_req,
+                    meta.pack_id
+                );
+            };
+            let out = staging
+                .path()
+                .join(format!("{}-{version}.gtpack", import.pack_id));
+            registry::pull(
+                registry_dir,
+                &import.pack_id,
+                &version.to_string(),
+                &out,
+                cache,
+                allow_yanked,
+            )?;
+            info!(pack = %import.pack_id, version = %version, "resolved dependency");
+            chosen.insert(import.pack_id.clone(), version.clone());
+            queue.push_back(
+                fs::read(&out).with_context(|| format!("failed to read {}", out.display()))?,
+            );
+        }
+        archives.push(bytes);
+    }
+    Ok(archives)
+}
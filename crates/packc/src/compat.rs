@@ -0,0 +1,532 @@
+//! Backwards compatibility between two versions of a pack.
+//!
+//! A change is breaking when a host or caller that worked with the old
+//! version can stop working with the new one: a flow, input, or trigger went
+//! away, an input changed type, a new import must be provided, or a quota or
+//! execution limit got tighter. Additions need a minor release and any other content change a
+//! patch. Packs before 1.0 follow the Cargo convention, where the minor
+//! number carries breaking changes and the patch number everything else.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use greentic_pack::builder::PackMeta;
+use greentic_pack::quotas::ImportQuota;
+use greentic_pack::{PackLoad, SigningPolicy, open_pack};
+use semver::Version;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+pub const COMPAT_FORMAT: &str = "greentic-pack-compat-v1";
+
+/// Release level a set of changes calls for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Bump::None => "none",
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        }
+    }
+
+    /// The level that going from `old` to `new` releases; a 0.x patch
+    /// release counts as minor and a 0.x minor release as major.
+    pub fn between(old: &Version, new: &Version) -> Self {
+        if new <= old {
+            Bump::None
+        } else if new.major > old.major {
+            Bump::Major
+        } else if new.minor > old.minor {
+            if new.major == 0 {
+                Bump::Major
+            } else {
+                Bump::Minor
+            }
+        } else if new.patch > old.patch && new.major == 0 {
+            Bump::Minor
+        } else {
+            Bump::Patch
+        }
+    }
+
+    /// The smallest version after `version` that releases at this level.
+    pub fn apply(self, version: &Version) -> Version {
+        let (major, minor, patch) = (version.major, version.minor, version.patch);
+        match (self, major) {
+            (Bump::None, _) => version.clone(),
+            (Bump::Major, 0) => Version::new(0, minor + 1, 0),
+            (Bump::Major, _) => Version::new(major + 1, 0, 0),
+            (Bump::Minor, 0) | (Bump::Patch, _) => Version::new(major, minor, patch + 1),
+            (Bump::Minor, _) => Version::new(major, minor + 1, 0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Area {
+    Flows,
+    Inputs,
+    Triggers,
+    Imports,
+    Limits,
+    Contents,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CompatChange {
+    pub bump: Bump,
+    pub area: Area,
+    pub subject: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompatReport {
+    pub format: &'static str,
+    pub pack_id: String,
+    pub old_version: Version,
+    pub new_version: Version,
+    pub breaking: bool,
+    pub suggested_bump: Bump,
+    /// The smallest version after `old_version` that the changes allow.
+    pub suggested_version: Version,
+    /// The level `new_version` actually releases at.
+    pub declared_bump: Bump,
+    /// Whether `new_version` is a large enough bump for the changes.
+    pub sufficient: bool,
+    /// Breaking changes first.
+    pub changes: Vec<CompatChange>,
+}
+
+/// Compares the archives at `old` and `new`, which must be builds of the
+/// same pack.
+pub fn check(old: &Path, new: &Path) -> Result<CompatReport> {
+    let old = PackSide::open(old)?;
+    let new = PackSide::open(new)?;
+    if old.meta.pack_id != new.meta.pack_id {
+        bail!(
+            "cannot compare different packs `{}` and `{}`",
+            old.meta.pack_id,
+            new.meta.pack_id
+        );
+    }
+    Ok(compare(&old, &new))
+}
+
+/// The parts of a pack version compatibility depends on.
+struct PackSide {
+    meta: PackMeta,
+    /// Inputs by flow id.
+    flows: BTreeMap<String, BTreeMap<String, String>>,
+    /// SBOM entry hashes by path.
+    contents: BTreeMap<String, String>,
+}
+
+impl PackSide {
+    fn open(path: &Path) -> Result<Self> {
+        let load = open_pack(path, SigningPolicy::DevOk)
+            .map_err(|err| anyhow!("{} failed verification: {}", path.display(), err.message))?;
+        Self::from_load(&load).with_context(|| format!("failed to read {}", path.display()))
+    }
+
+    fn from_load(load: &PackLoad) -> Result<Self> {
+        let mut flows = BTreeMap::new();
+        for flow in &load.manifest.flows {
+            let yaml = load.file(&flow.file_yaml).ok_or_else(|| {
+                anyhow!("flow `{}` source {} is missing", flow.id, flow.file_yaml)
+            })?;
+            let yaml = std::str::from_utf8(yaml)
+                .with_context(|| format!("flow `{}` source is not UTF-8", flow.id))?;
+            flows.insert(
+                flow.id.clone(),
+                flow_inputs(yaml).with_context(|| format!("flow `{}`", flow.id))?,
+            );
+        }
+        Ok(Self {
+            meta: load.manifest.meta.clone(),
+            flows,
+            contents: load
+                .sbom
+                .iter()
+                .map(|entry| (entry.path.clone(), entry.hash_blake3.clone()))
+                .collect(),
+        })
+    }
+}
+
+/// Inputs a flow takes, with their type: `parameters.<name>` typed by its
+/// default value, and `in.<id>` for every question a `qa.process` node asks,
+/// typed by its `answer_type`.
+fn flow_inputs(yaml: &str) -> Result<BTreeMap<String, String>> {
+    let source: JsonValue =
+        serde_yaml_bw::from_str(yaml).context("flow source is not valid YAML")?;
+    let mut inputs = BTreeMap::new();
+    if let Some(parameters) = source.get("parameters").and_then(JsonValue::as_object) {
+        for (name, default) in parameters {
+            inputs.insert(format!("parameters.{name}"), json_type(default).to_string());
+        }
+    }
+    let questions = source
+        .get("nodes")
+        .and_then(JsonValue::as_object)
+        .into_iter()
+        .flat_map(|nodes| nodes.values())
+        .filter_map(|node| node.get("qa.process")?.get("questions")?.as_array())
+        .flatten();
+    for question in questions {
+        if let Some(id) = question.get("id").and_then(JsonValue::as_str) {
+            let answer_type = question
+                .get("answer_type")
+                .and_then(JsonValue::as_str)
+                .unwrap_or("text");
+            inputs.insert(format!("in.{id}"), answer_type.to_string());
+        }
+    }
+    Ok(inputs)
+}
+
+fn json_type(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
+fn compare(old: &PackSide, new: &PackSide) -> CompatReport {
+    let mut changes = Vec::new();
+    let mut push = |bump, area, subject: String, message: String| {
+        changes.push(CompatChange {
+            bump,
+            area,
+            subject,
+            message,
+        })
+    };
+
+    for (flow, inputs) in &old.flows {
+        let Some(now) = new.flows.get(flow) else {
+            push(
+                Bump::Major,
+                Area::Flows,
+                flow.clone(),
+                "flow removed".into(),
+            );
+            continue;
+        };
+        for (field, ty) in inputs {
+            let subject = format!("{flow}:{field}");
+            match now.get(field) {
+                None => push(Bump::Major, Area::Inputs, subject, "input removed".into()),
+                Some(new_ty) if new_ty != ty => push(
+                    Bump::Major,
+                    Area::Inputs,
+                    subject,
+                    format!("type changed from {ty} to {new_ty}"),
+                ),
+                Some(_) => {}
+            }
+        }
+        for field in now.keys().filter(|field| !inputs.contains_key(*field)) {
+            push(
+                Bump::Minor,
+                Area::Inputs,
+                format!("{flow}:{field}"),
+                "input added".into(),
+            );
+        }
+    }
+    for flow in new
+        .flows
+        .keys()
+        .filter(|flow| !old.flows.contains_key(*flow))
+    {
+        push(Bump::Minor, Area::Flows, flow.clone(), "flow added".into());
+    }
+
+    let (before, after) = (triggers(&old.meta), triggers(&new.meta));
+    for (trigger, description) in &before {
+        match after.get(trigger) {
+            None => push(
+                Bump::Major,
+                Area::Triggers,
+                trigger.clone(),
+                format!("{description} removed"),
+            ),
+            Some(now) if now != description => push(
+                Bump::Major,
+                Area::Triggers,
+                trigger.clone(),
+                format!("changed from {description} to {now}"),
+            ),
+            Some(_) => {}
+        }
+    }
+    for (trigger, description) in after.iter().filter(|(t, _)| !before.contains_key(*t)) {
+        push(
+            Bump::Minor,
+            Area::Triggers,
+            trigger.clone(),
+            format!("{description} added"),
+        );
+    }
+
+    let imports = |meta: &PackMeta| -> BTreeSet<String> {
+        meta.imports.iter().map(|i| i.pack_id.clone()).collect()
+    };
+    let (before, after) = (imports(&old.meta), imports(&new.meta));
+    for import in after.difference(&before) {
+        push(
+            Bump::Major,
+            Area::Imports,
+            import.clone(),
+            "hosts must now provide this import".into(),
+        );
+    }
+    for import in before.difference(&after) {
+        push(
+            Bump::Patch,
+            Area::Imports,
+            import.clone(),
+            "import no longer used".into(),
+        );
+    }
+
+    let (before, after) = (quotas(&old.meta), quotas(&new.meta));
+    for (key, quota) in &after {
+        let subject = format!("{}/{}", key.0, key.1);
+        match before.get(key) {
+            None => push(
+                Bump::Major,
+                Area::Limits,
+                subject,
+                format!("calls newly limited to {}", describe_quota(quota)),
+            ),
+            Some(previous) if tighter(quota, previous) => push(
+                Bump::Major,
+                Area::Limits,
+                subject,
+                format!(
+                    "quota tightened from {} to {}",
+                    describe_quota(previous),
+                    describe_quota(quota)
+                ),
+            ),
+            Some(previous) if previous != quota => push(
+                Bump::Patch,
+                Area::Limits,
+                subject,
+                format!(
+                    "quota loosened from {} to {}",
+                    describe_quota(previous),
+                    describe_quota(quota)
+                ),
+            ),
+            Some(_) => {}
+        }
+    }
+    for key in before.keys().filter(|key| !after.contains_key(*key)) {
+        push(
+            Bump::Patch,
+            Area::Limits,
+            format!("{}/{}", key.0, key.1),
+            "quota removed".into(),
+        );
+    }
+
+    let (before, after) = (flow_limits(&old.meta), flow_limits(&new.meta));
+    for key in before.keys().chain(after.keys()).collect::<BTreeSet<_>>() {
+        let subject = format!("{}:{}", key.0, key.1);
+        match (before.get(key), after.get(key)) {
+            (previous, Some(limit)) if previous.is_none_or(|previous| limit < previous) => push(
+                Bump::Major,
+                Area::Limits,
+                subject,
+                match previous {
+                    Some(previous) => format!("tightened from {previous} to {limit}"),
+                    None => format!("newly limited to {limit}"),
+                },
+            ),
+            (Some(previous), limit) if limit != Some(previous) => push(
+                Bump::Patch,
+                Area::Limits,
+                subject,
+                match limit {
+                    Some(limit) => format!("loosened from {previous} to {limit}"),
+                    None => "limit removed".into(),
+                },
+            ),
+            _ => {}
+        }
+    }
+
+    if changes.is_empty() && old.contents != new.contents {
+        let changed = new
+            .contents
+            .iter()
+            .filter(|(path, hash)| old.contents.get(*path) != Some(*hash))
+            .count()
+            + old
+                .contents
+                .keys()
+                .filter(|path| !new.contents.contains_key(*path))
+                .count();
+        changes.push(CompatChange {
+            bump: Bump::Patch,
+            area: Area::Contents,
+            subject: "archive".into(),
+            message: format!("{changed} file(s) changed"),
+        });
+    }
+
+    changes.sort_by(|a, b| {
+        b.bump
+            .cmp(&a.bump)
+            .then(a.area.cmp(&b.area))
+            .then_with(|| a.subject.cmp(&b.subject))
+    });
+    let suggested_bump = changes.first().map_or(Bump::None, |change| change.bump);
+    let declared_bump = Bump::between(&old.meta.version, &new.meta.version);
+    CompatReport {
+        format: COMPAT_FORMAT,
+        pack_id: new.meta.pack_id.clone(),
+        old_version: old.meta.version.clone(),
+        new_version: new.meta.version.clone(),
+        breaking: suggested_bump == Bump::Major,
+        suggested_bump,
+        suggested_version: suggested_bump.apply(&old.meta.version),
+        declared_bump,
+        sufficient: declared_bump >= suggested_bump,
+        changes,
+    }
+}
+
+/// Ways a flow can be started, keyed by trigger, with a description of what
+/// starts it.
+fn triggers(meta: &PackMeta) -> BTreeMap<String, String> {
+    let mut triggers = BTreeMap::new();
+    for flow in &meta.entry_flows {
+        triggers.insert(format!("entry:{flow}"), "entry flow".to_string());
+    }
+    for provider in meta.events.iter().flat_map(|events| &events.providers) {
+        triggers.insert(
+            format!("events:{}", provider.name),
+            format!("{} events provider", provider.kind),
+        );
+        for topic in &provider.capabilities.topics {
+            triggers.insert(
+                format!("events:{}:{topic}", provider.name),
+                "topic".to_string(),
+            );
+        }
+    }
+    let adapters = meta
+        .messaging
+        .iter()
+        .flat_map(|messaging| messaging.adapters.iter().flatten());
+    for adapter in adapters {
+        triggers.insert(
+            format!("messaging:{}", adapter.name),
+            format!("{} messaging adapter", adapter.kind),
+        );
+    }
+    triggers
+}
+
+fn quotas(meta: &PackMeta) -> BTreeMap<(String, String), ImportQuota> {
+    meta.quotas
+        .iter()
+        .flat_map(|section| &section.flows)
+        .flat_map(|(flow, imports)| {
+            imports
+                .iter()
+                .map(move |(import, quota)| ((flow.clone(), import.clone()), *quota))
+        })
+        .collect()
+}
+
+/// Declared execution limits by flow id and limit name.
+fn flow_limits(meta: &PackMeta) -> BTreeMap<(String, &'static str), u64> {
+    let mut limits = BTreeMap::new();
+    for (flow, declared) in meta.limits.iter().flat_map(|section| &section.flows) {
+        let fields = [
+            ("timeout_ms", declared.timeout_ms),
+            ("max_output_bytes", declared.max_output_bytes),
+            ("fuel", declared.fuel),
+            ("memory_mb", declared.memory_mb.map(u64::from)),
+            ("drain_ms", declared.drain_ms),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                limits.insert((flow.clone(), name), value);
+            }
+        }
+    }
+    limits
+}
+
+/// Whether `quota` allows less than `previous` in any dimension; a missing
+/// burst allows as much as the per-minute rate.
+fn tighter(quota: &ImportQuota, previous: &ImportQuota) -> bool {
+    let burst = |q: &ImportQuota| q.burst.unwrap_or(q.requests_per_minute);
+    quota.requests_per_minute < previous.requests_per_minute || burst(quota) < burst(previous)
+}
+
+fn describe_quota(quota: &ImportQuota) -> String {
+    match quota.burst {
+        Some(burst) => format!("{}/min, burst {burst}", quota.requests_per_minute),
+        None => format!("{}/min", quota.requests_per_minute),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_parameters_and_questions_as_inputs() {
+        let inputs = flow_inputs(
+            "id: f\nparameters:\n  days: 3\n  units: metric\nnodes:\n  ask:\n    qa.process:\n      questions:\n        - id: city\n          answer_type: text\n        - id: when\n",
+        )
+        .unwrap();
+        let expected: BTreeMap<String, String> = [
+            ("in.city", "text"),
+            ("in.when", "text"),
+            ("parameters.days", "integer"),
+            ("parameters.units", "string"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        assert_eq!(inputs, expected);
+    }
+
+    #[test]
+    fn pre_release_versions_follow_cargo_bumps() {
+        let v = |s| Version::parse(s).unwrap();
+        assert_eq!(Bump::between(&v("0.3.1"), &v("0.4.0")), Bump::Major);
+        assert_eq!(Bump::between(&v("0.3.1"), &v("0.3.2")), Bump::Minor);
+        assert_eq!(Bump::between(&v("1.3.1"), &v("1.3.2")), Bump::Patch);
+        assert_eq!(Bump::between(&v("1.3.1"), &v("1.3.1")), Bump::None);
+        assert_eq!(Bump::Major.apply(&v("0.3.1")), v("0.4.0"));
+        assert_eq!(Bump::Minor.apply(&v("0.3.1")), v("0.3.2"));
+        assert_eq!(Bump::Major.apply(&v("1.3.1")), v("2.0.0"));
+        assert_eq!(Bump::Minor.apply(&v("1.3.1")), v("1.4.0"));
+    }
+}
@@ -0,0 +1,362 @@
+//! Compatibility of one pack with several host versions.
+//!
+//! Each host version is described by the newest pack format it reads, the
+//! WIT interface versions it provides, and the capabilities it refuses to
+//! grant:
+//!
+//! ```yaml
+//! hosts:
+//!   - name: runner-0.4
+//!     pack_version: 1
+//!     interfaces:
+//!       greentic:host-import: [0.4.0]
+//!     deny: [email]
+//!   - name: runner-0.6
+//!     pack_version: 1
+//!     interfaces:
+//!       greentic:host-import: [0.4.0, 0.6.0]
+//!       wasix:mcp: [25.6.18]
+//! ```
+//!
+//! A host is incompatible when it cannot read the pack's format or would
+//! have to disable every entry flow, degraded when it can run the pack with
+//! some flows disabled or degraded or some declared imports missing, and
+//! compatible otherwise. Flows are disabled or degraded as
+//! [`PackLoad::downgrade`] does, with the interfaces the host lacks denied
+//! alongside its `deny` list; a flow whose nodes have fallbacks for the
+//! missing optional imports keeps running degraded.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::ValueEnum;
+use greentic_pack::capabilities::CapabilityFilter;
+use greentic_pack::{PackLoad, SigningPolicy, open_pack};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+use crate::imports::is_wit_reference;
+
+pub const MATRIX_FORMAT: &str = "greentic-pack-compat-matrix-v1";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HostsFile {
+    pub hosts: Vec<HostDescriptor>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HostDescriptor {
+    pub name: String,
+    /// Newest `pack_version` the host reads.
+    pub pack_version: u32,
+    /// Versions the host provides of each WIT package.
+    #[serde(default)]
+    pub interfaces: BTreeMap<String, Vec<String>>,
+    /// Capabilities the host does not grant, as in a [`CapabilityFilter`].
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl HostsFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let hosts: Self = serde_yaml_bw::from_str(&text)
+            .with_context(|| format!("{} is not a valid host list", path.display()))?;
+        if hosts.hosts.is_empty() {
+            bail!("{} lists no hosts", path.display());
+        }
+        for host in &hosts.hosts {
+            if host.name.trim().is_empty() {
+                bail!("{}: every host needs a name", path.display());
+            }
+            for (package, versions) in &host.interfaces {
+                for version in versions {
+                    Version::parse(version).with_context(|| {
+                        format!(
+                            "host {} provides {package} at invalid version `{version}`",
+                            host.name
+                        )
+                    })?;
+                }
+            }
+        }
+        Ok(hosts)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum HostStatus {
+    Compatible,
+    Degraded,
+    Incompatible,
+}
+
+impl fmt::Display for HostStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HostStatus::Compatible => "compatible",
+            HostStatus::Degraded => "degraded",
+            HostStatus::Incompatible => "incompatible",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HostResult {
+    pub host: String,
+    pub status: HostStatus,
+    /// Why the host is not fully compatible.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub disabled_flows: Vec<String>,
+    /// Flows that run with fallbacks in place of missing optional imports.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub degraded_flows: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompatMatrix {
+    pub format: String,
+    pub pack_id: String,
+    pub version: String,
+    /// Oldest pack format a host must read, i.e. the pack's `pack_version`.
+    pub min_reader_version: u32,
+    /// WIT interfaces the pack imports, pinned to the versions it was built
+    /// against.
+    pub interfaces: Vec<String>,
+    pub hosts: Vec<HostResult>,
+}
+
+impl CompatMatrix {
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "## {} {} host compatibility\n\n| Host | Status | Notes |\n| --- | --- | --- |\n",
+            self.pack_id, self.version
+        );
+        for host in &self.hosts {
+            out.push_str(&format!(
+                "| {} | {} | {} |\n",
+                host.host,
+                host.status,
+                host.reasons.join("; ").replace('|', "\\|")
+            ));
+        }
+        out
+    }
+}
+
+/// Checks the pack at `gtpack` against every host in `hosts`.
+pub fn check(gtpack: &Path, hosts: &HostsFile) -> Result<CompatMatrix> {
+    let load = open_pack(gtpack, SigningPolicy::DevOk)
+        .map_err(|err| anyhow!("failed to open {}: {}", gtpack.display(), err.message))?;
+    let meta = &load.manifest.meta;
+    let interfaces = pinned_interfaces(&load)?;
+    let results = hosts
+        .hosts
+        .iter()
+        .map(|host| check_host(&load, &interfaces, host))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(CompatMatrix {
+        format: MATRIX_FORMAT.to_string(),
+        pack_id: meta.pack_id.clone(),
+        version: meta.version.to_string(),
+        min_reader_version: meta.pack_version,
+        interfaces: interfaces.iter().map(|import| import.to_string()).collect(),
+        hosts: results,
+    })
+}
+
+/// A WIT interface import and the version the pack was built against, when
+/// the build recorded one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PinnedInterface {
+    package: String,
+    interface: String,
+    version: Option<Version>,
+    optional: bool,
+}
+
+impl fmt::Display for PinnedInterface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.package, self.interface)?;
+        if let Some(version) = &self.version {
+            write!(f, "@{version}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The pack's WIT imports, with the versions from the `imports_resolved`
+/// annotation `packc build` records.
+fn pinned_interfaces(load: &PackLoad) -> Result<Vec<PinnedInterface>> {
+    let meta = &load.manifest.meta;
+    let resolved: Vec<&str> = meta
+        .annotations
+        .get("imports_resolved")
+        .and_then(|value| value.as_array())
+        .map(|entries| entries.iter().filter_map(|entry| entry.as_str()).collect())
+        .unwrap_or_default();
+
+    let mut interfaces = Vec::new();
+    for entry in &meta.imports {
+        let import = entry.pack_id.as_str();
+        if !is_wit_reference(import) {
+            continue;
+        }
+        let path = import.split('@').next().unwrap_or(import);
+        let (package, interface) = path
+            .split_once('/')
+            .ok_or_else(|| anyhow!("import `{import}` must name an interface"))?;
+        let version = resolved
+            .iter()
+            .find_map(|entry| entry.strip_prefix(path)?.strip_prefix('@'))
+            .map(Version::parse)
+            .transpose()
+            .with_context(|| format!("the resolved version of `{import}` is invalid"))?;
+        let pinned = PinnedInterface {
+            package: package.to_string(),
+            interface: interface.to_string(),
+            version,
+            optional: entry.optional,
+        };
+        if !interfaces.contains(&pinned) {
+            interfaces.push(pinned);
+        }
+    }
+    Ok(interfaces)
+}
+
+fn check_host(
+    load: &PackLoad,
+    interfaces: &[PinnedInterface],
+    host: &HostDescriptor,
+) -> Result<HostResult> {
+    let mut result = HostResult {
+        host: host.name.clone(),
+        status: HostStatus::Compatible,
+        reasons: Vec::new(),
+        disabled_flows: Vec::new(),
+        degraded_flows: Vec::new(),
+    };
+    let pack_version = load.manifest.meta.pack_version;
+    if pack_version > host.pack_version {
+        result.status = HostStatus::Incompatible;
+        result.reasons.push(format!(
+            "reads pack format {} at most; the pack needs {pack_version}",
+            host.pack_version
+        ));
+        return Ok(result);
+    }
+
+    let mut filter: CapabilityFilter = host.deny.iter().cloned().collect();
+    for import in interfaces {
+        if let Some(reason) = missing_interface(import, host) {
+            result.reasons.push(if import.optional {
+                format!("{reason} (optional)")
+            } else {
+                reason
+            });
+            filter = filter.deny(format!("{}/{}", import.package, import.interface));
+        }
+    }
+    if !result.reasons.is_empty() {
+        result.status = HostStatus::Degraded;
+    }
+
+    let had_entry_flows = !load.manifest.meta.entry_flows.is_empty();
+    let downgraded = load.clone().downgrade(&filter)?;
+    for flow in &downgraded.disabled_flows {
+        result.status = HostStatus::Degraded;
+        result.disabled_flows.push(flow.flow.clone());
+        result.reasons.push(flow.reason.clone());
+    }
+    for flow in &downgraded.degraded_flows {
+        result.status = HostStatus::Degraded;
+        result.degraded_flows.push(flow.flow.clone());
+        result.reasons.push(flow.reason.clone());
+    }
+    if had_entry_flows && downgraded.manifest.meta.entry_flows.is_empty() {
+        result.status = HostStatus::Incompatible;
+        result.reasons.push("no entry flow can run".to_string());
+    }
+    Ok(result)
+}
+
+/// Why `host` cannot satisfy `import`, if it cannot. A provided version
+/// satisfies a pinned one when it is semver compatible and not older.
+fn missing_interface(import: &PinnedInterface, host: &HostDescriptor) -> Option<String> {
+    let Some(provided) = host.interfaces.get(&import.package) else {
+        return Some(format!("does not provide {}", import.package));
+    };
+    let version = import.version.as_ref()?;
+    let req = VersionReq::parse(&format!("^{version}")).ok()?;
+    if provided
+        .iter()
+        .filter_map(|version| Version::parse(version).ok())
+        .any(|version| req.matches(&version))
+    {
+        return None;
+    }
+    Some(format!(
+        "provides {} {} but the pack needs {import}",
+        import.package,
+        provided.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(interfaces: &[(&str, &[&str])]) -> HostDescriptor {
+        HostDescriptor {
+            name: "runner".into(),
+            pack_version: 1,
+            interfaces: interfaces
+                .iter()
+                .map(|(package, versions)| {
+                    (
+                        package.to_string(),
+                        versions.iter().map(|v| v.to_string()).collect(),
+                    )
+                })
+                .collect(),
+            deny: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn interfaces_need_a_compatible_version_that_is_not_older() {
+        let import = PinnedInterface {
+            package: "greentic:host-import".into(),
+            interface: "secrets".into(),
+            version: Some(Version::new(0, 6, 0)),
+            optional: false,
+        };
+        assert!(
+            missing_interface(&import, &host(&[("greentic:host-import", &["0.6.2"])])).is_none()
+        );
+        let reason = missing_interface(
+            &import,
+            &host(&[("greentic:host-import", &["0.4.0", "0.7.0"])]),
+        )
+        .unwrap();
+        assert_eq!(
+            reason,
+            "provides greentic:host-import 0.4.0, 0.7.0 but the pack needs greentic:host-import/secrets@0.6.0"
+        );
+        assert!(
+            missing_interface(&import, &host(&[]))
+                .unwrap()
+                .contains("does not provide")
+        );
+    }
+}
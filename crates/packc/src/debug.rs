@@ -0,0 +1,371 @@
+//! Stepping through recorded runs, for `packc debug`.
+//!
+//! A [`Debugger`] walks the steps of a [`Trace`] that `packc run --trace`
+//! wrote. Each step shows what its node was given and got back: the import
+//! calls it made and the output it streamed. The state at a step is what the
+//! flow could refer to once the step's node ran: `in`, the flow's
+//! `parameters` when the pack is at hand, and `<node>.payload` for every node
+//! run so far. Expressions are evaluated against that state: dotted paths
+//! such as `fetch.payload.temp`, or Handlebars templates such as
+//! `"{{in.city}}: {{fetch.payload.temp}}"`.
+//!
+//! `export` writes the input and the calls up to the current step as a
+//! cassette under the pack's `tests` directory, the smallest recording that
+//! replays the run to where it went wrong.
+
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use greentic_pack::cassette::Outcome;
+use greentic_pack::trace::Trace;
+use serde_json::{Map, Value as JsonValue};
+use serde_yaml_bw::Value as YamlValue;
+
+use crate::reload;
+
+/// Where exported cases go, under the pack directory.
+pub const TESTS_DIR: &str = "tests";
+
+const HELP: &str = "\
+commands:
+  n, next            step forward
+  p, prev            step back
+  g, goto N          go to step N
+  l, list            list the steps
+  s, show            show the current step's calls and output
+  state              show the state at the current step
+  e, eval EXPR       evaluate a dotted path or Handlebars template
+  r, result          show how the run ended
+  x, export [NAME]   write a replay cassette up to the current step
+  h, help            show this help
+  q, quit            leave";
+
+/// Whether the debugger keeps taking commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Quit,
+}
+
+pub struct Debugger {
+    trace: Trace,
+    pack_dir: PathBuf,
+    /// The flow's `parameters`, when its pack is at hand.
+    parameters: Option<JsonValue>,
+    step: usize,
+}
+
+impl Debugger {
+    /// A debugger at the first step of `trace`, whose pack is in `pack_dir`.
+    pub fn new(trace: Trace, pack_dir: &Path) -> Self {
+        let parameters = reload::flow_source(pack_dir, &trace.flow)
+            .ok()
+            .and_then(|source| flow_parameters(&source));
+        Self {
+            trace,
+            pack_dir: pack_dir.to_path_buf(),
+            parameters,
+            step: 0,
+        }
+    }
+
+    /// The prompt naming the current step.
+    pub fn prompt(&self) -> String {
+        match self.trace.steps.get(self.step) {
+            Some(step) => format!(
+                "({}/{} {}) ",
+                self.step,
+                self.trace.steps.len().saturating_sub(1),
+                node_name(step.node.as_deref())
+            ),
+            None => "(no steps) ".to_string(),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let ended = match (&self.trace.result, &self.trace.error) {
+            (Some(result), _) => format!(
+                "ended with status `{}`",
+                result["status"].as_str().unwrap_or("unknown")
+            ),
+            (None, Some(error)) => format!("stopped: {error}"),
+            (None, None) => "ended without a result".to_string(),
+        };
+        format!(
+            "flow `{}` of {}: {} step(s), {ended}; `help` lists the commands",
+            self.trace.flow,
+            self.trace.pack_id,
+            self.trace.steps.len()
+        )
+    }
+
+    /// Runs one command line, writing what it shows to `out`.
+    pub fn command(&mut self, line: &str, out: &mut dyn Write) -> Result<Control> {
+        let line = line.trim();
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((command, arg)) => (command, arg.trim()),
+            None => (line, ""),
+        };
+        match command {
+            "" => {}
+            "n" | "next" => self.go(self.step + 1, out)?,
+            "p" | "prev" => match self.step.checked_sub(1) {
+                Some(step) => self.go(step, out)?,
+                None => bail!("already at the first step"),
+            },
+            "g" | "goto" => {
+                let step = arg
+                    .parse()
+                    .map_err(|_| anyhow!("`goto` takes a step number"))?;
+                self.go(step, out)?;
+            }
+            "l" | "list" => out.write_all(self.list().as_bytes())?,
+            "s" | "show" => out.write_all(self.show().as_bytes())?,
+            "state" => writeln!(out, "{}", serde_json::to_string_pretty(&self.state())?)?,
+            "e" | "eval" => {
+                let value = self.eval(arg)?;
+                writeln!(out, "{}", serde_json::to_string_pretty(&value)?)?;
+            }
+            "r" | "result" => match (&self.trace.result, &self.trace.error) {
+                (Some(result), _) => writeln!(out, "{}", serde_json::to_string_pretty(result)?)?,
+                (None, Some(error)) => writeln!(out, "stopped: {error}")?,
+                (None, None) => writeln!(out, "the run ended without a result")?,
+            },
+            "x" | "export" => {
+                let name = (!arg.is_empty()).then_some(arg);
+                let path = self.export(name)?;
+                writeln!(
+                    out,
+                    "wrote {}; replay it with `packc run {} --in {} --replay {}`",
+                    path.display(),
+                    self.trace.flow,
+                    self.pack_dir.display(),
+                    path.display()
+                )?;
+            }
+            "h" | "help" => writeln!(out, "{HELP}")?,
+            "q" | "quit" => return Ok(Control::Quit),
+            other => bail!("unknown command `{other}`; `help` lists the commands"),
+        }
+        Ok(Control::Continue)
+    }
+
+    fn go(&mut self, step: usize, out: &mut dyn Write) -> Result<()> {
+        if step >= self.trace.steps.len() {
+            bail!("the trace has {} step(s)", self.trace.steps.len());
+        }
+        self.step = step;
+        out.write_all(self.show().as_bytes())?;
+        Ok(())
+    }
+
+    fn list(&self) -> String {
+        let mut list = String::new();
+        for (index, step) in self.trace.steps.iter().enumerate() {
+            let marker = if index == self.step { '>' } else { ' ' };
+            let _ = writeln!(
+                list,
+                "{marker} {index:>3}  {}  {} call(s), {} chunk(s)",
+                node_name(step.node.as_deref()),
+                step.calls.len(),
+                step.chunks.len()
+            );
+        }
+        list
+    }
+
+    fn show(&self) -> String {
+        let Some(step) = self.trace.steps.get(self.step) else {
+            return "the trace has no steps\n".to_string();
+        };
+        let mut shown = format!("step {}: {}\n", self.step, node_name(step.node.as_deref()));
+        for call in &step.calls {
+            let _ = writeln!(shown, "  call {}", call.import);
+            let _ = writeln!(shown, "    request:  {}", call.request);
+            let _ = match &call.response {
+                Outcome::Ok(value) => writeln!(shown, "    response: {value}"),
+                Outcome::Error(message) => writeln!(shown, "    error:    {message}"),
+            };
+        }
+        for chunk in &step.chunks {
+            let _ = writeln!(shown, "  chunk {chunk}");
+        }
+        if step.calls.is_empty() && step.chunks.is_empty() {
+            shown.push_str("  no calls or output\n");
+        }
+        shown
+    }
+
+    /// The state once the current step's node ran.
+    pub fn state(&self) -> JsonValue {
+        let mut state = Map::new();
+        state.insert("in".into(), self.trace.input.clone());
+        if let Some(parameters) = &self.parameters {
+            state.insert("parameters".into(), parameters.clone());
+        }
+        for step in self.trace.steps.iter().take(self.step + 1) {
+            if let (Some(node), Some(output)) = (&step.node, step.output()) {
+                let mut node_state = Map::new();
+                node_state.insert("payload".into(), output.clone());
+                state.insert(node.clone(), JsonValue::Object(node_state));
+            }
+        }
+        JsonValue::Object(state)
+    }
+
+    /// Evaluates `expr` against [`state`](Self::state): a Handlebars
+    /// template when it holds `{{`, a dotted path otherwise.
+    pub fn eval(&self, expr: &str) -> Result<JsonValue> {
+        let expr = expr.trim();
+        if expr.is_empty() {
+            bail!("`eval` takes an expression");
+        }
+        let state = self.state();
+        if expr.contains("{{") {
+            let mut handlebars = handlebars::Handlebars::new();
+            handlebars.register_escape_fn(handlebars::no_escape);
+            let rendered = handlebars
+                .render_template(expr, &state)
+                .context("the template does not render")?;
+            return Ok(JsonValue::String(rendered));
+        }
+        let mut value = &state;
+        for segment in expr.split('.') {
+            let next = match value {
+                JsonValue::Object(map) => map.get(segment),
+                JsonValue::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+                _ => None,
+            };
+            value = next.ok_or_else(|| {
+                anyhow!(
+                    "`{expr}` is not in the state at step {}; `state` shows it",
+                    self.step
+                )
+            })?;
+        }
+        Ok(value.clone())
+    }
+
+    /// Writes the cassette replaying the run up to the current step to
+    /// `<pack>/tests/<name>.cassette.json`; `name` defaults to the flow and
+    /// node.
+    pub fn export(&self, name: Option<&str>) -> Result<PathBuf> {
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => match self
+                .trace
+                .steps
+                .get(self.step)
+                .and_then(|s| s.node.as_deref())
+            {
+                Some(node) => format!("{}-{node}", self.trace.flow),
+                None => format!("{}-step{}", self.trace.flow, self.step),
+            },
+        };
+        if name.contains(['/', '\\']) || name.starts_with('.') {
+            bail!("`{name}` is not a file name");
+        }
+        let path = self
+            .pack_dir
+            .join(TESTS_DIR)
+            .join(format!("{name}.cassette.json"));
+        self.trace.cassette_through(self.step).save(&path)?;
+        Ok(path)
+    }
+}
+
+fn node_name(node: Option<&str>) -> &str {
+    node.unwrap_or("(before the first node)")
+}
+
+/// The `parameters` of the flow in `source`, as JSON.
+fn flow_parameters(source: &str) -> Option<JsonValue> {
+    let flow: YamlValue = serde_yaml_bw::from_str(source).ok()?;
+    serde_json::to_value(flow.get("parameters")?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use greentic_pack::cassette::{Cassette, RedactionRules};
+    use greentic_pack::trace::TraceRecorder;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn trace() -> Trace {
+        let mut recorder = TraceRecorder::new(
+            "demo.weather",
+            "forecast",
+            &json!({ "city": "Oslo" }),
+            RedactionRules::standard(),
+        );
+        recorder.enter("fetch");
+        recorder.record(
+            "http.fetch",
+            &json!({ "url": "https://weather.example/oslo" }),
+            &Outcome::Ok(json!({ "temp": 4, "days": [{ "temp": 5 }] })),
+        );
+        recorder.enter("reply");
+        recorder.record(
+            "email.send",
+            &json!({ "to": "ops" }),
+            &Outcome::Error("quota".into()),
+        );
+        recorder.finish(Err("`email.send#send` failed: quota".into()))
+    }
+
+    fn run(debugger: &mut Debugger, line: &str) -> String {
+        let mut out = Vec::new();
+        debugger.command(line, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn steps_through_nodes_and_evaluates_against_recorded_state() {
+        let dir = tempdir().unwrap();
+        let mut debugger = Debugger::new(trace(), dir.path());
+        assert!(debugger.summary().contains("2 step(s), stopped"));
+        assert_eq!(debugger.prompt(), "(0/1 fetch) ");
+
+        assert!(run(&mut debugger, "show").contains("response: {\"days\""));
+        assert_eq!(debugger.eval("fetch.payload.days.0.temp").unwrap(), 5);
+        assert_eq!(
+            debugger
+                .eval("{{in.city}}: {{fetch.payload.temp}}°")
+                .unwrap(),
+            "Oslo: 4°"
+        );
+        assert!(debugger.eval("reply.payload").is_err());
+
+        let shown = run(&mut debugger, "n");
+        assert!(shown.contains("step 1: reply") && shown.contains("error:    quota"));
+        assert!(run(&mut debugger, "list").contains(">   1  reply"));
+        let err = debugger.command("next", &mut Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("2 step(s)"), "{err}");
+        assert_eq!(
+            debugger.command("quit", &mut Vec::new()).unwrap(),
+            Control::Quit
+        );
+    }
+
+    #[test]
+    fn exports_the_calls_up_to_the_current_step() {
+        let dir = tempdir().unwrap();
+        let mut debugger = Debugger::new(trace(), dir.path());
+        let shown = run(&mut debugger, "export");
+        let path = dir.path().join("tests/forecast-fetch.cassette.json");
+        assert!(shown.contains("--replay"), "{shown}");
+        let cassette = Cassette::load(&path).unwrap();
+        assert_eq!(cassette.interactions.len(), 1);
+        assert_eq!(cassette.input, json!({ "city": "Oslo" }));
+
+        run(&mut debugger, "goto 1");
+        run(&mut debugger, "export quota");
+        let cassette = Cassette::load(&dir.path().join("tests/quota.cassette.json")).unwrap();
+        assert_eq!(cassette.interactions.len(), 2);
+        assert!(debugger.export(Some("../escape")).is_err());
+    }
+}
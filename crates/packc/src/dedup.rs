@@ -0,0 +1,277 @@
+//! Template assets copied between the packs of a workspace.
+//!
+//! Assets are grouped by content digest first. Text assets are then compared
+//! by the overlap of their word 3-shingles, so copies that drifted apart by a
+//! few edits still show up as near-identical.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
+
+use serde::Serialize;
+
+/// A template asset of one pack.
+#[derive(Clone, Debug)]
+pub struct PackAsset {
+    pub pack: String,
+    pub path: String,
+    pub digest: String,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateKind {
+    Identical,
+    Similar,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DuplicateGroup {
+    pub kind: DuplicateKind,
+    /// Lowest similarity between linked copies, from 0 to 1; 1 when identical.
+    pub similarity: f64,
+    pub assets: Vec<AssetRef>,
+    /// Bytes saved by keeping a single copy.
+    pub duplicated_bytes: u64,
+    pub suggestion: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AssetRef {
+    pub pack: String,
+    pub path: String,
+    pub digest: String,
+    pub size: u64,
+}
+
+/// Finds assets shared by more than one pack: identical copies, then text
+/// assets at least `threshold` similar. Assets smaller than `min_size` bytes
+/// are ignored.
+pub fn find_duplicates(assets: &[PackAsset], threshold: f64, min_size: u64) -> Vec<DuplicateGroup> {
+    let mut by_digest: BTreeMap<&str, Vec<&PackAsset>> = BTreeMap::new();
+    for asset in assets.iter().filter(|a| a.bytes.len() as u64 >= min_size) {
+        by_digest.entry(&asset.digest).or_default().push(asset);
+    }
+    let units: Vec<Vec<&PackAsset>> = by_digest.into_values().collect();
+
+    let mut groups: Vec<DuplicateGroup> = units
+        .iter()
+        .filter(|unit| pack_count(unit.iter().copied()) > 1)
+        .map(|unit| {
+            let size = unit[0].bytes.len() as u64;
+            let paths = distinct_paths(unit.iter().copied());
+            DuplicateGroup {
+                kind: DuplicateKind::Identical,
+                similarity: 1.0,
+                assets: refs(unit.iter().copied()),
+                duplicated_bytes: size * (unit.len() as u64 - 1),
+                suggestion: format!(
+                    "extract {paths} into a shared template pack; {} packs carry identical copies",
+                    pack_count(unit.iter().copied())
+                ),
+            }
+        })
+        .collect();
+    groups.extend(similar_groups(&units, threshold));
+    groups.sort_by(|a, b| {
+        b.duplicated_bytes
+            .cmp(&a.duplicated_bytes)
+            .then_with(|| first_asset(a).cmp(&first_asset(b)))
+    });
+    groups
+}
+
+/// Clusters distinct contents whose shingle sets overlap by at least
+/// `threshold`.
+fn similar_groups(units: &[Vec<&PackAsset>], threshold: f64) -> Vec<DuplicateGroup> {
+    let shingles: Vec<Option<BTreeSet<u64>>> = units
+        .iter()
+        .map(|unit| std::str::from_utf8(&unit[0].bytes).ok().map(shingles))
+        .collect();
+
+    let mut parent: Vec<usize> = (0..units.len()).collect();
+    let mut weakest: BTreeMap<usize, f64> = BTreeMap::new();
+    for (i, a) in shingles.iter().enumerate() {
+        let Some(a) = a else { continue };
+        for (j, b) in shingles.iter().enumerate().skip(i + 1) {
+            let Some(b) = b else { continue };
+            let similarity = jaccard(a, b);
+            if similarity >= threshold {
+                let (root_i, root_j) = (find(&mut parent, i), find(&mut parent, j));
+                let low = similarity
+                    .min(weakest.remove(&root_i).unwrap_or(1.0))
+                    .min(weakest.remove(&root_j).unwrap_or(1.0));
+                parent[root_j] = root_i;
+                weakest.insert(root_i, low);
+            }
+        }
+    }
+
+    let mut clusters: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for index in 0..units.len() {
+        let root = find(&mut parent, index);
+        clusters.entry(root).or_default().push(index);
+    }
+    clusters
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .filter_map(|(root, members)| {
+            let assets: Vec<&PackAsset> = members
+                .iter()
+                .flat_map(|&index| units[index].iter().copied())
+                .collect();
+            if pack_count(assets.iter().copied()) < 2 {
+                return None;
+            }
+            let sizes: Vec<u64> = assets.iter().map(|a| a.bytes.len() as u64).collect();
+            let largest = sizes.iter().copied().max().unwrap_or(0);
+            Some(DuplicateGroup {
+                kind: DuplicateKind::Similar,
+                similarity: round(weakest.get(&root).copied().unwrap_or(1.0)),
+                duplicated_bytes: sizes.iter().sum::<u64>() - largest,
+                suggestion: format!(
+                    "reconcile the {} variants of {} and extract one into a shared template pack",
+                    members.len(),
+                    distinct_paths(assets.iter().copied())
+                ),
+                assets: refs(assets.into_iter()),
+            })
+        })
+        .collect()
+}
+
+/// Hashes of every run of three consecutive words; short texts hash their
+/// words instead.
+fn shingles(text: &str) -> BTreeSet<u64> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let hash = |parts: &[&str]| {
+        let mut hasher = DefaultHasher::new();
+        parts.hash(&mut hasher);
+        hasher.finish()
+    };
+    if words.len() < 3 {
+        return words.iter().map(|word| hash(&[word])).collect();
+    }
+    words.windows(3).map(hash).collect()
+}
+
+fn jaccard(a: &BTreeSet<u64>, b: &BTreeSet<u64>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+fn find(parent: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    parent[index] = root;
+    root
+}
+
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+fn pack_count<'a>(assets: impl Iterator<Item = &'a PackAsset>) -> usize {
+    assets
+        .map(|a| a.pack.as_str())
+        .collect::<BTreeSet<_>>()
+        .len()
+}
+
+fn distinct_paths<'a>(assets: impl Iterator<Item = &'a PackAsset>) -> String {
+    assets
+        .map(|a| format!("`{}`", a.path))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn refs<'a>(assets: impl Iterator<Item = &'a PackAsset>) -> Vec<AssetRef> {
+    let mut refs: Vec<AssetRef> = assets
+        .map(|a| AssetRef {
+            pack: a.pack.clone(),
+            path: a.path.clone(),
+            digest: a.digest.clone(),
+            size: a.bytes.len() as u64,
+        })
+        .collect();
+    refs.sort_by(|a, b| (&a.pack, &a.path).cmp(&(&b.pack, &b.path)));
+    refs
+}
+
+fn first_asset(group: &DuplicateGroup) -> Option<(&str, &str)> {
+    group
+        .assets
+        .first()
+        .map(|a| (a.pack.as_str(), a.path.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(pack: &str, path: &str, text: &str) -> PackAsset {
+        PackAsset {
+            pack: pack.to_string(),
+            path: path.to_string(),
+            digest: crate::digest::DigestAlgorithm::default().digest(text.as_bytes()),
+            bytes: text.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn reports_identical_and_near_identical_copies_across_packs() {
+        let footer = "Thanks for using our service. Reply STOP to unsubscribe from these updates.";
+        let card =
+            "Weather in {{city}} is {{summary}} with a high of {{high}} and a low of {{low}} today";
+        let assets = vec![
+            asset("a", "templates/footer.hbs", footer),
+            asset("b", "templates/partials/footer.hbs", footer),
+            asset("a", "templates/card.hbs", card),
+            asset(
+                "b",
+                "templates/card.hbs",
+                &card.replace(" today", " this afternoon"),
+            ),
+            asset(
+                "c",
+                "templates/other.hbs",
+                "Something else entirely, unrelated to the rest.",
+            ),
+            asset("c", "templates/tiny.hbs", "hi"),
+            asset("a", "templates/tiny.hbs", "hi"),
+        ];
+
+        let groups = find_duplicates(&assets, 0.7, 8);
+        assert_eq!(groups.len(), 2, "{groups:#?}");
+
+        let of_kind = |kind| groups.iter().find(|group| group.kind == kind).unwrap();
+        let identical = of_kind(DuplicateKind::Identical);
+        assert_eq!(identical.duplicated_bytes, footer.len() as u64);
+        assert_eq!(identical.assets.len(), 2);
+
+        let similar = of_kind(DuplicateKind::Similar);
+        assert!(similar.similarity >= 0.7 && similar.similarity < 1.0);
+        assert_eq!(
+            similar
+                .assets
+                .iter()
+                .map(|a| a.pack.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a", "b"]
+        );
+
+        assert!(
+            find_duplicates(&assets, 0.99, 8)
+                .iter()
+                .all(|group| group.kind == DuplicateKind::Identical)
+        );
+    }
+}
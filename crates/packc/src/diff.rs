@@ -0,0 +1,760 @@
+//! Semantic differences between two builds of a pack.
+//!
+//! Besides listing archive entries that were added, removed, or changed, the
+//! diff reads both manifests and reports changes by category, each with a
+//! severity that reflects how much scrutiny it deserves: a new capability or
+//! signing key is `high`, a new flow is `low`, and file churn is `info`.
+//! Flows, templates, and components are compared by digest, and changes to
+//! them carry the BLAKE3 digests on either side.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use greentic_pack::builder::{ComponentEntry, PackMeta};
+use greentic_pack::capabilities::FlowRequirements;
+use greentic_pack::quotas::ImportQuota;
+use greentic_pack::{ArchiveEntry, PackLoad, SignatureInfo};
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    Signature,
+    Capabilities,
+    Imports,
+    Secrets,
+    DataHandling,
+    Limits,
+    Events,
+    Flows,
+    Templates,
+    Components,
+    Metadata,
+    Files,
+}
+
+impl Category {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Category::Signature => "signature",
+            Category::Capabilities => "capabilities",
+            Category::Imports => "imports",
+            Category::Secrets => "secrets",
+            Category::DataHandling => "data_handling",
+            Category::Limits => "limits",
+            Category::Events => "events",
+            Category::Flows => "flows",
+            Category::Templates => "templates",
+            Category::Components => "components",
+            Category::Metadata => "metadata",
+            Category::Files => "files",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Change {
+    pub category: Category,
+    pub severity: Severity,
+    /// What changed: a capability, import, flow id, file path, ...
+    pub subject: String,
+    pub message: String,
+    /// BLAKE3 digest of the subject in the earlier build, for flows,
+    /// templates, and components.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_digest: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_digest: Option<String>,
+}
+
+/// The parts of a loaded pack the diff compares.
+pub struct PackSnapshot {
+    pub meta: PackMeta,
+    pub flows: Vec<String>,
+    /// Digest of each flow, by id.
+    pub flow_digests: BTreeMap<String, String>,
+    pub components: Vec<ComponentEntry>,
+    pub requirements: BTreeMap<String, FlowRequirements>,
+    pub signature: Option<SignatureInfo>,
+    pub entries: Vec<ArchiveEntry>,
+    /// Entries reported under `flows` or `components` rather than `files`.
+    pub covered: BTreeSet<String>,
+}
+
+impl PackSnapshot {
+    pub fn from_load(load: &PackLoad) -> Result<Self> {
+        let flows: Vec<String> = load.manifest.flows.iter().map(|f| f.id.clone()).collect();
+        let requirements = flows
+            .iter()
+            .map(|flow| Ok((flow.clone(), load.flow_requirements(flow)?)))
+            .collect::<Result<_>>()?;
+        let flow_digests = load
+            .manifest
+            .flows
+            .iter()
+            .map(|flow| (flow.id.clone(), flow.hash_blake3.clone()))
+            .collect();
+        let flow_files = load
+            .manifest
+            .flows
+            .iter()
+            .flat_map(|flow| [flow.file_yaml.clone(), flow.file_json.clone()]);
+        let component_files = load.manifest.components.iter().map(|c| c.file_wasm.clone());
+        let covered = flow_files.chain(component_files).collect();
+        Ok(Self {
+            meta: load.manifest.meta.clone(),
+            flows,
+            flow_digests,
+            components: load.manifest.components.clone(),
+            covered,
+            requirements,
+            signature: load.signature(),
+            entries: load.entries(),
+        })
+    }
+}
+
+/// Entries rewritten by every build, whose changes the manifest categories
+/// already describe.
+const BUILD_ENTRIES: &[&str] = &[
+    "manifest.cbor",
+    "manifest.cbor.zst",
+    "manifest.json",
+    "sbom.json",
+    "provenance.json",
+];
+
+/// Archive prefix of templates, which the builder stores as assets under
+/// their logical path.
+const TEMPLATE_PREFIX: &str = "assets/templates/";
+
+/// Changes from `old` to `new`, most severe first.
+pub fn diff(old: &PackSnapshot, new: &PackSnapshot) -> Vec<Change> {
+    let mut changes = Changes::default();
+    signature(&mut changes, old.signature.as_ref(), new.signature.as_ref());
+    capabilities(&mut changes, old, new);
+    imports(&mut changes, &old.meta, &new.meta);
+    secrets(&mut changes, &old.meta, &new.meta);
+    data_handling(&mut changes, &old.meta, &new.meta);
+    limits(&mut changes, &old.meta, &new.meta);
+    events(&mut changes, &old.meta, &new.meta);
+    flows(&mut changes, old, new);
+    templates(&mut changes, &old.entries, &new.entries);
+    components(&mut changes, &old.components, &new.components);
+    metadata(&mut changes, &old.meta, &new.meta);
+    files(&mut changes, old, new);
+
+    let mut changes = changes.0;
+    changes.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then(a.category.cmp(&b.category))
+            .then_with(|| a.subject.cmp(&b.subject))
+    });
+    changes
+}
+
+#[derive(Default)]
+struct Changes(Vec<Change>);
+
+impl Changes {
+    fn push(
+        &mut self,
+        category: Category,
+        severity: Severity,
+        subject: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        self.0.push(Change {
+            category,
+            severity,
+            subject: subject.into(),
+            message: message.into(),
+            from_digest: None,
+            to_digest: None,
+        });
+    }
+
+    /// Records a change to a digested subject; the message gains the
+    /// abbreviated digests so human output shows them too.
+    fn push_digests(
+        &mut self,
+        category: Category,
+        severity: Severity,
+        subject: impl Into<String>,
+        message: &str,
+        from: Option<&String>,
+        to: Option<&String>,
+    ) {
+        let short = |digest: &String| digest.chars().take(12).collect::<String>();
+        let message = match (from, to) {
+            (Some(from), Some(to)) => format!("{message} ({} -> {})", short(from), short(to)),
+            (Some(digest), None) | (None, Some(digest)) => {
+                format!("{message} ({})", short(digest))
+            }
+            (None, None) => message.to_string(),
+        };
+        self.0.push(Change {
+            category,
+            severity,
+            subject: subject.into(),
+            message,
+            from_digest: from.cloned(),
+            to_digest: to.cloned(),
+        });
+    }
+}
+
+fn signature(changes: &mut Changes, old: Option<&SignatureInfo>, new: Option<&SignatureInfo>) {
+    let (Some(old), Some(new)) = (old, new) else {
+        return;
+    };
+    if old.alg != new.alg {
+        changes.push(
+            Category::Signature,
+            Severity::High,
+            "alg",
+            format!(
+                "signature algorithm changed from {} to {}",
+                old.alg, new.alg
+            ),
+        );
+    }
+    if old.key_fingerprint != new.key_fingerprint {
+        let describe = |key: &Option<String>| key.clone().unwrap_or_else(|| "unknown".into());
+        changes.push(
+            Category::Signature,
+            Severity::High,
+            "key",
+            format!(
+                "signed by a different key: {} -> {}",
+                describe(&old.key_fingerprint),
+                describe(&new.key_fingerprint)
+            ),
+        );
+    }
+}
+
+fn capabilities(changes: &mut Changes, old: &PackSnapshot, new: &PackSnapshot) {
+    let used_by = |snapshot: &PackSnapshot| {
+        let mut by_capability: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (flow, requirements) in &snapshot.requirements {
+            for capability in requirements.capabilities() {
+                by_capability
+                    .entry(capability.to_string())
+                    .or_default()
+                    .push(flow.clone());
+            }
+        }
+        by_capability
+    };
+    let (before, after) = (used_by(old), used_by(new));
+
+    for (capability, flows) in &after {
+        match before.get(capability) {
+            None => changes.push(
+                Category::Capabilities,
+                Severity::High,
+                capability,
+                format!("newly required, by {}", flows.join(", ")),
+            ),
+            Some(previous) => {
+                let added: Vec<&str> = flows
+                    .iter()
+                    .filter(|flow| !previous.contains(flow))
+                    .map(String::as_str)
+                    .collect();
+                if !added.is_empty() {
+                    changes.push(
+                        Category::Capabilities,
+                        Severity::Medium,
+                        capability,
+                        format!("now also required by {}", added.join(", ")),
+                    );
+                }
+            }
+        }
+    }
+    for capability in before.keys().filter(|c| !after.contains_key(*c)) {
+        changes.push(
+            Category::Capabilities,
+            Severity::Low,
+            capability,
+            "no longer required",
+        );
+    }
+}
+
+fn imports(changes: &mut Changes, old: &PackMeta, new: &PackMeta) {
+    let requirements = |meta: &PackMeta| -> BTreeMap<String, String> {
+        meta.imports
+            .iter()
+            .map(|import| (import.pack_id.clone(), import.version_req.clone()))
+            .collect()
+    };
+    let (before, after) = (requirements(old), requirements(new));
+    for (import, version) in &after {
+        match before.get(import) {
+            None => changes.push(
+                Category::Imports,
+                Severity::High,
+                import,
+                format!("new import ({version})"),
+            ),
+            Some(previous) if previous != version => changes.push(
+                Category::Imports,
+                Severity::Medium,
+                import,
+                format!("version requirement changed from {previous} to {version}"),
+            ),
+            Some(_) => {}
+        }
+    }
+    for import in before.keys().filter(|i| !after.contains_key(*i)) {
+        changes.push(Category::Imports, Severity::Low, import, "import removed");
+    }
+}
+
+fn secrets(changes: &mut Changes, old: &PackMeta, new: &PackMeta) {
+    let secrets = |meta: &PackMeta| {
+        meta.config
+            .as_ref()
+            .map(|config| config.secrets.clone())
+            .unwrap_or_default()
+    };
+    let (before, after) = (secrets(old), secrets(new));
+    for (name, mapping) in &after {
+        match before.get(name) {
+            None => changes.push(
+                Category::Secrets,
+                Severity::High,
+                name,
+                format!("new secret read from `{}`", mapping.key),
+            ),
+            Some(previous) if previous != mapping => changes.push(
+                Category::Secrets,
+                Severity::Medium,
+                name,
+                format!(
+                    "now read from `{}`{}",
+                    mapping.key,
+                    if mapping.required { "" } else { " (optional)" }
+                ),
+            ),
+            Some(_) => {}
+        }
+    }
+    for name in before.keys().filter(|n| !after.contains_key(*n)) {
+        changes.push(Category::Secrets, Severity::Low, name, "secret removed");
+    }
+}
+
+fn data_handling(changes: &mut Changes, old: &PackMeta, new: &PackMeta) {
+    let level = |meta: &PackMeta| meta.data_handling.as_ref().and_then(|data| data.level);
+    let (before, after) = (level(old), level(new));
+    if before == after {
+        return;
+    }
+    let describe = |level: Option<_>| level.map_or("unclassified".to_string(), |l| format!("{l}"));
+    let severity = if after > before {
+        Severity::High
+    } else {
+        Severity::Low
+    };
+    changes.push(
+        Category::DataHandling,
+        severity,
+        "level",
+        format!(
+            "data handling level changed from {} to {}",
+            describe(before),
+            describe(after)
+        ),
+    );
+}
+
+fn limits(changes: &mut Changes, old: &PackMeta, new: &PackMeta) {
+    let quotas = |meta: &PackMeta| -> BTreeMap<(String, String), ImportQuota> {
+        meta.quotas
+            .iter()
+            .flat_map(|section| &section.flows)
+            .flat_map(|(flow, imports)| {
+                imports
+                    .iter()
+                    .map(move |(import, quota)| ((flow.clone(), import.clone()), *quota))
+            })
+            .collect()
+    };
+    let describe = |quota: &ImportQuota| match quota.burst {
+        Some(burst) => format!("{}/min, burst {burst}", quota.requests_per_minute),
+        None => format!("{}/min", quota.requests_per_minute),
+    };
+    let (before, after) = (quotas(old), quotas(new));
+    for ((flow, import), quota) in &after {
+        let subject = format!("{flow}/{import}");
+        match before.get(&(flow.clone(), import.clone())) {
+            None => changes.push(
+                Category::Limits,
+                Severity::Low,
+                subject,
+                format!("new quota {}", describe(quota)),
+            ),
+            Some(previous) if previous != quota => {
+                let loosened = quota.requests_per_minute > previous.requests_per_minute
+                    || quota.burst.unwrap_or(0) > previous.burst.unwrap_or(0);
+                changes.push(
+                    Category::Limits,
+                    if loosened {
+                        Severity::Medium
+                    } else {
+                        Severity::Low
+                    },
+                    subject,
+                    format!(
+                        "quota changed from {} to {}",
+                        describe(previous),
+                        describe(quota)
+                    ),
+                );
+            }
+            Some(_) => {}
+        }
+    }
+    for (flow, import) in before.keys().filter(|key| !after.contains_key(*key)) {
+        changes.push(
+            Category::Limits,
+            Severity::Medium,
+            format!("{flow}/{import}"),
+            "quota removed; calls are no longer rate limited",
+        );
+    }
+}
+
+fn events(changes: &mut Changes, old: &PackMeta, new: &PackMeta) {
+    let providers = |meta: &PackMeta| {
+        meta.events
+            .iter()
+            .flat_map(|section| &section.providers)
+            .map(|provider| (provider.name.clone(), provider.clone()))
+            .collect::<BTreeMap<_, _>>()
+    };
+    let (before, after) = (providers(old), providers(new));
+    for (name, provider) in &after {
+        match before.get(name) {
+            None => changes.push(
+                Category::Events,
+                Severity::Medium,
+                name,
+                format!(
+                    "new {} provider using {}",
+                    provider.kind, provider.component
+                ),
+            ),
+            Some(previous) if previous != provider => {
+                let mut fields = Vec::new();
+                if previous.kind != provider.kind {
+                    fields.push("kind");
+                }
+                if previous.component != provider.component {
+                    fields.push("component");
+                }
+                if previous.default_flow != provider.default_flow
+                    || previous.custom_flow != provider.custom_flow
+                {
+                    fields.push("flows");
+                }
+                if previous.capabilities != provider.capabilities {
+                    fields.push("capabilities");
+                }
+                changes.push(
+                    Category::Events,
+                    Severity::Medium,
+                    name,
+                    format!("provider changed: {}", fields.join(", ")),
+                );
+            }
+            Some(_) => {}
+        }
+    }
+    for name in before.keys().filter(|n| !after.contains_key(*n)) {
+        changes.push(Category::Events, Severity::Low, name, "provider removed");
+    }
+}
+
+fn flows(changes: &mut Changes, old: &PackSnapshot, new: &PackSnapshot) {
+    let (before, after) = (&old.flow_digests, &new.flow_digests);
+    for (flow, digest) in after {
+        match before.get(flow) {
+            None => changes.push_digests(
+                Category::Flows,
+                Severity::Low,
+                flow,
+                "flow added",
+                None,
+                Some(digest),
+            ),
+            Some(previous) if previous != digest => changes.push_digests(
+                Category::Flows,
+                Severity::Low,
+                flow,
+                "flow changed",
+                Some(previous),
+                Some(digest),
+            ),
+            Some(_) => {}
+        }
+    }
+    for (flow, digest) in before.iter().filter(|(f, _)| !after.contains_key(*f)) {
+        changes.push_digests(
+            Category::Flows,
+            Severity::Medium,
+            flow,
+            "flow removed",
+            Some(digest),
+            None,
+        );
+    }
+    let entry = |meta: &PackMeta| meta.entry_flows.iter().cloned().collect::<BTreeSet<_>>();
+    let (before, after) = (entry(&old.meta), entry(&new.meta));
+    for flow in after.difference(&before) {
+        changes.push(Category::Flows, Severity::Low, flow, "now an entry flow");
+    }
+    for flow in before.difference(&after) {
+        changes.push(
+            Category::Flows,
+            Severity::Medium,
+            flow,
+            "no longer an entry flow",
+        );
+    }
+}
+
+fn templates(changes: &mut Changes, old: &[ArchiveEntry], new: &[ArchiveEntry]) {
+    let index = |entries: &[ArchiveEntry]| -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .filter(|entry| entry.path.starts_with(TEMPLATE_PREFIX))
+            .map(|entry| {
+                let logical = entry.path.trim_start_matches("assets/").to_string();
+                (logical, entry.hash_blake3.clone())
+            })
+            .collect()
+    };
+    let (before, after) = (index(old), index(new));
+    for (path, digest) in &after {
+        match before.get(path) {
+            None => changes.push_digests(
+                Category::Templates,
+                Severity::Low,
+                path,
+                "template added",
+                None,
+                Some(digest),
+            ),
+            Some(previous) if previous != digest => changes.push_digests(
+                Category::Templates,
+                Severity::Low,
+                path,
+                "template changed",
+                Some(previous),
+                Some(digest),
+            ),
+            Some(_) => {}
+        }
+    }
+    for (path, digest) in before.iter().filter(|(p, _)| !after.contains_key(*p)) {
+        changes.push_digests(
+            Category::Templates,
+            Severity::Medium,
+            path,
+            "template removed",
+            Some(digest),
+            None,
+        );
+    }
+}
+
+fn components(changes: &mut Changes, old: &[ComponentEntry], new: &[ComponentEntry]) {
+    let index = |components: &[ComponentEntry]| -> BTreeMap<String, ComponentEntry> {
+        components
+            .iter()
+            .map(|component| (component.name.clone(), component.clone()))
+            .collect()
+    };
+    let (before, after) = (index(old), index(new));
+    for (name, component) in &after {
+        let digest = Some(&component.hash_blake3);
+        match before.get(name) {
+            None => changes.push_digests(
+                Category::Components,
+                Severity::Medium,
+                name,
+                &format!("component added at {}", component.version),
+                None,
+                digest,
+            ),
+            Some(previous) if previous.version != component.version => changes.push_digests(
+                Category::Components,
+                if component.version < previous.version {
+                    Severity::Medium
+                } else {
+                    Severity::Low
+                },
+                name,
+                &format!(
+                    "version changed from {} to {}",
+                    previous.version, component.version
+                ),
+                Some(&previous.hash_blake3),
+                digest,
+            ),
+            Some(previous) if previous.hash_blake3 != component.hash_blake3 => changes
+                .push_digests(
+                    Category::Components,
+                    Severity::Medium,
+                    name,
+                    &format!(
+                        "contents changed without a version bump ({})",
+                        component.version
+                    ),
+                    Some(&previous.hash_blake3),
+                    digest,
+                ),
+            Some(_) => {}
+        }
+    }
+    for (name, component) in before.iter().filter(|(n, _)| !after.contains_key(*n)) {
+        changes.push_digests(
+            Category::Components,
+            Severity::Medium,
+            name,
+            &format!("component removed (was {})", component.version),
+            Some(&component.hash_blake3),
+            None,
+        );
+    }
+}
+
+fn metadata(changes: &mut Changes, old: &PackMeta, new: &PackMeta) {
+    if old.pack_id != new.pack_id {
+        changes.push(
+            Category::Metadata,
+            Severity::High,
+            "pack_id",
+            format!("pack id changed from {} to {}", old.pack_id, new.pack_id),
+        );
+    }
+    if old.version != new.version {
+        let severity = if new.version < old.version {
+            Severity::Medium
+        } else {
+            Severity::Info
+        };
+        changes.push(
+            Category::Metadata,
+            severity,
+            "version",
+            format!("version changed from {} to {}", old.version, new.version),
+        );
+    }
+
+    let list = |values: &[String]| (!values.is_empty()).then(|| values.join(", "));
+    let fields = [
+        ("name", Some(old.name.clone()), Some(new.name.clone())),
+        (
+            "description",
+            old.description.clone(),
+            new.description.clone(),
+        ),
+        ("license", old.license.clone(), new.license.clone()),
+        ("homepage", old.homepage.clone(), new.homepage.clone()),
+        ("support", old.support.clone(), new.support.clone()),
+        ("vendor", old.vendor.clone(), new.vendor.clone()),
+        ("authors", list(&old.authors), list(&new.authors)),
+        ("tags", list(&old.tags), list(&new.tags)),
+        ("categories", list(&old.categories), list(&new.categories)),
+    ];
+    let describe = |value: &Option<String>| {
+        value
+            .as_ref()
+            .map_or("unset".to_string(), |value| format!("`{value}`"))
+    };
+    for (field, before, after) in fields {
+        if before == after {
+            continue;
+        }
+        // Relicensing changes the terms hosts run the pack under.
+        let severity = if field == "license" {
+            Severity::Medium
+        } else {
+            Severity::Info
+        };
+        changes.push(
+            Category::Metadata,
+            severity,
+            field,
+            format!(
+                "{field} changed from {} to {}",
+                describe(&before),
+                describe(&after)
+            ),
+        );
+    }
+}
+
+fn files(changes: &mut Changes, old: &PackSnapshot, new: &PackSnapshot) {
+    let index = |snapshot: &PackSnapshot| -> BTreeMap<String, String> {
+        snapshot
+            .entries
+            .iter()
+            .filter(|entry| {
+                !BUILD_ENTRIES.contains(&entry.path.as_str())
+                    && !entry.path.starts_with("signatures/")
+                    && !entry.path.starts_with(TEMPLATE_PREFIX)
+                    && !snapshot.covered.contains(&entry.path)
+            })
+            .map(|entry| (entry.path.clone(), entry.hash_blake3.clone()))
+            .collect()
+    };
+    let (before, after) = (index(old), index(new));
+    for (path, hash) in &after {
+        match before.get(path) {
+            None => changes.push(Category::Files, Severity::Info, path, "added"),
+            Some(previous) if previous != hash => {
+                changes.push(Category::Files, Severity::Info, path, "changed")
+            }
+            Some(_) => {}
+        }
+    }
+    for path in before.keys().filter(|p| !after.contains_key(*p)) {
+        changes.push(Category::Files, Severity::Info, path, "removed");
+    }
+}
@@ -0,0 +1,139 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Result, anyhow};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// Hash algorithm used for manifest entries, canonical pack digests, and
+/// signatures. Digests are recorded as `<alg>:<hex>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestAlgorithm {
+    #[default]
+    Sha256,
+    Sha384,
+    Sha512,
+    Blake3,
+}
+
+impl DigestAlgorithm {
+    pub const ALL: [DigestAlgorithm; 4] = [
+        DigestAlgorithm::Sha256,
+        DigestAlgorithm::Sha384,
+        DigestAlgorithm::Sha512,
+        DigestAlgorithm::Blake3,
+    ];
+
+    /// Prefix used in `<alg>:<hex>` digests.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DigestAlgorithm::Sha256 => "sha256",
+            DigestAlgorithm::Sha384 => "sha384",
+            DigestAlgorithm::Sha512 => "sha512",
+            DigestAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    /// Algorithm name as spelled in CycloneDX `hashes[].alg`.
+    pub fn cyclonedx_name(self) -> &'static str {
+        match self {
+            DigestAlgorithm::Sha256 => "SHA-256",
+            DigestAlgorithm::Sha384 => "SHA-384",
+            DigestAlgorithm::Sha512 => "SHA-512",
+            DigestAlgorithm::Blake3 => "BLAKE3",
+        }
+    }
+
+    /// Hex encoded digest of `bytes`, without the algorithm prefix.
+    pub fn hex(self, bytes: &[u8]) -> String {
+        match self {
+            DigestAlgorithm::Sha256 => hex::encode(Sha256::digest(bytes)),
+            DigestAlgorithm::Sha384 => hex::encode(Sha384::digest(bytes)),
+            DigestAlgorithm::Sha512 => hex::encode(Sha512::digest(bytes)),
+            DigestAlgorithm::Blake3 => blake3::hash(bytes).to_hex().to_string(),
+        }
+    }
+
+    /// `<alg>:<hex>` digest of `bytes`.
+    pub fn digest(self, bytes: &[u8]) -> String {
+        format!("{}:{}", self.as_str(), self.hex(bytes))
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DigestAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        DigestAlgorithm::ALL
+            .into_iter()
+            .find(|alg| alg.as_str().eq_ignore_ascii_case(value))
+            .ok_or_else(|| {
+                anyhow!(
+                    "unsupported digest algorithm `{value}` (expected one of: {})",
+                    supported_names()
+                )
+            })
+    }
+}
+
+/// Splits an `<alg>:<hex>` digest into its algorithm and hex value.
+///
+/// Manifests written before digests carried a prefix store bare SHA-256 hex;
+/// those are accepted as `sha256`.
+pub fn parse(digest: &str) -> Result<(DigestAlgorithm, &str)> {
+    match digest.split_once(':') {
+        Some((alg, hex)) => Ok((alg.parse()?, hex)),
+        None => Ok((DigestAlgorithm::Sha256, digest)),
+    }
+}
+
+/// Returns `true` when `digest` (in `<alg>:<hex>` form) matches `bytes`.
+pub fn matches(digest: &str, bytes: &[u8]) -> Result<bool> {
+    let (alg, expected) = parse(digest)?;
+    Ok(alg.hex(bytes).eq_ignore_ascii_case(expected))
+}
+
+fn supported_names() -> String {
+    DigestAlgorithm::ALL
+        .iter()
+        .map(|alg| alg.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digests_round_trip_through_parse() {
+        for alg in DigestAlgorithm::ALL {
+            let digest = alg.digest(b"hello");
+            let (parsed, hex) = parse(&digest).unwrap();
+            assert_eq!(parsed, alg);
+            assert_eq!(hex, alg.hex(b"hello"));
+            assert!(matches(&digest, b"hello").unwrap());
+            assert!(!matches(&digest, b"hello!").unwrap());
+        }
+        assert_eq!(DigestAlgorithm::Sha384.hex(b"").len(), 96);
+        assert_eq!(DigestAlgorithm::Sha512.hex(b"").len(), 128);
+    }
+
+    #[test]
+    fn bare_hex_is_treated_as_sha256_and_unknown_algorithms_fail() {
+        let legacy = DigestAlgorithm::Sha256.hex(b"flow");
+        assert!(matches(&legacy, b"flow").unwrap());
+
+        let err = parse("md5:abcd").unwrap_err().to_string();
+        assert!(err.contains("md5"), "{err}");
+        assert!(err.contains("blake3"), "{err}");
+    }
+}
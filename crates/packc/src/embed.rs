@@ -1,15 +1,97 @@
 use crate::flows::FlowAsset;
+use crate::manifest::PackSpec;
 use crate::templates::TemplateAsset;
 use anyhow::{Context, Result, anyhow};
-use pack_component_template::{CARGO_TOML, DATA_RS_PLACEHOLDER, LIB_RS};
+use greentic_pack::atomic::{self, Durability};
+use greentic_pack::capabilities::flow_requirements;
+use greentic_pack::flow_schema::flow_schemas;
+use pack_component_template::{CARGO_TOML, DATA_RS_PLACEHOLDER, LIB_RS, PACK_EXPORT_WIT};
+use std::borrow::Cow;
 use std::fmt::Write;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use tracing::info;
 
+/// Default feature lines of the component crate's `Cargo.toml`.
+const STD_DEFAULT_FEATURES: &str = "default = [\"std\"]";
+const NO_WASI_DEFAULT_FEATURES: &str = "default = [\"no-wasi\"]";
+
+/// What `find_flows` filters on, embedded next to the flow sources so the
+/// component can answer queries without parsing them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlowIndex {
+    /// Pack tags and categories; every flow carries them.
+    pub tags: Vec<String>,
+    pub categories: Vec<String>,
+    pub flows: Vec<IndexedFlow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedFlow {
+    pub id: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// The flow `type`, e.g. `messaging` or `events`.
+    pub trigger: String,
+    /// Pack imports the flow uses.
+    pub imports: Vec<String>,
+    /// `doc:` strings by node id.
+    pub node_docs: Vec<(String, String)>,
+    /// JSON Schemas of the flow input and output, as JSON text.
+    pub input_schema: String,
+    pub output_schema: String,
+    /// QoS class name and deadline from the pack's `qos` section.
+    pub qos: String,
+    pub deadline_ms: Option<u64>,
+}
+
+impl FlowIndex {
+    pub fn build(spec: &PackSpec, flows: &[FlowAsset]) -> Result<Self> {
+        let flows = flows
+            .iter()
+            .map(|flow| {
+                let requirements = flow_requirements(&flow.raw, &spec.imports_required)
+                    .with_context(|| format!("flow `{}`", flow.bundle.id))?;
+                let schemas = flow_schemas(&flow.raw)
+                    .with_context(|| format!("flow `{}`", flow.bundle.id))?;
+                let qos = spec
+                    .qos
+                    .as_ref()
+                    .map(|qos| qos.flow(&flow.bundle.id))
+                    .unwrap_or_default();
+                let text = |key: &str| {
+                    flow.bundle
+                        .json
+                        .get(key)
+                        .and_then(|value| value.as_str())
+                        .map(str::to_string)
+                };
+                Ok(IndexedFlow {
+                    id: flow.bundle.id.clone(),
+                    title: text("title"),
+                    description: text("description"),
+                    trigger: flow.bundle.kind.clone(),
+                    imports: requirements.imports,
+                    node_docs: flow.node_docs.clone().into_iter().collect(),
+                    input_schema: schemas.input.to_string(),
+                    output_schema: schemas.output.to_string(),
+                    qos: qos.class.to_string(),
+                    deadline_ms: qos.deadline_ms,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            tags: spec.tags.clone(),
+            categories: spec.categories.clone(),
+            flows,
+        })
+    }
+}
+
 pub fn generate_component_data(
     manifest_bytes: &[u8],
+    index: &FlowIndex,
     flows: &[FlowAsset],
     templates: &[TemplateAsset],
 ) -> Result<String> {
@@ -39,6 +121,86 @@ pub fn generate_component_data(
     }
     writeln!(&mut buffer, "];\n")?;
 
+    writeln!(
+        &mut buffer,
+        "pub static PACK_TAGS: &[&'static str] = {};\n",
+        str_slice_literal(&index.tags)
+    )?;
+    writeln!(
+        &mut buffer,
+        "pub static PACK_CATEGORIES: &[&'static str] = {};\n",
+        str_slice_literal(&index.categories)
+    )?;
+    writeln!(
+        &mut buffer,
+        "pub static FLOW_INDEX: &[crate::IndexedFlow] = &["
+    )?;
+    for flow in &index.flows {
+        writeln!(&mut buffer, "    crate::IndexedFlow {{")?;
+        writeln!(
+            &mut buffer,
+            "        id: {},",
+            rust_string_literal(&flow.id)
+        )?;
+        writeln!(
+            &mut buffer,
+            "        title: {},",
+            option_literal(flow.title.as_deref())
+        )?;
+        writeln!(
+            &mut buffer,
+            "        description: {},",
+            option_literal(flow.description.as_deref())
+        )?;
+        writeln!(
+            &mut buffer,
+            "        trigger: {},",
+            rust_string_literal(&flow.trigger)
+        )?;
+        writeln!(
+            &mut buffer,
+            "        imports: {},",
+            str_slice_literal(&flow.imports)
+        )?;
+        let node_docs: Vec<String> = flow
+            .node_docs
+            .iter()
+            .map(|(node, doc)| {
+                format!(
+                    "({}, {})",
+                    rust_string_literal(node),
+                    rust_string_literal(doc)
+                )
+            })
+            .collect();
+        writeln!(
+            &mut buffer,
+            "        node_docs: &[{}],",
+            node_docs.join(", ")
+        )?;
+        writeln!(
+            &mut buffer,
+            "        input_schema: {},",
+            rust_string_literal(&flow.input_schema)
+        )?;
+        writeln!(
+            &mut buffer,
+            "        output_schema: {},",
+            rust_string_literal(&flow.output_schema)
+        )?;
+        writeln!(
+            &mut buffer,
+            "        qos: {},",
+            rust_string_literal(&flow.qos)
+        )?;
+        match flow.deadline_ms {
+            Some(deadline) => writeln!(&mut buffer, "        deadline_ms: Some({deadline}),")?,
+            None => writeln!(&mut buffer, "        deadline_ms: None,")?,
+        }
+        writeln!(&mut buffer, "    }},")?;
+    }
+    writeln!(&mut buffer, "];\n")?;
+
     writeln!(
         &mut buffer,
         "pub static TEMPLATES: &[(&'static str, &'static [u8])] = &["
@@ -53,8 +215,13 @@ pub fn generate_component_data(
     Ok(buffer)
 }
 
-pub fn compile_component(component_data: &Path, output_wasm: &Path) -> Result<()> {
-    let crate_root = prepare_component_crate(component_data)?;
+pub fn compile_component(
+    component_data: &Path,
+    output_wasm: &Path,
+    no_wasi: bool,
+    durability: Durability,
+) -> Result<()> {
+    let crate_root = prepare_component_crate(component_data, no_wasi)?;
 
     info!(
         component_data = %component_data.display(),
@@ -101,12 +268,7 @@ pub fn compile_component(component_data: &Path, output_wasm: &Path) -> Result<()
         );
     }
 
-    if let Some(parent) = output_wasm.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("failed to create directory {}", parent.display()))?;
-    }
-
-    fs::copy(&artifact, output_wasm).with_context(|| {
+    atomic::copy(&artifact, output_wasm, durability).with_context(|| {
         format!(
             "failed to copy wasm artifact from {} to {}",
             artifact.display(),
@@ -181,7 +343,24 @@ fn rust_string_literal(value: &str) -> String {
     literal
 }
 
-fn prepare_component_crate(component_data: &Path) -> Result<PathBuf> {
+fn option_literal(value: Option<&str>) -> String {
+    match value {
+        Some(value) => format!("Some({})", rust_string_literal(value)),
+        None => "None".to_string(),
+    }
+}
+
+fn str_slice_literal(values: &[String]) -> String {
+    let items: Vec<String> = values
+        .iter()
+        .map(|value| rust_string_literal(value))
+        .collect();
+    format!("&[{}]", items.join(", "))
+}
+
+/// Writes the component crate around `component_data`. With `no_wasi` the
+/// crate builds with its `no-wasi` feature instead of `std` by default.
+pub(crate) fn prepare_component_crate(component_data: &Path, no_wasi: bool) -> Result<PathBuf> {
     let src_dir = component_data
         .parent()
         .ok_or_else(|| anyhow!("component data path lacks parent"))?;
@@ -191,8 +370,17 @@ fn prepare_component_crate(component_data: &Path) -> Result<PathBuf> {
         .parent()
         .ok_or_else(|| anyhow!("component data path lacks crate root"))?;
 
-    write_template_file(crate_root.join("Cargo.toml"), CARGO_TOML)?;
+    let cargo_toml = if no_wasi {
+        Cow::Owned(CARGO_TOML.replacen(STD_DEFAULT_FEATURES, NO_WASI_DEFAULT_FEATURES, 1))
+    } else {
+        Cow::Borrowed(CARGO_TOML)
+    };
+    write_template_file(crate_root.join("Cargo.toml"), &cargo_toml)?;
     write_template_file(src_dir.join("lib.rs"), LIB_RS)?;
+    write_template_file(
+        crate_root.join("wit").join("pack-export.wit"),
+        PACK_EXPORT_WIT,
+    )?;
     if !component_data.exists() {
         write_template_file(component_data.to_path_buf(), DATA_RS_PLACEHOLDER)?;
     }
@@ -212,13 +400,14 @@ fn write_template_file(path: PathBuf, contents: &str) -> Result<()> {
             .with_context(|| format!("failed to create directory {}", parent.display()))?;
     }
 
-    fs::write(&path, contents).with_context(|| format!("failed to write {}", path.display()))?;
+    atomic::write(&path, contents.as_bytes(), Durability::Buffered)?;
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::digest::DigestAlgorithm;
     use crate::{flows, manifest, templates};
 
     fn demo_pack_dir() -> PathBuf {
@@ -232,14 +421,27 @@ mod tests {
     fn component_data_contains_mcp_exec_flow_source() {
         let pack_dir = demo_pack_dir();
         let spec = manifest::load_spec(&pack_dir).expect("spec");
-        let flow_assets = flows::load_flows(&pack_dir, &spec.spec).expect("flows");
-        let template_assets =
-            templates::collect_templates(&pack_dir, &spec.spec).expect("templates");
-        let manifest_model = manifest::build_manifest(&spec, &flow_assets, &template_assets);
+        let flow_assets =
+            flows::load_flows(&pack_dir, &spec.spec, DigestAlgorithm::default()).expect("flows");
+        let template_assets = templates::collect_templates(
+            &pack_dir,
+            &spec.spec,
+            DigestAlgorithm::default(),
+            templates::SyntaxCheck::Parse,
+        )
+        .expect("templates");
+        let manifest_model = manifest::build_manifest(
+            &spec,
+            &flow_assets,
+            &template_assets,
+            "2025-01-01T00:00:00Z",
+        );
         let manifest_bytes = manifest::encode_manifest(&manifest_model).expect("manifest encoding");
+        let index = FlowIndex::build(&spec.spec, &flow_assets).expect("flow index");
 
         let generated =
-            generate_component_data(&manifest_bytes, &flow_assets, &template_assets).unwrap();
+            generate_component_data(&manifest_bytes, &index, &flow_assets, &template_assets)
+                .unwrap();
 
         assert!(
             generated.contains("pub static MANIFEST_CBOR"),
@@ -253,6 +455,21 @@ mod tests {
             generated.contains("templates/weather_now.hbs"),
             "template logical path should be present"
         );
+        assert!(
+            generated.contains("        trigger: \"messaging\",\n"),
+            "flow index should record the flow type as its trigger"
+        );
+        assert!(
+            generated.contains("        qos: \"standard\",\n        deadline_ms: None,\n"),
+            "flows without a qos entry should be indexed as standard"
+        );
+        let input: serde_json::Value =
+            serde_json::from_str(&index.flows[0].input_schema).expect("input schema");
+        assert_eq!(input["required"], serde_json::json!(["q_location"]));
+        assert!(
+            generated.contains("        input_schema: \"{"),
+            "flow index should embed the input schema"
+        );
     }
 
     #[test]
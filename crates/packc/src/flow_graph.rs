@@ -0,0 +1,416 @@
+//! Semantic checks on the node graph of a flow.
+//!
+//! The flow parser checks that a flow is well-formed; this looks at the graph
+//! it describes. Each node routes to other nodes with `to:` or ends the flow
+//! with `out: true`, and a node without routes ends the flow too. From that
+//! graph the checks report:
+//!
+//! - `missing-start`: the flow has no entry node, or `start` names a node
+//!   that does not exist;
+//! - `dangling-edge`: a route targets a node that does not exist;
+//! - `unreachable-node`: a node cannot be reached from the start node;
+//! - `inescapable-cycle`: nodes route to each other with no way out of the
+//!   loop. Loops are fine as long as one of their nodes can leave them, e.g.
+//!   a question that is asked again until the answer is valid.
+//!
+//! Lines are found in the source text and assume block-style YAML, like the
+//! editor support in [`crate::lsp`].
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_yaml_bw::Value as YamlValue;
+
+/// A check on the flow graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GraphRule {
+    MissingStart,
+    DanglingEdge,
+    UnreachableNode,
+    InescapableCycle,
+}
+
+impl GraphRule {
+    pub const ALL: [GraphRule; 4] = [
+        GraphRule::MissingStart,
+        GraphRule::DanglingEdge,
+        GraphRule::UnreachableNode,
+        GraphRule::InescapableCycle,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            GraphRule::MissingStart => "missing-start",
+            GraphRule::DanglingEdge => "dangling-edge",
+            GraphRule::UnreachableNode => "unreachable-node",
+            GraphRule::InescapableCycle => "inescapable-cycle",
+        }
+    }
+
+    pub const fn description(self) -> &'static str {
+        match self {
+            GraphRule::MissingStart => "A flow has no start node",
+            GraphRule::DanglingEdge => "A flow routes to a node that does not exist",
+            GraphRule::UnreachableNode => "A flow node cannot be reached from the start node",
+            GraphRule::InescapableCycle => "Flow nodes route to each other with no way out",
+        }
+    }
+}
+
+/// A problem with a flow graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphFinding {
+    pub rule: GraphRule,
+    /// 1-based line in the flow source, when it could be found.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    id: String,
+    /// 0-based line of the node key.
+    line: Option<usize>,
+    routes: Vec<Route>,
+}
+
+#[derive(Debug, Clone)]
+enum Route {
+    To { target: String, line: Option<usize> },
+    Out,
+}
+
+/// The node graph of a flow.
+#[derive(Debug, Clone)]
+pub struct FlowGraph {
+    /// `start` as written in the flow, if any.
+    declared_start: Option<String>,
+    start_line: Option<usize>,
+    nodes: Vec<Node>,
+}
+
+impl FlowGraph {
+    /// Reads the graph from flow source.
+    pub fn parse(text: &str) -> Result<Self> {
+        let source: YamlValue =
+            serde_yaml_bw::from_str(text).context("flow source is not valid YAML")?;
+        let declared_start = source
+            .get("start")
+            .and_then(YamlValue::as_str)
+            .map(str::to_string);
+        let start_line = text.lines().position(|line| line.starts_with("start:"));
+
+        let lines: BTreeMap<String, usize> = node_keys(text).into_iter().collect();
+        let mut nodes = Vec::new();
+        if let Some(mapping) = source.get("nodes").and_then(YamlValue::as_mapping) {
+            for (id, body) in mapping {
+                let id = id.as_str().unwrap_or_default().to_string();
+                let line = lines.get(&id).copied();
+                let routes = body
+                    .get("routing")
+                    .and_then(YamlValue::as_sequence)
+                    .map(|routing| {
+                        routing
+                            .iter()
+                            .filter_map(|route| {
+                                if route.get("out").and_then(YamlValue::as_bool) == Some(true) {
+                                    return Some(Route::Out);
+                                }
+                                let target = route.get("to")?.as_str()?.to_string();
+                                let line = line.and_then(|line| route_line(text, line, &target));
+                                Some(Route::To { target, line })
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                nodes.push(Node { id, line, routes });
+            }
+        }
+        Ok(Self {
+            declared_start,
+            start_line,
+            nodes,
+        })
+    }
+
+    /// Checks the graph, starting from `entry`, the entry node the flow
+    /// parser resolved.
+    pub fn check(&self, entry: &str) -> Vec<GraphFinding> {
+        // A flow without nodes has no graph to get wrong.
+        if self.nodes.is_empty() && self.declared_start.is_none() {
+            return Vec::new();
+        }
+        let ids: BTreeSet<&str> = self.nodes.iter().map(|node| node.id.as_str()).collect();
+        let mut findings = Vec::new();
+
+        let start = match &self.declared_start {
+            Some(start) if !ids.contains(start.as_str()) => {
+                findings.push(GraphFinding {
+                    rule: GraphRule::MissingStart,
+                    line: self.start_line.map(|line| line + 1),
+                    message: format!("start node `{start}` is not defined"),
+                });
+                None
+            }
+            Some(start) => Some(start.as_str()),
+            None if ids.contains(entry) => Some(entry),
+            None => {
+                findings.push(GraphFinding {
+                    rule: GraphRule::MissingStart,
+                    line: None,
+                    message: "flow has no start node; set `start`".to_string(),
+                });
+                None
+            }
+        };
+
+        for node in &self.nodes {
+            for route in &node.routes {
+                if let Route::To { target, line } = route
+                    && !ids.contains(target.as_str())
+                {
+                    findings.push(GraphFinding {
+                        rule: GraphRule::DanglingEdge,
+                        line: line.or(node.line).map(|line| line + 1),
+                        message: format!("node `{}` routes to undefined node `{target}`", node.id),
+                    });
+                }
+            }
+        }
+
+        if let Some(start) = start {
+            let reachable = self.reachable_from(start);
+            for node in &self.nodes {
+                if !reachable.contains(node.id.as_str()) {
+                    findings.push(GraphFinding {
+                        rule: GraphRule::UnreachableNode,
+                        line: node.line.map(|line| line + 1),
+                        message: format!("node `{}` is unreachable from `{start}`", node.id),
+                    });
+                }
+            }
+        }
+
+        for cycle in self.cycles() {
+            if self.can_leave(&cycle) {
+                continue;
+            }
+            let first = self.node(cycle[0]);
+            let mut path: Vec<&str> = cycle.clone();
+            path.push(cycle[0]);
+            findings.push(GraphFinding {
+                rule: GraphRule::InescapableCycle,
+                line: first.and_then(|node| node.line).map(|line| line + 1),
+                message: format!("nodes loop with no way out: {}", path.join(" -> ")),
+            });
+        }
+
+        findings
+    }
+
+    fn node(&self, id: &str) -> Option<&Node> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    fn targets<'a>(&'a self, node: &'a Node) -> impl Iterator<Item = &'a str> {
+        node.routes.iter().filter_map(|route| match route {
+            Route::To { target, .. } if self.node(target).is_some() => Some(target.as_str()),
+            _ => None,
+        })
+    }
+
+    fn reachable_from<'a>(&'a self, start: &'a str) -> BTreeSet<&'a str> {
+        let mut seen = BTreeSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(id) = queue.pop_front() {
+            let Some(node) = self.node(id) else {
+                continue;
+            };
+            for target in self.targets(node) {
+                if seen.insert(target) {
+                    queue.push_back(target);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Groups of nodes that can all reach each other, in declaration order,
+    /// including single nodes that route to themselves.
+    fn cycles(&self) -> Vec<Vec<&str>> {
+        let reach: BTreeMap<&str, BTreeSet<&str>> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let mut reachable: BTreeSet<&str> = BTreeSet::new();
+                for target in self.targets(node) {
+                    reachable.extend(self.reachable_from(target));
+                }
+                (node.id.as_str(), reachable)
+            })
+            .collect();
+
+        let mut assigned = BTreeSet::new();
+        let mut cycles = Vec::new();
+        for node in &self.nodes {
+            let id = node.id.as_str();
+            if assigned.contains(id) || !reach[id].contains(id) {
+                continue;
+            }
+            let cycle: Vec<&str> = self
+                .nodes
+                .iter()
+                .map(|other| other.id.as_str())
+                .filter(|other| reach[id].contains(other) && reach[other].contains(id))
+                .collect();
+            assigned.extend(cycle.iter().copied());
+            cycles.push(cycle);
+        }
+        cycles
+    }
+
+    /// Whether some node of `cycle` ends the flow or routes outside the cycle.
+    fn can_leave(&self, cycle: &[&str]) -> bool {
+        cycle.iter().filter_map(|id| self.node(id)).any(|node| {
+            node.routes.is_empty()
+                || node.routes.iter().any(|route| match route {
+                    Route::Out => true,
+                    Route::To { target, .. } => {
+                        self.node(target).is_some() && !cycle.contains(&target.as_str())
+                    }
+                })
+        })
+    }
+}
+
+/// Node ids of a flow with the 0-based line each is declared on. Read from
+/// the text so that it works while the document does not parse.
+pub(crate) fn node_keys(text: &str) -> Vec<(String, usize)> {
+    let mut nodes = Vec::new();
+    let mut in_nodes = false;
+    let mut child_indent = None;
+    for (number, line) in text.lines().enumerate() {
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - content.len();
+        if indent == 0 {
+            in_nodes = content.trim_end() == "nodes:";
+            continue;
+        }
+        if !in_nodes || *child_indent.get_or_insert(indent) != indent {
+            continue;
+        }
+        if let Some(key) = content.trim_end().strip_suffix(':') {
+            nodes.push((key.to_string(), number));
+        }
+    }
+    nodes
+}
+
+/// The lines after `start` that are indented deeper than it.
+pub(crate) fn block_lines(text: &str, start: usize) -> impl Iterator<Item = (usize, &str)> {
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let base = text.lines().nth(start).map(indent).unwrap_or_default();
+    text.lines()
+        .enumerate()
+        .skip(start + 1)
+        .take_while(move |(_, line)| line.trim().is_empty() || indent(line) > base)
+}
+
+/// The 0-based line of `to: target` in the block of the node on `node_line`.
+pub(crate) fn route_line(text: &str, node_line: usize, target: &str) -> Option<usize> {
+    block_lines(text, node_line)
+        .find(|(_, line)| {
+            let content = line.trim_start().trim_start_matches("- ").trim_start();
+            content
+                .strip_prefix("to:")
+                .is_some_and(|value| value.trim().trim_matches(['"', '\'']) == target)
+        })
+        .map(|(number, _)| number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLOW: &str = "\
+id: support
+type: messaging
+start: ask
+nodes:
+  ask:
+    qa.process:
+      welcome: Hi
+    routing:
+      - to: check
+  check:
+    mcp.exec:
+      component: validator
+    routing:
+      - to: ask
+      - to: reply
+  reply:
+    templating.handlebars:
+      text: done
+    routing:
+      - out: true
+";
+
+    fn rules(findings: &[GraphFinding]) -> Vec<(GraphRule, Option<usize>)> {
+        findings
+            .iter()
+            .map(|finding| (finding.rule, finding.line))
+            .collect()
+    }
+
+    #[test]
+    fn loops_that_can_be_left_are_allowed() {
+        let graph = FlowGraph::parse(FLOW).unwrap();
+        assert_eq!(graph.check("ask"), []);
+    }
+
+    #[test]
+    fn reports_graph_problems_with_their_lines() {
+        let broken = FLOW
+            .replace("start: ask", "start: greet")
+            .replace("      - to: reply\n", "      - to: answer\n");
+        let findings = FlowGraph::parse(&broken).unwrap().check("greet");
+        assert_eq!(
+            rules(&findings),
+            [
+                (GraphRule::MissingStart, Some(3)),
+                (GraphRule::DanglingEdge, Some(15)),
+                (GraphRule::InescapableCycle, Some(5)),
+            ]
+        );
+        assert_eq!(
+            findings[2].message,
+            "nodes loop with no way out: ask -> check -> ask"
+        );
+
+        let orphan = FLOW.replace("      - to: reply\n", "");
+        let findings = FlowGraph::parse(&orphan).unwrap().check("ask");
+        assert_eq!(
+            rules(&findings),
+            [
+                (GraphRule::UnreachableNode, Some(15)),
+                (GraphRule::InescapableCycle, Some(5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_parsed_entry_without_start() {
+        let implicit = FLOW.replace("start: ask\n", "");
+        let graph = FlowGraph::parse(&implicit).unwrap();
+        assert_eq!(graph.check("ask"), []);
+        assert_eq!(rules(&graph.check("")), [(GraphRule::MissingStart, None)]);
+
+        let empty = FlowGraph::parse("id: empty\ntype: messaging\nnodes: []\n").unwrap();
+        assert_eq!(empty.check(""), []);
+    }
+}
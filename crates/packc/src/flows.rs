@@ -1,7 +1,11 @@
+use crate::digest::DigestAlgorithm;
 use crate::manifest::PackSpec;
 use anyhow::{Context, Result};
 use greentic_flow::flow_bundle::{FlowBundle, load_and_validate_bundle_with_ir};
-use sha2::{Digest, Sha256};
+use greentic_pack::capabilities::flow_requirements;
+use greentic_pack::fallbacks::{check_fallbacks, node_fallbacks};
+use greentic_pack::flow_schema::flow_schemas;
+use greentic_pack::node_docs::{NodeDocs, node_docs, strip_node_docs};
 use std::collections::BTreeSet;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -9,15 +13,22 @@ use std::path::{Path, PathBuf};
 #[derive(Debug, Clone)]
 pub struct FlowAsset {
     pub bundle: FlowBundle,
-    #[allow(dead_code)]
     pub relative_path: PathBuf,
     pub raw: String,
-    pub sha256: String,
+    /// `<alg>:<hex>` digest of `raw`.
+    pub digest: String,
+    /// `doc:` strings by node id. The bundle is parsed from the source
+    /// without them.
+    pub node_docs: NodeDocs,
 }
 
 const FLOW_SCHEMA_JSON: &str = include_str!("../schemas/ygtc.flow.schema.json");
 
-pub fn load_flows(pack_dir: &Path, spec: &PackSpec) -> Result<Vec<FlowAsset>> {
+pub fn load_flows(
+    pack_dir: &Path,
+    spec: &PackSpec,
+    digest_alg: DigestAlgorithm,
+) -> Result<Vec<FlowAsset>> {
     let mut flows = Vec::new();
     let mut seen_ids = BTreeSet::new();
     ensure_flow_schema(pack_dir)?;
@@ -30,8 +41,19 @@ pub fn load_flows(pack_dir: &Path, spec: &PackSpec) -> Result<Vec<FlowAsset>> {
             .with_context(|| format!("failed to read flow {}", absolute_path.display()))?;
 
         let flow_id = derive_flow_id(&relative_path);
-        let (bundle, _ir) = load_and_validate_bundle_with_ir(&raw, Some(&absolute_path))
-            .with_context(|| format!("failed to parse flow {}", relative_path.display()))?;
+        let parse_error = || format!("failed to parse flow {}", relative_path.display());
+        let node_docs = node_docs(&raw).with_context(parse_error)?;
+        let stripped = strip_node_docs(&raw).with_context(parse_error)?;
+        let fallbacks = node_fallbacks(&raw).with_context(parse_error)?;
+        let requirements =
+            flow_requirements(&raw, &spec.imports_required).with_context(parse_error)?;
+        check_fallbacks(&requirements, &fallbacks, &spec.imports_required)
+            .with_context(|| format!("flow {}", relative_path.display()))?;
+        let (bundle, _ir) = load_and_validate_bundle_with_ir(
+            stripped.as_deref().unwrap_or(&raw),
+            Some(&absolute_path),
+        )
+        .with_context(parse_error)?;
         let mut bundle = bundle;
         let flow_identifier = if bundle.id.trim().is_empty() {
             flow_id.clone()
@@ -42,18 +64,53 @@ pub fn load_flows(pack_dir: &Path, spec: &PackSpec) -> Result<Vec<FlowAsset>> {
             anyhow::bail!("duplicate flow id detected: {}", flow_identifier);
         }
         bundle.id = flow_identifier.clone();
-        let digest = Sha256::digest(raw.as_bytes());
-        let sha256 = hex::encode(digest);
+        let digest = digest_alg.digest(raw.as_bytes());
 
         flows.push(FlowAsset {
             bundle,
             relative_path,
             raw,
-            sha256,
+            digest,
+            node_docs,
         });
     }
 
     flows.sort_by(|a, b| a.bundle.id.cmp(&b.bundle.id));
+    if let Some(lifecycle) = &spec.lifecycle {
+        lifecycle.check_flows(flows.iter().map(|flow| flow.bundle.id.as_str()))?;
+    }
+    if let Some(quotas) = &spec.quotas {
+        let imports: Vec<String> = spec
+            .imports_required
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        quotas.check_references(
+            flows.iter().map(|flow| flow.bundle.id.as_str()),
+            imports.iter().map(String::as_str),
+        )?;
+    }
+    if let Some(limits) = &spec.limits {
+        limits.check_flows(flows.iter().map(|flow| flow.bundle.id.as_str()))?;
+    }
+    if let Some(qos) = &spec.qos {
+        qos.check_flows(flows.iter().map(|flow| flow.bundle.id.as_str()))?;
+    }
+    if let Some(dispatch) = &spec.dispatch {
+        dispatch.check_flows(flows.iter().map(|flow| flow.bundle.id.as_str()))?;
+        for flow in &flows {
+            let schemas = flow_schemas(&flow.raw).with_context(|| {
+                format!("failed to parse flow {}", flow.relative_path.display())
+            })?;
+            dispatch.check_keys(&flow.bundle.id, &schemas.input)?;
+        }
+    }
+    if let Some(schedule) = &spec.schedule {
+        schedule.check_flows(flows.iter().map(|flow| flow.bundle.id.as_str()))?;
+    }
+    if let Some(classification) = &spec.classification {
+        classification.check_flows(flows.iter().map(|flow| flow.bundle.id.as_str()))?;
+    }
     Ok(flows)
 }
 
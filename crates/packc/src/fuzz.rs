@@ -0,0 +1,293 @@
+//! Runs the workspace's cargo-fuzz targets before a release.
+//!
+//! The harness lives in `fuzz/` at the workspace root: one libFuzzer target
+//! per untrusted-input surface, each with a checked-in seed corpus under
+//! `fuzz/corpus/<target>/`. `packc fuzz` runs targets for a fixed time with
+//! `cargo +nightly fuzz run`, keeping the inputs libFuzzer discovers in a
+//! scratch corpus under `fuzz/target/` so the checked-in seeds only change
+//! deliberately. Built `.gtpack` archives can be added as extra seeds.
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use zip::ZipArchive;
+
+/// Directory of the fuzz crate, relative to the workspace root.
+pub const FUZZ_DIR: &str = "fuzz";
+/// Toolchain `cargo fuzz` runs with; libFuzzer instrumentation needs nightly.
+pub const DEFAULT_TOOLCHAIN: &str = "nightly";
+
+/// Fuzz targets of the `fuzz/` crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FuzzTarget {
+    /// `greentic_pack_export__*` C ABI shims of the pack component
+    #[value(name = "component_abi")]
+    ComponentAbi,
+    /// `manifest.cbor` decoding
+    #[value(name = "manifest_cbor")]
+    ManifestCbor,
+    /// `.gtpack` archive reading and verification
+    #[value(name = "gtpack_archive")]
+    GtpackArchive,
+}
+
+impl FuzzTarget {
+    pub const ALL: [FuzzTarget; 3] = [
+        FuzzTarget::ComponentAbi,
+        FuzzTarget::ManifestCbor,
+        FuzzTarget::GtpackArchive,
+    ];
+
+    /// Binary name of the target in `fuzz/Cargo.toml`.
+    pub fn name(self) -> &'static str {
+        match self {
+            FuzzTarget::ComponentAbi => "component_abi",
+            FuzzTarget::ManifestCbor => "manifest_cbor",
+            FuzzTarget::GtpackArchive => "gtpack_archive",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            FuzzTarget::ComponentAbi => "greentic_pack_export__* C ABI shims of pack_component",
+            FuzzTarget::ManifestCbor => "manifest.cbor decoding",
+            FuzzTarget::GtpackArchive => ".gtpack archive reading and verification",
+        }
+    }
+}
+
+/// How `packc fuzz` runs each target.
+#[derive(Debug, Clone)]
+pub struct FuzzOptions {
+    /// The fuzz crate directory.
+    pub fuzz_dir: PathBuf,
+    /// Seconds each target runs for.
+    pub duration_secs: u64,
+    /// Parallel fuzzing processes per target.
+    pub jobs: u32,
+    pub toolchain: String,
+}
+
+/// Result of running one target.
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzRun {
+    pub target: FuzzTarget,
+    pub passed: bool,
+    /// Crashing or hanging inputs libFuzzer saved during the run.
+    pub artifacts: Vec<PathBuf>,
+}
+
+/// The fuzz crate in `start` or its nearest ancestor that has one.
+pub fn locate_fuzz_dir(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(FUZZ_DIR))
+        .find(|dir| dir.join("Cargo.toml").is_file())
+}
+
+/// Checked-in seeds of `target`.
+pub fn seed_corpus(fuzz_dir: &Path, target: FuzzTarget) -> PathBuf {
+    fuzz_dir.join("corpus").join(target.name())
+}
+
+/// Scratch corpus libFuzzer adds the inputs it discovers to.
+pub fn work_corpus(fuzz_dir: &Path, target: FuzzTarget) -> PathBuf {
+    fuzz_dir.join("target").join("corpus").join(target.name())
+}
+
+/// Where libFuzzer saves crashing inputs of `target`.
+pub fn artifact_dir(fuzz_dir: &Path, target: FuzzTarget) -> PathBuf {
+    fuzz_dir.join("artifacts").join(target.name())
+}
+
+/// The `cargo fuzz run` invocation for `target`.
+pub fn run_command(opts: &FuzzOptions, target: FuzzTarget) -> Command {
+    let mut command = Command::new("cargo");
+    command
+        .current_dir(&opts.fuzz_dir)
+        .arg(format!("+{}", opts.toolchain))
+        .args(["fuzz", "run", target.name()])
+        .arg(work_corpus(&opts.fuzz_dir, target))
+        .arg(seed_corpus(&opts.fuzz_dir, target))
+        .arg("--")
+        .arg(format!("-max_total_time={}", opts.duration_secs));
+    if opts.jobs > 1 {
+        command
+            .arg(format!("-jobs={}", opts.jobs))
+            .arg(format!("-workers={}", opts.jobs));
+    }
+    command
+}
+
+/// Fails with install instructions when `cargo fuzz` cannot run.
+pub fn ensure_cargo_fuzz(opts: &FuzzOptions) -> Result<()> {
+    let output = Command::new("cargo")
+        .current_dir(&opts.fuzz_dir)
+        .arg(format!("+{}", opts.toolchain))
+        .args(["fuzz", "--version"])
+        .output()
+        .context("failed to invoke cargo")?;
+    if !output.status.success() {
+        bail!(
+            "`cargo +{toolchain} fuzz` is unavailable; install it with `rustup toolchain install {toolchain}` and `cargo install cargo-fuzz`",
+            toolchain = opts.toolchain
+        );
+    }
+    Ok(())
+}
+
+/// Runs `target` for the configured time. A failing run is reported in the
+/// result rather than as an error, so the remaining targets still run.
+pub fn run_target(opts: &FuzzOptions, target: FuzzTarget) -> Result<FuzzRun> {
+    fs::create_dir_all(work_corpus(&opts.fuzz_dir, target))
+        .context("failed to create the scratch corpus")?;
+    let started = SystemTime::now();
+    let status = run_command(opts, target)
+        .status()
+        .with_context(|| format!("failed to run fuzz target {}", target.name()))?;
+    Ok(FuzzRun {
+        target,
+        passed: status.success(),
+        artifacts: artifacts_since(&artifact_dir(&opts.fuzz_dir, target), started)?,
+    })
+}
+
+fn artifacts_since(dir: &Path, since: SystemTime) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut artifacts = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let entry = entry?;
+        let modified = entry.metadata()?.modified()?;
+        if modified >= since {
+            artifacts.push(entry.path());
+        }
+    }
+    artifacts.sort();
+    Ok(artifacts)
+}
+
+/// Adds `archive` to the scratch corpora: the whole archive for
+/// `gtpack_archive` and its `manifest.cbor` for `manifest_cbor`. Seeds are
+/// named by content digest, as libFuzzer names its own. Returns the seeds
+/// written.
+pub fn add_archive_seed(fuzz_dir: &Path, archive: &Path) -> Result<Vec<PathBuf>> {
+    let bytes =
+        fs::read(archive).with_context(|| format!("failed to read {}", archive.display()))?;
+    let mut seeds = vec![write_seed(fuzz_dir, FuzzTarget::GtpackArchive, &bytes)?];
+
+    let mut zip = ZipArchive::new(std::io::Cursor::new(&bytes))
+        .with_context(|| format!("{} is not a .gtpack archive", archive.display()))?;
+    if let Ok(mut manifest) = zip.by_name("manifest.cbor") {
+        let mut manifest_bytes = Vec::new();
+        manifest
+            .read_to_end(&mut manifest_bytes)
+            .with_context(|| format!("failed to read manifest.cbor of {}", archive.display()))?;
+        seeds.push(write_seed(
+            fuzz_dir,
+            FuzzTarget::ManifestCbor,
+            &manifest_bytes,
+        )?);
+    }
+    Ok(seeds)
+}
+
+fn write_seed(fuzz_dir: &Path, target: FuzzTarget, bytes: &[u8]) -> Result<PathBuf> {
+    let dir = work_corpus(fuzz_dir, target);
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = dir.join(hex::encode(Sha256::digest(bytes)));
+    fs::write(&path, bytes).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    fn options(fuzz_dir: &Path, jobs: u32) -> FuzzOptions {
+        FuzzOptions {
+            fuzz_dir: fuzz_dir.to_path_buf(),
+            duration_secs: 30,
+            jobs,
+            toolchain: DEFAULT_TOOLCHAIN.to_string(),
+        }
+    }
+
+    #[test]
+    fn workspace_fuzz_crate_declares_every_target_with_seeds() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let fuzz_dir = locate_fuzz_dir(root).expect("workspace fuzz crate");
+        let manifest = fs::read_to_string(fuzz_dir.join("Cargo.toml")).unwrap();
+        for target in FuzzTarget::ALL {
+            assert!(
+                manifest.contains(&format!("name = \"{}\"", target.name())),
+                "{} missing from fuzz/Cargo.toml",
+                target.name()
+            );
+            let seeds = fs::read_dir(seed_corpus(&fuzz_dir, target)).unwrap();
+            assert!(seeds.count() > 0, "{} has no seeds", target.name());
+        }
+    }
+
+    #[test]
+    fn run_command_keeps_discoveries_out_of_the_seed_corpus() {
+        let fuzz_dir = Path::new("/work/fuzz");
+        let command = run_command(&options(fuzz_dir, 4), FuzzTarget::GtpackArchive);
+        let args: Vec<_> = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            args,
+            [
+                "+nightly",
+                "fuzz",
+                "run",
+                "gtpack_archive",
+                "/work/fuzz/target/corpus/gtpack_archive",
+                "/work/fuzz/corpus/gtpack_archive",
+                "--",
+                "-max_total_time=30",
+                "-jobs=4",
+                "-workers=4",
+            ]
+        );
+
+        let single = run_command(&options(fuzz_dir, 1), FuzzTarget::ComponentAbi);
+        assert!(
+            single
+                .get_args()
+                .all(|arg| !arg.to_string_lossy().starts_with("-jobs"))
+        );
+    }
+
+    #[test]
+    fn archive_seeds_feed_archive_and_manifest_targets() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("demo.gtpack");
+        let mut zip = ZipWriter::new(fs::File::create(&archive).unwrap());
+        zip.start_file("manifest.cbor", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"\xa0").unwrap();
+        zip.finish().unwrap();
+
+        let seeds = add_archive_seed(dir.path(), &archive).unwrap();
+        assert_eq!(seeds.len(), 2);
+        assert!(seeds[0].starts_with(work_corpus(dir.path(), FuzzTarget::GtpackArchive)));
+        assert!(seeds[1].starts_with(work_corpus(dir.path(), FuzzTarget::ManifestCbor)));
+        assert_eq!(fs::read(&seeds[1]).unwrap(), b"\xa0");
+    }
+}
@@ -0,0 +1,246 @@
+//! Hermetic compilation of the pack component inside a container.
+//!
+//! `packc build --hermetic` runs the cargo step in a pinned builder image
+//! instead of on the host toolchain. The container sees only two mounts: the
+//! pack directory, which holds the generated `pack_component` crate, and a
+//! cache directory for the cargo registry and target directory. The image is
+//! resolved to its registry digest before the build, and that digest is what
+//! the pack's provenance records, so the same inputs can be rebuilt in the
+//! same image later.
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use greentic_pack::atomic::{self, Durability};
+use tracing::info;
+
+use crate::build::SOURCE_DATE_EPOCH;
+use crate::cache::{self, CacheKind};
+use crate::{embed, tools};
+
+/// Image used when neither `--builder-image` nor `$PACKC_BUILDER_IMAGE` is set.
+pub const DEFAULT_BUILDER_IMAGE: &str = "docker.io/library/rust:1.89-slim";
+/// Overrides the default builder image.
+pub const BUILDER_IMAGE_ENV: &str = "PACKC_BUILDER_IMAGE";
+/// Container CLI to run, e.g. `podman` (defaults to `docker`).
+pub const CONTAINER_ENGINE_ENV: &str = "PACKC_CONTAINER_ENGINE";
+
+const PACK_MOUNT: &str = "/pack";
+const CACHE_MOUNT: &str = "/cache";
+const BUILD_SCRIPT: &str =
+    "rustup target add wasm32-wasip2 && cargo build --target wasm32-wasip2 --release";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HermeticOptions {
+    /// Image reference, with or without a digest.
+    pub image: String,
+    /// Host directory mounted as the cargo home and target directory.
+    pub cache_dir: PathBuf,
+    pub engine: String,
+}
+
+impl HermeticOptions {
+    /// Fills in the image from `$PACKC_BUILDER_IMAGE` or the default, and the
+    /// cache from `~/.cache/greentic/hermetic` or `<pack>/.packc/cache`.
+    pub fn resolve(image: Option<String>, cache_dir: Option<PathBuf>, pack_dir: &Path) -> Self {
+        let image = image
+            .or_else(|| std::env::var(BUILDER_IMAGE_ENV).ok())
+            .unwrap_or_else(|| DEFAULT_BUILDER_IMAGE.to_string());
+        let cache_dir = cache_dir
+            .or_else(|| tools::cache_root().map(|root| root.join("hermetic")))
+            .unwrap_or_else(|| pack_dir.join(".packc").join("cache"));
+        let engine = std::env::var(CONTAINER_ENGINE_ENV).unwrap_or_else(|_| "docker".to_string());
+        Self {
+            image,
+            cache_dir,
+            engine,
+        }
+    }
+}
+
+/// Compiles the component crate next to `component_data` in the builder
+/// image and copies the result to `output_wasm`. Returns the image as
+/// `<repository>@sha256:<digest>`. A pinned build time is passed into the
+/// container as `SOURCE_DATE_EPOCH`.
+pub fn compile_component(
+    pack_dir: &Path,
+    component_data: &Path,
+    output_wasm: &Path,
+    opts: &HermeticOptions,
+    no_wasi: bool,
+    source_date_epoch: Option<i64>,
+    durability: Durability,
+) -> Result<String> {
+    let crate_root = embed::prepare_component_crate(component_data, no_wasi)?;
+    let pack_dir = fs::canonicalize(pack_dir)
+        .with_context(|| format!("failed to resolve {}", pack_dir.display()))?;
+    let crate_root = fs::canonicalize(&crate_root)
+        .with_context(|| format!("failed to resolve {}", crate_root.display()))?;
+    let Ok(crate_rel) = crate_root.strip_prefix(&pack_dir) else {
+        bail!(
+            "hermetic builds only mount the pack directory, but the component crate is at {}; \
+             keep --component-data under {}",
+            crate_root.display(),
+            pack_dir.display()
+        );
+    };
+    fs::create_dir_all(&opts.cache_dir)
+        .with_context(|| format!("failed to create {}", opts.cache_dir.display()))?;
+    let cache_dir = fs::canonicalize(&opts.cache_dir)
+        .with_context(|| format!("failed to resolve {}", opts.cache_dir.display()))?;
+
+    let image = pinned_image(opts)?;
+    info!(image = %image, engine = %opts.engine, "compiling pack_component in builder image");
+
+    let source_date_epoch = source_date_epoch.map(|seconds| seconds.to_string());
+    let args = run_args(
+        &pack_dir,
+        crate_rel,
+        &cache_dir,
+        &image,
+        source_date_epoch.as_deref(),
+    );
+    let status = Command::new(&opts.engine)
+        .args(&args)
+        .status()
+        .with_context(|| format!("failed to invoke `{} run`", opts.engine))?;
+    if !status.success() {
+        bail!("hermetic build in {image} failed with status {status}");
+    }
+
+    let artifact = cache_dir
+        .join("target")
+        .join("wasm32-wasip2")
+        .join("release")
+        .join("pack_component.wasm");
+    if !artifact.exists() {
+        bail!(
+            "expected wasm artifact at {} but the builder image did not produce it",
+            artifact.display()
+        );
+    }
+    atomic::copy(&artifact, output_wasm, durability)?;
+    cache::enforce_configured(CacheKind::Builds, &cache_dir)?;
+    Ok(image)
+}
+
+/// The registry digest of the configured image, pulling it when it is not
+/// available locally.
+fn pinned_image(opts: &HermeticOptions) -> Result<String> {
+    if let Some(image) = repo_digest(opts)? {
+        return Ok(image);
+    }
+    let status = Command::new(&opts.engine)
+        .args(["pull", &opts.image])
+        .status()
+        .with_context(|| format!("failed to invoke `{} pull`", opts.engine))?;
+    if !status.success() {
+        bail!("failed to pull builder image {}", opts.image);
+    }
+    repo_digest(opts)?.with_context(|| {
+        format!(
+            "builder image {} has no registry digest; use an image pulled from a registry",
+            opts.image
+        )
+    })
+}
+
+fn repo_digest(opts: &HermeticOptions) -> Result<Option<String>> {
+    let output = Command::new(&opts.engine)
+        .args(["image", "inspect", "--format", "{{json .RepoDigests}}"])
+        .arg(&opts.image)
+        .output()
+        .with_context(|| {
+            format!(
+                "failed to invoke `{}`; install it or set {CONTAINER_ENGINE_ENV}",
+                opts.engine
+            )
+        })?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let digests: Vec<String> = serde_json::from_slice(&output.stdout).with_context(|| {
+        format!(
+            "unexpected `{} image inspect` output for {}",
+            opts.engine, opts.image
+        )
+    })?;
+    Ok(digests.into_iter().next())
+}
+
+fn run_args(
+    pack_dir: &Path,
+    crate_rel: &Path,
+    cache_dir: &Path,
+    image: &str,
+    source_date_epoch: Option<&str>,
+) -> Vec<OsString> {
+    let mount = |host: &Path, guest: &str| {
+        let mut spec = OsString::from(host);
+        spec.push(format!(":{guest}"));
+        spec
+    };
+    let workdir = Path::new(PACK_MOUNT).join(crate_rel);
+    let mut args: Vec<OsString> = vec!["run".into(), "--rm".into()];
+    args.extend(["--volume".into(), mount(pack_dir, PACK_MOUNT)]);
+    args.extend(["--volume".into(), mount(cache_dir, CACHE_MOUNT)]);
+    args.extend(["--workdir".into(), workdir.into_os_string()]);
+    args.extend([
+        "--env".into(),
+        format!("CARGO_HOME={CACHE_MOUNT}/cargo").into(),
+    ]);
+    args.extend([
+        "--env".into(),
+        format!("CARGO_TARGET_DIR={CACHE_MOUNT}/target").into(),
+    ]);
+    if let Some(epoch) = source_date_epoch {
+        args.extend([
+            "--env".into(),
+            format!("{SOURCE_DATE_EPOCH}={epoch}").into(),
+        ]);
+    }
+    args.extend([image.into(), "sh".into(), "-c".into(), BUILD_SCRIPT.into()]);
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn container_sees_only_the_pack_and_cache() {
+        let args = run_args(
+            Path::new("/home/dev/weather"),
+            Path::new(".packc/pack_component"),
+            Path::new("/home/dev/.cache/greentic/hermetic"),
+            "docker.io/library/rust@sha256:abc",
+            Some("1700000000"),
+        );
+        let args: Vec<String> = args
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        let volumes: Vec<&str> = args
+            .windows(2)
+            .filter(|pair| pair[0] == "--volume")
+            .map(|pair| pair[1].as_str())
+            .collect();
+        assert_eq!(
+            volumes,
+            [
+                "/home/dev/weather:/pack",
+                "/home/dev/.cache/greentic/hermetic:/cache"
+            ]
+        );
+        assert!(
+            args.windows(2)
+                .any(|pair| pair[0] == "--workdir" && pair[1] == "/pack/.packc/pack_component")
+        );
+        assert!(args.contains(&"SOURCE_DATE_EPOCH=1700000000".to_string()));
+        let image = args.iter().position(|arg| arg.contains("sha256")).unwrap();
+        assert_eq!(args[image + 1..], ["sh", "-c", BUILD_SCRIPT]);
+    }
+}
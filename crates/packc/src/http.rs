@@ -0,0 +1,232 @@
+//! HTTP requests through `curl`, for the registry index client, remote
+//! builds, Sigstore signing and tool downloads.
+//!
+//! packc has no HTTP stack of its own; `curl` brings the system's proxy
+//! settings and CA store along. Requests go to `https://` and `http://` URLs,
+//! and redirects may only lead to those, but a `file://` URL (e.g. an index
+//! on disk) is read as a file. Headers reach curl through a file rather than
+//! its command line, so tokens never appear in the process list. Every
+//! request is bounded by a total timeout.
+
+use std::fs;
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use greentic_pack::registry::Transport;
+use thiserror::Error;
+use tracing::debug;
+
+/// Bearer token sent to the registry, if set.
+pub const REGISTRY_TOKEN_ENV: &str = "PACKC_REGISTRY_TOKEN";
+/// Total timeout of every request in seconds, overriding each request's own.
+pub const TIMEOUT_ENV: &str = "PACKC_HTTP_TIMEOUT";
+/// Total timeout of a request that sets none.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// curl exit codes meaning the server was never reached: proxy or host not
+/// resolved, connection refused, timed out.
+const UNREACHABLE_EXIT_CODES: &[i32] = &[5, 6, 7, 28];
+
+/// A GET, or a POST when it has a body, sent with [`Request::send`].
+#[derive(Debug, Clone)]
+pub struct Request<'a> {
+    url: &'a str,
+    headers: Vec<String>,
+    body: Option<&'a [u8]>,
+    retries: u32,
+    timeout: Duration,
+}
+
+impl<'a> Request<'a> {
+    pub fn get(url: &'a str) -> Self {
+        Self {
+            url,
+            headers: Vec::new(),
+            body: None,
+            retries: 0,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// POSTs `body` as JSON.
+    pub fn post_json(url: &'a str, body: &'a [u8]) -> Self {
+        Self {
+            body: Some(body),
+            ..Self::get(url)
+        }
+        .header("Content-Type: application/json")
+    }
+
+    /// Adds a header written as `Name: value`.
+    pub fn header(mut self, header: impl Into<String>) -> Self {
+        self.headers.push(header.into());
+        self
+    }
+
+    pub fn accept_json(self) -> Self {
+        self.header("Accept: application/json")
+    }
+
+    pub fn bearer(self, token: &str) -> Self {
+        self.header(format!("Authorization: Bearer {token}"))
+    }
+
+    /// Retries transient failures up to `retries` times.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Gives up on the request, retries included, after `timeout`, unless
+    /// [`TIMEOUT_ENV`] sets another.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn method(&self) -> &'static str {
+        if self.body.is_some() { "POST" } else { "GET" }
+    }
+
+    /// Sends the request and returns the response body. A request curl
+    /// cannot complete fails with [`RequestFailed`].
+    pub fn send(self) -> Result<Vec<u8>> {
+        let staging = tempfile::tempdir().context("failed to create request directory")?;
+        let timeout = match std::env::var(TIMEOUT_ENV) {
+            Ok(seconds) => seconds
+                .trim()
+                .parse()
+                .map(Duration::from_secs)
+                .with_context(|| format!("{TIMEOUT_ENV} must be a number of seconds"))?,
+            Err(_) => self.timeout,
+        };
+        let protocols = if self.url.starts_with("file://") {
+            "=file"
+        } else {
+            "=https,http"
+        };
+        let mut command = Command::new("curl");
+        command
+            .args(["--fail", "--silent", "--show-error", "--location"])
+            .args(["--proto", protocols, "--proto-redir", "=https,http"])
+            .args(["--connect-timeout", "10", "--max-time"])
+            .arg(timeout.as_secs().max(1).to_string());
+        if self.retries > 0 {
+            command.arg("--retry").arg(self.retries.to_string());
+        }
+        if !self.headers.is_empty() {
+            let headers = staging.path().join("headers");
+            let lines: String = self
+                .headers
+                .iter()
+                .map(|header| format!("{header}\n"))
+                .collect();
+            fs::write(&headers, lines).context("failed to write request headers")?;
+            command
+                .arg("--header")
+                .arg(format!("@{}", headers.display()));
+        }
+        if let Some(body) = self.body {
+            let request = staging.path().join("body");
+            fs::write(&request, body).context("failed to write request body")?;
+            command
+                .args(["--request", "POST", "--data-binary"])
+                .arg(format!("@{}", request.display()));
+        }
+        let method = self.method();
+        debug!(%method, url = %self.url, "sending request");
+        let output = command
+            .arg("--")
+            .arg(self.url)
+            .output()
+            .context("failed to invoke `curl`")?;
+        if !output.status.success() {
+            return Err(RequestFailed {
+                method,
+                url: self.url.to_string(),
+                exit_code: output.status.code(),
+                message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            }
+            .into());
+        }
+        Ok(output.stdout)
+    }
+}
+
+/// A request curl could not complete.
+#[derive(Debug, Error)]
+#[error("{method} {url} failed: {message}")]
+pub struct RequestFailed {
+    pub method: &'static str,
+    pub url: String,
+    /// curl's exit code; `None` when it was killed by a signal.
+    pub exit_code: Option<i32>,
+    /// What curl printed to stderr.
+    pub message: String,
+}
+
+impl RequestFailed {
+    /// Whether the server was never reached, rather than answering with an
+    /// error.
+    pub fn unreachable(&self) -> bool {
+        self.exit_code
+            .is_some_and(|code| UNREACHABLE_EXIT_CODES.contains(&code))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CurlTransport;
+
+impl Transport for CurlTransport {
+    fn get(&self, url: &str) -> Result<Vec<u8>> {
+        let mut request = Request::get(url);
+        if let Ok(token) = std::env::var(REGISTRY_TOKEN_ENV) {
+            request = request.bearer(&token);
+        }
+        request.send()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_requests_report_whether_the_server_was_reached() {
+        let failed = |exit_code| RequestFailed {
+            method: "GET",
+            url: "https://registry.example/index.json".into(),
+            exit_code,
+            message: "curl: (7) Failed to connect".into(),
+        };
+        assert!(failed(Some(7)).unreachable());
+        assert!(!failed(Some(22)).unreachable());
+        assert!(!failed(None).unreachable());
+        assert_eq!(
+            failed(Some(7)).to_string(),
+            "GET https://registry.example/index.json failed: curl: (7) Failed to connect"
+        );
+    }
+
+    #[test]
+    fn only_http_and_local_files_are_fetched() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("index.json");
+        fs::write(&path, b"{}").unwrap();
+        let url = format!("file://{}", path.display());
+        assert_eq!(Request::get(&url).send().unwrap(), b"{}");
+
+        for url in [
+            format!("ftp://127.0.0.1{}", path.display()),
+            "dict://127.0.0.1/".to_string(),
+        ] {
+            let err = Request::get(&url).send().unwrap_err();
+            let failed = err.downcast_ref::<RequestFailed>().expect("curl ran");
+            assert!(failed.message.contains("not supported"), "{url}: {failed}");
+        }
+        // Read as a URL, not as an option.
+        let err = Request::get("--version").send().unwrap_err();
+        assert!(err.downcast_ref::<RequestFailed>().is_some(), "{err:#}");
+    }
+}
@@ -0,0 +1,252 @@
+//! Resolution of `imports_required` entries to WIT interfaces.
+//!
+//! Entries written as `namespace:package/interface[@version]` (for example
+//! `greentic:host-import/secrets@0.6`) are checked against an
+//! [`InterfaceCatalog`] and pinned to a concrete package version.
+//! The version is a semver requirement (`0.6` means `^0.6`); without one the
+//! newest known version is used, unless a [`PackLock`] pins the package to a
+//! version that still satisfies every entry. Older dotted capability names
+//! such as `secrets.get` are passed through unresolved.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anyhow::{Result, anyhow, bail};
+use greentic_pack::import_spec::ImportSpec;
+pub(crate) use greentic_pack::import_spec::valid_name;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+pub mod catalog;
+pub mod deterministic;
+pub mod host;
+pub mod mocks;
+
+pub use catalog::{CatalogPackage, InterfaceCatalog};
+
+use crate::lockfile::PackLock;
+
+/// An `imports_required` entry pinned to the interface version hosts must
+/// provide.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedImport {
+    /// The entry as written in `pack.yaml`.
+    pub import: String,
+    /// WIT package, e.g. `greentic:host-import`.
+    pub package: String,
+    pub interface: String,
+    pub version: String,
+}
+
+impl fmt::Display for ResolvedImport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}@{}", self.package, self.interface, self.version)
+    }
+}
+
+/// Returns `true` for entries written as WIT interface references rather
+/// than legacy dotted capability names.
+pub fn is_wit_reference(import: &str) -> bool {
+    import.contains(':')
+}
+
+/// Resolves every WIT-style entry of `imports`, skipping legacy names.
+///
+/// Entries naming the same package must agree on one version, since a host
+/// provides a single version of each package; the newest version satisfying
+/// all of them is chosen.
+pub fn resolve_all(
+    catalog: &InterfaceCatalog,
+    imports: &[ImportSpec],
+) -> Result<Vec<ResolvedImport>> {
+    resolve_locked(catalog, imports, None)
+}
+
+/// Like [`resolve_all`], but keeps the version `lock` pins a package to while
+/// the catalog still knows it and it satisfies every entry of the package.
+pub fn resolve_locked(
+    catalog: &InterfaceCatalog,
+    imports: &[ImportSpec],
+    lock: Option<&PackLock>,
+) -> Result<Vec<ResolvedImport>> {
+    let pinned = lock.map(PackLock::packages).unwrap_or_default();
+    let mut by_package: BTreeMap<&str, Vec<&ImportSpec>> = BTreeMap::new();
+    for import in imports {
+        if let Some(package) = &import.package {
+            by_package.entry(package).or_default().push(import);
+        }
+    }
+
+    let mut resolved = Vec::new();
+    for (package, refs) in by_package {
+        let version = pick_version(catalog, package, &refs, pinned.get(package))?;
+        resolved.extend(refs.into_iter().map(|r| ResolvedImport {
+            import: r.to_string(),
+            package: package.to_string(),
+            interface: r.interface.clone(),
+            version: version.to_string(),
+        }));
+    }
+
+    // Keep `pack.yaml` order so the manifest reads like the spec.
+    resolved.sort_by_key(|entry| {
+        imports
+            .iter()
+            .position(|import| import.to_string() == entry.import)
+    });
+    Ok(resolved)
+}
+
+fn pick_version(
+    catalog: &InterfaceCatalog,
+    package: &str,
+    refs: &[&ImportSpec],
+    pinned: Option<&Version>,
+) -> Result<Version> {
+    // Catalog versions are validated when the catalog is loaded.
+    let mut known: Vec<(Version, &CatalogPackage)> = catalog
+        .packages
+        .iter()
+        .filter(|known| known.name == package)
+        .filter_map(|known| Some((Version::parse(&known.version).ok()?, known)))
+        .collect();
+    if known.is_empty() {
+        bail!(
+            "import `{}` refers to unknown WIT package `{package}`",
+            refs[0]
+        );
+    }
+    known.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // Report the first entry that no known version can satisfy on its own.
+    for r in refs {
+        if !known
+            .iter()
+            .any(|(version, known)| satisfies(r, version, known))
+        {
+            let offering: Vec<&str> = known
+                .iter()
+                .filter(|(_, known)| known.interfaces.contains(&r.interface))
+                .map(|(_, known)| known.version.as_str())
+                .collect();
+            if offering.is_empty() {
+                bail!(
+                    "import `{r}`: package `{package}` has no interface `{}`",
+                    r.interface
+                );
+            }
+            bail!(
+                "import `{r}` is incompatible with the known versions of `{package}` providing `{}`: {}",
+                r.interface,
+                offering.join(", ")
+            );
+        }
+    }
+
+    let usable = |(version, known): &&(Version, &CatalogPackage)| {
+        refs.iter().all(|r| satisfies(r, version, known))
+    };
+    if let Some(locked) = known
+        .iter()
+        .filter(|(version, _)| Some(version) == pinned)
+        .find(usable)
+    {
+        return Ok(locked.0.clone());
+    }
+    known
+        .iter()
+        .rev()
+        .find(usable)
+        .map(|(version, _)| version.clone())
+        .ok_or_else(|| {
+            let entries: Vec<String> = refs.iter().map(ToString::to_string).collect();
+            anyhow!(
+                "imports {} need different versions of `{package}`; a host provides only one",
+                entries.join(", ")
+            )
+        })
+}
+
+fn satisfies(import: &ImportSpec, version: &Version, known: &CatalogPackage) -> bool {
+    // Requirements are validated when `pack.yaml` is read.
+    known.interfaces.contains(&import.interface)
+        && import
+            .requirement()
+            .ok()
+            .flatten()
+            .is_none_or(|req| req.matches(version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn imports(entries: &[&str]) -> Vec<ImportSpec> {
+        entries.iter().map(|entry| entry.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn resolves_to_newest_matching_version_and_skips_legacy_names() {
+        let catalog = InterfaceCatalog::builtin().unwrap();
+        let resolved = resolve_all(
+            &catalog,
+            &imports(&[
+                "secrets.get",
+                "greentic:host-import/secrets",
+                "greentic:host-import/telemetry@0.4",
+                "wasix:mcp/router@~25.3",
+            ]),
+        )
+        .unwrap();
+        let pinned: Vec<String> = resolved.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            pinned,
+            vec![
+                "greentic:host-import/secrets@0.4.0",
+                "greentic:host-import/telemetry@0.4.0",
+                "wasix:mcp/router@25.3.26",
+            ]
+        );
+        assert_eq!(resolved[0].import, "greentic:host-import/secrets");
+    }
+
+    #[test]
+    fn locked_versions_win_while_they_satisfy_every_entry() {
+        let catalog = InterfaceCatalog::builtin().unwrap();
+        let entries = imports(&["greentic:host-import/secrets", "wasix:mcp/router@>=25"]);
+        let unlocked = resolve_all(&catalog, &entries).unwrap();
+        assert_eq!(unlocked[0].version, "0.6.0");
+        assert_eq!(unlocked[1].version, "25.6.18");
+
+        let mut pins = unlocked.clone();
+        pins[0].version = "0.4.0".into();
+        pins[1].version = "24.11.5".into();
+        let lock = PackLock::new(pins);
+        let locked = resolve_locked(&catalog, &entries, Some(&lock)).unwrap();
+        // 24.11.5 no longer satisfies `>=25`, so the router is resolved again.
+        let versions: Vec<&str> = locked.iter().map(|i| i.version.as_str()).collect();
+        assert_eq!(versions, ["0.4.0", "25.6.18"]);
+
+        let changes = PackLock::new(unlocked).changes(&PackLock::new(locked));
+        assert_eq!(changes, ["~ greentic:host-import/secrets (0.6.0 -> 0.4.0)"]);
+    }
+
+    #[test]
+    fn rejects_unknown_and_incompatible_imports() {
+        let catalog = InterfaceCatalog::builtin().unwrap();
+        let err = |entries: &[&str]| {
+            resolve_all(&catalog, &imports(entries))
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(err(&["greentic:nope/thing"]).contains("unknown WIT package"));
+        assert!(err(&["greentic:host-import/missing"]).contains("no interface `missing`"));
+        assert!(err(&["greentic:host-import/mcp@0.4"]).contains("0.6.0"));
+        let conflict = err(&[
+            "greentic:host-import/mcp",
+            "greentic:host-import/secrets@0.4",
+        ]);
+        assert!(conflict.contains("need different versions"), "{conflict}");
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use greentic_pack::{SigningPolicy, open_pack};
+use schemars::JsonSchema;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use super::valid_name;
+
+/// File looked up in the pack root when no explicit catalog is passed.
+pub const CATALOG_FILE: &str = "interfaces.catalog.toml";
+
+/// Catalog format understood by this packc release.
+pub const CATALOG_FORMAT: u32 = 1;
+
+const DEFAULT_CATALOG: &str = include_str!("../../assets/interfaces.catalog.toml");
+
+/// Host interfaces that `imports_required` entries may resolve to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct InterfaceCatalog {
+    /// Catalog format version; must be 1.
+    pub format: u32,
+    /// Where the catalog was generated from, e.g. `greentic-interfaces 0.4.47`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, rename = "package")]
+    pub packages: Vec<CatalogPackage>,
+}
+
+/// One version of a WIT package and the interfaces it defines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CatalogPackage {
+    /// `namespace:package`, e.g. `greentic:host-import`.
+    pub name: String,
+    /// Exact semver version of the package.
+    pub version: String,
+    pub interfaces: Vec<String>,
+}
+
+impl InterfaceCatalog {
+    /// Loads `explicit` when given, otherwise `<pack_dir>/interfaces.catalog.toml`,
+    /// falling back to the built-in catalog.
+    pub fn load(pack_dir: &Path, explicit: Option<&Path>) -> Result<Self> {
+        match explicit {
+            Some(path) => Self::from_path(path),
+            None => {
+                let local = pack_dir.join(CATALOG_FILE);
+                if local.exists() {
+                    Self::from_path(&local)
+                } else {
+                    Self::builtin()
+                }
+            }
+        }
+    }
+
+    pub fn builtin() -> Result<Self> {
+        Self::parse(DEFAULT_CATALOG).context("built-in interface catalog is invalid")
+    }
+
+    /// Reads a catalog file, or the `interfaces.catalog.toml` carried by a
+    /// registry `.gtpack` (which must verify before it is trusted).
+    pub fn from_path(path: &Path) -> Result<Self> {
+        Self::parse(&read_source(path)?)
+            .with_context(|| format!("{} is not a valid interface catalog", path.display()))
+    }
+
+    pub fn parse(source: &str) -> Result<Self> {
+        let catalog: Self = toml::from_str(source)?;
+        catalog.validate()?;
+        Ok(catalog)
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string_pretty(self).context("failed to serialise interface catalog")
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.format != CATALOG_FORMAT {
+            bail!(
+                "unsupported catalog format {}; expected {CATALOG_FORMAT}",
+                self.format
+            );
+        }
+        let mut seen = BTreeSet::new();
+        for package in &self.packages {
+            let label = format!("{}@{}", package.name, package.version);
+            match package.name.split_once(':') {
+                Some((namespace, name)) if valid_name(namespace) && valid_name(name) => {}
+                _ => bail!(
+                    "package `{}` must be named `namespace:package`",
+                    package.name
+                ),
+            }
+            Version::parse(&package.version)
+                .with_context(|| format!("package `{label}` has an invalid version"))?;
+            if package.interfaces.is_empty() {
+                bail!("package `{label}` lists no interfaces");
+            }
+            if let Some(bad) = package.interfaces.iter().find(|name| !valid_name(name)) {
+                bail!("package `{label}` has an invalid interface name `{bad}`");
+            }
+            if !seen.insert((package.name.as_str(), package.version.as_str())) {
+                bail!("package `{label}` is listed more than once");
+            }
+        }
+        Ok(())
+    }
+
+    /// `name@version` of every package, for reporting catalog changes.
+    pub fn package_ids(&self) -> BTreeSet<String> {
+        self.packages
+            .iter()
+            .map(|package| format!("{}@{}", package.name, package.version))
+            .collect()
+    }
+}
+
+/// Raw catalog text from `path`, or from the built-in catalog when `None`.
+pub fn catalog_source(path: Option<&Path>) -> Result<String> {
+    match path {
+        Some(path) => read_source(path),
+        None => Ok(DEFAULT_CATALOG.to_string()),
+    }
+}
+
+fn read_source(path: &Path) -> Result<String> {
+    if path.extension().is_some_and(|ext| ext == "gtpack") {
+        return catalog_from_pack(path);
+    }
+    fs::read_to_string(path)
+        .with_context(|| format!("failed to read interface catalog {}", path.display()))
+}
+
+fn catalog_from_pack(path: &Path) -> Result<String> {
+    let load = open_pack(path, SigningPolicy::DevOk)
+        .map_err(|err| anyhow!("failed to open {}: {}", path.display(), err.message))?;
+    let entry = load
+        .sbom
+        .iter()
+        .map(|entry| entry.path.as_str())
+        .find(|entry| *entry == CATALOG_FILE || entry.ends_with(&format!("/{CATALOG_FILE}")))
+        .ok_or_else(|| anyhow!("{} does not contain {CATALOG_FILE}", path.display()))?;
+    let bytes = load.file(entry).unwrap_or_default();
+    String::from_utf8(bytes.to_vec())
+        .with_context(|| format!("{entry} in {} is not UTF-8", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_catalog_is_valid() {
+        let catalog = InterfaceCatalog::builtin().unwrap();
+        assert!(catalog.package_ids().contains("greentic:host-import@0.6.0"));
+        let round_trip = InterfaceCatalog::parse(&catalog.to_toml().unwrap()).unwrap();
+        assert_eq!(round_trip, catalog);
+    }
+
+    #[test]
+    fn invalid_catalogs_are_rejected() {
+        let err = |source: &str| format!("{:#}", InterfaceCatalog::parse(source).unwrap_err());
+
+        assert!(err("format = 2").contains("unsupported catalog format"));
+        assert!(err("format = 1\nextra = true").contains("unknown field"));
+        let package = |name: &str, version: &str, interfaces: &str| {
+            format!(
+                "format = 1\n[[package]]\nname = \"{name}\"\nversion = \"{version}\"\ninterfaces = {interfaces}\n"
+            )
+        };
+        assert!(err(&package("nocolon", "1.0.0", "[\"a\"]")).contains("namespace:package"));
+        assert!(err(&package("acme:x", "1.0", "[\"a\"]")).contains("invalid version"));
+        assert!(err(&package("acme:x", "1.0.0", "[]")).contains("no interfaces"));
+        let twice = format!(
+            "{}{}",
+            package("acme:x", "1.0.0", "[\"a\"]"),
+            package("acme:x", "1.0.0", "[\"b\"]").trim_start_matches("format = 1\n")
+        );
+        assert!(err(&twice).contains("more than once"));
+    }
+}
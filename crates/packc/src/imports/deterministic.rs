@@ -0,0 +1,216 @@
+//! Deterministic stand-ins for the WASI clock and random interfaces.
+//!
+//! Flows that read the time or draw random numbers give different results on
+//! every run. packc knows the shape of these interfaces, so instead of asking
+//! for hand-written mocks it provides them itself: wall-clock time is frozen
+//! at a chosen instant, the monotonic clock advances by a fixed tick on every
+//! read, and random values come from a seeded generator. The same seed and
+//! instant give the same values on every machine.
+
+use std::collections::BTreeMap;
+
+use serde_json::{Value, json};
+use time::OffsetDateTime;
+
+use super::ResolvedImport;
+use super::mocks::{FunctionMock, InterfaceMock};
+
+/// WIT packages whose interfaces packc provides itself.
+pub const BUILTIN_PACKAGES: &[&str] = &["wasi:clocks", "wasi:random"];
+
+/// How far the monotonic clock advances on each read: one millisecond.
+pub const MONOTONIC_TICK_NS: u64 = 1_000_000;
+
+/// Bytes returned by the mocked `get-random-bytes` functions.
+const MOCK_BYTES: u64 = 16;
+
+/// Whether packc provides `import` without a mock file or WIT definition.
+pub fn is_builtin(import: &ResolvedImport) -> bool {
+    BUILTIN_PACKAGES.contains(&import.package.as_str())
+}
+
+/// Clock and random source of a reproducible run.
+#[derive(Debug, Clone)]
+pub struct DeterministicHost {
+    now: OffsetDateTime,
+    monotonic_ns: u64,
+    state: u64,
+}
+
+impl DeterministicHost {
+    pub fn new(seed: u64, now: OffsetDateTime) -> Self {
+        Self {
+            now,
+            monotonic_ns: 0,
+            state: seed,
+        }
+    }
+
+    /// `wasi:clocks/wall-clock.now`: the frozen instant as seconds and
+    /// nanoseconds since the Unix epoch.
+    pub fn wall_clock_now(&self) -> (u64, u32) {
+        let nanos = self.now.unix_timestamp_nanos().max(0);
+        (
+            (nanos / 1_000_000_000) as u64,
+            (nanos % 1_000_000_000) as u32,
+        )
+    }
+
+    /// `wasi:clocks/monotonic-clock.now`: nanoseconds, advancing by
+    /// [`MONOTONIC_TICK_NS`] on every read.
+    pub fn monotonic_now(&mut self) -> u64 {
+        self.monotonic_ns += MONOTONIC_TICK_NS;
+        self.monotonic_ns
+    }
+
+    /// `get-random-u64`, from a SplitMix64 sequence.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// `get-random-bytes`.
+    pub fn bytes(&mut self, len: u64) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(len as usize);
+        while (bytes.len() as u64) < len {
+            let word = self.next_u64().to_le_bytes();
+            let take = (len - bytes.len() as u64).min(8) as usize;
+            bytes.extend_from_slice(&word[..take]);
+        }
+        bytes
+    }
+
+    /// Answers a call to `function` of `import` with fresh values from this
+    /// host, or `None` when packc does not provide the function. `len` is
+    /// the byte count `get-random-bytes` asks for.
+    pub fn call(&mut self, import: &ResolvedImport, function: &str, len: u64) -> Option<Value> {
+        let (seconds, nanoseconds) = self.wall_clock_now();
+        let returns = match (import.package.as_str(), import.interface.as_str(), function) {
+            ("wasi:clocks", "wall-clock", "now") => {
+                json!({ "seconds": seconds, "nanoseconds": nanoseconds })
+            }
+            ("wasi:clocks", "wall-clock", "resolution") => {
+                json!({ "seconds": 0, "nanoseconds": 1 })
+            }
+            ("wasi:clocks", "monotonic-clock", "now") => json!(self.monotonic_now()),
+            ("wasi:clocks", "monotonic-clock", "resolution") => json!(1),
+            ("wasi:random", "random", "get-random-bytes")
+            | ("wasi:random", "insecure", "get-insecure-random-bytes") => json!(self.bytes(len)),
+            ("wasi:random", "random", "get-random-u64")
+            | ("wasi:random", "insecure", "get-insecure-random-u64") => json!(self.next_u64()),
+            ("wasi:random", "insecure-seed", "insecure-seed") => {
+                json!([self.next_u64(), self.next_u64()])
+            }
+            _ => return None,
+        };
+        Some(returns)
+    }
+
+    /// A mock file for `import` with values drawn from this host, or `None`
+    /// when packc does not provide the interface.
+    pub fn mock(&mut self, import: &ResolvedImport) -> Option<InterfaceMock> {
+        let functions: BTreeMap<String, FunctionMock> = functions(import)?
+            .iter()
+            .map(|(name, signature)| {
+                let mock = FunctionMock {
+                    signature: Some(signature.to_string()),
+                    returns: self
+                        .call(import, name, MOCK_BYTES)
+                        .expect("packc answers the functions it provides"),
+                };
+                (name.to_string(), mock)
+            })
+            .collect();
+        Some(InterfaceMock {
+            interface: import.to_string(),
+            functions,
+        })
+    }
+}
+
+impl Default for DeterministicHost {
+    /// Seed 0, with the wall clock frozen at the Unix epoch.
+    fn default() -> Self {
+        Self::new(0, OffsetDateTime::UNIX_EPOCH)
+    }
+}
+
+/// The functions packc provides for `import`, with their WIT signatures.
+/// Functions returning resources (such as pollables) are left out.
+fn functions(import: &ResolvedImport) -> Option<&'static [(&'static str, &'static str)]> {
+    let functions: &[_] = match (import.package.as_str(), import.interface.as_str()) {
+        ("wasi:clocks", "wall-clock") => &[
+            ("now", "func() -> datetime"),
+            ("resolution", "func() -> datetime"),
+        ],
+        ("wasi:clocks", "monotonic-clock") => &[
+            ("now", "func() -> instant"),
+            ("resolution", "func() -> duration"),
+        ],
+        ("wasi:random", "random") => &[
+            ("get-random-bytes", "func(len: u64) -> list<u8>"),
+            ("get-random-u64", "func() -> u64"),
+        ],
+        ("wasi:random", "insecure") => &[
+            ("get-insecure-random-bytes", "func(len: u64) -> list<u8>"),
+            ("get-insecure-random-u64", "func() -> u64"),
+        ],
+        ("wasi:random", "insecure-seed") => &[("insecure-seed", "func() -> tuple<u64, u64>")],
+        _ => return None,
+    };
+    Some(functions)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn import(package: &str, interface: &str) -> ResolvedImport {
+        ResolvedImport {
+            import: format!("{package}/{interface}"),
+            package: package.into(),
+            interface: interface.into(),
+            version: "0.2.0".into(),
+        }
+    }
+
+    #[test]
+    fn same_seed_and_instant_give_the_same_values() {
+        let now = datetime!(2025-03-01 12:00:00.5 UTC);
+        let mut a = DeterministicHost::new(7, now);
+        let mut b = DeterministicHost::new(7, now);
+        assert_eq!(a.bytes(20), b.bytes(20));
+        assert_eq!(a.next_u64(), b.next_u64());
+        assert_ne!(
+            DeterministicHost::new(8, now).next_u64(),
+            DeterministicHost::new(7, now).next_u64()
+        );
+        assert_eq!(a.wall_clock_now(), (1_740_830_400, 500_000_000));
+        assert_eq!(a.monotonic_now(), MONOTONIC_TICK_NS);
+        assert_eq!(a.monotonic_now(), 2 * MONOTONIC_TICK_NS);
+
+        let mock = DeterministicHost::new(7, now)
+            .mock(&import("wasi:clocks", "wall-clock"))
+            .unwrap();
+        assert_eq!(mock.interface, "wasi:clocks/wall-clock@0.2.0");
+        assert_eq!(mock.functions["now"].returns["seconds"], 1_740_830_400u64);
+        let random = import("wasi:random", "random");
+        assert!(is_builtin(&random));
+        let mut live = DeterministicHost::new(7, now);
+        assert_eq!(
+            live.call(&random, "get-random-bytes", 3),
+            Some(json!(DeterministicHost::new(7, now).bytes(3)))
+        );
+        assert!(live.call(&random, "subscribe", 0).is_none());
+        assert!(
+            DeterministicHost::new(7, now)
+                .mock(&import("greentic:host", "kv-v1"))
+                .is_none()
+        );
+    }
+}
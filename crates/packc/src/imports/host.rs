@@ -0,0 +1,492 @@
+//! The host side of the interface imports of `packc run`.
+//!
+//! There are no real hosts behind the interfaces a pack imports when it runs
+//! on a laptop. Calls are answered from a [`Replayer`] when replaying a
+//! cassette. Otherwise `wasi:clocks` and `wasi:random` are answered by a
+//! [`DeterministicHost`], and other interfaces from the mock files
+//! `packc imports mocks` writes; a call nothing answers traps. When recording, every answered call is fed to
+//! a [`Recorder`] and the cassette is saved once the run ends. When tracing,
+//! calls also go to a [`TraceRecorder`] under the node that made them.
+//!
+//! A call is recorded under the interface it imports, such as
+//! `greentic:host/kv-v1@0.1.0`, with a request naming the function and its
+//! parameters: `{"function": "get", "params": {"key": "a"}}`. Values map
+//! onto JSON the way mock files map them onto YAML.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use greentic_pack::cassette::{Outcome, Recorder, Replayer};
+use greentic_pack::trace::TraceRecorder;
+use serde_json::{Map, Value as JsonValue, json};
+use walkdir::WalkDir;
+
+use super::ResolvedImport;
+use super::deterministic::{self, DeterministicHost};
+use super::mocks::{self, InterfaceMock};
+
+/// Answers the import calls of one run.
+#[derive(Default)]
+pub struct ImportHost {
+    mocks: BTreeMap<String, InterfaceMock>,
+    builtins: DeterministicHost,
+    replayer: Option<Replayer>,
+    recording: Option<(Recorder, PathBuf)>,
+    tracing: Option<(TraceRecorder, PathBuf)>,
+}
+
+impl ImportHost {
+    /// A host answering from `mocks`, keyed by the interface they target,
+    /// with clocks and random values from `builtins`.
+    pub fn new(mocks: Vec<InterfaceMock>, builtins: DeterministicHost) -> Self {
+        Self {
+            mocks: mocks
+                .into_iter()
+                .map(|mock| (mock.interface.clone(), mock))
+                .collect(),
+            builtins,
+            ..Self::default()
+        }
+    }
+
+    /// Reads every mock file under `dir`; a missing directory holds none.
+    pub fn load_mocks(dir: &Path) -> Result<Vec<InterfaceMock>> {
+        let mut found = Vec::new();
+        if !dir.is_dir() {
+            return Ok(found);
+        }
+        for entry in WalkDir::new(dir).sort_by_file_name() {
+            let entry =
+                entry.with_context(|| format!("failed to read mocks in {}", dir.display()))?;
+            let path = entry.path();
+            if entry.file_type().is_file() && path.extension().is_some_and(|ext| ext == "yaml") {
+                found.push(mocks::read_mock(path)?);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Answers calls from `replayer` instead of the mocks.
+    pub fn replay(&mut self, replayer: Replayer) {
+        self.replayer = Some(replayer);
+    }
+
+    /// Records every answered call, to be saved at `path` by
+    /// [`finish`](Self::finish).
+    pub fn record(&mut self, recorder: Recorder, path: PathBuf) {
+        self.recording = Some((recorder, path));
+    }
+
+    /// Traces the run, to be saved at `path` by
+    /// [`finish_trace`](Self::finish_trace).
+    pub fn trace(&mut self, recorder: TraceRecorder, path: PathBuf) {
+        self.tracing = Some((recorder, path));
+    }
+
+    /// Notes that the run moved on to `node`.
+    pub fn enter(&mut self, node: &str) {
+        if let Some((trace, _)) = &mut self.tracing {
+            trace.enter(node);
+        }
+    }
+
+    /// Notes output the run streamed.
+    pub fn chunk(&mut self, output: &JsonValue) {
+        if let Some((trace, _)) = &mut self.tracing {
+            trace.chunk(output);
+        }
+    }
+
+    /// Answers a call to `function` of `interface`.
+    pub fn call(
+        &mut self,
+        interface: &str,
+        function: &str,
+        params: Map<String, JsonValue>,
+    ) -> Result<Outcome> {
+        let request = json!({ "function": function, "params": params });
+        let outcome = match &mut self.replayer {
+            Some(replayer) => replayer.call(interface, &request)?,
+            None => {
+                let len = request["params"]["len"].as_u64().unwrap_or_default();
+                let builtin = parse_interface(interface)
+                    .filter(deterministic::is_builtin)
+                    .and_then(|import| self.builtins.call(&import, function, len));
+                let mock = || {
+                    self.mocks
+                        .get(interface)
+                        .and_then(|mock| mock.functions.get(function))
+                        .map(|mock| mock.returns.clone())
+                };
+                match builtin.or_else(mock) {
+                    Some(returns) => Outcome::Ok(returns),
+                    None => bail!(
+                        "no mock answers `{interface}#{function}`; write one with `packc imports mocks`"
+                    ),
+                }
+            }
+        };
+        if let Some((recorder, _)) = &mut self.recording {
+            recorder.record(interface, &request, &outcome);
+        }
+        if let Some((trace, _)) = &mut self.tracing {
+            trace.record(interface, &request, &outcome);
+        }
+        Ok(outcome)
+    }
+
+    /// Saves the trace of a run that ended with `result`, or stopped with
+    /// the error message.
+    pub fn finish_trace(&mut self, result: Result<JsonValue, String>) -> Result<()> {
+        if let Some((trace, path)) = self.tracing.take() {
+            trace
+                .finish(result)
+                .save(&path)
+                .with_context(|| format!("failed to save the trace {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Saves the recording, and fails when a replayed cassette holds calls
+    /// the run never made.
+    pub fn finish(&mut self) -> Result<()> {
+        if let Some((recorder, path)) = self.recording.take() {
+            recorder
+                .finish()
+                .save(&path)
+                .with_context(|| format!("failed to save the cassette {}", path.display()))?;
+        }
+        if let Some(replayer) = self.replayer.take() {
+            replayer.finish()?;
+        }
+        Ok(())
+    }
+}
+
+/// `interface` as components name their imports,
+/// `namespace:package/interface@version`.
+fn parse_interface(interface: &str) -> Option<ResolvedImport> {
+    let (path, version) = interface.split_once('@')?;
+    let (package, name) = path.split_once('/')?;
+    Some(ResolvedImport {
+        import: interface.to_string(),
+        package: package.to_string(),
+        interface: name.to_string(),
+        version: version.to_string(),
+    })
+}
+
+#[cfg(feature = "run")]
+pub use values::{from_json, to_json};
+
+/// Component values as JSON, for `packc run`'s embedded wasmtime.
+#[cfg(feature = "run")]
+mod values {
+    use anyhow::{Result, anyhow, bail};
+    use serde_json::{Map, Number, Value as JsonValue, json};
+    use wasmtime::component::{Type, Val};
+
+    /// `value` as JSON.
+    pub fn to_json(value: &Val) -> Result<JsonValue> {
+        Ok(match value {
+            Val::Bool(value) => json!(value),
+            Val::S8(value) => json!(value),
+            Val::U8(value) => json!(value),
+            Val::S16(value) => json!(value),
+            Val::U16(value) => json!(value),
+            Val::S32(value) => json!(value),
+            Val::U32(value) => json!(value),
+            Val::S64(value) => json!(value),
+            Val::U64(value) => json!(value),
+            Val::Float32(value) => float(f64::from(*value)),
+            Val::Float64(value) => float(*value),
+            Val::Char(value) => json!(value.to_string()),
+            Val::String(value) => json!(value),
+            Val::List(items) | Val::Tuple(items) => {
+                JsonValue::Array(items.iter().map(to_json).collect::<Result<_>>()?)
+            }
+            Val::Record(fields) => JsonValue::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| Ok((name.clone(), to_json(value)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            Val::Enum(case) | Val::Variant(case, None) => json!(case),
+            Val::Variant(case, Some(payload)) => {
+                JsonValue::Object(Map::from_iter([(case.clone(), to_json(payload)?)]))
+            }
+            Val::Option(None) => JsonValue::Null,
+            Val::Option(Some(value)) => to_json(value)?,
+            Val::Result(Ok(value)) => json!({ "ok": optional(value)? }),
+            Val::Result(Err(value)) => json!({ "err": optional(value)? }),
+            Val::Flags(names) => json!(names),
+            _ => bail!("resource and async values have no JSON form"),
+        })
+    }
+
+    fn float(value: f64) -> JsonValue {
+        Number::from_f64(value).map_or(JsonValue::Null, JsonValue::Number)
+    }
+
+    fn optional(value: &Option<Box<Val>>) -> Result<JsonValue> {
+        value.as_deref().map_or(Ok(JsonValue::Null), to_json)
+    }
+
+    /// `value` as a component value of type `ty`.
+    pub fn from_json(ty: &Type, value: &JsonValue) -> Result<Val> {
+        Ok(match ty {
+            Type::Bool => Val::Bool(
+                value
+                    .as_bool()
+                    .ok_or_else(|| mismatch("a boolean", value))?,
+            ),
+            Type::S8 => Val::S8(integer(value)?),
+            Type::U8 => Val::U8(integer(value)?),
+            Type::S16 => Val::S16(integer(value)?),
+            Type::U16 => Val::U16(integer(value)?),
+            Type::S32 => Val::S32(integer(value)?),
+            Type::U32 => Val::U32(integer(value)?),
+            Type::S64 => Val::S64(integer(value)?),
+            Type::U64 => Val::U64(integer(value)?),
+            Type::Float32 => Val::Float32(number(value)? as f32),
+            Type::Float64 => Val::Float64(number(value)?),
+            Type::Char => {
+                let text = string(value)?;
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Val::Char(c),
+                    _ => return Err(mismatch("a single character", value)),
+                }
+            }
+            Type::String => Val::String(string(value)?.to_string()),
+            Type::List(list) => Val::List(
+                array(value)?
+                    .iter()
+                    .map(|item| from_json(&list.ty(), item))
+                    .collect::<Result<_>>()?,
+            ),
+            Type::Tuple(tuple) => {
+                let items = array(value)?;
+                if items.len() != tuple.types().len() {
+                    return Err(mismatch(
+                        &format!("{} tuple elements", tuple.types().len()),
+                        value,
+                    ));
+                }
+                Val::Tuple(
+                    tuple
+                        .types()
+                        .zip(items)
+                        .map(|(ty, item)| from_json(&ty, item))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            Type::Record(record) => {
+                let fields = object(value)?;
+                Val::Record(
+                    record
+                        .fields()
+                        .map(|field| {
+                            let value = fields.get(field.name).unwrap_or(&JsonValue::Null);
+                            Ok((field.name.to_string(), from_json(&field.ty, value)?))
+                        })
+                        .collect::<Result<_>>()?,
+                )
+            }
+            Type::Variant(variant) => {
+                let (name, payload) = case(value)?;
+                let case = variant
+                    .cases()
+                    .find(|case| case.name == name)
+                    .ok_or_else(|| anyhow!("`{name}` is not a case of the variant"))?;
+                let payload = match (case.ty, payload) {
+                    (Some(ty), payload) => Some(Box::new(from_json(
+                        &ty,
+                        payload.unwrap_or(&JsonValue::Null),
+                    )?)),
+                    (None, None | Some(JsonValue::Null)) => None,
+                    (None, Some(_)) => bail!("variant case `{name}` carries no value"),
+                };
+                Val::Variant(name.to_string(), payload)
+            }
+            Type::Enum(cases) => {
+                let name = string(value)?;
+                if !cases.names().any(|case| case == name) {
+                    bail!("`{name}` is not a case of the enum");
+                }
+                Val::Enum(name.to_string())
+            }
+            Type::Option(option) => match value {
+                JsonValue::Null => Val::Option(None),
+                value => Val::Option(Some(Box::new(from_json(&option.ty(), value)?))),
+            },
+            Type::Result(result) => {
+                let (name, payload) = case(value)?;
+                let payload = |ty: Option<Type>| -> Result<Option<Box<Val>>> {
+                    ty.map(|ty| from_json(&ty, payload.unwrap_or(&JsonValue::Null)))
+                        .transpose()
+                        .map(|value| value.map(Box::new))
+                };
+                match name {
+                    "ok" => Val::Result(Ok(payload(result.ok())?)),
+                    "err" => Val::Result(Err(payload(result.err())?)),
+                    _ => return Err(mismatch("`{ok: ...}` or `{err: ...}`", value)),
+                }
+            }
+            Type::Flags(flags) => {
+                let mut set = Vec::new();
+                for name in array(value)? {
+                    let name = string(name)?;
+                    if !flags.names().any(|flag| flag == name) {
+                        bail!("`{name}` is not a flag of the type");
+                    }
+                    set.push(name.to_string());
+                }
+                Val::Flags(set)
+            }
+            _ => bail!("resource and async values have no JSON form"),
+        })
+    }
+
+    fn mismatch(expected: &str, value: &JsonValue) -> anyhow::Error {
+        anyhow!("expected {expected}, found `{value}`")
+    }
+
+    fn integer<N: TryFrom<i128>>(value: &JsonValue) -> Result<N> {
+        value
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| value.as_u64().map(i128::from))
+            .and_then(|value| N::try_from(value).ok())
+            .ok_or_else(|| mismatch("an integer in range", value))
+    }
+
+    fn number(value: &JsonValue) -> Result<f64> {
+        value.as_f64().ok_or_else(|| mismatch("a number", value))
+    }
+
+    fn string(value: &JsonValue) -> Result<&str> {
+        value.as_str().ok_or_else(|| mismatch("a string", value))
+    }
+
+    fn array(value: &JsonValue) -> Result<&Vec<JsonValue>> {
+        value.as_array().ok_or_else(|| mismatch("a list", value))
+    }
+
+    fn object(value: &JsonValue) -> Result<&Map<String, JsonValue>> {
+        value.as_object().ok_or_else(|| mismatch("a record", value))
+    }
+
+    /// The case name and payload of a variant or result: a bare name, or
+    /// an object with the case name as its only key.
+    fn case(value: &JsonValue) -> Result<(&str, Option<&JsonValue>)> {
+        match value {
+            JsonValue::String(name) => Ok((name, None)),
+            JsonValue::Object(map) if map.len() == 1 => {
+                let (name, payload) = map.iter().next().expect("one entry");
+                Ok((name, Some(payload)))
+            }
+            _ => Err(mismatch("a case name or `{case: value}`", value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use greentic_pack::cassette::{Cassette, RedactionRules};
+
+    use super::*;
+    use crate::imports::mocks::FunctionMock;
+
+    const KV: &str = "greentic:host/kv-v1@0.1.0";
+
+    fn host() -> ImportHost {
+        let get = FunctionMock {
+            signature: None,
+            returns: json!("stored"),
+        };
+        let mock = InterfaceMock {
+            interface: KV.into(),
+            functions: [("get".to_string(), get)].into(),
+        };
+        ImportHost::new(vec![mock], DeterministicHost::default())
+    }
+
+    fn params(key: &str) -> Map<String, JsonValue> {
+        Map::from_iter([("key".to_string(), json!(key))])
+    }
+
+    #[test]
+    fn records_mocked_calls_and_replays_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.cassette.json");
+        let mut recording = host();
+        let recorder = Recorder::new("demo", "main", &json!(null), RedactionRules::standard());
+        recording.record(recorder, path.clone());
+        assert_eq!(
+            recording.call(KV, "get", params("a")).unwrap(),
+            Outcome::Ok(json!("stored"))
+        );
+        let err = recording.call(KV, "put", params("a")).unwrap_err();
+        assert!(err.to_string().contains("packc imports mocks"), "{err}");
+        recording.finish().unwrap();
+
+        let cassette = Cassette::load(&path).unwrap();
+        assert_eq!(cassette.interactions.len(), 1);
+        assert_eq!(
+            cassette.interactions[0].request,
+            json!({ "function": "get", "params": { "key": "a" } })
+        );
+
+        let mut replaying = ImportHost::default();
+        replaying.replay(Replayer::new(cassette.clone(), RedactionRules::standard()));
+        assert_eq!(
+            replaying.call(KV, "get", params("a")).unwrap(),
+            Outcome::Ok(json!("stored"))
+        );
+        replaying.finish().unwrap();
+
+        let mut diverging = ImportHost::default();
+        diverging.replay(Replayer::new(cassette, RedactionRules::standard()));
+        assert!(diverging.call(KV, "get", params("b")).is_err());
+    }
+
+    #[test]
+    fn clocks_and_random_values_come_from_the_seed() {
+        let now = time::macros::datetime!(2025-03-01 12:00:00 UTC);
+        let wall_clock = "wasi:clocks/wall-clock@0.2.0";
+        let random = "wasi:random/random@0.2.0";
+        let mut imports = ImportHost::new(Vec::new(), DeterministicHost::new(7, now));
+        assert_eq!(
+            imports.call(wall_clock, "now", Map::new()).unwrap(),
+            Outcome::Ok(json!({ "seconds": 1_740_830_400u64, "nanoseconds": 0 }))
+        );
+        let len = Map::from_iter([("len".to_string(), json!(4))]);
+        let Outcome::Ok(bytes) = imports.call(random, "get-random-bytes", len).unwrap() else {
+            panic!("random bytes are answered");
+        };
+        assert_eq!(bytes, json!(DeterministicHost::new(7, now).bytes(4)));
+        assert!(imports.call(random, "subscribe", Map::new()).is_err());
+    }
+
+    #[cfg(feature = "run")]
+    #[test]
+    fn values_map_onto_json_like_mocks() {
+        use wasmtime::component::Val;
+
+        let value = Val::Record(vec![
+            ("id".into(), Val::U32(7)),
+            ("tags".into(), Val::List(vec![Val::String("a".into())])),
+            ("note".into(), Val::Option(None)),
+            (
+                "status".into(),
+                Val::Result(Ok(Some(Box::new(Val::Enum("open".into()))))),
+            ),
+        ]);
+        assert_eq!(
+            to_json(&value).unwrap(),
+            json!({ "id": 7, "tags": ["a"], "note": null, "status": { "ok": "open" } })
+        );
+    }
+}
@@ -0,0 +1,647 @@
+//! Host interface mocks generated from WIT.
+//!
+//! Each resolved import gets a YAML file under `mocks/<namespace>/<package>/`
+//! listing the interface's functions with their WIT signatures and a
+//! placeholder `returns` value shaped like the result type. Authors fill in
+//! the values; [`load_mocks`] checks every value against the WIT type so a
+//! mock that drifts from the interface is rejected before anything runs.
+//!
+//! WIT values map onto YAML as follows: records are mappings keyed by field
+//! name, tuples and lists are sequences, `option` is `null` or the value,
+//! `result` is `{ok: ...}` or `{err: ...}`, enums are the case name, variants
+//! are the case name or `{case: payload}`, and flags are a list of names.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use walkdir::WalkDir;
+use wit_parser::{
+    Function, FunctionKind, Handle, Interface, PackageId, Resolve, Type, TypeDefKind,
+};
+
+use super::ResolvedImport;
+use super::deterministic;
+
+/// Directory, relative to the pack root, that holds interface mocks.
+pub const MOCKS_DIR: &str = "mocks";
+
+/// Canned responses for the functions of one host interface.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InterfaceMock {
+    /// `namespace:package/interface@version` the mock was written against.
+    pub interface: String,
+    #[serde(default)]
+    pub functions: BTreeMap<String, FunctionMock>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FunctionMock {
+    /// WIT signature, for reference only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Value handed back to the caller; `null` for functions without a result.
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub returns: Value,
+}
+
+/// WIT packages backing a set of resolved imports.
+pub struct WitLibrary {
+    resolve: Resolve,
+    packages: BTreeMap<String, PackageId>,
+}
+
+impl WitLibrary {
+    /// Loads the WIT package behind every import from `wit_root`.
+    ///
+    /// Packages are found by their `package ns:name@version;` declaration,
+    /// either as a single `.wit` file or as a directory with a `deps/` folder
+    /// (the layout used by `greentic-interfaces`).
+    pub fn load(wit_root: &Path, imports: &[ResolvedImport]) -> Result<Self> {
+        let wanted: BTreeSet<String> = imports.iter().map(package_id).collect();
+        let mut found = BTreeMap::new();
+        for entry in WalkDir::new(wit_root)
+            .into_iter()
+            .filter_entry(|entry| entry.file_name() != "deps")
+        {
+            let entry = entry
+                .with_context(|| format!("failed to read WIT directory {}", wit_root.display()))?;
+            let path = entry.path();
+            if !entry.file_type().is_file() || path.extension().is_none_or(|ext| ext != "wit") {
+                continue;
+            }
+            let source = fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            if let Some(id) = declared_package(&source).filter(|id| wanted.contains(*id)) {
+                let parent = path.parent().unwrap_or(wit_root);
+                let location = if parent.join("deps").is_dir() {
+                    parent.to_path_buf()
+                } else {
+                    path.to_path_buf()
+                };
+                found.entry(id.to_string()).or_insert(location);
+            }
+        }
+
+        let mut resolve = Resolve::default();
+        let mut packages = BTreeMap::new();
+        for id in wanted {
+            let location = found.get(&id).ok_or_else(|| {
+                anyhow!("no WIT definition for `{id}` under {}", wit_root.display())
+            })?;
+            let (package, _) = resolve
+                .push_path(location)
+                .with_context(|| format!("failed to parse WIT package {}", location.display()))?;
+            packages.insert(id, package);
+        }
+        Ok(Self { resolve, packages })
+    }
+
+    fn interface(&self, import: &ResolvedImport) -> Result<&Interface> {
+        let package = &self.resolve.packages[self.packages[&package_id(import)]];
+        let id = package.interfaces.get(&import.interface).ok_or_else(|| {
+            anyhow!(
+                "WIT package `{}` has no interface `{}`",
+                package_id(import),
+                import.interface
+            )
+        })?;
+        Ok(&self.resolve.interfaces[*id])
+    }
+
+    /// A mock for `import` with every function stubbed with a placeholder
+    /// value of the right shape.
+    pub fn skeleton(&self, import: &ResolvedImport) -> Result<InterfaceMock> {
+        let interface = self.interface(import)?;
+        let functions = callable(interface)
+            .map(|function| {
+                let mock = FunctionMock {
+                    signature: Some(self.signature(function)),
+                    returns: function
+                        .result
+                        .map(|ty| self.placeholder(ty))
+                        .unwrap_or(Value::Null),
+                };
+                (function.name.clone(), mock)
+            })
+            .collect();
+        Ok(InterfaceMock {
+            interface: import.to_string(),
+            functions,
+        })
+    }
+
+    /// Checks that `mock` targets `import` and that every canned value
+    /// matches the WIT result type of its function.
+    pub fn check(&self, import: &ResolvedImport, mock: &InterfaceMock) -> Result<()> {
+        check_target(import, mock)?;
+        let expected = import.to_string();
+        let interface = self.interface(import)?;
+        for (name, mock) in &mock.functions {
+            let function = callable(interface)
+                .find(|function| function.name == *name)
+                .ok_or_else(|| anyhow!("`{name}` is not a function of `{expected}`"))?;
+            let path = format!("{name}.returns");
+            match function.result {
+                Some(ty) => self.check_value(ty, &mock.returns, &path)?,
+                None if !mock.returns.is_null() => {
+                    bail!("{path}: `{name}` has no result, so it cannot return a value")
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn signature(&self, function: &Function) -> String {
+        let params: Vec<String> = function
+            .params
+            .iter()
+            .map(|(name, ty)| format!("{name}: {}", self.type_name(*ty)))
+            .collect();
+        let mut signature = format!("func({})", params.join(", "));
+        if let Some(result) = function.result {
+            signature.push_str(" -> ");
+            signature.push_str(&self.type_name(result));
+        }
+        signature
+    }
+
+    /// The type as it would be written in WIT.
+    fn type_name(&self, ty: Type) -> String {
+        let id = match ty {
+            Type::Bool => return "bool".into(),
+            Type::U8 => return "u8".into(),
+            Type::U16 => return "u16".into(),
+            Type::U32 => return "u32".into(),
+            Type::U64 => return "u64".into(),
+            Type::S8 => return "s8".into(),
+            Type::S16 => return "s16".into(),
+            Type::S32 => return "s32".into(),
+            Type::S64 => return "s64".into(),
+            Type::F32 => return "f32".into(),
+            Type::F64 => return "f64".into(),
+            Type::Char => return "char".into(),
+            Type::String => return "string".into(),
+            Type::ErrorContext => return "error-context".into(),
+            Type::Id(id) => id,
+        };
+        let def = &self.resolve.types[id];
+        if let Some(name) = &def.name {
+            return name.clone();
+        }
+        let optional = |ty: Option<Type>| ty.map(|ty| self.type_name(ty));
+        match &def.kind {
+            TypeDefKind::Option(ty) => format!("option<{}>", self.type_name(*ty)),
+            TypeDefKind::List(ty) => format!("list<{}>", self.type_name(*ty)),
+            TypeDefKind::FixedSizeList(ty, len) => {
+                format!("list<{}, {len}>", self.type_name(*ty))
+            }
+            TypeDefKind::Tuple(tuple) => {
+                let types: Vec<String> = tuple.types.iter().map(|ty| self.type_name(*ty)).collect();
+                format!("tuple<{}>", types.join(", "))
+            }
+            TypeDefKind::Result(result) => match (optional(result.ok), optional(result.err)) {
+                (None, None) => "result".into(),
+                (Some(ok), None) => format!("result<{ok}>"),
+                (None, Some(err)) => format!("result<_, {err}>"),
+                (Some(ok), Some(err)) => format!("result<{ok}, {err}>"),
+            },
+            TypeDefKind::Handle(Handle::Own(id)) => self.type_name(Type::Id(*id)),
+            TypeDefKind::Handle(Handle::Borrow(id)) => {
+                format!("borrow<{}>", self.type_name(Type::Id(*id)))
+            }
+            TypeDefKind::Future(ty) => match optional(*ty) {
+                Some(ty) => format!("future<{ty}>"),
+                None => "future".into(),
+            },
+            TypeDefKind::Stream(ty) => match optional(*ty) {
+                Some(ty) => format!("stream<{ty}>"),
+                None => "stream".into(),
+            },
+            TypeDefKind::Type(ty) => self.type_name(*ty),
+            other => other.as_str().into(),
+        }
+    }
+
+    /// Placeholder value for `ty`; types that cannot be mocked become `null`,
+    /// which [`WitLibrary::check`] later rejects.
+    fn placeholder(&self, ty: Type) -> Value {
+        let id = match ty {
+            Type::Bool => return Value::Bool(false),
+            Type::U8 | Type::U16 | Type::U32 | Type::U64 => return Value::from(0),
+            Type::S8 | Type::S16 | Type::S32 | Type::S64 => return Value::from(0),
+            Type::F32 | Type::F64 => return Value::from(0.0),
+            Type::Char => return Value::from("a"),
+            Type::String => return Value::from(""),
+            Type::ErrorContext => return Value::Null,
+            Type::Id(id) => id,
+        };
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Record(record) => Value::Object(
+                record
+                    .fields
+                    .iter()
+                    .map(|field| (field.name.clone(), self.placeholder(field.ty)))
+                    .collect(),
+            ),
+            TypeDefKind::Tuple(tuple) => {
+                Value::Array(tuple.types.iter().map(|ty| self.placeholder(*ty)).collect())
+            }
+            TypeDefKind::FixedSizeList(ty, len) => {
+                Value::Array((0..*len).map(|_| self.placeholder(*ty)).collect())
+            }
+            TypeDefKind::Flags(_) | TypeDefKind::List(_) => Value::Array(Vec::new()),
+            TypeDefKind::Enum(enum_) => enum_
+                .cases
+                .first()
+                .map(|case| Value::from(case.name.as_str()))
+                .unwrap_or(Value::Null),
+            TypeDefKind::Variant(variant) => match variant.cases.first() {
+                Some(case) => match case.ty {
+                    Some(ty) => single(&case.name, self.placeholder(ty)),
+                    None => Value::from(case.name.as_str()),
+                },
+                None => Value::Null,
+            },
+            TypeDefKind::Result(result) => single(
+                "ok",
+                result
+                    .ok
+                    .map(|ty| self.placeholder(ty))
+                    .unwrap_or(Value::Null),
+            ),
+            TypeDefKind::Type(ty) => self.placeholder(*ty),
+            TypeDefKind::Option(_)
+            | TypeDefKind::Resource
+            | TypeDefKind::Handle(_)
+            | TypeDefKind::Future(_)
+            | TypeDefKind::Stream(_)
+            | TypeDefKind::Unknown => Value::Null,
+        }
+    }
+
+    fn check_value(&self, ty: Type, value: &Value, path: &str) -> Result<()> {
+        let mismatch = || {
+            anyhow!(
+                "{path}: expected {}, found {}",
+                self.type_name(ty),
+                describe(value)
+            )
+        };
+        let integer = |min: i128, max: i128| {
+            let number = value
+                .as_u64()
+                .map(i128::from)
+                .or_else(|| value.as_i64().map(i128::from));
+            match number {
+                Some(number) if (min..=max).contains(&number) => Ok(()),
+                _ => Err(mismatch()),
+            }
+        };
+        let id = match ty {
+            Type::Bool => return value.is_boolean().then_some(()).ok_or_else(mismatch),
+            Type::U8 => return integer(0, u8::MAX.into()),
+            Type::U16 => return integer(0, u16::MAX.into()),
+            Type::U32 => return integer(0, u32::MAX.into()),
+            Type::U64 => return integer(0, u64::MAX.into()),
+            Type::S8 => return integer(i8::MIN.into(), i8::MAX.into()),
+            Type::S16 => return integer(i16::MIN.into(), i16::MAX.into()),
+            Type::S32 => return integer(i32::MIN.into(), i32::MAX.into()),
+            Type::S64 => return integer(i64::MIN.into(), i64::MAX.into()),
+            Type::F32 | Type::F64 => return value.is_number().then_some(()).ok_or_else(mismatch),
+            Type::Char => {
+                let single_char = value.as_str().is_some_and(|s| s.chars().count() == 1);
+                return single_char.then_some(()).ok_or_else(mismatch);
+            }
+            Type::String => return value.is_string().then_some(()).ok_or_else(mismatch),
+            Type::ErrorContext => bail!("{path}: `error-context` values cannot be mocked"),
+            Type::Id(id) => id,
+        };
+
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Record(record) => {
+                let fields = value.as_object().ok_or_else(mismatch)?;
+                for field in &record.fields {
+                    let value = fields
+                        .get(&field.name)
+                        .ok_or_else(|| anyhow!("{path}: missing field `{}`", field.name))?;
+                    self.check_value(field.ty, value, &format!("{path}.{}", field.name))?;
+                }
+                if let Some(extra) = fields
+                    .keys()
+                    .find(|key| !record.fields.iter().any(|field| field.name == **key))
+                {
+                    bail!("{path}: unknown field `{extra}`");
+                }
+                Ok(())
+            }
+            TypeDefKind::Tuple(tuple) => {
+                let items = value
+                    .as_array()
+                    .filter(|items| items.len() == tuple.types.len())
+                    .ok_or_else(mismatch)?;
+                self.check_items(tuple.types.iter().copied().zip(items), path)
+            }
+            TypeDefKind::List(ty) => {
+                let items = value.as_array().ok_or_else(mismatch)?;
+                self.check_items(items.iter().map(|item| (*ty, item)), path)
+            }
+            TypeDefKind::FixedSizeList(ty, len) => {
+                let items = value
+                    .as_array()
+                    .filter(|items| items.len() == *len as usize)
+                    .ok_or_else(mismatch)?;
+                self.check_items(items.iter().map(|item| (*ty, item)), path)
+            }
+            TypeDefKind::Flags(flags) => {
+                let items = value.as_array().ok_or_else(mismatch)?;
+                for item in items {
+                    let known = item
+                        .as_str()
+                        .is_some_and(|name| flags.flags.iter().any(|flag| flag.name == name));
+                    if !known {
+                        bail!("{path}: {item} is not a flag of {}", self.type_name(ty));
+                    }
+                }
+                Ok(())
+            }
+            TypeDefKind::Enum(enum_) => {
+                let known = value
+                    .as_str()
+                    .is_some_and(|name| enum_.cases.iter().any(|case| case.name == name));
+                known.then_some(()).ok_or_else(mismatch)
+            }
+            TypeDefKind::Variant(variant) => {
+                let (name, payload) = case_of(value).ok_or_else(mismatch)?;
+                let case = variant
+                    .cases
+                    .iter()
+                    .find(|case| case.name == name)
+                    .ok_or_else(|| {
+                        anyhow!("{path}: `{name}` is not a case of {}", self.type_name(ty))
+                    })?;
+                self.check_payload(case.ty, payload, &format!("{path}.{name}"))
+            }
+            TypeDefKind::Option(inner) => match value {
+                Value::Null => Ok(()),
+                value => self.check_value(*inner, value, path),
+            },
+            TypeDefKind::Result(result) => match case_of(value) {
+                Some(("ok", payload)) => {
+                    self.check_payload(result.ok, payload, &format!("{path}.ok"))
+                }
+                Some(("err", payload)) => {
+                    self.check_payload(result.err, payload, &format!("{path}.err"))
+                }
+                _ => Err(mismatch()),
+            },
+            TypeDefKind::Type(inner) => self.check_value(*inner, value, path),
+            TypeDefKind::Resource
+            | TypeDefKind::Handle(_)
+            | TypeDefKind::Future(_)
+            | TypeDefKind::Stream(_)
+            | TypeDefKind::Unknown => {
+                bail!("{path}: `{}` values cannot be mocked", self.type_name(ty))
+            }
+        }
+    }
+
+    fn check_items<'v>(
+        &self,
+        items: impl Iterator<Item = (Type, &'v Value)>,
+        path: &str,
+    ) -> Result<()> {
+        items.enumerate().try_for_each(|(index, (ty, item))| {
+            self.check_value(ty, item, &format!("{path}[{index}]"))
+        })
+    }
+
+    fn check_payload(&self, ty: Option<Type>, payload: &Value, path: &str) -> Result<()> {
+        match ty {
+            Some(ty) => self.check_value(ty, payload, path),
+            None if payload.is_null() => Ok(()),
+            None => bail!("{path}: expected no payload, found {}", describe(payload)),
+        }
+    }
+}
+
+/// Where the mock for `import` lives under `dir`.
+pub fn mock_path(dir: &Path, import: &ResolvedImport) -> PathBuf {
+    let (namespace, package) = import
+        .package
+        .split_once(':')
+        .unwrap_or(("", &import.package));
+    dir.join(namespace)
+        .join(package)
+        .join(format!("{}.yaml", import.interface))
+}
+
+/// Renders a mock as YAML with a short header pointing back at its source.
+pub fn to_yaml(mock: &InterfaceMock) -> Result<String> {
+    let body = serde_yaml_bw::to_string(mock).context("failed to serialise mock")?;
+    Ok(format!(
+        "# Mock for {}, generated by `packc imports mocks`.\n# Fill in `returns`; values are checked against the WIT types when loaded.\n{body}",
+        mock.interface
+    ))
+}
+
+/// Loads and checks the mock of every import that has one under `dir`.
+/// Imports without a mock file are skipped; mocks of interfaces packc
+/// provides itself are only checked against the interface they target.
+/// `wit` must hold the packages of every other import.
+pub fn load_mocks(
+    dir: &Path,
+    wit: &WitLibrary,
+    imports: &[ResolvedImport],
+) -> Result<Vec<(ResolvedImport, InterfaceMock)>> {
+    let mut mocks = Vec::new();
+    for import in unique(imports) {
+        let path = mock_path(dir, import);
+        if !path.exists() {
+            continue;
+        }
+        let mock = read_mock(&path)?;
+        if deterministic::is_builtin(import) {
+            check_target(import, &mock)?;
+        } else {
+            wit.check(import, &mock).with_context(|| {
+                format!("mock {} does not match the WIT definition", path.display())
+            })?;
+        }
+        mocks.push((import.clone(), mock));
+    }
+    Ok(mocks)
+}
+
+/// Checks that `mock` was written for the interface version `import` pins.
+pub fn check_target(import: &ResolvedImport, mock: &InterfaceMock) -> Result<()> {
+    let expected = import.to_string();
+    if mock.interface != expected {
+        bail!(
+            "mock targets `{}` but the pack imports `{expected}`; regenerate it with `packc imports mocks`",
+            mock.interface
+        );
+    }
+    Ok(())
+}
+
+pub fn read_mock(path: &Path) -> Result<InterfaceMock> {
+    let source =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_yaml_bw::from_str(&source)
+        .with_context(|| format!("{} is not a valid mock", path.display()))
+}
+
+/// Imports with distinct interfaces, in order; several `imports_required`
+/// entries may resolve to the same interface.
+pub fn unique(imports: &[ResolvedImport]) -> Vec<&ResolvedImport> {
+    let mut seen = BTreeSet::new();
+    imports
+        .iter()
+        .filter(|import| seen.insert(import.to_string()))
+        .collect()
+}
+
+fn package_id(import: &ResolvedImport) -> String {
+    format!("{}@{}", import.package, import.version)
+}
+
+fn declared_package(source: &str) -> Option<&str> {
+    source.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("package ")
+            .map(|rest| rest.trim().trim_end_matches(';').trim())
+    })
+}
+
+fn callable(interface: &Interface) -> impl Iterator<Item = &Function> {
+    interface.functions.values().filter(|function| {
+        matches!(
+            function.kind,
+            FunctionKind::Freestanding | FunctionKind::AsyncFreestanding
+        )
+    })
+}
+
+fn single(key: &str, value: Value) -> Value {
+    Value::Object(Map::from_iter([(key.to_string(), value)]))
+}
+
+/// Splits a variant-style value (`case` or `{case: payload}`) into its parts.
+fn case_of(value: &Value) -> Option<(&str, &Value)> {
+    match value {
+        Value::String(name) => Some((name, &Value::Null)),
+        Value::Object(map) if map.len() == 1 => map.iter().next().map(|(k, v)| (k.as_str(), v)),
+        _ => None,
+    }
+}
+
+fn describe(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "a list",
+        Value::Object(_) => "a mapping",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIT: &str = r#"
+package acme:host@1.2.0;
+
+interface store {
+  record entry { key: string, size: u32, tags: list<string> }
+  enum level { low, high }
+  variant lookup { hit(entry), miss }
+
+  get: func(key: string) -> result<entry, string>;
+  find: func(key: string) -> option<lookup>;
+  current-level: func() -> level;
+  put: func(key: string, value: string);
+}
+"#;
+
+    fn library() -> (WitLibrary, ResolvedImport) {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("acme-host.wit"), WIT).unwrap();
+        let import = ResolvedImport {
+            import: "acme:host/store@1".into(),
+            package: "acme:host".into(),
+            interface: "store".into(),
+            version: "1.2.0".into(),
+        };
+        let library = WitLibrary::load(dir.path(), std::slice::from_ref(&import)).unwrap();
+        (library, import)
+    }
+
+    #[test]
+    fn skeleton_follows_wit_signatures() {
+        let (library, import) = library();
+        let mock = library.skeleton(&import).unwrap();
+        assert_eq!(mock.interface, "acme:host/store@1.2.0");
+        let get = &mock.functions["get"];
+        assert_eq!(
+            get.signature.as_deref(),
+            Some("func(key: string) -> result<entry, string>")
+        );
+        assert_eq!(
+            get.returns,
+            serde_json::json!({"ok": {"key": "", "size": 0, "tags": []}})
+        );
+        assert_eq!(mock.functions["current-level"].returns, "low");
+        assert!(mock.functions["put"].returns.is_null());
+        library.check(&import, &mock).unwrap();
+
+        let yaml = to_yaml(&mock).unwrap();
+        let parsed: InterfaceMock = serde_yaml_bw::from_str(&yaml).unwrap();
+        assert_eq!(parsed, mock);
+    }
+
+    #[test]
+    fn check_reports_type_mismatches() {
+        let (library, import) = library();
+        let err = |function: &str, returns: Value| {
+            let mut mock = library.skeleton(&import).unwrap();
+            mock.functions.get_mut(function).unwrap().returns = returns;
+            library.check(&import, &mock).unwrap_err().to_string()
+        };
+
+        let message = err(
+            "get",
+            serde_json::json!({"ok": {"key": "k", "size": -1, "tags": []}}),
+        );
+        assert_eq!(message, "get.returns.ok.size: expected u32, found a number");
+        let message = err("get", serde_json::json!({"ok": {"key": "k", "size": 1}}));
+        assert!(message.contains("missing field `tags`"), "{message}");
+        let message = err("find", serde_json::json!({"hit": "nope"}));
+        assert!(
+            message.contains("find.returns.hit: expected entry"),
+            "{message}"
+        );
+        assert!(err("find", serde_json::json!("gone")).contains("not a case"));
+        assert!(err("current-level", serde_json::json!("medium")).contains("expected level"));
+        assert!(err("put", serde_json::json!(true)).contains("has no result"));
+
+        let mut mock = library.skeleton(&import).unwrap();
+        mock.functions.insert(
+            "delete".into(),
+            FunctionMock {
+                signature: None,
+                returns: Value::Null,
+            },
+        );
+        let message = library.check(&import, &mock).unwrap_err().to_string();
+        assert!(message.contains("`delete` is not a function"), "{message}");
+    }
+}
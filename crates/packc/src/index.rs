@@ -0,0 +1,289 @@
+use anyhow::{Context, Result, anyhow};
+use greentic_pack::builder::PackManifest;
+use greentic_pack::{SigningPolicy, open_pack};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use tracing::{debug, info};
+use walkdir::WalkDir;
+
+use crate::advisory::Advisory;
+use crate::registry::{self, Yank};
+
+pub const INDEX_FORMAT: &str = "greentic-pack-index-v1";
+
+/// Inverted search index over a directory of built `.gtpack` archives.
+///
+/// `terms` maps each lowercase token to the sorted list of pack keys
+/// (`<pack_id>@<version>`) whose id, name, description, flows, tags,
+/// categories, or capabilities contain it. `advisories` are authored with
+/// `packc advisory add` and kept when the index is rebuilt.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SearchIndex {
+    pub format: String,
+    pub packs: Vec<IndexedPack>,
+    pub terms: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub advisories: Vec<Advisory>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndexedPack {
+    pub key: String,
+    pub pack_id: String,
+    pub version: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub flows: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Archive path relative to the indexed directory.
+    pub source: String,
+    /// `sha256:<hex>` digest of the archive bytes, used for incremental updates.
+    pub digest: String,
+    /// Set when the archive sits in a registry that yanked it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yanked: Option<Yank>,
+}
+
+/// Restricts which packs end up in the index.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexFilter {
+    /// Packs must carry every listed tag.
+    pub tags: Vec<String>,
+    /// Packs must belong to at least one listed category (when non-empty).
+    pub categories: Vec<String>,
+}
+
+impl IndexFilter {
+    pub fn matches(&self, pack: &IndexedPack) -> bool {
+        self.matches_labels(&pack.tags, &pack.categories)
+    }
+
+    /// Whether a pack with these (normalised) tags and categories passes.
+    pub fn matches_labels(&self, tags: &[String], categories: &[String]) -> bool {
+        let tags_ok = self.tags.iter().all(|tag| tags.contains(tag));
+        let categories_ok = self.categories.is_empty()
+            || self
+                .categories
+                .iter()
+                .any(|category| categories.contains(category));
+        tags_ok && categories_ok
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+pub struct IndexStats {
+    pub indexed: usize,
+    pub reused: usize,
+    pub removed: usize,
+    pub filtered: usize,
+}
+
+/// Indexes every `.gtpack` under `dir`, reusing entries from `previous` whose
+/// archive digest has not changed. Packs rejected by `filter` are left out;
+/// the advisories of `previous` are kept.
+pub fn build_index(
+    dir: &Path,
+    previous: Option<&SearchIndex>,
+    filter: &IndexFilter,
+) -> Result<(SearchIndex, IndexStats)> {
+    let mut cached: BTreeMap<&str, &IndexedPack> = previous
+        .map(|index| {
+            index
+                .packs
+                .iter()
+                .map(|pack| (pack.source.as_str(), pack))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut stats = IndexStats::default();
+    let mut packs = Vec::new();
+    let mut archives: Vec<_> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "gtpack"))
+        .collect();
+    archives.sort();
+
+    for path in archives {
+        let source = path
+            .strip_prefix(dir)
+            .unwrap_or(&path)
+            .components()
+            .map(|comp| comp.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let bytes =
+            fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let digest = format!("sha256:{}", hex::encode(Sha256::digest(&bytes)));
+        // Yanking only writes a marker, so it is re-read even for reused entries.
+        let yanked = registry::read_yank(&path)?;
+
+        if let Some(existing) = cached.remove(source.as_str())
+            && existing.digest == digest
+        {
+            debug!(source = %source, "index entry unchanged");
+            stats.reused += 1;
+            packs.push(IndexedPack {
+                yanked,
+                ..existing.clone()
+            });
+            continue;
+        }
+
+        let load = open_pack(&path, SigningPolicy::DevOk)
+            .map_err(|err| anyhow!("failed to open {}: {}", path.display(), err.message))?;
+        info!(source = %source, pack = %load.manifest.meta.pack_id, "indexed pack");
+        stats.indexed += 1;
+        packs.push(IndexedPack {
+            yanked,
+            ..index_entry(&load.manifest, source, digest)
+        });
+    }
+    stats.removed = cached.len();
+    let total = packs.len();
+    packs.retain(|pack| filter.matches(pack));
+    stats.filtered = total - packs.len();
+
+    packs.sort_by(|a, b| a.key.cmp(&b.key).then_with(|| a.source.cmp(&b.source)));
+    let terms = invert(&packs);
+    Ok((
+        SearchIndex {
+            format: INDEX_FORMAT.to_string(),
+            packs,
+            terms,
+            advisories: previous
+                .map(|index| index.advisories.clone())
+                .unwrap_or_default(),
+        },
+        stats,
+    ))
+}
+
+/// Loads an existing index; returns `None` when the file is absent.
+pub fn load_index(path: &Path) -> Result<Option<SearchIndex>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let index: SearchIndex = serde_json::from_slice(&bytes)
+        .with_context(|| format!("{} is not a valid search index", path.display()))?;
+    if index.format != INDEX_FORMAT {
+        anyhow::bail!(
+            "{} uses unsupported index format {}",
+            path.display(),
+            index.format
+        );
+    }
+    Ok(Some(index))
+}
+
+fn index_entry(manifest: &PackManifest, source: String, digest: String) -> IndexedPack {
+    let meta = &manifest.meta;
+    let mut capabilities = BTreeSet::new();
+    if let Some(repo) = &meta.repo {
+        capabilities.insert(format!("repo:{}", repo.kind));
+        if let Ok(serde_json::Value::Object(map)) = serde_json::to_value(&repo.capabilities) {
+            for (key, values) in map {
+                for value in values.as_array().into_iter().flatten() {
+                    if let Some(value) = value.as_str() {
+                        capabilities.insert(format!("{key}:{value}"));
+                    }
+                }
+            }
+        }
+    }
+    for binding in &meta.interfaces {
+        capabilities.insert(format!("{}/{}", binding.package, binding.world));
+    }
+
+    IndexedPack {
+        key: format!("{}@{}", meta.pack_id, meta.version),
+        pack_id: meta.pack_id.clone(),
+        version: meta.version.to_string(),
+        name: meta.name.clone(),
+        description: meta.description.clone(),
+        flows: manifest.flows.iter().map(|flow| flow.id.clone()).collect(),
+        tags: meta.tags.clone(),
+        categories: meta.categories.clone(),
+        capabilities: capabilities.into_iter().collect(),
+        source,
+        digest,
+        yanked: None,
+    }
+}
+
+fn invert(packs: &[IndexedPack]) -> BTreeMap<String, Vec<String>> {
+    let mut terms: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for pack in packs {
+        let fields = [pack.pack_id.as_str(), pack.name.as_str()]
+            .into_iter()
+            .chain(pack.description.as_deref())
+            .chain(pack.flows.iter().map(String::as_str))
+            .chain(pack.tags.iter().map(String::as_str))
+            .chain(pack.categories.iter().map(String::as_str))
+            .chain(pack.capabilities.iter().map(String::as_str));
+        for field in fields {
+            for token in tokenize(field) {
+                terms.entry(token).or_default().insert(pack.key.clone());
+            }
+        }
+    }
+    terms
+        .into_iter()
+        .map(|(term, keys)| (term, keys.into_iter().collect()))
+        .collect()
+}
+
+/// Splits text into lowercase alphanumeric tokens of at least two characters.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|token| token.chars().count() >= 2)
+        .map(str::to_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(key: &str, description: &str) -> IndexedPack {
+        IndexedPack {
+            key: key.into(),
+            pack_id: key.split('@').next().unwrap().into(),
+            version: "0.1.0".into(),
+            name: "Demo".into(),
+            description: Some(description.into()),
+            flows: vec!["weather_bot".into()],
+            tags: Vec::new(),
+            categories: Vec::new(),
+            capabilities: vec!["search:semantic".into()],
+            source: format!("{key}.gtpack"),
+            digest: "sha256:00".into(),
+            yanked: None,
+        }
+    }
+
+    #[test]
+    fn inverted_terms_are_lowercase_and_deduplicated() {
+        let packs = vec![
+            pack("a.pack@0.1.0", "Weather forecasts"),
+            pack("b.pack@0.1.0", "Billing and weather"),
+        ];
+        let terms = invert(&packs);
+        assert_eq!(terms["weather"], vec!["a.pack@0.1.0", "b.pack@0.1.0"]);
+        assert_eq!(terms["billing"], vec!["b.pack@0.1.0"]);
+        assert_eq!(terms["semantic"].len(), 2);
+        assert!(!terms.contains_key("a"), "single characters are skipped");
+    }
+}
@@ -0,0 +1,170 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use greentic_pack::{SigningPolicy, VerifyFinding, open_pack};
+use serde::Serialize;
+
+use crate::manifest::{self, PackManifest};
+
+/// Archive prefix of template assets in a `.gtpack`.
+const ASSETS_PREFIX: &str = "assets/";
+
+/// Summary of a built pack, read from either `dist/manifest.cbor` or a
+/// `.gtpack` archive.
+#[derive(Debug, Clone, Serialize)]
+pub struct Inspection {
+    pub artifact: ArtifactKind,
+    pub pack_id: String,
+    pub version: String,
+    pub flows: Vec<InspectedFlow>,
+    pub templates: Vec<String>,
+    pub imports: Vec<String>,
+    pub signature: SignatureStatus,
+    /// Verification findings; only archives are verified.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<VerifyFinding>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    Manifest,
+    Gtpack,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InspectedFlow {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub flow_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// The archive signature was checked against the signing policy.
+    Verified {
+        alg: String,
+        signed_at_utc: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        key_fingerprint: Option<String>,
+    },
+    /// The pack manifest next to `dist/` carries signatures; `packc verify`
+    /// checks them against a public key.
+    Signed {
+        algs: Vec<String>,
+    },
+    Unsigned,
+}
+
+/// Reads `path` as a `.gtpack` archive or a CBOR pack manifest, telling them
+/// apart by content rather than extension.
+pub fn inspect(path: &Path, policy: SigningPolicy) -> Result<Inspection> {
+    let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    if bytes.starts_with(b"PK\x03\x04") {
+        inspect_gtpack(path, policy)
+    } else {
+        let manifest: PackManifest = serde_cbor::from_slice(&bytes).with_context(|| {
+            format!(
+                "{} is neither a .gtpack nor a pack manifest",
+                path.display()
+            )
+        })?;
+        inspect_manifest(path, manifest)
+    }
+}
+
+fn inspect_manifest(path: &Path, manifest: PackManifest) -> Result<Inspection> {
+    // `dist/manifest.cbor` is unsigned; signatures live on the pack manifest
+    // of the pack directory that built it.
+    let pack_dir = path
+        .parent()
+        .and_then(Path::parent)
+        .filter(|dir| manifest::find_manifest_path(dir).is_some());
+    let signatures = match pack_dir {
+        Some(dir) => manifest::read_signatures(dir)?,
+        None => Vec::new(),
+    };
+    let signature = if signatures.is_empty() {
+        SignatureStatus::Unsigned
+    } else {
+        SignatureStatus::Signed {
+            algs: signatures
+                .into_iter()
+                .map(|signature| signature.alg)
+                .collect(),
+        }
+    };
+
+    Ok(Inspection {
+        artifact: ArtifactKind::Manifest,
+        pack_id: manifest.pack_id,
+        version: manifest.version,
+        flows: manifest
+            .flows
+            .into_iter()
+            .map(|flow| InspectedFlow {
+                id: flow.id,
+                flow_type: flow.flow_type,
+                start: flow.start,
+            })
+            .collect(),
+        templates: manifest
+            .templates
+            .into_iter()
+            .map(|template| template.logical_path)
+            .collect(),
+        imports: manifest
+            .imports_required
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        signature,
+        findings: Vec::new(),
+    })
+}
+
+fn inspect_gtpack(path: &Path, policy: SigningPolicy) -> Result<Inspection> {
+    let load = open_pack(path, policy).map_err(|err| anyhow!(err.message))?;
+    let signature = match load.signature() {
+        Some(info) => SignatureStatus::Verified {
+            alg: info.alg,
+            signed_at_utc: info.signed_at_utc,
+            key_fingerprint: info.key_fingerprint,
+        },
+        None => SignatureStatus::Unsigned,
+    };
+    let templates = load
+        .entries()
+        .into_iter()
+        .filter_map(|entry| entry.path.strip_prefix(ASSETS_PREFIX).map(str::to_string))
+        .collect();
+    let meta = &load.manifest.meta;
+
+    Ok(Inspection {
+        artifact: ArtifactKind::Gtpack,
+        pack_id: meta.pack_id.clone(),
+        version: meta.version.to_string(),
+        flows: load
+            .manifest
+            .flows
+            .iter()
+            .map(|flow| InspectedFlow {
+                id: flow.id.clone(),
+                flow_type: flow.kind.clone(),
+                start: Some(flow.entry.clone()),
+            })
+            .collect(),
+        templates,
+        imports: meta
+            .imports
+            .iter()
+            .map(|import| import.pack_id.clone())
+            .collect(),
+        signature,
+        findings: load.report.findings.clone(),
+    })
+}
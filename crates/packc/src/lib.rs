@@ -1,17 +1,60 @@
 #![forbid(unsafe_code)]
 
+pub mod advisory;
+pub mod analysis;
+pub mod assets;
+pub mod audit;
 pub mod build;
+pub mod build_cache;
+pub mod build_lock;
+pub mod cache;
 pub mod cli;
+pub mod closure;
+pub mod compat;
+pub mod compat_matrix;
+pub mod debug;
+pub mod dedup;
+pub mod diff;
+pub mod digest;
 pub mod embed;
+pub mod flow_graph;
 pub mod flows;
+pub mod fuzz;
+pub mod hermetic;
+pub mod http;
+pub mod imports;
+pub mod index;
+pub mod inspect;
+pub mod lockfile;
+pub mod lsp;
 pub mod manifest;
 pub mod mcp;
+pub mod media;
 pub mod new;
+pub mod permissions;
+pub mod profile;
+pub mod readme;
+pub mod rebuild;
+pub mod registry;
+pub mod reload;
+pub mod remote;
+pub mod run;
 pub mod sbom;
+pub mod schema;
+pub mod serve_ui;
 pub mod signing;
+pub mod taxonomy;
 pub mod telemetry;
 pub mod templates;
+pub mod tools;
+pub mod usage;
+pub mod wasi;
+pub mod workspace;
 
 pub use cli::BuildArgs;
+pub use digest::DigestAlgorithm;
 pub use manifest::PackSignature;
-pub use signing::{VerificationError, VerifyOptions, sign_pack_dir, verify_pack_dir};
+pub use signing::{
+    TrustStore, VerificationError, VerifyOptions, append_signatures, sign_pack_dir,
+    sign_pack_dir_with, sign_pack_dir_with_keys, verify_pack_dir,
+};
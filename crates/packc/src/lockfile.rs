@@ -0,0 +1,217 @@
+//! `pack.lock`: the inputs a pack was last built from.
+//!
+//! `packc build` writes the lock next to `pack.yaml`. It records the
+//! interface versions the imports were resolved to, the BLAKE3 digests of the
+//! components composed into the pack (MCP adapters and the router components
+//! they wrap), and the digest of every template, so a build on another
+//! machine can be checked against the same inputs.
+//!
+//! Later builds prefer the locked interface versions, so a newer interface
+//! catalog does not silently move a pack onto interface versions its hosts
+//! may not provide yet. An entry whose requirement changed, or whose locked
+//! version the catalog no longer knows, is resolved again. `packc build
+//! --locked` fails instead of updating the lock, and `packc verify --locked`
+//! checks the current inputs against it without building.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use greentic_pack::atomic::{self, Durability};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::imports::{self, InterfaceCatalog, ResolvedImport};
+use crate::manifest::{SpecBundle, normalize_protocol};
+use crate::mcp;
+use crate::templates::TemplateAsset;
+
+pub const LOCK_FILE: &str = "pack.lock";
+
+/// Lock format written by this packc release.
+pub const LOCK_FORMAT: u32 = 2;
+
+/// Oldest lock format this packc still reads; it had no component or
+/// template entries.
+const OLDEST_LOCK_FORMAT: u32 = 1;
+
+const HEADER: &str = "# @generated by packc build -- do not edit by hand.\n\n";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackLock {
+    pub format: u32,
+    /// Resolved WIT-style `imports_required` entries, in `pack.yaml` order.
+    #[serde(default, rename = "import")]
+    pub imports: Vec<ResolvedImport>,
+    /// Components composed into the pack, in `pack.yaml` order.
+    #[serde(default, rename = "component")]
+    pub components: Vec<LockedComponent>,
+    /// Templates, by logical path.
+    #[serde(default, rename = "template")]
+    pub templates: Vec<LockedTemplate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedComponent {
+    /// `<mcp id>/adapter` or `<mcp id>/router`.
+    pub name: String,
+    /// Where the component came from: the router's `router_ref`, or the MCP
+    /// protocol of the adapter.
+    pub source: String,
+    pub blake3: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedTemplate {
+    pub path: String,
+    pub blake3: String,
+}
+
+impl PackLock {
+    pub fn new(imports: Vec<ResolvedImport>) -> Self {
+        Self {
+            format: LOCK_FORMAT,
+            imports,
+            components: Vec::new(),
+            templates: Vec::new(),
+        }
+    }
+
+    /// The lock for the current inputs of the pack in `pack_dir`, resolving
+    /// imports against `catalog` while keeping the versions `locked` pins.
+    pub fn current(
+        pack_dir: &Path,
+        spec_bundle: &SpecBundle,
+        templates: &[TemplateAsset],
+        catalog: &InterfaceCatalog,
+        locked: Option<&PackLock>,
+    ) -> Result<Self> {
+        let imports = imports::resolve_locked(catalog, &spec_bundle.spec.imports_required, locked)?;
+        let mut templates: Vec<LockedTemplate> = templates
+            .iter()
+            .map(|template| LockedTemplate {
+                path: template.logical_path.clone(),
+                blake3: blake3::hash(&template.bytes).to_hex().to_string(),
+            })
+            .collect();
+        templates.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Self {
+            format: LOCK_FORMAT,
+            imports,
+            components: component_digests(pack_dir, spec_bundle)?,
+            templates,
+        })
+    }
+
+    /// Loads `<pack_dir>/pack.lock`, or `None` when the pack has none.
+    pub fn load(pack_dir: &Path) -> Result<Option<Self>> {
+        let path = pack_dir.join(LOCK_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let lock: Self =
+            toml::from_str(&text).with_context(|| format!("{} is not valid", path.display()))?;
+        if !(OLDEST_LOCK_FORMAT..=LOCK_FORMAT).contains(&lock.format) {
+            bail!(
+                "{} has format {}; this packc understands formats {OLDEST_LOCK_FORMAT} to {LOCK_FORMAT}",
+                path.display(),
+                lock.format
+            );
+        }
+        Ok(Some(lock))
+    }
+
+    pub fn write(&self, pack_dir: &Path, durability: Durability) -> Result<()> {
+        let body = toml::to_string_pretty(self).context("failed to serialise pack.lock")?;
+        atomic::write(
+            &pack_dir.join(LOCK_FILE),
+            format!("{HEADER}{body}").as_bytes(),
+            durability,
+        )
+    }
+
+    /// Locked version of each WIT package.
+    pub fn packages(&self) -> BTreeMap<&str, Version> {
+        self.imports
+            .iter()
+            .filter_map(|import| {
+                Some((
+                    import.package.as_str(),
+                    Version::parse(&import.version).ok()?,
+                ))
+            })
+            .collect()
+    }
+
+    /// How `updated` differs from this lock, one line per entry.
+    pub fn changes(&self, updated: &PackLock) -> Vec<String> {
+        let (old, new) = (self.entries(), updated.entries());
+        let mut changes = Vec::new();
+        for (entry, value) in &new {
+            match old.get(entry) {
+                None => changes.push(format!("+ {entry} ({value})")),
+                Some(locked) if locked != value => {
+                    changes.push(format!("~ {entry} ({locked} -> {value})"))
+                }
+                Some(_) => {}
+            }
+        }
+        for (entry, value) in &old {
+            if !new.contains_key(entry) {
+                changes.push(format!("- {entry} ({value})"));
+            }
+        }
+        changes
+    }
+
+    /// Every locked entry with the value a change would show: the version of
+    /// an import, or the abbreviated digest of a component or template.
+    fn entries(&self) -> BTreeMap<String, String> {
+        let short = |digest: &str| digest.chars().take(12).collect::<String>();
+        let imports = self
+            .imports
+            .iter()
+            .map(|import| (import.import.clone(), import.version.clone()));
+        let components = self.components.iter().map(|component| {
+            (
+                format!("component {}", component.name),
+                short(&component.blake3),
+            )
+        });
+        let templates = self.templates.iter().map(|template| {
+            (
+                format!("template {}", template.path),
+                short(&template.blake3),
+            )
+        });
+        imports.chain(components).chain(templates).collect()
+    }
+}
+
+/// Digests of the router and adapter of each MCP component.
+fn component_digests(pack_dir: &Path, spec_bundle: &SpecBundle) -> Result<Vec<LockedComponent>> {
+    let digest = |path: &Path| -> Result<String> {
+        let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        Ok(blake3::hash(&bytes).to_hex().to_string())
+    };
+    let mut components = Vec::new();
+    for entry in &spec_bundle.spec.mcp_components {
+        let protocol = normalize_protocol(&entry.protocol);
+        let router = mcp::resolve_router_path(pack_dir, &entry.router_ref)?;
+        let adapter = mcp::resolve_adapter_template(&protocol, &entry.adapter_template)?;
+        components.push(LockedComponent {
+            name: format!("{}/router", entry.id),
+            source: entry.router_ref.clone(),
+            blake3: digest(&router)?,
+        });
+        components.push(LockedComponent {
+            name: format!("{}/adapter", entry.id),
+            source: format!("mcp-adapter@{protocol}"),
+            blake3: digest(&adapter)?,
+        });
+    }
+    Ok(components)
+}
@@ -0,0 +1,583 @@
+//! Editor support for pack sources, served by `packc lsp`.
+//!
+//! Two kinds of documents are understood: flows (`*.ygtc`) and `pack.yaml`.
+//! Flows are checked with the same greentic-flow parser `packc build` uses,
+//! plus the lint passes that look at a single flow; `pack.yaml` is parsed and
+//! validated as a [`PackSpec`], and once saved the whole pack is linted.
+//! Everything here works on the editor's text, so documents are checked as
+//! they are typed rather than as they are on disk.
+//!
+//! Locations in greentic-flow errors are JSON pointers or node ids rather than
+//! positions, so they are mapped back onto the text by finding the keys they
+//! name. The mapping is line-based and assumes block-style YAML, which is what
+//! flows are written in.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use greentic_flow::error::FlowError;
+use greentic_flow::flow_bundle::load_and_validate_bundle_with_ir;
+use greentic_pack::metrics::metric_emissions;
+use greentic_pack::node_docs::{DOC_KEY, strip_node_docs};
+use greentic_pack::secrets::secret_refs;
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, NumberOrString, Position,
+    Range,
+};
+use serde_yaml_bw::Value as YamlValue;
+
+use crate::cli::lint::rules::LintConfig;
+use crate::cli::lint::{Severity, lint_pack};
+use crate::digest::DigestAlgorithm;
+use crate::flow_graph::{FlowGraph, block_lines, node_keys, route_line};
+use crate::imports::InterfaceCatalog;
+use crate::manifest::{self, PackSpec};
+use crate::templates;
+
+/// `source` of every diagnostic, shown by editors next to the message.
+pub const DIAGNOSTIC_SOURCE: &str = "packc";
+
+/// pack.yaml sections whose entries or keys are flow ids.
+const FLOW_ID_SECTIONS: &[&str] = &[
+    "entry_flows",
+    "lifecycle",
+    "limits",
+    "dispatch",
+    "qos",
+    "quotas",
+    "schedule",
+    "classification",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Flow,
+    PackSpec,
+}
+
+impl DocumentKind {
+    pub fn of(path: &Path) -> Option<Self> {
+        if path.file_name().is_some_and(|name| name == "pack.yaml") {
+            Some(DocumentKind::PackSpec)
+        } else if path.extension().is_some_and(|ext| ext == "ygtc") {
+            Some(DocumentKind::Flow)
+        } else {
+            None
+        }
+    }
+}
+
+/// The nearest directory at or above `path` that holds a `pack.yaml`.
+pub fn pack_dir_of(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .skip(1)
+        .find(|dir| dir.join("pack.yaml").is_file())
+        .map(Path::to_path_buf)
+}
+
+/// The local path of a `file://` URI.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%'
+            && let Some(byte) = encoded
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            index += 3;
+            continue;
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+    let path = String::from_utf8(decoded).ok()?;
+    // `file:///C:/pack` on Windows.
+    let path = match path.strip_prefix('/') {
+        Some(rest) if rest.get(1..2) == Some(":") => rest.to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+/// Problems with the document at `path` whose current text is `text`.
+pub fn diagnostics(path: &Path, text: &str) -> Vec<Diagnostic> {
+    let pack_dir = pack_dir_of(path);
+    match DocumentKind::of(path) {
+        Some(DocumentKind::Flow) => {
+            let spec = pack_dir
+                .as_deref()
+                .and_then(|dir| manifest::load_spec(dir).ok())
+                .map(|bundle| bundle.spec);
+            let config = pack_dir
+                .as_deref()
+                .and_then(|dir| LintConfig::load(dir).ok())
+                .unwrap_or_default();
+            flow_diagnostics(text, spec.as_ref(), &config)
+        }
+        Some(DocumentKind::PackSpec) => {
+            let saved = fs::read_to_string(path).is_ok_and(|disk| disk == text);
+            let pack_dir = path.parent().filter(|_| saved);
+            spec_diagnostics(text, pack_dir)
+        }
+        None => Vec::new(),
+    }
+}
+
+/// Problems with a flow; `spec` is the pack's, when it has a valid one, and
+/// `config` sets the levels of the flow graph rules.
+pub fn flow_diagnostics(
+    text: &str,
+    spec: Option<&PackSpec>,
+    config: &LintConfig,
+) -> Vec<Diagnostic> {
+    let source: YamlValue = match serde_yaml_bw::from_str(text) {
+        Ok(source) => source,
+        Err(err) => {
+            let range = err
+                .location()
+                .map(|location| point(location.line(), location.column()))
+                .unwrap_or_default();
+            return vec![error(range, err.to_string())];
+        }
+    };
+
+    let mut problems = Vec::new();
+    if let Some(nodes) = source.get("nodes").and_then(YamlValue::as_mapping) {
+        for (node, body) in nodes {
+            let node = node.as_str().unwrap_or_default();
+            let doc = body.get(DOC_KEY);
+            if doc.is_some_and(|doc| doc.as_str().is_none_or(|doc| doc.trim().is_empty())) {
+                problems.push(error(
+                    key_range(text, node, DOC_KEY),
+                    format!("`{DOC_KEY}` must be a non-empty string"),
+                ));
+            }
+        }
+    }
+    if !problems.is_empty() {
+        return problems;
+    }
+
+    let stripped = strip_node_docs(text).ok().flatten();
+    let bundle = match load_and_validate_bundle_with_ir(stripped.as_deref().unwrap_or(text), None) {
+        Ok((bundle, _ir)) => bundle,
+        Err(err) => return flow_error_diagnostics(text, &err),
+    };
+
+    if let Ok(graph) = FlowGraph::parse(text) {
+        for finding in graph.check(&bundle.entry) {
+            let Some(severity) = config.level(finding.rule.name()).severity() else {
+                continue;
+            };
+            let range = finding.line.map(|line| point(line, 1)).unwrap_or_default();
+            problems.push(Diagnostic {
+                severity: Some(lsp_severity(severity)),
+                code: Some(NumberOrString::String(finding.rule.name().to_string())),
+                ..error(range, finding.message)
+            });
+        }
+    }
+
+    if let Some(spec) = spec {
+        let flow = source
+            .get("id")
+            .and_then(YamlValue::as_str)
+            .unwrap_or_default();
+        let declared = spec.metrics.clone().unwrap_or_default();
+        for emission in metric_emissions(text).unwrap_or_default() {
+            for message in declared.check_emissions(flow, std::slice::from_ref(&emission)) {
+                problems.push(error(node_range(text, &emission.node), message));
+            }
+        }
+        let declared = spec.secrets.clone().unwrap_or_default();
+        for secret in secret_refs(text).unwrap_or_default() {
+            for message in declared.check_refs(flow, std::slice::from_ref(&secret)) {
+                problems.push(error(node_range(text, &secret.node), message));
+            }
+        }
+    }
+    problems
+}
+
+fn flow_error_diagnostics(text: &str, err: &FlowError) -> Vec<Diagnostic> {
+    match err {
+        FlowError::Yaml { message, location } => {
+            let range = match (location.line, location.col) {
+                (Some(line), col) => point(line, col.unwrap_or(1)),
+                _ => Range::default(),
+            };
+            vec![error(range, message.clone())]
+        }
+        FlowError::Schema { details, .. } => details
+            .iter()
+            .map(|detail| {
+                let range = detail
+                    .location
+                    .json_pointer
+                    .as_deref()
+                    .map(|pointer| pointer_range(text, pointer))
+                    .unwrap_or_default();
+                error(range, detail.message.clone())
+            })
+            .collect(),
+        FlowError::NodeComponentShape { node_id, .. }
+        | FlowError::BadComponentKey { node_id, .. } => {
+            vec![error(node_range(text, node_id), strip_location(err))]
+        }
+        FlowError::MissingNode {
+            target, node_id, ..
+        } => {
+            let range =
+                route_range(text, node_id, target).unwrap_or_else(|| node_range(text, node_id));
+            vec![error(range, strip_location(err))]
+        }
+        FlowError::Internal { .. } => vec![error(Range::default(), strip_location(err))],
+    }
+}
+
+/// Problems with `pack.yaml`. The rest of the pack is linted when
+/// `pack_dir` is given, i.e. when the text is what is saved on disk.
+pub fn spec_diagnostics(text: &str, pack_dir: Option<&Path>) -> Vec<Diagnostic> {
+    let spec: PackSpec = match serde_yaml_bw::from_str(text) {
+        Ok(spec) => spec,
+        Err(err) => {
+            let range = err
+                .location()
+                .map(|location| point(location.line(), location.column()))
+                .unwrap_or_default();
+            return vec![error(range, err.to_string())];
+        }
+    };
+    if let Err(err) = spec.validate() {
+        return vec![error(Range::default(), format!("{err:#}"))];
+    }
+    let Some(pack_dir) = pack_dir else {
+        return Vec::new();
+    };
+    match lint_pack(pack_dir, None, None) {
+        Ok(lint) => lint
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| Diagnostic {
+                severity: Some(lsp_severity(diagnostic.severity)),
+                code: Some(NumberOrString::String(diagnostic.rule.to_string())),
+                ..error(Range::default(), diagnostic.to_string())
+            })
+            .collect(),
+        Err(err) => vec![error(Range::default(), format!("{err:#}"))],
+    }
+}
+
+/// Completion items at `position` in the document at `path`.
+pub fn completions(path: &Path, text: &str, position: Position) -> Vec<CompletionItem> {
+    let Some(pack_dir) = pack_dir_of(path) else {
+        return Vec::new();
+    };
+    let line = text.lines().nth(position.line as usize).unwrap_or_default();
+    let prefix: String = line.chars().take(position.character as usize).collect();
+    let prefix = prefix.trim_start().trim_start_matches("- ").trim_start();
+
+    match DocumentKind::of(path) {
+        Some(DocumentKind::Flow) => {
+            if prefix.starts_with("to:") {
+                return items(
+                    node_keys(text).into_iter().map(|(node, _)| node),
+                    CompletionItemKind::REFERENCE,
+                    "node",
+                );
+            }
+            let Ok(spec) = manifest::load_spec(&pack_dir) else {
+                return Vec::new();
+            };
+            let mut completions = items(
+                spec.spec.imports_required.iter().map(ToString::to_string),
+                CompletionItemKind::MODULE,
+                "import",
+            );
+            let templates = templates::collect_templates(
+                &pack_dir,
+                &spec.spec,
+                DigestAlgorithm::default(),
+                templates::SyntaxCheck::Skip,
+            )
+            .unwrap_or_default();
+            completions.extend(items(
+                templates.into_iter().map(|template| template.logical_path),
+                CompletionItemKind::FILE,
+                "template",
+            ));
+            completions
+        }
+        Some(DocumentKind::PackSpec) => match section_at(text, position.line as usize).as_deref() {
+            Some("imports_required") => {
+                let Ok(catalog) = InterfaceCatalog::load(&pack_dir, None) else {
+                    return Vec::new();
+                };
+                let names = catalog.packages.iter().flat_map(|package| {
+                    package.interfaces.iter().map(move |interface| {
+                        format!("{}/{interface}@{}", package.name, package.version)
+                    })
+                });
+                items(names, CompletionItemKind::MODULE, "interface")
+            }
+            Some(section) if FLOW_ID_SECTIONS.contains(&section) => items(
+                flow_ids(&pack_dir, text),
+                CompletionItemKind::REFERENCE,
+                "flow",
+            ),
+            _ => Vec::new(),
+        },
+        None => Vec::new(),
+    }
+}
+
+/// Where the node named at `position` is defined, for `to:` targets and
+/// other mentions of a node id in a flow.
+pub fn definition(text: &str, position: Position) -> Option<Range> {
+    let line = text.lines().nth(position.line as usize)?;
+    let word = word_at(line, position.character as usize)?;
+    node_keys(text)
+        .into_iter()
+        .find(|(node, _)| *node == word)
+        .map(|(node, line)| key_on_line(text, line, &node))
+}
+
+/// The top-level pack.yaml section `line` belongs to.
+fn section_at(text: &str, line: usize) -> Option<String> {
+    text.lines()
+        .take(line + 1)
+        .filter(|line| line.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_'))
+        .filter_map(|line| line.split_once(':').map(|(key, _)| key.trim().to_string()))
+        .last()
+}
+
+/// Ids of the flows listed in `flow_files`, as far as they can be read.
+fn flow_ids(pack_dir: &Path, spec_text: &str) -> BTreeSet<String> {
+    let Ok(spec) = serde_yaml_bw::from_str::<PackSpec>(spec_text) else {
+        return BTreeSet::new();
+    };
+    spec.flow_files
+        .iter()
+        .filter_map(|file| {
+            let source = fs::read_to_string(pack_dir.join(file)).ok()?;
+            let flow: YamlValue = serde_yaml_bw::from_str(&source).ok()?;
+            flow.get("id")
+                .and_then(YamlValue::as_str)
+                .map(str::to_string)
+        })
+        .collect()
+}
+
+fn items(
+    labels: impl IntoIterator<Item = String>,
+    kind: CompletionItemKind,
+    detail: &str,
+) -> Vec<CompletionItem> {
+    labels
+        .into_iter()
+        .map(|label| CompletionItem {
+            label,
+            kind: Some(kind),
+            detail: Some(detail.to_string()),
+            ..CompletionItem::default()
+        })
+        .collect()
+}
+
+fn word_at(line: &str, character: usize) -> Option<String> {
+    let chars: Vec<char> = line.chars().collect();
+    let is_word = |c: &char| c.is_alphanumeric() || *c == '_' || *c == '-';
+    let mut start = character.min(chars.len());
+    while start > 0 && is_word(&chars[start - 1]) {
+        start -= 1;
+    }
+    let end = chars[start..]
+        .iter()
+        .position(|c| !is_word(c))
+        .map_or(chars.len(), |offset| start + offset);
+    (end > start).then(|| chars[start..end].iter().collect())
+}
+
+fn node_range(text: &str, node: &str) -> Range {
+    node_keys(text)
+        .into_iter()
+        .find(|(key, _)| key == node)
+        .map(|(_, line)| key_on_line(text, line, node))
+        .unwrap_or_default()
+}
+
+/// The range of `key` inside the block of `node`, or of the node itself.
+fn key_range(text: &str, node: &str, key: &str) -> Range {
+    let Some(start) = node_keys(text)
+        .into_iter()
+        .find(|(name, _)| name == node)
+        .map(|(_, line)| line)
+    else {
+        return Range::default();
+    };
+    block_lines(text, start)
+        .find(|(_, line)| line.trim_start().starts_with(&format!("{key}:")))
+        .map(|(number, _)| key_on_line(text, number, key))
+        .unwrap_or_else(|| key_on_line(text, start, node))
+}
+
+/// The `to: target` line in the routing of `node`.
+fn route_range(text: &str, node: &str, target: &str) -> Option<Range> {
+    let start = node_keys(text)
+        .into_iter()
+        .find(|(name, _)| name == node)?
+        .1;
+    route_line(text, start, target).map(|line| key_on_line(text, line, target))
+}
+
+/// Maps a JSON pointer such as `/nodes/ask/routing` onto the text: the
+/// deepest node or key along it that can be found.
+fn pointer_range(text: &str, pointer: &str) -> Range {
+    let segments: Vec<&str> = pointer.split('/').filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        ["nodes", node, key, ..] => key_range(text, node, key),
+        ["nodes", node] => node_range(text, node),
+        [key, ..] => text
+            .lines()
+            .position(|line| line.starts_with(&format!("{key}:")))
+            .map(|line| key_on_line(text, line, key))
+            .unwrap_or_default(),
+        [] => Range::default(),
+    }
+}
+
+/// The range of `needle` on `line`, or the whole line when it is absent.
+fn key_on_line(text: &str, line: usize, needle: &str) -> Range {
+    let content = text.lines().nth(line).unwrap_or_default();
+    let (start, len) = match content.find(needle) {
+        Some(byte) => (content[..byte].chars().count(), needle.chars().count()),
+        None => (0, content.chars().count()),
+    };
+    Range::new(
+        Position::new(line as u32, start as u32),
+        Position::new(line as u32, (start + len) as u32),
+    )
+}
+
+/// An empty range at a 1-based line and column.
+fn point(line: usize, column: usize) -> Range {
+    let position = Position::new(
+        line.saturating_sub(1) as u32,
+        column.saturating_sub(1) as u32,
+    );
+    Range::new(position, position)
+}
+
+/// The message of `err` without its location; the range carries it.
+fn strip_location(err: &FlowError) -> String {
+    let location = match err {
+        FlowError::Yaml { location, .. }
+        | FlowError::Schema { location, .. }
+        | FlowError::NodeComponentShape { location, .. }
+        | FlowError::BadComponentKey { location, .. }
+        | FlowError::MissingNode { location, .. }
+        | FlowError::Internal { location, .. } => location.to_string(),
+    };
+    let message = err.to_string();
+    if location.is_empty() {
+        message
+    } else {
+        message.replacen(&location, "", 1)
+    }
+}
+
+fn lsp_severity(severity: Severity) -> DiagnosticSeverity {
+    match severity {
+        Severity::Error => DiagnosticSeverity::ERROR,
+        Severity::Warning => DiagnosticSeverity::WARNING,
+    }
+}
+
+fn error(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some(DIAGNOSTIC_SOURCE.to_string()),
+        message,
+        ..Diagnostic::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLOW: &str = "\
+id: weather_bot
+type: messaging
+nodes:
+  ask:
+    doc: Asks for a city.
+    qa.process:
+      welcome: Hi
+    routing:
+      - to: reply
+  reply:
+    templating.handlebars:
+      text: done
+";
+
+    #[test]
+    fn reports_flow_problems_where_they_are() {
+        assert!(flow_diagnostics(FLOW, None, &LintConfig::default()).is_empty());
+
+        let missing = FLOW.replace("- to: reply", "- to: replies");
+        let problems = flow_diagnostics(&missing, None, &LintConfig::default());
+        assert_eq!(problems.len(), 1, "{problems:?}");
+        assert!(problems[0].message.contains("replies"), "{problems:?}");
+        assert_eq!(problems[0].range.start, Position::new(8, 12));
+
+        let shape = FLOW.replace(
+            "      text: done\n",
+            "      text: done\n    email.send: {}\n",
+        );
+        let problems = flow_diagnostics(&shape, None, &LintConfig::default());
+        assert_eq!(problems[0].range.start.line, 9, "{problems:?}");
+
+        let looped = FLOW.replace(
+            "      text: done\n",
+            "      text: done\n    routing:\n      - to: reply\n",
+        );
+        let problems = flow_diagnostics(&looped, None, &LintConfig::default());
+        assert_eq!(problems.len(), 1, "{problems:?}");
+        assert_eq!(
+            problems[0].code,
+            Some(NumberOrString::String("inescapable-cycle".to_string()))
+        );
+        assert_eq!(problems[0].range.start.line, 9);
+
+        let syntax = FLOW.replace("welcome: Hi", "welcome: [Hi");
+        let problems = flow_diagnostics(&syntax, None, &LintConfig::default());
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].range.start.line >= 6, "{problems:?}");
+    }
+
+    #[test]
+    fn completes_and_resolves_node_references() {
+        assert_eq!(
+            node_keys(FLOW),
+            [("ask".to_string(), 3), ("reply".to_string(), 9)]
+        );
+        let target = definition(FLOW, Position::new(8, 14)).expect("definition");
+        assert_eq!(target, Range::new(Position::new(9, 2), Position::new(9, 7)));
+        assert_eq!(definition(FLOW, Position::new(1, 2)), None);
+
+        let spec = "id: demo\nentry_flows:\n  - \nimports_required:\n  - secrets.get\n";
+        assert_eq!(section_at(spec, 2).as_deref(), Some("entry_flows"));
+        assert_eq!(section_at(spec, 4).as_deref(), Some("imports_required"));
+        assert_eq!(
+            uri_to_path("file:///tmp/my%20pack/pack.yaml"),
+            Some(PathBuf::from("/tmp/my pack/pack.yaml"))
+        );
+    }
+}
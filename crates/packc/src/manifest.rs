@@ -1,12 +1,31 @@
 use crate::flows::FlowAsset;
+use crate::imports::ResolvedImport;
+use crate::signing::{KeyConstraints, SigstoreProof};
 use crate::templates::TemplateAsset;
 use anyhow::{Context, Result, anyhow};
 use base64::Engine as _;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use greentic_pack::atomic::{self, Durability};
 use greentic_pack::builder::PACK_VERSION;
+use greentic_pack::capabilities::CapabilitiesSection;
+use greentic_pack::classification::{ClassificationSection, DataHandling};
+use greentic_pack::config::ConfigSection;
+use greentic_pack::config_schema::{ConfigDefaults, ConfigSchema};
+use greentic_pack::dispatch::DispatchSection;
 use greentic_pack::events::EventsSection;
+use greentic_pack::import_spec::ImportSpec;
+use greentic_pack::lifecycle::LifecycleSection;
+use greentic_pack::limits::LimitsSection;
+use greentic_pack::locales::LocalesSection;
+use greentic_pack::media::MediaSection;
 use greentic_pack::messaging::MessagingSection;
+use greentic_pack::metrics::MetricsSection;
+use greentic_pack::permissions::PermissionSummary;
+use greentic_pack::qos::{FlowQos, QosSection};
+use greentic_pack::quotas::QuotaSection;
 use greentic_pack::repo::{InterfaceBinding, RepoPackSection};
+use greentic_pack::schedule::ScheduleSection;
+use greentic_pack::secrets::SecretsSection;
 use greentic_types::{PackKind, Signature as SharedSignature, SignatureAlgorithm};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -15,7 +34,6 @@ use std::collections::BTreeSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 use time::OffsetDateTime;
-use time::format_description::well_known::Rfc3339;
 use toml::Value;
 
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
@@ -42,13 +60,17 @@ pub struct PackSpec {
     #[serde(default)]
     pub vendor: Option<String>,
     #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
     pub flow_files: Vec<String>,
     #[serde(default)]
     pub template_dirs: Vec<String>,
     #[serde(default)]
     pub entry_flows: Vec<String>,
     #[serde(default)]
-    pub imports_required: Vec<String>,
+    pub imports_required: Vec<ImportSpec>,
     #[serde(default)]
     pub events: Option<EventsSection>,
     #[serde(default)]
@@ -60,11 +82,41 @@ pub struct PackSpec {
     #[serde(default)]
     pub mcp_components: Vec<McpComponentSpec>,
     #[serde(default)]
+    pub media: Option<MediaSection>,
+    #[serde(default)]
+    pub config: Option<ConfigSection>,
+    #[serde(default)]
+    pub config_schema: Option<ConfigSchema>,
+    #[serde(default)]
+    pub config_defaults: Option<ConfigDefaults>,
+    #[serde(default)]
+    pub lifecycle: Option<LifecycleSection>,
+    #[serde(default)]
+    pub quotas: Option<QuotaSection>,
+    #[serde(default)]
+    pub limits: Option<LimitsSection>,
+    #[serde(default)]
+    pub dispatch: Option<DispatchSection>,
+    #[serde(default)]
+    pub qos: Option<QosSection>,
+    #[serde(default)]
+    pub capabilities: Option<CapabilitiesSection>,
+    #[serde(default)]
+    pub secrets: Option<SecretsSection>,
+    #[serde(default)]
+    pub metrics: Option<MetricsSection>,
+    #[serde(default)]
+    pub schedule: Option<ScheduleSection>,
+    #[serde(default)]
+    pub classification: Option<ClassificationSection>,
+    #[serde(default)]
+    pub locales: Option<LocalesSection>,
+    #[serde(default)]
     pub annotations: JsonMap<String, JsonValue>,
 }
 
 impl PackSpec {
-    fn validate(&self) -> Result<()> {
+    pub fn validate(&self) -> Result<()> {
         if self.pack_version != PACK_VERSION {
             anyhow::bail!(
                 "unsupported packVersion {}; expected {}",
@@ -91,8 +143,70 @@ impl PackSpec {
             binding.validate("interfaces")?;
         }
         McpComponentSpec::validate_all(&self.mcp_components)?;
+        if let Some(media) = &self.media {
+            media.validate()?;
+        }
+        if let Some(config) = &self.config {
+            config.validate()?;
+        }
+        if let Some(config_schema) = &self.config_schema {
+            if self
+                .config
+                .as_ref()
+                .is_some_and(|config| !config.params.is_empty())
+            {
+                anyhow::bail!(
+                    "declare install-time settings in config.params or config_schema, not both"
+                );
+            }
+            config_schema.validate()?;
+        }
+        if let Some(config_defaults) = &self.config_defaults {
+            config_defaults.validate(self.settings_schema().as_ref())?;
+        }
+        if let Some(lifecycle) = &self.lifecycle {
+            lifecycle.validate()?;
+        }
+        if let Some(quotas) = &self.quotas {
+            quotas.validate()?;
+        }
+        if let Some(limits) = &self.limits {
+            limits.validate()?;
+        }
+        if let Some(dispatch) = &self.dispatch {
+            dispatch.validate()?;
+        }
+        if let Some(qos) = &self.qos {
+            qos.validate()?;
+        }
+        if let Some(capabilities) = &self.capabilities {
+            capabilities.validate()?;
+        }
+        if let Some(secrets) = &self.secrets {
+            secrets.validate()?;
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.validate()?;
+        }
+        if let Some(schedule) = &self.schedule {
+            schedule.validate()?;
+        }
+        if let Some(classification) = &self.classification {
+            classification.validate()?;
+        }
+        if let Some(locales) = &self.locales {
+            locales.validate()?;
+        }
         Ok(())
     }
+
+    /// The schema of install-time settings: `config_schema`, or the one
+    /// `config.params` describe.
+    pub fn settings_schema(&self) -> Option<ConfigSchema> {
+        self.config_schema
+            .clone()
+            .or_else(|| self.config.as_ref().and_then(ConfigSection::params_schema))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -124,7 +238,15 @@ pub struct PackManifest {
     pub created_at: String,
     pub flows: Vec<FlowEntry>,
     pub templates: Vec<BlobEntry>,
-    pub imports_required: Vec<String>,
+    pub imports_required: Vec<ImportSpec>,
+    /// WIT interface versions the WIT-style `imports_required` entries
+    /// resolved to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub imports_resolved: Vec<ResolvedImport>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub events: Option<EventsSection>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -133,6 +255,36 @@ pub struct PackManifest {
     pub messaging: Option<MessagingSection>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub mcp_components: Vec<McpComponentManifest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<MediaSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<ConfigSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_schema: Option<ConfigSchema>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_defaults: Option<ConfigDefaults>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lifecycle: Option<LifecycleSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quotas: Option<QuotaSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<LimitsSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dispatch: Option<DispatchSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qos: Option<QosSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<CapabilitiesSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<SecretsSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<MetricsSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<ScheduleSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_handling: Option<DataHandling>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<PermissionSummary>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -143,16 +295,25 @@ pub struct FlowEntry {
     pub start: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub sha256: Option<String>,
+    /// `<alg>:<hex>` digest of the flow source; older manifests stored bare
+    /// SHA-256 hex under `sha256`.
+    #[serde(alias = "sha256", skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub size: Option<u64>,
+    /// Marked hot by the profile data of the build.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub eager: bool,
+    /// The flow's entry in the `qos` section.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qos: Option<FlowQos>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlobEntry {
     pub logical_path: String,
-    pub sha256: String,
+    #[serde(alias = "sha256")]
+    pub digest: String,
     pub size: u64,
 }
 
@@ -231,11 +392,8 @@ pub fn build_manifest(
     bundle: &SpecBundle,
     flows: &[FlowAsset],
     templates: &[TemplateAsset],
+    created_at: &str,
 ) -> PackManifest {
-    let created_at = OffsetDateTime::now_utc()
-        .format(&Rfc3339)
-        .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string());
-
     let flow_entries = flows
         .iter()
         .map(|flow| FlowEntry {
@@ -243,8 +401,15 @@ pub fn build_manifest(
             flow_type: flow.bundle.kind.clone(),
             start: Some(flow.bundle.entry.clone()),
             source: Some(flow.relative_path.to_string_lossy().to_string()),
-            sha256: Some(flow.sha256.clone()),
+            digest: Some(flow.digest.clone()),
             size: Some(flow.raw.len() as u64),
+            // Set by `packc build` when it is given profile data.
+            eager: false,
+            qos: bundle
+                .spec
+                .qos
+                .as_ref()
+                .and_then(|qos| qos.flows.get(&flow.bundle.id).copied()),
         })
         .collect();
 
@@ -252,7 +417,7 @@ pub fn build_manifest(
         .iter()
         .map(|blob| BlobEntry {
             logical_path: blob.logical_path.clone(),
-            sha256: blob.sha256.clone(),
+            digest: blob.digest.clone(),
             size: blob.size,
         })
         .collect();
@@ -271,14 +436,42 @@ pub fn build_manifest(
     PackManifest {
         pack_id: bundle.spec.id.clone(),
         version: bundle.spec.version.clone(),
-        created_at,
+        created_at: created_at.to_string(),
         flows: flow_entries,
         templates: template_entries,
         imports_required: bundle.spec.imports_required.clone(),
+        // Filled in by `packc build` once the interface catalog is loaded.
+        imports_resolved: Vec::new(),
+        tags: bundle.spec.tags.clone(),
+        categories: bundle.spec.categories.clone(),
         events: bundle.spec.events.clone(),
         repo: bundle.spec.repo.clone(),
         messaging: bundle.spec.messaging.clone(),
         mcp_components: mcp_entries,
+        media: bundle
+            .spec
+            .media
+            .as_ref()
+            .map(crate::media::archive_section),
+        config: bundle.spec.config.clone(),
+        config_schema: bundle.spec.settings_schema(),
+        config_defaults: bundle.spec.config_defaults.clone(),
+        lifecycle: bundle.spec.lifecycle.clone(),
+        quotas: bundle.spec.quotas.clone(),
+        limits: bundle.spec.limits.clone(),
+        dispatch: bundle.spec.dispatch.clone(),
+        qos: bundle.spec.qos.clone(),
+        capabilities: bundle.spec.capabilities.clone(),
+        secrets: bundle.spec.secrets.clone(),
+        metrics: bundle.spec.metrics.clone(),
+        schedule: bundle.spec.schedule.clone(),
+        data_handling: bundle
+            .spec
+            .classification
+            .as_ref()
+            .map(ClassificationSection::summarize),
+        // Filled in by `packc build`, which reads the i18n bundles.
+        permissions: None,
     }
 }
 
@@ -294,26 +487,37 @@ pub struct PackSignature {
     pub created_at: OffsetDateTime,
     pub digest: String,
     pub sig: String,
+    /// Signer-chosen limits on where this signature is accepted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constraints: Option<KeyConstraints>,
+    /// Certificate and transparency log entry of a keyless signature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sigstore: Option<SigstoreProof>,
 }
 
 impl PackSignature {
     pub const ED25519: &'static str = "ed25519";
+    /// ML-DSA-65 (FIPS 204), a post-quantum signature scheme.
+    pub const ML_DSA_65: &'static str = "ml-dsa-65";
+
+    /// Returns `true` for post-quantum signature algorithms.
+    pub fn is_post_quantum(&self) -> bool {
+        self.alg.eq_ignore_ascii_case(Self::ML_DSA_65)
+    }
 
     /// Converts this signature into the shared `greentic-types` representation.
     pub fn to_shared(&self) -> Result<SharedSignature> {
-        if self.alg.to_ascii_lowercase() != Self::ED25519 {
-            anyhow::bail!("unsupported algorithm {}", self.alg);
-        }
+        let algorithm = match self.alg.to_ascii_lowercase().as_str() {
+            Self::ED25519 => SignatureAlgorithm::Ed25519,
+            Self::ML_DSA_65 => SignatureAlgorithm::Other(Self::ML_DSA_65.to_string()),
+            _ => anyhow::bail!("unsupported algorithm {}", self.alg),
+        };
 
         let raw = URL_SAFE_NO_PAD
             .decode(self.sig.as_bytes())
             .map_err(|err| anyhow!("invalid signature encoding: {err}"))?;
 
-        Ok(SharedSignature::new(
-            self.key_id.clone(),
-            SignatureAlgorithm::Ed25519,
-            raw,
-        ))
+        Ok(SharedSignature::new(self.key_id.clone(), algorithm, raw))
     }
 }
 
@@ -348,13 +552,47 @@ pub fn read_manifest_without_signature(path: &Path) -> Result<Vec<u8>> {
     Ok(serialized.into_bytes())
 }
 
-pub fn read_signature(pack_dir: &Path) -> Result<Option<PackSignature>> {
+/// Identifier of the pack in `pack_dir`, as checked against signature
+/// constraints: the `id` from `pack.yaml` when present, otherwise
+/// `[package].name` from the TOML manifest.
+pub fn pack_id(pack_dir: &Path) -> Result<Option<String>> {
+    let spec_path = pack_dir.join("pack.yaml");
+    if spec_path.exists() {
+        let contents = fs::read_to_string(&spec_path)
+            .with_context(|| format!("failed to read {}", spec_path.display()))?;
+        let doc: serde_yaml_bw::Value = serde_yaml_bw::from_str(&contents)
+            .with_context(|| format!("{} is not valid YAML", spec_path.display()))?;
+        if let Some(id) = doc.get("id").and_then(|id| id.as_str()) {
+            return Ok(Some(id.to_string()));
+        }
+    }
+
     let Some(path) = find_manifest_path(pack_dir) else {
         return Ok(None);
     };
+    let doc = load_manifest_value(&path)?;
+    Ok(doc
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(Value::as_str)
+        .map(str::to_string))
+}
+
+/// Reads the primary `[greentic.signature]` block.
+pub fn read_signature(pack_dir: &Path) -> Result<Option<PackSignature>> {
+    Ok(read_signatures(pack_dir)?.into_iter().next())
+}
+
+/// Reads every signature on the manifest: the primary `[greentic.signature]`
+/// block followed by any `[[greentic.signatures]]` entries (e.g. the ML-DSA
+/// half of a hybrid signature).
+pub fn read_signatures(pack_dir: &Path) -> Result<Vec<PackSignature>> {
+    let Some(path) = find_manifest_path(pack_dir) else {
+        return Ok(Vec::new());
+    };
 
     let doc = load_manifest_value(&path)?;
-    signature_from_doc(&doc)
+    signatures_from_doc(&doc)
 }
 
 pub fn write_signature(
@@ -362,20 +600,29 @@ pub fn write_signature(
     signature: &PackSignature,
     out_path: Option<&Path>,
 ) -> Result<()> {
+    write_signatures(pack_dir, std::slice::from_ref(signature), out_path)
+}
+
+/// Replaces the manifest signatures. The first one becomes the primary
+/// `[greentic.signature]` block, which verifiers that predate multi-signature
+/// support keep checking; the rest go to `[[greentic.signatures]]`.
+pub fn write_signatures(
+    pack_dir: &Path,
+    signatures: &[PackSignature],
+    out_path: Option<&Path>,
+) -> Result<()> {
+    if signatures.is_empty() {
+        anyhow::bail!("at least one signature is required");
+    }
     let manifest_path = manifest_path(pack_dir)?;
     let mut doc = load_manifest_value(&manifest_path)?;
-    set_signature(&mut doc, signature)?;
+    set_signatures(&mut doc, signatures)?;
 
     let target_path = out_path.unwrap_or(&manifest_path);
-    if let Some(parent) = target_path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("failed to create directory {}", parent.display()))?;
-    }
-
     let serialized = toml::to_string_pretty(&doc)
         .map_err(|err| anyhow!("failed to serialise manifest: {err}"))?;
-    fs::write(target_path, serialized.as_bytes())
-        .with_context(|| format!("failed to write {}", target_path.display()))?;
+    // The manifest is usually rewritten in place, so never leave it half-written.
+    atomic::write(target_path, serialized.as_bytes(), Durability::Fsync)?;
 
     Ok(())
 }
@@ -388,7 +635,7 @@ fn load_manifest_value(path: &Path) -> Result<Value> {
     Ok(Value::Table(table))
 }
 
-fn set_signature(doc: &mut Value, signature: &PackSignature) -> Result<()> {
+fn set_signatures(doc: &mut Value, signatures: &[PackSignature]) -> Result<()> {
     let table = doc
         .as_table_mut()
         .ok_or_else(|| anyhow!("pack manifest must be a table"))?;
@@ -401,10 +648,21 @@ fn set_signature(doc: &mut Value, signature: &PackSignature) -> Result<()> {
         .as_table_mut()
         .ok_or_else(|| anyhow!("[greentic] must be a table"))?;
 
-    let signature_value = Value::try_from(signature.clone())
-        .map_err(|err| anyhow!("failed to serialise signature: {err}"))?;
+    let mut values = signatures
+        .iter()
+        .map(|signature| {
+            Value::try_from(signature.clone())
+                .map_err(|err| anyhow!("failed to serialise signature: {err}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let primary = values.remove(0);
 
-    greentic_table.insert("signature".to_string(), signature_value);
+    greentic_table.insert("signature".to_string(), primary);
+    if values.is_empty() {
+        greentic_table.remove("signatures");
+    } else {
+        greentic_table.insert("signatures".to_string(), Value::Array(values));
+    }
     Ok(())
 }
 
@@ -417,19 +675,20 @@ fn strip_signature(doc: &mut Value) {
         && let Some(section) = greentic.as_table_mut()
     {
         section.remove("signature");
+        section.remove("signatures");
         if section.is_empty() {
             table.remove("greentic");
         }
     }
 }
 
-fn signature_from_doc(doc: &Value) -> Result<Option<PackSignature>> {
+fn signatures_from_doc(doc: &Value) -> Result<Vec<PackSignature>> {
     let table = doc
         .as_table()
         .ok_or_else(|| anyhow!("pack manifest must be a table"))?;
 
     let Some(greentic) = table.get("greentic") else {
-        return Ok(None);
+        return Ok(Vec::new());
     };
 
     let greentic_table = greentic
@@ -437,15 +696,24 @@ fn signature_from_doc(doc: &Value) -> Result<Option<PackSignature>> {
         .ok_or_else(|| anyhow!("[greentic] must be a table"))?;
 
     let Some(signature_value) = greentic_table.get("signature") else {
-        return Ok(None);
+        return Ok(Vec::new());
     };
 
     let signature: PackSignature = signature_value
         .clone()
         .try_into()
         .map_err(|err| anyhow!("invalid greentic.signature block: {err}"))?;
+    let mut signatures = vec![signature];
+
+    if let Some(extra) = greentic_table.get("signatures") {
+        let extra: Vec<PackSignature> = extra
+            .clone()
+            .try_into()
+            .map_err(|err| anyhow!("invalid greentic.signatures block: {err}"))?;
+        signatures.extend(extra);
+    }
 
-    Ok(Some(signature))
+    Ok(signatures)
 }
 
 const MANIFEST_CANDIDATES: [&str; 2] = ["pack.toml", "greentic-pack.toml"];
@@ -461,8 +729,11 @@ pub fn normalize_protocol(raw: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::digest::DigestAlgorithm;
     use crate::{flows, templates};
 
+    const CREATED_AT: &str = "2025-01-01T00:00:00Z";
+
     fn demo_pack_dir() -> PathBuf {
         PathBuf::from(env!("CARGO_MANIFEST_DIR"))
             .join("..")
@@ -476,18 +747,24 @@ mod tests {
         let spec_bundle = load_spec(&pack_dir).expect("spec loads");
         assert_eq!(spec_bundle.spec.id, "greentic.weather.demo");
 
-        let flows = flows::load_flows(&pack_dir, &spec_bundle.spec).expect("flows load");
+        let flows = flows::load_flows(&pack_dir, &spec_bundle.spec, DigestAlgorithm::default())
+            .expect("flows load");
         assert_eq!(flows.len(), 1);
         assert!(
             flows[0].raw.contains("mcp.exec"),
             "flow should reference mcp.exec node"
         );
 
-        let templates =
-            templates::collect_templates(&pack_dir, &spec_bundle.spec).expect("templates load");
+        let templates = templates::collect_templates(
+            &pack_dir,
+            &spec_bundle.spec,
+            DigestAlgorithm::default(),
+            templates::SyntaxCheck::Parse,
+        )
+        .expect("templates load");
         assert_eq!(templates.len(), 1);
 
-        let manifest = build_manifest(&spec_bundle, &flows, &templates);
+        let manifest = build_manifest(&spec_bundle, &flows, &templates, CREATED_AT);
         assert_eq!(manifest.flows[0].id, "weather_bot");
         assert_eq!(manifest.flows[0].flow_type, "messaging");
         assert_eq!(manifest.flows[0].start.as_deref(), Some("collect_location"));
@@ -499,6 +776,12 @@ mod tests {
             manifest.templates[0].logical_path,
             "templates/weather_now.hbs"
         );
+        assert!(
+            manifest.flows[0]
+                .digest
+                .as_deref()
+                .is_some_and(|digest| digest.starts_with("sha256:"))
+        );
         assert_eq!(manifest.imports_required.len(), 2);
 
         let encoded = encode_manifest(&manifest).expect("manifest encodes");
@@ -519,6 +802,8 @@ mod tests {
             homepage: None,
             support: None,
             vendor: None,
+            tags: Vec::new(),
+            categories: Vec::new(),
             flow_files: Vec::new(),
             template_dirs: Vec::new(),
             entry_flows: Vec::new(),
@@ -533,13 +818,28 @@ mod tests {
                 protocol: McpComponentSpec::PROTOCOL_LATEST.into(),
                 adapter_template: McpComponentSpec::ADAPTER_DEFAULT.into(),
             }],
+            media: None,
+            config: None,
+            config_schema: None,
+            config_defaults: None,
+            lifecycle: None,
+            quotas: None,
+            limits: None,
+            dispatch: None,
+            qos: None,
+            secrets: None,
+            capabilities: None,
+            metrics: None,
+            schedule: None,
+            classification: None,
+            locales: None,
             annotations: JsonMap::new(),
         };
         let bundle = SpecBundle {
             spec,
             source: PathBuf::from("pack.yaml"),
         };
-        let manifest = build_manifest(&bundle, &[], &[]);
+        let manifest = build_manifest(&bundle, &[], &[], CREATED_AT);
         assert_eq!(manifest.mcp_components.len(), 1);
         assert_eq!(
             manifest.mcp_components[0].protocol,
@@ -12,6 +12,7 @@ use semver::Version;
 use serde_json::Map as JsonMap;
 
 use crate::manifest::{McpComponentSpec, SpecBundle, normalize_protocol};
+use crate::tools;
 
 #[derive(Debug, Clone)]
 pub struct ComposedMcpComponent {
@@ -73,7 +74,7 @@ pub fn compose_all_with_override(
     Ok(outputs)
 }
 
-fn resolve_router_path(pack_dir: &Path, router_ref: &str) -> Result<PathBuf> {
+pub(crate) fn resolve_router_path(pack_dir: &Path, router_ref: &str) -> Result<PathBuf> {
     let path = pack_dir.join(router_ref);
     if path.exists() {
         Ok(path)
@@ -86,7 +87,7 @@ fn resolve_router_path(pack_dir: &Path, router_ref: &str) -> Result<PathBuf> {
     }
 }
 
-fn resolve_adapter_template(protocol: &str, adapter_template: &str) -> Result<PathBuf> {
+pub(crate) fn resolve_adapter_template(protocol: &str, adapter_template: &str) -> Result<PathBuf> {
     if adapter_template != McpComponentSpec::ADAPTER_DEFAULT {
         bail!(
             "unsupported adapter_template `{}` (only `default` is available)",
@@ -141,7 +142,7 @@ fn compose_with_wasm_tools(
         return Ok(());
     }
 
-    let status = Command::new("wasm-tools")
+    let status = Command::new(tools::locate("wasm-tools"))
         .arg("compose")
         .arg(adapter)
         .arg("-d")
@@ -208,6 +209,8 @@ mod tests {
             homepage: None,
             support: None,
             vendor: None,
+            tags: Vec::new(),
+            categories: Vec::new(),
             flow_files: Vec::new(),
             template_dirs: Vec::new(),
             entry_flows: Vec::new(),
@@ -222,6 +225,21 @@ mod tests {
                 protocol: McpComponentSpec::PROTOCOL_25_06_18.into(),
                 adapter_template: McpComponentSpec::ADAPTER_DEFAULT.into(),
             }],
+            media: None,
+            config: None,
+            config_schema: None,
+            config_defaults: None,
+            lifecycle: None,
+            quotas: None,
+            limits: None,
+            dispatch: None,
+            qos: None,
+            secrets: None,
+            capabilities: None,
+            metrics: None,
+            schedule: None,
+            classification: None,
+            locales: None,
             annotations: JsonMap::new(),
         };
 
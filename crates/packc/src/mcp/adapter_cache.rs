@@ -1,19 +1,47 @@
+//! Local copies of MCP adapter components.
+//!
+//! Adapters live in the `adapters` cache, one directory per protocol
+//! (`~/.cache/greentic/adapters/<protocol>/adapter.component.wasm`), and are
+//! held to its quota like the other caches in [`crate::cache`].
+
+use std::fs;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
+use greentic_pack::atomic::{self, Durability};
 
 use super::adapter_ref::{MCP_ADAPTER_25_06_18, McpAdapterRef};
+use crate::cache::{self, CacheKind};
+
+const ADAPTER_FILE: &str = "adapter.component.wasm";
+const BUNDLED_25_06_18: &[u8] = include_bytes!("../../assets/mcp_adapter_25_06_18.component.wasm");
 
 /// Return the local adapter path for the given reference.
 ///
-/// Current behaviour: use the vendored asset bundled in packc.
-/// Future: implement OCI pull + cache when GHCR is the source of truth.
+/// Current behaviour: copy the asset bundled in packc into the adapter cache,
+/// or use the vendored file when there is no cache directory.
+/// Future: implement OCI pull when GHCR is the source of truth.
 pub fn ensure_adapter_local(adapter: &McpAdapterRef) -> Result<PathBuf> {
-    if adapter.protocol == MCP_ADAPTER_25_06_18.protocol {
-        vendored_adapter_path()
-    } else {
+    if adapter.protocol != MCP_ADAPTER_25_06_18.protocol {
         anyhow::bail!("unsupported MCP adapter protocol `{}`", adapter.protocol)
     }
+    match CacheKind::Adapters.dir() {
+        Some(dir) => cached_adapter(&dir, adapter.protocol, BUNDLED_25_06_18),
+        None => vendored_adapter_path(),
+    }
+}
+
+/// The cached copy of `bytes` for `protocol` under `dir`, written if it is
+/// missing or differs.
+fn cached_adapter(dir: &Path, protocol: &str, bytes: &[u8]) -> Result<PathBuf> {
+    let path = dir.join(protocol).join(ADAPTER_FILE);
+    if fs::read(&path).is_ok_and(|cached| cached == bytes) {
+        cache::touch(&path)?;
+    } else {
+        atomic::write(&path, bytes, Durability::Buffered)?;
+        cache::enforce_configured(CacheKind::Adapters, dir)?;
+    }
+    Ok(path)
 }
 
 fn vendored_adapter_path() -> Result<PathBuf> {
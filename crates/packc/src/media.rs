@@ -0,0 +1,118 @@
+use crate::manifest::PackSpec;
+use anyhow::{Context, Result};
+use greentic_pack::media::{
+    MAX_ICON_BYTES, MAX_SCREENSHOT_BYTES, MediaRef, MediaSection, validate_media_bytes,
+};
+use std::fs;
+use std::path::Path;
+
+/// Icons above this size still build but are flagged by `packc lint`.
+pub const RECOMMENDED_ICON_BYTES: u64 = 64 * 1024;
+/// Screenshots above this size still build but are flagged by `packc lint`.
+pub const RECOMMENDED_SCREENSHOT_BYTES: u64 = 512 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct MediaAsset {
+    pub role: MediaRole,
+    pub media: MediaRef,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaRole {
+    Icon,
+    Screenshot,
+}
+
+impl MediaRole {
+    pub fn limit(self) -> u64 {
+        match self {
+            Self::Icon => MAX_ICON_BYTES,
+            Self::Screenshot => MAX_SCREENSHOT_BYTES,
+        }
+    }
+
+    pub fn recommended(self) -> u64 {
+        match self {
+            Self::Icon => RECOMMENDED_ICON_BYTES,
+            Self::Screenshot => RECOMMENDED_SCREENSHOT_BYTES,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Icon => "media.icon",
+            Self::Screenshot => "media.screenshots",
+        }
+    }
+}
+
+/// Reads every image declared under `media:` and enforces format and hard size limits.
+pub fn collect_media(pack_dir: &Path, spec: &PackSpec) -> Result<Vec<MediaAsset>> {
+    let Some(section) = &spec.media else {
+        return Ok(Vec::new());
+    };
+
+    let mut assets = Vec::new();
+    let declared = section
+        .icon
+        .iter()
+        .map(|icon| (MediaRole::Icon, icon))
+        .chain(
+            section
+                .screenshots
+                .iter()
+                .map(|shot| (MediaRole::Screenshot, shot)),
+        );
+    for (role, media) in declared {
+        let path = pack_dir.join(&media.path);
+        let bytes = fs::read(&path)
+            .with_context(|| format!("failed to read {} {}", role.label(), path.display()))?;
+        validate_media_bytes(role.label(), media, &bytes, role.limit())?;
+        assets.push(MediaAsset {
+            role,
+            media: media.clone(),
+            bytes,
+        });
+    }
+    Ok(assets)
+}
+
+/// Returns the lint warnings for media that exceed the recommended sizes.
+pub fn size_warnings(assets: &[MediaAsset]) -> Vec<String> {
+    assets.iter().filter_map(size_warning).collect()
+}
+
+/// The warning for `asset` if it exceeds the recommended size.
+pub fn size_warning(asset: &MediaAsset) -> Option<String> {
+    (asset.bytes.len() as u64 > asset.role.recommended()).then(|| {
+        format!(
+            "{} `{}` is {} bytes; keep it under {} bytes for marketplace listings",
+            asset.role.label(),
+            asset.media.path,
+            asset.bytes.len(),
+            asset.role.recommended()
+        )
+    })
+}
+
+/// Path of a spec media file relative to the archive's `media/` directory.
+///
+/// Sources already kept under `media/` (as in an extracted pack) are not
+/// nested a second time.
+pub fn media_relative(spec_path: &str) -> &str {
+    let path = spec_path.trim_start_matches("./");
+    path.strip_prefix("media/").unwrap_or(path)
+}
+
+/// Rewrites the spec's media section so paths point at the archive's `media/` directory.
+pub fn archive_section(section: &MediaSection) -> MediaSection {
+    let rewrite = |media: &MediaRef| MediaRef {
+        path: format!("media/{}", media_relative(&media.path)),
+        caption: media.caption.clone(),
+    };
+    MediaSection {
+        icon: section.icon.as_ref().map(rewrite),
+        screenshots: section.screenshots.iter().map(rewrite).collect(),
+    }
+}
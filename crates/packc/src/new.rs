@@ -1,21 +1,27 @@
 #![forbid(unsafe_code)]
 
-use crate::manifest::PackSpec;
+use crate::cli::lint::lint_pack;
+use crate::manifest::{self, PackSpec};
 use anyhow::{Context, Result, anyhow};
 use clap::{Args, ValueEnum};
 use ed25519_dalek::SigningKey;
 use ed25519_dalek::pkcs8::{EncodePrivateKey, EncodePublicKey};
+use greentic_pack::atomic::{self, Durability};
 use greentic_pack::builder::PACK_VERSION;
+use greentic_pack::events::{
+    EventProviderCapabilities, EventProviderKind, EventProviderSpec, EventsSection,
+};
+use greentic_pack::messaging::{MessagingAdapter, MessagingAdapterKind, MessagingSection};
 use pkcs8::LineEnding;
 use rand_core_06::OsRng;
 use serde::Serialize;
 use serde_json::Map as JsonMap;
 use sha2::{Digest, Sha256};
 use std::fs;
+use std::io::{BufRead, IsTerminal, Write};
 use std::path::{Path, PathBuf};
 
 const DEFAULT_VERSION: &str = "0.1.0";
-const DEFAULT_FLOW_FILE: &str = "flows/welcome.ygtc";
 const DEFAULT_FLOW_CONTENT: &str = r#"id: welcome
 title: Welcome Flow
 description: Send a single greeting message.
@@ -45,6 +51,61 @@ const GITIGNORE: &str = r#"dist/
 *.swp
 "#;
 
+const MESSAGING_FLOW: &str = r#"id: __ID__
+title: __TITLE__
+description: Reply to an incoming message.
+type: messaging
+start: reply
+
+nodes:
+  reply:
+    templating.handlebars:
+      text: |
+        You said: {{in.text}}
+    routing:
+      - out: true
+"#;
+
+const EVENTS_FLOW: &str = r#"id: __ID__
+title: __TITLE__
+description: Handle an event delivered by an events provider.
+type: pubsub
+start: handle_event
+
+nodes:
+  handle_event:
+    flow.return:
+      payload:
+        event: "{{in.type}}"
+        handled: true
+    routing:
+      - out: true
+"#;
+
+const MCP_EXEC_FLOW: &str = r#"id: __ID__
+title: __TITLE__
+description: Call an MCP tool and reply with its result.
+type: messaging
+start: call_tool
+
+nodes:
+  call_tool:
+    mcp.exec:
+      component: __COMPONENT__
+      action: __ACTION__
+      args:
+        input: in.text
+    routing:
+      - to: reply
+
+  reply:
+    templating.handlebars:
+      text: |
+        {{call_tool.payload}}
+    routing:
+      - out: true
+"#;
+
 #[derive(Debug, Clone, ValueEnum, Eq, PartialEq)]
 pub enum TemplateKind {
     Minimal,
@@ -52,9 +113,9 @@ pub enum TemplateKind {
 
 #[derive(Debug, Clone, Args)]
 pub struct NewArgs {
-    /// Identifier for the new pack (written to pack.yaml)
+    /// Identifier for the new pack (written to pack.yaml); asked for when omitted in a terminal
     #[arg(value_name = "ID")]
-    pub id: String,
+    pub id: Option<String>,
 
     /// Output directory for the scaffolded pack (defaults to ./<id>)
     #[arg(long = "dir", value_name = "DIR")]
@@ -71,6 +132,278 @@ pub struct NewArgs {
     /// Overwrite existing files/directories if they already exist
     #[arg(long)]
     pub force: bool,
+
+    /// Use the defaults instead of asking, even in a terminal
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+}
+
+/// Starting point for a flow added with `packc add-flow`.
+#[derive(Debug, Clone, Copy, ValueEnum, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FlowTemplate {
+    /// Reply to a message with a Handlebars template
+    Messaging,
+    /// Handle an event from an events provider
+    Events,
+    /// Call an MCP tool and reply with its result
+    McpExec,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct AddFlowArgs {
+    /// Identifier for the new flow; the file is flows/<ID>.ygtc
+    #[arg(value_name = "ID")]
+    pub id: String,
+
+    /// Root directory of the pack (must contain pack.yaml)
+    #[arg(long = "in", value_name = "DIR", default_value = ".")]
+    pub input: PathBuf,
+
+    /// Flow template to apply
+    #[arg(long, value_enum, default_value = "messaging")]
+    pub template: FlowTemplate,
+
+    /// MCP component the mcp-exec template calls (defaults to the first in pack.yaml)
+    #[arg(long, value_name = "ID")]
+    pub component: Option<String>,
+
+    /// MCP action the mcp-exec template calls
+    #[arg(long, value_name = "ACTION", default_value = "run")]
+    pub action: String,
+
+    /// Also list the flow under entry_flows
+    #[arg(long)]
+    pub entry: bool,
+
+    /// Overwrite the flow file if it already exists
+    #[arg(long)]
+    pub force: bool,
+}
+
+/// Kinds of flow `packc new` can scaffold.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum ScaffoldFlow {
+    Messaging,
+    Events,
+}
+
+impl ScaffoldFlow {
+    fn id(self) -> &'static str {
+        match self {
+            ScaffoldFlow::Messaging => "welcome",
+            ScaffoldFlow::Events => "on_event",
+        }
+    }
+
+    fn file(self) -> String {
+        format!("flows/{}.ygtc", self.id())
+    }
+
+    fn contents(self) -> String {
+        match self {
+            ScaffoldFlow::Messaging => DEFAULT_FLOW_CONTENT.to_string(),
+            ScaffoldFlow::Events => EVENTS_FLOW
+                .replace("__ID__", self.id())
+                .replace("__TITLE__", "On Event"),
+        }
+    }
+}
+
+/// Everything `packc new` needs to know about the pack it scaffolds.
+#[derive(Debug, Clone, PartialEq)]
+struct PackAnswers {
+    id: String,
+    version: String,
+    flows: Vec<ScaffoldFlow>,
+    adapters: Vec<MessagingAdapter>,
+    providers: Vec<EventProviderSpec>,
+    sign: bool,
+}
+
+impl PackAnswers {
+    /// The answers used with `--yes` or when stdin is not a terminal.
+    fn defaults(args: &NewArgs) -> Result<Self> {
+        let id = args.id.clone().ok_or_else(|| {
+            anyhow!("pack id is required when not running interactively (pass it as ID)")
+        })?;
+        Ok(Self {
+            id,
+            version: DEFAULT_VERSION.to_string(),
+            flows: vec![ScaffoldFlow::Messaging],
+            adapters: Vec::new(),
+            providers: Vec::new(),
+            sign: args.sign,
+        })
+    }
+}
+
+/// Asks questions on `output` and reads answers from `input`; an empty
+/// answer takes the default shown in brackets.
+struct Prompter<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Prompter<R, W> {
+    fn ask(&mut self, question: &str, default: Option<&str>) -> Result<String> {
+        match default {
+            Some(default) => write!(self.output, "{question} [{default}]: ")?,
+            None => write!(self.output, "{question}: ")?,
+        }
+        self.output.flush()?;
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            anyhow::bail!("input ended before every question was answered");
+        }
+        let answer = line.trim();
+        Ok(match default {
+            Some(default) if answer.is_empty() => default.to_string(),
+            _ => answer.to_string(),
+        })
+    }
+
+    /// Asks again until `parse` accepts the answer.
+    fn ask_until<T>(
+        &mut self,
+        question: &str,
+        default: Option<&str>,
+        parse: impl Fn(&str) -> Result<T>,
+    ) -> Result<T> {
+        loop {
+            let answer = self.ask(question, default)?;
+            match parse(&answer) {
+                Ok(value) => return Ok(value),
+                Err(err) => writeln!(self.output, "  {err}")?,
+            }
+        }
+    }
+
+    fn confirm(&mut self, question: &str, default: bool) -> Result<bool> {
+        let default = if default { "y" } else { "n" };
+        self.ask_until(question, Some(default), |answer| {
+            match answer.to_ascii_lowercase().as_str() {
+                "y" | "yes" => Ok(true),
+                "n" | "no" => Ok(false),
+                _ => Err(anyhow!("answer y or n")),
+            }
+        })
+    }
+
+    /// Comma-separated answers; an empty answer means none.
+    fn list(&mut self, question: &str) -> Result<Vec<String>> {
+        Ok(split_list(&self.ask(question, None)?))
+    }
+}
+
+fn split_list(answer: &str) -> Vec<String> {
+    answer
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn non_empty(answer: &str) -> Result<String> {
+    if answer.is_empty() {
+        anyhow::bail!("an answer is required");
+    }
+    Ok(answer.to_string())
+}
+
+fn run_wizard<R: BufRead, W: Write>(
+    prompter: &mut Prompter<R, W>,
+    args: &NewArgs,
+) -> Result<PackAnswers> {
+    let id = prompter.ask_until("Pack id", args.id.as_deref(), non_empty)?;
+    let version = prompter.ask_until("Version", Some(DEFAULT_VERSION), |answer| {
+        semver::Version::parse(answer)
+            .map(|_| answer.to_string())
+            .map_err(|err| anyhow!("not a semver version: {err}"))
+    })?;
+    let flows = prompter.ask_until(
+        "Flow types (messaging, events)",
+        Some("messaging"),
+        |answer| {
+            let mut flows = Vec::new();
+            for kind in split_list(answer) {
+                let flow = match kind.as_str() {
+                    "messaging" => ScaffoldFlow::Messaging,
+                    "events" => ScaffoldFlow::Events,
+                    other => anyhow::bail!("unknown flow type `{other}`"),
+                };
+                if !flows.contains(&flow) {
+                    flows.push(flow);
+                }
+            }
+            if flows.is_empty() {
+                anyhow::bail!("choose at least one flow type");
+            }
+            Ok(flows)
+        },
+    )?;
+
+    let mut adapters = Vec::new();
+    if flows.contains(&ScaffoldFlow::Messaging) {
+        for name in prompter.list("Messaging adapters (comma-separated, empty for none)")? {
+            let kind = prompter.ask_until(
+                &format!("  {name} kind (ingress, egress, ingress-egress)"),
+                Some("ingress-egress"),
+                |answer| match answer {
+                    "ingress" => Ok(MessagingAdapterKind::Ingress),
+                    "egress" => Ok(MessagingAdapterKind::Egress),
+                    "ingress-egress" => Ok(MessagingAdapterKind::IngressEgress),
+                    other => Err(anyhow!("unknown adapter kind `{other}`")),
+                },
+            )?;
+            let component = prompter.ask_until(
+                &format!("  {name} component"),
+                Some(format!("{name}@0.1.0").as_str()),
+                non_empty,
+            )?;
+            adapters.push(MessagingAdapter {
+                name,
+                kind,
+                component,
+                default_flow: Some(ScaffoldFlow::Messaging.file()),
+                custom_flow: None,
+                capabilities: None,
+            });
+        }
+    }
+
+    let mut providers = Vec::new();
+    if flows.contains(&ScaffoldFlow::Events) {
+        for name in prompter.list("Events providers (comma-separated, empty for none)")? {
+            let kind = prompter.ask_until(
+                &format!("  {name} kind (broker, source, sink, bridge)"),
+                Some("broker"),
+                |answer| answer.parse::<EventProviderKind>(),
+            )?;
+            let component = prompter.ask_until(
+                &format!("  {name} component"),
+                Some(format!("{name}@0.1.0").as_str()),
+                non_empty,
+            )?;
+            let topics = prompter.list(&format!("  {name} topics (comma-separated)"))?;
+            providers.push(
+                EventProviderSpec::new(name, kind, component)
+                    .with_default_flow(ScaffoldFlow::Events.file())
+                    .with_capabilities(EventProviderCapabilities::default().with_topics(topics)),
+            );
+        }
+    }
+
+    let sign = prompter.confirm("Generate a development signing keypair?", args.sign)?;
+    Ok(PackAnswers {
+        id,
+        version,
+        flows,
+        adapters,
+        providers,
+        sign,
+    })
 }
 
 #[derive(Debug, Serialize)]
@@ -94,17 +427,27 @@ struct SigningSummary {
 }
 
 pub fn handle(args: NewArgs, emit_json: bool) -> Result<()> {
-    if args.id.trim().is_empty() {
+    ensure_template_supported(&args.template)?;
+
+    let answers = if !args.yes && std::io::stdin().is_terminal() {
+        // Questions go to stderr so `--json` output stays parseable.
+        let mut prompter = Prompter {
+            input: std::io::stdin().lock(),
+            output: std::io::stderr(),
+        };
+        run_wizard(&mut prompter, &args)?
+    } else {
+        PackAnswers::defaults(&args)?
+    };
+    if answers.id.trim().is_empty() {
         anyhow::bail!("pack id must not be empty");
     }
 
-    ensure_template_supported(&args.template)?;
-
-    let target_dir = normalize(args.dir.unwrap_or_else(|| PathBuf::from(&args.id)));
+    let target_dir = normalize(args.dir.unwrap_or_else(|| PathBuf::from(&answers.id)));
     ensure_target_dir(&target_dir, args.force)?;
 
-    let pack_yaml = render_pack_spec(&args.id);
-    let readme = render_readme(&args.id, args.sign);
+    let pack_yaml = render_pack_spec(&answers);
+    let readme = render_readme(&answers.id, answers.sign);
 
     let mut created = Vec::new();
 
@@ -115,12 +458,18 @@ pub fn handle(args: NewArgs, emit_json: bool) -> Result<()> {
     )?;
     created.push(entry("pack.yaml", "file"));
 
-    write_file(
-        &target_dir.join(DEFAULT_FLOW_FILE),
-        DEFAULT_FLOW_CONTENT.as_bytes(),
-        args.force,
-    )?;
-    created.push(entry(DEFAULT_FLOW_FILE, "file"));
+    for flow in &answers.flows {
+        let file = flow.file();
+        write_file(
+            &target_dir.join(&file),
+            flow.contents().as_bytes(),
+            args.force,
+        )?;
+        created.push(ScaffoldEntry {
+            path: file,
+            kind: "file",
+        });
+    }
 
     write_file(&target_dir.join("README.md"), readme.as_bytes(), args.force)?;
     created.push(entry("README.md", "file"));
@@ -144,7 +493,7 @@ pub fn handle(args: NewArgs, emit_json: bool) -> Result<()> {
     created.push(entry("dist", "dir"));
 
     let mut signing_summary = None;
-    if args.sign {
+    if answers.sign {
         let keys = generate_dev_keys()?;
         let keys_dir = target_dir.join("keys");
         ensure_dir(&keys_dir)?;
@@ -186,17 +535,183 @@ pub fn handle(args: NewArgs, emit_json: bool) -> Result<()> {
     Ok(())
 }
 
+pub fn handle_add_flow(args: AddFlowArgs, emit_json: bool) -> Result<()> {
+    let id = args.id.trim();
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        anyhow::bail!("flow id `{id}` may only contain letters, digits, `_`, and `-`");
+    }
+    let pack_dir = normalize(args.input.clone());
+    let spec = manifest::load_spec(&pack_dir)?.spec;
+    let flow_file = format!("flows/{id}.ygtc");
+    if spec
+        .flow_files
+        .iter()
+        .any(|entry| Path::new(entry).file_stem().and_then(|stem| stem.to_str()) == Some(id))
+    {
+        anyhow::bail!("pack.yaml already lists a flow named `{id}`");
+    }
+
+    let contents = render_flow(id, &args, &spec)?;
+    let manifest_path = pack_dir.join("pack.yaml");
+    let original = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("failed to read {}", manifest_path.display()))?;
+    let mut updated = with_list_entry(&original, "flow_files", &flow_file);
+    if args.entry && !spec.entry_flows.iter().any(|entry| entry == id) {
+        updated = with_list_entry(&updated, "entry_flows", id);
+    }
+    let reparsed: PackSpec = serde_yaml_bw::from_str(&updated).context(
+        "pack.yaml could not be updated automatically; add the flow to flow_files by hand",
+    )?;
+    if reparsed.flow_files.last() != Some(&flow_file)
+        || reparsed.flow_files.len() != spec.flow_files.len() + 1
+        || (args.entry && !reparsed.entry_flows.iter().any(|entry| entry == id))
+    {
+        anyhow::bail!(
+            "pack.yaml could not be updated automatically; add the flow to flow_files by hand"
+        );
+    }
+
+    let flow_path = pack_dir.join(&flow_file);
+    write_file(&flow_path, contents.as_bytes(), args.force)?;
+    atomic::write(&manifest_path, updated.as_bytes(), Durability::Buffered)?;
+
+    // Nothing is left behind if the pack no longer passes lint.
+    let lint = match lint_pack(&pack_dir, None, None) {
+        Ok(lint) if lint.errors() == 0 => lint,
+        outcome => {
+            atomic::write(&manifest_path, original.as_bytes(), Durability::Buffered)?;
+            let _ = fs::remove_file(&flow_path);
+            return match outcome {
+                Ok(lint) => Err(anyhow!(
+                    "the new flow fails lint: {}",
+                    lint.diagnostics
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                )),
+                Err(err) => Err(err.context("the new flow fails validation")),
+            };
+        }
+    };
+
+    if emit_json {
+        let payload = serde_json::json!({
+            "flow": id,
+            "file": flow_file,
+            "template": args.template,
+            "entry": args.entry,
+            "diagnostics": lint.diagnostics,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        println!("Added flow {id} at {flow_file}");
+        for diagnostic in &lint.diagnostics {
+            println!(
+                "  {}[{}] {diagnostic}",
+                diagnostic.severity, diagnostic.rule
+            );
+        }
+    }
+    Ok(())
+}
+
+fn render_flow(id: &str, args: &AddFlowArgs, spec: &PackSpec) -> Result<String> {
+    let title = id
+        .split(['_', '-'])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let template = match args.template {
+        FlowTemplate::Messaging => MESSAGING_FLOW,
+        FlowTemplate::Events => EVENTS_FLOW,
+        FlowTemplate::McpExec => MCP_EXEC_FLOW,
+    };
+    let mut flow = template.replace("__ID__", id).replace("__TITLE__", &title);
+    if args.template == FlowTemplate::McpExec {
+        let component = match &args.component {
+            Some(component) => component.clone(),
+            None => spec
+                .mcp_components
+                .first()
+                .map(|component| component.id.clone())
+                .ok_or_else(|| anyhow!("pack.yaml declares no mcp_components; pass --component"))?,
+        };
+        flow = flow
+            .replace("__COMPONENT__", &component)
+            .replace("__ACTION__", &args.action);
+    }
+    Ok(flow)
+}
+
+/// Appends `item` to the top-level list `key` of a YAML document, keeping
+/// the rest of the text as written. Block lists keep their indentation,
+/// flow lists (`[a, b]`) stay on one line, and a missing key is added at the
+/// end.
+fn with_list_entry(yaml: &str, key: &str, item: &str) -> String {
+    let prefix = format!("{key}:");
+    let mut lines: Vec<String> = yaml.lines().map(str::to_string).collect();
+    let Some(index) = lines.iter().position(|line| line.starts_with(&prefix)) else {
+        let mut out = yaml.to_string();
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&format!("{prefix}\n- {item}\n"));
+        return out;
+    };
+    let rest = lines[index][prefix.len()..].trim().to_string();
+    if let Some(inner) = rest
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        let inner = inner.trim();
+        lines[index] = if inner.is_empty() {
+            format!("{prefix} [{item}]")
+        } else {
+            format!("{prefix} [{inner}, {item}]")
+        };
+    } else {
+        let mut last_item = None;
+        let mut indent = String::new();
+        for (offset, line) in lines[index + 1..].iter().enumerate() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("- ") || trimmed == "-" {
+                indent = line[..line.len() - trimmed.len()].to_string();
+                last_item = Some(index + 1 + offset);
+            } else if !(trimmed.is_empty() || trimmed.starts_with('#')) {
+                break;
+            }
+        }
+        lines.insert(last_item.unwrap_or(index) + 1, format!("{indent}- {item}"));
+    }
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
 fn ensure_template_supported(template: &TemplateKind) -> Result<()> {
     match template {
         TemplateKind::Minimal => Ok(()),
     }
 }
 
-fn render_pack_spec(id: &str) -> String {
+fn render_pack_spec(answers: &PackAnswers) -> String {
+    let id = &answers.id;
     let spec = PackSpec {
         pack_version: PACK_VERSION,
         id: id.to_string(),
-        version: DEFAULT_VERSION.to_string(),
+        version: answers.version.clone(),
         kind: Some(PackKind::Application),
         name: Some(id.to_string()),
         description: Some("Starter Greentic pack".into()),
@@ -205,15 +720,40 @@ fn render_pack_spec(id: &str) -> String {
         homepage: None,
         support: None,
         vendor: None,
-        flow_files: vec![DEFAULT_FLOW_FILE.to_string()],
+        tags: Vec::new(),
+        categories: Vec::new(),
+        flow_files: answers.flows.iter().map(|flow| flow.file()).collect(),
         template_dirs: Vec::new(),
-        entry_flows: vec!["welcome".to_string()],
+        entry_flows: answers
+            .flows
+            .iter()
+            .map(|flow| flow.id().to_string())
+            .collect(),
         imports_required: Vec::new(),
-        events: None,
+        events: (!answers.providers.is_empty()).then(|| EventsSection {
+            providers: answers.providers.clone(),
+        }),
         repo: None,
-        messaging: None,
+        messaging: (!answers.adapters.is_empty()).then(|| MessagingSection {
+            adapters: Some(answers.adapters.clone()),
+        }),
         interfaces: Vec::new(),
         mcp_components: Vec::new(),
+        media: None,
+        config: None,
+        config_schema: None,
+        config_defaults: None,
+        lifecycle: None,
+        quotas: None,
+        limits: None,
+        dispatch: None,
+        qos: None,
+        secrets: None,
+        capabilities: None,
+        metrics: None,
+        schedule: None,
+        classification: None,
+        locales: None,
         annotations: JsonMap::new(),
     };
     serde_yaml_bw::to_string(&spec).expect("pack spec serialises")
@@ -359,3 +899,104 @@ fn normalize(path: PathBuf) -> PathBuf {
     }
 }
 use greentic_types::PackKind;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_entries_keep_the_style_of_the_list() {
+        let block = "id: demo\nflow_files:\n  - flows/a.ygtc\n\n# entry points\nentry_flows: [a]\n";
+        assert_eq!(
+            with_list_entry(block, "flow_files", "flows/b.ygtc"),
+            "id: demo\nflow_files:\n  - flows/a.ygtc\n  - flows/b.ygtc\n\n# entry points\nentry_flows: [a]\n"
+        );
+        assert_eq!(
+            with_list_entry(block, "entry_flows", "b"),
+            "id: demo\nflow_files:\n  - flows/a.ygtc\n\n# entry points\nentry_flows: [a, b]\n"
+        );
+        assert_eq!(
+            with_list_entry("id: demo\nflow_files: []\n", "flow_files", "flows/b.ygtc"),
+            "id: demo\nflow_files: [flows/b.ygtc]\n"
+        );
+        assert_eq!(
+            with_list_entry("id: demo", "flow_files", "flows/b.ygtc"),
+            "id: demo\nflow_files:\n- flows/b.ygtc\n"
+        );
+    }
+
+    #[test]
+    fn wizard_answers_become_pack_yaml_sections() {
+        let args = NewArgs {
+            id: None,
+            dir: None,
+            template: TemplateKind::Minimal,
+            sign: false,
+            force: false,
+            yes: false,
+        };
+        let script = "demo.support\n\
+                      not-a-version\n\
+                      1.2.0\n\
+                      messaging, events\n\
+                      webchat\n\
+                      \n\
+                      \n\
+                      nats\n\
+                      \n\
+                      nats-provider@1.0.0\n\
+                      orders.created, orders.paid\n\
+                      y\n";
+        let mut prompter = Prompter {
+            input: std::io::Cursor::new(script),
+            output: Vec::new(),
+        };
+        let answers = run_wizard(&mut prompter, &args).expect("wizard");
+        let transcript = String::from_utf8(prompter.output).unwrap();
+        assert!(transcript.contains("not a semver version"), "{transcript}");
+
+        assert_eq!(answers.version, "1.2.0");
+        assert_eq!(
+            answers.flows,
+            vec![ScaffoldFlow::Messaging, ScaffoldFlow::Events]
+        );
+        assert!(answers.sign);
+        let adapter = &answers.adapters[0];
+        assert_eq!(adapter.kind, MessagingAdapterKind::IngressEgress);
+        assert_eq!(adapter.component, "webchat@0.1.0");
+
+        let spec: PackSpec = serde_yaml_bw::from_str(&render_pack_spec(&answers)).unwrap();
+        spec.validate().expect("generated spec is valid");
+        assert_eq!(spec.id, "demo.support");
+        assert_eq!(spec.entry_flows, vec!["welcome", "on_event"]);
+        let provider = &spec.events.expect("events").providers[0];
+        assert_eq!(provider.kind, EventProviderKind::Broker);
+        assert_eq!(provider.component, "nats-provider@1.0.0");
+        assert_eq!(
+            provider.capabilities.topics,
+            vec!["orders.created", "orders.paid"]
+        );
+        assert_eq!(
+            provider.default_flow.as_deref(),
+            Some("flows/on_event.ygtc")
+        );
+    }
+
+    #[test]
+    fn wizard_stops_when_input_ends() {
+        let args = NewArgs {
+            id: Some("demo".into()),
+            dir: None,
+            template: TemplateKind::Minimal,
+            sign: false,
+            force: false,
+            yes: false,
+        };
+        let mut prompter = Prompter {
+            input: std::io::Cursor::new("\n"),
+            output: Vec::new(),
+        };
+        let err = run_wizard(&mut prompter, &args).unwrap_err();
+        assert!(err.to_string().contains("input ended"), "{err}");
+    }
+}
@@ -0,0 +1,93 @@
+//! Permission prompts embedded in the manifest at build time.
+//!
+//! Translations live in the pack's i18n bundles, one flat JSON object per
+//! locale at `i18n/<locale>.json`. Only `permission.<root>` keys are read
+//! here; bundles may hold other strings for the pack's own use, and are
+//! embedded in the `.gtpack` as they are for hosts rendering templates.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use greentic_pack::capabilities::flow_requirements;
+pub use greentic_pack::permissions::I18N_DIR;
+use greentic_pack::permissions::{I18nBundle, MESSAGE_PREFIX, PermissionSummary, validate_locale};
+use serde_json::Value as JsonValue;
+
+use crate::flows::FlowAsset;
+use crate::manifest::PackSpec;
+
+/// Reads the bundles under `pack_dir/i18n` as raw bytes by locale.
+pub fn bundle_files(pack_dir: &Path) -> Result<BTreeMap<String, Vec<u8>>> {
+    let dir = pack_dir.join(I18N_DIR);
+    let mut files = BTreeMap::new();
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+    let entries =
+        fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("failed to read {}", dir.display()))?
+            .path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let locale = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()
+            .to_string();
+        validate_locale(&locale).with_context(|| format!("i18n bundle {}", path.display()))?;
+        let bytes =
+            fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        files.insert(locale, bytes);
+    }
+    Ok(files)
+}
+
+/// Loads `i18n/<locale>.json` from `pack_dir`, keeping permission messages.
+pub fn load_bundles(pack_dir: &Path) -> Result<BTreeMap<String, I18nBundle>> {
+    let mut bundles = BTreeMap::new();
+    for (locale, bytes) in bundle_files(pack_dir)? {
+        let path = pack_dir.join(I18N_DIR).join(format!("{locale}.json"));
+        let messages: BTreeMap<String, JsonValue> = serde_json::from_slice(&bytes)
+            .with_context(|| format!("{} is not a JSON object of messages", path.display()))?;
+        let mut bundle = I18nBundle::new();
+        for (key, value) in messages {
+            if !key.starts_with(MESSAGE_PREFIX) {
+                continue;
+            }
+            let Some(text) = value.as_str().filter(|text| !text.trim().is_empty()) else {
+                bail!("{}: `{key}` must be a non-empty string", path.display());
+            };
+            bundle.insert(key, text.to_string());
+        }
+        bundles.insert(locale, bundle);
+    }
+    Ok(bundles)
+}
+
+/// The permission summary of the pack's flows, or `None` when they need
+/// nothing a host has to grant.
+pub fn summarize(
+    pack_dir: &Path,
+    spec: &PackSpec,
+    flows: &[FlowAsset],
+) -> Result<Option<PermissionSummary>> {
+    let bundles = load_bundles(pack_dir)?;
+    let requirements = flows
+        .iter()
+        .map(|flow| {
+            let requirements = flow_requirements(&flow.raw, &spec.imports_required)
+                .with_context(|| format!("flow `{}`", flow.bundle.id))?;
+            Ok((flow.bundle.id.as_str(), requirements))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let summary = PermissionSummary::build(
+        requirements.iter().map(|(flow, req)| (*flow, req)),
+        &bundles,
+    );
+    Ok((!summary.permissions.is_empty()).then_some(summary))
+}
@@ -0,0 +1,166 @@
+//! Profile data hosts report about a running pack, used to guide its build.
+//!
+//! A profile counts how often each flow ran and each template rendered:
+//!
+//! ```json
+//! {
+//!   "flows": { "weather_bot": 1200, "alerts": 40 },
+//!   "templates": { "templates/weather_now.hbs": 900 }
+//! }
+//! ```
+//!
+//! `packc build --profile-data` turns it into a [`ProfileGuide`]: flows and
+//! templates are embedded hottest first, so the data a host touches most sits
+//! together, and the hottest flows that together account for [`HOT_SHARE`] of
+//! the runs are marked for eager instantiation.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::flows::FlowAsset;
+use crate::templates::TemplateAsset;
+
+/// Share of all reported runs, in percent, that the eager flows cover.
+pub const HOT_SHARE: u64 = 80;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileData {
+    /// Runs by flow id.
+    #[serde(default)]
+    pub flows: BTreeMap<String, u64>,
+    /// Renders by template logical path.
+    #[serde(default)]
+    pub templates: BTreeMap<String, u64>,
+}
+
+/// How a profile-guided build lays out a pack.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileGuide {
+    /// Flow ids, hottest first; flows not listed follow in their usual order.
+    pub flow_order: Vec<String>,
+    /// Template logical paths, hottest first.
+    pub template_order: Vec<String>,
+    /// Flows hosts should instantiate when the pack loads.
+    pub eager_flows: Vec<String>,
+    /// BLAKE3 digest of the profile the guide was derived from.
+    pub profile_blake3: String,
+}
+
+impl ProfileGuide {
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let data: ProfileData = serde_json::from_slice(&bytes)
+            .with_context(|| format!("{} is not valid profile data", path.display()))?;
+        Ok(Self::from_data(
+            &data,
+            blake3::hash(&bytes).to_hex().to_string(),
+        ))
+    }
+
+    pub fn from_data(data: &ProfileData, profile_blake3: String) -> Self {
+        let flow_order = hottest_first(&data.flows);
+        let total: u64 = data.flows.values().sum();
+        let mut covered = 0u64;
+        let mut eager_flows = Vec::new();
+        for id in &flow_order {
+            if covered * 100 >= total * HOT_SHARE {
+                break;
+            }
+            covered += data.flows[id];
+            eager_flows.push(id.clone());
+        }
+        Self {
+            flow_order,
+            template_order: hottest_first(&data.templates),
+            eager_flows,
+            profile_blake3,
+        }
+    }
+
+    /// Orders `flows` hottest first, warning about profiled flows the pack
+    /// does not have, e.g. because the profile predates a rename.
+    pub fn order_flows(&self, flows: &mut [FlowAsset]) {
+        warn_unknown(
+            "flow",
+            &self.flow_order,
+            flows.iter().map(|flow| flow.bundle.id.as_str()),
+        );
+        flows.sort_by_key(|flow| rank(&self.flow_order, &flow.bundle.id));
+    }
+
+    pub fn order_templates(&self, templates: &mut [TemplateAsset]) {
+        warn_unknown(
+            "template",
+            &self.template_order,
+            templates
+                .iter()
+                .map(|template| template.logical_path.as_str()),
+        );
+        templates.sort_by_key(|template| rank(&self.template_order, &template.logical_path));
+    }
+
+    pub fn is_eager(&self, flow_id: &str) -> bool {
+        self.eager_flows.iter().any(|id| id == flow_id)
+    }
+}
+
+/// Keys with a non-zero count, highest count first and by key on ties.
+fn hottest_first(counts: &BTreeMap<String, u64>) -> Vec<String> {
+    let mut keys: Vec<(&String, u64)> = counts
+        .iter()
+        .filter(|(_, count)| **count > 0)
+        .map(|(key, count)| (key, *count))
+        .collect();
+    keys.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    keys.into_iter().map(|(key, _)| key.clone()).collect()
+}
+
+/// Position of `key` in `order`; unlisted keys sort after every listed one
+/// and, as the sorts are stable, keep their relative order.
+fn rank(order: &[String], key: &str) -> usize {
+    order
+        .iter()
+        .position(|listed| listed == key)
+        .unwrap_or(order.len())
+}
+
+fn warn_unknown<'a>(what: &str, order: &[String], present: impl Iterator<Item = &'a str>) {
+    let present: Vec<&str> = present.collect();
+    for key in order {
+        if !present.contains(&key.as_str()) {
+            warn!(%key, "profile data names a {what} the pack does not have");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hottest_flows_covering_the_hot_share_are_eager() {
+        let data: ProfileData = serde_json::from_value(serde_json::json!({
+            "flows": { "alerts": 150, "weather_bot": 700, "digest": 150, "setup": 0 },
+            "templates": { "templates/a.hbs": 3, "templates/b.hbs": 9 },
+        }))
+        .unwrap();
+        let guide = ProfileGuide::from_data(&data, "digest".into());
+        assert_eq!(guide.flow_order, ["weather_bot", "alerts", "digest"]);
+        assert_eq!(guide.eager_flows, ["weather_bot", "alerts"]);
+        assert_eq!(guide.template_order, ["templates/b.hbs", "templates/a.hbs"]);
+        assert!(!guide.is_eager("setup"));
+
+        let mut ids = vec!["setup", "digest", "weather_bot", "onboarding"];
+        ids.sort_by_key(|id| rank(&guide.flow_order, id));
+        assert_eq!(ids, ["weather_bot", "digest", "setup", "onboarding"]);
+
+        let empty = ProfileGuide::from_data(&ProfileData::default(), String::new());
+        assert!(empty.eager_flows.is_empty());
+    }
+}
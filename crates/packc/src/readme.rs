@@ -0,0 +1,49 @@
+use crate::flows::FlowAsset;
+use crate::media::MediaAsset;
+use crate::templates::TemplateAsset;
+use anyhow::{Context, Result, bail};
+use greentic_pack::readme::{README_PATH, relative_links};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Loads `README.md` from the pack root (if present) and checks that every
+/// relative link points at a file that is embedded in the pack.
+pub fn load_readme(
+    pack_dir: &Path,
+    flows: &[FlowAsset],
+    templates: &[TemplateAsset],
+    media: &[MediaAsset],
+) -> Result<Option<String>> {
+    let path = pack_dir.join(README_PATH);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let markdown =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+
+    let embedded: BTreeSet<String> = flows
+        .iter()
+        .map(|flow| flow.relative_path.to_string_lossy().replace('\\', "/"))
+        .chain(templates.iter().map(|tpl| tpl.logical_path.clone()))
+        .chain(
+            media
+                .iter()
+                .map(|asset| asset.media.path.trim_start_matches("./").to_string()),
+        )
+        .collect();
+
+    let broken: Vec<String> = relative_links(&markdown)
+        .into_iter()
+        .filter(|link| !embedded.contains(link))
+        .collect();
+    if !broken.is_empty() {
+        bail!(
+            "{} links to files that are not embedded in the pack: {}",
+            README_PATH,
+            broken.join(", ")
+        );
+    }
+
+    Ok(Some(markdown))
+}
@@ -0,0 +1,225 @@
+use crate::build::{self, BuildOptions, PrebuiltComponent};
+use crate::build_lock::WaitPolicy;
+use crate::digest::DigestAlgorithm;
+use crate::imports::catalog::{CATALOG_FILE, CATALOG_FORMAT};
+use crate::imports::{CatalogPackage, InterfaceCatalog};
+use crate::manifest;
+use crate::profile::ProfileGuide;
+use crate::templates::SyntaxCheck;
+use anyhow::{Context, Result, anyhow, bail};
+use greentic_pack::atomic::Durability;
+use greentic_pack::builder::{MANIFEST_PATH, MANIFEST_ZSTD_PATH, ManifestEncoding, Provenance};
+use greentic_pack::extract::{ExtractOptions, extract_pack};
+use greentic_pack::{SigningPolicy, open_pack};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tracing::info;
+
+/// Permissive taxonomy used while rebuilding: the archive's tags and categories
+/// were already checked against whatever taxonomy produced it.
+const PASSTHROUGH_TAXONOMY: &str = "categories = []\n";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RebuildOutcome {
+    pub from: PathBuf,
+    pub out: PathBuf,
+    /// Content digest of the input archive (see `PackLoad::content_digest`).
+    pub original_digest: String,
+    pub rebuilt_digest: String,
+}
+
+impl RebuildOutcome {
+    pub fn is_equivalent(&self) -> bool {
+        self.original_digest == self.rebuilt_digest
+    }
+}
+
+/// Extracts `from`, runs `packc build` on the result, and writes the new
+/// archive to `out`. The original build timestamp, provenance, and compiled
+/// pack component are carried over, so an unchanged pack rebuilds to the same
+/// content digest.
+pub fn rebuild(from: &Path, out: &Path, durability: Durability) -> Result<RebuildOutcome> {
+    let original = open_pack(from, SigningPolicy::DevOk)
+        .map_err(|err| anyhow!("failed to open {}: {}", from.display(), err.message))?;
+
+    let workspace = tempfile::tempdir().context("failed to create rebuild workspace")?;
+    let source_dir = workspace.path().join("source");
+    let report = extract_pack(
+        from,
+        &source_dir,
+        ExtractOptions {
+            verify: Some(SigningPolicy::DevOk),
+            pack_yaml: true,
+        },
+    )?;
+    for warning in &report.warnings {
+        tracing::warn!("{warning}");
+    }
+
+    let spec = manifest::load_spec(&source_dir)?.spec;
+    if !spec.mcp_components.is_empty() {
+        bail!(
+            "{} declares mcp_components; composed MCP components cannot be rebuilt from an archive yet",
+            from.display()
+        );
+    }
+
+    let component = original
+        .manifest
+        .components
+        .iter()
+        .find(|component| component.name == "pack_component")
+        .ok_or_else(|| anyhow!("{} does not contain a pack_component", from.display()))?;
+    let prebuilt = PrebuiltComponent {
+        wasm: source_dir.join(&component.file_wasm),
+        version: component.version.clone(),
+    };
+
+    let created_at = OffsetDateTime::parse(&original.manifest.meta.created_at_utc, &Rfc3339)
+        .with_context(|| {
+            format!(
+                "manifest created_at_utc `{}` is not an RFC 3339 timestamp",
+                original.manifest.meta.created_at_utc
+            )
+        })?;
+    let provenance: Provenance = original
+        .file("provenance.json")
+        .map(serde_json::from_slice)
+        .transpose()
+        .context("provenance.json is invalid")?
+        .ok_or_else(|| anyhow!("{} has no provenance.json", from.display()))?;
+
+    // A profile-guided layout is recovered from the archive itself: flows and
+    // template assets were written in the order the profile gave them.
+    let profile = provenance
+        .profile_blake3
+        .clone()
+        .map(|profile_blake3| ProfileGuide {
+            flow_order: original
+                .manifest
+                .flows
+                .iter()
+                .map(|flow| flow.id.clone())
+                .collect(),
+            template_order: original
+                .sbom
+                .iter()
+                .filter_map(|entry| entry.path.strip_prefix("assets/"))
+                .map(str::to_string)
+                .collect(),
+            eager_flows: original
+                .manifest
+                .flows
+                .iter()
+                .filter(|flow| flow.eager)
+                .map(|flow| flow.id.clone())
+                .collect(),
+            profile_blake3,
+        });
+
+    let taxonomy = workspace.path().join("taxonomy.toml");
+    fs::write(&taxonomy, PASSTHROUGH_TAXONOMY)
+        .with_context(|| format!("failed to write {}", taxonomy.display()))?;
+
+    let interface_catalog = match pinned_catalog(&original.manifest.meta.annotations) {
+        Some(catalog) => {
+            let path = workspace.path().join(CATALOG_FILE);
+            fs::write(&path, catalog.to_toml()?)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            Some(path)
+        }
+        None => None,
+    };
+
+    let manifest_encoding = match (
+        original.file(MANIFEST_PATH).is_some(),
+        original.file(MANIFEST_ZSTD_PATH).is_some(),
+    ) {
+        (true, true) => ManifestEncoding::Both,
+        (false, true) => ManifestEncoding::Zstd,
+        _ => ManifestEncoding::Plain,
+    };
+
+    let dist = workspace.path().join("dist");
+    let opts = BuildOptions {
+        pack_dir: source_dir.clone(),
+        component_out: dist.join("pack.wasm"),
+        manifest_out: dist.join("manifest.cbor"),
+        sbom_out: dist.join("sbom.cdx.json"),
+        gtpack_out: Some(out.to_path_buf()),
+        manifest_encoding,
+        component_data: source_dir
+            .join(".packc")
+            .join("pack_component")
+            .join("src")
+            .join("data.rs"),
+        profile_data: None,
+        profile,
+        taxonomy: Some(taxonomy),
+        interface_catalog,
+        locked: false,
+        dry_run: false,
+        // The archive was accepted when it was built; reproduce it as is.
+        template_syntax: SyntaxCheck::Skip,
+        wait: WaitPolicy::NoWait,
+        durability,
+        digest: DigestAlgorithm::default(),
+        prebuilt_component: Some(prebuilt),
+        created_at: Some(created_at),
+        provenance: Some(provenance),
+        hermetic: None,
+        remote_build: None,
+        force: false,
+        assert_no_wasi: false,
+    };
+    info!(from = %from.display(), out = %out.display(), "rebuilding pack from archive");
+    build::run(&opts)?;
+
+    let rebuilt = open_pack(out, SigningPolicy::DevOk)
+        .map_err(|err| anyhow!("rebuilt archive failed verification: {}", err.message))?;
+
+    Ok(RebuildOutcome {
+        from: from.to_path_buf(),
+        out: out.to_path_buf(),
+        original_digest: original.content_digest(),
+        rebuilt_digest: rebuilt.content_digest(),
+    })
+}
+
+/// Catalog holding exactly the interface versions the archive was built
+/// against (its `imports_resolved` annotation), so imports resolve the same
+/// way whichever catalog produced the original.
+fn pinned_catalog(
+    annotations: &serde_json::Map<String, serde_json::Value>,
+) -> Option<InterfaceCatalog> {
+    let pinned = annotations.get("imports_resolved")?.as_array()?;
+    let mut packages: BTreeMap<(&str, &str), Vec<String>> = BTreeMap::new();
+    for entry in pinned.iter().filter_map(serde_json::Value::as_str) {
+        let Some((path, version)) = entry.rsplit_once('@') else {
+            continue;
+        };
+        let Some((package, interface)) = path.split_once('/') else {
+            continue;
+        };
+        packages
+            .entry((package, version))
+            .or_default()
+            .push(interface.to_string());
+    }
+    Some(InterfaceCatalog {
+        format: CATALOG_FORMAT,
+        source: Some("pinned by the original archive".into()),
+        packages: packages
+            .into_iter()
+            .map(|((name, version), interfaces)| CatalogPackage {
+                name: name.to_string(),
+                version: version.to_string(),
+                interfaces,
+            })
+            .collect(),
+    })
+}
@@ -0,0 +1,335 @@
+//! Publishing and pulling `.gtpack` archives through a directory registry.
+//!
+//! Archives travel as content-defined chunks (see
+//! [`greentic_pack::chunking`]), so a new version of a pack with a large
+//! component only moves the chunks that changed. The registry layout is:
+//!
+//! ```text
+//! <registry>/chunks/<hh>/<blake3>
+//! <registry>/packs/<pack_id>/<version>.gtpack
+//! <registry>/packs/<pack_id>/<version>.gtpack.chunks.json
+//! <registry>/packs/<pack_id>/<version>.gtpack.yanked.json
+//! ```
+//!
+//! A `yanked.json` marker records that a version was withdrawn; the archive
+//! and its chunks stay, so packs already depending on it keep working, but
+//! `pull` refuses it unless asked to allow yanked versions.
+//!
+//! Every chunk is checked against its digest on both sides. Pulled chunks go
+//! to a local cache before the archive is assembled, so an interrupted pull
+//! resumes where it stopped, and an older copy of the archive at the output
+//! path seeds the cache with the chunks it shares with the new one.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use greentic_pack::atomic::{self, Durability};
+use greentic_pack::chunking::{ChunkIndex, ChunkStore, ChunkerConfig};
+use greentic_pack::{SigningPolicy, open_pack};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tracing::info;
+
+use crate::tools;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferStatus {
+    Published,
+    Unchanged,
+    Pulled,
+}
+
+/// Outcome of a publish or pull.
+#[derive(Debug, Clone, Serialize)]
+pub struct Transfer {
+    pub status: TransferStatus,
+    pub pack_id: String,
+    pub version: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub chunks: usize,
+    /// Chunks that had to be copied; the rest were already on the other side.
+    pub transferred_chunks: usize,
+    pub transferred_bytes: u64,
+    /// Set when a pull fetched a yanked version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub yanked: Option<Yank>,
+}
+
+/// Marker of a yanked version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Yank {
+    /// RFC 3339 time of the yank.
+    pub yanked_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Path of the yank marker of the archive at `archive`.
+pub fn yank_path(archive: &Path) -> PathBuf {
+    let mut name = archive.file_name().unwrap_or_default().to_os_string();
+    name.push(".yanked.json");
+    archive.with_file_name(name)
+}
+
+/// The yank marker of the archive at `archive`, if it was yanked.
+pub fn read_yank(archive: &Path) -> Result<Option<Yank>> {
+    let path = yank_path(archive);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes = fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let yank = serde_json::from_slice(&bytes)
+        .with_context(|| format!("{} is not a valid yank marker", path.display()))?;
+    Ok(Some(yank))
+}
+
+/// Default local chunk cache: `~/.cache/greentic/chunks`.
+pub fn default_cache() -> Option<PathBuf> {
+    tools::cache_root().map(|root| root.join("chunks"))
+}
+
+fn archive_path(registry: &Path, pack_id: &str, version: &str) -> PathBuf {
+    registry
+        .join("packs")
+        .join(pack_id)
+        .join(format!("{version}.gtpack"))
+}
+
+/// Versions of `pack_id` published in `registry`, each with its yank marker.
+pub fn published_versions(
+    registry: &Path,
+    pack_id: &str,
+) -> Result<Vec<(semver::Version, Option<Yank>)>> {
+    let dir = registry.join("packs").join(pack_id);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut versions = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let Some(version) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".gtpack"))
+            .and_then(|version| semver::Version::parse(version).ok())
+        else {
+            continue;
+        };
+        // Only versions whose publish finished have a chunk index.
+        if ChunkIndex::path_for(&path).exists() {
+            versions.push((version, read_yank(&path)?));
+        }
+    }
+    versions.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(versions)
+}
+
+/// Copies the chunks of `gtpack` the registry lacks and assembles the
+/// archive there. A version can be published again only with identical
+/// contents.
+pub fn publish(registry: &Path, gtpack: &Path) -> Result<Transfer> {
+    let load = open_pack(gtpack, SigningPolicy::DevOk)
+        .map_err(|err| anyhow!("{} failed verification: {}", gtpack.display(), err.message))?;
+    let pack_id = load.manifest.meta.pack_id.clone();
+    let version = load.manifest.meta.version.to_string();
+    let data = fs::read(gtpack).with_context(|| format!("failed to read {}", gtpack.display()))?;
+    let index = ChunkIndex::build(&data, &ChunkerConfig::default())?;
+
+    let target = archive_path(registry, &pack_id, &version);
+    let index_path = ChunkIndex::path_for(&target);
+    let mut transfer = Transfer {
+        status: TransferStatus::Published,
+        pack_id,
+        version,
+        path: target.clone(),
+        size: index.size,
+        chunks: index.chunks.len(),
+        transferred_chunks: 0,
+        transferred_bytes: 0,
+        yanked: None,
+    };
+    if index_path.exists() {
+        let published = ChunkIndex::load(&index_path)?;
+        if published.blake3 != index.blake3 {
+            bail!(
+                "{}@{} is already published with different contents; bump the version",
+                transfer.pack_id,
+                transfer.version
+            );
+        }
+        transfer.status = TransferStatus::Unchanged;
+        return Ok(transfer);
+    }
+
+    let store = ChunkStore::new(registry.join("chunks"));
+    for chunk in &index.chunks {
+        if store.contains(&chunk.blake3) {
+            continue;
+        }
+        let range = chunk.offset as usize..(chunk.offset + chunk.size) as usize;
+        store.write(&data[range])?;
+        transfer.transferred_chunks += 1;
+        transfer.transferred_bytes += chunk.size;
+    }
+
+    let assembled = index.assemble(&store)?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    atomic::write(&target, &assembled, Durability::default())?;
+    // The index goes last: its presence marks the version as published.
+    index.save(&index_path, Durability::default())?;
+    info!(
+        pack = %transfer.pack_id,
+        version = %transfer.version,
+        chunks = transfer.chunks,
+        uploaded = transfer.transferred_chunks,
+        "published pack"
+    );
+    Ok(transfer)
+}
+
+/// Marks the published `pack_id@version` as yanked. Only the marker is
+/// written; yanking a version again keeps the first marker.
+pub fn yank(registry: &Path, pack_id: &str, version: &str, reason: Option<String>) -> Result<Yank> {
+    let archive = archive_path(registry, pack_id, version);
+    if !ChunkIndex::path_for(&archive).exists() {
+        bail!(
+            "{pack_id}@{version} is not published in {}",
+            registry.display()
+        );
+    }
+    if let Some(existing) = read_yank(&archive)? {
+        return Ok(existing);
+    }
+    let yank = Yank {
+        yanked_at: OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .context("failed to format the yank time")?,
+        reason,
+    };
+    atomic::write(
+        &yank_path(&archive),
+        &serde_json::to_vec_pretty(&yank)?,
+        Durability::default(),
+    )?;
+    info!(pack = %pack_id, version = %version, "yanked pack");
+    Ok(yank)
+}
+
+/// Fetches `pack_id@version` into `out`, copying only chunks that are in
+/// neither `cache` nor the file already at `out`. Yanked versions are refused
+/// unless `allow_yanked` is set.
+pub fn pull(
+    registry: &Path,
+    pack_id: &str,
+    version: &str,
+    out: &Path,
+    cache: &ChunkStore,
+    allow_yanked: bool,
+) -> Result<Transfer> {
+    let source = archive_path(registry, pack_id, version);
+    let index_path = ChunkIndex::path_for(&source);
+    if !index_path.exists() {
+        bail!(
+            "{pack_id}@{version} is not published in {}",
+            registry.display()
+        );
+    }
+    let yanked = read_yank(&source)?;
+    if let Some(yank) = &yanked
+        && !allow_yanked
+    {
+        let reason = yank
+            .reason
+            .as_deref()
+            .map(|reason| format!(" ({reason})"))
+            .unwrap_or_default();
+        bail!(
+            "{pack_id}@{version} was yanked on {}{reason}; pass --allow-yanked to pull it anyway",
+            yank.yanked_at
+        );
+    }
+    let index = ChunkIndex::load(&index_path)?;
+
+    if let Ok(existing) = fs::read(out) {
+        seed_cache(&existing, &index, cache)?;
+    }
+
+    let remote = ChunkStore::new(registry.join("chunks"));
+    let mut transfer = Transfer {
+        status: TransferStatus::Pulled,
+        pack_id: pack_id.to_string(),
+        version: version.to_string(),
+        path: out.to_path_buf(),
+        size: index.size,
+        chunks: index.chunks.len(),
+        transferred_chunks: 0,
+        transferred_bytes: 0,
+        yanked,
+    };
+    for chunk in &index.chunks {
+        if cache.read(&chunk.blake3)?.is_some() {
+            continue;
+        }
+        // ChunkStore::read drops chunks that fail their digest.
+        let bytes = remote.read(&chunk.blake3)?.with_context(|| {
+            format!(
+                "registry chunk {} of {pack_id}@{version} is missing or corrupt",
+                chunk.blake3
+            )
+        })?;
+        cache.write(&bytes)?;
+        transfer.transferred_chunks += 1;
+        transfer.transferred_bytes += chunk.size;
+    }
+
+    let data = index.assemble(cache)?;
+    let parent = match out.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent).with_context(|| format!("failed to create {}", parent.display()))?;
+    // Checked before it replaces `out`, which may hold an older version.
+    let staged = tempfile::NamedTempFile::new_in(parent)
+        .with_context(|| format!("failed to stage the archive in {}", parent.display()))?;
+    fs::write(staged.path(), &data).context("failed to write the pulled archive")?;
+    let load = open_pack(staged.path(), SigningPolicy::DevOk)
+        .map_err(|err| anyhow!("pulled archive failed verification: {}", err.message))?;
+    if load.manifest.meta.pack_id != pack_id || load.manifest.meta.version.to_string() != version {
+        bail!(
+            "registry returned {}@{} for {pack_id}@{version}",
+            load.manifest.meta.pack_id,
+            load.manifest.meta.version
+        );
+    }
+    staged
+        .persist(out)
+        .with_context(|| format!("failed to write {}", out.display()))?;
+    info!(
+        pack = %pack_id,
+        version = %version,
+        chunks = transfer.chunks,
+        downloaded = transfer.transferred_chunks,
+        "pulled pack"
+    );
+    Ok(transfer)
+}
+
+/// Adds the chunks of `existing` that `index` needs to `cache`.
+fn seed_cache(existing: &[u8], index: &ChunkIndex, cache: &ChunkStore) -> Result<()> {
+    let local = ChunkIndex::build(existing, &ChunkerConfig::default())?;
+    for chunk in &local.chunks {
+        let needed = index.chunks.iter().any(|c| c.blake3 == chunk.blake3);
+        if needed && !cache.contains(&chunk.blake3) {
+            let range = chunk.offset as usize..(chunk.offset + chunk.size) as usize;
+            cache.write(&existing[range])?;
+        }
+    }
+    Ok(())
+}
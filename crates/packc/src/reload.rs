@@ -0,0 +1,193 @@
+//! Live reload of a pack's flows, for `packc run --watch`.
+//!
+//! [`FlowWatch`] polls `pack.yaml` and the flow files it lists, and reports
+//! the files whose contents changed, so saving a file unchanged rebuilds
+//! nothing. The runner then rebuilds the pack, where the component cache
+//! skips compiling what did not change, and swaps the new component into
+//! its session. A run caught mid-way carries on from its checkpoint when
+//! [`resumable`] says the new flow still fits it, and starts over otherwise.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use greentic_pack::flow_schema::flow_schemas;
+use serde::Deserialize;
+use serde_yaml_bw::Value as YamlValue;
+
+use crate::digest::DigestAlgorithm;
+use crate::flows;
+use crate::manifest;
+
+/// Watched files of a pack and their contents when last polled.
+#[derive(Debug)]
+pub struct FlowWatch {
+    pack_dir: PathBuf,
+    /// `None` for files that could not be read.
+    files: BTreeMap<PathBuf, Option<String>>,
+}
+
+impl FlowWatch {
+    pub fn new(pack_dir: &Path) -> Result<Self> {
+        let mut watch = Self {
+            pack_dir: pack_dir.to_path_buf(),
+            files: BTreeMap::new(),
+        };
+        watch.files = watch
+            .listed()?
+            .into_iter()
+            .map(|path| {
+                let contents = fs::read_to_string(&path).ok();
+                (path, contents)
+            })
+            .collect();
+        Ok(watch)
+    }
+
+    /// The watched files whose contents changed since the last poll. When
+    /// `pack.yaml` is among them, the flow files it now lists are watched.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (path, contents) in &mut self.files {
+            let current = fs::read_to_string(path).ok();
+            if current != *contents {
+                *contents = current;
+                changed.push(path.clone());
+            }
+        }
+        if changed.contains(&self.manifest_path()) {
+            // A pack.yaml that does not parse keeps the files it listed.
+            if let Ok(listed) = self.listed() {
+                self.files.retain(|path, _| listed.contains(path));
+                for path in listed {
+                    if !self.files.contains_key(&path) {
+                        let contents = fs::read_to_string(&path).ok();
+                        self.files.insert(path.clone(), contents);
+                        changed.push(path);
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    fn manifest_path(&self) -> PathBuf {
+        self.pack_dir.join("pack.yaml")
+    }
+
+    /// `pack.yaml` and the flow files it lists.
+    fn listed(&self) -> Result<Vec<PathBuf>> {
+        let spec = manifest::load_spec(&self.pack_dir)?.spec;
+        let mut files = vec![self.manifest_path()];
+        files.extend(spec.flow_files.iter().map(|file| self.pack_dir.join(file)));
+        Ok(files)
+    }
+}
+
+/// The source of the pack's flow `flow_id`, once the pack's flows validate.
+pub fn flow_source(pack_dir: &Path, flow_id: &str) -> Result<String> {
+    let spec = manifest::load_spec(pack_dir)?.spec;
+    flows::load_flows(pack_dir, &spec, DigestAlgorithm::default())?
+        .into_iter()
+        .find(|flow| flow.bundle.id == flow_id)
+        .map(|flow| flow.raw)
+        .ok_or_else(|| anyhow!("the pack has no flow `{flow_id}`"))
+}
+
+/// Whether a run checkpointed under the flow source `old` can carry on under
+/// `new`: the flow keeps its input and output schemas and still has the node
+/// the run continues with.
+pub fn resumable(old: &str, new: &str, checkpoint: &str) -> bool {
+    #[derive(Deserialize)]
+    struct Checkpoint {
+        node: String,
+    }
+
+    let (Ok(before), Ok(after)) = (flow_schemas(old), flow_schemas(new)) else {
+        return false;
+    };
+    let Ok(checkpoint) = serde_json::from_str::<Checkpoint>(checkpoint) else {
+        return false;
+    };
+    let has_node = serde_yaml_bw::from_str::<YamlValue>(new)
+        .ok()
+        .and_then(|flow| {
+            flow.get("nodes")?
+                .as_mapping()?
+                .get(checkpoint.node.as_str())
+                .cloned()
+        })
+        .is_some();
+    before == after && has_node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const FLOW: &str = r#"id: greet
+type: messaging
+start: hello
+nodes:
+  hello:
+    flow.return:
+      payload:
+        text: "Hello {{in.name}}"
+    routing:
+      - out: true
+"#;
+
+    fn pack(dir: &Path, flows: &[&str]) {
+        let listed: Vec<String> = flows.iter().map(|flow| format!("  - {flow}\n")).collect();
+        fs::write(
+            dir.join("pack.yaml"),
+            format!(
+                "packVersion: 1\nid: demo.watch\nversion: 0.1.0\nflow_files:\n{}",
+                listed.concat()
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn reports_files_whose_contents_changed() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("flows")).unwrap();
+        let (greet, bye) = (
+            dir.path().join("flows/greet.ygtc"),
+            dir.path().join("flows/bye.ygtc"),
+        );
+        fs::write(&greet, FLOW).unwrap();
+        fs::write(&bye, FLOW).unwrap();
+        pack(dir.path(), &["flows/greet.ygtc"]);
+
+        let mut watch = FlowWatch::new(dir.path()).unwrap();
+        assert!(watch.poll().is_empty());
+        fs::write(&greet, FLOW).unwrap();
+        assert!(watch.poll().is_empty(), "rewritten unchanged");
+        fs::write(&bye, FLOW.replace("Hello", "Bye")).unwrap();
+        assert!(watch.poll().is_empty(), "not listed in pack.yaml");
+        fs::write(&greet, FLOW.replace("Hello", "Hi")).unwrap();
+        assert_eq!(watch.poll(), [greet]);
+
+        pack(dir.path(), &["flows/greet.ygtc", "flows/bye.ygtc"]);
+        assert_eq!(watch.poll(), [dir.path().join("pack.yaml"), bye.clone()]);
+        fs::write(&bye, FLOW).unwrap();
+        assert_eq!(watch.poll(), [bye]);
+    }
+
+    #[test]
+    fn runs_resume_while_the_schema_and_node_stay() {
+        let checkpoint = r#"{"flow_id":"greet","node":"hello","state":{}}"#;
+        let reworded = FLOW.replace("Hello", "Hi");
+        assert!(resumable(FLOW, &reworded, checkpoint));
+
+        let new_input = FLOW.replace("in.name", "in.user");
+        assert!(!resumable(FLOW, &new_input, checkpoint));
+        let renamed = FLOW.replace("hello", "welcome");
+        assert!(!resumable(FLOW, &renamed, checkpoint));
+        assert!(!resumable(FLOW, &reworded, "not a checkpoint"));
+    }
+}
@@ -0,0 +1,228 @@
+//! Remote compilation of the pack component.
+//!
+//! `packc build --remote-build <URL>` posts the generated `pack_component`
+//! crate to a build service instead of compiling it locally. The request
+//! lists every input file with its SHA-256 and an `inputs_digest` over that
+//! listing; the service answers with the compiled component, its digest and a
+//! build report echoing the inputs digest. Both digests are checked before the
+//! component is used, so a misbehaving service cannot substitute a different
+//! build. When the service cannot be reached at all, the caller falls back to
+//! compiling locally.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use greentic_pack::atomic::{self, Durability};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+use tracing::info;
+use walkdir::WalkDir;
+
+use crate::embed;
+use crate::http::{Request, RequestFailed};
+
+pub const REMOTE_BUILD_FORMAT: &str = "greentic-remote-build-v1";
+/// Bearer token sent to the build service, if set.
+pub const REMOTE_TOKEN_ENV: &str = "PACKC_REMOTE_BUILD_TOKEN";
+
+const TARGET: &str = "wasm32-wasip2";
+/// Remote builds compile components, so they get far longer than a fetch.
+const BUILD_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteBuildOptions {
+    /// Base URL of the build service; requests go to `<url>/v1/builds`.
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct BuildRequest {
+    format: &'static str,
+    target: &'static str,
+    inputs_digest: String,
+    files: Vec<InputFile>,
+}
+
+#[derive(Debug, Serialize)]
+struct InputFile {
+    path: String,
+    sha256: String,
+    /// Base64 of the file contents.
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BuildResponse {
+    /// Base64 of the compiled component.
+    component: String,
+    component_sha256: String,
+    report: BuildReport,
+}
+
+/// What the build service reports about a build, written next to the
+/// component as `<name>.build-report.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildReport {
+    pub inputs_digest: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub toolchain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Any other fields the service adds.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, JsonValue>,
+}
+
+#[derive(Debug)]
+pub enum RemoteOutcome {
+    Built(BuildReport),
+    /// The service could not be reached; the reason is curl's message.
+    Unreachable(String),
+}
+
+/// Sends the component crate next to `component_data` to the build service
+/// and writes the verified result to `output_wasm`.
+pub fn compile_component(
+    component_data: &Path,
+    output_wasm: &Path,
+    opts: &RemoteBuildOptions,
+    no_wasi: bool,
+    durability: Durability,
+) -> Result<RemoteOutcome> {
+    let crate_root = embed::prepare_component_crate(component_data, no_wasi)?;
+    let files = collect_inputs(&crate_root)?;
+    let inputs_digest = inputs_digest(&files);
+    let request = BuildRequest {
+        format: REMOTE_BUILD_FORMAT,
+        target: TARGET,
+        inputs_digest: inputs_digest.clone(),
+        files,
+    };
+    let endpoint = format!("{}/v1/builds", opts.url.trim_end_matches('/'));
+    info!(endpoint = %endpoint, inputs_digest = %inputs_digest, "requesting remote build");
+
+    let body = match post(&endpoint, &serde_json::to_vec(&request)?)? {
+        Ok(body) => body,
+        Err(reason) => return Ok(RemoteOutcome::Unreachable(reason)),
+    };
+    let response: BuildResponse = serde_json::from_slice(&body)
+        .with_context(|| format!("{endpoint} returned an invalid build response"))?;
+    let component = STANDARD
+        .decode(response.component.as_bytes())
+        .with_context(|| format!("{endpoint} returned a component that is not base64"))?;
+
+    let actual = hex::encode(Sha256::digest(&component));
+    if !actual.eq_ignore_ascii_case(&response.component_sha256) {
+        bail!(
+            "remote build component digest mismatch: service reported sha256 {}, received {actual}",
+            response.component_sha256
+        );
+    }
+    if response.report.inputs_digest != inputs_digest {
+        bail!(
+            "remote build report is for inputs {}, but packc sent {inputs_digest}",
+            response.report.inputs_digest
+        );
+    }
+
+    atomic::write(output_wasm, &component, durability)?;
+    let report_path = output_wasm.with_extension("build-report.json");
+    atomic::write(
+        &report_path,
+        &serde_json::to_vec_pretty(&response.report)?,
+        durability,
+    )?;
+    info!(output = %output_wasm.display(), report = %report_path.display(), "remote build verified");
+    Ok(RemoteOutcome::Built(response.report))
+}
+
+/// Every file of the crate except build output, sorted by path.
+fn collect_inputs(crate_root: &Path) -> Result<Vec<InputFile>> {
+    let mut files = Vec::new();
+    let walker = WalkDir::new(crate_root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.depth() != 1 || entry.file_name() != "target");
+    for entry in walker {
+        let entry = entry.with_context(|| format!("failed to walk {}", crate_root.display()))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let bytes = fs::read(entry.path())
+            .with_context(|| format!("failed to read {}", entry.path().display()))?;
+        let path = entry
+            .path()
+            .strip_prefix(crate_root)
+            .expect("walked below the crate root")
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.push(InputFile {
+            path,
+            sha256: hex::encode(Sha256::digest(&bytes)),
+            content: STANDARD.encode(&bytes),
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// SHA-256 over `<path>\0<sha256>\n` of each file, in path order.
+fn inputs_digest(files: &[InputFile]) -> String {
+    let mut hasher = Sha256::new();
+    for file in files {
+        hasher.update(file.path.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(file.sha256.as_bytes());
+        hasher.update(b"\n");
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// POSTs `body` as JSON. The inner `Err` carries curl's message when the
+/// service could not be reached; any other failure is an error.
+fn post(endpoint: &str, body: &[u8]) -> Result<Result<Vec<u8>, String>> {
+    let mut request = Request::post_json(endpoint, body).timeout(BUILD_TIMEOUT);
+    if let Ok(token) = std::env::var(REMOTE_TOKEN_ENV) {
+        request = request.bearer(&token);
+    }
+    match request.send() {
+        Ok(response) => Ok(Ok(response)),
+        Err(err) => match err.downcast_ref::<RequestFailed>() {
+            Some(failed) if failed.unreachable() => Ok(Err(failed.message.clone())),
+            _ => Err(err.context("remote build request failed")),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inputs_skip_build_output_and_digest_depends_on_content() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("target/release")).unwrap();
+        fs::write(root.join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(root.join("src/lib.rs"), "pub fn a() {}\n").unwrap();
+        fs::write(root.join("target/release/out.wasm"), "junk").unwrap();
+
+        let files = collect_inputs(root).unwrap();
+        let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, ["Cargo.toml", "src/lib.rs"]);
+        let before = inputs_digest(&files);
+        assert_eq!(before, inputs_digest(&collect_inputs(root).unwrap()));
+
+        fs::write(root.join("src/lib.rs"), "pub fn b() {}\n").unwrap();
+        assert_ne!(before, inputs_digest(&collect_inputs(root).unwrap()));
+    }
+}
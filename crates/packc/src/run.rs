@@ -0,0 +1,1135 @@
+//! Runs a flow of a compiled pack component, for `packc run`.
+//!
+//! The component runs in an embedded wasmtime, which packc links only when
+//! built with its `run` feature. Functions of the interfaces the component
+//! imports are answered by an [`ImportHost`]: from a cassette when replaying,
+//! otherwise from a seeded [`DeterministicHost`] for WASI clocks and random
+//! values and from the pack's mocks for the rest, and recorded into a
+//! cassette when asked.
+//! A call nothing answers traps, as does every other import, so a flow
+//! reaching for WASI fails with a trap instead of touching the host.
+//! Components built with `--assert-no-wasi` import nothing that matters.
+//!
+//! Components export the `greentic:pack-export` interface, and the runner
+//! calls its `run-flow` with the flow id, the input as JSON text and the
+//! [`RunContext`] of the run. A
+//! streamed run calls `run-flow-stream` instead and pulls the events of the
+//! returned `flow-run` until the result arrives.
+//! Components built with the `legacy-abi` feature, or by older packc
+//! versions, may only have the C ABI shims: a `wasm32-wasip2` build wraps
+//! core modules, and the runner instantiates the one exporting the
+//! `greentic_pack_export__*` shims. The flow id and input are copied into
+//! buffers from `greentic_pack_export__alloc`, and
+//! `greentic_pack_export__run_flow_json` returns the `RunResult` JSON in a
+//! buffer released with `greentic_pack_export__free`.
+//!
+//! Runs are held to the [`FlowLimits`] in [`RunOptions`] the way hosts hold
+//! them: wasmtime fuel, an epoch deadline for the timeout, and a resource
+//! limiter for linear memory, with the output size checked once the run
+//! returns. A run over budget fails with a [`LimitExceeded`] error.
+//!
+//! `packc run --watch` keeps one instance in a [`Session`] and runs a turn
+//! for each input, replacing the instance when the pack is rebuilt.
+
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+#[cfg(feature = "run")]
+use std::sync::atomic::Ordering;
+#[cfg(feature = "run")]
+use std::sync::{Arc, Mutex, PoisonError};
+#[cfg(feature = "run")]
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+#[cfg(feature = "run")]
+use greentic_pack::cassette::{Cassette, Outcome, Recorder, RedactionRules, Replayer};
+use greentic_pack::limits::FlowLimits;
+#[cfg(feature = "run")]
+use greentic_pack::limits::LimitExceeded;
+#[cfg(feature = "run")]
+use greentic_pack::trace::TraceRecorder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use wasmparser::{Parser, Payload};
+#[cfg(feature = "run")]
+use wasmtime::component::ResourceAny;
+
+use crate::imports::deterministic::DeterministicHost;
+#[cfg(feature = "run")]
+use crate::imports::host::{self, ImportHost};
+
+/// The component interface pack components export.
+pub const PACK_EXPORT_INTERFACE: &str = "greentic:pack-export/pack-export@0.1.0";
+/// The function of [`PACK_EXPORT_INTERFACE`] that runs a flow.
+#[cfg(feature = "run")]
+const RUN_FUNCTION: &str = "run-flow";
+/// The function of [`PACK_EXPORT_INTERFACE`] that starts a streamed run, and
+/// the method returning its next event.
+#[cfg(feature = "run")]
+const STREAM_FUNCTION: &str = "run-flow-stream";
+#[cfg(feature = "run")]
+const NEXT_METHOD: &str = "[method]flow-run.next";
+/// The functions of [`PACK_EXPORT_INTERFACE`] a [`Session`] swaps instances
+/// with.
+#[cfg(feature = "run")]
+const DRAIN_FUNCTION: &str = "drain";
+#[cfg(feature = "run")]
+const RESUME_FUNCTION: &str = "resume-flow-stream";
+/// The shim that runs a flow with an input.
+pub const RUN_EXPORT: &str = "greentic_pack_export__run_flow_json";
+#[cfg(feature = "run")]
+const ALLOC_EXPORT: &str = "greentic_pack_export__alloc";
+#[cfg(feature = "run")]
+const FREE_EXPORT: &str = "greentic_pack_export__free";
+
+/// What `run_flow` reports, as `pack_component` serialises it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunResult {
+    pub status: String,
+    #[serde(default)]
+    pub output: Option<JsonValue>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Who a flow runs for, passed to `run-flow` as the interface's
+/// `run-context`. The C ABI shims take none.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "run",
+    derive(wasmtime::component::ComponentType, wasmtime::component::Lower),
+    component(record)
+)]
+pub struct RunContext {
+    pub tenant: String,
+    pub env: String,
+    pub locale: Option<String>,
+    #[cfg_attr(feature = "run", component(name = "correlation-id"))]
+    pub correlation_id: Option<String>,
+    /// Opaque handle of the secrets the host grants the run.
+    pub secrets: Option<String>,
+}
+
+/// How the runner runs a flow.
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// The flow's declared limits; unlimited by default.
+    pub limits: FlowLimits,
+    /// Id of the pack, written into recorded cassettes.
+    pub pack_id: String,
+    /// Clock and random source answering `wasi:clocks` and `wasi:random`.
+    pub builtins: DeterministicHost,
+    /// Directory of mock files answering the component's interface imports.
+    pub mocks: Option<PathBuf>,
+    /// Where to save a cassette of the run's import calls.
+    pub record: Option<PathBuf>,
+    /// Cassette answering the import calls instead of the mocks.
+    pub replay: Option<PathBuf>,
+    /// Where to save an execution trace of the run.
+    pub trace: Option<PathBuf>,
+}
+
+/// What a streamed run reports before its result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RunEvent {
+    Progress { message: String },
+    Chunk { output: JsonValue },
+}
+
+/// Whether `wasm` is a component exporting [`PACK_EXPORT_INTERFACE`].
+pub fn exports_interface(wasm: &[u8]) -> Result<bool> {
+    if !Parser::is_component(wasm) {
+        return Ok(false);
+    }
+    for payload in Parser::new(0).parse_all(wasm) {
+        if let Payload::ComponentExportSection(reader) = payload.context("invalid wasm binary")? {
+            for export in reader {
+                if export.context("invalid component export")?.name.0 == PACK_EXPORT_INTERFACE {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// The core module of `wasm` that exports [`RUN_EXPORT`]: `wasm` itself when
+/// it is a core module, otherwise the first nested module that does.
+pub fn shim_module(wasm: &[u8]) -> Result<&[u8]> {
+    let mut modules = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload.context("invalid wasm binary")? {
+            Payload::Version {
+                encoding: wasmparser::Encoding::Module,
+                ..
+            } if modules.is_empty() => modules.push(wasm),
+            Payload::ModuleSection {
+                unchecked_range, ..
+            } => modules.push(&wasm[unchecked_range]),
+            _ => {}
+        }
+    }
+    for module in modules {
+        if exports_run_shim(module)? {
+            return Ok(module);
+        }
+    }
+    bail!(
+        "the component exports neither `{PACK_EXPORT_INTERFACE}` nor `{RUN_EXPORT}`; rebuild it with this version of packc"
+    )
+}
+
+fn exports_run_shim(module: &[u8]) -> Result<bool> {
+    for payload in Parser::new(0).parse_all(module) {
+        if let Payload::ExportSection(reader) = payload.context("invalid core module")? {
+            for export in reader {
+                if export.context("invalid core export")?.name == RUN_EXPORT {
+                    return Ok(true);
+                }
+            }
+            return Ok(false);
+        }
+    }
+    Ok(false)
+}
+
+/// Instantiates the component in `wasm` and runs `flow_id` with `input`.
+#[cfg(feature = "run")]
+pub fn run_flow(
+    wasm: &[u8],
+    flow_id: &str,
+    input: &JsonValue,
+    ctx: &RunContext,
+    opts: &RunOptions,
+) -> Result<RunResult> {
+    run(wasm, flow_id, input, ctx, opts, None)
+}
+
+/// Like [`run_flow`], but streams the run and passes each event to
+/// `on_event` as the component reports it. Components without
+/// `run-flow-stream`, shim-only ones included, report no events.
+#[cfg(feature = "run")]
+pub fn run_flow_stream(
+    wasm: &[u8],
+    flow_id: &str,
+    input: &JsonValue,
+    ctx: &RunContext,
+    opts: &RunOptions,
+    on_event: &mut dyn FnMut(RunEvent),
+) -> Result<RunResult> {
+    run(wasm, flow_id, input, ctx, opts, Some(on_event))
+}
+
+#[cfg(feature = "run")]
+fn run(
+    wasm: &[u8],
+    flow_id: &str,
+    input: &JsonValue,
+    ctx: &RunContext,
+    opts: &RunOptions,
+    on_event: Option<&mut dyn FnMut(RunEvent)>,
+) -> Result<RunResult> {
+    use std::sync::mpsc;
+    use std::time::Instant;
+
+    let limits = &opts.limits;
+    let mut config = wasmtime::Config::new();
+    config.wasm_component_model(true);
+    config.consume_fuel(limits.fuel.is_some());
+    config.epoch_interruption(limits.timeout_ms.is_some());
+    let engine = wasmtime::Engine::new(&config)?;
+
+    // Dropping `_stop` when the run returns ends the timer early.
+    let (_stop, stopped) = mpsc::channel::<()>();
+    if let Some(timeout) = limits.timeout() {
+        let timer = engine.clone();
+        std::thread::spawn(move || {
+            if let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(timeout) {
+                timer.increment_epoch();
+            }
+        });
+    }
+
+    let imports = Arc::new(Mutex::new(import_host(opts, flow_id, input)?));
+    let store = limited_store(&engine, limits)?;
+    let started = Instant::now();
+    // A traced run is streamed so its steps can be told apart.
+    let stream = opts.trace.is_some() || on_event.is_some();
+    let mut on_event = on_event;
+    let mut observe = |event: RunEvent| {
+        let mut host = imports.lock().unwrap_or_else(PoisonError::into_inner);
+        match &event {
+            RunEvent::Progress { message } => host.enter(message),
+            RunEvent::Chunk { output } => host.chunk(output),
+        }
+        drop(host);
+        if let Some(on_event) = on_event.as_mut() {
+            on_event(event);
+        }
+    };
+    let on_event: Option<&mut dyn FnMut(RunEvent)> = if stream { Some(&mut observe) } else { None };
+    let result = if exports_interface(wasm)? {
+        run_interface(store, wasm, flow_id, input, ctx, &imports, on_event)
+    } else {
+        run_legacy(store, wasm, flow_id, input)
+    };
+    let result = result.map_err(|err| limit_error(err, limits, started.elapsed()));
+    // The cassette and trace are saved even when the run fails, so the
+    // failure can be replayed and stepped through.
+    let mut host = imports.lock().unwrap_or_else(PoisonError::into_inner);
+    let traced = host.finish_trace(match &result {
+        Ok(result) => Ok(serde_json::to_value(result)?),
+        Err(err) => Err(format!("{err:#}")),
+    });
+    let finished = host.finish();
+    drop(host);
+    let result = result?;
+    traced?;
+    finished?;
+    limits.check_elapsed(started.elapsed())?;
+    if let Some(output) = &result.output {
+        limits.check_output(serde_json::to_vec(output)?.len() as u64)?;
+    }
+    Ok(result)
+}
+
+/// The host answering the import calls of a run of `flow_id`.
+#[cfg(feature = "run")]
+fn import_host(opts: &RunOptions, flow_id: &str, input: &JsonValue) -> Result<ImportHost> {
+    let mocks = match &opts.mocks {
+        Some(dir) => ImportHost::load_mocks(dir)?,
+        None => Vec::new(),
+    };
+    let mut imports = ImportHost::new(mocks, opts.builtins.clone());
+    if let Some(path) = &opts.replay {
+        let cassette = Cassette::load(path)?;
+        if cassette.flow != flow_id {
+            bail!(
+                "{} was recorded for flow `{}`, not `{flow_id}`",
+                path.display(),
+                cassette.flow
+            );
+        }
+        imports.replay(Replayer::new(cassette, RedactionRules::standard()));
+    }
+    if let Some(path) = &opts.record {
+        let recorder = Recorder::new(&opts.pack_id, flow_id, input, RedactionRules::standard());
+        imports.record(recorder, path.clone());
+    }
+    if let Some(path) = &opts.trace {
+        let recorder =
+            TraceRecorder::new(&opts.pack_id, flow_id, input, RedactionRules::standard());
+        imports.trace(recorder, path.clone());
+    }
+    Ok(imports)
+}
+
+/// Binds every import of `component`. Functions of imported interfaces call
+/// `imports`; imported resources are stubbed so the component links, and
+/// bare function imports trap.
+#[cfg(feature = "run")]
+fn link_imports(
+    engine: &wasmtime::Engine,
+    linker: &mut wasmtime::component::Linker<RunHost>,
+    component: &wasmtime::component::Component,
+    imports: &Arc<Mutex<ImportHost>>,
+) -> Result<()> {
+    use wasmtime::component::ResourceType;
+    use wasmtime::component::types::ComponentItem;
+
+    let ty = component.component_type();
+    for (name, item) in ty.imports(engine) {
+        match item {
+            ComponentItem::ComponentInstance(instance) => {
+                let mut linker = linker.instance(name)?;
+                for (export, item) in instance.exports(engine) {
+                    match item {
+                        ComponentItem::ComponentFunc(_) => {
+                            let imports = Arc::clone(imports);
+                            let interface = name.to_string();
+                            let function = export.to_string();
+                            linker.func_new(export, move |_, ty, params, results| {
+                                call_import(&imports, &interface, &function, &ty, params, results)
+                                    .map_err(wasmtime::Error::from_anyhow)
+                            })?;
+                        }
+                        ComponentItem::Resource(_) => {
+                            let stub = ResourceType::host::<()>();
+                            linker.resource(export, stub, |_, _| Ok(()))?;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            ComponentItem::ComponentFunc(_) => {
+                let trap = format!("unknown import: `{name}` has not been defined");
+                linker.root().func_new(name, move |_, _, _, _| {
+                    Err(wasmtime::Error::msg(trap.clone()))
+                })?;
+            }
+            ComponentItem::Resource(_) => {
+                let stub = ResourceType::host::<()>();
+                linker.root().resource(name, stub, |_, _| Ok(()))?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Answers a call to `function` of `interface` through `imports`, passing
+/// the parameters as JSON and lowering the answer into `results`.
+#[cfg(feature = "run")]
+fn call_import(
+    imports: &Mutex<ImportHost>,
+    interface: &str,
+    function: &str,
+    ty: &wasmtime::component::types::ComponentFunc,
+    params: &[wasmtime::component::Val],
+    results: &mut [wasmtime::component::Val],
+) -> Result<()> {
+    let params = ty
+        .params()
+        .zip(params)
+        .map(|((name, _), value)| Ok((name.to_string(), host::to_json(value)?)))
+        .collect::<Result<_>>()?;
+    let outcome = imports
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .call(interface, function, params)?;
+    let returned = match outcome {
+        Outcome::Ok(returned) => returned,
+        Outcome::Error(message) => bail!("`{interface}#{function}` failed: {message}"),
+    };
+    if let (Some(ty), Some(result)) = (ty.results().next(), results.first_mut()) {
+        *result = host::from_json(&ty, &returned).with_context(|| {
+            format!("`{interface}#{function}` answered with a value its result type does not hold")
+        })?;
+    }
+    Ok(())
+}
+
+/// Store data of a run: the memory limit its instances grow under.
+#[cfg(feature = "run")]
+struct RunHost {
+    memory: MemoryLimit,
+}
+
+/// Fails memory growth past `limit_bytes` with
+/// [`LimitExceeded::MemoryExceeded`].
+#[cfg(feature = "run")]
+struct MemoryLimit {
+    limit_bytes: Option<u64>,
+}
+
+#[cfg(feature = "run")]
+impl wasmtime::ResourceLimiter for MemoryLimit {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        match self.limit_bytes {
+            Some(limit_bytes) if desired as u64 > limit_bytes => {
+                Err(LimitExceeded::MemoryExceeded { limit_bytes }.into())
+            }
+            _ => Ok(true),
+        }
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        _desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        Ok(true)
+    }
+}
+
+/// A store that holds its instances to `limits`. The timeout takes an epoch
+/// tick, which [`run`] sends once it has passed.
+#[cfg(feature = "run")]
+fn limited_store(
+    engine: &wasmtime::Engine,
+    limits: &FlowLimits,
+) -> Result<wasmtime::Store<RunHost>> {
+    let mut store = wasmtime::Store::new(
+        engine,
+        RunHost {
+            memory: MemoryLimit {
+                limit_bytes: limits.memory_bytes(),
+            },
+        },
+    );
+    store.limiter(|host| &mut host.memory);
+    if let Some(fuel) = limits.fuel {
+        store.set_fuel(fuel)?;
+    }
+    if limits.timeout_ms.is_some() {
+        store.set_epoch_deadline(1);
+    }
+    Ok(store)
+}
+
+/// The [`LimitExceeded`] behind a trap caused by `limits`, or `err` as is.
+#[cfg(feature = "run")]
+fn limit_error(err: anyhow::Error, limits: &FlowLimits, elapsed: Duration) -> anyhow::Error {
+    let exceeded = match err.downcast_ref::<wasmtime::Trap>() {
+        Some(wasmtime::Trap::OutOfFuel) => {
+            limits.fuel.map(|limit| LimitExceeded::OutOfFuel { limit })
+        }
+        Some(wasmtime::Trap::Interrupt) => {
+            limits.timeout_ms.map(|limit_ms| LimitExceeded::Timeout {
+                limit_ms,
+                elapsed_ms: elapsed.as_millis().try_into().unwrap_or(u64::MAX),
+            })
+        }
+        _ => None,
+    };
+    match exceeded {
+        Some(exceeded) => exceeded.into(),
+        None => err,
+    }
+}
+
+/// `run-result` of the interface, with `output` as JSON text.
+#[cfg(feature = "run")]
+#[derive(wasmtime::component::ComponentType, wasmtime::component::Lift)]
+#[component(record)]
+struct WitRunResult {
+    status: String,
+    output: Option<String>,
+    error: Option<String>,
+}
+
+/// `run-event` of the interface.
+#[cfg(feature = "run")]
+#[derive(wasmtime::component::ComponentType, wasmtime::component::Lift)]
+#[component(variant)]
+enum WitRunEvent {
+    #[component(name = "progress")]
+    Progress(String),
+    #[component(name = "chunk")]
+    Chunk(String),
+    #[component(name = "done")]
+    Done(WitRunResult),
+    #[component(name = "checkpoint")]
+    Checkpoint(String),
+}
+
+/// Runs `flow_id` through the `run-flow` function of [`PACK_EXPORT_INTERFACE`],
+/// or through `run-flow-stream` when `on_event` is given and the component
+/// has it.
+#[cfg(feature = "run")]
+fn run_interface(
+    mut store: wasmtime::Store<RunHost>,
+    wasm: &[u8],
+    flow_id: &str,
+    input: &JsonValue,
+    ctx: &RunContext,
+    imports: &Arc<Mutex<ImportHost>>,
+    on_event: Option<&mut dyn FnMut(RunEvent)>,
+) -> Result<RunResult> {
+    use anyhow::anyhow;
+    use wasmtime::component::{Component, Linker, ResourceAny};
+
+    let engine = store.engine().clone();
+    let component = Component::new(&engine, wasm)
+        .map_err(anyhow::Error::from)
+        .context("failed to compile the pack component")?;
+    let mut linker = Linker::new(&engine);
+    link_imports(&engine, &mut linker, &component, imports)?;
+    let instance = linker
+        .instantiate(&mut store, &component)
+        .map_err(anyhow::Error::from)
+        .context("failed to instantiate the pack component")?;
+    let interface = instance
+        .get_export_index(&mut store, None, PACK_EXPORT_INTERFACE)
+        .ok_or_else(|| anyhow!("the pack component exports no `{PACK_EXPORT_INTERFACE}`"))?;
+    let input = serde_json::to_string(input).context("failed to serialise the input")?;
+    let stream = on_event.and_then(|on_event| {
+        let start = instance.get_export_index(&mut store, Some(&interface), STREAM_FUNCTION)?;
+        Some((start, on_event))
+    });
+
+    let result = if let Some((start, on_event)) = stream {
+        let start = instance
+            .get_typed_func::<(&str, &str, &RunContext), (ResourceAny,)>(&mut store, &start)?;
+        let next = instance
+            .get_export_index(&mut store, Some(&interface), NEXT_METHOD)
+            .ok_or_else(|| anyhow!("`{PACK_EXPORT_INTERFACE}` has no `{NEXT_METHOD}`"))?;
+        let next =
+            instance.get_typed_func::<(ResourceAny,), (Option<WitRunEvent>,)>(&mut store, &next)?;
+
+        let (run,) = start
+            .call(&mut store, (flow_id, input.as_str(), ctx))
+            .map_err(anyhow::Error::from)
+            .with_context(|| format!("flow `{flow_id}` trapped"))?;
+        let result = loop {
+            let (event,) = next
+                .call(&mut store, (run,))
+                .map_err(anyhow::Error::from)
+                .with_context(|| format!("flow `{flow_id}` trapped"))?;
+            match event {
+                Some(WitRunEvent::Progress(message)) => on_event(RunEvent::Progress { message }),
+                Some(WitRunEvent::Chunk(output)) => on_event(RunEvent::Chunk {
+                    output: serde_json::from_str(&output)
+                        .context("the component streamed output that is not JSON")?,
+                }),
+                Some(WitRunEvent::Done(result)) => break result,
+                // Local runs never ask the component to drain.
+                Some(WitRunEvent::Checkpoint(checkpoint)) => bail!(
+                    "flow `{flow_id}` checkpointed without being asked to drain: {checkpoint}"
+                ),
+                None => bail!("flow `{flow_id}` ended its stream without a result"),
+            }
+        };
+        run.resource_drop(&mut store)?;
+        result
+    } else {
+        let run = instance
+            .get_export_index(&mut store, Some(&interface), RUN_FUNCTION)
+            .ok_or_else(|| anyhow!("`{PACK_EXPORT_INTERFACE}` has no `{RUN_FUNCTION}`"))?;
+        let run = instance
+            .get_typed_func::<(&str, &str, &RunContext), (WitRunResult,)>(&mut store, &run)?;
+        let (result,) = run
+            .call(&mut store, (flow_id, input.as_str(), ctx))
+            .map_err(anyhow::Error::from)
+            .with_context(|| format!("flow `{flow_id}` trapped"))?;
+        result
+    };
+    let output = result
+        .output
+        .map(|output| serde_json::from_str(&output))
+        .transpose()
+        .context("the component returned an output that is not JSON")?;
+    Ok(RunResult {
+        status: result.status,
+        output,
+        error: result.error,
+    })
+}
+
+/// Runs `flow_id` through the C ABI shims of `wasm`.
+#[cfg(feature = "run")]
+fn run_legacy(
+    mut store: wasmtime::Store<RunHost>,
+    wasm: &[u8],
+    flow_id: &str,
+    input: &JsonValue,
+) -> Result<RunResult> {
+    use anyhow::anyhow;
+    use wasmtime::{Linker, Memory, Module, Store, TypedFunc};
+
+    fn copy_in(
+        store: &mut Store<RunHost>,
+        memory: Memory,
+        alloc: &TypedFunc<u32, u32>,
+        bytes: &[u8],
+    ) -> Result<(u32, u32)> {
+        let len = u32::try_from(bytes.len()).context("input is too large")?;
+        let ptr = alloc.call(&mut *store, len)?;
+        memory.write(&mut *store, ptr as usize, bytes)?;
+        Ok((ptr, len))
+    }
+
+    let engine = store.engine().clone();
+    let module = Module::new(&engine, shim_module(wasm)?)
+        .map_err(anyhow::Error::from)
+        .context("failed to compile the pack component")?;
+    let mut linker = Linker::new(&engine);
+    linker.define_unknown_imports_as_traps(&module)?;
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .map_err(anyhow::Error::from)
+        .context("failed to instantiate the pack component")?;
+    let memory = instance
+        .get_memory(&mut store, "memory")
+        .ok_or_else(|| anyhow!("the pack component exports no memory"))?;
+    let alloc = instance.get_typed_func::<u32, u32>(&mut store, ALLOC_EXPORT)?;
+    let free = instance.get_typed_func::<(u32, u32), ()>(&mut store, FREE_EXPORT)?;
+    let run = instance.get_typed_func::<(u32, u32, u32, u32, u32), u32>(&mut store, RUN_EXPORT)?;
+
+    let input = serde_json::to_vec(input).context("failed to serialise the input")?;
+    let (flow_ptr, flow_len) = copy_in(&mut store, memory, &alloc, flow_id.as_bytes())?;
+    let (input_ptr, input_len) = copy_in(&mut store, memory, &alloc, &input)?;
+    // wasm32 `usize`, written by the shim.
+    let (len_ptr, _) = copy_in(&mut store, memory, &alloc, &[0; 4])?;
+    let result_ptr = run
+        .call(
+            &mut store,
+            (flow_ptr, flow_len, input_ptr, input_len, len_ptr),
+        )
+        .map_err(anyhow::Error::from)
+        .with_context(|| format!("flow `{flow_id}` trapped"))?;
+    let mut len = [0u8; 4];
+    memory.read(&store, len_ptr as usize, &mut len)?;
+    let result_len = u32::from_le_bytes(len);
+    let mut result = vec![0u8; result_len as usize];
+    memory.read(&store, result_ptr as usize, &mut result)?;
+    for (ptr, len) in [
+        (flow_ptr, flow_len),
+        (input_ptr, input_len),
+        (len_ptr, 4),
+        (result_ptr, result_len),
+    ] {
+        free.call(&mut store, (ptr, len))?;
+    }
+    serde_json::from_slice(&result).context("the component returned an invalid RunResult")
+}
+
+/// How a turn of a [`Session`] ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Turn {
+    Done(RunResult),
+    /// The instance was drained mid-run; the checkpoint, as JSON text, for
+    /// [`Session::resume`] on the instance that replaces it.
+    Drained(String),
+}
+
+/// A local runner session, for `packc run --watch`: one instance of the pack
+/// component runs turns of a flow, streamed, until [`Session::swap`] replaces
+/// it with another build. When `reload` is raised during a turn the instance
+/// is drained, so the run ends at its next safe point with [`Turn::Drained`]
+/// and can carry on from there on the new instance.
+#[cfg(feature = "run")]
+pub struct Session {
+    flow_id: String,
+    ctx: RunContext,
+    limits: FlowLimits,
+    imports: Arc<Mutex<ImportHost>>,
+    engine: wasmtime::Engine,
+    store: wasmtime::Store<RunHost>,
+    instance: wasmtime::component::Instance,
+    interface: wasmtime::component::ComponentExportIndex,
+}
+
+#[cfg(feature = "run")]
+impl Session {
+    /// Instantiates the component in `wasm` for turns of `flow_id`.
+    pub fn start(wasm: &[u8], flow_id: &str, ctx: &RunContext, opts: &RunOptions) -> Result<Self> {
+        let limits = opts.limits.clone();
+        let mut config = wasmtime::Config::new();
+        config.wasm_component_model(true);
+        config.consume_fuel(limits.fuel.is_some());
+        config.epoch_interruption(limits.timeout_ms.is_some());
+        let engine = wasmtime::Engine::new(&config)?;
+        let imports = Arc::new(Mutex::new(import_host(opts, flow_id, &JsonValue::Null)?));
+        let (store, instance, interface) = instantiate_session(&engine, &limits, &imports, wasm)?;
+        Ok(Self {
+            flow_id: flow_id.to_string(),
+            ctx: ctx.clone(),
+            limits,
+            imports,
+            engine,
+            store,
+            instance,
+            interface,
+        })
+    }
+
+    /// Runs the flow with `input`.
+    pub fn run(
+        &mut self,
+        input: &JsonValue,
+        reload: &AtomicBool,
+        on_event: &mut dyn FnMut(RunEvent),
+    ) -> Result<Turn> {
+        let input = serde_json::to_string(input).context("failed to serialise the input")?;
+        let flow_id = self.flow_id.clone();
+        let ctx = self.ctx.clone();
+        self.turn(reload, on_event, |store, instance, interface| {
+            let start = instance
+                .get_export_index(&mut *store, Some(interface), STREAM_FUNCTION)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("`{PACK_EXPORT_INTERFACE}` has no `{STREAM_FUNCTION}`")
+                })?;
+            let start = instance
+                .get_typed_func::<(&str, &str, &RunContext), (ResourceAny,)>(&mut *store, &start)?;
+            let (run,) = start
+                .call(&mut *store, (flow_id.as_str(), input.as_str(), &ctx))
+                .map_err(anyhow::Error::from)?;
+            Ok(run)
+        })
+    }
+
+    /// Continues a run from `checkpoint`, which a drained instance returned.
+    pub fn resume(
+        &mut self,
+        checkpoint: &str,
+        reload: &AtomicBool,
+        on_event: &mut dyn FnMut(RunEvent),
+    ) -> Result<Turn> {
+        let ctx = self.ctx.clone();
+        self.turn(reload, on_event, |store, instance, interface| {
+            let resume = instance
+                .get_export_index(&mut *store, Some(interface), RESUME_FUNCTION)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("`{PACK_EXPORT_INTERFACE}` has no `{RESUME_FUNCTION}`")
+                })?;
+            let resume = instance
+                .get_typed_func::<(&str, &RunContext), (ResourceAny,)>(&mut *store, &resume)?;
+            let (run,) = resume
+                .call(&mut *store, (checkpoint, &ctx))
+                .map_err(anyhow::Error::from)?;
+            Ok(run)
+        })
+    }
+
+    /// Replaces the instance with one of the component in `wasm`. The import
+    /// host, with its clock and random state, carries over.
+    pub fn swap(&mut self, wasm: &[u8]) -> Result<()> {
+        let (store, instance, interface) =
+            instantiate_session(&self.engine, &self.limits, &self.imports, wasm)?;
+        self.store = store;
+        self.instance = instance;
+        self.interface = interface;
+        Ok(())
+    }
+
+    /// Pulls the events of the run `start` begins, under the flow's limits.
+    fn turn(
+        &mut self,
+        reload: &AtomicBool,
+        on_event: &mut dyn FnMut(RunEvent),
+        start: impl FnOnce(
+            &mut wasmtime::Store<RunHost>,
+            &wasmtime::component::Instance,
+            &wasmtime::component::ComponentExportIndex,
+        ) -> Result<ResourceAny>,
+    ) -> Result<Turn> {
+        use std::sync::mpsc;
+        use std::time::Instant;
+
+        let limits = self.limits.clone();
+        if let Some(fuel) = limits.fuel {
+            self.store.set_fuel(fuel)?;
+        }
+        if limits.timeout_ms.is_some() {
+            self.store.set_epoch_deadline(1);
+        }
+        let (_stop, stopped) = mpsc::channel::<()>();
+        if let Some(timeout) = limits.timeout() {
+            let timer = self.engine.clone();
+            std::thread::spawn(move || {
+                if let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(timeout) {
+                    timer.increment_epoch();
+                }
+            });
+        }
+        let started = Instant::now();
+        let turn = self
+            .pull(reload, on_event, start)
+            .map_err(|err| limit_error(err, &limits, started.elapsed()))?;
+        limits.check_elapsed(started.elapsed())?;
+        if let Turn::Done(RunResult {
+            output: Some(output),
+            ..
+        }) = &turn
+        {
+            limits.check_output(serde_json::to_vec(output)?.len() as u64)?;
+        }
+        Ok(turn)
+    }
+
+    fn pull(
+        &mut self,
+        reload: &AtomicBool,
+        on_event: &mut dyn FnMut(RunEvent),
+        start: impl FnOnce(
+            &mut wasmtime::Store<RunHost>,
+            &wasmtime::component::Instance,
+            &wasmtime::component::ComponentExportIndex,
+        ) -> Result<ResourceAny>,
+    ) -> Result<Turn> {
+        let store = &mut self.store;
+        let (instance, interface) = (&self.instance, &self.interface);
+        let next = instance
+            .get_export_index(&mut *store, Some(interface), NEXT_METHOD)
+            .ok_or_else(|| anyhow::anyhow!("`{PACK_EXPORT_INTERFACE}` has no `{NEXT_METHOD}`"))?;
+        let next = instance
+            .get_typed_func::<(ResourceAny,), (Option<WitRunEvent>,)>(&mut *store, &next)?;
+        let drain = instance
+            .get_export_index(&mut *store, Some(interface), DRAIN_FUNCTION)
+            .ok_or_else(|| {
+                anyhow::anyhow!("`{PACK_EXPORT_INTERFACE}` has no `{DRAIN_FUNCTION}`")
+            })?;
+        let drain = instance.get_typed_func::<(), ()>(&mut *store, &drain)?;
+
+        let trapped = || format!("flow `{}` trapped", self.flow_id);
+        let run = start(store, instance, interface).with_context(trapped)?;
+        let mut drained = false;
+        let turn = loop {
+            if !drained && reload.load(Ordering::SeqCst) {
+                drain
+                    .call(&mut *store, ())
+                    .map_err(anyhow::Error::from)
+                    .with_context(trapped)?;
+                drained = true;
+            }
+            let (event,) = next
+                .call(&mut *store, (run,))
+                .map_err(anyhow::Error::from)
+                .with_context(trapped)?;
+            match event {
+                Some(WitRunEvent::Progress(message)) => on_event(RunEvent::Progress { message }),
+                Some(WitRunEvent::Chunk(output)) => on_event(RunEvent::Chunk {
+                    output: serde_json::from_str(&output)
+                        .context("the component streamed output that is not JSON")?,
+                }),
+                Some(WitRunEvent::Done(result)) => {
+                    let output = result
+                        .output
+                        .map(|output| serde_json::from_str(&output))
+                        .transpose()
+                        .context("the component returned an output that is not JSON")?;
+                    break Turn::Done(RunResult {
+                        status: result.status,
+                        output,
+                        error: result.error,
+                    });
+                }
+                Some(WitRunEvent::Checkpoint(checkpoint)) if drained => {
+                    break Turn::Drained(checkpoint);
+                }
+                Some(WitRunEvent::Checkpoint(checkpoint)) => bail!(
+                    "flow `{}` checkpointed without being asked to drain: {checkpoint}",
+                    self.flow_id
+                ),
+                None => bail!("flow `{}` ended its stream without a result", self.flow_id),
+            }
+        };
+        run.resource_drop(&mut *store)?;
+        Ok(turn)
+    }
+}
+
+/// A store holding a new instance of the component in `wasm`, and the index
+/// of its [`PACK_EXPORT_INTERFACE`].
+#[cfg(feature = "run")]
+fn instantiate_session(
+    engine: &wasmtime::Engine,
+    limits: &FlowLimits,
+    imports: &Arc<Mutex<ImportHost>>,
+    wasm: &[u8],
+) -> Result<(
+    wasmtime::Store<RunHost>,
+    wasmtime::component::Instance,
+    wasmtime::component::ComponentExportIndex,
+)> {
+    use wasmtime::component::{Component, Linker};
+
+    if !exports_interface(wasm)? {
+        bail!(
+            "sessions need a component exporting `{PACK_EXPORT_INTERFACE}`; rebuild it with this version of packc"
+        );
+    }
+    let mut store = limited_store(engine, limits)?;
+    let component = Component::new(engine, wasm)
+        .map_err(anyhow::Error::from)
+        .context("failed to compile the pack component")?;
+    let mut linker = Linker::new(engine);
+    link_imports(engine, &mut linker, &component, imports)?;
+    let instance = linker
+        .instantiate(&mut store, &component)
+        .map_err(anyhow::Error::from)
+        .context("failed to instantiate the pack component")?;
+    let interface = instance
+        .get_export_index(&mut store, None, PACK_EXPORT_INTERFACE)
+        .ok_or_else(|| {
+            anyhow::anyhow!("the pack component exports no `{PACK_EXPORT_INTERFACE}`")
+        })?;
+    Ok((store, instance, interface))
+}
+
+#[cfg(not(feature = "run"))]
+pub enum Session {}
+
+#[cfg(not(feature = "run"))]
+impl Session {
+    pub fn start(
+        _wasm: &[u8],
+        _flow_id: &str,
+        _ctx: &RunContext,
+        _opts: &RunOptions,
+    ) -> Result<Self> {
+        bail!("packc run requires packc to be built with the `run` feature")
+    }
+
+    pub fn run(
+        &mut self,
+        _input: &JsonValue,
+        _reload: &AtomicBool,
+        _on_event: &mut dyn FnMut(RunEvent),
+    ) -> Result<Turn> {
+        match *self {}
+    }
+
+    pub fn resume(
+        &mut self,
+        _checkpoint: &str,
+        _reload: &AtomicBool,
+        _on_event: &mut dyn FnMut(RunEvent),
+    ) -> Result<Turn> {
+        match *self {}
+    }
+
+    pub fn swap(&mut self, _wasm: &[u8]) -> Result<()> {
+        match *self {}
+    }
+}
+
+#[cfg(not(feature = "run"))]
+pub fn run_flow(
+    _wasm: &[u8],
+    _flow_id: &str,
+    _input: &JsonValue,
+    _ctx: &RunContext,
+    _opts: &RunOptions,
+) -> Result<RunResult> {
+    bail!("packc run requires packc to be built with the `run` feature")
+}
+
+#[cfg(not(feature = "run"))]
+pub fn run_flow_stream(
+    _wasm: &[u8],
+    _flow_id: &str,
+    _input: &JsonValue,
+    _ctx: &RunContext,
+    _opts: &RunOptions,
+    _on_event: &mut dyn FnMut(RunEvent),
+) -> Result<RunResult> {
+    bail!("packc run requires packc to be built with the `run` feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(id: u8, contents: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![id, contents.len() as u8];
+        bytes.extend_from_slice(contents);
+        bytes
+    }
+
+    /// A core module exporting function 0 as `export`.
+    fn core_module(export: &str) -> Vec<u8> {
+        let mut exports = vec![0x01, export.len() as u8];
+        exports.extend_from_slice(export.as_bytes());
+        exports.extend([0x00, 0x00]);
+        let mut wasm = b"\0asm\x01\x00\x00\x00".to_vec();
+        wasm.extend(section(7, &exports));
+        wasm
+    }
+
+    fn component(modules: &[Vec<u8>]) -> Vec<u8> {
+        let mut wasm = b"\0asm\x0d\x00\x01\x00".to_vec();
+        for module in modules {
+            wasm.extend(section(1, module));
+        }
+        wasm
+    }
+
+    #[test]
+    fn finds_the_module_exporting_the_run_shim() {
+        let shims = core_module(RUN_EXPORT);
+        assert_eq!(shim_module(&shims).unwrap(), shims.as_slice());
+
+        let adapter = core_module("cabi_realloc");
+        let wrapped = component(&[adapter.clone(), shims.clone()]);
+        assert_eq!(shim_module(&wrapped).unwrap(), shims.as_slice());
+
+        let err = shim_module(&component(&[adapter])).unwrap_err();
+        assert!(err.to_string().contains(RUN_EXPORT), "{err}");
+    }
+
+    #[test]
+    fn detects_components_exporting_the_interface() {
+        let mut export = vec![0x01, 0x00, PACK_EXPORT_INTERFACE.len() as u8];
+        export.extend_from_slice(PACK_EXPORT_INTERFACE.as_bytes());
+        export.extend([0x05, 0x00, 0x00]);
+        let mut wasm = component(&[]);
+        wasm.extend(section(11, &export));
+        assert!(exports_interface(&wasm).unwrap());
+
+        let shims = core_module(RUN_EXPORT);
+        assert!(!exports_interface(&component(std::slice::from_ref(&shims))).unwrap());
+        assert!(!exports_interface(&shims).unwrap());
+    }
+
+    #[cfg(feature = "run")]
+    #[test]
+    fn traps_caused_by_limits_become_typed_errors() {
+        use wasmtime::ResourceLimiter;
+
+        let limits = FlowLimits {
+            timeout_ms: Some(100),
+            fuel: Some(1_000),
+            ..FlowLimits::default()
+        };
+        let exceeded = |err: anyhow::Error| err.downcast::<LimitExceeded>().unwrap();
+        // Traps reach `limit_error` as wasmtime errors with context added.
+        let trapped = |trap: wasmtime::Trap| {
+            anyhow::Error::from(wasmtime::Error::from(trap)).context("flow `main` trapped")
+        };
+        let elapsed = Duration::from_millis(150);
+        assert_eq!(
+            exceeded(limit_error(
+                trapped(wasmtime::Trap::OutOfFuel),
+                &limits,
+                elapsed
+            )),
+            LimitExceeded::OutOfFuel { limit: 1_000 }
+        );
+        assert_eq!(
+            exceeded(limit_error(
+                trapped(wasmtime::Trap::Interrupt),
+                &limits,
+                elapsed
+            )),
+            LimitExceeded::Timeout {
+                limit_ms: 100,
+                elapsed_ms: 150
+            }
+        );
+        let other = limit_error(
+            trapped(wasmtime::Trap::Interrupt),
+            &FlowLimits::default(),
+            elapsed,
+        );
+        assert!(other.downcast_ref::<LimitExceeded>().is_none());
+
+        let mut memory = MemoryLimit {
+            limit_bytes: Some(65_536),
+        };
+        assert!(memory.memory_growing(0, 65_536, None).unwrap());
+        let err = memory.memory_growing(65_536, 131_072, None).unwrap_err();
+        assert_eq!(exceeded(err.into()).code(), "memory_exceeded");
+    }
+
+    #[test]
+    fn run_events_serialise_with_their_kind() {
+        let events = [
+            RunEvent::Progress {
+                message: "call_llm".into(),
+            },
+            RunEvent::Chunk {
+                output: serde_json::json!({ "text": "Hel" }),
+            },
+        ];
+        let lines: Vec<String> = events
+            .iter()
+            .map(|event| serde_json::to_string(event).unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                r#"{"kind":"progress","message":"call_llm"}"#,
+                r#"{"kind":"chunk","output":{"text":"Hel"}}"#,
+            ]
+        );
+    }
+}
@@ -1,9 +1,8 @@
+use crate::digest;
 use crate::flows::FlowAsset;
 use crate::manifest::SpecBundle;
 use crate::templates::TemplateAsset;
 use serde::Serialize;
-use time::OffsetDateTime;
-use time::format_description::well_known::Rfc3339;
 
 #[derive(Debug, Serialize)]
 pub struct CycloneDxBom {
@@ -47,25 +46,28 @@ pub struct HashEntry {
     pub content: String,
 }
 
+/// CycloneDX hash entry for an `<alg>:<hex>` digest.
+fn hashes(digest: &str) -> Option<Vec<HashEntry>> {
+    let (alg, hex) = digest::parse(digest).ok()?;
+    Some(vec![HashEntry {
+        alg: alg.cyclonedx_name(),
+        content: hex.to_string(),
+    }])
+}
+
 pub fn generate(
     spec: &SpecBundle,
     flows: &[FlowAsset],
     templates: &[TemplateAsset],
+    timestamp: &str,
 ) -> CycloneDxBom {
-    let timestamp = OffsetDateTime::now_utc()
-        .format(&Rfc3339)
-        .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string());
-
     let mut components = Vec::new();
     for flow in flows {
         components.push(Component {
             name: flow.bundle.id.clone(),
             component_type: "file",
             version: None,
-            hashes: Some(vec![HashEntry {
-                alg: "SHA-256",
-                content: flow.sha256.clone(),
-            }]),
+            hashes: hashes(&flow.digest),
         });
     }
 
@@ -74,10 +76,7 @@ pub fn generate(
             name: template.logical_path.clone(),
             component_type: "file",
             version: None,
-            hashes: Some(vec![HashEntry {
-                alg: "SHA-256",
-                content: template.sha256.clone(),
-            }]),
+            hashes: hashes(&template.digest),
         });
     }
 
@@ -86,7 +85,7 @@ pub fn generate(
         spec_version: "1.5",
         version: 1,
         metadata: Metadata {
-            timestamp,
+            timestamp: timestamp.to_string(),
             component: ComponentSummary {
                 name: spec.spec.id.clone(),
                 version: spec.spec.version.clone(),
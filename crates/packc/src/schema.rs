@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use greentic_pack::builder::PackManifest;
+use greentic_pack::events::EventsSection;
+use greentic_pack::messaging::MessagingSection;
+use greentic_pack::repo::RepoPackSection;
+use schemars::schema_for;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::manifest::PackSpec;
+
+/// Editor-facing flow schema: the rules of `ygtc.flow.schema.json` plus
+/// descriptions, examples, and documented enum values and components.
+const FLOW_EDITOR_SCHEMA_JSON: &str = include_str!("../schemas/ygtc.flow.editor.schema.json");
+
+/// Document kinds `packc schema` can describe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaKind {
+    /// `.ygtc` flow files
+    Flow,
+    /// `pack.yaml`
+    Pack,
+    /// The `events` section of `pack.yaml`
+    Events,
+    /// The `messaging` section of `pack.yaml`
+    Messaging,
+    /// The `repo` section of `pack.yaml`
+    Repo,
+    /// `manifest.cbor` inside a `.gtpack`, as decoded by `packc inspect`
+    Manifest,
+}
+
+impl SchemaKind {
+    pub const ALL: [SchemaKind; 6] = [
+        SchemaKind::Flow,
+        SchemaKind::Pack,
+        SchemaKind::Events,
+        SchemaKind::Messaging,
+        SchemaKind::Repo,
+        SchemaKind::Manifest,
+    ];
+
+    /// File name the schema is conventionally written to.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            SchemaKind::Flow => "ygtc.flow.editor.schema.json",
+            SchemaKind::Pack => "pack.v1.schema.json",
+            SchemaKind::Events => "events.v1.schema.json",
+            SchemaKind::Messaging => "messaging.v1.schema.json",
+            SchemaKind::Repo => "repo.v1.schema.json",
+            SchemaKind::Manifest => "manifest.v1.schema.json",
+        }
+    }
+
+    /// Globs of the files the schema applies to; empty for schemas of
+    /// sections and of documents editors do not open.
+    pub fn globs(self) -> &'static [&'static str] {
+        match self {
+            SchemaKind::Flow => &["*.ygtc", "**/*.ygtc"],
+            SchemaKind::Pack => &["pack.yaml"],
+            SchemaKind::Events
+            | SchemaKind::Messaging
+            | SchemaKind::Repo
+            | SchemaKind::Manifest => &[],
+        }
+    }
+}
+
+/// The JSON Schema for `kind`.
+pub fn schema(kind: SchemaKind) -> Result<Value> {
+    match kind {
+        SchemaKind::Flow => serde_json::from_str(FLOW_EDITOR_SCHEMA_JSON)
+            .context("embedded flow editor schema is not valid JSON"),
+        SchemaKind::Pack => Ok(serde_json::to_value(schema_for!(PackSpec))?),
+        SchemaKind::Events => Ok(serde_json::to_value(schema_for!(EventsSection))?),
+        SchemaKind::Messaging => Ok(serde_json::to_value(schema_for!(MessagingSection))?),
+        SchemaKind::Repo => Ok(serde_json::to_value(schema_for!(RepoPackSection))?),
+        SchemaKind::Manifest => Ok(serde_json::to_value(schema_for!(PackManifest))?),
+    }
+}
+
+/// How to point the YAML language server at a schema, either per file or
+/// through editor settings.
+#[derive(Debug, Clone, Serialize)]
+pub struct EditorMapping {
+    pub kind: SchemaKind,
+    /// Path or URL of the schema.
+    pub schema: String,
+    /// First-line comment binding a single document to the schema.
+    pub modeline: String,
+    /// `yaml.schemas` setting binding the schema to file globs.
+    pub yaml_schemas: BTreeMap<String, Vec<String>>,
+    /// `files.associations` needed for the globs to be treated as YAML.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub file_associations: BTreeMap<String, String>,
+}
+
+/// Editor settings binding `kind` documents to the schema at `schema_ref`.
+pub fn editor_mapping(kind: SchemaKind, schema_ref: &str) -> EditorMapping {
+    let globs: Vec<String> = kind.globs().iter().map(|glob| glob.to_string()).collect();
+    let file_associations = match kind {
+        SchemaKind::Flow => BTreeMap::from([("*.ygtc".to_string(), "yaml".to_string())]),
+        _ => BTreeMap::new(),
+    };
+    EditorMapping {
+        kind,
+        schema: schema_ref.to_string(),
+        modeline: format!("# yaml-language-server: $schema={schema_ref}"),
+        yaml_schemas: BTreeMap::from([(schema_ref.to_string(), globs)]),
+        file_associations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    const FLOW_SCHEMA_JSON: &str = include_str!("../schemas/ygtc.flow.schema.json");
+
+    #[test]
+    fn flow_editor_schema_matches_parser_schema() {
+        let editor = schema(SchemaKind::Flow).expect("editor schema");
+        let parser: Value = serde_json::from_str(FLOW_SCHEMA_JSON).expect("parser schema");
+
+        assert_eq!(editor["required"], parser["required"]);
+        let properties = editor["properties"].as_object().expect("properties");
+        for key in parser["properties"].as_object().expect("properties").keys() {
+            let property = &properties[key];
+            assert!(property["description"].is_string(), "{key} undocumented");
+        }
+
+        let documented: Vec<&Value> = editor["properties"]["type"]["oneOf"]
+            .as_array()
+            .expect("documented flow types")
+            .iter()
+            .map(|variant| &variant["const"])
+            .collect();
+        let allowed: Vec<&Value> = parser["properties"]["type"]["enum"]
+            .as_array()
+            .expect("flow types")
+            .iter()
+            .collect();
+        assert_eq!(documented, allowed);
+    }
+
+    #[test]
+    fn section_and_manifest_schemas_describe_their_fields() {
+        let pack = schema(SchemaKind::Pack).expect("pack schema");
+        for (kind, field) in [
+            (SchemaKind::Events, "providers"),
+            (SchemaKind::Messaging, "adapters"),
+            (SchemaKind::Repo, "kind"),
+            (SchemaKind::Manifest, "components"),
+        ] {
+            let schema = schema(kind).expect("schema");
+            assert!(
+                schema["properties"].get(field).is_some(),
+                "{kind:?} schema lacks `{field}`"
+            );
+            assert!(kind.globs().is_empty());
+        }
+        assert!(pack["properties"].get("events").is_some());
+
+        let names: BTreeSet<&str> = SchemaKind::ALL
+            .iter()
+            .map(|kind| kind.file_name())
+            .collect();
+        assert_eq!(names.len(), SchemaKind::ALL.len());
+    }
+
+    #[test]
+    fn mapping_covers_ygtc_files() {
+        let mapping = editor_mapping(SchemaKind::Flow, ".packc/schemas/flow.json");
+        assert_eq!(
+            mapping.modeline,
+            "# yaml-language-server: $schema=.packc/schemas/flow.json"
+        );
+        assert_eq!(
+            mapping.yaml_schemas[".packc/schemas/flow.json"],
+            ["*.ygtc", "**/*.ygtc"]
+        );
+        assert_eq!(mapping.file_associations["*.ygtc"], "yaml");
+    }
+}
@@ -0,0 +1,179 @@
+//! A small web page for inspecting a `.gtpack`.
+//!
+//! `packc serve-ui` reads the archive once and serves two resources over
+//! plain HTTP/1.1: the page itself at `/`, and everything it shows at
+//! `/api/pack`: the inspection summary and signature status, the manifest
+//! metadata, each flow's node graph, the SBOM, and the archive entries. The
+//! page is embedded in packc and loads nothing else, so it works through a
+//! bare port forward to a server.
+//!
+//! The server answers one request at a time and only `GET` or `HEAD`, and
+//! drops a connection that stalls for ten seconds; it is a triage tool,
+//! not something to expose beyond a tunnel. Requests must name it as
+//! `127.0.0.1:<port>` or `localhost:<port>` in `Host`, which keeps
+//! DNS-rebinding pages out.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use greentic_pack::builder::PackMeta;
+use greentic_pack::{ArchiveEntry, SigningPolicy, open_pack};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use tracing::{debug, warn};
+
+use crate::cli::graph::{FlowGraph, archived_flow_graph};
+use crate::inspect::{self, Inspection};
+
+const PAGE: &str = include_str!("serve_ui.html");
+
+/// Longest request line or header accepted, in bytes.
+const MAX_LINE: usize = 8 * 1024;
+
+/// How long a connection may stall mid-request or mid-response before it is
+/// dropped, so one idle client cannot hold up the ones queued behind it.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Everything the page shows about a pack.
+#[derive(Debug, Serialize)]
+pub struct PackView {
+    pub inspection: Inspection,
+    pub meta: PackMeta,
+    pub flows: Vec<FlowGraph>,
+    pub sbom: JsonValue,
+    pub files: Vec<ArchiveEntry>,
+}
+
+impl PackView {
+    pub fn load(path: &Path, policy: SigningPolicy) -> Result<Self> {
+        let inspection = inspect::inspect(path, policy)?;
+        let load = open_pack(path, policy)
+            .map_err(|err| anyhow!("failed to open {}: {}", path.display(), err.message))?;
+        let flows = load
+            .manifest
+            .flows
+            .iter()
+            .map(|flow| {
+                let yaml = load
+                    .file(&flow.file_yaml)
+                    .ok_or_else(|| anyhow!("flow `{}` source is missing", flow.id))?;
+                let yaml = std::str::from_utf8(yaml)
+                    .with_context(|| format!("flow `{}` source is not UTF-8", flow.id))?;
+                archived_flow_graph(&flow.id, &flow.entry, yaml)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let sbom = match load.file("sbom.json") {
+            Some(bytes) => serde_json::from_slice(bytes).context("sbom.json is not valid JSON")?,
+            None => JsonValue::Null,
+        };
+        Ok(Self {
+            inspection,
+            meta: load.manifest.meta.clone(),
+            flows,
+            sbom,
+            files: load.entries(),
+        })
+    }
+}
+
+/// Serves `view` on `listener` until the process is stopped.
+pub fn serve(listener: TcpListener, view: &PackView) -> Result<()> {
+    let body = serde_json::to_vec(view).context("failed to serialise the pack view")?;
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!(%err, "failed to accept a connection");
+                continue;
+            }
+        };
+        if let Err(err) = respond(stream, &body) {
+            debug!(%err, "request failed");
+        }
+    }
+    Ok(())
+}
+
+fn respond(stream: TcpStream, api_body: &[u8]) -> Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let request_line = read_line(&mut reader)?;
+    // Only `Host` matters; the rest are read so the client sees its request
+    // consumed before the response.
+    let mut host = None;
+    loop {
+        let line = read_line(&mut reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.trim().eq_ignore_ascii_case("host")
+        {
+            host = Some(value.trim().to_ascii_lowercase());
+        }
+    }
+    let port = stream.local_addr()?.port();
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (
+        parts.next().unwrap_or_default(),
+        parts.next().unwrap_or("/"),
+    );
+    let path = target.split('?').next().unwrap_or(target);
+    let (status, content_type, body): (&str, &str, &[u8]) = match (method, path) {
+        _ if !local_host(host.as_deref(), port) => {
+            ("403 Forbidden", "text/plain", b"unexpected Host header\n")
+        }
+        ("GET" | "HEAD", "/" | "/index.html") => {
+            ("200 OK", "text/html; charset=utf-8", PAGE.as_bytes())
+        }
+        ("GET" | "HEAD", "/api/pack") => ("200 OK", "application/json", api_body),
+        ("GET" | "HEAD", _) => ("404 Not Found", "text/plain", b"not found\n"),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            b"method not allowed\n",
+        ),
+    };
+    debug!(%method, %path, %status, "served request");
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Cache-Control: no-store\r\nX-Content-Type-Options: nosniff\r\n\
+         Content-Security-Policy: default-src 'self'; style-src 'unsafe-inline'; script-src 'unsafe-inline'\r\n\
+         Connection: close\r\n\r\n",
+        body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(body)?;
+    }
+    stream.flush()?;
+    Ok(())
+}
+
+/// Whether the request names the server by its loopback address, so a page
+/// that rebinds its own DNS name to 127.0.0.1 cannot read the pack.
+fn local_host(host: Option<&str>, port: u16) -> bool {
+    host.is_some_and(|host| {
+        host == format!("127.0.0.1:{port}") || host == format!("localhost:{port}")
+    })
+}
+
+fn read_line(reader: &mut impl BufRead) -> Result<String> {
+    let mut line = String::new();
+    let read = reader
+        .by_ref()
+        .take(MAX_LINE as u64)
+        .read_line(&mut line)
+        .context("failed to read the request")?;
+    if read == MAX_LINE && !line.ends_with('\n') {
+        bail!("request line is longer than {MAX_LINE} bytes");
+    }
+    Ok(line.trim_end().to_string())
+}
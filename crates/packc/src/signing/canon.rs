@@ -4,22 +4,48 @@ use std::ffi::OsStr;
 use std::fs;
 use std::path::{Component, Path};
 
+use crate::digest::DigestAlgorithm;
+use crate::{build_lock, manifest};
 use anyhow::{Context, Result, anyhow};
 use ignore::WalkBuilder;
-use sha2::{Digest, Sha256};
 
-use crate::manifest;
+/// Version of the canonical byte layout below. Bump it whenever the set of
+/// covered files or the entry encoding changes, since old signatures would no
+/// longer verify.
+pub const CANONICALIZATION_VERSION: u32 = 1;
 
 /// Canonical representation of a pack directory used for signing.
 pub struct CanonicalizedPack {
     /// Concatenated canonical bytes over which the signature is produced.
     pub bytes: Vec<u8>,
-    /// Hex encoded SHA-256 digest of the canonical bytes.
+    /// Algorithm used for `digest_hex`.
+    pub algorithm: DigestAlgorithm,
+    /// Hex encoded digest of the canonical bytes.
     pub digest_hex: String,
+    /// Number of files covered by the canonical bytes.
+    pub files: usize,
 }
 
-/// Computes the canonical byte stream of the provided pack directory.
+impl CanonicalizedPack {
+    /// Digest of the canonical bytes in `<alg>:<hex>` form, as recorded in
+    /// signatures.
+    pub fn digest(&self) -> String {
+        format!("{}:{}", self.algorithm, self.digest_hex)
+    }
+}
+
+/// Computes the canonical byte stream of the provided pack directory, digested
+/// with SHA-256.
 pub fn canonicalize_pack_dir(pack_dir: &Path) -> Result<CanonicalizedPack> {
+    canonicalize_pack_dir_with(pack_dir, DigestAlgorithm::default())
+}
+
+/// Computes the canonical byte stream of the provided pack directory, digested
+/// with `algorithm`.
+pub fn canonicalize_pack_dir_with(
+    pack_dir: &Path,
+    algorithm: DigestAlgorithm,
+) -> Result<CanonicalizedPack> {
     let pack_dir = pack_dir
         .canonicalize()
         .with_context(|| format!("failed to resolve pack directory {}", pack_dir.display()))?;
@@ -88,12 +114,13 @@ pub fn canonicalize_pack_dir(pack_dir: &Path) -> Result<CanonicalizedPack> {
         buffer.extend_from_slice(&entry.contents);
     }
 
-    let digest = Sha256::digest(&buffer);
-    let digest_hex = hex::encode(digest);
+    let digest_hex = algorithm.hex(&buffer);
 
     Ok(CanonicalizedPack {
         bytes: buffer,
+        algorithm,
         digest_hex,
+        files: entries.len(),
     })
 }
 
@@ -110,6 +137,10 @@ fn should_skip(path: &Path) -> bool {
         return true;
     }
 
+    if build_lock::is_lock_path(path) {
+        return true;
+    }
+
     matches!(path.file_name().and_then(OsStr::to_str), Some(".DS_Store"))
 }
 
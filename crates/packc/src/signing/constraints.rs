@@ -0,0 +1,172 @@
+#![forbid(unsafe_code)]
+
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+/// Limits on where a signature is valid, chosen by the signer.
+///
+/// Constraints are appended to the signed message, so they cannot be loosened
+/// or stripped without invalidating the signature.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KeyConstraints {
+    /// Pack ids the signature may cover; `*` matches any run of characters
+    /// (e.g. `greentic.dev.*`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack_id: Option<String>,
+    /// Instant after which the signature is no longer accepted.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::rfc3339::option"
+    )]
+    pub expires_at: Option<OffsetDateTime>,
+    /// Environments in which the signature is accepted; empty means any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub environments: Vec<String>,
+}
+
+impl KeyConstraints {
+    pub fn is_empty(&self) -> bool {
+        self.pack_id.is_none() && self.expires_at.is_none() && self.environments.is_empty()
+    }
+
+    /// Checks the constraints, returning a description of the first violation.
+    ///
+    /// `environment` is only consulted when environments are restricted; a
+    /// restricted signature is rejected if the verifier does not name one.
+    pub fn check(
+        &self,
+        pack_id: Option<&str>,
+        environment: Option<&str>,
+        now: OffsetDateTime,
+    ) -> Result<(), String> {
+        if let Some(pattern) = &self.pack_id {
+            match pack_id {
+                Some(id) if pattern_matches(pattern, id) => {}
+                Some(id) => return Err(format!("pack id `{id}` does not match `{pattern}`")),
+                None => return Err(format!("pack id is unknown but must match `{pattern}`")),
+            }
+        }
+
+        if let Some(expires_at) = self.expires_at
+            && now > expires_at
+        {
+            let when = expires_at
+                .format(&Rfc3339)
+                .unwrap_or_else(|_| expires_at.to_string());
+            return Err(format!("signature expired at {when}"));
+        }
+
+        if !self.environments.is_empty() {
+            let allowed = self.environments.join(", ");
+            match environment {
+                Some(env) if self.environments.iter().any(|allowed| allowed == env) => {}
+                Some(env) => {
+                    return Err(format!(
+                        "environment `{env}` is not one of the allowed environments ({allowed})"
+                    ));
+                }
+                None => {
+                    return Err(format!(
+                        "signature is restricted to environments ({allowed}); pass the target environment"
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Bytes actually signed: the canonical pack bytes, followed by the
+    /// constraints when there are any. Unconstrained signatures sign the
+    /// canonical bytes alone, as they always have.
+    pub fn signed_message<'a>(constraints: Option<&Self>, canonical: &'a [u8]) -> Cow<'a, [u8]> {
+        match constraints.filter(|constraints| !constraints.is_empty()) {
+            Some(constraints) => {
+                let mut message = canonical.to_vec();
+                message.extend_from_slice(b"CONSTRAINTS\0");
+                // Struct fields serialise in declaration order, so this is stable.
+                message.extend_from_slice(
+                    &serde_json::to_vec(constraints).expect("constraints serialise to JSON"),
+                );
+                Cow::Owned(message)
+            }
+            None => Cow::Borrowed(canonical),
+        }
+    }
+}
+
+/// Matches `value` against `pattern`, where `*` stands for any (possibly empty)
+/// run of characters and everything else matches literally.
+pub fn pattern_matches(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            // Last segment is anchored to the end of the value.
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    // No `*` at all: the prefix must have consumed the whole value.
+    rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::Duration;
+
+    #[test]
+    fn patterns_support_wildcards() {
+        assert!(pattern_matches("greentic.dev.*", "greentic.dev.weather"));
+        assert!(pattern_matches("*", "anything"));
+        assert!(pattern_matches("acme.*.demo", "acme.sales.demo"));
+        assert!(pattern_matches("exact.id", "exact.id"));
+        assert!(!pattern_matches("exact.id", "exact.id.more"));
+        assert!(!pattern_matches("greentic.dev.*", "greentic.prod.weather"));
+        assert!(!pattern_matches("acme.*.demo", "acme.sales.prod"));
+    }
+
+    #[test]
+    fn check_reports_each_violation() {
+        let now = OffsetDateTime::now_utc();
+        let constraints = KeyConstraints {
+            pack_id: Some("dev.*".into()),
+            expires_at: Some(now + Duration::hours(1)),
+            environments: vec!["dev".into(), "staging".into()],
+        };
+        assert!(
+            constraints
+                .check(Some("dev.demo"), Some("dev"), now)
+                .is_ok()
+        );
+
+        let err = constraints
+            .check(Some("prod.demo"), Some("dev"), now)
+            .unwrap_err();
+        assert!(err.contains("prod.demo"), "{err}");
+
+        let err = constraints
+            .check(Some("dev.demo"), Some("prod"), now)
+            .unwrap_err();
+        assert!(err.contains("`prod`"), "{err}");
+        assert!(constraints.check(Some("dev.demo"), None, now).is_err());
+
+        let later = now + Duration::hours(2);
+        let err = constraints
+            .check(Some("dev.demo"), Some("dev"), later)
+            .unwrap_err();
+        assert!(err.contains("expired"), "{err}");
+    }
+}
@@ -0,0 +1,243 @@
+#![forbid(unsafe_code)]
+
+//! PEM key handling shared by signing and verification.
+//!
+//! Ed25519 keys use PKCS#8/SPKI. ML-DSA-65 has no settled PKCS#8 encoding yet,
+//! so its keys are stored as raw bytes under their own PEM labels: the 32-byte
+//! FIPS 204 seed for private keys and the encoded public key for public keys.
+
+use anyhow::{Result, anyhow, bail};
+use ed25519_dalek::Signer as _;
+use ed25519_dalek::Verifier as _;
+use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use ed25519_dalek::{Signature as Ed25519Signature, SigningKey, VerifyingKey};
+use pkcs8::der::pem;
+use sha2::{Digest, Sha256};
+
+use crate::manifest::PackSignature;
+
+use super::verify::VerificationError;
+
+pub const ML_DSA_65_PRIVATE_KEY_LABEL: &str = "MLDSA65 PRIVATE KEY";
+pub const ML_DSA_65_PUBLIC_KEY_LABEL: &str = "MLDSA65 PUBLIC KEY";
+
+/// FIPS 204 context string binding ML-DSA signatures to pack manifests.
+#[cfg(feature = "ml-dsa")]
+const ML_DSA_CONTEXT: &[u8] = b"greentic-pack";
+
+/// A private key loaded for signing.
+pub(crate) enum PrivateKey {
+    Ed25519(SigningKey),
+    #[cfg(feature = "ml-dsa")]
+    MlDsa65(mysten_mldsa_native_rs::SigningKeySeed),
+}
+
+impl PrivateKey {
+    pub(crate) fn from_pem(pem: &str) -> Result<Self> {
+        if pem_label(pem).as_deref() == Some(ML_DSA_65_PRIVATE_KEY_LABEL) {
+            return ml_dsa_private_key(pem);
+        }
+        load_ed25519_signing_key(pem).map(PrivateKey::Ed25519)
+    }
+
+    pub(crate) fn algorithm(&self) -> &'static str {
+        match self {
+            PrivateKey::Ed25519(_) => PackSignature::ED25519,
+            #[cfg(feature = "ml-dsa")]
+            PrivateKey::MlDsa65(_) => PackSignature::ML_DSA_65,
+        }
+    }
+
+    pub(crate) fn key_id(&self) -> String {
+        match self {
+            PrivateKey::Ed25519(key) => derive_key_id(key.verifying_key().as_bytes()),
+            #[cfg(feature = "ml-dsa")]
+            PrivateKey::MlDsa65(seed) => derive_key_id(seed.expand().1.as_bytes()),
+        }
+    }
+
+    pub(crate) fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            PrivateKey::Ed25519(key) => Ok(key.sign(message).to_bytes().to_vec()),
+            #[cfg(feature = "ml-dsa")]
+            PrivateKey::MlDsa65(seed) => {
+                use rand_core_06::{OsRng, RngCore};
+
+                let mut rnd = [0u8; mysten_mldsa_native_rs::RND_LENGTH];
+                OsRng.fill_bytes(&mut rnd);
+                let (signing_key, _) = seed.expand();
+                let signature = signing_key
+                    .sign(message, ML_DSA_CONTEXT, &rnd)
+                    .map_err(|err| anyhow!("ML-DSA signing failed: {err:?}"))?;
+                Ok(signature.as_bytes().to_vec())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "ml-dsa")]
+fn ml_dsa_private_key(pem: &str) -> Result<PrivateKey> {
+    let (_, seed) =
+        pem::decode_vec(pem.as_bytes()).map_err(|err| anyhow!("invalid ML-DSA key PEM: {err}"))?;
+    mysten_mldsa_native_rs::SigningKeySeed::from_bytes(&seed)
+        .map(PrivateKey::MlDsa65)
+        .map_err(|_| {
+            anyhow!(
+                "ML-DSA-65 private key must be a {}-byte seed",
+                mysten_mldsa_native_rs::SEED_LENGTH
+            )
+        })
+}
+
+#[cfg(not(feature = "ml-dsa"))]
+fn ml_dsa_private_key(_pem: &str) -> Result<PrivateKey> {
+    bail!("ML-DSA keys require packc to be built with the `ml-dsa` feature")
+}
+
+/// A public key supplied for verification.
+pub(crate) enum PublicKey {
+    Ed25519(VerifyingKey),
+    /// Encoded ML-DSA-65 public key. Parsing does not need the `ml-dsa`
+    /// feature, so key ids can still be matched without it.
+    MlDsa65(Vec<u8>),
+}
+
+impl PublicKey {
+    pub(crate) fn from_pem(pem: &str) -> Result<Self, VerificationError> {
+        if pem_label(pem).as_deref() == Some(ML_DSA_65_PUBLIC_KEY_LABEL) {
+            let (_, bytes) = pem::decode_vec(pem.as_bytes())
+                .map_err(|err| VerificationError::Manifest(anyhow!("invalid ML-DSA key: {err}")))?;
+            return Ok(PublicKey::MlDsa65(bytes));
+        }
+        VerifyingKey::from_public_key_pem(pem)
+            .map(PublicKey::Ed25519)
+            .map_err(VerificationError::PublicKeySpki)
+    }
+
+    pub(crate) fn algorithm(&self) -> &'static str {
+        match self {
+            PublicKey::Ed25519(_) => PackSignature::ED25519,
+            PublicKey::MlDsa65(_) => PackSignature::ML_DSA_65,
+        }
+    }
+
+    pub(crate) fn key_id(&self) -> String {
+        match self {
+            PublicKey::Ed25519(key) => derive_key_id(key.as_bytes()),
+            PublicKey::MlDsa65(bytes) => derive_key_id(bytes),
+        }
+    }
+
+    pub(crate) fn verify(
+        &self,
+        message: &[u8],
+        raw_signature: &[u8],
+        key_id: &str,
+    ) -> Result<(), VerificationError> {
+        match self {
+            PublicKey::Ed25519(key) => {
+                if raw_signature.len() != Ed25519Signature::BYTE_SIZE {
+                    return Err(VerificationError::SignatureLength(raw_signature.len()));
+                }
+                let signature = Ed25519Signature::from_slice(raw_signature)
+                    .map_err(|_| VerificationError::SignatureMalformed)?;
+                key.verify(message, &signature)
+                    .map_err(|_| VerificationError::InvalidSignature {
+                        key_id: key_id.to_string(),
+                    })
+            }
+            PublicKey::MlDsa65(bytes) => verify_ml_dsa(bytes, message, raw_signature, key_id),
+        }
+    }
+}
+
+#[cfg(feature = "ml-dsa")]
+fn verify_ml_dsa(
+    public_key: &[u8],
+    message: &[u8],
+    raw_signature: &[u8],
+    key_id: &str,
+) -> Result<(), VerificationError> {
+    use mysten_mldsa_native_rs::{SIGNATURE_LENGTH, Signature, VerifyingKey};
+
+    let key = VerifyingKey::from_bytes(public_key).map_err(|_| {
+        VerificationError::Manifest(anyhow!("ML-DSA-65 public key has an invalid length"))
+    })?;
+    if raw_signature.len() != SIGNATURE_LENGTH {
+        return Err(VerificationError::SignatureLength(raw_signature.len()));
+    }
+    let signature =
+        Signature::from_bytes(raw_signature).map_err(|_| VerificationError::SignatureMalformed)?;
+    key.verify(message, ML_DSA_CONTEXT, &signature)
+        .map_err(|_| VerificationError::InvalidSignature {
+            key_id: key_id.to_string(),
+        })
+}
+
+#[cfg(not(feature = "ml-dsa"))]
+fn verify_ml_dsa(
+    _public_key: &[u8],
+    _message: &[u8],
+    _raw_signature: &[u8],
+    _key_id: &str,
+) -> Result<(), VerificationError> {
+    Err(VerificationError::UnsupportedAlgorithm {
+        algorithm: format!(
+            "{} (packc was built without the `ml-dsa` feature)",
+            PackSignature::ML_DSA_65
+        ),
+    })
+}
+
+/// Generates a fresh ML-DSA-65 keypair, returned as `(private_pem, public_pem)`.
+#[cfg(feature = "ml-dsa")]
+pub fn generate_ml_dsa_keypair() -> Result<(String, String)> {
+    use rand_core_06::{OsRng, RngCore};
+
+    let mut seed = [0u8; mysten_mldsa_native_rs::SEED_LENGTH];
+    OsRng.fill_bytes(&mut seed);
+    let seed = mysten_mldsa_native_rs::SigningKeySeed::from_bytes(&seed)
+        .map_err(|err| anyhow!("invalid ML-DSA seed: {err:?}"))?;
+    let (_, verifying_key) = seed.expand();
+
+    let encode = |label: &str, bytes: &[u8]| {
+        pem::encode_string(label, pem::LineEnding::LF, bytes)
+            .map_err(|err| anyhow!("failed to encode {label}: {err}"))
+    };
+    Ok((
+        encode(ML_DSA_65_PRIVATE_KEY_LABEL, seed.as_bytes())?,
+        encode(ML_DSA_65_PUBLIC_KEY_LABEL, verifying_key.as_bytes())?,
+    ))
+}
+
+fn load_ed25519_signing_key(pem: &str) -> Result<SigningKey> {
+    match SigningKey::from_pkcs8_pem(pem) {
+        Ok(key) => Ok(key),
+        Err(primary_err) => {
+            // Support "BEGIN ED25519 PRIVATE KEY" by duck-typing the label.
+            let (label, doc) = pkcs8::SecretDocument::from_pem(pem)
+                .map_err(|err| anyhow!("failed to parse private key PEM: {err}"))?;
+
+            if label != "ED25519 PRIVATE KEY" {
+                bail!("unsupported private key format: {primary_err}");
+            }
+
+            SigningKey::from_pkcs8_der(doc.as_bytes()).map_err(|err| {
+                anyhow!("failed to load ED25519 private key from PKCS#8 data: {err}")
+            })
+        }
+    }
+}
+
+/// Key ids are the first 16 bytes (32 hex characters) of the SHA-256 of the
+/// encoded public key.
+pub(crate) fn derive_key_id(public_key_bytes: &[u8]) -> String {
+    let digest = Sha256::digest(public_key_bytes);
+    hex::encode(&digest[..16])
+}
+
+fn pem_label(pem: &str) -> Option<String> {
+    let start = pem.find("-----BEGIN ")? + "-----BEGIN ".len();
+    let end = pem[start..].find("-----")?;
+    Some(pem[start..start + end].trim().to_string())
+}
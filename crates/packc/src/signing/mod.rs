@@ -1,16 +1,29 @@
 #![forbid(unsafe_code)]
 
+use std::collections::BTreeMap;
 use std::path::Path;
 
 use anyhow::Result;
 
+use crate::digest::{self, DigestAlgorithm};
 use crate::manifest::{self, PackSignature};
 
 pub mod canon;
+pub mod constraints;
+pub mod keys;
 pub mod signer;
+pub mod sigstore;
+pub mod trust;
 pub mod verify;
 
-pub use canon::{CanonicalizedPack, canonicalize_pack_dir};
+pub use canon::{
+    CANONICALIZATION_VERSION, CanonicalizedPack, canonicalize_pack_dir, canonicalize_pack_dir_with,
+};
+pub use constraints::KeyConstraints;
+#[cfg(feature = "ml-dsa")]
+pub use keys::generate_ml_dsa_keypair;
+pub use sigstore::{KeylessOptions, KeylessPolicy, SigstoreProof};
+pub use trust::{TrustStore, TrustedKey};
 pub use verify::VerificationError;
 
 /// Options used when verifying pack signatures.
@@ -18,22 +31,120 @@ pub use verify::VerificationError;
 pub struct VerifyOptions<'a> {
     /// Public key in PEM format. When absent, signatures cannot be validated.
     pub public_key_pem: Option<&'a str>,
+    /// Further public keys, such as the ML-DSA key of a hybrid signature. Each
+    /// signature is checked against the key whose id it names.
+    pub additional_public_keys_pem: &'a [&'a str],
+    /// Keys to look up by the key id a signature names, when none of the
+    /// keys above matches it.
+    pub trust_store: Option<&'a TrustStore>,
     /// Allow manifests without signatures.
     pub allow_unsigned: bool,
+    /// Fail unless at least one ML-DSA signature verifies.
+    pub require_post_quantum: bool,
+    /// Environment the pack is being verified for, checked against
+    /// environment-restricted signatures.
+    pub environment: Option<&'a str>,
+    /// Accept the pack once this many signatures by distinct supplied keys
+    /// verify (e.g. 2 of 3), instead of requiring every signature to verify.
+    pub threshold: Option<usize>,
+    /// Trust roots and identity policy for keyless (Sigstore) signatures.
+    pub keyless: KeylessPolicy<'a>,
 }
 
-/// Signs a pack directory using the provided private key and embeds the signature
-/// into the manifest.
+/// Signs a pack directory using the provided private key and adds the signature
+/// to the manifest (see [`append_signatures`]).
 pub fn sign_pack_dir(
     pack_dir: &Path,
     private_key_pem: &str,
     key_id: Option<&str>,
 ) -> Result<PackSignature> {
-    let outcome = signer::sign_pack(pack_dir, private_key_pem, key_id)?;
-    manifest::write_signature(pack_dir, &outcome.signature, None)?;
+    sign_pack_dir_with(
+        pack_dir,
+        private_key_pem,
+        key_id,
+        DigestAlgorithm::default(),
+    )
+}
+
+/// Like [`sign_pack_dir`], recording the canonical digest with `digest_alg`.
+pub fn sign_pack_dir_with(
+    pack_dir: &Path,
+    private_key_pem: &str,
+    key_id: Option<&str>,
+    digest_alg: DigestAlgorithm,
+) -> Result<PackSignature> {
+    let outcome = signer::sign_pack(pack_dir, private_key_pem, key_id, digest_alg)?;
+    append_signatures(pack_dir, std::slice::from_ref(&outcome.signature), None)?;
     Ok(outcome.signature)
 }
 
+/// Signs a pack directory with several keys at once (e.g. Ed25519 and ML-DSA
+/// for a hybrid signature), adding the signatures to the manifest. `constraints`
+/// restrict every produced signature.
+pub fn sign_pack_dir_with_keys(
+    pack_dir: &Path,
+    private_keys_pem: &[&str],
+    digest_alg: DigestAlgorithm,
+    constraints: Option<&KeyConstraints>,
+) -> Result<Vec<PackSignature>> {
+    let signatures =
+        signer::sign_pack_with_keys(pack_dir, private_keys_pem, digest_alg, constraints)?;
+    append_signatures(pack_dir, &signatures, None)?;
+    Ok(signatures)
+}
+
+/// Adds `signatures` to those already on the manifest, so a pack can carry
+/// e.g. a developer and a release manager signature, and returns the full set.
+///
+/// A new signature replaces an existing one with the same key id. Existing
+/// signatures over different pack contents are dropped, since they could no
+/// longer verify. The primary signature stays first.
+pub fn append_signatures(
+    pack_dir: &Path,
+    signatures: &[PackSignature],
+    out_path: Option<&Path>,
+) -> Result<Vec<PackSignature>> {
+    let mut merged: Vec<PackSignature> = Vec::new();
+    let mut current_digests = BTreeMap::new();
+    for existing in manifest::read_signatures(pack_dir)? {
+        let replacement = signatures
+            .iter()
+            .find(|signature| signature.key_id == existing.key_id);
+        if let Some(replacement) = replacement {
+            if !merged.iter().any(|kept| kept.key_id == existing.key_id) {
+                merged.push(replacement.clone());
+            }
+            continue;
+        }
+        let Ok((alg, _)) = digest::parse(&existing.digest) else {
+            tracing::warn!(key_id = %existing.key_id, "dropping signature with an unsupported digest");
+            continue;
+        };
+        if !current_digests.contains_key(alg.as_str()) {
+            let canonical = canonicalize_pack_dir_with(pack_dir, alg)?;
+            current_digests.insert(alg.as_str(), canonical.digest());
+        }
+        if existing
+            .digest
+            .eq_ignore_ascii_case(&current_digests[alg.as_str()])
+        {
+            merged.push(existing);
+        } else {
+            tracing::warn!(
+                key_id = %existing.key_id,
+                "dropping signature over different pack contents"
+            );
+        }
+    }
+    for signature in signatures {
+        if !merged.iter().any(|kept| kept.key_id == signature.key_id) {
+            merged.push(signature.clone());
+        }
+    }
+    manifest::write_signatures(pack_dir, &merged, out_path)?;
+    Ok(merged)
+}
+
 /// Verifies a pack directory using the supplied options.
 pub fn verify_pack_dir(pack_dir: &Path, opts: VerifyOptions<'_>) -> Result<PackSignature> {
     verify::verify_pack(pack_dir, opts).map_err(anyhow::Error::new)
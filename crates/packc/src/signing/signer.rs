@@ -2,17 +2,17 @@
 
 use std::path::Path;
 
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, bail};
 use base64::Engine as _;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
-use ed25519_dalek::Signer as _;
-use ed25519_dalek::{SigningKey, pkcs8::DecodePrivateKey};
-use sha2::{Digest, Sha256};
 use time::OffsetDateTime;
 
-use crate::manifest::PackSignature;
+use crate::digest::DigestAlgorithm;
+use crate::manifest::{self, PackSignature};
 
-use super::canon::{CanonicalizedPack, canonicalize_pack_dir};
+use super::canon::{CanonicalizedPack, canonicalize_pack_dir_with};
+use super::constraints::KeyConstraints;
+use super::keys::PrivateKey;
 
 /// Result of signing a pack directory.
 pub struct SigningOutcome {
@@ -26,59 +26,76 @@ pub fn sign_pack(
     pack_dir: &Path,
     private_key_pem: &str,
     key_id_override: Option<&str>,
+    digest_alg: DigestAlgorithm,
 ) -> Result<SigningOutcome> {
-    let canonical = canonicalize_pack_dir(pack_dir)?;
-
-    let signing_key = load_signing_key(private_key_pem)?;
-    let verifying_key = signing_key.verifying_key();
-
-    let key_id = key_id_override
-        .map(|value| value.to_string())
-        .unwrap_or_else(|| derive_key_id(verifying_key.as_bytes()));
-
-    let signature = signing_key.sign(&canonical.bytes);
-    let encoded_sig = URL_SAFE_NO_PAD.encode(signature.to_bytes());
-
-    let pack_signature = PackSignature {
-        alg: "ed25519".to_string(),
-        key_id,
-        created_at: OffsetDateTime::now_utc(),
-        digest: format!("sha256:{}", canonical.digest_hex),
-        sig: encoded_sig,
-    };
+    let canonical = canonicalize_pack_dir_with(pack_dir, digest_alg)?;
+    let signature = sign_canonical(&canonical, private_key_pem, key_id_override, None)?;
 
     Ok(SigningOutcome {
-        signature: pack_signature,
+        signature,
         canonical,
     })
 }
 
-fn load_signing_key(pem: &str) -> Result<SigningKey> {
-    match SigningKey::from_pkcs8_pem(pem) {
-        Ok(key) => Ok(key),
-        Err(primary_err) => {
-            // Support "BEGIN ED25519 PRIVATE KEY" by duck-typing the label.
-            let (label, doc) = pkcs8::SecretDocument::from_pem(pem)
-                .map_err(|err| anyhow!("failed to parse private key PEM: {err}"))?;
-
-            if label != "ED25519 PRIVATE KEY" {
-                return Err(anyhow!("unsupported private key format: {primary_err}"));
-            }
-
-            SigningKey::from_pkcs8_der(doc.as_bytes()).map_err(|err| {
-                anyhow!("failed to load ED25519 private key from PKCS#8 data: {err}")
-            })
-        }
+/// Signs the canonical pack bytes with every key, producing one signature per
+/// key (e.g. an Ed25519 + ML-DSA hybrid). Key ids are derived from the keys.
+pub fn sign_pack_with_keys(
+    pack_dir: &Path,
+    private_keys_pem: &[&str],
+    digest_alg: DigestAlgorithm,
+    constraints: Option<&KeyConstraints>,
+) -> Result<Vec<PackSignature>> {
+    if private_keys_pem.is_empty() {
+        bail!("at least one signing key is required");
     }
+    if let Some(constraints) = constraints {
+        check_constraints_cover(pack_dir, constraints)?;
+    }
+    let canonical = canonicalize_pack_dir_with(pack_dir, digest_alg)?;
+    private_keys_pem
+        .iter()
+        .map(|pem| sign_canonical(&canonical, pem, None, constraints))
+        .collect()
 }
 
-fn derive_key_id(public_key_bytes: &[u8]) -> String {
-    let digest = Sha256::digest(public_key_bytes);
-    // Truncate to the first 16 bytes = 32 hex characters.
-    hex::encode(&digest[..16])
+/// Signs already canonicalised pack bytes with a single Ed25519 or ML-DSA key.
+/// Non-empty `constraints` are recorded on the signature and signed with it.
+pub fn sign_canonical(
+    canonical: &CanonicalizedPack,
+    private_key_pem: &str,
+    key_id_override: Option<&str>,
+    constraints: Option<&KeyConstraints>,
+) -> Result<PackSignature> {
+    let key = PrivateKey::from_pem(private_key_pem)?;
+
+    let key_id = key_id_override
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| key.key_id());
+
+    let constraints = constraints.filter(|constraints| !constraints.is_empty());
+    let signature = key.sign(&KeyConstraints::signed_message(
+        constraints,
+        &canonical.bytes,
+    ))?;
+
+    Ok(PackSignature {
+        alg: key.algorithm().to_string(),
+        key_id,
+        created_at: OffsetDateTime::now_utc(),
+        digest: canonical.digest(),
+        sig: URL_SAFE_NO_PAD.encode(signature),
+        constraints: constraints.cloned(),
+        sigstore: None,
+    })
 }
 
-// Re-export `SecretDocument` under this module to avoid leaking dependency internals.
-mod pkcs8 {
-    pub use pkcs8::SecretDocument;
+/// Refuses to produce a signature that its own constraints would reject for
+/// this pack (wrong id, or already expired).
+pub fn check_constraints_cover(pack_dir: &Path, constraints: &KeyConstraints) -> Result<()> {
+    let pack_id = manifest::pack_id(pack_dir)?;
+    let environment = constraints.environments.first().map(String::as_str);
+    constraints
+        .check(pack_id.as_deref(), environment, OffsetDateTime::now_utc())
+        .map_err(anyhow::Error::msg)
+        .context("signature constraints do not cover this pack")
 }
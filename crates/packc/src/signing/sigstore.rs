@@ -0,0 +1,1211 @@
+#![forbid(unsafe_code)]
+
+//! Keyless signing through Sigstore.
+//!
+//! `packc sign --keyless` signs with a throwaway Ed25519 key. Fulcio certifies
+//! that key for the OIDC identity of the caller (e.g. a CI workflow), and the
+//! signature is recorded in the Rekor transparency log. The certificate is only
+//! valid for minutes, so verification checks that the log integrated the entry
+//! while the certificate was valid, instead of checking the current time.
+//!
+//! Keyless signatures sign the SHA-256 of the signed message rather than the
+//! message itself, so only that digest is uploaded to Rekor.
+//!
+//! The signature keeps the log entry with its signed entry timestamp (SET)
+//! and inclusion proof, which verify offline against the log's public key;
+//! the log is only asked for entries recorded without them. Fulcio
+//! certificates must be issued for code signing, by CA certificates.
+
+use ::time::OffsetDateTime;
+use anyhow::{Context, Result, anyhow, bail};
+use base64::Engine as _;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use ed25519_dalek::pkcs8::EncodePublicKey;
+use ed25519_dalek::{
+    Signature as Ed25519Signature, Signer as _, SigningKey, Verifier as _, VerifyingKey,
+};
+use pkcs8::LineEnding;
+use rand_core_06::OsRng;
+use ring::signature::{ECDSA_P256_SHA256_ASN1, UnparsedPublicKey};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use x509_parser::extensions::GeneralName;
+use x509_parser::pem::parse_x509_pem;
+use x509_parser::prelude::*;
+
+use crate::http::Request;
+use crate::manifest::PackSignature;
+
+use super::canon::CanonicalizedPack;
+use super::constraints::KeyConstraints;
+use super::keys::derive_key_id;
+use super::verify::VerificationError;
+
+pub const DEFAULT_FULCIO_URL: &str = "https://fulcio.sigstore.dev";
+pub const DEFAULT_REKOR_URL: &str = "https://rekor.sigstore.dev";
+/// OIDC identity token used for keyless signing, if set.
+pub const ID_TOKEN_ENV: &str = "SIGSTORE_ID_TOKEN";
+
+/// Fulcio certificate extensions naming the OIDC issuer: the current
+/// DER-encoded one and the deprecated raw-string one.
+const OIDC_ISSUER_OID: &str = "1.3.6.1.4.1.57264.1.8";
+const OIDC_ISSUER_LEGACY_OID: &str = "1.3.6.1.4.1.57264.1.1";
+
+/// Evidence carried by a keyless signature.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SigstoreProof {
+    /// Fulcio certificate chain as PEM, leaf first.
+    pub certificate_chain: Vec<String>,
+    /// Identity the certificate was issued to (an email or workflow URI).
+    pub identity: String,
+    /// OIDC issuer that vouched for the identity.
+    pub issuer: String,
+    pub rekor: RekorEntry,
+}
+
+/// Where the signature was recorded in the transparency log.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RekorEntry {
+    /// Log the entry was uploaded to.
+    pub url: String,
+    pub uuid: String,
+    pub log_index: u64,
+    /// Unix time at which the log integrated the entry.
+    pub integrated_time: i64,
+    /// Signed entry timestamp returned by the log.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_entry_timestamp: Option<String>,
+    /// Entry body (base64) the timestamp and the inclusion proof cover.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// SHA-256 of the log's public key, hex.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inclusion_proof: Option<InclusionProof>,
+}
+
+/// Proof that the entry is a leaf of the log's Merkle tree, as of a
+/// checkpoint the log signed.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InclusionProof {
+    /// Index of the leaf in the tree, which on a sharded log differs from
+    /// the entry's log index.
+    pub log_index: u64,
+    pub tree_size: u64,
+    /// Root hash of the tree, hex.
+    pub root_hash: String,
+    /// Sibling hashes from the leaf up, hex.
+    pub hashes: Vec<String>,
+    /// Signed note committing the log to the tree size and root hash.
+    pub checkpoint: String,
+}
+
+/// Services used for keyless signing.
+#[derive(Debug, Clone, Copy)]
+pub struct KeylessOptions<'a> {
+    pub fulcio_url: &'a str,
+    pub rekor_url: &'a str,
+    /// OIDC identity token. When absent it is read from [`ID_TOKEN_ENV`] or
+    /// requested from GitHub Actions.
+    pub identity_token: Option<&'a str>,
+}
+
+impl Default for KeylessOptions<'_> {
+    fn default() -> Self {
+        Self {
+            fulcio_url: DEFAULT_FULCIO_URL,
+            rekor_url: DEFAULT_REKOR_URL,
+            identity_token: None,
+        }
+    }
+}
+
+/// What a keyless signature must satisfy to verify.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeylessPolicy<'a> {
+    /// Trusted Fulcio root certificates (PEM). Keyless signatures do not
+    /// verify without at least one.
+    pub trusted_roots_pem: &'a [&'a str],
+    /// Required certificate identity.
+    pub identity: Option<&'a str>,
+    /// Required OIDC issuer.
+    pub issuer: Option<&'a str>,
+    /// Log to look the entry up in (defaults to [`DEFAULT_REKOR_URL`]).
+    pub rekor_url: Option<&'a str>,
+    /// Public key (PEM) of that log, which its signed entry timestamps and
+    /// checkpoints must verify against. Keyless signatures do not verify
+    /// without it.
+    pub rekor_public_key_pem: Option<&'a str>,
+}
+
+/// Signs canonical pack bytes with a Fulcio-certified throwaway key and records
+/// the signature in Rekor.
+pub fn sign_canonical(
+    canonical: &CanonicalizedPack,
+    constraints: Option<&KeyConstraints>,
+    opts: &KeylessOptions<'_>,
+) -> Result<PackSignature> {
+    let token = match opts.identity_token {
+        Some(token) => token.to_string(),
+        None => identity_token()?,
+    };
+    let claims = TokenClaims::parse(&token)?;
+
+    let key = SigningKey::generate(&mut OsRng);
+    let public_pem = key
+        .verifying_key()
+        .to_public_key_pem(LineEnding::LF)
+        .map_err(|err| anyhow!("failed to encode the signing key: {err}"))?;
+    let proof_of_possession = key.sign(claims.subject.as_bytes());
+    let chain = request_certificate(
+        opts.fulcio_url,
+        &token,
+        &public_pem,
+        &proof_of_possession.to_bytes(),
+    )?;
+    let leaf_der = pem_to_der(&chain[0])?;
+    let (identity, issuer) = {
+        let leaf = parse_certificate(&leaf_der)?;
+        (certificate_identity(&leaf)?, certificate_issuer(&leaf)?)
+    };
+
+    let constraints = constraints.filter(|constraints| !constraints.is_empty());
+    let prehash = Sha256::digest(KeyConstraints::signed_message(
+        constraints,
+        &canonical.bytes,
+    ));
+    let signature = key.sign(&prehash).to_bytes();
+    let rekor = upload_entry(opts.rekor_url, &signature, &chain[0], &prehash)?;
+    tracing::info!(
+        identity = %identity,
+        log_index = rekor.log_index,
+        "recorded keyless signature in the transparency log"
+    );
+
+    Ok(PackSignature {
+        alg: PackSignature::ED25519.to_string(),
+        key_id: derive_key_id(key.verifying_key().as_bytes()),
+        created_at: OffsetDateTime::now_utc(),
+        digest: canonical.digest(),
+        sig: URL_SAFE_NO_PAD.encode(signature),
+        constraints: constraints.cloned(),
+        sigstore: Some(SigstoreProof {
+            certificate_chain: chain,
+            identity,
+            issuer,
+            rekor,
+        }),
+    })
+}
+
+/// Verifies a keyless signature over `message`: the certificate chain, the
+/// signature, the identity policy and the transparency log entry, whose SET
+/// and inclusion proof must verify against the log's key.
+pub(crate) fn verify_signature(
+    signature: &PackSignature,
+    proof: &SigstoreProof,
+    message: &[u8],
+    policy: &KeylessPolicy<'_>,
+) -> Result<(), VerificationError> {
+    let fail = |reason: String| VerificationError::Keyless {
+        key_id: signature.key_id.clone(),
+        reason,
+    };
+    let chain = proof
+        .certificate_chain
+        .iter()
+        .map(|pem| pem_to_der(pem))
+        .collect::<Result<Vec<_>>>()
+        .map_err(|err| fail(format!("{err:#}")))?;
+    let leaf =
+        verify_chain(&chain, policy.trusted_roots_pem).map_err(|err| fail(format!("{err:#}")))?;
+
+    let key = ed25519_key(&leaf).map_err(|err| fail(format!("{err:#}")))?;
+    if derive_key_id(key.as_bytes()) != signature.key_id {
+        return Err(VerificationError::KeyIdMismatch {
+            expected: signature.key_id.clone(),
+            provided: derive_key_id(key.as_bytes()),
+        });
+    }
+    let raw_signature = URL_SAFE_NO_PAD.decode(signature.sig.as_bytes())?;
+    let ed_signature = Ed25519Signature::from_slice(&raw_signature)
+        .map_err(|_| VerificationError::SignatureLength(raw_signature.len()))?;
+    let prehash = Sha256::digest(message);
+    key.verify(&prehash, &ed_signature)
+        .map_err(|_| VerificationError::InvalidSignature {
+            key_id: signature.key_id.clone(),
+        })?;
+
+    let identity = certificate_identity(&leaf).map_err(|err| fail(format!("{err:#}")))?;
+    let issuer = certificate_issuer(&leaf).map_err(|err| fail(format!("{err:#}")))?;
+    if identity != proof.identity || issuer != proof.issuer {
+        return Err(fail(format!(
+            "certificate was issued to {identity} by {issuer}, not {} by {}",
+            proof.identity, proof.issuer
+        )));
+    }
+    if let Some(required) = policy.identity.filter(|required| *required != identity) {
+        return Err(fail(format!(
+            "signed by {identity}, but {required} is required"
+        )));
+    }
+    if let Some(required) = policy.issuer.filter(|required| *required != issuer) {
+        return Err(fail(format!(
+            "identity issued by {issuer}, but {required} is required"
+        )));
+    }
+
+    let rekor_url = policy.rekor_url.unwrap_or(DEFAULT_REKOR_URL);
+    let log_key = policy
+        .rekor_public_key_pem
+        .ok_or_else(|| anyhow!("no Rekor public key was supplied"))
+        .and_then(LogKey::from_pem)
+        .map_err(|err| fail(format!("{err:#}")))?;
+    let entry = if proof.rekor.body.is_some() {
+        proof.rekor.clone()
+    } else {
+        fetch_entry(rekor_url, &proof.rekor.uuid).map_err(|err| fail(format!("{err:#}")))?
+    };
+    verify_log_entry(&entry, &log_key)
+        .and_then(|()| check_log_entry(&entry, &raw_signature, &chain[0], &prehash))
+        .map_err(|err| fail(format!("{rekor_url}: {err:#}")))?;
+    let integrated_time = entry.integrated_time;
+    if integrated_time != proof.rekor.integrated_time || entry.log_index != proof.rekor.log_index {
+        return Err(fail(format!(
+            "{rekor_url} integrated the entry at {integrated_time} as log index {}, not at {} as {}",
+            entry.log_index, proof.rekor.integrated_time, proof.rekor.log_index
+        )));
+    }
+    let validity = leaf.validity();
+    if integrated_time < validity.not_before.timestamp()
+        || integrated_time > validity.not_after.timestamp()
+    {
+        return Err(fail(
+            "the log integrated the entry outside the certificate's validity".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Reads the OIDC identity token from [`ID_TOKEN_ENV`], or requests one for
+/// the `sigstore` audience from GitHub Actions.
+fn identity_token() -> Result<String> {
+    if let Ok(token) = std::env::var(ID_TOKEN_ENV) {
+        return Ok(token.trim().to_string());
+    }
+    let (Ok(url), Ok(bearer)) = (
+        std::env::var("ACTIONS_ID_TOKEN_REQUEST_URL"),
+        std::env::var("ACTIONS_ID_TOKEN_REQUEST_TOKEN"),
+    ) else {
+        bail!(
+            "no OIDC identity token: set {ID_TOKEN_ENV}, or run in GitHub Actions with `id-token: write`"
+        );
+    };
+    let response = Request::get(&format!("{url}&audience=sigstore"))
+        .accept_json()
+        .bearer(&bearer)
+        .send()
+        .context("failed to request an identity token from GitHub Actions")?;
+    let response: Value = serde_json::from_slice(&response)
+        .context("GitHub Actions returned an invalid identity token response")?;
+    response["value"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("GitHub Actions returned no identity token"))
+}
+
+/// The claims of an identity token that Fulcio checks.
+#[derive(Debug, PartialEq, Eq)]
+struct TokenClaims {
+    /// Value Fulcio expects the proof of possession to sign: the email when
+    /// the token carries one, otherwise the subject.
+    subject: String,
+}
+
+impl TokenClaims {
+    fn parse(token: &str) -> Result<Self> {
+        let payload = token
+            .split('.')
+            .nth(1)
+            .ok_or_else(|| anyhow!("identity token is not a JWT"))?;
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload.trim_end_matches('='))
+            .context("identity token payload is not base64url")?;
+        let claims: Value =
+            serde_json::from_slice(&payload).context("identity token payload is not JSON")?;
+        let subject = claims["email"]
+            .as_str()
+            .or_else(|| claims["sub"].as_str())
+            .ok_or_else(|| anyhow!("identity token has neither `email` nor `sub`"))?;
+        Ok(Self {
+            subject: subject.to_string(),
+        })
+    }
+}
+
+/// Asks Fulcio to certify `public_pem`, returning the chain as PEM, leaf first.
+fn request_certificate(
+    fulcio_url: &str,
+    token: &str,
+    public_pem: &str,
+    proof_of_possession: &[u8],
+) -> Result<Vec<String>> {
+    let endpoint = format!("{}/api/v2/signingCert", fulcio_url.trim_end_matches('/'));
+    let request = json!({
+        "credentials": { "oidcIdentityToken": token },
+        "publicKeyRequest": {
+            "publicKey": { "algorithm": "ED25519", "content": public_pem },
+            "proofOfPossession": STANDARD.encode(proof_of_possession),
+        },
+    });
+    let response = Request::post_json(&endpoint, &serde_json::to_vec(&request)?)
+        .accept_json()
+        .send()
+        .with_context(|| format!("Fulcio at {fulcio_url} did not issue a certificate"))?;
+    let response: Value = serde_json::from_slice(&response)
+        .with_context(|| format!("{endpoint} returned an invalid response"))?;
+    let certificates = [
+        "signedCertificateEmbeddedSct",
+        "signedCertificateDetachedSct",
+    ]
+    .iter()
+    .find_map(|kind| response[kind]["chain"]["certificates"].as_array())
+    .ok_or_else(|| anyhow!("{endpoint} returned no certificate chain"))?;
+    let chain = certificates
+        .iter()
+        .map(|pem| {
+            pem.as_str()
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("{endpoint} returned a malformed certificate chain"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if chain.is_empty() {
+        bail!("{endpoint} returned an empty certificate chain");
+    }
+    Ok(chain)
+}
+
+/// Records the signature over `prehash` in Rekor as a `rekord` entry.
+fn upload_entry(
+    rekor_url: &str,
+    signature: &[u8],
+    leaf_pem: &str,
+    prehash: &[u8],
+) -> Result<RekorEntry> {
+    let endpoint = format!("{}/api/v1/log/entries", rekor_url.trim_end_matches('/'));
+    let request = json!({
+        "apiVersion": "0.0.1",
+        "kind": "rekord",
+        "spec": {
+            "signature": {
+                "format": "x509",
+                "content": STANDARD.encode(signature),
+                "publicKey": { "content": STANDARD.encode(leaf_pem) },
+            },
+            "data": { "content": STANDARD.encode(prehash) },
+        },
+    });
+    let response = Request::post_json(&endpoint, &serde_json::to_vec(&request)?)
+        .accept_json()
+        .send()
+        .with_context(|| format!("failed to record the signature in Rekor at {rekor_url}"))?;
+    let response: Value = serde_json::from_slice(&response)
+        .with_context(|| format!("{endpoint} returned an invalid response"))?;
+    let (uuid, entry) = response
+        .as_object()
+        .and_then(|entries| entries.iter().next())
+        .ok_or_else(|| anyhow!("{endpoint} returned no log entry"))?;
+    parse_entry(rekor_url, uuid, entry)
+        .with_context(|| format!("{endpoint} returned a malformed entry"))
+}
+
+/// Looks `uuid` up in the log, for signatures recorded without the entry.
+fn fetch_entry(rekor_url: &str, uuid: &str) -> Result<RekorEntry> {
+    let endpoint = format!(
+        "{}/api/v1/log/entries/{uuid}",
+        rekor_url.trim_end_matches('/')
+    );
+    let response = Request::get(&endpoint)
+        .accept_json()
+        .send()
+        .with_context(|| format!("entry {uuid} was not found in the transparency log"))?;
+    let response: Value = serde_json::from_slice(&response)
+        .with_context(|| format!("{endpoint} returned invalid JSON"))?;
+    let (key, entry) = response
+        .as_object()
+        .and_then(|entries| {
+            // Logs may key entries by `<tree id><uuid>`.
+            entries
+                .get_key_value(uuid)
+                .or_else(|| entries.iter().find(|(key, _)| key.ends_with(uuid)))
+        })
+        .ok_or_else(|| anyhow!("entry {uuid} is missing from the response"))?;
+    parse_entry(rekor_url, key, entry).with_context(|| format!("entry {uuid} is malformed"))
+}
+
+/// A log entry as Rekor returns it.
+fn parse_entry(rekor_url: &str, uuid: &str, entry: &Value) -> Result<RekorEntry> {
+    let text = |value: &Value, name: &str| {
+        value
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("no {name}"))
+    };
+    let number = |value: &Value, name: &str| value.as_u64().ok_or_else(|| anyhow!("no {name}"));
+    let proof = &entry["verification"]["inclusionProof"];
+    let inclusion_proof = if proof.is_null() {
+        None
+    } else {
+        Some(InclusionProof {
+            log_index: number(&proof["logIndex"], "inclusion proof log index")?,
+            tree_size: number(&proof["treeSize"], "inclusion proof tree size")?,
+            root_hash: text(&proof["rootHash"], "inclusion proof root hash")?,
+            hashes: proof["hashes"]
+                .as_array()
+                .ok_or_else(|| anyhow!("no inclusion proof hashes"))?
+                .iter()
+                .map(|hash| text(hash, "inclusion proof hash"))
+                .collect::<Result<_>>()?,
+            checkpoint: text(&proof["checkpoint"], "checkpoint")?,
+        })
+    };
+    Ok(RekorEntry {
+        url: rekor_url.to_string(),
+        uuid: uuid.to_string(),
+        log_index: number(&entry["logIndex"], "log index")?,
+        integrated_time: entry["integratedTime"]
+            .as_i64()
+            .ok_or_else(|| anyhow!("no integration time"))?,
+        signed_entry_timestamp: entry["verification"]["signedEntryTimestamp"]
+            .as_str()
+            .map(str::to_string),
+        body: Some(text(&entry["body"], "body")?),
+        log_id: Some(text(&entry["logID"], "log id")?),
+        inclusion_proof,
+    })
+}
+
+/// The public key of a transparency log.
+struct LogKey {
+    /// Uncompressed P-256 point.
+    point: Vec<u8>,
+    /// SHA-256 of the DER key, hex, as logs name themselves.
+    id: String,
+}
+
+impl LogKey {
+    fn from_pem(pem: &str) -> Result<Self> {
+        let (_, pem) = parse_x509_pem(pem.as_bytes())
+            .map_err(|err| anyhow!("invalid Rekor public key PEM: {err}"))?;
+        if pem.label != "PUBLIC KEY" {
+            bail!("unexpected PEM label {}; expected PUBLIC KEY", pem.label);
+        }
+        let (_, key) = SubjectPublicKeyInfo::from_der(&pem.contents)
+            .map_err(|err| anyhow!("invalid Rekor public key: {err}"))?;
+        Ok(Self {
+            point: key.subject_public_key.data.to_vec(),
+            id: hex::encode(Sha256::digest(&pem.contents)),
+        })
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, &self.point)
+            .verify(message, signature)
+            .is_ok()
+    }
+}
+
+/// Checks that the log vouches for `entry`: its SET signs the entry, and its
+/// inclusion proof leads to the root of a checkpoint the log signed.
+fn verify_log_entry(entry: &RekorEntry, key: &LogKey) -> Result<()> {
+    let uuid = &entry.uuid;
+    let (Some(body), Some(log_id)) = (&entry.body, &entry.log_id) else {
+        bail!("entry {uuid} has no body");
+    };
+    if *log_id != key.id {
+        bail!("entry {uuid} was recorded by another log ({log_id})");
+    }
+
+    let set = entry
+        .signed_entry_timestamp
+        .as_deref()
+        .ok_or_else(|| anyhow!("entry {uuid} has no signed entry timestamp"))?;
+    let set = STANDARD
+        .decode(set)
+        .context("signed entry timestamp is not base64")?;
+    // The canonical JSON of the entry, keys sorted.
+    let signed = format!(
+        r#"{{"body":{},"integratedTime":{},"logID":{},"logIndex":{}}}"#,
+        serde_json::to_string(body)?,
+        entry.integrated_time,
+        serde_json::to_string(log_id)?,
+        entry.log_index
+    );
+    if !key.verify(signed.as_bytes(), &set) {
+        bail!("the signed entry timestamp of entry {uuid} does not verify");
+    }
+
+    let proof = entry
+        .inclusion_proof
+        .as_ref()
+        .ok_or_else(|| anyhow!("entry {uuid} has no inclusion proof"))?;
+    let leaf: [u8; 32] = Sha256::new()
+        .chain_update([0x00])
+        .chain_update(STANDARD.decode(body).context("entry body is not base64")?)
+        .finalize()
+        .into();
+    if !uuid.ends_with(&hex::encode(leaf)) {
+        bail!("entry {uuid} does not name the hash of its body");
+    }
+    let hashes = proof
+        .hashes
+        .iter()
+        .map(|hash| merkle_hash(hash))
+        .collect::<Result<Vec<_>>>()?;
+    let root = merkle_root(proof.log_index, proof.tree_size, leaf, &hashes)
+        .with_context(|| format!("the inclusion proof of entry {uuid} is invalid"))?;
+    if root != merkle_hash(&proof.root_hash)? {
+        bail!("the inclusion proof of entry {uuid} does not lead to its root hash");
+    }
+    let (tree_size, checkpoint_root) = verify_checkpoint(&proof.checkpoint, key)?;
+    if tree_size != proof.tree_size || checkpoint_root != root {
+        bail!("the checkpoint of entry {uuid} is for another tree");
+    }
+    Ok(())
+}
+
+/// The root hash an RFC 9162 inclusion proof for the leaf at `index` leads
+/// to, in a tree of `size` leaves.
+fn merkle_root(index: u64, size: u64, leaf: [u8; 32], proof: &[[u8; 32]]) -> Result<[u8; 32]> {
+    if index >= size {
+        bail!("leaf {index} is outside a tree of {size}");
+    }
+    let node = |left: &[u8], right: &[u8]| -> [u8; 32] {
+        Sha256::new()
+            .chain_update([0x01])
+            .chain_update(left)
+            .chain_update(right)
+            .finalize()
+            .into()
+    };
+    let (mut fn_, mut sn) = (index, size - 1);
+    let mut root = leaf;
+    for sibling in proof {
+        if sn == 0 {
+            bail!("the proof is longer than the tree is deep");
+        }
+        if fn_ & 1 == 1 || fn_ == sn {
+            root = node(sibling, &root);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            root = node(&root, sibling);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+    if sn != 0 {
+        bail!("the proof is shorter than the tree is deep");
+    }
+    Ok(root)
+}
+
+fn merkle_hash(hex_hash: &str) -> Result<[u8; 32]> {
+    hex::decode(hex_hash)
+        .ok()
+        .and_then(|hash| hash.try_into().ok())
+        .ok_or_else(|| anyhow!("{hex_hash} is not a SHA-256 hash"))
+}
+
+/// Checks that the log signed a checkpoint note, returning the tree size and
+/// root hash it commits to.
+fn verify_checkpoint(checkpoint: &str, key: &LogKey) -> Result<(u64, [u8; 32])> {
+    let (text, signatures) = checkpoint
+        .split_once("\n\n")
+        .ok_or_else(|| anyhow!("checkpoint is not a signed note"))?;
+    let signed = format!("{text}\n");
+    // Signature lines are `— <name> <base64 of a 4-byte key hint and the signature>`.
+    let verified = signatures.lines().any(|line| {
+        line.strip_prefix("\u{2014} ")
+            .and_then(|line| line.rsplit_once(' '))
+            .and_then(|(_, signature)| STANDARD.decode(signature).ok())
+            .is_some_and(|signature| {
+                signature.len() > 4 && key.verify(signed.as_bytes(), &signature[4..])
+            })
+    });
+    if !verified {
+        bail!("checkpoint is not signed by the log");
+    }
+    let mut lines = text.lines().skip(1);
+    let tree_size = lines
+        .next()
+        .and_then(|size| size.parse().ok())
+        .ok_or_else(|| anyhow!("checkpoint has no tree size"))?;
+    let root = lines
+        .next()
+        .and_then(|root| STANDARD.decode(root).ok())
+        .and_then(|root| root.try_into().ok())
+        .ok_or_else(|| anyhow!("checkpoint has no root hash"))?;
+    Ok((tree_size, root))
+}
+
+/// Checks that the log entry records this signature by this certificate over
+/// `prehash`.
+fn check_log_entry(
+    entry: &RekorEntry,
+    signature: &[u8],
+    leaf_der: &[u8],
+    prehash: &[u8],
+) -> Result<()> {
+    let uuid = &entry.uuid;
+    let body = entry
+        .body
+        .as_deref()
+        .ok_or_else(|| anyhow!("entry {uuid} has no body"))?;
+    let body: Value =
+        serde_json::from_slice(&STANDARD.decode(body).context("entry body is not base64")?)
+            .context("entry body is not JSON")?;
+    if body["kind"] != "rekord" {
+        bail!("entry {uuid} is a `{}` entry, not `rekord`", body["kind"]);
+    }
+
+    let spec = &body["spec"];
+    if spec["signature"]["content"].as_str() != Some(STANDARD.encode(signature).as_str()) {
+        bail!("entry {uuid} records a different signature");
+    }
+    let logged_pem = spec["signature"]["publicKey"]["content"]
+        .as_str()
+        .and_then(|content| STANDARD.decode(content).ok())
+        .and_then(|pem| String::from_utf8(pem).ok())
+        .ok_or_else(|| anyhow!("entry {uuid} has no certificate"))?;
+    if pem_to_der(&logged_pem)? != leaf_der {
+        bail!("entry {uuid} records a different certificate");
+    }
+    let logged_hash = spec["data"]["hash"]["value"].as_str().unwrap_or_default();
+    if !logged_hash.eq_ignore_ascii_case(&hex::encode(Sha256::digest(prehash))) {
+        bail!("entry {uuid} records a signature over different data");
+    }
+    Ok(())
+}
+
+/// Checks that every certificate is signed by the next one and that the last
+/// is, or is signed by, a trusted root. Returns the leaf.
+fn verify_chain<'c>(
+    chain: &'c [Vec<u8>],
+    trusted_roots_pem: &[&str],
+) -> Result<X509Certificate<'c>> {
+    if trusted_roots_pem.is_empty() {
+        bail!("no trusted Fulcio root certificate was supplied");
+    }
+    let certs = chain
+        .iter()
+        .map(|der| parse_certificate(der))
+        .collect::<Result<Vec<_>>>()?;
+    let Some(last) = certs.last() else {
+        bail!("certificate chain is empty");
+    };
+    let leaf = &certs[0];
+    let code_signing = leaf
+        .extended_key_usage()
+        .map_err(|err| anyhow!("invalid extended key usage: {err}"))?
+        .is_some_and(|usage| usage.value.code_signing);
+    if !code_signing {
+        bail!(
+            "certificate `{}` is not issued for code signing",
+            leaf.subject()
+        );
+    }
+    for pair in certs.windows(2) {
+        let is_ca = pair[1]
+            .basic_constraints()
+            .map_err(|err| anyhow!("invalid basic constraints: {err}"))?
+            .is_some_and(|constraints| constraints.value.ca);
+        if !is_ca {
+            bail!(
+                "certificate `{}` issued `{}` but is not a CA",
+                pair[1].subject(),
+                pair[0].subject()
+            );
+        }
+        pair[0]
+            .verify_signature(Some(pair[1].public_key()))
+            .map_err(|err| {
+                anyhow!(
+                    "certificate `{}` is not signed by `{}`: {err}",
+                    pair[0].subject(),
+                    pair[1].subject()
+                )
+            })?;
+    }
+
+    let roots = trusted_roots_pem
+        .iter()
+        .map(|pem| pem_to_der(pem))
+        .collect::<Result<Vec<_>>>()?;
+    let anchored = roots.iter().any(|root_der| {
+        root_der.as_slice() == chain[chain.len() - 1].as_slice()
+            || parse_certificate(root_der)
+                .is_ok_and(|root| last.verify_signature(Some(root.public_key())).is_ok())
+    });
+    if !anchored {
+        bail!("certificate chain does not lead to a trusted Fulcio root");
+    }
+    Ok(certs.into_iter().next().expect("chain is not empty"))
+}
+
+/// The email or URI the certificate was issued to.
+fn certificate_identity(cert: &X509Certificate<'_>) -> Result<String> {
+    let names = cert
+        .subject_alternative_name()
+        .map_err(|err| anyhow!("invalid subject alternative name: {err}"))?
+        .ok_or_else(|| anyhow!("certificate names no identity"))?;
+    names
+        .value
+        .general_names
+        .iter()
+        .find_map(|name| match name {
+            GeneralName::RFC822Name(email) => Some(email.to_string()),
+            GeneralName::URI(uri) => Some(uri.to_string()),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("certificate names no email or URI identity"))
+}
+
+/// The OIDC issuer recorded by Fulcio.
+fn certificate_issuer(cert: &X509Certificate<'_>) -> Result<String> {
+    for extension in cert.extensions() {
+        match extension.oid.to_id_string().as_str() {
+            OIDC_ISSUER_OID => {
+                let (_, value) =
+                    x509_parser::der_parser::der::parse_der_utf8string(extension.value)
+                        .map_err(|err| anyhow!("invalid OIDC issuer extension: {err}"))?;
+                return value
+                    .as_str()
+                    .map(str::to_string)
+                    .map_err(|err| anyhow!("invalid OIDC issuer extension: {err}"));
+            }
+            OIDC_ISSUER_LEGACY_OID => {
+                return std::str::from_utf8(extension.value)
+                    .map(str::to_string)
+                    .context("invalid OIDC issuer extension");
+            }
+            _ => {}
+        }
+    }
+    bail!("certificate does not record an OIDC issuer")
+}
+
+fn ed25519_key(cert: &X509Certificate<'_>) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = cert
+        .public_key()
+        .subject_public_key
+        .data
+        .as_ref()
+        .try_into()
+        .map_err(|_| anyhow!("certificate key is not an Ed25519 key"))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|err| anyhow!("invalid Ed25519 key: {err}"))
+}
+
+fn parse_certificate(der: &[u8]) -> Result<X509Certificate<'_>> {
+    let (_, cert) =
+        X509Certificate::from_der(der).map_err(|err| anyhow!("invalid certificate: {err}"))?;
+    Ok(cert)
+}
+
+fn pem_to_der(pem: &str) -> Result<Vec<u8>> {
+    let (_, pem) =
+        parse_x509_pem(pem.as_bytes()).map_err(|err| anyhow!("invalid certificate PEM: {err}"))?;
+    if pem.label != "CERTIFICATE" {
+        bail!("unexpected PEM label {}; expected CERTIFICATE", pem.label);
+    }
+    Ok(pem.contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use ed25519_dalek::pkcs8::EncodePrivateKey;
+    use rcgen::{
+        BasicConstraints, CertificateParams, CustomExtension, ExtendedKeyUsagePurpose, IsCa,
+        Issuer, KeyPair, PKCS_ED25519, SanType,
+    };
+    use ring::rand::SystemRandom;
+    use ring::signature::{ECDSA_P256_SHA256_ASN1_SIGNING, EcdsaKeyPair, KeyPair as _};
+    use rustls_pki_types::PrivatePkcs8KeyDer;
+
+    const ISSUER: &str = "https://token.actions.githubusercontent.com";
+    const IDENTITY: &str = "ci@example.com";
+
+    /// DER prefix of a P-256 `SubjectPublicKeyInfo`, before the point.
+    const P256_SPKI_PREFIX: [u8; 26] = [
+        0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08,
+        0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00,
+    ];
+
+    /// A Fulcio-like root and a code-signing leaf certificate for `key`.
+    fn fulcio(key: &SigningKey) -> (String, String) {
+        fulcio_with(key, vec![ExtendedKeyUsagePurpose::CodeSigning])
+    }
+
+    fn fulcio_with(key: &SigningKey, usages: Vec<ExtendedKeyUsagePurpose>) -> (String, String) {
+        let root_key = KeyPair::generate_for(&PKCS_ED25519).unwrap();
+        let mut root_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        root_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let root = root_params.self_signed(&root_key).unwrap();
+
+        let pkcs8_der = PrivatePkcs8KeyDer::from(key.to_pkcs8_der().unwrap().as_bytes().to_vec());
+        let leaf_key = KeyPair::from_pkcs8_der_and_sign_algo(&pkcs8_der, &PKCS_ED25519).unwrap();
+        let mut leaf_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        leaf_params.subject_alt_names = vec![SanType::Rfc822Name(IDENTITY.try_into().unwrap())];
+        leaf_params.extended_key_usages = usages;
+        let mut issuer = vec![0x0c, ISSUER.len() as u8];
+        issuer.extend_from_slice(ISSUER.as_bytes());
+        leaf_params.custom_extensions = vec![CustomExtension::from_oid_content(
+            &[1, 3, 6, 1, 4, 1, 57264, 1, 8],
+            issuer,
+        )];
+        let leaf = leaf_params
+            .signed_by(&leaf_key, &Issuer::new(root_params, root_key))
+            .unwrap();
+        (root.pem(), leaf.pem())
+    }
+
+    /// A transparency log with a P-256 key, like Rekor's.
+    struct FakeRekor {
+        key: EcdsaKeyPair,
+        public_pem: String,
+        log_id: String,
+    }
+
+    impl FakeRekor {
+        fn new() -> Self {
+            let rng = SystemRandom::new();
+            let pkcs8 =
+                EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+            let key =
+                EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+                    .unwrap();
+            let mut der = P256_SPKI_PREFIX.to_vec();
+            der.extend_from_slice(key.public_key().as_ref());
+            Self {
+                public_pem: format!(
+                    "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+                    STANDARD.encode(&der)
+                ),
+                log_id: hex::encode(Sha256::digest(&der)),
+                key,
+            }
+        }
+
+        fn sign(&self, message: &[u8]) -> Vec<u8> {
+            self.key
+                .sign(&SystemRandom::new(), message)
+                .unwrap()
+                .as_ref()
+                .to_vec()
+        }
+
+        /// Logs a `rekord` entry as the second leaf of a three-leaf tree,
+        /// with its SET, inclusion proof and checkpoint.
+        fn record(
+            &self,
+            url: &str,
+            signature: &[u8],
+            leaf_pem: &str,
+            prehash: &[u8],
+        ) -> RekorEntry {
+            let body = json!({
+                "apiVersion": "0.0.1",
+                "kind": "rekord",
+                "spec": {
+                    "signature": {
+                        "format": "x509",
+                        "content": STANDARD.encode(signature),
+                        "publicKey": { "content": STANDARD.encode(leaf_pem) },
+                    },
+                    "data": { "hash": { "algorithm": "sha256", "value": hex::encode(Sha256::digest(prehash)) } },
+                },
+            })
+            .to_string();
+            let leaf = |data: &[u8]| -> [u8; 32] {
+                Sha256::new()
+                    .chain_update([0x00])
+                    .chain_update(data)
+                    .finalize()
+                    .into()
+            };
+            let node = |left: &[u8], right: &[u8]| -> [u8; 32] {
+                Sha256::new()
+                    .chain_update([0x01])
+                    .chain_update(left)
+                    .chain_update(right)
+                    .finalize()
+                    .into()
+            };
+            let (first, ours, last) = (leaf(b"first"), leaf(body.as_bytes()), leaf(b"last"));
+            let root = node(&node(&first, &ours), &last);
+
+            let note = format!("rekor.test - 1\n3\n{}\n", STANDARD.encode(root));
+            let mut note_signature = vec![0; 4];
+            note_signature.extend(self.sign(note.as_bytes()));
+            let checkpoint = format!(
+                "{note}\n\u{2014} rekor.test {}\n",
+                STANDARD.encode(note_signature)
+            );
+
+            let body = STANDARD.encode(&body);
+            let set = format!(
+                r#"{{"body":"{body}","integratedTime":1760000000,"logID":"{}","logIndex":42}}"#,
+                self.log_id
+            );
+            RekorEntry {
+                url: url.to_string(),
+                uuid: hex::encode(ours),
+                log_index: 42,
+                integrated_time: 1_760_000_000,
+                signed_entry_timestamp: Some(STANDARD.encode(self.sign(set.as_bytes()))),
+                body: Some(body),
+                log_id: Some(self.log_id.clone()),
+                inclusion_proof: Some(InclusionProof {
+                    log_index: 1,
+                    tree_size: 3,
+                    root_hash: hex::encode(root),
+                    hashes: vec![hex::encode(first), hex::encode(last)],
+                    checkpoint,
+                }),
+            }
+        }
+    }
+
+    /// Serves `entry` from disk the way Rekor's API returns it.
+    fn serve(dir: &std::path::Path, entry: &RekorEntry) {
+        let proof = entry.inclusion_proof.as_ref().unwrap();
+        let response = json!({ &entry.uuid: {
+            "body": entry.body,
+            "integratedTime": entry.integrated_time,
+            "logID": entry.log_id,
+            "logIndex": entry.log_index,
+            "verification": {
+                "signedEntryTimestamp": entry.signed_entry_timestamp,
+                "inclusionProof": {
+                    "logIndex": proof.log_index,
+                    "treeSize": proof.tree_size,
+                    "rootHash": proof.root_hash,
+                    "hashes": proof.hashes,
+                    "checkpoint": proof.checkpoint,
+                },
+            },
+        }});
+        let entries = dir.join("api/v1/log/entries");
+        fs::create_dir_all(&entries).unwrap();
+        fs::write(entries.join(&entry.uuid), response.to_string()).unwrap();
+    }
+
+    /// A keyless signature over `message`, its proof, and the log's key.
+    fn signed(
+        key: &SigningKey,
+        leaf_pem: &str,
+        root_pem: &str,
+        message: &[u8],
+        rekor_url: &str,
+    ) -> (PackSignature, SigstoreProof, FakeRekor) {
+        let prehash = Sha256::digest(message);
+        let raw_signature = key.sign(&prehash).to_bytes();
+        let log = FakeRekor::new();
+        let entry = log.record(rekor_url, &raw_signature, leaf_pem, &prehash);
+        let signature = PackSignature {
+            alg: PackSignature::ED25519.to_string(),
+            key_id: derive_key_id(key.verifying_key().as_bytes()),
+            created_at: OffsetDateTime::now_utc(),
+            digest: "sha256:00".to_string(),
+            sig: URL_SAFE_NO_PAD.encode(raw_signature),
+            constraints: None,
+            sigstore: None,
+        };
+        let proof = SigstoreProof {
+            certificate_chain: vec![leaf_pem.to_string(), root_pem.to_string()],
+            identity: IDENTITY.to_string(),
+            issuer: ISSUER.to_string(),
+            rekor: entry,
+        };
+        (signature, proof, log)
+    }
+
+    #[test]
+    fn token_claims_prefer_email() {
+        let token =
+            |claims: Value| format!("e30.{}.sig", URL_SAFE_NO_PAD.encode(claims.to_string()));
+        let claims =
+            TokenClaims::parse(&token(json!({"sub": "repo:acme/app", "email": IDENTITY}))).unwrap();
+        assert_eq!(claims.subject, IDENTITY);
+        let claims = TokenClaims::parse(&token(json!({"sub": "repo:acme/app"}))).unwrap();
+        assert_eq!(claims.subject, "repo:acme/app");
+        assert!(TokenClaims::parse("not-a-jwt").is_err());
+    }
+
+    #[test]
+    fn keyless_signature_verifies_against_the_log() {
+        let temp = tempfile::tempdir().unwrap();
+        let key = SigningKey::generate(&mut OsRng);
+        let (root_pem, leaf_pem) = fulcio(&key);
+        let message = b"canonical pack bytes";
+        let rekor_url = format!("file://{}", temp.path().display());
+        let (signature, proof, log) = signed(&key, &leaf_pem, &root_pem, message, &rekor_url);
+        serve(temp.path(), &proof.rekor);
+
+        let roots = [root_pem.as_str()];
+        let policy = KeylessPolicy {
+            trusted_roots_pem: &roots,
+            identity: Some(IDENTITY),
+            issuer: Some(ISSUER),
+            rekor_url: Some(&rekor_url),
+            rekor_public_key_pem: Some(&log.public_pem),
+        };
+
+        verify_signature(&signature, &proof, message, &policy).expect("verifies");
+        // Without the entry in the signature, it is looked up in the log.
+        let looked_up = SigstoreProof {
+            rekor: RekorEntry {
+                signed_entry_timestamp: None,
+                body: None,
+                log_id: None,
+                inclusion_proof: None,
+                ..proof.rekor.clone()
+            },
+            ..proof.clone()
+        };
+        verify_signature(&signature, &looked_up, message, &policy).expect("verifies");
+
+        let err = verify_signature(&signature, &proof, b"other bytes", &policy).unwrap_err();
+        assert!(
+            matches!(err, VerificationError::InvalidSignature { .. }),
+            "{err}"
+        );
+
+        let someone_else = KeylessPolicy {
+            identity: Some("mallory@example.com"),
+            ..policy
+        };
+        let err = verify_signature(&signature, &proof, message, &someone_else).unwrap_err();
+        assert!(err.to_string().contains("mallory@example.com"), "{err}");
+
+        let (other_root, _) = fulcio(&key);
+        let untrusted = [other_root.as_str()];
+        let err = verify_signature(
+            &signature,
+            &proof,
+            message,
+            &KeylessPolicy {
+                trusted_roots_pem: &untrusted,
+                ..policy
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("trusted Fulcio root"), "{err}");
+
+        let unlogged = SigstoreProof {
+            rekor: RekorEntry {
+                uuid: "def456".to_string(),
+                ..looked_up.rekor.clone()
+            },
+            ..proof.clone()
+        };
+        let err = verify_signature(&signature, &unlogged, message, &policy).unwrap_err();
+        assert!(err.to_string().contains("def456"), "{err}");
+
+        let other_log = FakeRekor::new();
+        let err = verify_signature(
+            &signature,
+            &proof,
+            message,
+            &KeylessPolicy {
+                rekor_public_key_pem: Some(&other_log.public_pem),
+                ..policy
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("another log"), "{err}");
+    }
+
+    #[test]
+    fn tampered_log_entries_are_rejected() {
+        let key = SigningKey::generate(&mut OsRng);
+        let (root_pem, leaf_pem) = fulcio(&key);
+        let message = b"canonical pack bytes";
+        let (signature, proof, log) = signed(&key, &leaf_pem, &root_pem, message, "file:///none");
+        let roots = [root_pem.as_str()];
+        let policy = KeylessPolicy {
+            trusted_roots_pem: &roots,
+            rekor_url: Some("file:///none"),
+            rekor_public_key_pem: Some(&log.public_pem),
+            ..KeylessPolicy::default()
+        };
+        verify_signature(&signature, &proof, message, &policy).expect("verifies");
+
+        // The SET no longer covers the integration time the entry claims.
+        let mut backdated = proof.clone();
+        backdated.rekor.integrated_time -= 3600;
+        let err = verify_signature(&signature, &backdated, message, &policy).unwrap_err();
+        assert!(err.to_string().contains("signed entry timestamp"), "{err}");
+
+        let mut forged = proof.clone();
+        forged.rekor.signed_entry_timestamp = Some(STANDARD.encode(vec![0x30; 70]));
+        let err = verify_signature(&signature, &forged, message, &policy).unwrap_err();
+        assert!(err.to_string().contains("signed entry timestamp"), "{err}");
+
+        let mut unproven = proof.clone();
+        let inclusion = unproven.rekor.inclusion_proof.as_mut().unwrap();
+        inclusion.hashes.swap(0, 1);
+        let err = verify_signature(&signature, &unproven, message, &policy).unwrap_err();
+        assert!(err.to_string().contains("inclusion proof"), "{err}");
+
+        let mut unsigned = proof.clone();
+        let inclusion = unsigned.rekor.inclusion_proof.as_mut().unwrap();
+        inclusion.checkpoint = inclusion
+            .checkpoint
+            .replace("rekor.test - 1\n3", "rekor.test - 1\n4");
+        let err = verify_signature(&signature, &unsigned, message, &policy).unwrap_err();
+        assert!(err.to_string().contains("checkpoint"), "{err}");
+    }
+
+    #[test]
+    fn certificates_must_be_for_code_signing_from_a_ca() {
+        let key = SigningKey::generate(&mut OsRng);
+        let (root_pem, leaf_pem) =
+            fulcio_with(&key, vec![ExtendedKeyUsagePurpose::EmailProtection]);
+        let chain = [
+            pem_to_der(&leaf_pem).unwrap(),
+            pem_to_der(&root_pem).unwrap(),
+        ];
+        let err = verify_chain(&chain, &[root_pem.as_str()]).unwrap_err();
+        assert!(err.to_string().contains("code signing"), "{err}");
+
+        let (root_pem, leaf_pem) = fulcio_with(&key, Vec::new());
+        let chain = [
+            pem_to_der(&leaf_pem).unwrap(),
+            pem_to_der(&root_pem).unwrap(),
+        ];
+        let err = verify_chain(&chain, &[root_pem.as_str()]).unwrap_err();
+        assert!(err.to_string().contains("code signing"), "{err}");
+
+        // A code-signing leaf issued by a certificate that is not a CA.
+        let issuer_key = KeyPair::generate_for(&PKCS_ED25519).unwrap();
+        let issuer_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        let issuer = issuer_params.self_signed(&issuer_key).unwrap();
+        let leaf_key = KeyPair::generate_for(&PKCS_ED25519).unwrap();
+        let mut leaf_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        leaf_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::CodeSigning];
+        let leaf = leaf_params
+            .signed_by(&leaf_key, &Issuer::new(issuer_params, issuer_key))
+            .unwrap();
+        let chain = [leaf.der().to_vec(), issuer.der().to_vec()];
+        let err = verify_chain(&chain, &[issuer.pem().as_str()]).unwrap_err();
+        assert!(err.to_string().contains("is not a CA"), "{err}");
+    }
+}
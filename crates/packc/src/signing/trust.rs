@@ -0,0 +1,218 @@
+#![forbid(unsafe_code)]
+
+//! Public keys trusted for verification, resolved by the key id a signature
+//! names.
+//!
+//! A trust store is either a directory of public key PEM files or a JSON
+//! keyring keyed by key id:
+//!
+//! ```json
+//! {
+//!   "format": "greentic-keyring-v1",
+//!   "keys": {
+//!     "3f1c…": { "name": "release-manager", "public_key_pem": "-----BEGIN PUBLIC KEY-----…" }
+//!   }
+//! }
+//! ```
+//!
+//! Key ids are always recomputed from the keys themselves, so a keyring entry
+//! filed under the wrong id is rejected rather than trusted for it.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use super::keys::PublicKey;
+
+/// Overrides where `packc verify` looks for a trust store.
+pub const TRUST_STORE_ENV: &str = "PACKC_TRUST_STORE";
+pub const KEYRING_FORMAT: &str = "greentic-keyring-v1";
+
+/// A key in a [`TrustStore`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedKey {
+    /// Label shown when the key verifies a signature, e.g. who holds it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub public_key_pem: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Keyring {
+    format: String,
+    keys: BTreeMap<String, TrustedKey>,
+}
+
+/// Public keys by key id, so verification can pick the key each signature
+/// names instead of being handed the exact key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustStore {
+    keys: BTreeMap<String, TrustedKey>,
+}
+
+impl TrustStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a directory of PEM files, or a JSON keyring file.
+    pub fn load(path: &Path) -> Result<Self> {
+        if path.is_dir() {
+            Self::from_dir(path)
+        } else {
+            Self::from_keyring(path)
+        }
+    }
+
+    /// Loads every `*.pem` file in `dir`, named after the file stem.
+    pub fn from_dir(dir: &Path) -> Result<Self> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+            .with_context(|| format!("failed to read trust store {}", dir.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<_>>()
+            .with_context(|| format!("failed to read trust store {}", dir.display()))?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "pem") && path.is_file());
+        paths.sort();
+
+        let mut store = Self::new();
+        for path in paths {
+            let pem = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned());
+            store
+                .insert(&pem, name)
+                .with_context(|| format!("{} is not a public key", path.display()))?;
+        }
+        Ok(store)
+    }
+
+    /// Loads a JSON keyring, checking that every key is filed under its own id.
+    pub fn from_keyring(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let keyring: Keyring = serde_json::from_slice(&bytes)
+            .with_context(|| format!("{} is not a valid keyring", path.display()))?;
+        if keyring.format != KEYRING_FORMAT {
+            bail!(
+                "{} has unsupported keyring format `{}` (expected `{KEYRING_FORMAT}`)",
+                path.display(),
+                keyring.format
+            );
+        }
+
+        let mut store = Self::new();
+        for (listed_id, key) in keyring.keys {
+            let key_id = store
+                .insert(&key.public_key_pem, key.name)
+                .with_context(|| format!("keyring entry {listed_id} is not a public key"))?;
+            if key_id != listed_id {
+                bail!(
+                    "keyring entry {listed_id} holds the key with id {key_id}; refusing to trust it"
+                );
+            }
+        }
+        Ok(store)
+    }
+
+    /// Adds a public key and returns its key id.
+    pub fn insert(&mut self, public_key_pem: &str, name: Option<String>) -> Result<String> {
+        let key_id = PublicKey::from_pem(public_key_pem)?.key_id();
+        self.keys.insert(
+            key_id.clone(),
+            TrustedKey {
+                name,
+                public_key_pem: public_key_pem.to_string(),
+            },
+        );
+        Ok(key_id)
+    }
+
+    pub fn get(&self, key_id: &str) -> Option<&TrustedKey> {
+        self.keys.get(key_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Keys by key id.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &TrustedKey)> {
+        self.keys.iter().map(|(id, key)| (id.as_str(), key))
+    }
+
+    /// Writes the store as a JSON keyring.
+    pub fn to_keyring_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&Keyring {
+            format: KEYRING_FORMAT.to_string(),
+            keys: self.keys.clone(),
+        })?)
+    }
+
+    /// The parsed key for `key_id`, if the store trusts one.
+    pub(crate) fn public_key(&self, key_id: &str) -> Option<PublicKey> {
+        let key = self.keys.get(key_id)?;
+        PublicKey::from_pem(&key.public_key_pem).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SigningKey;
+    use ed25519_dalek::pkcs8::EncodePublicKey;
+    use pkcs8::LineEnding;
+    use tempfile::tempdir;
+
+    fn public_pem(seed: u8) -> String {
+        SigningKey::from_bytes(&[seed; 32])
+            .verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .unwrap()
+    }
+
+    #[test]
+    fn directory_keys_are_named_after_their_files() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("release.pem"), public_pem(1)).unwrap();
+        fs::write(dir.path().join("README.md"), "not a key").unwrap();
+
+        let store = TrustStore::load(dir.path()).unwrap();
+        assert_eq!(store.len(), 1);
+        let (key_id, key) = store.iter().next().unwrap();
+        assert_eq!(key.name.as_deref(), Some("release"));
+        assert!(store.public_key(key_id).is_some());
+
+        fs::write(dir.path().join("broken.pem"), "garbage").unwrap();
+        let err = TrustStore::load(dir.path()).unwrap_err();
+        assert!(format!("{err:#}").contains("broken.pem"), "{err:#}");
+    }
+
+    #[test]
+    fn keyring_round_trips_and_rejects_misfiled_keys() {
+        let dir = tempdir().unwrap();
+        let mut store = TrustStore::new();
+        let key_id = store
+            .insert(&public_pem(2), Some("ci".to_string()))
+            .unwrap();
+        let keyring = dir.path().join("keyring.json");
+        fs::write(&keyring, store.to_keyring_json().unwrap()).unwrap();
+        assert_eq!(TrustStore::load(&keyring).unwrap(), store);
+
+        let misfiled = store
+            .to_keyring_json()
+            .unwrap()
+            .replace(&key_id, &"0".repeat(key_id.len()));
+        fs::write(&keyring, misfiled).unwrap();
+        let err = TrustStore::load(&keyring).unwrap_err();
+        assert!(err.to_string().contains("refusing to trust"), "{err}");
+    }
+}
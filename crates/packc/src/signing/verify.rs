@@ -1,18 +1,20 @@
 #![forbid(unsafe_code)]
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
 
 use base64::Engine as _;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
-use ed25519_dalek::Verifier as _;
-use ed25519_dalek::{Signature as Ed25519Signature, VerifyingKey, pkcs8::DecodePublicKey};
-use sha2::{Digest, Sha256};
 use thiserror::Error;
 use time::OffsetDateTime;
 
+use crate::digest::{self, DigestAlgorithm};
 use crate::manifest::{self, PackSignature};
 
-use super::{VerifyOptions, canonicalize_pack_dir};
+use super::constraints::KeyConstraints;
+use super::keys::PublicKey;
+use super::sigstore;
+use super::{CanonicalizedPack, VerifyOptions, canonicalize_pack_dir_with};
 
 /// Errors that may occur while verifying a pack signature.
 #[derive(Debug, Error)]
@@ -21,6 +23,8 @@ pub enum VerificationError {
     MissingSignature,
     #[error("computed digest {computed} does not match manifest digest {expected}")]
     DigestMismatch { expected: String, computed: String },
+    #[error("digest `{digest}` does not use a supported algorithm: {reason}")]
+    UnsupportedDigest { digest: String, reason: String },
     #[error("signature algorithm {algorithm} is not supported")]
     UnsupportedAlgorithm { algorithm: String },
     #[error("public key not provided for key id {key_id}")]
@@ -39,81 +43,256 @@ pub enum VerificationError {
     InvalidSignature { key_id: String },
     #[error("signature bytes were malformed")]
     SignatureMalformed,
+    #[error(
+        "a post-quantum ({}) signature is required but none was verified",
+        PackSignature::ML_DSA_65
+    )]
+    PostQuantumRequired,
+    #[error("keyless signature by key {key_id} did not verify: {reason}")]
+    Keyless { key_id: String, reason: String },
+    #[error("signature by key {key_id} is not valid here: {reason}")]
+    ConstraintViolated { key_id: String, reason: String },
+    #[error(
+        "{valid} of the required {required} signatures verified{}",
+        summarize_failures(.failures)
+    )]
+    ThresholdNotMet {
+        required: usize,
+        valid: usize,
+        failures: Vec<String>,
+    },
     #[error("manifest error: {0}")]
     Manifest(#[from] anyhow::Error),
 }
 
-/// Verifies a signed pack directory.
+/// Verifies a signed pack directory, returning its primary signature.
 pub fn verify_pack(
     pack_dir: &Path,
     opts: VerifyOptions<'_>,
 ) -> Result<PackSignature, VerificationError> {
-    let canonical = canonicalize_pack_dir(pack_dir).map_err(VerificationError::Manifest)?;
+    let mut verified = verify_signatures(pack_dir, opts)?;
+    Ok(verified.remove(0))
+}
 
-    let signature_opt = manifest::read_signature(pack_dir).map_err(VerificationError::Manifest)?;
+/// Verifies every signature on a pack directory and returns those that were
+/// checked, primary first.
+///
+/// All signatures must verify, unless [`VerifyOptions::threshold`] is set: then
+/// signatures that fail are left out, and at least that many distinct keys
+/// must have verified. ML-DSA signatures are skipped (with a warning) when
+/// packc is built without the `ml-dsa` feature, unless
+/// [`VerifyOptions::require_post_quantum`] is set.
+pub fn verify_signatures(
+    pack_dir: &Path,
+    opts: VerifyOptions<'_>,
+) -> Result<Vec<PackSignature>, VerificationError> {
+    let signatures = manifest::read_signatures(pack_dir).map_err(VerificationError::Manifest)?;
 
-    let Some(signature) = signature_opt else {
+    if signatures.is_empty() {
+        if opts.require_post_quantum {
+            return Err(VerificationError::PostQuantumRequired);
+        }
         if opts.allow_unsigned {
-            return Ok(PackSignature {
+            let canonical = canonicalize_pack_dir_with(pack_dir, DigestAlgorithm::default())
+                .map_err(VerificationError::Manifest)?;
+            return Ok(vec![PackSignature {
                 alg: "none".to_string(),
                 key_id: "unsigned".to_string(),
                 created_at: OffsetDateTime::UNIX_EPOCH,
-                digest: format!("sha256:{}", canonical.digest_hex),
+                digest: canonical.digest(),
                 sig: String::new(),
-            });
+                constraints: None,
+                sigstore: None,
+            }]);
         }
 
         return Err(VerificationError::MissingSignature);
+    }
+
+    let keys = opts
+        .public_key_pem
+        .into_iter()
+        .chain(opts.additional_public_keys_pem.iter().copied())
+        .map(PublicKey::from_pem)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let pack_id = if signatures.iter().any(|signature| {
+        signature
+            .constraints
+            .as_ref()
+            .is_some_and(|c| c.pack_id.is_some())
+    }) {
+        manifest::pack_id(pack_dir).map_err(VerificationError::Manifest)?
+    } else {
+        None
     };
+    let now = OffsetDateTime::now_utc();
+
+    let mut canonical_by_alg: BTreeMap<&'static str, CanonicalizedPack> = BTreeMap::new();
+    let mut verified = Vec::new();
+    let mut failures = Vec::new();
+    for signature in signatures {
+        let checked = check_signature(
+            pack_dir,
+            &signature,
+            &keys,
+            pack_id.as_deref(),
+            &opts,
+            now,
+            &mut canonical_by_alg,
+        );
+        match checked {
+            Ok(true) => verified.push(signature),
+            Ok(false) => {}
+            Err(err) if opts.threshold.is_some() => {
+                failures.push(format!("{}: {err}", signature.key_id));
+            }
+            Err(err) => return Err(err),
+        }
+    }
 
-    if !signature.alg.eq_ignore_ascii_case("ed25519") {
+    if let Some(threshold) = opts.threshold {
+        let required = threshold.max(1);
+        let valid = verified
+            .iter()
+            .map(|signature| signature.key_id.as_str())
+            .collect::<BTreeSet<_>>()
+            .len();
+        if valid < required {
+            return Err(VerificationError::ThresholdNotMet {
+                required,
+                valid,
+                failures,
+            });
+        }
+    }
+    if verified.is_empty() {
         return Err(VerificationError::UnsupportedAlgorithm {
-            algorithm: signature.alg.clone(),
+            algorithm: format!(
+                "{} (packc was built without the `ml-dsa` feature)",
+                PackSignature::ML_DSA_65
+            ),
         });
     }
+    if opts.require_post_quantum && !verified.iter().any(PackSignature::is_post_quantum) {
+        return Err(VerificationError::PostQuantumRequired);
+    }
 
-    let expected_digest = format!("sha256:{}", canonical.digest_hex);
-    if signature.digest != expected_digest {
-        return Err(VerificationError::DigestMismatch {
-            expected: signature.digest.clone(),
-            computed: expected_digest,
+    Ok(verified)
+}
+
+/// Checks one signature, returning `false` when it was skipped.
+fn check_signature(
+    pack_dir: &Path,
+    signature: &PackSignature,
+    keys: &[PublicKey],
+    pack_id: Option<&str>,
+    opts: &VerifyOptions<'_>,
+    now: OffsetDateTime,
+    canonical_by_alg: &mut BTreeMap<&'static str, CanonicalizedPack>,
+) -> Result<bool, VerificationError> {
+    let algorithm = signature.alg.to_ascii_lowercase();
+    if algorithm != PackSignature::ED25519 && algorithm != PackSignature::ML_DSA_65 {
+        return Err(VerificationError::UnsupportedAlgorithm {
+            algorithm: signature.alg.clone(),
         });
     }
+    if signature.is_post_quantum() && !cfg!(feature = "ml-dsa") && !opts.require_post_quantum {
+        tracing::warn!(
+            key_id = %signature.key_id,
+            "skipping {} signature: packc was built without the `ml-dsa` feature",
+            PackSignature::ML_DSA_65
+        );
+        return Ok(false);
+    }
 
-    let public_key_pem = opts
-        .public_key_pem
-        .ok_or_else(|| VerificationError::KeyNotFound {
-            key_id: signature.key_id.clone(),
+    // Recompute the digest with whichever supported algorithm the signer chose.
+    let (digest_alg, _) =
+        digest::parse(&signature.digest).map_err(|err| VerificationError::UnsupportedDigest {
+            digest: signature.digest.clone(),
+            reason: err.to_string(),
         })?;
-
-    let verifying_key = VerifyingKey::from_public_key_pem(public_key_pem)
-        .map_err(VerificationError::PublicKeySpki)?;
-    let derived_key_id = derive_key_id(verifying_key.as_bytes());
-    if derived_key_id != signature.key_id {
-        return Err(VerificationError::KeyIdMismatch {
-            expected: signature.key_id.clone(),
-            provided: derived_key_id,
+    if !canonical_by_alg.contains_key(digest_alg.as_str()) {
+        let canonical = canonicalize_pack_dir_with(pack_dir, digest_alg)
+            .map_err(VerificationError::Manifest)?;
+        canonical_by_alg.insert(digest_alg.as_str(), canonical);
+    }
+    let canonical = &canonical_by_alg[digest_alg.as_str()];
+    let computed_digest = canonical.digest();
+    if !signature.digest.eq_ignore_ascii_case(&computed_digest) {
+        return Err(VerificationError::DigestMismatch {
+            expected: signature.digest.clone(),
+            computed: computed_digest,
         });
     }
 
-    let raw_signature = URL_SAFE_NO_PAD.decode(signature.sig.as_bytes())?;
-    if raw_signature.len() != Ed25519Signature::BYTE_SIZE {
-        return Err(VerificationError::SignatureLength(raw_signature.len()));
+    let message = KeyConstraints::signed_message(signature.constraints.as_ref(), &canonical.bytes);
+    if let Some(proof) = &signature.sigstore {
+        sigstore::verify_signature(signature, proof, &message, &opts.keyless)?;
+    } else {
+        let trusted = resolve_trusted_key(keys, &algorithm, &signature.key_id, opts);
+        let key = match &trusted {
+            Some(key) => key,
+            None => select_key(keys, &algorithm, &signature.key_id)?,
+        };
+        let raw_signature = URL_SAFE_NO_PAD.decode(signature.sig.as_bytes())?;
+        key.verify(&message, &raw_signature, &signature.key_id)?;
     }
 
-    let ed_signature = Ed25519Signature::from_slice(&raw_signature)
-        .map_err(|_| VerificationError::SignatureMalformed)?;
+    if let Some(constraints) = &signature.constraints {
+        constraints
+            .check(pack_id, opts.environment, now)
+            .map_err(|reason| VerificationError::ConstraintViolated {
+                key_id: signature.key_id.clone(),
+                reason,
+            })?;
+    }
+    Ok(true)
+}
 
-    verifying_key
-        .verify(&canonical.bytes, &ed_signature)
-        .map_err(|_| VerificationError::InvalidSignature {
-            key_id: signature.key_id.clone(),
-        })?;
+fn summarize_failures(failures: &[String]) -> String {
+    if failures.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", failures.join("; "))
+    }
+}
 
-    Ok(signature)
+/// The trust store's key for `key_id`, unless a directly supplied key already
+/// matches it.
+fn resolve_trusted_key(
+    keys: &[PublicKey],
+    algorithm: &str,
+    key_id: &str,
+    opts: &VerifyOptions<'_>,
+) -> Option<PublicKey> {
+    if keys.iter().any(|key| key.key_id() == key_id) {
+        return None;
+    }
+    opts.trust_store?
+        .public_key(key_id)
+        .filter(|key| key.algorithm() == algorithm)
 }
 
-fn derive_key_id(public_key_bytes: &[u8]) -> String {
-    let digest = Sha256::digest(public_key_bytes);
-    hex::encode(&digest[..16])
+/// Picks the supplied key whose id matches the signature.
+fn select_key<'k>(
+    keys: &'k [PublicKey],
+    algorithm: &str,
+    key_id: &str,
+) -> Result<&'k PublicKey, VerificationError> {
+    let mut candidates = keys.iter().filter(|key| key.algorithm() == algorithm);
+    let Some(first) = candidates.next() else {
+        return Err(VerificationError::KeyNotFound {
+            key_id: key_id.to_string(),
+        });
+    };
+    if first.key_id() == key_id {
+        return Ok(first);
+    }
+    candidates
+        .find(|key| key.key_id() == key_id)
+        .ok_or_else(|| VerificationError::KeyIdMismatch {
+            expected: key_id.to_string(),
+            provided: first.key_id(),
+        })
 }
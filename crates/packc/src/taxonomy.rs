@@ -0,0 +1,178 @@
+use crate::manifest::PackSpec;
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+/// File looked up in the pack root when no explicit taxonomy is passed.
+pub const TAXONOMY_FILE: &str = "taxonomy.toml";
+
+const DEFAULT_TAXONOMY: &str = include_str!("../assets/taxonomy.default.toml");
+
+/// Allowed categories and tag normalisation rules applied to `tags:`/`categories:`.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct Taxonomy {
+    /// Allowed category identifiers. An empty list accepts any category.
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub tags: TagRules,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct TagRules {
+    #[serde(default = "default_max_length")]
+    pub max_length: usize,
+    #[serde(default = "default_max_tags")]
+    pub max_tags: usize,
+    /// Maps alternative spellings to their canonical tag (applied after lowercasing).
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl Default for TagRules {
+    fn default() -> Self {
+        Self {
+            max_length: default_max_length(),
+            max_tags: default_max_tags(),
+            aliases: BTreeMap::new(),
+        }
+    }
+}
+
+fn default_max_length() -> usize {
+    32
+}
+
+fn default_max_tags() -> usize {
+    16
+}
+
+/// A tag that was rewritten during normalisation; surfaced as a lint note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagRewrite {
+    pub original: String,
+    pub normalized: String,
+}
+
+impl Taxonomy {
+    /// Loads `explicit` when given, otherwise `<pack_dir>/taxonomy.toml`, falling
+    /// back to the built-in taxonomy.
+    pub fn load(pack_dir: &Path, explicit: Option<&Path>) -> Result<Self> {
+        let candidate = explicit
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| pack_dir.join(TAXONOMY_FILE));
+        if explicit.is_none() && !candidate.exists() {
+            return Self::builtin();
+        }
+        let source = fs::read_to_string(&candidate)
+            .with_context(|| format!("failed to read taxonomy {}", candidate.display()))?;
+        toml::from_str(&source)
+            .with_context(|| format!("{} is not a valid taxonomy file", candidate.display()))
+    }
+
+    pub fn builtin() -> Result<Self> {
+        toml::from_str(DEFAULT_TAXONOMY).context("built-in taxonomy is invalid")
+    }
+
+    /// Normalises a single tag: lowercase, whitespace/underscores to `-`, aliases applied.
+    pub fn normalize_tag(&self, raw: &str) -> Result<String> {
+        let mut tag = String::new();
+        for ch in raw.trim().chars() {
+            let ch = if ch.is_whitespace() || ch == '_' {
+                '-'
+            } else {
+                ch.to_ascii_lowercase()
+            };
+            if ch == '-' && (tag.is_empty() || tag.ends_with('-')) {
+                continue;
+            }
+            tag.push(ch);
+        }
+        let tag = tag.trim_end_matches('-').to_string();
+        let tag = self.tags.aliases.get(&tag).cloned().unwrap_or(tag);
+
+        if tag.is_empty() {
+            bail!("tags may not contain empty entries");
+        }
+        if !tag
+            .chars()
+            .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-')
+        {
+            bail!("tag `{raw}` may only contain letters, digits, and `-`");
+        }
+        if tag.len() > self.tags.max_length {
+            bail!(
+                "tag `{raw}` exceeds the maximum length of {} characters",
+                self.tags.max_length
+            );
+        }
+        Ok(tag)
+    }
+
+    /// Normalises `spec.tags` in place and checks `spec.categories` against the
+    /// allowed list, returning the tags that were rewritten.
+    pub fn apply(&self, spec: &mut PackSpec) -> Result<Vec<TagRewrite>> {
+        let mut rewrites = Vec::new();
+        let mut seen = BTreeSet::new();
+        let mut tags = Vec::new();
+        for raw in &spec.tags {
+            let normalized = self.normalize_tag(raw)?;
+            if normalized != *raw {
+                rewrites.push(TagRewrite {
+                    original: raw.clone(),
+                    normalized: normalized.clone(),
+                });
+            }
+            if seen.insert(normalized.clone()) {
+                tags.push(normalized);
+            }
+        }
+        if tags.len() > self.tags.max_tags {
+            bail!(
+                "pack declares {} tags; at most {} are allowed",
+                tags.len(),
+                self.tags.max_tags
+            );
+        }
+        spec.tags = tags;
+
+        let mut seen = BTreeSet::new();
+        for category in &spec.categories {
+            if !self.categories.is_empty() && !self.categories.contains(category) {
+                bail!(
+                    "unknown category `{}` (allowed: {})",
+                    category,
+                    self.categories.join(", ")
+                );
+            }
+            if !seen.insert(category.as_str()) {
+                bail!("duplicate category `{}`", category);
+            }
+        }
+        Ok(rewrites)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_normalized_with_aliases() {
+        let taxonomy = Taxonomy::builtin().unwrap();
+        assert_eq!(
+            taxonomy.normalize_tag(" Weather  Bot ").unwrap(),
+            "weather-bot"
+        );
+        assert_eq!(taxonomy.normalize_tag("ML").unwrap(), "machine-learning");
+        assert!(taxonomy.normalize_tag("emoji☀").is_err());
+    }
+
+    #[test]
+    fn builtin_taxonomy_lists_categories() {
+        let taxonomy = Taxonomy::builtin().unwrap();
+        assert!(taxonomy.categories.iter().any(|c| c == "messaging"));
+    }
+}
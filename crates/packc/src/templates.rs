@@ -1,6 +1,6 @@
+use crate::digest::DigestAlgorithm;
 use crate::manifest::PackSpec;
-use anyhow::{Context, Result};
-use sha2::{Digest, Sha256};
+use anyhow::{Context, Result, bail};
 use std::collections::BTreeSet;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -12,11 +12,26 @@ pub struct TemplateAsset {
     #[allow(dead_code)]
     pub absolute_path: PathBuf,
     pub bytes: Vec<u8>,
-    pub sha256: String,
+    /// `<alg>:<hex>` digest of `bytes`.
+    pub digest: String,
     pub size: u64,
 }
 
-pub fn collect_templates(pack_dir: &Path, spec: &PackSpec) -> Result<Vec<TemplateAsset>> {
+/// Whether [`collect_templates`] parses the templates it has an engine for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxCheck {
+    /// Fail on the first Handlebars (`.hbs`, `.handlebars`) or Liquid
+    /// (`.liquid`) template that does not parse.
+    Parse,
+    Skip,
+}
+
+pub fn collect_templates(
+    pack_dir: &Path,
+    spec: &PackSpec,
+    digest_alg: DigestAlgorithm,
+    syntax: SyntaxCheck,
+) -> Result<Vec<TemplateAsset>> {
     let mut assets = Vec::new();
     let mut seen_paths = BTreeSet::new();
 
@@ -50,20 +65,22 @@ pub fn collect_templates(pack_dir: &Path, spec: &PackSpec) -> Result<Vec<Templat
                 .join("/");
 
             if !seen_paths.insert(logical_path.clone()) {
-                anyhow::bail!("duplicate template asset detected: {}", logical_path);
+                bail!("duplicate template asset detected: {}", logical_path);
             }
 
             let bytes = fs::read(&path)
                 .with_context(|| format!("failed to read template {}", path.display()))?;
-            let digest = Sha256::digest(&bytes);
-            let sha256 = hex::encode(digest);
+            if syntax == SyntaxCheck::Parse {
+                check_syntax(&logical_path, &bytes)?;
+            }
+            let digest = digest_alg.digest(&bytes);
             let size = bytes.len() as u64;
 
             assets.push(TemplateAsset {
                 logical_path,
                 absolute_path: path,
                 bytes,
-                sha256,
+                digest,
                 size,
             });
         }
@@ -72,3 +89,32 @@ pub fn collect_templates(pack_dir: &Path, spec: &PackSpec) -> Result<Vec<Templat
     assets.sort_by(|a, b| a.logical_path.cmp(&b.logical_path));
     Ok(assets)
 }
+
+/// Parses `bytes` with the engine its extension names; other files pass.
+/// Liquid templates are parsed with the standard tags and filters only.
+pub fn check_syntax(logical_path: &str, bytes: &[u8]) -> Result<()> {
+    let extension = Path::new(logical_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default();
+    if !matches!(extension, "hbs" | "handlebars" | "liquid") {
+        return Ok(());
+    }
+    let source = std::str::from_utf8(bytes)
+        .with_context(|| format!("template {logical_path} is not valid UTF-8"))?;
+    let error = if extension == "liquid" {
+        liquid::ParserBuilder::with_stdlib()
+            .build()?
+            .parse(source)
+            .err()
+            .map(|err| err.to_string())
+    } else {
+        handlebars::Template::compile(source)
+            .err()
+            .map(|err| err.to_string())
+    };
+    if let Some(error) = error {
+        bail!("template {logical_path} has a syntax error: {error}");
+    }
+    Ok(())
+}
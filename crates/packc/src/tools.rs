@@ -0,0 +1,470 @@
+//! Native helper tools packc downloads and manages.
+//!
+//! Some build steps shell out to native helpers such as `wasm-tools`. Rather
+//! than expecting them on `PATH`, a project pins them in a toolchain manifest
+//! (`toolchain.toml`): one archive per platform, each with its SHA-256 digest.
+//! `packc tools install` downloads the archive for the current platform,
+//! checks the digest, unpacks the binary into the tools directory and records
+//! it in that directory's `tools.json` index, which [`locate`] consults when a
+//! build step needs the helper.
+//!
+//! ```toml
+//! [tools.wasm-tools]
+//! version = "1.240.0"
+//!
+//! [tools.wasm-tools.platforms.x86_64-linux]
+//! url = "https://github.com/bytecodealliance/wasm-tools/releases/download/v1.240.0/wasm-tools-1.240.0-x86_64-linux.tar.gz"
+//! sha256 = "…"
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use greentic_pack::atomic::{self, Durability};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use walkdir::WalkDir;
+
+use crate::cache::{self, CacheKind};
+use crate::http::Request;
+
+/// Toolchain manifest looked up in the working directory.
+pub const TOOLCHAIN_FILE: &str = "toolchain.toml";
+/// Overrides the directory tools are installed into.
+pub const TOOLS_DIR_ENV: &str = "PACKC_TOOLS_DIR";
+pub(crate) const INDEX_FILE: &str = "tools.json";
+const INDEX_FORMAT: &str = "greentic-tools-v1";
+
+/// Helpers a project pins, by tool name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Toolchain {
+    #[serde(default)]
+    pub tools: BTreeMap<String, ToolSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolSpec {
+    pub version: String,
+    /// Archives by platform, e.g. `x86_64-linux` or `aarch64-macos`.
+    pub platforms: BTreeMap<String, ToolArchive>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolArchive {
+    /// `https://`, `file://` or a path relative to the toolchain manifest.
+    /// `.zip` and `.tar.gz` archives are unpacked; anything else is the
+    /// binary itself.
+    pub url: String,
+    /// Hex SHA-256 of the downloaded file.
+    pub sha256: String,
+    /// Path of the binary inside the archive. By default the archive is
+    /// searched for a file named after the tool (`.exe` on Windows).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary: Option<String>,
+}
+
+/// The `tools.json` index of an install directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolIndex {
+    pub format: String,
+    pub tools: BTreeMap<String, InstalledTool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledTool {
+    pub version: String,
+    pub platform: String,
+    pub sha256: String,
+    /// Binary path, relative to the install directory.
+    pub path: String,
+    pub installed_at_utc: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallStatus {
+    Installed,
+    UpToDate,
+}
+
+impl InstallStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InstallStatus::Installed => "installed",
+            InstallStatus::UpToDate => "up_to_date",
+        }
+    }
+}
+
+impl Toolchain {
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let toolchain: Self = toml::from_str(&source)
+            .with_context(|| format!("{} is not a valid toolchain manifest", path.display()))?;
+        toolchain
+            .validate()
+            .with_context(|| format!("invalid toolchain manifest {}", path.display()))?;
+        Ok(toolchain)
+    }
+
+    fn validate(&self) -> Result<()> {
+        for (name, spec) in &self.tools {
+            if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+                bail!("`{name}` is not a valid tool name");
+            }
+            if spec.version.trim().is_empty() {
+                bail!("tools.{name}.version must not be empty");
+            }
+            if spec.platforms.is_empty() {
+                bail!("tools.{name} must list at least one platform");
+            }
+            for (platform, archive) in &spec.platforms {
+                if archive.url.trim().is_empty() {
+                    bail!("tools.{name}.platforms.{platform}.url must not be empty");
+                }
+                if archive.sha256.len() != 64
+                    || !archive.sha256.bytes().all(|b| b.is_ascii_hexdigit())
+                {
+                    bail!("tools.{name}.platforms.{platform}.sha256 must be a hex SHA-256 digest");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ToolIndex {
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(Self {
+                format: INDEX_FORMAT.to_string(),
+                tools: BTreeMap::new(),
+            });
+        }
+        let bytes =
+            fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let index: Self = match serde_json::from_slice(&bytes) {
+            Ok(index) => index,
+            Err(err) => {
+                // A damaged index only costs reinstalls; it is rewritten on
+                // the next install.
+                tracing::warn!(index = %path.display(), %err, "ignoring corrupt tools index");
+                return Ok(Self {
+                    format: INDEX_FORMAT.to_string(),
+                    tools: BTreeMap::new(),
+                });
+            }
+        };
+        if index.format != INDEX_FORMAT {
+            bail!(
+                "{} has unsupported format `{}`; expected `{INDEX_FORMAT}`",
+                path.display(),
+                index.format
+            );
+        }
+        Ok(index)
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self).context("failed to serialise tools index")?;
+        atomic::write(&dir.join(INDEX_FILE), &json, Durability::default())
+    }
+}
+
+/// `<arch>-<os>` of the running packc, e.g. `x86_64-linux`.
+pub fn current_platform() -> String {
+    format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+}
+
+/// Resolves `$PACKC_TOOLS_DIR`, `$XDG_CACHE_HOME/greentic/tools`, or
+/// `~/.cache/greentic/tools`.
+pub fn tools_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(TOOLS_DIR_ENV) {
+        return Some(PathBuf::from(dir));
+    }
+    Some(cache_root()?.join("tools"))
+}
+
+/// `$XDG_CACHE_HOME/greentic`, or `~/.cache/greentic`.
+pub(crate) fn cache_root() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("greentic"))
+}
+
+/// The installed binary of `name` for this platform, falling back to `name`
+/// itself so the command is looked up on `PATH`.
+pub fn locate(name: &str) -> PathBuf {
+    tools_dir()
+        .and_then(|dir| {
+            let index = ToolIndex::load(&dir).ok()?;
+            let tool = index.tools.get(name)?;
+            let path = dir.join(&tool.path);
+            (tool.platform == current_platform() && path.is_file()).then_some(path)
+        })
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// Installs `name` for `platform` into `dir`, unless the index already holds
+/// the same archive. `base` resolves relative archive paths.
+pub fn install(
+    dir: &Path,
+    base: &Path,
+    name: &str,
+    spec: &ToolSpec,
+    platform: &str,
+    force: bool,
+) -> Result<(InstalledTool, InstallStatus)> {
+    let Some(archive) = spec.platforms.get(platform) else {
+        let known: Vec<&str> = spec.platforms.keys().map(String::as_str).collect();
+        bail!(
+            "{name} {} has no archive for {platform} (available: {})",
+            spec.version,
+            known.join(", ")
+        );
+    };
+    let expected = archive.sha256.to_ascii_lowercase();
+
+    let mut index = ToolIndex::load(dir)?;
+    if let Some(existing) = index.tools.get(name)
+        && !force
+        && existing.version == spec.version
+        && existing.platform == platform
+        && existing.sha256 == expected
+        && dir.join(&existing.path).is_file()
+    {
+        return Ok((existing.clone(), InstallStatus::UpToDate));
+    }
+
+    let bytes = fetch(&archive.url, base)?;
+    let actual = hex::encode(Sha256::digest(&bytes));
+    if actual != expected {
+        bail!(
+            "digest mismatch for {}: expected sha256 {expected}, got {actual}",
+            archive.url
+        );
+    }
+
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let staging = tempfile::tempdir_in(dir).context("failed to create staging directory")?;
+    let unpacked = staging.path().join("unpacked");
+    fs::create_dir_all(&unpacked)?;
+    let file_name = if platform.ends_with("windows") {
+        format!("{name}.exe")
+    } else {
+        name.to_string()
+    };
+    unpack(&archive.url, &bytes, &unpacked, &file_name)?;
+    let binary = match &archive.binary {
+        Some(relative) => {
+            let path = unpacked.join(relative);
+            if !path.is_file() {
+                bail!("{} does not contain {relative}", archive.url);
+            }
+            path
+        }
+        None => WalkDir::new(&unpacked)
+            .into_iter()
+            .filter_map(Result::ok)
+            .find(|entry| entry.file_type().is_file() && entry.file_name() == file_name.as_str())
+            .map(|entry| entry.into_path())
+            .with_context(|| format!("{} does not contain {file_name}", archive.url))?,
+    };
+
+    let relative = format!("{name}/{}/{platform}/{file_name}", spec.version);
+    let target = dir.join(&relative);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    atomic::copy(&binary, &target, Durability::default())?;
+    make_executable(&target)?;
+
+    let installed = InstalledTool {
+        version: spec.version.clone(),
+        platform: platform.to_string(),
+        sha256: expected,
+        path: relative,
+        installed_at_utc: OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string()),
+    };
+    index.tools.insert(name.to_string(), installed.clone());
+    index.save(dir)?;
+    // An evicted tool stays in the index; `locate` and `install` check the
+    // binary is still there.
+    cache::enforce_configured(CacheKind::Tools, dir)?;
+    Ok((installed, InstallStatus::Installed))
+}
+
+fn fetch(url: &str, base: &Path) -> Result<Vec<u8>> {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        return Request::get(url).retries(3).send().context(
+            "failed to download the tool; point the toolchain at a file:// mirror if curl cannot reach it",
+        );
+    }
+    let path = match url.strip_prefix("file://") {
+        Some(path) => PathBuf::from(path),
+        None => base.join(url),
+    };
+    fs::read(&path).with_context(|| format!("failed to read {}", path.display()))
+}
+
+fn unpack(url: &str, bytes: &[u8], into: &Path, file_name: &str) -> Result<()> {
+    if url.ends_with(".zip") {
+        zip::ZipArchive::new(Cursor::new(bytes))
+            .and_then(|mut archive| archive.extract(into))
+            .with_context(|| format!("failed to unpack {url}"))
+    } else if url.ends_with(".tar.gz") || url.ends_with(".tgz") {
+        tar::Archive::new(flate2::read::GzDecoder::new(bytes))
+            .unpack(into)
+            .with_context(|| format!("failed to unpack {url}"))
+    } else {
+        fs::write(into.join(file_name), bytes)
+            .with_context(|| format!("failed to write {file_name}"))
+    }
+}
+
+fn make_executable(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("failed to mark {} executable", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+
+    #[test]
+    fn installs_pinned_archives_once_and_rejects_bad_digests() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("wasm-tools-1.0.0/wasm-tools", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"#!/bin/sh\n").unwrap();
+        let bytes = zip.finish().unwrap().into_inner();
+        fs::write(temp.path().join("wasm-tools.zip"), &bytes).unwrap();
+
+        let mut spec = ToolSpec {
+            version: "1.0.0".into(),
+            platforms: BTreeMap::from([(
+                "x86_64-linux".to_string(),
+                ToolArchive {
+                    url: "wasm-tools.zip".into(),
+                    sha256: hex::encode(Sha256::digest(&bytes)),
+                    binary: None,
+                },
+            )]),
+        };
+        let dir = temp.path().join("tools");
+        let (tool, status) = install(
+            &dir,
+            temp.path(),
+            "wasm-tools",
+            &spec,
+            "x86_64-linux",
+            false,
+        )
+        .unwrap();
+        assert_eq!(status, InstallStatus::Installed);
+        assert_eq!(tool.path, "wasm-tools/1.0.0/x86_64-linux/wasm-tools");
+        assert_eq!(
+            fs::read(dir.join(&tool.path)).unwrap(),
+            b"#!/bin/sh\n".to_vec()
+        );
+        assert_eq!(ToolIndex::load(&dir).unwrap().tools["wasm-tools"], tool);
+
+        let (_, status) = install(
+            &dir,
+            temp.path(),
+            "wasm-tools",
+            &spec,
+            "x86_64-linux",
+            false,
+        )
+        .unwrap();
+        assert_eq!(status, InstallStatus::UpToDate);
+        assert!(
+            install(
+                &dir,
+                temp.path(),
+                "wasm-tools",
+                &spec,
+                "aarch64-macos",
+                false
+            )
+            .is_err()
+        );
+
+        spec.platforms.get_mut("x86_64-linux").unwrap().sha256 = "0".repeat(64);
+        let err = install(&dir, temp.path(), "wasm-tools", &spec, "x86_64-linux", true)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("digest mismatch"), "{err}");
+    }
+
+    #[test]
+    fn unpacks_tar_gz_archives_without_a_system_tar() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o755);
+        header.set_size(10);
+        tar.append_data(
+            &mut header,
+            "wasm-tools-1.0.0/wasm-tools",
+            &b"#!/bin/sh\n"[..],
+        )
+        .unwrap();
+        let bytes = tar.into_inner().unwrap().finish().unwrap();
+        fs::write(temp.path().join("wasm-tools.tar.gz"), &bytes).unwrap();
+
+        let spec = ToolSpec {
+            version: "1.0.0".into(),
+            platforms: BTreeMap::from([(
+                "x86_64-linux".to_string(),
+                ToolArchive {
+                    url: "wasm-tools.tar.gz".into(),
+                    sha256: hex::encode(Sha256::digest(&bytes)),
+                    binary: None,
+                },
+            )]),
+        };
+        let dir = temp.path().join("tools");
+        let (tool, _) = install(
+            &dir,
+            temp.path(),
+            "wasm-tools",
+            &spec,
+            "x86_64-linux",
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read(dir.join(&tool.path)).unwrap(),
+            b"#!/bin/sh\n".to_vec()
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Opt-in anonymous usage reporting.
+//!
+//! When the user has enabled it with `packc telemetry enable`, each command
+//! emits a single `packc::usage` tracing event carrying the subcommand name,
+//! duration, outcome, and packc version. No paths, pack ids, or arguments are
+//! recorded. The event flows through the greentic-telemetry OTLP pipeline
+//! installed by the `packc` binary.
+
+use anyhow::{Context, Result};
+use greentic_pack::atomic::{self, Durability};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::cache::CacheConfig;
+
+/// Overrides the directory holding `packc.toml` (mainly for tests and CI).
+pub const CONFIG_DIR_ENV: &str = "PACKC_CONFIG_DIR";
+/// Disables usage reporting regardless of the stored consent.
+pub const DISABLE_ENV: &str = "PACKC_NO_USAGE";
+const CONFIG_FILE: &str = "packc.toml";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UserConfig {
+    #[serde(default)]
+    pub telemetry: TelemetryConsent,
+    #[serde(default, skip_serializing_if = "CacheConfig::is_empty")]
+    pub cache: CacheConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TelemetryConsent {
+    /// `None` means the user has never been asked; reporting stays off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage_reporting: Option<bool>,
+}
+
+/// Resolves `$PACKC_CONFIG_DIR`, `$XDG_CONFIG_HOME/greentic`, or `~/.config/greentic`.
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(CONFIG_DIR_ENV) {
+        return Some(PathBuf::from(dir).join(CONFIG_FILE));
+    }
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("greentic").join(CONFIG_FILE))
+}
+
+pub fn load_config() -> Result<UserConfig> {
+    let Some(path) = config_path() else {
+        return Ok(UserConfig::default());
+    };
+    if !path.exists() {
+        return Ok(UserConfig::default());
+    }
+    let source =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    toml::from_str(&source).with_context(|| format!("{} is not valid TOML", path.display()))
+}
+
+pub fn store_consent(enabled: bool) -> Result<PathBuf> {
+    let path = config_path()
+        .context("cannot determine the user config directory; set PACKC_CONFIG_DIR")?;
+    let mut config = load_config()?;
+    config.telemetry.usage_reporting = Some(enabled);
+    let serialized = toml::to_string_pretty(&config)
+        .map_err(|err| anyhow::anyhow!("failed to serialise config: {err}"))?;
+    atomic::write(&path, serialized.as_bytes(), Durability::Buffered)?;
+    Ok(path)
+}
+
+/// Whether usage events should be emitted for this process.
+pub fn reporting_enabled() -> bool {
+    if disabled_by_env() {
+        return false;
+    }
+    load_config()
+        .ok()
+        .and_then(|config| config.telemetry.usage_reporting)
+        .unwrap_or(false)
+}
+
+pub fn disabled_by_env() -> bool {
+    [DISABLE_ENV, "DO_NOT_TRACK"].iter().any(|name| {
+        std::env::var(name)
+            .map(|value| !value.is_empty() && value != "0" && value != "false")
+            .unwrap_or(false)
+    })
+}
+
+/// Emits the anonymous usage event for one command invocation.
+pub fn record(command: &'static str, duration: Duration, success: bool) {
+    tracing::info!(
+        target: "packc::usage",
+        command,
+        duration_ms = duration.as_millis() as u64,
+        success,
+        packc_version = env!("CARGO_PKG_VERSION"),
+        "packc usage"
+    );
+}
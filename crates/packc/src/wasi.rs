@@ -0,0 +1,165 @@
+//! Checks that a compiled pack component stays free of WASI host access.
+//!
+//! Some hosts run pack components without WASI filesystem or clock access,
+//! so the component must be purely data-backed. `pack_component` itself uses
+//! no std APIs, but any dependency built with std links WASI imports on
+//! `wasm32-wasip2`. `packc build --assert-no-wasi` compiles the component with
+//! its `no-wasi` feature and then rejects the result if such imports remain.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use wasmparser::{Parser, Payload};
+
+/// Component-model interfaces a data-backed component must not import.
+const FORBIDDEN_INTERFACES: &[&str] = &["wasi:filesystem/", "wasi:clocks/"];
+/// Core-module WASI namespaces and the function prefixes that reach the
+/// filesystem or a clock.
+const FORBIDDEN_CORE_MODULES: &[&str] = &["wasi_snapshot_preview1", "wasi_unstable"];
+const FORBIDDEN_CORE_PREFIXES: &[&str] = &["fd_", "path_", "clock_"];
+
+/// Every import of `wasm`, including those of nested modules and components.
+/// Component imports are listed by name, core imports as `<module>#<name>`.
+pub fn imports(wasm: &[u8]) -> Result<Vec<String>> {
+    let mut imports = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload.context("invalid wasm binary")? {
+            Payload::ComponentImportSection(reader) => {
+                for import in reader {
+                    imports.push(
+                        import
+                            .context("invalid component import")?
+                            .name
+                            .0
+                            .to_string(),
+                    );
+                }
+            }
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import.context("invalid core import")?;
+                    imports.push(format!("{}#{}", import.module, import.name));
+                }
+            }
+            _ => {}
+        }
+    }
+    imports.sort();
+    imports.dedup();
+    Ok(imports)
+}
+
+/// The imports that give access to the filesystem or a clock.
+pub fn forbidden(imports: &[String]) -> Vec<&str> {
+    imports
+        .iter()
+        .map(String::as_str)
+        .filter(|import| match import.split_once('#') {
+            Some((module, name)) => {
+                FORBIDDEN_CORE_MODULES.contains(&module)
+                    && FORBIDDEN_CORE_PREFIXES
+                        .iter()
+                        .any(|prefix| name.starts_with(prefix))
+            }
+            None => FORBIDDEN_INTERFACES
+                .iter()
+                .any(|interface| import.starts_with(interface)),
+        })
+        .collect()
+}
+
+/// Fails when the component at `path` imports filesystem or clock interfaces.
+pub fn assert_no_wasi(path: &Path) -> Result<()> {
+    let wasm = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let imports =
+        imports(&wasm).with_context(|| format!("failed to inspect {}", path.display()))?;
+    let forbidden = forbidden(&imports);
+    if !forbidden.is_empty() {
+        bail!(
+            "{} imports WASI filesystem or clock interfaces: {}; a dependency is probably built with std",
+            path.display(),
+            forbidden.join(", ")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(id: u8, contents: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![id, contents.len() as u8];
+        bytes.extend_from_slice(contents);
+        bytes
+    }
+
+    fn name(value: &str) -> Vec<u8> {
+        let mut bytes = vec![value.len() as u8];
+        bytes.extend_from_slice(value.as_bytes());
+        bytes
+    }
+
+    /// A component importing instances named `names`.
+    fn component(names: &[&str]) -> Vec<u8> {
+        let mut imports = vec![names.len() as u8];
+        for import in names {
+            imports.push(0x00);
+            imports.extend(name(import));
+            // An instance of type 0.
+            imports.extend([0x05, 0x00]);
+        }
+        let mut wasm = b"\0asm\x0d\x00\x01\x00".to_vec();
+        wasm.extend(section(10, &imports));
+        wasm
+    }
+
+    /// A core module importing `module#name` functions of type 0.
+    fn core_module(functions: &[(&str, &str)]) -> Vec<u8> {
+        let mut imports = vec![functions.len() as u8];
+        for (module, function) in functions {
+            imports.extend(name(module));
+            imports.extend(name(function));
+            imports.extend([0x00, 0x00]);
+        }
+        let mut wasm = b"\0asm\x01\x00\x00\x00".to_vec();
+        wasm.extend(section(1, &[0x01, 0x60, 0x00, 0x00]));
+        wasm.extend(section(2, &imports));
+        wasm
+    }
+
+    #[test]
+    fn flags_filesystem_and_clock_interfaces() {
+        let imports = imports_of(&component(&[
+            "greentic:interfaces/secrets@0.4.0",
+            "wasi:clocks/wall-clock@0.2.0",
+            "wasi:filesystem/types@0.2.0",
+        ]));
+        assert_eq!(imports.len(), 3);
+        assert_eq!(
+            forbidden(&imports),
+            [
+                "wasi:clocks/wall-clock@0.2.0",
+                "wasi:filesystem/types@0.2.0"
+            ]
+        );
+
+        let clean = imports_of(&component(&["greentic:interfaces/secrets@0.4.0"]));
+        assert!(forbidden(&clean).is_empty());
+    }
+
+    #[test]
+    fn flags_preview1_filesystem_calls_in_core_modules() {
+        let imports = imports_of(&core_module(&[
+            ("wasi_snapshot_preview1", "fd_write"),
+            ("wasi_snapshot_preview1", "random_get"),
+            ("env", "fd_helper"),
+        ]));
+        assert_eq!(forbidden(&imports), ["wasi_snapshot_preview1#fd_write"]);
+    }
+
+    fn imports_of(wasm: &[u8]) -> Vec<String> {
+        imports(wasm).unwrap()
+    }
+}
@@ -0,0 +1,100 @@
+//! Packs kept together under one root, such as a monorepo of packs.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use anyhow::{Result, bail};
+use walkdir::WalkDir;
+
+/// Directories never searched for packs.
+const SKIPPED_DIRS: &[&str] = &["target", "dist", "node_modules"];
+
+/// Finds every pack directory (one containing `pack.yaml`) under `root`,
+/// sorted by path. Hidden and build output directories are skipped, and the
+/// search does not descend into a pack.
+pub fn discover(root: &Path) -> Result<Vec<PathBuf>> {
+    if !root.is_dir() {
+        bail!("workspace {} is not a directory", root.display());
+    }
+    let mut packs = Vec::new();
+    let mut walker = WalkDir::new(root).sort_by_file_name().into_iter();
+    while let Some(entry) = walker.next() {
+        let entry = entry?;
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy();
+        if entry.depth() > 0 && (name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref())) {
+            walker.skip_current_dir();
+            continue;
+        }
+        if entry.path().join("pack.yaml").is_file() {
+            packs.push(entry.into_path());
+            walker.skip_current_dir();
+        }
+    }
+    Ok(packs)
+}
+
+/// Applies `f` to every item on up to `jobs` threads, keeping the input order.
+pub fn map_parallel<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<(usize, R)>> = Mutex::new(Vec::with_capacity(items.len()));
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+                    let result = f(item);
+                    results
+                        .lock()
+                        .expect("result lock poisoned")
+                        .push((index, result));
+                }
+            });
+        }
+    });
+    let mut results = results.into_inner().expect("result lock poisoned");
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Threads to use when the caller does not say.
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, |jobs| jobs.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn discovers_packs_without_descending_into_them() {
+        let temp = tempfile::tempdir().unwrap();
+        for dir in ["a", "group/b", "a/nested", "target/c", ".git/d"] {
+            fs::create_dir_all(temp.path().join(dir)).unwrap();
+            fs::write(temp.path().join(dir).join("pack.yaml"), "id: x\n").unwrap();
+        }
+        let found: Vec<PathBuf> = discover(temp.path())
+            .unwrap()
+            .into_iter()
+            .map(|path| path.strip_prefix(temp.path()).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(found, vec![PathBuf::from("a"), PathBuf::from("group/b")]);
+
+        let squares = map_parallel(&[1, 2, 3, 4, 5], 3, |n| n * n);
+        assert_eq!(squares, vec![1, 4, 9, 16, 25]);
+    }
+}
@@ -1,3 +1,5 @@
+mod common;
+
 use assert_cmd::prelude::*;
 use serde_json::Value;
 use std::fs;
@@ -5,18 +7,11 @@ use std::net::ToSocketAddrs;
 use std::path::PathBuf;
 use std::process::Command;
 use tempfile::tempdir;
-use walkdir::WalkDir;
-
-fn workspace_root() -> PathBuf {
-    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("..")
-        .join("..")
-}
 
 #[test]
 fn dry_run_weather_demo_succeeds() {
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("packc"));
-    cmd.current_dir(workspace_root());
+    cmd.current_dir(common::workspace_root());
     cmd.args([
         "build",
         "--in",
@@ -31,18 +26,58 @@ fn dry_run_weather_demo_succeeds() {
 #[test]
 fn dry_run_rejects_missing_manifest() {
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("packc"));
-    cmd.current_dir(workspace_root());
+    cmd.current_dir(common::workspace_root());
     cmd.args(["build", "--in", "examples", "--dry-run"]);
     cmd.assert().failure();
 }
 
+#[test]
+fn build_rejects_templates_that_do_not_parse() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let dry_run = |extra: &[&str]| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("packc"));
+        cmd.args(["build", "--in", pack_dir.to_str().unwrap(), "--dry-run"])
+            .args(extra)
+            .args(["--log", "warn"]);
+        cmd.assert()
+    };
+
+    fs::write(
+        pack_dir.join("templates/forecast.liquid"),
+        "{% for day in days %}{{ day.high }}\n",
+    )
+    .unwrap();
+    let assert = dry_run(&[]).failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("templates/forecast.liquid has a syntax error"),
+        "{stderr}"
+    );
+    fs::remove_file(pack_dir.join("templates/forecast.liquid")).unwrap();
+
+    fs::write(
+        pack_dir.join("templates/weather_now.hbs"),
+        "{{#if sunny}}Sunny in {{city}}\n",
+    )
+    .unwrap();
+    let assert = dry_run(&[]).failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("templates/weather_now.hbs has a syntax error"),
+        "{stderr}"
+    );
+    dry_run(&["--no-validate-templates"]).success();
+}
+
 #[test]
 fn scaffold_minimal_pack_builds() {
     let temp = tempdir().expect("temp dir");
     let pack_dir = temp.path().join("demo-pack");
 
     let mut scaffold = Command::new(assert_cmd::cargo::cargo_bin!("packc"));
-    scaffold.current_dir(workspace_root());
+    scaffold.current_dir(common::workspace_root());
     scaffold.args([
         "new",
         "demo-pack",
@@ -60,7 +95,7 @@ fn scaffold_minimal_pack_builds() {
     );
 
     let mut build = Command::new(assert_cmd::cargo::cargo_bin!("packc"));
-    build.current_dir(workspace_root());
+    build.current_dir(common::workspace_root());
     build.args([
         "build",
         "--in",
@@ -72,13 +107,82 @@ fn scaffold_minimal_pack_builds() {
     build.assert().success();
 }
 
+#[test]
+fn add_flow_scaffolds_and_lists_the_flow() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("demo-pack");
+    let packc = |args: &[&str]| {
+        let mut command = Command::new(assert_cmd::cargo::cargo_bin!("packc"));
+        command
+            .current_dir(common::workspace_root())
+            .args(args)
+            .args(["--log", "warn"]);
+        command.assert()
+    };
+    packc(&["new", "demo-pack", "--dir", pack_dir.to_str().unwrap()]).success();
+
+    let pack = pack_dir.to_str().unwrap();
+    packc(&["add-flow", "order_status", "--in", pack, "--entry"]).success();
+    packc(&[
+        "add-flow",
+        "on_signup",
+        "--in",
+        pack,
+        "--template",
+        "events",
+    ])
+    .success();
+    let manifest = fs::read_to_string(pack_dir.join("pack.yaml")).unwrap();
+    let spec: serde_yaml_bw::Value = serde_yaml_bw::from_str(&manifest).unwrap();
+    let flow_files: Vec<&str> = spec["flow_files"]
+        .as_sequence()
+        .unwrap()
+        .iter()
+        .map(|file| file.as_str().unwrap())
+        .collect();
+    assert_eq!(
+        flow_files,
+        [
+            "flows/welcome.ygtc",
+            "flows/order_status.ygtc",
+            "flows/on_signup.ygtc"
+        ]
+    );
+    assert!(
+        spec["entry_flows"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .any(|flow| flow.as_str() == Some("order_status"))
+    );
+    let flow = fs::read_to_string(pack_dir.join("flows/order_status.ygtc")).unwrap();
+    assert!(flow.starts_with("id: order_status\ntitle: Order Status\n"));
+
+    let duplicate = packc(&["add-flow", "order_status", "--in", pack]).failure();
+    let stderr = String::from_utf8_lossy(&duplicate.get_output().stderr).to_string();
+    assert!(stderr.contains("already lists a flow"), "{stderr}");
+
+    // The scaffold declares no MCP component, so nothing is written.
+    let before = fs::read_to_string(pack_dir.join("pack.yaml")).unwrap();
+    let mcp = packc(&["add-flow", "lookup", "--in", pack, "--template", "mcp-exec"]).failure();
+    let stderr = String::from_utf8_lossy(&mcp.get_output().stderr).to_string();
+    assert!(stderr.contains("pass --component"), "{stderr}");
+    assert_eq!(
+        fs::read_to_string(pack_dir.join("pack.yaml")).unwrap(),
+        before
+    );
+    assert!(!pack_dir.join("flows/lookup.ygtc").exists());
+
+    packc(&["build", "--in", pack, "--dry-run"]).success();
+}
+
 #[test]
 fn scaffold_with_sign_generates_keys() {
     let temp = tempdir().expect("temp dir");
     let pack_dir = temp.path().join("signed-pack");
 
     let mut scaffold = Command::new(assert_cmd::cargo::cargo_bin!("packc"));
-    scaffold.current_dir(workspace_root());
+    scaffold.current_dir(common::workspace_root());
     scaffold.args([
         "new",
         "signed-pack",
@@ -104,6 +208,69 @@ fn scaffold_with_sign_generates_keys() {
         "public key should be PEM"
     );
 }
+#[test]
+fn sign_dry_run_reports_digest_without_touching_keys() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("signed-pack");
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args([
+            "new",
+            "signed-pack",
+            "--dir",
+            pack_dir.to_str().unwrap(),
+            "--sign",
+            "--log",
+            "warn",
+        ])
+        .assert()
+        .success();
+    let manifest_before = "[package]\nname = \"signed-pack\"\n";
+    fs::write(pack_dir.join("pack.toml"), manifest_before).expect("write pack.toml");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args([
+            "--json",
+            "sign",
+            "--pack",
+            pack_dir.to_str().unwrap(),
+            "--key",
+            temp.path().join("missing.pem").to_str().unwrap(),
+            "--dry-run",
+            "--log",
+            "warn",
+        ])
+        .assert()
+        .success();
+    let dry_run: Value = serde_json::from_slice(&assert.get_output().stdout).expect("json");
+    assert_eq!(dry_run["dry_run"], true);
+    assert_eq!(dry_run["canonicalization_version"], 1);
+    assert!(dry_run["files"].as_u64().unwrap() > 0);
+    let digest = dry_run["digest"].as_str().expect("digest");
+    assert!(digest.starts_with("sha256:"), "{digest}");
+    assert_eq!(
+        fs::read_to_string(pack_dir.join("pack.toml")).expect("pack.toml"),
+        manifest_before,
+        "a dry run must not modify the manifest"
+    );
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args([
+            "--json",
+            "sign",
+            "--pack",
+            pack_dir.to_str().unwrap(),
+            "--key",
+            pack_dir.join("keys/dev_ed25519.sk").to_str().unwrap(),
+            "--log",
+            "warn",
+        ])
+        .assert()
+        .success();
+    let signed: Value = serde_json::from_slice(&assert.get_output().stdout).expect("json");
+    assert_eq!(signed["digest"], digest);
+}
+
 #[test]
 fn build_outputs_gtpack_archive() {
     let temp = tempdir().expect("temp dir");
@@ -135,7 +302,7 @@ fn build_outputs_gtpack_archive() {
     let component_data = base.join("pack_component").join("src").join("data.rs");
 
     let mut build = Command::new(assert_cmd::cargo::cargo_bin!("packc"));
-    build.current_dir(workspace_root());
+    build.current_dir(common::workspace_root());
     build.args([
         "build",
         "--in",
@@ -156,7 +323,7 @@ fn build_outputs_gtpack_archive() {
     build.assert().success();
 
     let mut inspect = Command::new("cargo");
-    inspect.current_dir(workspace_root());
+    inspect.current_dir(common::workspace_root());
     inspect.args([
         "run",
         "-p",
@@ -202,11 +369,11 @@ fn dns_resolves_index_crates() -> bool {
 fn lint_accepts_valid_events_provider_block() {
     let temp = tempdir().expect("temp dir");
     let pack_dir = temp.path().join("weather-demo");
-    copy_example_pack(&pack_dir);
+    common::copy_example_pack(&pack_dir);
     inject_events_section(&pack_dir, "broker");
 
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("packc"));
-    cmd.current_dir(workspace_root());
+    cmd.current_dir(common::workspace_root());
     cmd.args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"]);
     cmd.assert().success();
 }
@@ -215,11 +382,11 @@ fn lint_accepts_valid_events_provider_block() {
 fn lint_rejects_invalid_events_kind() {
     let temp = tempdir().expect("temp dir");
     let pack_dir = temp.path().join("weather-demo");
-    copy_example_pack(&pack_dir);
+    common::copy_example_pack(&pack_dir);
     inject_events_section(&pack_dir, "invalid-kind");
 
     let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
-        .current_dir(workspace_root())
+        .current_dir(common::workspace_root())
         .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
         .assert()
         .failure();
@@ -235,11 +402,11 @@ fn lint_rejects_invalid_events_kind() {
 fn lint_accepts_valid_messaging_adapter_block() {
     let temp = tempdir().expect("temp dir");
     let pack_dir = temp.path().join("weather-demo");
-    copy_example_pack(&pack_dir);
+    common::copy_example_pack(&pack_dir);
     inject_messaging_section(&pack_dir);
 
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("packc"));
-    cmd.current_dir(workspace_root());
+    cmd.current_dir(common::workspace_root());
     cmd.args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"]);
     cmd.assert().success();
 }
@@ -248,7 +415,7 @@ fn lint_accepts_valid_messaging_adapter_block() {
 fn lint_accepts_valid_repo_scanner_pack() {
     let temp = tempdir().expect("temp dir");
     let pack_dir = temp.path().join("weather-demo");
-    copy_example_pack(&pack_dir);
+    common::copy_example_pack(&pack_dir);
     inject_repo_section(
         &pack_dir,
         r#"
@@ -269,7 +436,7 @@ repo:
     );
 
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("packc"));
-    cmd.current_dir(workspace_root());
+    cmd.current_dir(common::workspace_root());
     cmd.args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"]);
     cmd.assert().success();
 }
@@ -278,7 +445,7 @@ repo:
 fn lint_accepts_valid_billing_pack() {
     let temp = tempdir().expect("temp dir");
     let pack_dir = temp.path().join("billing-demo");
-    copy_example_pack(&pack_dir);
+    common::copy_example_pack(&pack_dir);
     inject_repo_section(
         &pack_dir,
         r#"
@@ -302,7 +469,7 @@ interfaces:
     );
 
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("packc"));
-    cmd.current_dir(workspace_root());
+    cmd.current_dir(common::workspace_root());
     cmd.args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"]);
     cmd.assert().success();
 }
@@ -311,7 +478,7 @@ interfaces:
 fn lint_rejects_repo_missing_binding() {
     let temp = tempdir().expect("temp dir");
     let pack_dir = temp.path().join("weather-demo");
-    copy_example_pack(&pack_dir);
+    common::copy_example_pack(&pack_dir);
     inject_repo_section(
         &pack_dir,
         r#"
@@ -325,7 +492,7 @@ repo:
     );
 
     let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
-        .current_dir(workspace_root())
+        .current_dir(common::workspace_root())
         .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
         .assert()
         .failure();
@@ -337,11 +504,39 @@ repo:
     );
 }
 
+#[test]
+fn lint_rejects_capabilities_with_a_url_for_a_host() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    inject_repo_section(
+        &pack_dir,
+        r#"
+capabilities:
+  network:
+    - "https://api.weather.example"
+  secrets: [WEATHER_API_KEY]
+"#,
+    );
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .failure();
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("capabilities.network") && stderr.contains("not a URL"),
+        "stderr should name the invalid host, got: {stderr}"
+    );
+}
+
 #[test]
 fn lint_rejects_rollout_strategy_kind() {
     let temp = tempdir().expect("temp dir");
     let pack_dir = temp.path().join("weather-demo");
-    copy_example_pack(&pack_dir);
+    common::copy_example_pack(&pack_dir);
     inject_repo_section(
         &pack_dir,
         r#"
@@ -355,7 +550,7 @@ repo:
     );
 
     let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
-        .current_dir(workspace_root())
+        .current_dir(common::workspace_root())
         .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
         .assert()
         .failure();
@@ -367,15 +562,392 @@ repo:
     );
 }
 
+#[test]
+fn lint_checks_lifecycle_health_flow() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let manifest = pack_dir.join("pack.yaml");
+    let spec = fs::read_to_string(&manifest).unwrap();
+    fs::write(
+        &manifest,
+        format!("{spec}lifecycle:\n  health: weather_bot\n"),
+    )
+    .unwrap();
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["describe", "--in", pack_dir.to_str().unwrap()])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout.contains("flow weather_bot (health)"),
+        "describe should mark the health flow: {stdout}"
+    );
+
+    fs::write(&manifest, format!("{spec}lifecycle:\n  health: ping\n")).unwrap();
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("lifecycle.health refers to unknown flow `ping`"),
+        "stderr should name the unknown health flow: {stderr}"
+    );
+}
+
+#[test]
+fn run_health_needs_a_lifecycle_health_flow() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["run", "--health", "--in", pack_dir.to_str().unwrap()])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("names no `lifecycle.health` flow"),
+        "stderr should ask for a health flow: {stderr}"
+    );
+}
+
+#[test]
+fn run_watch_reads_turns_from_stdin() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["run", "weather_bot", "--watch", "--input", "-", "--in"])
+        .arg(&pack_dir)
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("--watch reads its turns from stdin"),
+        "stderr should refuse stdin input: {stderr}"
+    );
+}
+
+#[test]
+fn debug_steps_through_a_trace_and_exports_a_replay() {
+    use greentic_pack::cassette::{Cassette, Outcome, RedactionRules};
+    use greentic_pack::trace::TraceRecorder;
+
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let mut recorder = TraceRecorder::new(
+        "greentic.weather.demo",
+        "weather_bot",
+        &serde_json::json!({ "city": "Oslo" }),
+        RedactionRules::standard(),
+    );
+    recorder.enter("forecast");
+    recorder.record(
+        "weather.get",
+        &serde_json::json!({ "city": "Oslo" }),
+        &Outcome::Ok(serde_json::json!({ "temp": 4 })),
+    );
+    let trace = temp.path().join("trace.json");
+    recorder
+        .finish(Ok(serde_json::json!({ "status": "ok" })))
+        .save(&trace)
+        .unwrap();
+
+    let assert = assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["debug", "--trace"])
+        .arg(&trace)
+        .arg("--in")
+        .arg(&pack_dir)
+        .write_stdin("eval {{in.city}} is {{forecast.payload.temp}}\nexport\nquit\n")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout.contains("1 step(s), ended with status `ok`"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("\"Oslo is 4\""), "{stdout}");
+    let exported = pack_dir.join("tests/weather_bot-forecast.cassette.json");
+    assert_eq!(Cassette::load(&exported).unwrap().interactions.len(), 1);
+}
+
+#[test]
+fn lint_requires_classification_for_flows_sending_email() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let flow_path = pack_dir.join("flows/weather_bot.ygtc");
+    let flow = fs::read_to_string(&flow_path).unwrap().replace(
+        "    routing:\n      - out: true\n",
+        "    routing:\n      - to: mail_forecast\n\n  mail_forecast:\n    email.send:\n      to: in.email\n    routing:\n      - out: true\n",
+    );
+    fs::write(&flow_path, flow).unwrap();
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("weather_bot (uses `email.send`)"),
+        "stderr should name the unclassified flow: {stderr}"
+    );
+
+    let manifest = pack_dir.join("pack.yaml");
+    let spec = fs::read_to_string(&manifest).unwrap();
+    fs::write(
+        &manifest,
+        format!("{spec}classification:\n  weather_bot:\n    inputs: {{ email: pii, q_location: internal }}\n"),
+    )
+    .unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .success();
+
+    let spec_bundle = packc::manifest::load_spec(&pack_dir).unwrap();
+    let flows = packc::flows::load_flows(
+        &pack_dir,
+        &spec_bundle.spec,
+        packc::DigestAlgorithm::default(),
+    )
+    .unwrap();
+    let manifest =
+        packc::manifest::build_manifest(&spec_bundle, &flows, &[], "2025-01-01T00:00:00Z");
+    let handling = manifest.data_handling.expect("data handling summary");
+    assert!(handling.handles_pii());
+    assert_eq!(handling.flows["weather_bot"].pii, vec!["inputs.email"]);
+}
+
+#[test]
+fn lint_reports_flow_graph_problems_with_locations() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let flow_path = pack_dir.join("flows/weather_bot.ygtc");
+    let original = fs::read_to_string(&flow_path).unwrap();
+    let orphaned = format!(
+        "{original}\n  farewell:\n    templating.handlebars:\n      text: bye\n    routing:\n      - out: true\n"
+    );
+    fs::write(&flow_path, &orphaned).unwrap();
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["--json", "lint", "--in", pack_dir.to_str().unwrap()])
+        .assert()
+        .success();
+    let payload: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    let diagnostics: Vec<&Value> = payload["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|diagnostic| diagnostic["file"] == "flows/weather_bot.ygtc")
+        .collect();
+    assert_eq!(diagnostics.len(), 1, "{payload}");
+    assert_eq!(diagnostics[0]["rule"], "unreachable-node");
+    assert_eq!(diagnostics[0]["severity"], "warning");
+    assert_eq!(diagnostics[0]["file"], "flows/weather_bot.ygtc");
+    assert_eq!(diagnostics[0]["line"], original.lines().count() + 2);
+
+    let looped = original.replace(
+        "    routing:\n      - out: true\n",
+        "    routing:\n      - to: collect_location\n",
+    );
+    fs::write(&flow_path, looped).unwrap();
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .failure();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout.contains("error[inescapable-cycle] flows/weather_bot.ygtc:11:"),
+        "stdout should locate the cycle: {stdout}"
+    );
+}
+
+#[test]
+fn packlint_toml_sets_rule_levels() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let lint = || {
+        Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+            .current_dir(common::workspace_root())
+            .args(["--json", "lint", "--in", pack_dir.to_str().unwrap()])
+            .assert()
+    };
+
+    fs::write(
+        pack_dir.join("packlint.toml"),
+        "[rules]\nmissing-description = \"error\"\n",
+    )
+    .unwrap();
+    let assert = lint().failure();
+    let payload: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(payload["status"], "failed");
+    let failing: Vec<&Value> = payload["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|diagnostic| diagnostic["severity"] == "error")
+        .collect();
+    assert_eq!(failing.len(), 1, "{payload}");
+    assert_eq!(failing[0]["rule"], "missing-description");
+    assert_eq!(failing[0]["file"], "pack.yaml");
+
+    fs::write(
+        pack_dir.join("packlint.toml"),
+        "[rules]\nmissing-description = \"off\"\nnoncanonical-tag = \"off\"\n",
+    )
+    .unwrap();
+    let assert = lint().success();
+    let payload: Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert!(
+        payload["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|diagnostic| diagnostic["rule"] != "missing-description"),
+        "{payload}"
+    );
+
+    fs::write(
+        pack_dir.join("packlint.toml"),
+        "[rules]\nno-such-rule = \"warn\"\n",
+    )
+    .unwrap();
+    let assert = lint().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("unknown rule(s): no-such-rule"),
+        "stderr should name the unknown rule: {stderr}"
+    );
+}
+
+#[test]
+fn lint_rejects_undeclared_metrics_and_manifest_carries_declarations() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let flow_path = pack_dir.join("flows/weather_bot.ygtc");
+    let flow = fs::read_to_string(&flow_path).unwrap().replace(
+        "    routing:\n      - out: true\n",
+        "    routing:\n      - to: count_forecast\n\n  count_forecast:\n    metrics.increment:\n      name: forecasts_served\n      labels: { country: in.country }\n    routing:\n      - out: true\n",
+    );
+    fs::write(&flow_path, flow).unwrap();
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("emits undeclared metric `forecasts_served`"),
+        "stderr should name the undeclared metric: {stderr}"
+    );
+
+    let manifest = pack_dir.join("pack.yaml");
+    let spec = fs::read_to_string(&manifest).unwrap();
+    fs::write(
+        &manifest,
+        format!("{spec}metrics:\n  forecasts_served:\n    kind: counter\n    labels: [country]\n"),
+    )
+    .unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .success();
+
+    let spec_bundle = packc::manifest::load_spec(&pack_dir).unwrap();
+    let flows = packc::flows::load_flows(
+        &pack_dir,
+        &spec_bundle.spec,
+        packc::DigestAlgorithm::default(),
+    )
+    .unwrap();
+    let manifest =
+        packc::manifest::build_manifest(&spec_bundle, &flows, &[], "2025-01-01T00:00:00Z");
+    let metrics = manifest.metrics.expect("metric declarations");
+    assert_eq!(metrics.metrics["forecasts_served"].labels, ["country"]);
+}
+
+#[test]
+fn lint_rejects_undeclared_secrets_and_manifest_carries_declarations() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let flow_path = pack_dir.join("flows/weather_bot.ygtc");
+    let flow = fs::read_to_string(&flow_path).unwrap().replace(
+        "        days: parameters.days_default\n",
+        "        days: parameters.days_default\n        api_key: secret://WEATHER_API_KEY\n",
+    );
+    fs::write(&flow_path, flow).unwrap();
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("node `forecast_weather` reads undeclared secret `WEATHER_API_KEY`"),
+        "stderr should name the undeclared secret: {stderr}"
+    );
+
+    inject_repo_section(
+        &pack_dir,
+        r#"
+secrets:
+  WEATHER_API_KEY:
+    type: api_key
+    description: Key for the weather API
+"#,
+    );
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .success();
+
+    let spec_bundle = packc::manifest::load_spec(&pack_dir).unwrap();
+    let flows = packc::flows::load_flows(
+        &pack_dir,
+        &spec_bundle.spec,
+        packc::DigestAlgorithm::default(),
+    )
+    .unwrap();
+    let manifest =
+        packc::manifest::build_manifest(&spec_bundle, &flows, &[], "2025-01-01T00:00:00Z");
+    let secrets = manifest.secrets.expect("secret declarations");
+    assert_eq!(
+        secrets.secrets["WEATHER_API_KEY"].kind,
+        greentic_pack::secrets::SecretKind::ApiKey
+    );
+}
+
 #[test]
 fn lint_rejects_missing_pack_version() {
     let temp = tempdir().expect("temp dir");
     let pack_dir = temp.path().join("weather-demo");
-    copy_example_pack(&pack_dir);
+    common::copy_example_pack(&pack_dir);
     strip_pack_version(&pack_dir);
 
     let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
-        .current_dir(workspace_root())
+        .current_dir(common::workspace_root())
         .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
         .assert()
         .failure();
@@ -387,15 +959,112 @@ fn lint_rejects_missing_pack_version() {
     );
 }
 
+#[test]
+fn workspace_lint_reports_cross_pack_conflicts() {
+    let temp = tempdir().expect("temp dir");
+    let root = temp.path().join("packs");
+    let first = root.join("weather");
+    let second = root.join("team/weather-copy");
+    let broken = root.join("broken");
+    for dir in [&first, &second, &broken] {
+        common::copy_example_pack(dir);
+    }
+    inject_events_section(&first, "broker");
+    inject_events_section(&second, "broker");
+    let spec = second.join("pack.yaml");
+    let yaml = fs::read_to_string(&spec).expect("read pack.yaml");
+    fs::write(&spec, yaml.replace("ordering: per_key", "ordering: global")).expect("write");
+    strip_pack_version(&broken);
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["lint", "--workspace", root.to_str().unwrap(), "--jobs", "2"])
+        .args(["--json", "--log", "warn"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("3 of 3 pack(s) failed lint"), "{stderr}");
+
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).expect("JSON report");
+    let packs = report["packs"].as_array().expect("packs");
+    let paths: Vec<&str> = packs.iter().map(|p| p["path"].as_str().unwrap()).collect();
+    assert_eq!(paths, vec!["broken", "team/weather-copy", "weather"]);
+    let rules = |path: &str| -> Vec<String> {
+        report["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|d| d["path"] == path)
+            .map(|d| d["rule"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(rules("broken"), vec!["pack-invalid", "duplicate-pack-id"]);
+    let weather = rules("weather");
+    assert!(
+        weather.contains(&"duplicate-pack-id".to_string()),
+        "{weather:?}"
+    );
+    assert!(
+        weather.contains(&"conflicting-topic".to_string()),
+        "{weather:?}"
+    );
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["lint", "--workspace", root.to_str().unwrap(), "--sarif"])
+        .args(["--log", "warn"])
+        .assert()
+        .failure();
+    let sarif: Value = serde_json::from_slice(&assert.get_output().stdout).expect("SARIF");
+    assert_eq!(sarif["version"], "2.1.0");
+    let result = &sarif["runs"][0]["results"][0];
+    assert_eq!(result["ruleId"], "pack-invalid");
+    assert_eq!(
+        result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+        "broken/pack.yaml"
+    );
+}
+
+#[test]
+fn dedup_reports_templates_copied_between_packs() {
+    let temp = tempdir().expect("temp dir");
+    let root = temp.path().join("packs");
+    common::copy_example_pack(&root.join("weather"));
+    common::copy_example_pack(&root.join("forecast"));
+    let spec = root.join("forecast/pack.yaml");
+    let yaml = fs::read_to_string(&spec).expect("read pack.yaml");
+    fs::write(
+        &spec,
+        yaml.replace("id: greentic.weather.demo", "id: greentic.forecast.demo"),
+    )
+    .expect("write pack.yaml");
+    let out = temp.path().join("dedup.json");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["dedup", "--workspace", root.to_str().unwrap()])
+        .args(["--min-size", "0", "--out", out.to_str().unwrap()])
+        .args(["--log", "warn"])
+        .assert()
+        .success();
+
+    let report: Value =
+        serde_json::from_slice(&fs::read(&out).expect("report written")).expect("JSON report");
+    assert_eq!(report["packs"], 2);
+    assert_eq!(report["identical_groups"], 1);
+    let group = &report["groups"][0];
+    assert_eq!(group["kind"], "identical");
+    assert_eq!(group["assets"][0]["pack"], "greentic.forecast.demo");
+    assert_eq!(group["assets"][1]["pack"], "greentic.weather.demo");
+    assert_eq!(group["assets"][0]["path"], "templates/weather_now.hbs");
+}
+
 #[test]
 fn lint_rejects_invalid_pack_version() {
     let temp = tempdir().expect("temp dir");
     let pack_dir = temp.path().join("weather-demo");
-    copy_example_pack(&pack_dir);
+    common::copy_example_pack(&pack_dir);
     set_pack_version(&pack_dir, 99);
 
     let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
-        .current_dir(workspace_root())
+        .current_dir(common::workspace_root())
         .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
         .assert()
         .failure();
@@ -407,38 +1076,1399 @@ fn lint_rejects_invalid_pack_version() {
     );
 }
 
-fn copy_example_pack(target: &std::path::Path) {
-    let source = workspace_root().join("examples/weather-demo");
-    for entry in WalkDir::new(&source)
-        .into_iter()
-        .filter_map(Result::ok)
-        .filter(|e| e.file_type().is_file())
-    {
-        let relative = entry.path().strip_prefix(&source).expect("strip prefix");
-        let destination = target.join(relative);
-        if let Some(parent) = destination.parent() {
-            fs::create_dir_all(parent).expect("create parent dirs");
-        }
-        fs::copy(entry.path(), &destination).expect("copy fixture file");
-    }
-}
-
-fn inject_events_section(pack_dir: &std::path::Path, kind: &str) {
-    let path = pack_dir.join("pack.yaml");
-    let original = fs::read_to_string(&path).expect("read pack.yaml");
-    let events_block = format!(
-        "\nevents:\n  providers:\n    - name: \"nats-core\"\n      kind: {kind}\n      component: \"nats-provider@1.0.0\"\n      default_flow: \"flows/events/nats/default.ygtc\"\n      custom_flow: \"flows/events/nats/custom.ygtc\"\n      capabilities:\n        transport: nats\n        reliability: at_least_once\n        ordering: per_key\n        topics:\n          - \"greentic.*\"\n"
+#[test]
+fn lint_reports_media_and_oversized_screenshot() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    fs::create_dir_all(pack_dir.join("media")).expect("media dir");
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    fs::write(pack_dir.join("media/icon.png"), &png).expect("write icon");
+    png.resize(600 * 1024, 0);
+    fs::write(pack_dir.join("media/chat.png"), &png).expect("write screenshot");
+    inject_repo_section(
+        &pack_dir,
+        r#"
+media:
+  icon:
+    path: media/icon.png
+  screenshots:
+    - path: media/chat.png
+      caption: "Asking for the weather"
+"#,
     );
-    fs::write(&path, format!("{original}{events_block}")).expect("write updated pack.yaml");
-}
 
-fn inject_repo_section(pack_dir: &std::path::Path, section: &str) {
-    let path = pack_dir.join("pack.yaml");
-    let original = fs::read_to_string(&path).expect("read pack.yaml");
-    fs::write(&path, format!("{original}{section}\n")).expect("write updated pack.yaml");
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args([
+            "lint",
+            "--in",
+            pack_dir.to_str().unwrap(),
+            "--json",
+            "--log",
+            "warn",
+        ])
+        .output()
+        .expect("run packc lint");
+    assert!(output.status.success(), "lint should pass with warnings");
+    let report: Value = serde_json::from_slice(&output.stdout).expect("lint json");
+    assert_eq!(report["media"], 2);
+    let warnings = report["warnings"].as_array().expect("warnings array");
+    assert!(
+        warnings
+            .iter()
+            .any(|w| w.as_str().unwrap_or_default().contains("media/chat.png")),
+        "oversized screenshot should be flagged: {warnings:?}"
+    );
 }
 
-fn inject_messaging_section(pack_dir: &std::path::Path) {
+#[test]
+fn lint_rejects_media_with_wrong_format() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    fs::write(pack_dir.join("icon.png"), b"GIF89a").expect("write icon");
+    inject_repo_section(&pack_dir, "\nmedia:\n  icon:\n    path: icon.png\n");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("image/png"),
+        "stderr should mention format: {stderr}"
+    );
+}
+
+#[test]
+fn lint_normalizes_tags_and_rejects_unknown_category() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    inject_repo_section(
+        &pack_dir,
+        "\ntags:\n  - \"Weather Bot\"\n  - ML\ncategories:\n  - messaging\n",
+    );
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args([
+            "lint",
+            "--in",
+            pack_dir.to_str().unwrap(),
+            "--json",
+            "--log",
+            "warn",
+        ])
+        .output()
+        .expect("run packc lint");
+    assert!(output.status.success());
+    let report: Value = serde_json::from_slice(&output.stdout).expect("lint json");
+    assert_eq!(report["tags"][0], "weather-bot");
+    assert_eq!(report["tags"][1], "machine-learning");
+    let rewrites = report["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|diagnostic| diagnostic["rule"] == "noncanonical-tag")
+        .count();
+    assert_eq!(rewrites, 2, "{report}");
+
+    fs::write(
+        pack_dir.join("taxonomy.toml"),
+        "categories = [\"search\"]\n",
+    )
+    .expect("write taxonomy");
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("unknown category `messaging`"),
+        "stderr should mention the category: {stderr}"
+    );
+}
+
+#[test]
+fn lint_rejects_readme_with_dangling_link() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    fs::write(
+        pack_dir.join("README.md"),
+        "# Weather\n\nSee [the flow](flows/weather_bot.ygtc) and [the template](templates/weather_now.hbs).\n",
+    )
+    .expect("write readme");
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .success();
+
+    fs::write(pack_dir.join("README.md"), "![diagram](docs/diagram.png)\n").expect("write readme");
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("docs/diagram.png"),
+        "stderr should list the broken link: {stderr}"
+    );
+}
+
+#[test]
+fn lint_and_graph_follow_template_asset_references() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let templates = pack_dir.join("templates");
+    fs::create_dir_all(templates.join("partials")).unwrap();
+    fs::create_dir_all(templates.join("img")).unwrap();
+    fs::write(
+        templates.join("forecast.hbs"),
+        "{{> partials/header}}<img src=\"img/sun.png\">\n",
+    )
+    .unwrap();
+    fs::write(templates.join("partials/header.hbs"), "<h1>Forecast</h1>\n").unwrap();
+    fs::write(templates.join("img/sun.png"), b"png").unwrap();
+    fs::write(templates.join("img/unused.png"), b"png").unwrap();
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout.contains("templates/img/unused.png"),
+        "lint should flag the orphaned asset: {stdout}"
+    );
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args([
+            "--json",
+            "graph",
+            "--assets",
+            "--in",
+            pack_dir.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+    let graph: Value = serde_json::from_slice(&assert.get_output().stdout).expect("graph json");
+    let resolved: Vec<&str> = graph["references"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|r| r["resolved"].as_str())
+        .collect();
+    assert_eq!(
+        resolved,
+        vec!["templates/partials/header.hbs", "templates/img/sun.png"]
+    );
+    assert_eq!(
+        graph["orphans"],
+        serde_json::json!(["templates/img/unused.png"])
+    );
+
+    fs::remove_file(templates.join("img/sun.png")).unwrap();
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("templates/forecast.hbs -> img/sun.png"),
+        "stderr should list the missing target: {stderr}"
+    );
+}
+
+#[test]
+fn describe_reports_imports_and_templates_per_flow() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let manifest = pack_dir.join("pack.yaml");
+    let spec = fs::read_to_string(&manifest)
+        .unwrap()
+        .replace("  - telemetry.emit\n", "  - telemetry.emit\n  - mcp.exec\n");
+    fs::write(&manifest, spec).unwrap();
+    let flow_path = pack_dir.join("flows/weather_bot.ygtc");
+    let flow = fs::read_to_string(&flow_path)
+        .unwrap()
+        .replace(
+            "        days: parameters.days_default\n",
+            "        days: parameters.days_default\n        token: secrets.weather_api_key\n",
+        )
+        .replace(
+            "    templating.handlebars:\n",
+            "    templating.handlebars:\n      template: weather_now\n",
+        );
+    fs::write(&flow_path, flow).unwrap();
+    fs::write(
+        pack_dir.join("templates/weather_now.hbs"),
+        "{{> partials/units}} {{temp_c}}\n",
+    )
+    .unwrap();
+    fs::create_dir_all(pack_dir.join("templates/partials")).unwrap();
+    fs::write(pack_dir.join("templates/partials/units.hbs"), "°C").unwrap();
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["--json", "describe", "--in", pack_dir.to_str().unwrap()])
+        .assert()
+        .success();
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).expect("describe json");
+    let flow = &report["flows"][0];
+    assert_eq!(flow["flow"], "weather_bot");
+    assert_eq!(flow["nodes"], 3);
+    assert_eq!(flow["components"]["mcp.exec"], 1);
+    assert_eq!(
+        flow["imports"],
+        serde_json::json!(["secrets.get", "mcp.exec"])
+    );
+    assert_eq!(
+        flow["templates"],
+        serde_json::json!(["templates/partials/units.hbs", "templates/weather_now.hbs"])
+    );
+    assert_eq!(
+        report["unattributed_imports"],
+        serde_json::json!(["telemetry.emit"])
+    );
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args(["describe", "--in", pack_dir.to_str().unwrap()])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout.contains("imports: secrets.get, mcp.exec"),
+        "unexpected describe output: {stdout}"
+    );
+}
+
+#[test]
+fn describe_filters_by_tag_and_category() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let manifest = pack_dir.join("pack.yaml");
+    let spec = fs::read_to_string(&manifest).unwrap().replace(
+        "version: 0.1.0\n",
+        "version: 0.1.0\ntags:\n  - Weather Bot\ncategories:\n  - messaging\n",
+    );
+    fs::write(&manifest, spec).unwrap();
+
+    let describe = |filters: &[&str]| {
+        let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+            .current_dir(common::workspace_root())
+            .args(["--json", "describe", "--in", pack_dir.to_str().unwrap()])
+            .args(filters)
+            .assert()
+            .success();
+        String::from_utf8_lossy(&assert.get_output().stdout).into_owned()
+    };
+
+    let report: Value = serde_json::from_str(&describe(&[
+        "--tag",
+        "weather-bot",
+        "--category",
+        "ai",
+        "--category",
+        "messaging",
+    ]))
+    .expect("describe json");
+    assert_eq!(report["pack"]["tags"], serde_json::json!(["weather-bot"]));
+    assert_eq!(
+        report["pack"]["categories"],
+        serde_json::json!(["messaging"])
+    );
+
+    assert!(describe(&["--tag", "weather-bot", "--tag", "finance"]).is_empty());
+    assert!(describe(&["--category", "ai"]).is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn hermetic_build_records_the_builder_image_digest() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let engine = fake_container_engine(temp.path());
+
+    let dist = temp.path().join("dist");
+    let gtpack = temp.path().join("weather.gtpack");
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .env("PACKC_CONTAINER_ENGINE", &engine)
+        .args(["build", "--hermetic", "--in"])
+        .arg(&pack_dir)
+        .arg("--out")
+        .arg(dist.join("pack.wasm"))
+        .arg("--manifest")
+        .arg(dist.join("manifest.cbor"))
+        .arg("--sbom")
+        .arg(dist.join("sbom.cdx.json"))
+        .arg("--build-cache")
+        .arg(temp.path().join("cache"))
+        .arg("--gtpack-out")
+        .arg(&gtpack)
+        .args(["--log", "warn"])
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read(dist.join("pack.wasm")).expect("component copied"),
+        b"\0asm\x01\0\0\0"
+    );
+    let load = greentic_pack::open_pack(&gtpack, greentic_pack::SigningPolicy::DevOk)
+        .expect("open gtpack");
+    let provenance: Value =
+        serde_json::from_slice(load.file("provenance.json").expect("provenance")).unwrap();
+    assert_eq!(provenance["toolchain"], "registry.example/rust@sha256:feed");
+}
+
+#[test]
+fn remote_build_verifies_the_returned_component() {
+    use base64::Engine;
+    use sha2::{Digest, Sha256};
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("accept");
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let (mut length, mut request_line) = (0, String::new());
+        reader.read_line(&mut request_line).unwrap();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let lower = line.to_ascii_lowercase();
+            if let Some(value) = lower.strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+            if lower.starts_with("expect: 100-continue") {
+                (&stream)
+                    .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")
+                    .unwrap();
+            }
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        let request: Value = serde_json::from_slice(&body).unwrap();
+        let wasm = b"\0asm\x01\0\0\0";
+        let response = serde_json::json!({
+            "component": base64::engine::general_purpose::STANDARD.encode(wasm),
+            "component_sha256": hex::encode(Sha256::digest(wasm)),
+            "report": {
+                "inputs_digest": request["inputs_digest"],
+                "toolchain": "rustc 1.89.0 (build farm)",
+            },
+        })
+        .to_string();
+        write!(
+            &stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+            response.len()
+        )
+        .unwrap();
+        (request_line, request)
+    });
+
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let dist = temp.path().join("dist");
+    let gtpack = temp.path().join("weather.gtpack");
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["build", "--in"])
+        .arg(&pack_dir)
+        .arg("--out")
+        .arg(dist.join("pack.wasm"))
+        .arg("--manifest")
+        .arg(dist.join("manifest.cbor"))
+        .arg("--sbom")
+        .arg(dist.join("sbom.cdx.json"))
+        .args(["--remote-build", &url])
+        .arg("--gtpack-out")
+        .arg(&gtpack)
+        .args(["--log", "warn"])
+        .assert()
+        .success();
+
+    let (request_line, request) = server.join().expect("server");
+    assert!(
+        request_line.starts_with("POST /v1/builds "),
+        "{request_line}"
+    );
+    assert_eq!(request["format"], "greentic-remote-build-v1");
+    let paths: Vec<&str> = request["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|file| file["path"].as_str().unwrap())
+        .collect();
+    assert!(paths.contains(&"src/data.rs"), "{paths:?}");
+    assert_eq!(
+        fs::read(dist.join("pack.wasm")).expect("component written"),
+        b"\0asm\x01\0\0\0"
+    );
+    let report: Value = serde_json::from_slice(
+        &fs::read(dist.join("pack.build-report.json")).expect("report written"),
+    )
+    .unwrap();
+    assert_eq!(report["inputs_digest"], request["inputs_digest"]);
+    let load = greentic_pack::open_pack(&gtpack, greentic_pack::SigningPolicy::DevOk)
+        .expect("open gtpack");
+    let provenance: Value =
+        serde_json::from_slice(load.file("provenance.json").expect("provenance")).unwrap();
+    assert_eq!(provenance["toolchain"], "rustc 1.89.0 (build farm)");
+}
+
+#[cfg(unix)]
+#[test]
+fn unreachable_remote_build_falls_back_to_a_local_build() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let engine = fake_container_engine(temp.path());
+    let closed = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let url = format!("http://{}", closed.local_addr().unwrap());
+    drop(closed);
+
+    let dist = temp.path().join("dist");
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .env("PACKC_CONTAINER_ENGINE", &engine)
+        .args(["build", "--hermetic", "--in"])
+        .arg(&pack_dir)
+        .arg("--out")
+        .arg(dist.join("pack.wasm"))
+        .arg("--manifest")
+        .arg(dist.join("manifest.cbor"))
+        .arg("--sbom")
+        .arg(dist.join("sbom.cdx.json"))
+        .arg("--build-cache")
+        .arg(temp.path().join("cache"))
+        .args(["--remote-build", &url, "--log", "warn"])
+        .assert()
+        .success();
+
+    assert!(dist.join("pack.wasm").exists());
+    assert!(!dist.join("pack.build-report.json").exists());
+}
+
+#[test]
+fn build_embeds_localized_permission_prompts() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    fs::create_dir_all(pack_dir.join("i18n")).unwrap();
+    fs::write(
+        pack_dir.join("i18n/de.json"),
+        r#"{ "greeting": "Hallo", "permission.mcp": "Externe Werkzeuge über MCP ausführen" }"#,
+    )
+    .unwrap();
+    let wasm = temp.path().join("prebuilt.wasm");
+    fs::write(&wasm, [0x00u8, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]).expect("write wasm");
+    let dist = temp.path().join("dist");
+    let gtpack = temp.path().join("weather.gtpack");
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["build", "--in"])
+        .arg(&pack_dir)
+        .arg("--out")
+        .arg(dist.join("pack.wasm"))
+        .arg("--manifest")
+        .arg(dist.join("manifest.cbor"))
+        .arg("--sbom")
+        .arg(dist.join("sbom.cdx.json"))
+        .arg("--component-wasm")
+        .arg(&wasm)
+        .arg("--gtpack-out")
+        .arg(&gtpack)
+        .args(["--log", "warn"])
+        .assert()
+        .success();
+
+    let load = greentic_pack::open_pack(&gtpack, greentic_pack::SigningPolicy::DevOk)
+        .expect("open gtpack");
+    let german = load.permission_summary("de-AT");
+    assert_eq!(german.len(), 1, "{german:?}");
+    assert_eq!(german[0].capability, "mcp");
+    assert_eq!(german[0].flows, ["weather_bot"]);
+    assert_eq!(german[0].locale, "de");
+    assert_eq!(german[0].text, "Externe Werkzeuge über MCP ausführen");
+    let fallback = load.permission_summary("ja");
+    assert_eq!(fallback[0].text, "Run external tools through MCP servers");
+}
+
+#[test]
+fn profile_data_marks_hot_flows_eager_and_is_recorded_in_provenance() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let profile = temp.path().join("runs.json");
+    fs::write(
+        &profile,
+        r#"{ "flows": { "weather_bot": 950, "retired_flow": 50 } }"#,
+    )
+    .unwrap();
+    let wasm = temp.path().join("prebuilt.wasm");
+    fs::write(&wasm, [0x00u8, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]).expect("write wasm");
+    let dist = temp.path().join("dist");
+    let gtpack = temp.path().join("weather.gtpack");
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["build", "--in"])
+        .arg(&pack_dir)
+        .arg("--out")
+        .arg(dist.join("pack.wasm"))
+        .arg("--manifest")
+        .arg(dist.join("manifest.cbor"))
+        .arg("--sbom")
+        .arg(dist.join("sbom.cdx.json"))
+        .arg("--component-wasm")
+        .arg(&wasm)
+        .arg("--gtpack-out")
+        .arg(&gtpack)
+        .arg("--profile-data")
+        .arg(&profile)
+        .args(["--log", "warn"])
+        .assert()
+        .success();
+
+    let load = greentic_pack::open_pack(&gtpack, greentic_pack::SigningPolicy::DevOk)
+        .expect("open gtpack");
+    let eager: Vec<&str> = load
+        .manifest
+        .flows
+        .iter()
+        .filter(|flow| flow.eager)
+        .map(|flow| flow.id.as_str())
+        .collect();
+    assert_eq!(eager, ["weather_bot"]);
+    let provenance = load.provenance().expect("provenance");
+    assert_eq!(
+        provenance.profile_blake3.as_deref(),
+        Some(blake3::hash(&fs::read(&profile).unwrap()).to_hex().as_str())
+    );
+
+    let manifest: Value =
+        serde_cbor::from_slice(&fs::read(dist.join("manifest.cbor")).unwrap()).unwrap();
+    assert_eq!(manifest["flows"][0]["eager"], true);
+}
+
+#[test]
+fn build_embeds_config_schema_and_lint_rejects_broken_ones() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    inject_repo_section(
+        &pack_dir,
+        "\nconfig_schema:\n  type: object\n  required: [api_base_url]\n  properties:\n    api_base_url: { type: string, format: uri }\n    beta_ui: { type: boolean, default: false }",
+    );
+    let wasm = temp.path().join("prebuilt.wasm");
+    fs::write(&wasm, [0x00u8, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]).expect("write wasm");
+    let dist = temp.path().join("dist");
+    let gtpack = temp.path().join("weather.gtpack");
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["build", "--in"])
+        .arg(&pack_dir)
+        .arg("--out")
+        .arg(dist.join("pack.wasm"))
+        .arg("--manifest")
+        .arg(dist.join("manifest.cbor"))
+        .arg("--sbom")
+        .arg(dist.join("sbom.cdx.json"))
+        .arg("--component-wasm")
+        .arg(&wasm)
+        .arg("--gtpack-out")
+        .arg(&gtpack)
+        .args(["--log", "warn"])
+        .assert()
+        .success();
+
+    let load = greentic_pack::open_pack(&gtpack, greentic_pack::SigningPolicy::DevOk)
+        .expect("open gtpack");
+    let config_schema = load.config_schema().expect("embedded config schema");
+    assert_eq!(config_schema.required_keys(), ["api_base_url"]);
+
+    let broken = temp.path().join("broken");
+    common::copy_example_pack(&broken);
+    inject_repo_section(
+        &broken,
+        "\nconfig_schema:\n  type: object\n  required: [api_base_url]\n  properties: {}",
+    );
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["lint", "--in", broken.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("config_schema.required"), "{stderr}");
+}
+
+#[test]
+fn lint_rejects_config_defaults_that_violate_the_schema() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    inject_repo_section(
+        &pack_dir,
+        "\nconfig_schema:\n  type: object\n  properties:\n    timeout_ms: { type: integer }\nconfig_defaults:\n  environments: [prod]\n  values: { timeout_ms: fast }\n  overrides:\n    staging: { timeout_ms: 100 }",
+    );
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("config_defaults.values: timeout_ms"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("undeclared environment `staging`"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn config_params_become_the_manifest_config_schema() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    inject_repo_section(
+        &pack_dir,
+        "\nconfig:\n  params:\n    units: { type: enum, values: [metric, imperial], default: metric }\n    api_base_url: { type: string, required: true }",
+    );
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .success();
+
+    let spec_bundle = packc::manifest::load_spec(&pack_dir).unwrap();
+    let flows = packc::flows::load_flows(
+        &pack_dir,
+        &spec_bundle.spec,
+        packc::DigestAlgorithm::default(),
+    )
+    .unwrap();
+    let manifest =
+        packc::manifest::build_manifest(&spec_bundle, &flows, &[], "2025-01-01T00:00:00Z");
+    let schema = manifest.config_schema.expect("schema derived from params");
+    assert_eq!(schema.required_keys(), ["api_base_url"]);
+    assert_eq!(schema.defaults()["units"], "metric");
+
+    inject_repo_section(
+        &pack_dir,
+        "config_schema:\n  type: object\n  properties: {}",
+    );
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["lint", "--in", pack_dir.to_str().unwrap(), "--log", "warn"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("not both"), "{stderr}");
+}
+
+#[test]
+fn node_docs_reach_graph_and_embedded_index() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let flow_path = pack_dir.join("flows/weather_bot.ygtc");
+    let flow = fs::read_to_string(&flow_path).unwrap().replace(
+        "  forecast_weather:\n",
+        "  forecast_weather:\n    doc: Calls the weather API for the chosen city.\n",
+    );
+    fs::write(&flow_path, flow).unwrap();
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["--json", "graph", "--flows", "--in"])
+        .arg(&pack_dir)
+        .assert()
+        .success();
+    let graph: Value = serde_json::from_slice(&assert.get_output().stdout).expect("graph json");
+    let nodes = graph["flows"][0]["nodes"].as_array().unwrap();
+    let forecast = nodes
+        .iter()
+        .find(|node| node["id"] == "forecast_weather")
+        .expect("forecast node");
+    assert_eq!(forecast["component"], "mcp.exec");
+    assert_eq!(
+        forecast["doc"],
+        "Calls the weather API for the chosen city."
+    );
+    assert_eq!(forecast["routes"], serde_json::json!(["weather_text"]));
+
+    let wasm = temp.path().join("prebuilt.wasm");
+    fs::write(&wasm, [0x00u8, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]).expect("write wasm");
+    let dist = temp.path().join("dist");
+    let component_data = temp.path().join("data.rs");
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["build", "--in"])
+        .arg(&pack_dir)
+        .arg("--out")
+        .arg(dist.join("pack.wasm"))
+        .arg("--manifest")
+        .arg(dist.join("manifest.cbor"))
+        .arg("--sbom")
+        .arg(dist.join("sbom.cdx.json"))
+        .arg("--component-wasm")
+        .arg(&wasm)
+        .arg("--component-data")
+        .arg(&component_data)
+        .args(["--log", "warn"])
+        .assert()
+        .success();
+    let generated = fs::read_to_string(&component_data).unwrap();
+    assert!(
+        generated.contains(
+            r#"node_docs: &[("forecast_weather", "Calls the weather API for the chosen city.")],"#
+        ),
+        "{generated}"
+    );
+}
+
+#[test]
+fn lsp_reports_flow_diagnostics_and_completes_node_ids() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let flow_path = pack_dir.join("flows/weather_bot.ygtc");
+    let flow = fs::read_to_string(&flow_path)
+        .unwrap()
+        .replace("- to: weather_text", "- to: weather_txt");
+    let uri = format!("file://{}", flow_path.display());
+
+    let messages = [
+        serde_json::json!({
+            "jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": { "capabilities": {} }
+        }),
+        serde_json::json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }),
+        serde_json::json!({
+            "jsonrpc": "2.0", "method": "textDocument/didOpen",
+            "params": { "textDocument": {
+                "uri": uri, "languageId": "yaml", "version": 1, "text": flow
+            } }
+        }),
+        serde_json::json!({
+            "jsonrpc": "2.0", "id": 2, "method": "textDocument/completion",
+            "params": { "textDocument": { "uri": uri }, "position": { "line": 29, "character": 12 } }
+        }),
+        serde_json::json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }),
+        serde_json::json!({ "jsonrpc": "2.0", "method": "exit" }),
+    ];
+    let mut child = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["lsp", "--stdio"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("spawn packc lsp");
+    let mut stdin = child.stdin.take().unwrap();
+    for message in &messages {
+        let body = message.to_string();
+        write!(stdin, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+    }
+    drop(stdin);
+    let output = child.wait_with_output().expect("packc lsp output");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let replies: Vec<Value> = stdout
+        .split("Content-Length: ")
+        .filter_map(|frame| frame.split_once("\r\n\r\n"))
+        .map(|(_, body)| serde_json::from_str(body).expect("json-rpc body"))
+        .collect();
+    let diagnostics = replies
+        .iter()
+        .find(|reply| reply["method"] == "textDocument/publishDiagnostics")
+        .expect("diagnostics published");
+    let problems = diagnostics["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(problems.len(), 1, "{problems:?}");
+    assert!(
+        problems[0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("weather_txt")
+    );
+    assert_eq!(problems[0]["range"]["start"]["line"], 29);
+
+    let completion = replies
+        .iter()
+        .find(|reply| reply["id"] == 2)
+        .expect("completion reply");
+    let labels: Vec<&str> = completion["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|item| item["label"].as_str())
+        .collect();
+    assert_eq!(
+        labels,
+        ["collect_location", "forecast_weather", "weather_text"]
+    );
+}
+
+#[test]
+fn audit_bundles_signature_sbom_and_policy_results_for_a_gtpack() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let wasm = temp.path().join("prebuilt.wasm");
+    fs::write(&wasm, [0x00u8, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]).expect("write wasm");
+    let dist = temp.path().join("dist");
+    let gtpack = temp.path().join("weather.gtpack");
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["build", "--in"])
+        .arg(&pack_dir)
+        .arg("--out")
+        .arg(dist.join("pack.wasm"))
+        .arg("--manifest")
+        .arg(dist.join("manifest.cbor"))
+        .arg("--sbom")
+        .arg(dist.join("sbom.cdx.json"))
+        .arg("--component-wasm")
+        .arg(&wasm)
+        .arg("--gtpack-out")
+        .arg(&gtpack)
+        .args(["--log", "warn"])
+        .assert()
+        .success();
+
+    let out = temp.path().join("audit.json");
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["audit", "--in"])
+        .arg(&gtpack)
+        .arg("--out")
+        .arg(&out)
+        .args(["--deny", "mcp", "--fail-on-policy", "--log", "warn"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("capabilities"), "{stderr}");
+
+    let audit: Value =
+        serde_json::from_slice(&fs::read(&out).expect("audit written")).expect("audit is JSON");
+    assert_eq!(audit["format"], "greentic-pack-audit-v1");
+    assert_eq!(audit["source"]["kind"], "gtpack");
+    assert_eq!(audit["signature"]["status"], "verified");
+    assert_eq!(audit["sbom"]["format"], "greentic-sbom-v1");
+    assert!(audit["provenance"]["builder"].is_string(), "{audit}");
+    let weather = audit["capabilities"]["flows"]["weather_bot"]
+        .as_array()
+        .expect("weather_bot capabilities");
+    assert!(weather.contains(&Value::from("mcp.exec")), "{weather:?}");
+
+    let rule = |name: &str| {
+        audit["policy"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|result| result["rule"] == name)
+            .cloned()
+            .unwrap_or_else(|| panic!("missing rule {name}"))
+    };
+    assert_eq!(rule("signed")["passed"], true);
+    assert_eq!(rule("classification")["passed"], true);
+    assert_eq!(rule("capabilities")["passed"], false);
+    assert!(
+        rule("capabilities")["detail"]
+            .as_str()
+            .unwrap()
+            .contains("weather_bot"),
+        "{audit}"
+    );
+    assert!(audit.get("attestation").is_none());
+}
+
+#[test]
+fn pack_lock_pins_import_versions_across_builds() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let yaml = fs::read_to_string(pack_dir.join("pack.yaml")).expect("read pack.yaml");
+    fs::write(
+        pack_dir.join("pack.yaml"),
+        yaml.replace(
+            "  - telemetry.emit\n",
+            "  - telemetry.emit\n  - greentic:host-import/secrets\n",
+        ),
+    )
+    .expect("write pack.yaml");
+    let wasm = temp.path().join("prebuilt.wasm");
+    fs::write(&wasm, [0x00u8, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]).expect("write wasm");
+    let dist = temp.path().join("dist");
+    let build = |extra: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+            .args(["build", "--in"])
+            .arg(&pack_dir)
+            .arg("--out")
+            .arg(dist.join("pack.wasm"))
+            .arg("--manifest")
+            .arg(dist.join("manifest.cbor"))
+            .arg("--sbom")
+            .arg(dist.join("sbom.cdx.json"))
+            .arg("--component-wasm")
+            .arg(&wasm)
+            .args(extra)
+            .args(["--log", "warn"])
+            .assert()
+    };
+    let resolved_version = || {
+        let manifest: Value =
+            serde_cbor::from_slice(&fs::read(dist.join("manifest.cbor")).unwrap()).unwrap();
+        manifest["imports_resolved"][0]["version"].clone()
+    };
+
+    let assert = build(&["--locked"]).failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("--locked requires pack.lock"), "{stderr}");
+
+    build(&[]).success();
+    let lock_path = pack_dir.join("pack.lock");
+    let lock = fs::read_to_string(&lock_path).expect("build writes pack.lock");
+    assert!(lock.contains("version = \"0.6.0\""), "{lock}");
+    assert_eq!(resolved_version(), "0.6.0");
+
+    fs::write(&lock_path, lock.replace("0.6.0", "0.4.0")).unwrap();
+    build(&["--locked"]).success();
+    assert_eq!(resolved_version(), "0.4.0");
+
+    let yaml = fs::read_to_string(pack_dir.join("pack.yaml")).unwrap();
+    fs::write(
+        pack_dir.join("pack.yaml"),
+        yaml.replace("host-import/secrets\n", "host-import/secrets@0.6\n"),
+    )
+    .unwrap();
+    let assert = build(&["--locked"]).failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("pack.lock is out of date"), "{stderr}");
+    assert!(
+        stderr.contains("greentic:host-import/secrets@0.6 (0.6.0)"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn verify_locked_detects_inputs_that_diverge_from_pack_lock() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let wasm = temp.path().join("prebuilt.wasm");
+    fs::write(&wasm, [0x00u8, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]).expect("write wasm");
+    let dist = temp.path().join("dist");
+    let build = |extra: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+            .args(["build", "--in"])
+            .arg(&pack_dir)
+            .arg("--out")
+            .arg(dist.join("pack.wasm"))
+            .arg("--manifest")
+            .arg(dist.join("manifest.cbor"))
+            .arg("--sbom")
+            .arg(dist.join("sbom.cdx.json"))
+            .arg("--component-wasm")
+            .arg(&wasm)
+            .args(extra)
+            .args(["--log", "warn"])
+            .assert()
+    };
+    let verify = || {
+        Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+            .args(["verify", "--locked", "--pack"])
+            .arg(&pack_dir)
+            .args(["--log", "warn"])
+            .assert()
+    };
+
+    let assert = verify().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("run `packc build` first"), "{stderr}");
+
+    build(&[]).success();
+    let lock = fs::read_to_string(pack_dir.join("pack.lock")).expect("build writes pack.lock");
+    assert!(lock.contains("[[template]]"), "{lock}");
+    assert!(
+        lock.contains("path = \"templates/weather_now.hbs\""),
+        "{lock}"
+    );
+    verify().success();
+
+    fs::write(
+        pack_dir.join("templates/weather_now.hbs"),
+        "It is {{temperature}} degrees.\n",
+    )
+    .unwrap();
+    let assert = verify().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("pack.lock does not match"), "{stderr}");
+    assert!(
+        stderr.contains("~ template templates/weather_now.hbs"),
+        "{stderr}"
+    );
+    build(&["--locked"]).failure();
+
+    build(&[]).success();
+    verify().success();
+}
+
+#[test]
+fn interface_catalog_can_be_refreshed_and_overridden() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let yaml = fs::read_to_string(pack_dir.join("pack.yaml")).expect("read pack.yaml");
+    fs::write(
+        pack_dir.join("pack.yaml"),
+        yaml.replace(
+            "  - telemetry.emit\n",
+            "  - telemetry.emit\n  - acme:billing/invoices@1\n",
+        ),
+    )
+    .expect("write pack.yaml");
+    let lint = |extra: &[&str]| {
+        let mut args = vec![
+            "--json",
+            "lint",
+            "--in",
+            pack_dir.to_str().unwrap(),
+            "--log",
+            "warn",
+        ];
+        args.extend_from_slice(extra);
+        Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+            .current_dir(common::workspace_root())
+            .args(args)
+            .assert()
+    };
+
+    let assert = lint(&[]).failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("unknown WIT package `acme:billing`"),
+        "stderr should name the unknown package: {stderr}"
+    );
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(common::workspace_root())
+        .args([
+            "--json",
+            "imports",
+            "update-catalog",
+            "--in",
+            pack_dir.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).expect("json");
+    assert_eq!(report["source"], "built-in catalog");
+    assert!(report["removed"].as_array().unwrap().is_empty());
+    let local = pack_dir.join("interfaces.catalog.toml");
+    let mut catalog = fs::read_to_string(&local).expect("local catalog");
+    catalog.push_str(
+        "\n[[package]]\nname = \"acme:billing\"\nversion = \"1.2.0\"\ninterfaces = [\"invoices\"]\n",
+    );
+    fs::write(&local, catalog).expect("write catalog");
+
+    let assert = lint(&[]).success();
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).expect("lint json");
+    assert_eq!(report["imports_resolved"][0]["version"], "1.2.0");
+
+    let broken = temp.path().join("broken.toml");
+    fs::write(&broken, "format = 2\n").expect("write catalog");
+    let assert = lint(&["--interface-catalog", broken.to_str().unwrap()]).failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("unsupported catalog format"),
+        "stderr should explain the rejected catalog: {stderr}"
+    );
+}
+
+#[test]
+fn import_mocks_are_generated_from_wit_and_checked() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let yaml = fs::read_to_string(pack_dir.join("pack.yaml")).expect("read pack.yaml");
+    fs::write(
+        pack_dir.join("pack.yaml"),
+        yaml.replace(
+            "  - telemetry.emit\n",
+            "  - telemetry.emit\n  - greentic:host-import/secrets@0.6\n",
+        ),
+    )
+    .expect("write pack.yaml");
+    let wit_dir = pack_dir.join("wit/greentic/host-import@0.6.0");
+    fs::create_dir_all(&wit_dir).expect("wit dir");
+    fs::write(
+        wit_dir.join("package.wit"),
+        "package greentic:host-import@0.6.0;\n\ninterface secrets {\n  get: func(key: string) -> result<string, string>;\n}\n",
+    )
+    .expect("write wit");
+    let mocks = |extra: &[&str]| {
+        let mut args = vec![
+            "--json",
+            "imports",
+            "mocks",
+            "--in",
+            pack_dir.to_str().unwrap(),
+        ];
+        args.extend_from_slice(extra);
+        Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+            .current_dir(common::workspace_root())
+            .args(args)
+            .assert()
+    };
+
+    let assert = mocks(&[]).success();
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).expect("json");
+    assert_eq!(
+        report["mocks"][0]["interface"],
+        "greentic:host-import/secrets@0.6.0"
+    );
+    assert_eq!(report["mocks"][0]["status"], "created");
+    let mock_path = pack_dir.join("mocks/greentic/host-import/secrets.yaml");
+    let mock = fs::read_to_string(&mock_path).expect("mock written");
+    assert!(
+        mock.contains("func(key: string) -> result<string, string>"),
+        "mock should carry the WIT signature: {mock}"
+    );
+
+    let assert = mocks(&["--check"]).success();
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout).expect("json");
+    assert_eq!(report["mocks"][0]["status"], "ok");
+
+    fs::write(
+        &mock_path,
+        "interface: greentic:host-import/secrets@0.6.0\nfunctions:\n  get:\n    returns:\n      ok: 42\n",
+    )
+    .expect("write mock");
+    let assert = mocks(&["--check"]).failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("get.returns.ok: expected string, found a number"),
+        "stderr should explain the mismatch: {stderr}"
+    );
+}
+
+#[test]
+fn clock_and_random_mocks_are_deterministic_without_wit() {
+    let temp = tempdir().expect("temp dir");
+    let write_mocks = |name: &str| {
+        let pack_dir = temp.path().join(name);
+        common::copy_example_pack(&pack_dir);
+        let yaml = fs::read_to_string(pack_dir.join("pack.yaml")).expect("read pack.yaml");
+        fs::write(
+            pack_dir.join("pack.yaml"),
+            yaml.replace(
+                "  - telemetry.emit\n",
+                "  - telemetry.emit\n  - wasi:clocks/wall-clock\n  - wasi:random/random@0.2\n",
+            ),
+        )
+        .expect("write pack.yaml");
+        let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+            .args(["--json", "imports", "mocks", "--in"])
+            .arg(&pack_dir)
+            .args(["--seed", "42", "--now", "2025-03-01T12:00:00Z"])
+            .assert()
+            .success();
+        let report: Value = serde_json::from_slice(&assert.get_output().stdout).expect("json");
+        assert_eq!(report["mocks"][0]["status"], "builtin");
+        assert_eq!(report["mocks"][1]["status"], "builtin");
+        let clock = fs::read_to_string(pack_dir.join("mocks/wasi/clocks/wall-clock.yaml"))
+            .expect("clock mock");
+        let random = fs::read_to_string(pack_dir.join("mocks/wasi/random/random.yaml"))
+            .expect("random mock");
+        (clock, random)
+    };
+
+    let (clock, random) = write_mocks("first");
+    assert!(clock.contains("seconds: 1740830400"), "{clock}");
+    assert_eq!(write_mocks("second"), (clock, random));
+}
+
+#[test]
+fn telemetry_consent_round_trips_through_user_config() {
+    let temp = tempdir().expect("temp dir");
+    let status = |args: &[&str]| -> Value {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+            .env("PACKC_CONFIG_DIR", temp.path())
+            .env_remove("PACKC_NO_USAGE")
+            .env_remove("DO_NOT_TRACK")
+            .args(["telemetry"])
+            .args(args)
+            .args(["--json", "--log", "warn"])
+            .output()
+            .expect("run packc telemetry");
+        assert!(output.status.success());
+        serde_json::from_slice(&output.stdout).expect("telemetry json")
+    };
+
+    assert_eq!(
+        status(&["status"])["usage_reporting"],
+        "not configured (disabled)"
+    );
+    assert_eq!(status(&["enable"])["usage_reporting"], "enabled");
+    let config = fs::read_to_string(temp.path().join("packc.toml")).expect("config written");
+    assert!(config.contains("usage_reporting = true"));
+    assert_eq!(status(&["status"])["usage_reporting"], "enabled");
+    assert_eq!(status(&["disable"])["usage_reporting"], "disabled");
+}
+
+#[test]
+fn tools_install_pins_archives_and_records_them() {
+    use sha2::{Digest, Sha256};
+
+    let temp = tempdir().expect("temp dir");
+    let helper = b"#!/bin/sh\necho helper\n";
+    fs::write(temp.path().join("wasm-opt"), helper).expect("write helper");
+    let platform = format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS);
+    let manifest = temp.path().join("toolchain.toml");
+    let write_manifest = |sha256: &str| {
+        fs::write(
+            &manifest,
+            format!(
+                "[tools.wasm-opt]\nversion = \"119\"\n\n[tools.wasm-opt.platforms.{platform}]\nurl = \"wasm-opt\"\nsha256 = \"{sha256}\"\n"
+            ),
+        )
+        .expect("write toolchain.toml");
+    };
+    let packc = |args: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+            .env("PACKC_TOOLS_DIR", temp.path().join("tools"))
+            .args(["--json", "--log", "warn", "tools"])
+            .args(args)
+            .arg("--manifest")
+            .arg(&manifest)
+            .output()
+            .expect("run packc tools")
+    };
+
+    write_manifest(&hex::encode(Sha256::digest(helper)));
+    let output = packc(&["install"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(report["tools"][0]["status"], "installed");
+    let installed = PathBuf::from(report["tools"][0]["path"].as_str().unwrap());
+    assert_eq!(fs::read(&installed).expect("installed binary"), helper);
+
+    let output = packc(&["install"]);
+    let report: Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(report["tools"][0]["status"], "up_to_date");
+
+    let list = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .env("PACKC_TOOLS_DIR", temp.path().join("tools"))
+        .args(["--json", "tools", "list"])
+        .output()
+        .expect("run packc tools list");
+    let list: Value = serde_json::from_slice(&list.stdout).expect("json");
+    assert_eq!(list["tools"]["wasm-opt"]["version"], "119");
+
+    write_manifest(&"ab".repeat(32));
+    let output = packc(&["install", "--force"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("digest mismatch"));
+}
+
+#[test]
+fn sign_refuses_pack_locked_by_another_process() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    fs::create_dir_all(pack_dir.join(".packc")).expect("create .packc");
+    // The test process itself stands in for a live competing build.
+    let holder = serde_json::json!({
+        "pid": std::process::id(),
+        "host": "ci-runner",
+        "command": "packc build",
+        "acquired_at": "2030-01-01T00:00:00Z",
+    });
+    let lock_path = pack_dir.join(".packc/build.lock");
+    fs::write(&lock_path, holder.to_string()).expect("write lock");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args([
+            "sign",
+            "--pack",
+            pack_dir.to_str().unwrap(),
+            "--key",
+            temp.path().join("unused.pem").to_str().unwrap(),
+            "--no-wait",
+            "--log",
+            "warn",
+        ])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("is locked by `packc build`"), "{stderr}");
+    assert!(
+        stderr.contains(&format!("pid {} on ci-runner", std::process::id())),
+        "{stderr}"
+    );
+    assert!(lock_path.exists(), "a foreign lock must not be removed");
+}
+
+/// A stand-in for docker that reports a digest and "compiles" into the
+/// cache mount.
+#[cfg(unix)]
+fn fake_container_engine(dir: &std::path::Path) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let engine = dir.join("fake-engine");
+    fs::write(
+        &engine,
+        r#"#!/bin/sh
+case "$1" in
+  image) echo '["registry.example/rust@sha256:feed"]' ;;
+  run)
+    for arg in "$@"; do
+      case "$arg" in *:/cache) cache="${arg%:/cache}" ;; esac
+    done
+    mkdir -p "$cache/target/wasm32-wasip2/release"
+    printf '\000asm\001\000\000\000' > "$cache/target/wasm32-wasip2/release/pack_component.wasm"
+    ;;
+esac
+"#,
+    )
+    .expect("write engine");
+    fs::set_permissions(&engine, fs::Permissions::from_mode(0o755)).expect("chmod engine");
+    engine
+}
+
+fn inject_events_section(pack_dir: &std::path::Path, kind: &str) {
+    let path = pack_dir.join("pack.yaml");
+    let original = fs::read_to_string(&path).expect("read pack.yaml");
+    let events_block = format!(
+        "\nevents:\n  providers:\n    - name: \"nats-core\"\n      kind: {kind}\n      component: \"nats-provider@1.0.0\"\n      default_flow: \"flows/events/nats/default.ygtc\"\n      custom_flow: \"flows/events/nats/custom.ygtc\"\n      capabilities:\n        transport: nats\n        reliability: at_least_once\n        ordering: per_key\n        topics:\n          - \"greentic.*\"\n"
+    );
+    fs::write(&path, format!("{original}{events_block}")).expect("write updated pack.yaml");
+}
+
+fn inject_repo_section(pack_dir: &std::path::Path, section: &str) {
+    let path = pack_dir.join("pack.yaml");
+    let original = fs::read_to_string(&path).expect("read pack.yaml");
+    fs::write(&path, format!("{original}{section}\n")).expect("write updated pack.yaml");
+}
+
+fn inject_messaging_section(pack_dir: &std::path::Path) {
     let path = pack_dir.join("pack.yaml");
     let original = fs::read_to_string(&path).expect("read pack.yaml");
     let block = r#"
@@ -484,3 +2514,192 @@ fn set_pack_version(pack_dir: &std::path::Path, version: u32) {
     }
     fs::write(&path, lines.join("\n") + "\n").expect("write pack.yaml with packVersion");
 }
+
+#[test]
+fn inspect_reads_manifest_and_gtpack() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("weather-demo");
+    common::copy_example_pack(&pack_dir);
+    let wasm = temp.path().join("prebuilt.wasm");
+    fs::write(&wasm, [0x00u8, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]).expect("write wasm");
+    let dist = pack_dir.join("dist");
+    let gtpack = dist.join("weather.gtpack");
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["build", "--in"])
+        .arg(&pack_dir)
+        .arg("--out")
+        .arg(dist.join("pack.wasm"))
+        .arg("--manifest")
+        .arg(dist.join("manifest.cbor"))
+        .arg("--sbom")
+        .arg(dist.join("sbom.cdx.json"))
+        .arg("--component-wasm")
+        .arg(&wasm)
+        .arg("--gtpack-out")
+        .arg(&gtpack)
+        .args(["--log", "warn"])
+        .assert()
+        .success();
+
+    let inspect = |path: &std::path::Path| -> Value {
+        let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+            .args(["--json", "inspect"])
+            .arg(path)
+            .assert()
+            .success();
+        serde_json::from_slice(&assert.get_output().stdout).expect("inspect json")
+    };
+    let manifest = inspect(&dist.join("manifest.cbor"));
+    let archive = inspect(&gtpack);
+    assert_eq!(manifest["artifact"], "manifest");
+    assert_eq!(archive["artifact"], "gtpack");
+    for summary in [&manifest, &archive] {
+        assert_eq!(summary["pack_id"], "greentic.weather.demo");
+        assert_eq!(summary["flows"][0]["id"], "weather_bot");
+    }
+    assert_eq!(manifest["templates"], archive["templates"]);
+    assert_eq!(manifest["imports"], archive["imports"]);
+    assert_eq!(manifest["signature"]["status"], "unsigned");
+    assert_eq!(archive["signature"]["status"], "verified");
+
+    let human = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .arg("inspect")
+        .arg(&gtpack)
+        .assert()
+        .success();
+    let human = String::from_utf8_lossy(&human.get_output().stdout).into_owned();
+    assert!(human.contains("signature: verified"), "{human}");
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["inspect", "--strict"])
+        .arg(&gtpack)
+        .assert()
+        .failure();
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .arg("inspect")
+        .arg(pack_dir.join("pack.yaml"))
+        .assert()
+        .failure();
+}
+
+#[test]
+fn schema_writes_flow_schema_with_editor_mapping() {
+    let temp = tempdir().expect("temp dir");
+    let out = temp.path().join("ygtc.schema.json");
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["--json", "schema", "--what", "flow", "--out"])
+        .arg(&out)
+        .args(["--url", "https://example.com/ygtc.schema.json"])
+        .assert()
+        .success();
+    let mapping: Value = serde_json::from_slice(&assert.get_output().stdout).expect("mapping json");
+    assert_eq!(
+        mapping["modeline"],
+        "# yaml-language-server: $schema=https://example.com/ygtc.schema.json"
+    );
+    assert_eq!(
+        mapping["yaml_schemas"]["https://example.com/ygtc.schema.json"][0],
+        "*.ygtc"
+    );
+    assert_eq!(mapping["file_associations"]["*.ygtc"], "yaml");
+
+    let schema: Value =
+        serde_json::from_slice(&fs::read(&out).expect("read schema")).expect("schema json");
+    assert!(schema["properties"]["type"]["oneOf"].is_array());
+    assert!(schema["definitions"]["node"]["properties"]["doc"]["description"].is_string());
+
+    let printed = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["schema", "--what", "pack"])
+        .assert()
+        .success();
+    let pack: Value = serde_json::from_slice(&printed.get_output().stdout).expect("pack schema");
+    assert!(pack["properties"]["flow_files"].is_object());
+}
+
+#[test]
+fn schema_out_dir_writes_every_schema() {
+    let temp = tempdir().expect("temp dir");
+    let dir = temp.path().join("schemas");
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["--json", "schema", "--out-dir"])
+        .arg(&dir)
+        .assert()
+        .success();
+    let summary: Value = serde_json::from_slice(&assert.get_output().stdout).expect("summary json");
+    let kinds: Vec<&str> = summary["schemas"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|schema| schema["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        kinds,
+        ["flow", "pack", "events", "messaging", "repo", "manifest"]
+    );
+    let pack_schema = dir
+        .join("pack.v1.schema.json")
+        .to_string_lossy()
+        .replace('\\', "/");
+    assert_eq!(
+        summary["yaml_schemas"][pack_schema.as_str()][0],
+        "pack.yaml"
+    );
+    assert_eq!(summary["file_associations"]["*.ygtc"], "yaml");
+
+    let manifest: Value = serde_json::from_slice(
+        &fs::read(dir.join("manifest.v1.schema.json")).expect("read manifest schema"),
+    )
+    .expect("manifest schema json");
+    assert!(manifest["properties"]["meta"].is_object());
+    let messaging: Value = serde_json::from_slice(
+        &fs::read(dir.join("messaging.v1.schema.json")).expect("read messaging schema"),
+    )
+    .expect("messaging schema json");
+    assert!(messaging["properties"]["adapters"].is_object());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["schema", "--what", "pack", "--out-dir"])
+        .arg(&dir)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn cache_import_requires_a_trusted_key() {
+    let temp = tempdir().expect("temp dir");
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .env_remove("PACKC_TRUST_STORE")
+        .args(["cache", "import"])
+        .arg(temp.path().join("build-cache.zip"))
+        .arg("--in")
+        .arg(temp.path())
+        .args(["--log", "warn"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("no trusted keys"), "{stderr}");
+}
+
+#[test]
+fn fuzz_lists_targets_and_requires_a_fuzz_crate() {
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["--json", "fuzz", "--list"])
+        .assert()
+        .success();
+    let targets: Value = serde_json::from_slice(&assert.get_output().stdout).expect("json");
+    let names: Vec<&str> = targets
+        .as_array()
+        .expect("targets")
+        .iter()
+        .map(|target| target["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["component_abi", "manifest_cbor", "gtpack_archive"]);
+
+    let temp = tempdir().expect("temp dir");
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .current_dir(temp.path())
+        .args(["fuzz", "component_abi", "--duration", "1"])
+        .output()
+        .expect("run packc fuzz");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--fuzz-dir"));
+}
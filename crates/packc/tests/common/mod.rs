@@ -0,0 +1,161 @@
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use greentic_pack::builder::{
+    ComponentArtifact, FlowBundle, PACK_VERSION, PackBuilder, PackMeta, Provenance, Signing,
+    WasmSource,
+};
+use semver::Version;
+use serde_json::{Map, Value, json};
+use walkdir::WalkDir;
+
+/// Builds a small dev-signed `.gtpack` with a single flow and component.
+pub fn build_sample_gtpack(dir: &Path, pack_id: &str, description: &str) -> PathBuf {
+    build_gtpack_with(dir, sample_meta(pack_id, description), |builder| builder)
+}
+
+/// Builds a `.gtpack` from `meta`, letting the caller extend the builder first.
+pub fn build_gtpack_with(
+    dir: &Path,
+    meta: PackMeta,
+    customize: impl FnOnce(PackBuilder) -> PackBuilder,
+) -> PathBuf {
+    fs::create_dir_all(dir).expect("create output dir");
+    let wasm = dir.join(format!("{}.wasm", meta.pack_id));
+    fs::write(&wasm, [0x00u8, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]).expect("write wasm");
+    let out = dir.join(format!("{}.gtpack", meta.pack_id));
+    let builder = PackBuilder::new(meta)
+        .with_flow(sample_flow("main"))
+        .with_component(ComponentArtifact {
+            name: "pack_component".into(),
+            version: Version::parse("1.0.0").unwrap(),
+            wasm: WasmSource::Path(wasm),
+            schema_json: None,
+            manifest_json: None,
+            capabilities: None,
+            world: None,
+            hash_blake3: None,
+        })
+        .with_signing(Signing::Dev)
+        .with_provenance(Provenance {
+            builder: "packc@test".into(),
+            git_commit: None,
+            git_repo: None,
+            toolchain: None,
+            built_at_utc: "2025-01-01T00:00:00Z".into(),
+            host: None,
+            notes: None,
+            profile_blake3: None,
+        });
+    customize(builder).build(&out).expect("build gtpack");
+    out
+}
+
+pub fn sample_meta(pack_id: &str, description: &str) -> PackMeta {
+    PackMeta {
+        pack_version: PACK_VERSION,
+        pack_id: pack_id.into(),
+        version: Version::parse("0.1.0").unwrap(),
+        name: pack_id.into(),
+        kind: None,
+        description: Some(description.into()),
+        authors: vec!["Greentic".into()],
+        license: None,
+        homepage: None,
+        support: None,
+        vendor: None,
+        tags: Vec::new(),
+        categories: Vec::new(),
+        imports: Vec::new(),
+        entry_flows: vec!["main".into()],
+        created_at_utc: "2025-01-01T00:00:00Z".into(),
+        events: None,
+        repo: None,
+        messaging: None,
+        interfaces: Vec::new(),
+        media: None,
+        config: None,
+        config_schema: None,
+        config_defaults: None,
+        lifecycle: None,
+        quotas: None,
+        limits: None,
+        dispatch: None,
+        qos: None,
+        secrets: None,
+        capabilities: None,
+        metrics: None,
+        schedule: None,
+        classification: None,
+        locales: None,
+        data_handling: None,
+        permissions: None,
+        annotations: Map::new(),
+    }
+}
+
+pub fn sample_flow(id: &str) -> FlowBundle {
+    let flow_json = json!({
+        "id": id,
+        "kind": "messaging",
+        "entry": "start",
+        "nodes": []
+    });
+    FlowBundle {
+        id: id.into(),
+        kind: "messaging".into(),
+        entry: "start".into(),
+        yaml: format!("id: {id}\nentry: start\n"),
+        json: flow_json.clone(),
+        hash_blake3: blake3::hash(&serde_json::to_vec(&flow_json).unwrap())
+            .to_hex()
+            .to_string(),
+        nodes: Vec::new(),
+    }
+}
+
+/// Runs `packc --json` with `args`, keeping logs out of the output.
+pub fn packc(args: &[&str]) -> Output {
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["--json", "--log", "warn"])
+        .args(args)
+        .output()
+        .expect("run packc")
+}
+
+/// Parses the stdout of a run that must have succeeded.
+pub fn stdout_json(output: &Output) -> Value {
+    assert!(
+        output.status.success(),
+        "packc failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("json")
+}
+
+/// The workspace root, which holds `examples/`.
+pub fn workspace_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("..")
+}
+
+/// Copies the weather demo pack into `target`.
+pub fn copy_example_pack(target: &Path) {
+    let source = workspace_root().join("examples/weather-demo");
+    for entry in WalkDir::new(&source)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+    {
+        let relative = entry.path().strip_prefix(&source).expect("strip prefix");
+        let destination = target.join(relative);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).expect("create parent dirs");
+        }
+        fs::copy(entry.path(), &destination).expect("copy fixture file");
+    }
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use std::process::Command;
+
+use semver::Version;
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn ask_flow(days: &str) -> greentic_pack::builder::FlowBundle {
+    let mut flow = common::sample_flow("ask");
+    flow.yaml = format!(
+        "id: ask\nentry: start\nparameters:\n  days: {days}\nnodes:\n  start:\n    qa.process:\n      questions:\n        - id: city\n"
+    );
+    flow
+}
+
+#[test]
+fn compat_flags_breaking_changes_and_an_insufficient_bump() {
+    let temp = tempdir().expect("temp dir");
+    let old = common::build_gtpack_with(
+        &temp.path().join("old"),
+        common::sample_meta("demo.compat", "Compat demo"),
+        |builder| {
+            builder
+                .with_flow(ask_flow("3"))
+                .with_flow(common::sample_flow("legacy"))
+        },
+    );
+    let mut meta = common::sample_meta("demo.compat", "Compat demo");
+    meta.version = Version::parse("0.1.1").unwrap();
+    let new = common::build_gtpack_with(&temp.path().join("new"), meta, |builder| {
+        builder.with_flow(ask_flow("\"three\""))
+    });
+
+    let run = |strict: bool| {
+        let mut command = Command::new(assert_cmd::cargo::cargo_bin!("packc"));
+        command
+            .args(["compat", "--old"])
+            .arg(&old)
+            .arg("--new")
+            .arg(&new)
+            .args(["--json", "--log", "warn"]);
+        if strict {
+            command.arg("--strict");
+        }
+        command.output().expect("run packc compat")
+    };
+
+    let output = run(false);
+    assert!(
+        output.status.success(),
+        "packc compat failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: Value = serde_json::from_slice(&output.stdout).expect("compat json");
+    assert_eq!(report["format"], "greentic-pack-compat-v1");
+    assert_eq!(report["breaking"], true);
+    assert_eq!(report["suggested_bump"], "major");
+    assert_eq!(report["suggested_version"], "0.2.0");
+    assert_eq!(report["declared_bump"], "minor");
+    assert_eq!(report["sufficient"], false);
+
+    let changes = report["changes"].as_array().unwrap();
+    let find = |subject: &str| {
+        changes
+            .iter()
+            .find(|change| change["subject"] == subject)
+            .unwrap_or_else(|| panic!("no change for {subject}: {changes:#?}"))
+    };
+    assert_eq!(find("legacy")["area"], "flows");
+    assert_eq!(find("legacy")["bump"], "major");
+    assert_eq!(
+        find("ask:parameters.days")["message"],
+        "type changed from integer to string"
+    );
+    assert!(
+        changes
+            .iter()
+            .all(|change| change["subject"] != "ask:in.city"),
+        "unchanged inputs are not reported"
+    );
+
+    let strict = run(true);
+    assert!(!strict.status.success());
+    assert!(String::from_utf8_lossy(&strict.stderr).contains("need a major release"));
+}
+
+#[test]
+fn compat_matrix_grades_each_host() {
+    let temp = tempdir().expect("temp dir");
+    let mut meta = common::sample_meta("demo.matrix", "Matrix demo");
+    meta.imports.push(greentic_pack::builder::ImportRef {
+        pack_id: "greentic:host-import/email@0.6".into(),
+        version_req: "*".into(),
+        optional: false,
+    });
+    meta.annotations.insert(
+        "imports_resolved".into(),
+        serde_json::json!(["greentic:host-import/email@0.6.0"]),
+    );
+    let gtpack = common::build_gtpack_with(&temp.path().join("pack"), meta, |builder| {
+        let mut notify = common::sample_flow("notify");
+        notify.yaml =
+            "id: notify\nentry: send\nnodes:\n  send:\n    email.send:\n      to: ops\n".into();
+        builder.with_flow(notify)
+    });
+    let hosts = temp.path().join("hosts.yaml");
+    std::fs::write(
+        &hosts,
+        r#"hosts:
+  - name: runner-0.6
+    pack_version: 1
+    interfaces:
+      greentic:host-import: [0.4.0, 0.6.1]
+  - name: runner-0.4
+    pack_version: 1
+    interfaces:
+      greentic:host-import: [0.4.0]
+  - name: runner-0.1
+    pack_version: 0
+"#,
+    )
+    .unwrap();
+
+    let run = |fail_on: &str| {
+        Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+            .args(["compat-matrix", "--in"])
+            .arg(&gtpack)
+            .arg("--hosts")
+            .arg(&hosts)
+            .arg("--markdown")
+            .arg(temp.path().join("matrix.md"))
+            .args(["--fail-on", fail_on, "--json", "--log", "warn"])
+            .output()
+            .expect("run packc compat-matrix")
+    };
+    let output = run("incompatible");
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("incompatible or worse on runner-0.1"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let matrix: Value = serde_json::from_slice(&output.stdout).expect("matrix json");
+    assert_eq!(matrix["format"], "greentic-pack-compat-matrix-v1");
+    assert_eq!(matrix["min_reader_version"], 1);
+    assert_eq!(
+        matrix["interfaces"],
+        serde_json::json!(["greentic:host-import/email@0.6.0"])
+    );
+    let statuses: Vec<&str> = matrix["hosts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|host| host["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(statuses, ["compatible", "degraded", "incompatible"]);
+    assert_eq!(
+        matrix["hosts"][1]["disabled_flows"],
+        serde_json::json!(["notify"])
+    );
+    let markdown = std::fs::read_to_string(temp.path().join("matrix.md")).unwrap();
+    assert!(markdown.contains("| runner-0.4 | degraded |"), "{markdown}");
+}
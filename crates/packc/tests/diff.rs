@@ -0,0 +1,148 @@
+mod common;
+
+use std::process::Command;
+
+use greentic_pack::builder::ImportRef;
+use semver::Version;
+use serde_json::Value;
+use tempfile::tempdir;
+
+#[test]
+fn diff_ranks_new_capabilities_and_imports_as_high() {
+    let temp = tempdir().expect("temp dir");
+    let old = common::build_sample_gtpack(&temp.path().join("old"), "demo.diff", "Diff demo");
+
+    let mut meta = common::sample_meta("demo.diff", "Diff demo");
+    meta.version = Version::parse("0.2.0").unwrap();
+    meta.imports.push(ImportRef {
+        pack_id: "email.send".into(),
+        version_req: "^1".into(),
+        optional: false,
+    });
+    let new = common::build_gtpack_with(&temp.path().join("new"), meta, |builder| {
+        let mut notify = common::sample_flow("notify");
+        notify.yaml = "id: notify\nentry: start\nnodes:\n  start:\n    http.fetch:\n      target: email.send\n".into();
+        builder.with_flow(notify)
+    });
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["diff", "--from"])
+        .arg(&old)
+        .arg("--to")
+        .arg(&new)
+        .args(["--json", "--log", "warn"])
+        .output()
+        .expect("run packc diff");
+    assert!(
+        output.status.success(),
+        "packc diff failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: Value = serde_json::from_slice(&output.stdout).expect("diff json");
+    assert_eq!(report["highest_severity"], "high");
+
+    let changes = report["changes"].as_array().unwrap();
+    let find = |category: &str, subject: &str| {
+        changes
+            .iter()
+            .find(|change| change["category"] == category && change["subject"] == subject)
+            .unwrap_or_else(|| panic!("no {category} change for {subject}: {changes:#?}"))
+    };
+    assert_eq!(find("imports", "email.send")["severity"], "high");
+    assert_eq!(find("capabilities", "http.fetch")["severity"], "high");
+    assert_eq!(find("flows", "notify")["severity"], "low");
+    assert_eq!(find("metadata", "version")["severity"], "info");
+
+    let gated = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["diff", "--from"])
+        .arg(&old)
+        .arg("--to")
+        .arg(&new)
+        .args(["--fail-on", "high", "--log", "warn"])
+        .output()
+        .expect("run packc diff");
+    assert!(!gated.status.success());
+    assert!(String::from_utf8_lossy(&gated.stderr).contains("at or above `high` severity"));
+}
+
+#[test]
+fn diff_reports_flow_template_and_component_digests() {
+    let temp = tempdir().expect("temp dir");
+    let meta = common::sample_meta("demo.digests", "Digest demo");
+    let old = common::build_gtpack_with(&temp.path().join("old"), meta.clone(), |builder| {
+        builder
+            .with_flow(common::sample_flow("notify"))
+            .with_asset_bytes("templates/greeting.hbs", b"Hello {{name}}".to_vec())
+    });
+
+    let helper = temp.path().join("helper.wasm");
+    std::fs::write(&helper, [0x00u8, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]).unwrap();
+    let mut meta = meta;
+    meta.license = Some("MIT".into());
+    let new = common::build_gtpack_with(&temp.path().join("new"), meta, |builder| {
+        let mut notify = common::sample_flow("notify");
+        notify.yaml = "id: notify\nentry: send\n".into();
+        notify.hash_blake3 = blake3::hash(notify.yaml.as_bytes()).to_hex().to_string();
+        builder
+            .with_flow(notify)
+            .with_asset_bytes("templates/greeting.hbs", b"Hi {{name}}!".to_vec())
+            .with_component_wasm("helper", Version::parse("0.3.0").unwrap(), &helper)
+    });
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["diff", "--from"])
+        .arg(&old)
+        .arg("--to")
+        .arg(&new)
+        .args(["--json", "--log", "warn"])
+        .output()
+        .expect("run packc diff");
+    assert!(
+        output.status.success(),
+        "packc diff failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: Value = serde_json::from_slice(&output.stdout).expect("diff json");
+    let changes = report["changes"].as_array().unwrap();
+    let find = |category: &str, subject: &str| {
+        changes
+            .iter()
+            .find(|change| change["category"] == category && change["subject"] == subject)
+            .unwrap_or_else(|| panic!("no {category} change for {subject}: {changes:#?}"))
+    };
+
+    let flow = find("flows", "notify");
+    assert!(
+        flow["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("flow changed")
+    );
+    assert_ne!(flow["from_digest"], flow["to_digest"]);
+    let template = find("templates", "templates/greeting.hbs");
+    assert_eq!(
+        template["to_digest"],
+        blake3::hash(b"Hi {{name}}!").to_hex().as_str()
+    );
+    let component = find("components", "helper");
+    assert_eq!(component["severity"], "medium");
+    assert!(component["from_digest"].is_null());
+    assert_eq!(find("metadata", "license")["severity"], "medium");
+    assert!(
+        changes.iter().all(|change| change["category"] != "files"
+            || !change["subject"].as_str().unwrap().starts_with("flows/")),
+        "flow files are reported as flows: {changes:#?}"
+    );
+
+    let human = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["diff", "--from"])
+        .arg(&old)
+        .arg("--to")
+        .arg(&new)
+        .args(["--log", "warn"])
+        .output()
+        .expect("run packc diff");
+    let stdout = String::from_utf8_lossy(&human.stdout);
+    let short = &template["from_digest"].as_str().unwrap()[..12];
+    assert!(stdout.contains(short), "digests are shown: {stdout}");
+}
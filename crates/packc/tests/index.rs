@@ -0,0 +1,187 @@
+mod common;
+
+use serde_json::Value;
+use std::fs;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn run_index(dir: &std::path::Path, out: &std::path::Path) -> Value {
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args([
+            "index",
+            "--dir",
+            dir.to_str().unwrap(),
+            "--out",
+            out.to_str().unwrap(),
+            "--json",
+            "--log",
+            "warn",
+        ])
+        .output()
+        .expect("run packc index");
+    assert!(
+        output.status.success(),
+        "packc index failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("index summary json")
+}
+
+#[test]
+fn index_builds_inverted_terms_and_updates_incrementally() {
+    let temp = tempdir().expect("temp dir");
+    let dist = temp.path().join("dist");
+    common::build_sample_gtpack(&dist, "demo.weather", "Weather forecasts");
+    common::build_sample_gtpack(&dist, "demo.billing", "Invoices and billing");
+    let out = temp.path().join("search-index.json");
+
+    let summary = run_index(&dist, &out);
+    assert_eq!(summary["stats"]["indexed"], 2);
+
+    let index: Value = serde_json::from_slice(&fs::read(&out).unwrap()).unwrap();
+    assert_eq!(index["format"], "greentic-pack-index-v1");
+    assert_eq!(index["terms"]["weather"][0], "demo.weather@0.1.0");
+    assert_eq!(index["terms"]["main"].as_array().unwrap().len(), 2);
+
+    fs::remove_file(dist.join("demo.billing.gtpack")).unwrap();
+    common::build_sample_gtpack(&dist, "demo.search", "Semantic search");
+    let summary = run_index(&dist, &out);
+    assert_eq!(summary["stats"]["reused"], 1);
+    assert_eq!(summary["stats"]["indexed"], 1);
+    assert_eq!(summary["stats"]["removed"], 1);
+
+    let index: Value = serde_json::from_slice(&fs::read(&out).unwrap()).unwrap();
+    assert!(index["terms"].get("billing").is_none());
+    assert_eq!(index["terms"]["semantic"][0], "demo.search@0.1.0");
+}
+
+#[test]
+fn index_filters_by_tag_and_category() {
+    let temp = tempdir().expect("temp dir");
+    let dist = temp.path().join("dist");
+    let mut meta = common::sample_meta("demo.weather", "Weather forecasts");
+    meta.tags = vec!["weather".into()];
+    meta.categories = vec!["messaging".into()];
+    common::build_gtpack_with(&dist, meta, |builder| builder);
+    common::build_sample_gtpack(&dist, "demo.billing", "Invoices and billing");
+    let out = temp.path().join("search-index.json");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args([
+            "index",
+            "--dir",
+            dist.to_str().unwrap(),
+            "--out",
+            out.to_str().unwrap(),
+            "--tag",
+            "weather",
+            "--category",
+            "messaging",
+            "--log",
+            "warn",
+        ])
+        .output()
+        .expect("run packc index");
+    assert!(output.status.success());
+
+    let index: Value = serde_json::from_slice(&fs::read(&out).unwrap()).unwrap();
+    let packs = index["packs"].as_array().unwrap();
+    assert_eq!(packs.len(), 1);
+    assert_eq!(packs[0]["categories"][0], "messaging");
+    assert_eq!(index["terms"]["messaging"][0], "demo.weather@0.1.0");
+}
+
+fn run_advisory(args: &[&str]) -> std::process::Output {
+    Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .arg("advisory")
+        .args(args)
+        .args(["--json", "--log", "warn"])
+        .output()
+        .expect("run packc advisory")
+}
+
+#[test]
+fn advisories_survive_reindexing_and_flag_installed_packs() {
+    let temp = tempdir().expect("temp dir");
+    let dist = temp.path().join("dist");
+    common::build_sample_gtpack(&dist, "demo.weather", "Weather forecasts");
+    common::build_sample_gtpack(&dist, "demo.billing", "Invoices and billing");
+    let out = temp.path().join("search-index.json");
+    run_index(&dist, &out);
+    let index_arg = out.to_str().unwrap();
+
+    let added = run_advisory(&[
+        "add",
+        "--index",
+        index_arg,
+        "--id",
+        "GPA-2026-0001",
+        "--pack-id",
+        "demo.weather",
+        "--versions",
+        "<0.2.0",
+        "--severity",
+        "high",
+        "--description",
+        "forecast template leaks the API key",
+        "--superseded-by",
+        "0.2.0",
+    ]);
+    assert!(
+        added.status.success(),
+        "{}",
+        String::from_utf8_lossy(&added.stderr)
+    );
+    let summary: Value = serde_json::from_slice(&added.stdout).unwrap();
+    assert_eq!(summary["indexed_packs_affected"][0], "demo.weather@0.1.0");
+
+    let duplicate = run_advisory(&[
+        "add",
+        "--index",
+        index_arg,
+        "--id",
+        "GPA-2026-0001",
+        "--pack-id",
+        "demo.billing",
+        "--versions",
+        "*",
+        "--severity",
+        "low",
+        "--description",
+        "duplicate",
+    ]);
+    assert!(!duplicate.status.success());
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["index", "--dir", dist.to_str().unwrap(), "--out", index_arg])
+        .args(["--full", "--log", "warn"])
+        .output()
+        .expect("run packc index");
+    assert!(output.status.success());
+    let index: Value = serde_json::from_slice(&fs::read(&out).unwrap()).unwrap();
+    assert_eq!(index["advisories"][0]["id"], "GPA-2026-0001");
+    assert_eq!(index["advisories"][0]["superseded_by"], "0.2.0");
+
+    let checked = run_advisory(&["check", dist.to_str().unwrap(), "--index", index_arg]);
+    assert!(!checked.status.success());
+    let report: Value = serde_json::from_slice(&checked.stdout).unwrap();
+    assert_eq!(report["status"], "failed");
+    let affected = report["affected"].as_array().unwrap();
+    assert_eq!(affected.len(), 1, "{report}");
+    assert_eq!(affected[0]["pack_id"], "demo.weather");
+    assert_eq!(affected[0]["advisories"][0]["severity"], "high");
+
+    let tolerated = run_advisory(&[
+        "check",
+        dist.to_str().unwrap(),
+        "--index",
+        index_arg,
+        "--fail-on",
+        "critical",
+    ]);
+    assert!(
+        tolerated.status.success(),
+        "{}",
+        String::from_utf8_lossy(&tolerated.stderr)
+    );
+}
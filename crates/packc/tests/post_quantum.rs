@@ -0,0 +1,132 @@
+#![forbid(unsafe_code)]
+#![cfg(feature = "ml-dsa")]
+
+use std::fs;
+use std::path::Path;
+
+use ed25519_dalek::SigningKey;
+use ed25519_dalek::pkcs8::{EncodePrivateKey, EncodePublicKey};
+use packc::signing::generate_ml_dsa_keypair;
+use packc::{
+    DigestAlgorithm, PackSignature, VerificationError, VerifyOptions, manifest,
+    sign_pack_dir_with_keys, verify_pack_dir,
+};
+use pkcs8::LineEnding;
+use tempfile::tempdir;
+
+fn write_file(path: &Path, contents: &str) {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).expect("create parent");
+    }
+    fs::write(path, contents).expect("write file");
+}
+
+fn verification_error(err: anyhow::Error) -> VerificationError {
+    err.downcast::<VerificationError>()
+        .expect("verification error")
+}
+
+#[test]
+fn hybrid_signature_requires_both_keys() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path();
+    write_file(&pack_dir.join("pack.toml"), "[package]\nname = \"demo\"\n");
+    write_file(&pack_dir.join("flows/main.flow"), "start: node");
+
+    let ed_key = SigningKey::from_bytes(&[0x42; 32]);
+    let ed_private = ed_key.to_pkcs8_pem(LineEnding::LF).expect("ed25519 pem");
+    let ed_public = ed_key
+        .verifying_key()
+        .to_public_key_pem(LineEnding::LF)
+        .expect("ed25519 public pem");
+    let (pq_private, pq_public) = generate_ml_dsa_keypair().expect("ml-dsa keypair");
+
+    let signatures = sign_pack_dir_with_keys(
+        pack_dir,
+        &[ed_private.as_str(), pq_private.as_str()],
+        DigestAlgorithm::Sha512,
+        None,
+    )
+    .expect("hybrid sign");
+    assert_eq!(signatures.len(), 2);
+    assert_eq!(signatures[0].alg, PackSignature::ED25519);
+    assert_eq!(signatures[1].alg, PackSignature::ML_DSA_65);
+    assert_eq!(
+        manifest::read_signature(pack_dir).unwrap().unwrap(),
+        signatures[0],
+        "Ed25519 stays in the primary block for older verifiers"
+    );
+    assert_eq!(manifest::read_signatures(pack_dir).unwrap(), signatures);
+
+    let both = [pq_public.as_str()];
+    let opts = VerifyOptions {
+        public_key_pem: Some(ed_public.as_str()),
+        additional_public_keys_pem: &both,
+        require_post_quantum: true,
+        ..Default::default()
+    };
+    let primary = verify_pack_dir(pack_dir, opts).expect("hybrid verifies");
+    assert_eq!(primary.alg, PackSignature::ED25519);
+
+    let ed_only = VerifyOptions {
+        public_key_pem: Some(ed_public.as_str()),
+        ..Default::default()
+    };
+    let err = verification_error(verify_pack_dir(pack_dir, ed_only).unwrap_err());
+    assert!(
+        matches!(&err, VerificationError::KeyNotFound { key_id } if *key_id == signatures[1].key_id),
+        "{err}"
+    );
+
+    let mut tampered = signatures.clone();
+    tampered[1].sig = tampered[0].sig.clone();
+    manifest::write_signatures(pack_dir, &tampered, None).expect("rewrite signatures");
+    let err = verification_error(verify_pack_dir(pack_dir, opts).unwrap_err());
+    assert!(
+        matches!(err, VerificationError::SignatureLength(_)),
+        "{err}"
+    );
+}
+
+#[test]
+fn ml_dsa_only_signature_satisfies_pq_policy() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path();
+    write_file(&pack_dir.join("pack.toml"), "[package]\nname = \"demo\"\n");
+
+    let (pq_private, pq_public) = generate_ml_dsa_keypair().expect("ml-dsa keypair");
+    sign_pack_dir_with_keys(
+        pack_dir,
+        &[pq_private.as_str()],
+        DigestAlgorithm::Sha256,
+        None,
+    )
+    .expect("sign");
+
+    let signature = verify_pack_dir(
+        pack_dir,
+        VerifyOptions {
+            public_key_pem: Some(pq_public.as_str()),
+            require_post_quantum: true,
+            ..Default::default()
+        },
+    )
+    .expect("ml-dsa verifies");
+    assert!(signature.is_post_quantum());
+
+    write_file(&pack_dir.join("flows/extra.flow"), "start: other");
+    let err = verification_error(
+        verify_pack_dir(
+            pack_dir,
+            VerifyOptions {
+                public_key_pem: Some(pq_public.as_str()),
+                ..Default::default()
+            },
+        )
+        .unwrap_err(),
+    );
+    assert!(
+        matches!(err, VerificationError::DigestMismatch { .. }),
+        "{err}"
+    );
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use greentic_pack::{SigningPolicy, open_pack};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::tempdir;
+
+const EPOCH: &str = "1700000000";
+
+/// Builds the weather demo with a stand-in component so no wasm toolchain is needed.
+fn build_archive(base: &Path, name: &str) -> PathBuf {
+    build_archive_with(base, name, &[])
+}
+
+fn build_archive_with(base: &Path, name: &str, extra_args: &[&str]) -> PathBuf {
+    let pack_dir = base.join(format!("{name}-src"));
+    common::copy_example_pack(&pack_dir);
+    let wasm = base.join("prebuilt.wasm");
+    fs::write(&wasm, [0x00u8, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]).expect("write wasm");
+    let dist = base.join(format!("{name}-dist"));
+    let gtpack = base.join(format!("{name}.gtpack"));
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .env("SOURCE_DATE_EPOCH", EPOCH)
+        .args(["build", "--in"])
+        .arg(&pack_dir)
+        .arg("--out")
+        .arg(dist.join("pack.wasm"))
+        .arg("--manifest")
+        .arg(dist.join("manifest.cbor"))
+        .arg("--sbom")
+        .arg(dist.join("sbom.cdx.json"))
+        .arg("--component-wasm")
+        .arg(&wasm)
+        .arg("--gtpack-out")
+        .arg(&gtpack)
+        .args(extra_args)
+        .args(["--log", "warn"])
+        .output()
+        .expect("run packc build");
+    assert!(
+        output.status.success(),
+        "packc build failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    gtpack
+}
+
+#[test]
+fn reproducible_builds_share_a_content_digest() {
+    let temp = tempdir().expect("temp dir");
+    let first = build_archive(temp.path(), "first");
+    let second = build_archive(temp.path(), "second");
+    assert_eq!(
+        fs::read(&first).unwrap(),
+        fs::read(&second).unwrap(),
+        "archives are byte-identical"
+    );
+
+    let first = open_pack(&first, SigningPolicy::DevOk).expect("first opens");
+    let second = open_pack(&second, SigningPolicy::DevOk).expect("second opens");
+    assert_eq!(first.manifest.meta.created_at_utc, "2023-11-14T22:13:20Z");
+    assert_eq!(first.content_digest(), second.content_digest());
+    assert!(first.file("pack.yaml").is_some(), "source spec is embedded");
+}
+
+#[test]
+fn timestamp_flag_overrides_source_date_epoch() {
+    let temp = tempdir().expect("temp dir");
+    let args = ["--timestamp", "2025-01-01T00:00:00Z"];
+    let first = build_archive_with(temp.path(), "first", &args);
+    let second = build_archive_with(temp.path(), "second", &args);
+    assert_eq!(fs::read(&first).unwrap(), fs::read(&second).unwrap());
+
+    let load = open_pack(&first, SigningPolicy::DevOk).expect("archive opens");
+    assert_eq!(load.manifest.meta.created_at_utc, "2025-01-01T00:00:00Z");
+    let provenance: Value = serde_json::from_slice(load.file("provenance.json").unwrap()).unwrap();
+    assert_eq!(provenance["built_at_utc"], "2025-01-01T00:00:00Z");
+    let sbom: Value = serde_json::from_slice(
+        &fs::read(temp.path().join("first-dist").join("sbom.cdx.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(sbom["metadata"]["timestamp"], "2025-01-01T00:00:00Z");
+}
+
+#[test]
+fn rebuild_from_archive_round_trips() {
+    let temp = tempdir().expect("temp dir");
+    let original = build_archive(temp.path(), "original");
+    let rebuilt = temp.path().join("rebuilt.gtpack");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .env_remove("SOURCE_DATE_EPOCH")
+        .args(["rebuild", "--from"])
+        .arg(&original)
+        .arg("--out")
+        .arg(&rebuilt)
+        .args(["--check", "--json", "--log", "warn"])
+        .output()
+        .expect("run packc rebuild");
+    assert!(
+        output.status.success(),
+        "packc rebuild failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let summary: Value = serde_json::from_slice(&output.stdout).expect("rebuild json");
+    assert_eq!(summary["equivalent"], true);
+    assert_eq!(summary["original_digest"], summary["rebuilt_digest"]);
+
+    let before = open_pack(&original, SigningPolicy::DevOk).expect("original opens");
+    let after = open_pack(&rebuilt, SigningPolicy::DevOk).expect("rebuilt opens");
+    assert_eq!(
+        before.manifest.meta.created_at_utc,
+        after.manifest.meta.created_at_utc
+    );
+    assert_eq!(
+        before.file("provenance.json"),
+        after.file("provenance.json")
+    );
+    assert_eq!(before.file("pack.yaml"), after.file("pack.yaml"));
+    let component = |load: &greentic_pack::PackLoad| {
+        load.manifest
+            .components
+            .iter()
+            .map(|c| (c.name.clone(), c.version.to_string(), c.hash_blake3.clone()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(component(&before), component(&after));
+}
@@ -0,0 +1,329 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use greentic_pack::SigningPolicy;
+use greentic_pack::atomic::Durability;
+use greentic_pack::builder::ImportRef;
+use greentic_pack::closure::open_closure;
+use semver::Version;
+use serde_json::Value;
+use tempfile::tempdir;
+
+/// Bytes that deflate cannot shrink, standing in for a large component.
+fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+fn build(dir: &Path, version: &str, asset: Vec<u8>) -> std::path::PathBuf {
+    let mut meta = common::sample_meta("demo.chunks", "Chunked transfer demo");
+    meta.version = Version::parse(version).unwrap();
+    common::build_gtpack_with(dir, meta, |builder| {
+        builder.with_asset_bytes("templates/model.bin", asset)
+    })
+}
+
+fn packc(args: &[&str], extra: &[&Path]) -> Value {
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["--json", "--log", "warn"])
+        .args(args)
+        .args(extra)
+        .output()
+        .expect("run packc");
+    assert!(
+        output.status.success(),
+        "packc {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("json")
+}
+
+#[test]
+fn publish_and_pull_move_only_changed_chunks() {
+    let temp = tempdir().expect("temp dir");
+    let registry = temp.path().join("registry");
+    let cache = temp.path().join("cache");
+    let asset = noise(3 * 1024 * 1024);
+    let mut changed = asset.clone();
+    changed[1_500_000..1_500_064].copy_from_slice(&[0xAA; 64]);
+    let v1 = build(&temp.path().join("v1"), "0.1.0", asset);
+    let v2 = build(&temp.path().join("v2"), "0.2.0", changed);
+
+    let publish = |gtpack: &Path| {
+        packc(
+            &["publish"],
+            &[
+                Path::new("--in"),
+                gtpack,
+                Path::new("--registry"),
+                &registry,
+            ],
+        )
+    };
+    let first = publish(&v1);
+    assert_eq!(first["status"], "published");
+    assert_eq!(first["transferred_chunks"], first["chunks"]);
+    let second = publish(&v2);
+    let uploaded = second["transferred_bytes"].as_u64().unwrap();
+    assert!(
+        uploaded * 4 < second["size"].as_u64().unwrap(),
+        "uploaded {uploaded} bytes: {second}"
+    );
+    assert_eq!(publish(&v2)["status"], "unchanged");
+    assert!(
+        registry
+            .join("packs/demo.chunks/0.2.0.gtpack.chunks.json")
+            .exists()
+    );
+
+    let out = temp.path().join("pulled/demo.gtpack");
+    let pull = |pack: &str, cache: &Path| {
+        packc(
+            &["pull", pack],
+            &[
+                Path::new("--registry"),
+                &registry,
+                Path::new("--out"),
+                &out,
+                Path::new("--cache"),
+                cache,
+            ],
+        )
+    };
+    let pulled = pull("demo.chunks@0.1.0", &cache);
+    assert_eq!(pulled["transferred_chunks"], pulled["chunks"]);
+    assert_eq!(fs::read(&out).unwrap(), fs::read(&v1).unwrap());
+
+    // A fresh cache: the shared chunks come from the old archive at --out.
+    let upgraded = pull("demo.chunks@0.2.0", &temp.path().join("fresh-cache"));
+    let downloaded = upgraded["transferred_bytes"].as_u64().unwrap();
+    assert!(
+        downloaded * 4 < upgraded["size"].as_u64().unwrap(),
+        "downloaded {downloaded} bytes: {upgraded}"
+    );
+    assert_eq!(fs::read(&out).unwrap(), fs::read(&v2).unwrap());
+}
+
+#[test]
+fn chunk_cache_stays_within_its_quota_and_evicts_corrupt_chunks() {
+    let temp = tempdir().expect("temp dir");
+    let registry = temp.path().join("registry");
+    let config = temp.path().join("config");
+    fs::create_dir_all(&config).unwrap();
+    fs::write(config.join("packc.toml"), "[cache.chunks]\nmax_mb = 1\n").unwrap();
+    let gtpack = build(&temp.path().join("v1"), "0.1.0", noise(3 * 1024 * 1024));
+
+    let packc = |args: &[&str], extra: &[&Path]| -> Value {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+            .env("PACKC_CONFIG_DIR", &config)
+            .env("XDG_CACHE_HOME", temp.path().join("xdg"))
+            .args(["--json", "--log", "warn"])
+            .args(args)
+            .args(extra)
+            .output()
+            .expect("run packc");
+        assert!(
+            output.status.success(),
+            "packc {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        serde_json::from_slice(&output.stdout).expect("json")
+    };
+    packc(
+        &["publish"],
+        &[
+            Path::new("--in"),
+            &gtpack,
+            Path::new("--registry"),
+            &registry,
+        ],
+    );
+    packc(
+        &["pull", "demo.chunks@0.1.0"],
+        &[
+            Path::new("--registry"),
+            &registry,
+            Path::new("--out"),
+            &temp.path().join("demo.gtpack"),
+        ],
+    );
+
+    let stats = packc(&["cache", "stats"], &[]);
+    let chunks = &stats[0];
+    assert_eq!(chunks["cache"], "chunks");
+    assert_eq!(chunks["quota_bytes"], 1024 * 1024);
+    assert!(chunks["bytes"].as_u64().unwrap() <= 1024 * 1024, "{stats}");
+    assert!(chunks["entries"].as_u64().unwrap() > 0, "{stats}");
+
+    let cache = temp.path().join("xdg/greentic/chunks");
+    let victim = fs::read_dir(&cache)
+        .unwrap()
+        .flat_map(|dir| fs::read_dir(dir.unwrap().path()).unwrap())
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    fs::write(&victim, b"bit rot").unwrap();
+    let verified = packc(&["cache", "verify", "chunks"], &[]);
+    assert_eq!(
+        verified[0]["evicted"],
+        serde_json::json!([victim.display().to_string()])
+    );
+
+    let cleaned = packc(&["cache", "clean", "--all"], &[]);
+    assert!(cleaned[0]["evicted_entries"].as_u64().unwrap() > 0);
+    assert_eq!(packc(&["cache", "stats"], &[])[0]["entries"], 0);
+}
+
+#[test]
+fn yanked_versions_need_allow_yanked_and_show_in_the_index() {
+    let temp = tempdir().expect("temp dir");
+    let registry = temp.path().join("registry");
+    let v1 = build(&temp.path().join("v1"), "0.1.0", noise(64 * 1024));
+    packc(
+        &["publish"],
+        &[Path::new("--in"), &v1, Path::new("--registry"), &registry],
+    );
+
+    let yanked = packc(
+        &[
+            "publish",
+            "--yank",
+            "demo.chunks@0.1.0",
+            "--reason",
+            "leaks tokens",
+        ],
+        &[Path::new("--registry"), &registry],
+    );
+    assert_eq!(yanked["status"], "yanked");
+    assert!(
+        registry
+            .join("packs/demo.chunks/0.1.0.gtpack.yanked.json")
+            .exists()
+    );
+
+    let out = temp.path().join("installed/demo.gtpack");
+    let cache = temp.path().join("cache");
+    let refused = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["pull", "demo.chunks@0.1.0", "--log", "warn"])
+        .args([Path::new("--registry"), &registry, Path::new("--out"), &out])
+        .args([Path::new("--cache"), &cache])
+        .output()
+        .expect("run packc");
+    assert!(!refused.status.success());
+    let stderr = String::from_utf8_lossy(&refused.stderr);
+    assert!(stderr.contains("leaks tokens"), "{stderr}");
+    assert!(!out.exists());
+
+    let pulled = packc(
+        &["pull", "demo.chunks@0.1.0", "--allow-yanked"],
+        &[
+            Path::new("--registry"),
+            &registry,
+            Path::new("--out"),
+            &out,
+            Path::new("--cache"),
+            &cache,
+        ],
+    );
+    assert_eq!(pulled["yanked"]["reason"], "leaks tokens");
+
+    let index_path = temp.path().join("search-index.json");
+    packc(
+        &["index"],
+        &[
+            Path::new("--dir"),
+            &registry.join("packs"),
+            Path::new("--out"),
+            &index_path,
+        ],
+    );
+    let index: Value = serde_json::from_slice(&fs::read(&index_path).unwrap()).unwrap();
+    assert_eq!(index["packs"][0]["yanked"]["reason"], "leaks tokens");
+
+    let checked = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .args(["--json", "advisory", "check", "--log", "warn"])
+        .arg(&out)
+        .arg("--index")
+        .arg(&index_path)
+        .output()
+        .expect("run packc");
+    assert!(!checked.status.success());
+    let report: Value = serde_json::from_slice(&checked.stdout).unwrap();
+    assert_eq!(report["affected"][0]["yanked"]["reason"], "leaks tokens");
+}
+
+#[test]
+fn self_contained_bundles_embed_resolved_dependencies() {
+    let temp = tempdir().expect("temp dir");
+    let registry = temp.path().join("registry");
+    let cache = temp.path().join("cache");
+    for version in ["0.1.0", "0.1.1", "0.2.0"] {
+        let mut meta = common::sample_meta("demo.lib", "Shared flows");
+        meta.version = Version::parse(version).unwrap();
+        let lib = common::build_gtpack_with(&temp.path().join(version), meta, |b| b);
+        packc(
+            &["publish"],
+            &[Path::new("--in"), &lib, Path::new("--registry"), &registry],
+        );
+    }
+    let mut meta = common::sample_meta("demo.app", "Uses the shared flows");
+    meta.imports = vec![
+        ImportRef {
+            pack_id: "demo.lib".into(),
+            version_req: "^0.1".into(),
+            optional: false,
+        },
+        ImportRef {
+            pack_id: "secrets.get".into(),
+            version_req: "*".into(),
+            optional: false,
+        },
+    ];
+    let app = common::build_gtpack_with(&temp.path().join("app"), meta, |b| b);
+
+    let out = temp.path().join("app.gtbundle");
+    let bundled = packc(
+        &["bundle", "--self-contained"],
+        &[
+            Path::new("--in"),
+            &app,
+            Path::new("--registry"),
+            &registry,
+            Path::new("--cache"),
+            &cache,
+            Path::new("--out"),
+            &out,
+        ],
+    );
+    let members: Vec<String> = bundled["closure"]["packs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|member| format!("{}@{}", member["pack_id"], member["version"]).replace('"', ""))
+        .collect();
+    assert_eq!(members, ["demo.app@0.1.0", "demo.lib@0.1.1"]);
+    assert_eq!(
+        bundled["closure"]["host_imports"],
+        serde_json::json!(["secrets.get"])
+    );
+
+    let closure = open_closure(&out, SigningPolicy::DevOk).unwrap();
+    let installed = temp.path().join("installed");
+    closure.install(&installed, Durability::Buffered).unwrap();
+    assert_eq!(
+        fs::read(installed.join("demo.app-0.1.0.gtpack")).unwrap(),
+        fs::read(&app).unwrap()
+    );
+    assert!(installed.join("demo.lib-0.1.1.gtpack").exists());
+}
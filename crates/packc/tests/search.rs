@@ -0,0 +1,127 @@
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use semver::Version;
+use serde_json::{Value, json};
+use tempfile::tempdir;
+
+/// Lays out a static registry index for `demo.weather` with the given
+/// versions and returns its `file://` URL.
+fn index(root: &Path, versions: &[(&str, bool)]) -> String {
+    let pack_dir = root.join("packs/demo.weather");
+    fs::create_dir_all(&pack_dir).unwrap();
+    let mut published = Vec::new();
+    for (version, yanked) in versions {
+        let mut meta = common::sample_meta("demo.weather", "Answers weather questions");
+        meta.version = Version::parse(version).unwrap();
+        let built = common::build_gtpack_with(&root.join("build").join(version), meta, |b| b);
+        let bytes = fs::read(&built).unwrap();
+        fs::write(pack_dir.join(format!("{version}.gtpack")), &bytes).unwrap();
+        published.push(json!({
+            "version": version,
+            "size": bytes.len(),
+            "blake3": blake3::hash(&bytes).to_hex().to_string(),
+            "yanked": yanked,
+        }));
+    }
+    let document = |value: Value| serde_json::to_vec_pretty(&value).unwrap();
+    fs::write(
+        root.join("index.json"),
+        document(json!({
+            "format": "greentic-pack-registry-v1",
+            "packs": [{
+                "pack_id": "demo.weather",
+                "name": "Weather",
+                "description": "Answers weather questions",
+                "latest": "1.1.0",
+                "tags": ["forecast"],
+            }],
+        })),
+    )
+    .unwrap();
+    fs::write(
+        pack_dir.join("index.json"),
+        document(json!({
+            "format": "greentic-pack-registry-v1",
+            "pack_id": "demo.weather",
+            "name": "Weather",
+            "versions": published,
+        })),
+    )
+    .unwrap();
+    format!("file://{}", root.display())
+}
+
+#[test]
+fn search_and_add_resolve_versions_from_an_index() {
+    let temp = tempdir().expect("temp dir");
+    let url = index(
+        &temp.path().join("registry"),
+        &[("1.0.0", false), ("1.1.0", false), ("1.2.0", true)],
+    );
+
+    let found = common::stdout_json(&common::packc(&["search", "forecast", "--index", &url]));
+    assert_eq!(found[0]["pack_id"], "demo.weather");
+    let none = common::stdout_json(&common::packc(&["search", "billing", "--index", &url]));
+    assert_eq!(none, json!([]));
+
+    let out_dir = temp.path().join("deps");
+    let out = out_dir.to_str().unwrap();
+    let added = common::stdout_json(&common::packc(&[
+        "add",
+        "demo.weather@^1",
+        "--index",
+        &url,
+        "--out-dir",
+        out,
+    ]));
+    assert_eq!(added["status"], "added");
+    assert_eq!(added["version"], "1.1.0", "yanked 1.2.0 is skipped");
+    assert!(out_dir.join("demo.weather-1.1.0.gtpack").exists());
+
+    let again = common::stdout_json(&common::packc(&[
+        "add",
+        "demo.weather@^1",
+        "--index",
+        &url,
+        "--out-dir",
+        out,
+    ]));
+    assert_eq!(again["status"], "unchanged");
+
+    let pinned = common::stdout_json(&common::packc(&[
+        "add",
+        "demo.weather@=1.0.0",
+        "--index",
+        &url,
+        "--out-dir",
+        out,
+    ]));
+    assert_eq!(pinned["version"], "1.0.0");
+}
+
+#[test]
+fn add_rejects_archives_that_do_not_match_the_index() {
+    let temp = tempdir().expect("temp dir");
+    let root = temp.path().join("registry");
+    let url = index(&root, &[("1.0.0", false), ("1.1.0", false)]);
+    // A mirror serving another version's bytes under 1.1.0.
+    let archive: PathBuf = root.join("packs/demo.weather/1.1.0.gtpack");
+    fs::copy(root.join("packs/demo.weather/1.0.0.gtpack"), &archive).unwrap();
+
+    let out_dir = temp.path().join("deps");
+    let output = common::packc(&[
+        "add",
+        "demo.weather",
+        "--index",
+        &url,
+        "--out-dir",
+        out_dir.to_str().unwrap(),
+    ]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("registry pins"), "{stderr}");
+    assert!(!out_dir.join("demo.weather-1.1.0.gtpack").exists());
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn get(addr: &str, path: &str) -> (String, String) {
+    get_as(addr, addr, path)
+}
+
+fn get_as(addr: &str, host: &str, path: &str) -> (String, String) {
+    let mut stream = TcpStream::connect(addr).expect("connect to serve-ui");
+    write!(stream, "GET {path} HTTP/1.1\r\nHost: {host}\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .expect("response has a body");
+    (head.to_string(), body.to_string())
+}
+
+/// Starts `packc serve-ui` on a free loopback port and returns its address.
+fn serve(gtpack: &Path) -> (Child, String) {
+    let mut child = Command::new(assert_cmd::cargo::cargo_bin!("packc"))
+        .arg("serve-ui")
+        .arg(gtpack)
+        .args(["--listen", "127.0.0.1:0", "--json", "--log", "warn"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("spawn packc serve-ui");
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .expect("read the url");
+    let started: Value = serde_json::from_str(&line).expect("startup json");
+    let url = started["url"].as_str().unwrap();
+    let addr = url
+        .trim_start_matches("http://")
+        .trim_end_matches('/')
+        .to_string();
+    (child, addr)
+}
+
+#[test]
+fn serve_ui_serves_the_page_and_the_pack_view() {
+    let temp = tempdir().expect("temp dir");
+    let gtpack = common::build_sample_gtpack(temp.path(), "demo.ui", "UI demo");
+    let (mut child, addr) = serve(&gtpack);
+
+    let (head, page) = get(&addr, "/");
+    assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+    assert!(page.contains("/api/pack"));
+    assert!(
+        !page.contains("<script src"),
+        "the page loads no external assets"
+    );
+
+    let (head, body) = get(&addr, "/api/pack");
+    assert!(head.contains("application/json"), "{head}");
+    let view: Value = serde_json::from_str(&body).expect("pack view json");
+    assert_eq!(view["meta"]["pack_id"], "demo.ui");
+    assert_eq!(view["inspection"]["signature"]["status"], "verified");
+    assert_eq!(view["flows"][0]["flow"], "main");
+    assert!(
+        view["sbom"]["files"]
+            .as_array()
+            .is_some_and(|f| !f.is_empty())
+    );
+    assert!(
+        view["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|entry| entry["path"] == "manifest.cbor")
+    );
+
+    let (head, _) = get(&addr, "/missing");
+    assert!(head.starts_with("HTTP/1.1 404"), "{head}");
+
+    child.kill().expect("stop serve-ui");
+    let _ = child.wait();
+}
+
+#[test]
+fn serve_ui_rejects_foreign_host_headers() {
+    let temp = tempdir().expect("temp dir");
+    let gtpack = common::build_sample_gtpack(temp.path(), "demo.ui", "UI demo");
+    let (mut child, addr) = serve(&gtpack);
+    let port = addr.rsplit_once(':').unwrap().1;
+
+    let (head, body) = get_as(&addr, &format!("attacker.example:{port}"), "/api/pack");
+    assert!(head.starts_with("HTTP/1.1 403"), "{head}");
+    assert!(!body.contains("demo.ui"));
+    let (head, _) = get_as(&addr, "localhost:1", "/");
+    assert!(head.starts_with("HTTP/1.1 403"), "{head}");
+    let (head, _) = get_as(&addr, &format!("localhost:{port}"), "/api/pack");
+    assert!(head.starts_with("HTTP/1.1 200"), "{head}");
+
+    child.kill().expect("stop serve-ui");
+    let _ = child.wait();
+}
@@ -5,9 +5,14 @@ use std::path::Path;
 
 use ed25519_dalek::SigningKey;
 use ed25519_dalek::pkcs8::{EncodePrivateKey, EncodePublicKey};
-use packc::{VerifyOptions, manifest, sign_pack_dir, verify_pack_dir};
+use packc::signing::KeyConstraints;
+use packc::{
+    DigestAlgorithm, TrustStore, VerificationError, VerifyOptions, manifest, sign_pack_dir,
+    sign_pack_dir_with, sign_pack_dir_with_keys, verify_pack_dir,
+};
 use pkcs8::LineEnding;
 use tempfile::tempdir;
+use time::{Duration, OffsetDateTime};
 
 fn write_file(path: &Path, contents: &str) {
     if let Some(parent) = path.parent() {
@@ -54,6 +59,7 @@ fn sign_and_verify_pack_manifest() {
         VerifyOptions {
             public_key_pem: Some(public_pem.as_str()),
             allow_unsigned: false,
+            ..Default::default()
         },
     )
     .expect("verify signature");
@@ -65,3 +71,295 @@ fn sign_and_verify_pack_manifest() {
     assert_eq!(second_signature.sig, first_signature.sig);
     assert_eq!(second_signature.digest, first_signature.digest);
 }
+
+#[test]
+fn verification_follows_the_signature_digest_algorithm() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path();
+    write_file(&pack_dir.join("pack.toml"), "[package]\nname = \"demo\"\n");
+    write_file(&pack_dir.join("flows/main.flow"), "start: node");
+
+    let signing_key = SigningKey::from_bytes(&TEST_SECRET_KEY);
+    let private_pem = signing_key
+        .to_pkcs8_pem(LineEnding::LF)
+        .expect("encode private key");
+    let public_pem = signing_key
+        .verifying_key()
+        .to_public_key_pem(LineEnding::LF)
+        .expect("encode public key");
+    let opts = VerifyOptions {
+        public_key_pem: Some(public_pem.as_str()),
+        allow_unsigned: false,
+        ..Default::default()
+    };
+
+    for alg in DigestAlgorithm::ALL {
+        let signature =
+            sign_pack_dir_with(pack_dir, private_pem.as_str(), None, alg).expect("sign pack");
+        assert!(
+            signature.digest.starts_with(&format!("{alg}:")),
+            "{}",
+            signature.digest
+        );
+        let verified = verify_pack_dir(pack_dir, opts).expect("verify signature");
+        assert_eq!(verified.digest, signature.digest);
+    }
+
+    let mut signature = manifest::read_signature(pack_dir)
+        .expect("read signature")
+        .expect("signature present");
+    signature.digest = format!("md5:{}", "0".repeat(32));
+    manifest::write_signature(pack_dir, &signature, None).expect("rewrite signature");
+    let err = verify_pack_dir(pack_dir, opts).expect_err("unknown digest algorithm");
+    assert!(matches!(
+        err.downcast_ref::<VerificationError>(),
+        Some(VerificationError::UnsupportedDigest { .. })
+    ));
+}
+
+#[test]
+fn post_quantum_policy_rejects_classical_only_signatures() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path();
+    write_file(&pack_dir.join("pack.toml"), "[package]\nname = \"demo\"\n");
+
+    let signing_key = SigningKey::from_bytes(&TEST_SECRET_KEY);
+    let private_pem = signing_key
+        .to_pkcs8_pem(LineEnding::LF)
+        .expect("encode private key");
+    let public_pem = signing_key
+        .verifying_key()
+        .to_public_key_pem(LineEnding::LF)
+        .expect("encode public key");
+    sign_pack_dir(pack_dir, private_pem.as_str(), None).expect("sign pack");
+
+    let err = verify_pack_dir(
+        pack_dir,
+        VerifyOptions {
+            public_key_pem: Some(public_pem.as_str()),
+            require_post_quantum: true,
+            ..Default::default()
+        },
+    )
+    .expect_err("ed25519 alone does not satisfy the policy");
+    assert!(matches!(
+        err.downcast_ref::<VerificationError>(),
+        Some(VerificationError::PostQuantumRequired)
+    ));
+}
+
+#[test]
+fn signature_constraints_are_signed_and_enforced() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path();
+    write_file(
+        &pack_dir.join("pack.toml"),
+        "[package]\nname = \"greentic.dev.demo\"\n",
+    );
+
+    let signing_key = SigningKey::from_bytes(&TEST_SECRET_KEY);
+    let private_pem = signing_key
+        .to_pkcs8_pem(LineEnding::LF)
+        .expect("encode private key");
+    let public_pem = signing_key
+        .verifying_key()
+        .to_public_key_pem(LineEnding::LF)
+        .expect("encode public key");
+    let verify_in = |environment: Option<&str>| {
+        verify_pack_dir(
+            pack_dir,
+            VerifyOptions {
+                public_key_pem: Some(public_pem.as_str()),
+                environment,
+                ..Default::default()
+            },
+        )
+    };
+    let violation = |err: anyhow::Error| match err.downcast::<VerificationError>() {
+        Ok(VerificationError::ConstraintViolated { reason, .. }) => reason,
+        other => panic!("expected a constraint violation, got {other:?}"),
+    };
+
+    let prod_only = KeyConstraints {
+        pack_id: Some("greentic.prod.*".into()),
+        ..Default::default()
+    };
+    let err = sign_pack_dir_with_keys(
+        pack_dir,
+        &[private_pem.as_str()],
+        DigestAlgorithm::Sha256,
+        Some(&prod_only),
+    )
+    .expect_err("signing outside the allowed ids is refused");
+    assert!(format!("{err:#}").contains("greentic.prod.*"), "{err:#}");
+
+    let constraints = KeyConstraints {
+        pack_id: Some("greentic.dev.*".into()),
+        expires_at: Some(OffsetDateTime::now_utc() + Duration::days(1)),
+        environments: vec!["dev".into()],
+    };
+    let signatures = sign_pack_dir_with_keys(
+        pack_dir,
+        &[private_pem.as_str()],
+        DigestAlgorithm::Sha256,
+        Some(&constraints),
+    )
+    .expect("sign with constraints");
+    assert_eq!(signatures[0].constraints.as_ref(), Some(&constraints));
+
+    verify_in(Some("dev")).expect("allowed environment verifies");
+    let reason = violation(verify_in(Some("prod")).unwrap_err());
+    assert!(reason.contains("`prod`"), "{reason}");
+    violation(verify_in(None).unwrap_err());
+
+    // Loosening the recorded constraints breaks the signature itself.
+    let mut signature = signatures[0].clone();
+    signature.constraints = Some(KeyConstraints {
+        pack_id: Some("*".into()),
+        ..constraints.clone()
+    });
+    manifest::write_signature(pack_dir, &signature, None).unwrap();
+    let err = verify_in(Some("dev")).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<VerificationError>(),
+        Some(VerificationError::InvalidSignature { .. })
+    ));
+}
+
+#[test]
+fn co_signatures_accumulate_and_meet_a_threshold() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path();
+    write_file(&pack_dir.join("pack.toml"), "[package]\nname = \"demo\"\n");
+    write_file(&pack_dir.join("flows/main.flow"), "start: node");
+
+    let keypair = |seed: u8| {
+        let key = SigningKey::from_bytes(&[seed; 32]);
+        let private = key.to_pkcs8_pem(LineEnding::LF).expect("private pem");
+        let public = key
+            .verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .expect("public pem");
+        (private.as_str().to_owned(), public)
+    };
+    let (developer, developer_pub) = keypair(0x11);
+    let (release, release_pub) = keypair(0x22);
+    let (_, auditor_pub) = keypair(0x33);
+
+    let first = sign_pack_dir(pack_dir, &developer, None).expect("developer signs");
+    sign_pack_dir(pack_dir, &release, None).expect("release manager signs");
+    // Re-signing with the same key replaces that key's signature.
+    let second = sign_pack_dir(pack_dir, &release, None).expect("release manager re-signs");
+    let signatures = manifest::read_signatures(pack_dir).expect("read signatures");
+    assert_eq!(signatures, [first.clone(), second]);
+
+    let others = [release_pub.as_str(), auditor_pub.as_str()];
+    let two_of_three = VerifyOptions {
+        public_key_pem: Some(developer_pub.as_str()),
+        additional_public_keys_pem: &others,
+        threshold: Some(2),
+        ..Default::default()
+    };
+    let primary = verify_pack_dir(pack_dir, two_of_three).expect("2 of 3 verify");
+    assert_eq!(primary.key_id, first.key_id);
+
+    let release_only = [auditor_pub.as_str()];
+    let err = verify_pack_dir(
+        pack_dir,
+        VerifyOptions {
+            public_key_pem: Some(release_pub.as_str()),
+            additional_public_keys_pem: &release_only,
+            threshold: Some(2),
+            ..Default::default()
+        },
+    )
+    .expect_err("only one trusted signature");
+    assert!(
+        matches!(
+            err.downcast_ref::<VerificationError>(),
+            Some(VerificationError::ThresholdNotMet {
+                required: 2,
+                valid: 1,
+                ..
+            })
+        ),
+        "{err}"
+    );
+
+    // Changing the pack drops the stale signatures on the next signing.
+    write_file(&pack_dir.join("flows/extra.flow"), "start: other");
+    let fresh = sign_pack_dir(pack_dir, &developer, None).expect("developer re-signs");
+    assert_eq!(
+        manifest::read_signatures(pack_dir).expect("read signatures"),
+        [fresh]
+    );
+}
+
+#[test]
+fn trust_store_resolves_signing_keys_by_key_id() {
+    let temp = tempdir().expect("temp dir");
+    let pack_dir = temp.path().join("pack");
+    write_file(&pack_dir.join("pack.toml"), "[package]\nname = \"demo\"\n");
+    write_file(&pack_dir.join("flows/main.flow"), "start: node");
+
+    let keypair = |seed: u8| {
+        let key = SigningKey::from_bytes(&[seed; 32]);
+        let private = key.to_pkcs8_pem(LineEnding::LF).expect("private pem");
+        let public = key
+            .verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .expect("public pem");
+        (private.as_str().to_owned(), public)
+    };
+    let (developer, developer_pub) = keypair(0x11);
+    let (release, release_pub) = keypair(0x22);
+    let (_, other_pub) = keypair(0x33);
+    sign_pack_dir(&pack_dir, &developer, None).expect("developer signs");
+    sign_pack_dir(&pack_dir, &release, None).expect("release manager signs");
+
+    let keys_dir = temp.path().join("trusted");
+    write_file(&keys_dir.join("developer.pem"), &developer_pub);
+    write_file(&keys_dir.join("release.pem"), &release_pub);
+    write_file(&keys_dir.join("other.pem"), &other_pub);
+    let store = TrustStore::load(&keys_dir).expect("load key directory");
+    assert_eq!(store.len(), 3);
+    let verified = verify_pack_dir(
+        &pack_dir,
+        VerifyOptions {
+            trust_store: Some(&store),
+            ..Default::default()
+        },
+    )
+    .expect("every signature resolves through the store");
+    assert_eq!(
+        store
+            .get(&verified.key_id)
+            .and_then(|key| key.name.as_deref()),
+        Some("developer")
+    );
+
+    // The same keys as a keyring; the release key alone meets no 2-of-N policy.
+    let keyring = temp.path().join("keyring.json");
+    write_file(&keyring, &store.to_keyring_json().expect("keyring json"));
+    assert_eq!(TrustStore::load(&keyring).expect("load keyring"), store);
+    let mut partial = TrustStore::new();
+    partial
+        .insert(&release_pub, Some("release".to_string()))
+        .expect("insert key");
+    let err = verify_pack_dir(
+        &pack_dir,
+        VerifyOptions {
+            trust_store: Some(&partial),
+            threshold: Some(2),
+            ..Default::default()
+        },
+    )
+    .expect_err("developer key is not trusted");
+    assert!(
+        matches!(
+            err.downcast_ref::<VerificationError>(),
+            Some(VerificationError::ThresholdNotMet { valid: 1, .. })
+        ),
+        "{err}"
+    );
+}
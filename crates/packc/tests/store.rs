@@ -0,0 +1,113 @@
+mod common;
+
+use greentic_pack::SigningPolicy;
+use greentic_pack::lifecycle::LifecycleSection;
+use greentic_pack::store::PackStore;
+use semver::Version;
+use tempfile::tempdir;
+
+#[test]
+fn store_state_moves_to_a_new_node() {
+    let temp = tempdir().expect("temp dir");
+    let source = temp.path().join("source");
+    let store = PackStore::open(&source)
+        .unwrap()
+        .with_policy(SigningPolicy::DevOk);
+    for pack_id in ["demo.weather", "demo.billing"] {
+        let built = common::build_sample_gtpack(&temp.path().join(pack_id), pack_id, "demo");
+        store.install(&built).unwrap();
+    }
+
+    let state = temp.path().join("state.tar.zst");
+    let exported = common::stdout_json(&common::packc(&[
+        "store",
+        "export",
+        "--store",
+        source.to_str().unwrap(),
+        "--out",
+        state.to_str().unwrap(),
+    ]));
+    assert_eq!(exported["files"].as_array().unwrap().len(), 5);
+
+    let node = temp.path().join("node");
+    let node_arg = node.to_str().unwrap();
+    let state_arg = state.to_str().unwrap();
+    let imported = common::stdout_json(&common::packc(&[
+        "store",
+        "import",
+        state_arg,
+        "--store",
+        node_arg,
+        "--reverify",
+    ]));
+    let ids: Vec<&str> = imported["packs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|pack| pack["pack_id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["demo.billing", "demo.weather"]);
+    assert_eq!(imported["history_entries"], 2);
+
+    // A node that already holds packs is left alone.
+    let again = common::packc(&["store", "import", state_arg, "--store", node_arg]);
+    assert!(!again.status.success());
+    assert!(
+        String::from_utf8_lossy(&again.stderr).contains("not empty"),
+        "{}",
+        String::from_utf8_lossy(&again.stderr)
+    );
+
+    // Re-verifying under the strict policy rejects the dev-signed packs.
+    let strict = temp.path().join("strict");
+    let output = common::packc(&[
+        "store",
+        "import",
+        state_arg,
+        "--store",
+        strict.to_str().unwrap(),
+        "--reverify",
+        "--strict",
+    ]);
+    assert!(!output.status.success());
+    assert!(!strict.join("packs/demo.weather").exists());
+}
+
+#[test]
+fn store_health_reports_the_latest_checks() {
+    let temp = tempdir().expect("temp dir");
+    let root = temp.path().join("store");
+    let store = PackStore::open(&root)
+        .unwrap()
+        .with_policy(SigningPolicy::DevOk);
+    let mut meta = common::sample_meta("demo.weather", "demo");
+    meta.lifecycle = Some(LifecycleSection {
+        health: Some("main".into()),
+    });
+    let built = common::build_gtpack_with(&temp.path().join("weather"), meta, |builder| builder);
+    store.install(&built).unwrap();
+    let version = Version::new(0, 1, 0);
+    let check = store
+        .health_check("demo.weather", &version)
+        .unwrap()
+        .unwrap();
+    store
+        .run_health_check(&check, |_| anyhow::bail!("forecast backend is down"))
+        .unwrap();
+
+    let root_arg = root.to_str().unwrap();
+    let records = common::stdout_json(&common::packc(&["store", "health", "--store", root_arg]));
+    assert_eq!(records[0]["pack_id"], "demo.weather");
+    assert_eq!(records[0]["status"], "unhealthy");
+    assert_eq!(records[0]["error"], "forecast backend is down");
+
+    let output = common::packc(&["store", "health", "--store", root_arg, "--metrics"]);
+    assert!(output.status.success());
+    let metrics = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        metrics.contains(
+            "greentic_pack_healthy{pack_id=\"demo.weather\",version=\"0.1.0\",flow=\"main\"} 0"
+        ),
+        "{metrics}"
+    );
+}
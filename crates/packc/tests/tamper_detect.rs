@@ -51,6 +51,7 @@ fn tampering_is_detected() {
         VerifyOptions {
             public_key_pem: Some(public_pem.as_str()),
             allow_unsigned: false,
+            ..Default::default()
         },
     )
     .expect("verify baseline");
@@ -64,6 +65,7 @@ fn tampering_is_detected() {
         VerifyOptions {
             public_key_pem: Some(public_pem.as_str()),
             allow_unsigned: false,
+            ..Default::default()
         },
     )
     .expect("verify after ignored dirs change");
@@ -76,6 +78,7 @@ fn tampering_is_detected() {
         VerifyOptions {
             public_key_pem: Some(public_pem.as_str()),
             allow_unsigned: false,
+            ..Default::default()
         },
     )
     .expect("verify after packignore change");
@@ -88,6 +91,7 @@ fn tampering_is_detected() {
         VerifyOptions {
             public_key_pem: Some(public_pem.as_str()),
             allow_unsigned: false,
+            ..Default::default()
         },
     )
     .expect_err("digest mismatch expected");
@@ -111,6 +115,7 @@ fn tampering_is_detected() {
         VerifyOptions {
             public_key_pem: Some(public_pem.as_str()),
             allow_unsigned: false,
+            ..Default::default()
         },
     )
     .expect_err("invalid signature expected");
@@ -131,6 +136,7 @@ fn tampering_is_detected() {
         VerifyOptions {
             public_key_pem: Some(public_pem.as_str()),
             allow_unsigned: false,
+            ..Default::default()
         },
     )
     .expect_err("missing signature expected");
@@ -145,6 +151,7 @@ fn tampering_is_detected() {
         VerifyOptions {
             public_key_pem: None,
             allow_unsigned: true,
+            ..Default::default()
         },
     )
     .expect("unsigned allowed");
@@ -0,0 +1,82 @@
+//! Calls the `greentic_pack_export__*` C ABI shims of `pack_component` with
+//! host-controlled pointers, lengths, and payloads.
+//!
+//! Input layout (see `fuzz/README.md`):
+//!
+//! | bytes  | meaning                                                   |
+//! |--------|-----------------------------------------------------------|
+//! | 0      | entry point, modulo [`ENTRY_POINTS`]                      |
+//! | 1..3   | output buffer length, `u16` LE; `0` passes a null buffer  |
+//! | 3..7   | `offset` for `list_flows_page`, `u32` LE                  |
+//! | 7..11  | `limit` for `list_flows_page`, `u32` LE                   |
+//! | 11..   | payload: a flow filter or flow id; empty passes null      |
+#![no_main]
+
+use std::ptr;
+
+use libfuzzer_sys::fuzz_target;
+use pack_component::{
+    greentic_pack_export__a2a_search, greentic_pack_export__config_schema,
+    greentic_pack_export__count_flows, greentic_pack_export__find_flows,
+    greentic_pack_export__list_flows, greentic_pack_export__list_flows_page,
+    greentic_pack_export__node_docs, greentic_pack_export__prepare_flow,
+    greentic_pack_export__run_flow,
+};
+
+const HEADER_LEN: usize = 11;
+const ENTRY_POINTS: u8 = 9;
+const COUNT_FLOWS: u8 = 1;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < HEADER_LEN {
+        return;
+    }
+    let (header, payload) = data.split_at(HEADER_LEN);
+    let entry = header[0] % ENTRY_POINTS;
+    let buffer_len = usize::from(u16::from_le_bytes([header[1], header[2]]));
+    let offset = u32::from_le_bytes(header[3..7].try_into().unwrap()) as usize;
+    let limit = u32::from_le_bytes(header[7..11].try_into().unwrap()) as usize;
+
+    if entry == COUNT_FLOWS {
+        assert_eq!(
+            greentic_pack_export__count_flows(),
+            pack_component::flow_index().len()
+        );
+        return;
+    }
+
+    let (input, input_len) = if payload.is_empty() {
+        (ptr::null(), 0)
+    } else {
+        (payload.as_ptr(), payload.len())
+    };
+    let call = |buffer: *mut u8, len: usize| -> usize {
+        // SAFETY: `input` is null or points to `input_len` bytes of `payload`,
+        // and `buffer` is null or points to `len` writable bytes.
+        unsafe {
+            match entry {
+                0 => greentic_pack_export__list_flows(buffer, len),
+                2 => greentic_pack_export__list_flows_page(offset, limit, buffer, len),
+                3 => greentic_pack_export__find_flows(input, input_len, buffer, len),
+                4 => greentic_pack_export__prepare_flow(input, input_len, buffer, len),
+                5 => greentic_pack_export__run_flow(input, input_len, buffer, len),
+                6 => greentic_pack_export__a2a_search(buffer, len),
+                7 => greentic_pack_export__node_docs(input, input_len, buffer, len),
+                _ => greentic_pack_export__config_schema(buffer, len),
+            }
+        }
+    };
+
+    // A null buffer asks for the size of the response, whatever `len` says.
+    let size = call(ptr::null_mut(), buffer_len);
+    if buffer_len == 0 {
+        return;
+    }
+    let mut buffer = vec![0u8; buffer_len];
+    let written = call(buffer.as_mut_ptr(), buffer.len());
+    assert_eq!(written, size.min(buffer_len), "short or overlong write");
+    if written == size {
+        serde_json::from_slice::<serde_json::Value>(&buffer[..written])
+            .expect("complete responses are JSON");
+    }
+});
@@ -0,0 +1,24 @@
+//! Opens `.gtpack` archives from untrusted bytes and reads every part of the
+//! result a host would.
+#![no_main]
+
+use greentic_pack::{SigningPolicy, open_pack_bytes};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(load) = open_pack_bytes(data, SigningPolicy::DevOk) else {
+        return;
+    };
+    let _ = load.entries();
+    let _ = load.content_digest();
+    let _ = load.signature();
+    let _ = load.provenance();
+    let _ = load.permission_summary("en");
+    let _ = load.config_schema();
+    let _ = load.readme();
+    let _ = load.icon();
+    let _ = load.screenshots();
+    for flow in &load.manifest.flows {
+        let _ = load.flow_requirements(&flow.id);
+    }
+});
@@ -0,0 +1,15 @@
+//! Decodes `manifest.cbor` bytes the way pack readers do, and re-encodes
+//! whatever decodes.
+#![no_main]
+
+use greentic_pack::builder::PackManifest;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(manifest) = serde_cbor::from_slice::<PackManifest>(data) {
+        let encoded = serde_cbor::to_vec(&manifest).expect("decoded manifests encode");
+        serde_cbor::from_slice::<PackManifest>(&encoded).expect("re-encoded manifests decode");
+    }
+    // Hosts that do not know the manifest schema read it as plain data.
+    let _ = serde_cbor::from_slice::<serde_json::Value>(data);
+});